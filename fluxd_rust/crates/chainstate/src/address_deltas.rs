@@ -105,9 +105,9 @@ impl<S: KeyValueStore> AddressDeltaIndex<S> {
         let Some(prefix) = address_index::address_prefix(script_pubkey) else {
             return Ok(Vec::new());
         };
-        let entries = self.store.scan_prefix(Column::AddressDelta, &prefix)?;
-        let mut out = Vec::with_capacity(entries.len());
-        for (key, value) in entries {
+        let mut out = Vec::new();
+        for entry in self.store.scan_prefix_iter(Column::AddressDelta, &prefix)? {
+            let (key, value) = entry?;
             let Some(entry) = decode_entry(&key, &value) else {
                 continue;
             };
@@ -15,7 +15,7 @@ pub fn script_hash(script_pubkey: &[u8]) -> Option<Hash256> {
     match classify_script_pubkey(script_pubkey) {
         ScriptType::P2Pkh | ScriptType::P2Sh => Some(sha256(script_pubkey)),
         ScriptType::P2Pk => normalized_p2pk_hash(script_pubkey),
-        ScriptType::P2Wpkh | ScriptType::P2Wsh | ScriptType::Unknown => None,
+        ScriptType::P2Wpkh | ScriptType::P2Wsh | ScriptType::Witness | ScriptType::Unknown => None,
     }
 }
 
@@ -95,9 +95,12 @@ impl<S: KeyValueStore> AddressIndex<S> {
         let Some(prefix) = address_prefix(script_pubkey) else {
             return Ok(Vec::new());
         };
-        let entries = self.store.scan_prefix(Column::AddressOutpoint, &prefix)?;
-        let mut outpoints = Vec::with_capacity(entries.len());
-        for (key, _) in entries {
+        let mut outpoints = Vec::new();
+        for entry in self
+            .store
+            .scan_prefix_iter(Column::AddressOutpoint, &prefix)?
+        {
+            let (key, _) = entry?;
             if let Some(outpoint) = outpoint_from_key(&key) {
                 outpoints.push(outpoint);
             }
@@ -20,7 +20,7 @@ impl<S: KeyValueStore> AnchorSet<S> {
     }
 
     pub fn contains(&self, anchor: &Hash256) -> Result<bool, StoreError> {
-        Ok(self.store.get(self.column, anchor)?.is_some())
+        self.store.contains(self.column, anchor)
     }
 
     pub fn insert(&self, batch: &mut WriteBatch, anchor: &Hash256, tree: Vec<u8>) {
@@ -45,7 +45,7 @@ impl<S> NullifierSet<S> {
 
 impl<S: KeyValueStore> NullifierSet<S> {
     pub fn contains(&self, nullifier: &Hash256) -> Result<bool, StoreError> {
-        Ok(self.store.get(self.column, nullifier)?.is_some())
+        self.store.contains(self.column, nullifier)
     }
 
     pub fn insert(&self, batch: &mut WriteBatch, nullifier: &Hash256) {
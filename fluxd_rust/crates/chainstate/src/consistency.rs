@@ -0,0 +1,142 @@
+//! Cross-checks between the transaction, spent and UTXO indexes.
+//!
+//! See `ChainState::verify_index_consistency`. Issues carry the keys and locations needed to
+//! find the offending entry; txids are shown in RPC byte order.
+
+use std::fmt;
+
+use fluxd_primitives::ids::Txid;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_storage::Column;
+
+use crate::txindex::TxLocation;
+
+/// Upper bound on issues kept in a report; `issue_count` still counts every mismatch.
+pub const MAX_REPORTED_ISSUES: usize = 1_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsistencyIssue {
+    /// An entry in `column` whose key or value does not decode.
+    UndecodableEntry { column: Column, key: Vec<u8> },
+    /// A txindex entry whose block could not be read or decoded.
+    TxIndexUnreadableBlock {
+        txid: Txid,
+        location: TxLocation,
+        error: String,
+    },
+    /// A txindex entry pointing past the end of its block.
+    TxIndexMissingTx { txid: Txid, location: TxLocation },
+    /// A txindex entry whose transaction hashes to a different txid.
+    TxIndexTxidMismatch {
+        txid: Txid,
+        location: TxLocation,
+        found: Txid,
+    },
+    /// A spentindex entry whose spending transaction is not in the txindex.
+    SpentIndexMissingSpender { outpoint: OutPoint, spender: Txid },
+    /// A spentindex entry whose spending input does not reference the spent outpoint.
+    SpentIndexInputMismatch {
+        outpoint: OutPoint,
+        spender: Txid,
+        input_index: u32,
+        found: Option<OutPoint>,
+    },
+    /// An output that is still in the UTXO set but has a spentindex entry.
+    UtxoMarkedSpent { outpoint: OutPoint, spender: Txid },
+}
+
+impl fmt::Display for ConsistencyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsistencyIssue::UndecodableEntry { column, key } => {
+                write!(f, "undecodable {column:?} entry (key length {})", key.len())
+            }
+            ConsistencyIssue::TxIndexUnreadableBlock {
+                txid,
+                location,
+                error,
+            } => write!(
+                f,
+                "txindex {txid}: block at data{:05}.dat offset {} unreadable: {error}",
+                location.block.file_id, location.block.offset
+            ),
+            ConsistencyIssue::TxIndexMissingTx { txid, location } => write!(
+                f,
+                "txindex {txid}: block at data{:05}.dat offset {} has no tx #{}",
+                location.block.file_id, location.block.offset, location.index
+            ),
+            ConsistencyIssue::TxIndexTxidMismatch {
+                txid,
+                location,
+                found,
+            } => write!(
+                f,
+                "txindex {txid}: tx #{} in block at data{:05}.dat offset {} is {found}",
+                location.index, location.block.file_id, location.block.offset
+            ),
+            ConsistencyIssue::SpentIndexMissingSpender { outpoint, spender } => write!(
+                f,
+                "spentindex {}:{}: spender {spender} not in txindex",
+                outpoint.txid(),
+                outpoint.index
+            ),
+            ConsistencyIssue::SpentIndexInputMismatch {
+                outpoint,
+                spender,
+                input_index,
+                found,
+            } => {
+                write!(
+                    f,
+                    "spentindex {}:{}: input {input_index} of {spender} spends ",
+                    outpoint.txid(),
+                    outpoint.index
+                )?;
+                match found {
+                    Some(prevout) => write!(f, "{}:{}", prevout.txid(), prevout.index),
+                    None => write!(f, "nothing (no such input)"),
+                }
+            }
+            ConsistencyIssue::UtxoMarkedSpent { outpoint, spender } => write!(
+                f,
+                "utxo {}:{} is unspent but spentindex names spender {spender}",
+                outpoint.txid(),
+                outpoint.index
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    pub tx_index_checked: u64,
+    pub spent_index_checked: u64,
+    pub utxo_checked: u64,
+    /// Total number of mismatches found, including any beyond `MAX_REPORTED_ISSUES`.
+    pub issue_count: u64,
+    pub issues: Vec<ConsistencyIssue>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.issue_count == 0
+    }
+
+    pub(crate) fn record(&mut self, issue: ConsistencyIssue) {
+        self.issue_count = self.issue_count.saturating_add(1);
+        if self.issues.len() < MAX_REPORTED_ISSUES {
+            self.issues.push(issue);
+        }
+    }
+}
+
+/// Decodes a `txid || vout` key as used by the UTXO set and the spent index.
+pub(crate) fn outpoint_from_key(key: &[u8]) -> Option<OutPoint> {
+    if key.len() != 36 {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&key[..32]);
+    let index = u32::from_le_bytes(key[32..].try_into().ok()?);
+    Some(OutPoint { hash, index })
+}
@@ -64,6 +64,12 @@ pub struct FlatFileStore {
     prefix: String,
     max_file_size: u64,
     state: Mutex<FlatFileState>,
+    #[cfg(feature = "mmap")]
+    mapped: Mutex<mapped::MappedFiles>,
+    /// Held shared while a mapping is read and exclusively by `truncate`, so no mapping is in
+    /// use while its file shrinks.
+    #[cfg(feature = "mmap")]
+    map_guard: std::sync::RwLock<()>,
 }
 
 #[derive(Debug)]
@@ -94,6 +100,10 @@ impl FlatFileStore {
                 current_file,
                 current_len,
             }),
+            #[cfg(feature = "mmap")]
+            mapped: Mutex::new(mapped::MappedFiles::new(mapped::MAPPED_FILE_CAPACITY)),
+            #[cfg(feature = "mmap")]
+            map_guard: std::sync::RwLock::new(()),
         })
     }
 
@@ -123,6 +133,10 @@ impl FlatFileStore {
         if location.len == 0 {
             return Err(FlatFileError::InvalidLocation);
         }
+        #[cfg(feature = "mmap")]
+        if let Some(result) = self.read_mapped(location) {
+            return result;
+        }
         let path = self.file_path(location.file_id);
         let mut file = File::open(&path)?;
         file.seek(SeekFrom::Start(location.offset))?;
@@ -137,6 +151,146 @@ impl FlatFileStore {
         Ok(buffer)
     }
 
+    /// Reads the length prefix stored at `location` without reading the record body.
+    pub fn read_stored_len(&self, location: FileLocation) -> Result<u32, FlatFileError> {
+        #[cfg(feature = "mmap")]
+        if let Some(result) = self.read_mapped_len(location) {
+            return result;
+        }
+        let path = self.file_path(location.file_id);
+        let mut file = File::open(&path)?;
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut len_bytes = [0u8; 4];
+        file.read_exact(&mut len_bytes)?;
+        Ok(u32::from_le_bytes(len_bytes))
+    }
+
+    /// Length on disk of `file_id`, or `None` when the file does not exist.
+    pub fn file_len(&self, file_id: u32) -> Result<Option<u64>, FlatFileError> {
+        match std::fs::metadata(self.file_path(file_id)) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Id of the file the next append goes to.
+    pub fn current_file(&self) -> u32 {
+        self.state.lock().expect("flatfile lock").current_file
+    }
+
+    /// Walks the records of `file_id` from `offset` to the end of the file. Returns the complete
+    /// records and, when the file ends partway through one (or its length prefix is zero), the
+    /// offset that record starts at.
+    pub fn scan_records(
+        &self,
+        file_id: u32,
+        offset: u64,
+    ) -> Result<(Vec<FileLocation>, Option<u64>), FlatFileError> {
+        let Some(file_len) = self.file_len(file_id)? else {
+            return Ok((Vec::new(), None));
+        };
+        let mut records = Vec::new();
+        if offset >= file_len {
+            return Ok((records, None));
+        }
+        let mut file = File::open(self.file_path(file_id))?;
+        let mut offset = offset;
+        while offset < file_len {
+            if file_len - offset < 4 {
+                return Ok((records, Some(offset)));
+            }
+            file.seek(SeekFrom::Start(offset))?;
+            let mut len_bytes = [0u8; 4];
+            file.read_exact(&mut len_bytes)?;
+            let len = u32::from_le_bytes(len_bytes);
+            let end = offset + 4 + u64::from(len);
+            if len == 0 || end > file_len {
+                return Ok((records, Some(offset)));
+            }
+            records.push(FileLocation {
+                file_id,
+                offset,
+                len,
+            });
+            offset = end;
+        }
+        Ok((records, None))
+    }
+
+    /// Cuts `file_id` back to `len` bytes, dropping a record a crash left half-written. Appends
+    /// continue from the new end when it is the active file.
+    pub fn truncate(&self, file_id: u32, len: u64) -> Result<(), FlatFileError> {
+        let mut state = self.state.lock().expect("flatfile lock");
+        #[cfg(feature = "mmap")]
+        let _map_guard = self.map_guard.write().expect("flatfile map lock");
+        #[cfg(feature = "mmap")]
+        if let Ok(mut mapped) = self.mapped.lock() {
+            mapped.remove(file_id);
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .open(self.file_path(file_id))?;
+        file.set_len(len)?;
+        file.sync_all()?;
+        if state.current_file == file_id {
+            state.current_len = len;
+        }
+        Ok(())
+    }
+
+    /// Serves a record from a mapped data file. Returns `None` when the file cannot be
+    /// mapped (or the record lies past the mapped length) so the caller falls back to pread.
+    #[cfg(feature = "mmap")]
+    fn read_mapped(&self, location: FileLocation) -> Option<Result<Vec<u8>, FlatFileError>> {
+        let _map_guard = self.map_guard.read().ok()?;
+        let end = location
+            .offset
+            .checked_add(4)?
+            .checked_add(u64::from(location.len))?;
+        let map = self.mapped_file(location.file_id, end)?;
+        let start = location.offset as usize;
+        let stored_len = u32::from_le_bytes(map[start..start + 4].try_into().ok()?);
+        if stored_len != location.len {
+            return Some(Err(FlatFileError::LengthMismatch));
+        }
+        Some(Ok(map[start + 4..end as usize].to_vec()))
+    }
+
+    #[cfg(feature = "mmap")]
+    fn read_mapped_len(&self, location: FileLocation) -> Option<Result<u32, FlatFileError>> {
+        let _map_guard = self.map_guard.read().ok()?;
+        let end = location.offset.checked_add(4)?;
+        let map = self.mapped_file(location.file_id, end)?;
+        let start = location.offset as usize;
+        let len_bytes: [u8; 4] = map[start..start + 4].try_into().ok()?;
+        Some(Ok(u32::from_le_bytes(len_bytes)))
+    }
+
+    /// Returns a mapping of `file_id` covering at least `min_len` bytes, remapping when the
+    /// cached mapping predates later appends.
+    #[cfg(feature = "mmap")]
+    fn mapped_file(&self, file_id: u32, min_len: u64) -> Option<std::sync::Arc<memmap2::Mmap>> {
+        let mut mapped = self.mapped.lock().ok()?;
+        if let Some(map) = mapped.get(file_id) {
+            if map.len() as u64 >= min_len {
+                return Some(map);
+            }
+        }
+        let file = File::open(self.file_path(file_id)).ok()?;
+        // SAFETY: flatfiles are append-only apart from `truncate`, which drops the cached mapping
+        // and shrinks the file only while holding `map_guard` exclusively. Mappings are read
+        // under the shared guard and never escape the read, so no mapped byte is rewritten or
+        // cut while it can be accessed.
+        let map = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+        if (map.len() as u64) < min_len {
+            return None;
+        }
+        let map = std::sync::Arc::new(map);
+        mapped.insert(file_id, std::sync::Arc::clone(&map));
+        Some(map)
+    }
+
     fn file_path(&self, file_id: u32) -> PathBuf {
         self.dir.join(format!("{}{file_id:05}.dat", self.prefix))
     }
@@ -171,3 +325,48 @@ impl FlatFileStore {
         }
     }
 }
+
+#[cfg(feature = "mmap")]
+mod mapped {
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    use memmap2::Mmap;
+
+    pub(super) const MAPPED_FILE_CAPACITY: usize = 8;
+
+    /// Small LRU of mapped data files keyed by file id.
+    pub(super) struct MappedFiles {
+        entries: VecDeque<(u32, Arc<Mmap>)>,
+        capacity: usize,
+    }
+
+    impl MappedFiles {
+        pub(super) fn new(capacity: usize) -> Self {
+            Self {
+                entries: VecDeque::with_capacity(capacity),
+                capacity,
+            }
+        }
+
+        pub(super) fn get(&mut self, file_id: u32) -> Option<Arc<Mmap>> {
+            let position = self.entries.iter().position(|(id, _)| *id == file_id)?;
+            let entry = self.entries.remove(position)?;
+            let map = Arc::clone(&entry.1);
+            self.entries.push_front(entry);
+            Some(map)
+        }
+
+        pub(super) fn remove(&mut self, file_id: u32) {
+            self.entries.retain(|(id, _)| *id != file_id);
+        }
+
+        pub(super) fn insert(&mut self, file_id: u32, map: Arc<Mmap>) {
+            self.entries.retain(|(id, _)| *id != file_id);
+            self.entries.push_front((file_id, map));
+            while self.entries.len() > self.capacity {
+                self.entries.pop_back();
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! Deterministic fluxnode payee selection.
+//!
+//! Block validation and the payee debug tooling both go through this module so the winner for a
+//! tier can only be computed one way.
+
+use fluxd_consensus::constants::{
+    FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1, FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2,
+    FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3, FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4,
+};
+use fluxd_consensus::upgrades::{network_upgrade_active, UpgradeIndex};
+use fluxd_consensus::{fluxnode_collateral_matches_tier, ChainParams, ConsensusParams};
+use fluxd_fluxnode::storage::FluxnodeRecord;
+use fluxd_primitives::hash::hash160;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_storage::KeyValueStore;
+
+use crate::fluxnode_index::{p2pkh_script, p2sh_script};
+use crate::state::{hex_to_bytes_cached, ChainState, ChainStateError};
+use crate::utxo::{outpoint_key_bytes, OutPointKey, UtxoEntry};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FluxnodePayee {
+    pub tier: u8,
+    pub outpoint: OutPoint,
+    pub script_pubkey: Vec<u8>,
+    pub is_p2sh: bool,
+}
+
+/// Why a candidate at the front of the payment queue is passed over.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PayeeSkipReason {
+    Expired,
+    MissingUtxo,
+    CollateralMismatch,
+}
+
+impl PayeeSkipReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PayeeSkipReason::Expired => "expired",
+            PayeeSkipReason::MissingUtxo => "missing_utxo",
+            PayeeSkipReason::CollateralMismatch => "collateral_mismatch",
+        }
+    }
+}
+
+/// Payment queue order: lowest comparator height first (last paid height, or confirmed height
+/// for never-paid nodes), unpaid before paid on ties, then collateral hash and index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FluxnodePayeeKey {
+    pub comparator_height: u32,
+    pub has_last_paid: bool,
+    pub(crate) hash_le: [u8; 32],
+    pub(crate) index: u32,
+    pub(crate) outpoint: OutPointKey,
+}
+
+impl FluxnodePayeeKey {
+    /// Returns `None` for records that are never paid (unknown tier or not yet confirmed).
+    pub fn from_record(record: &FluxnodeRecord) -> Option<Self> {
+        if !(1..=3).contains(&record.tier) {
+            return None;
+        }
+        if record.confirmed_height == 0 {
+            return None;
+        }
+        let has_last_paid = record.last_paid_height > 0;
+        let comparator_height = if record.last_paid_height > 0 {
+            record.last_paid_height
+        } else {
+            record.confirmed_height
+        };
+        let hash_le = record.collateral.hash;
+        Some(Self {
+            comparator_height,
+            has_last_paid,
+            hash_le,
+            index: record.collateral.index,
+            outpoint: outpoint_key_bytes(&record.collateral),
+        })
+    }
+}
+
+impl Ord for FluxnodePayeeKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.comparator_height
+            .cmp(&other.comparator_height)
+            .then_with(|| self.has_last_paid.cmp(&other.has_last_paid))
+            .then_with(|| self.hash_le.cmp(&other.hash_le))
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for FluxnodePayeeKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub fn fluxnode_confirm_expiration_count(height: i32, consensus: &ConsensusParams) -> u32 {
+    let upgrades = &consensus.upgrades;
+    let count = if network_upgrade_active(height, upgrades, UpgradeIndex::Pon) {
+        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4
+    } else if network_upgrade_active(height, upgrades, UpgradeIndex::Halving) {
+        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3
+    } else if network_upgrade_active(height, upgrades, UpgradeIndex::Flux) {
+        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2
+    } else {
+        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1
+    };
+    u32::try_from(count).unwrap_or_default()
+}
+
+/// First height at which a node last confirmed at `last_confirmed_height` counts as expired,
+/// re-evaluated until the expiration window stops changing across upgrades.
+pub fn fluxnode_confirm_expire_height(
+    last_confirmed_height: u32,
+    consensus: &ConsensusParams,
+) -> u32 {
+    let mut expiration = fluxnode_confirm_expiration_count(
+        i32::try_from(last_confirmed_height).unwrap_or(i32::MAX),
+        consensus,
+    );
+    let mut expire_height = last_confirmed_height
+        .saturating_add(expiration)
+        .saturating_add(1);
+    loop {
+        let candidate_height = i32::try_from(expire_height).unwrap_or(i32::MAX);
+        let next_expiration = fluxnode_confirm_expiration_count(candidate_height, consensus);
+        if next_expiration == expiration {
+            break;
+        }
+        expiration = next_expiration;
+        expire_height = last_confirmed_height
+            .saturating_add(expiration)
+            .saturating_add(1);
+    }
+    expire_height
+}
+
+pub fn fluxnode_confirm_expired_for_pay_height(
+    pay_height: u32,
+    last_confirmed_height: u32,
+    consensus: &ConsensusParams,
+) -> bool {
+    if pay_height <= last_confirmed_height {
+        return false;
+    }
+    pay_height >= fluxnode_confirm_expire_height(last_confirmed_height, consensus)
+}
+
+/// Checks a queued candidate against `pay_height` (the height before the paying block).
+pub fn check_payee_candidate(
+    tier: u8,
+    pay_height: i32,
+    last_confirmed_height: u32,
+    utxo: Option<&UtxoEntry>,
+    params: &ChainParams,
+) -> Result<(), PayeeSkipReason> {
+    let pay_height_u32 = u32::try_from(pay_height).unwrap_or_default();
+    if fluxnode_confirm_expired_for_pay_height(
+        pay_height_u32,
+        last_confirmed_height,
+        &params.consensus,
+    ) {
+        return Err(PayeeSkipReason::Expired);
+    }
+    let utxo = utxo.ok_or(PayeeSkipReason::MissingUtxo)?;
+    if !fluxnode_collateral_matches_tier(pay_height, utxo.value, tier, &params.fluxnode) {
+        return Err(PayeeSkipReason::CollateralMismatch);
+    }
+    Ok(())
+}
+
+/// Script a winner is paid to, and whether it is a P2SH payout. Nodes with a redeem script are
+/// paid to its P2SH; nodes whose collateral pubkey is a P2SH signing key are paid to the
+/// collateral script itself; everyone else is paid P2PKH of the collateral pubkey.
+pub fn payee_script(
+    redeem_script: Option<&[u8]>,
+    collateral_pubkey: Option<&[u8]>,
+    collateral_script: &[u8],
+    params: &ChainParams,
+) -> Result<(Vec<u8>, bool), ChainStateError> {
+    if let Some(redeem_script) = redeem_script {
+        return Ok((p2sh_script(&hash160(redeem_script)), true));
+    }
+    let pubkey = collateral_pubkey.ok_or(ChainStateError::CorruptIndex(
+        "missing fluxnode collateral pubkey bytes",
+    ))?;
+    let is_p2sh_signing_key = params.fluxnode.p2sh_public_keys.iter().any(|key| {
+        hex_to_bytes_cached(key.key).is_some_and(|expected| expected.as_ref() == pubkey)
+    });
+    if is_p2sh_signing_key {
+        return Ok((collateral_script.to_vec(), true));
+    }
+    Ok((p2pkh_script(&hash160(pubkey)), false))
+}
+
+/// Deterministic winner for `tier` in the block at `height`.
+pub fn select_payee<S: KeyValueStore>(
+    tier: u8,
+    height: i32,
+    chainstate: &ChainState<S>,
+    params: &ChainParams,
+) -> Result<Option<FluxnodePayee>, ChainStateError> {
+    if height <= 0 {
+        return Ok(None);
+    }
+    chainstate.next_fluxnode_payee(tier, height - 1, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluxd_consensus::params::{chain_params, Network};
+    use fluxd_fluxnode::storage::KeyId;
+
+    fn record(
+        hash_byte: u8,
+        index: u32,
+        confirmed_height: u32,
+        last_paid_height: u32,
+    ) -> FluxnodeRecord {
+        FluxnodeRecord {
+            collateral: OutPoint {
+                hash: [hash_byte; 32],
+                index,
+            },
+            tier: 1,
+            start_height: 1,
+            confirmed_height,
+            last_confirmed_height: confirmed_height,
+            last_paid_height,
+            collateral_value: 0,
+            operator_pubkey: KeyId([0u8; 32]),
+            collateral_pubkey: None,
+            p2sh_script: None,
+            delegates: None,
+            ip: String::new(),
+        }
+    }
+
+    #[test]
+    fn fluxnode_payee_ordering_matches_cpp_unpaid_before_paid_on_equal_height() {
+        let unpaid = record(0xff, 1, 100, 0);
+        let paid = record(0x00, 1, 50, 100);
+        let unpaid_key = FluxnodePayeeKey::from_record(&unpaid).expect("unpaid key");
+        let paid_key = FluxnodePayeeKey::from_record(&paid).expect("paid key");
+        assert!(unpaid_key < paid_key);
+
+        let unpaid_a = record(0x01, 1, 100, 0);
+        let unpaid_b = record(0x02, 1, 100, 0);
+        let unpaid_a_key = FluxnodePayeeKey::from_record(&unpaid_a).expect("unpaid a key");
+        let unpaid_b_key = FluxnodePayeeKey::from_record(&unpaid_b).expect("unpaid b key");
+        assert!(unpaid_a_key < unpaid_b_key);
+    }
+
+    #[test]
+    fn payee_ordering_uses_last_paid_height_over_confirmed_height() {
+        // Paid at 90 after confirming at 10 sorts ahead of a node only confirmed at 95.
+        let paid_earlier = record(0xff, 0, 10, 90);
+        let confirmed_later = record(0x00, 0, 95, 0);
+        let paid_key = FluxnodePayeeKey::from_record(&paid_earlier).expect("paid key");
+        let confirmed_key = FluxnodePayeeKey::from_record(&confirmed_later).expect("confirmed key");
+        assert_eq!(paid_key.comparator_height, 90);
+        assert_eq!(confirmed_key.comparator_height, 95);
+        assert!(paid_key < confirmed_key);
+
+        let same_hash_low_index = record(0x10, 0, 100, 0);
+        let same_hash_high_index = record(0x10, 1, 100, 0);
+        assert!(
+            FluxnodePayeeKey::from_record(&same_hash_low_index)
+                < FluxnodePayeeKey::from_record(&same_hash_high_index)
+        );
+        assert!(FluxnodePayeeKey::from_record(&record(0x10, 0, 0, 0)).is_none());
+    }
+
+    #[test]
+    fn fluxnode_confirm_expiration_respects_upgrade_changes() {
+        let params = chain_params(Network::Mainnet);
+        let pon_height =
+            params.consensus.upgrades[UpgradeIndex::Pon.as_usize()].activation_height as u32;
+
+        let pre_pon_old = pon_height.saturating_sub(344);
+        let pre_pon_recent = pon_height.saturating_sub(10);
+        let pay_height = pon_height.saturating_add(264);
+
+        let expire_old = fluxnode_confirm_expire_height(pre_pon_old, &params.consensus);
+        assert!(expire_old < pon_height);
+        assert!(fluxnode_confirm_expired_for_pay_height(
+            pay_height,
+            pre_pon_old,
+            &params.consensus
+        ));
+
+        let expire_recent = fluxnode_confirm_expire_height(pre_pon_recent, &params.consensus);
+        assert!(expire_recent > pon_height);
+        assert!(!fluxnode_confirm_expired_for_pay_height(
+            pay_height,
+            pre_pon_recent,
+            &params.consensus
+        ));
+    }
+
+    #[test]
+    fn expiration_boundary_is_exclusive_of_the_last_live_height() {
+        let params = chain_params(Network::Mainnet);
+        let pon_height =
+            params.consensus.upgrades[UpgradeIndex::Pon.as_usize()].activation_height as u32;
+        let last_confirmed = pon_height.saturating_add(1_000);
+        let expire_height = fluxnode_confirm_expire_height(last_confirmed, &params.consensus);
+
+        assert!(!fluxnode_confirm_expired_for_pay_height(
+            expire_height - 1,
+            last_confirmed,
+            &params.consensus
+        ));
+        assert!(fluxnode_confirm_expired_for_pay_height(
+            expire_height,
+            last_confirmed,
+            &params.consensus
+        ));
+        assert!(!fluxnode_confirm_expired_for_pay_height(
+            last_confirmed,
+            last_confirmed,
+            &params.consensus
+        ));
+
+        let live = i32::try_from(expire_height - 1).expect("height");
+        assert_eq!(
+            check_payee_candidate(1, live, last_confirmed, None, &params),
+            Err(PayeeSkipReason::MissingUtxo)
+        );
+        let expired = i32::try_from(expire_height).expect("height");
+        assert_eq!(
+            check_payee_candidate(1, expired, last_confirmed, None, &params),
+            Err(PayeeSkipReason::Expired)
+        );
+    }
+
+    #[test]
+    fn payee_script_prefers_redeem_script_then_collateral_pubkey() {
+        let params = chain_params(Network::Mainnet);
+        let redeem = [0x52, 0xae];
+        let pubkey = [0x02; 33];
+        let collateral_script = [0x51];
+
+        let (script, is_p2sh) =
+            payee_script(Some(&redeem), Some(&pubkey), &collateral_script, &params).expect("p2sh");
+        assert_eq!(script, p2sh_script(&hash160(&redeem)));
+        assert!(is_p2sh);
+
+        let (script, is_p2sh) =
+            payee_script(None, Some(&pubkey), &collateral_script, &params).expect("p2pkh");
+        assert_eq!(script, p2pkh_script(&hash160(&pubkey)));
+        assert!(!is_p2sh);
+
+        let signing_key =
+            hex_to_bytes_cached(params.fluxnode.p2sh_public_keys[0].key).expect("signing key");
+        let (script, is_p2sh) = payee_script(None, Some(&signing_key), &collateral_script, &params)
+            .expect("signing key");
+        assert_eq!(script, collateral_script.to_vec());
+        assert!(is_p2sh);
+
+        assert!(payee_script(None, None, &collateral_script, &params).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Fluxnode secondary index (tier + payout script hash + collateral outpoint).
+
+use fluxd_consensus::Hash256;
+use fluxd_primitives::hash::sha256;
+use fluxd_primitives::outpoint::OutPoint;
+
+use crate::utxo::outpoint_key_bytes;
+
+const TIER_LEN: usize = 1;
+const SCRIPT_HASH_LEN: usize = 32;
+const OUTPOINT_KEY_LEN: usize = 36;
+pub const FLUXNODE_INDEX_KEY_LEN: usize = TIER_LEN + SCRIPT_HASH_LEN + OUTPOINT_KEY_LEN;
+
+pub fn fluxnode_index_key(
+    tier: u8,
+    script_pubkey: &[u8],
+    outpoint: &OutPoint,
+) -> [u8; FLUXNODE_INDEX_KEY_LEN] {
+    let mut key = [0u8; FLUXNODE_INDEX_KEY_LEN];
+    key[0] = tier;
+    key[TIER_LEN..TIER_LEN + SCRIPT_HASH_LEN].copy_from_slice(&sha256(script_pubkey));
+    key[TIER_LEN + SCRIPT_HASH_LEN..].copy_from_slice(outpoint_key_bytes(outpoint).as_bytes());
+    key
+}
+
+pub fn fluxnode_index_prefix(tier: u8, script_pubkey: &[u8]) -> [u8; TIER_LEN + SCRIPT_HASH_LEN] {
+    let mut prefix = [0u8; TIER_LEN + SCRIPT_HASH_LEN];
+    prefix[0] = tier;
+    prefix[TIER_LEN..].copy_from_slice(&sha256(script_pubkey));
+    prefix
+}
+
+pub fn fluxnode_index_outpoint(key: &[u8]) -> Option<OutPoint> {
+    if key.len() != FLUXNODE_INDEX_KEY_LEN {
+        return None;
+    }
+    let outpoint = &key[TIER_LEN + SCRIPT_HASH_LEN..];
+    let mut hash: Hash256 = [0u8; 32];
+    hash.copy_from_slice(&outpoint[..32]);
+    let index = u32::from_le_bytes(outpoint[32..36].try_into().ok()?);
+    Some(OutPoint { hash, index })
+}
+
+pub fn p2pkh_script(hash: &[u8; 20]) -> Vec<u8> {
+    let mut script = Vec::with_capacity(25);
+    script.extend_from_slice(&[0x76, 0xa9, 0x14]);
+    script.extend_from_slice(hash);
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+pub fn p2sh_script(hash: &[u8; 20]) -> Vec<u8> {
+    let mut script = Vec::with_capacity(23);
+    script.extend_from_slice(&[0xa9, 0x14]);
+    script.extend_from_slice(hash);
+    script.push(0x87);
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_key_roundtrips_outpoint_and_shares_prefix() {
+        let outpoint = OutPoint {
+            hash: [0x42; 32],
+            index: 7,
+        };
+        let script = p2pkh_script(&[0x11; 20]);
+        let key = fluxnode_index_key(2, &script, &outpoint);
+        assert!(key.starts_with(&fluxnode_index_prefix(2, &script)));
+        assert_eq!(key[0], 2);
+        assert_eq!(fluxnode_index_outpoint(&key), Some(outpoint));
+        assert_eq!(fluxnode_index_outpoint(&key[1..]), None);
+    }
+}
@@ -1,11 +1,12 @@
 use fluxd_consensus::Hash256;
 use fluxd_primitives::encoding::{Decoder, Encoder};
-use fluxd_storage::{Column, KeyValueStore, StoreError, WriteBatch};
+use fluxd_storage::{Column, KeyValueStore, ReadSnapshot, StoreError, WriteBatch};
 use primitive_types::U256;
+use std::cmp::Ordering;
 use std::sync::Arc;
 
 const META_BEST_HEADER_KEY: &[u8] = b"best_header";
-const META_BEST_BLOCK_KEY: &[u8] = b"best_block";
+pub(crate) const META_BEST_BLOCK_KEY: &[u8] = b"best_block";
 
 const STATUS_HAS_HEADER: u8 = 1 << 0;
 const STATUS_HAS_BLOCK: u8 = 1 << 1;
@@ -41,6 +42,17 @@ impl HeaderEntry {
     }
 }
 
+/// Orders two cumulative chainwork values. They are stored big-endian, so byte order is numeric
+/// order.
+pub fn compare_work(a: &[u8; 32], b: &[u8; 32]) -> Ordering {
+    a.cmp(b)
+}
+
+/// Formats chainwork as 64 hex digits, most significant first, like the `chainwork` RPC fields.
+pub fn chainwork_hex(work: &[u8; 32]) -> String {
+    work.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct ChainTip {
     pub hash: Hash256,
@@ -67,6 +79,10 @@ impl<S: KeyValueStore> ChainIndex<S> {
             .map_err(StoreError::Backend)
     }
 
+    pub fn has_header(&self, hash: &Hash256) -> Result<bool, StoreError> {
+        self.store.contains(Column::HeaderIndex, hash)
+    }
+
     pub fn put_header(&self, batch: &mut WriteBatch, hash: &Hash256, entry: &HeaderEntry) {
         batch.put(Column::HeaderIndex, hash, encode_header_entry(entry));
     }
@@ -186,6 +202,16 @@ pub(crate) fn decode_header_entry(bytes: &[u8]) -> Result<HeaderEntry, String> {
     })
 }
 
+/// Best block hash as recorded in `snapshot`, for readers pairing it with other columns.
+pub fn snapshot_best_block_hash(
+    snapshot: &dyn ReadSnapshot,
+) -> Result<Option<Hash256>, StoreError> {
+    snapshot
+        .get(Column::Meta, META_BEST_BLOCK_KEY)?
+        .map(|bytes| decode_hash(&bytes).map_err(StoreError::Backend))
+        .transpose()
+}
+
 fn decode_hash(bytes: &[u8]) -> Result<Hash256, String> {
     if bytes.len() != 32 {
         return Err("invalid hash length".to_string());
@@ -211,6 +237,10 @@ pub fn status_with_failed(status: u8) -> u8 {
     status | STATUS_FAILED_VALIDATION
 }
 
+pub fn status_without_failed(status: u8) -> u8 {
+    status & !STATUS_FAILED_MASK
+}
+
 pub fn has_header(status: u8) -> bool {
     (status & STATUS_HAS_HEADER) != 0
 }
@@ -7,11 +7,17 @@ pub mod address_neighbors;
 pub mod address_tx_index;
 pub mod anchors;
 pub mod blockindex;
+pub mod consistency;
 pub mod filemeta;
 pub mod flatfiles;
+pub mod fluxnode;
+pub mod fluxnode_index;
 pub mod index;
 pub mod metrics;
+pub mod muhash;
+pub mod reconcile;
 mod shielded;
+pub mod shielded_scan;
 pub mod spentindex;
 pub mod state;
 pub mod txindex;
@@ -39,6 +39,9 @@ pub struct ConnectMetrics {
     pon_sig_blocks: AtomicU64,
     payout_us: AtomicU64,
     payout_blocks: AtomicU64,
+    utxo_flushes: AtomicU64,
+    utxo_flush_us: AtomicU64,
+    utxo_flush_entries: AtomicU64,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -77,6 +80,10 @@ pub struct ConnectMetricsSnapshot {
     pub pon_sig_blocks: u64,
     pub payout_us: u64,
     pub payout_blocks: u64,
+    /// Flushes of deferred UTXO writes (see `UtxoFlushPolicy`), their total time and size.
+    pub utxo_flushes: u64,
+    pub utxo_flush_us: u64,
+    pub utxo_flush_entries: u64,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -205,6 +212,14 @@ impl ConnectMetrics {
         self.flatfile_blocks.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn record_utxo_flush(&self, entries: u64, elapsed: Duration) {
+        self.utxo_flushes.fetch_add(1, Ordering::Relaxed);
+        self.utxo_flush_us
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.utxo_flush_entries
+            .fetch_add(entries, Ordering::Relaxed);
+    }
+
     pub fn snapshot(&self) -> ConnectMetricsSnapshot {
         ConnectMetricsSnapshot {
             utxo_us: self.utxo_us.load(Ordering::Relaxed),
@@ -241,6 +256,9 @@ impl ConnectMetrics {
             pon_sig_blocks: self.pon_sig_blocks.load(Ordering::Relaxed),
             payout_us: self.payout_us.load(Ordering::Relaxed),
             payout_blocks: self.payout_blocks.load(Ordering::Relaxed),
+            utxo_flushes: self.utxo_flushes.load(Ordering::Relaxed),
+            utxo_flush_us: self.utxo_flush_us.load(Ordering::Relaxed),
+            utxo_flush_entries: self.utxo_flush_entries.load(Ordering::Relaxed),
         }
     }
 }
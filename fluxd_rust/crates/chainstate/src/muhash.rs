@@ -0,0 +1,235 @@
+//! MuHash3072 rolling set hash, as used by bitcoind's `gettxoutsetinfo` `muhash` output.
+//!
+//! Each element is hashed with SHA256, expanded with ChaCha20 into a 3072-bit number and
+//! multiplied into an accumulator modulo the prime `2^3072 - 1103717`. The product does not
+//! depend on insertion order, so two nodes with the same UTXO set agree on the result however
+//! their databases iterate.
+
+use fluxd_consensus::Hash256;
+use sha2::{Digest, Sha256};
+
+const LIMBS: usize = 48;
+const BYTE_SIZE: usize = LIMBS * 8;
+/// `2^3072 - MAX_PRIME_DIFF` is the modulus.
+const MAX_PRIME_DIFF: u64 = 1_103_717;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Num3072([u64; LIMBS]);
+
+impl Num3072 {
+    fn one() -> Self {
+        let mut limbs = [0u64; LIMBS];
+        limbs[0] = 1;
+        Self(limbs)
+    }
+
+    fn from_le_bytes(bytes: &[u8; BYTE_SIZE]) -> Self {
+        let mut limbs = [0u64; LIMBS];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+        }
+        Self(limbs)
+    }
+
+    fn to_le_bytes(self) -> [u8; BYTE_SIZE] {
+        let mut bytes = [0u8; BYTE_SIZE];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Multiplies in place and leaves the result fully reduced below the modulus.
+    fn mul_assign(&mut self, other: &Self) {
+        let mut wide = [0u64; 2 * LIMBS];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in other.0.iter().enumerate() {
+                let cur = u128::from(wide[i + j]) + u128::from(a) * u128::from(b) + carry;
+                wide[i + j] = cur as u64;
+                carry = cur >> 64;
+            }
+            wide[i + LIMBS] = carry as u64;
+        }
+
+        // 2^3072 == MAX_PRIME_DIFF (mod p), so the high half folds into the low half.
+        let mut limbs = [0u64; LIMBS];
+        let mut carry = 0u128;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let cur = u128::from(wide[i])
+                + u128::from(wide[LIMBS + i]) * u128::from(MAX_PRIME_DIFF)
+                + carry;
+            *limb = cur as u64;
+            carry = cur >> 64;
+        }
+        while carry != 0 {
+            let mut add = carry * u128::from(MAX_PRIME_DIFF);
+            for limb in limbs.iter_mut() {
+                let cur = u128::from(*limb) + add;
+                *limb = cur as u64;
+                add = cur >> 64;
+                if add == 0 {
+                    break;
+                }
+            }
+            carry = add;
+        }
+
+        // The value is now below 2^3072; it is at least p exactly when adding MAX_PRIME_DIFF
+        // overflows, and the wrapped sum is then the reduced value.
+        let mut reduced = limbs;
+        let mut add = u128::from(MAX_PRIME_DIFF);
+        for limb in reduced.iter_mut() {
+            let cur = u128::from(*limb) + add;
+            *limb = cur as u64;
+            add = cur >> 64;
+        }
+        self.0 = if add != 0 { reduced } else { limbs };
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MuHash3072 {
+    numerator: Num3072,
+}
+
+impl Default for MuHash3072 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MuHash3072 {
+    /// The hash of the empty set.
+    pub fn new() -> Self {
+        Self {
+            numerator: Num3072::one(),
+        }
+    }
+
+    pub fn insert(&mut self, data: &[u8]) {
+        let element = element_to_num(data);
+        self.numerator.mul_assign(&element);
+    }
+
+    /// SHA256 of the accumulator's 384-byte little-endian encoding, in the byte order bitcoind
+    /// stores its `uint256` results (so `hash256_to_hex` prints the same string).
+    pub fn finalize(&self) -> Hash256 {
+        let digest = Sha256::digest(self.numerator.to_le_bytes());
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&digest);
+        out
+    }
+}
+
+fn element_to_num(data: &[u8]) -> Num3072 {
+    let digest = Sha256::digest(data);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&digest);
+    let mut bytes = [0u8; BYTE_SIZE];
+    chacha20_keystream(&key, &mut bytes);
+    Num3072::from_le_bytes(&bytes)
+}
+
+/// ChaCha20 keystream with a zero nonce, starting at block counter 0.
+fn chacha20_keystream(key: &[u8; 32], out: &mut [u8]) {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    for (word, chunk) in state[4..12].iter_mut().zip(key.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().expect("4-byte chunk"));
+    }
+    for (counter, block) in out.chunks_mut(64).enumerate() {
+        state[12] = counter as u32;
+        let mut working = state;
+        for _ in 0..10 {
+            quarter_round(&mut working, 0, 4, 8, 12);
+            quarter_round(&mut working, 1, 5, 9, 13);
+            quarter_round(&mut working, 2, 6, 10, 14);
+            quarter_round(&mut working, 3, 7, 11, 15);
+            quarter_round(&mut working, 0, 5, 10, 15);
+            quarter_round(&mut working, 1, 6, 11, 12);
+            quarter_round(&mut working, 2, 7, 8, 13);
+            quarter_round(&mut working, 3, 4, 9, 14);
+        }
+        for (bytes, (word, initial)) in block.chunks_mut(4).zip(working.iter().zip(state)) {
+            let word = word.wrapping_add(initial).to_le_bytes();
+            bytes.copy_from_slice(&word[..bytes.len()]);
+        }
+    }
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small(value: u64) -> Num3072 {
+        let mut limbs = [0u64; LIMBS];
+        limbs[0] = value;
+        Num3072(limbs)
+    }
+
+    #[test]
+    fn chacha20_matches_rfc8439_zero_key_block() {
+        let mut out = [0u8; 64];
+        chacha20_keystream(&[0u8; 32], &mut out);
+        let expected = [
+            0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5, 0x53, 0x86,
+            0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a, 0xa8, 0x36, 0xef, 0xcc,
+            0x8b, 0x77, 0x0d, 0xc7,
+        ];
+        assert_eq!(out[..32], expected);
+    }
+
+    #[test]
+    fn multiplication_reduces_modulo_prime() {
+        let mut product = small(6);
+        product.mul_assign(&small(7));
+        assert_eq!(product, small(42));
+
+        // p - 1 is -1 mod p, so its square is 1.
+        let mut minus_one = Num3072([u64::MAX; LIMBS]);
+        minus_one.0[0] = u64::MAX - MAX_PRIME_DIFF;
+        let mut square = minus_one;
+        square.mul_assign(&minus_one);
+        assert_eq!(square, Num3072::one());
+
+        // -1 * 2 = p - 2.
+        let mut doubled = minus_one;
+        doubled.mul_assign(&small(2));
+        let mut expected = minus_one;
+        expected.0[0] -= 1;
+        assert_eq!(doubled, expected);
+    }
+
+    #[test]
+    fn set_hash_ignores_insertion_order() {
+        let elements: [&[u8]; 3] = [b"alpha", b"beta", b"gamma"];
+        let mut forward = MuHash3072::new();
+        for element in elements {
+            forward.insert(element);
+        }
+        let mut backward = MuHash3072::new();
+        for element in elements.iter().rev() {
+            backward.insert(element);
+        }
+        assert_eq!(forward.finalize(), backward.finalize());
+
+        let mut partial = MuHash3072::new();
+        partial.insert(b"alpha");
+        assert_ne!(partial.finalize(), forward.finalize());
+
+        let empty = Sha256::digest(Num3072::one().to_le_bytes());
+        assert_eq!(MuHash3072::new().finalize()[..], empty[..]);
+    }
+}
@@ -0,0 +1,28 @@
+//! Startup reconciliation between the block/undo flatfiles and the block index.
+//!
+//! `connect_block` appends the block and its undo data to the flatfiles before the batch that
+//! indexes them is committed, so a crash in between leaves records nothing points at. The
+//! reverse, an index entry whose record is gone, needs the store to persist while the flatfile
+//! write is lost, as with a power cut before the page cache reaches disk. See
+//! `ChainState::reconcile_flatfiles_and_index`.
+
+/// What `ChainState::reconcile_flatfiles_and_index` found and repaired.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlatfileReconcileReport {
+    /// Complete block records past the committed end of the block files whose header is known
+    /// but not connected. They are parked as unconnected blocks so the node connects them again.
+    pub parked_blocks: usize,
+    /// Half-written records cut from the end of the block and undo files.
+    pub truncated_records: usize,
+    /// Blocks off the active chain whose record was missing. Their index entry is dropped and
+    /// the header no longer claims the block, so it is fetched again if needed.
+    pub dropped_blocks: usize,
+    /// Blocks whose undo record was missing; the undo reference is cleared as if pruned.
+    pub dropped_undo: usize,
+}
+
+impl FlatfileReconcileReport {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
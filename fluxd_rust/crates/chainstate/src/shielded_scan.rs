@@ -0,0 +1,308 @@
+//! Trial decryption of Sapling outputs and Sprout joinsplits against viewing keys.
+
+use std::sync::atomic::AtomicBool;
+
+use fluxd_consensus::Hash256;
+use fluxd_primitives::block::Block;
+use fluxd_primitives::transaction::{OutputDescription, Transaction};
+use fluxd_shielded::{joinsplit_hsig, SproutNotePlaintext, SproutViewingKey, ZCNoteDecryption};
+use sapling_crypto::keys::PreparedIncomingViewingKey;
+use sapling_crypto::note_encryption::{
+    try_sapling_note_decryption, SaplingDomain, Zip212Enforcement,
+};
+use sapling_crypto::SaplingIvk;
+use zcash_note_encryption::{EphemeralKeyBytes, ShieldedOutput, ENC_CIPHERTEXT_SIZE};
+
+use crate::state::ChainStateError;
+
+/// Blocks read per batch of [`crate::state::ChainState::scan_shielded`]; cancellation and
+/// progress are checked between batches.
+pub const SCAN_CHUNK_BLOCKS: i32 = 64;
+
+/// Keys to trial-decrypt with. Receipts refer back to them by their position in the slices
+/// passed to [`ShieldedViewingKeys::new`].
+pub struct ShieldedViewingKeys {
+    sapling: Vec<PreparedIncomingViewingKey>,
+    sprout: Vec<ZCNoteDecryption>,
+}
+
+impl ShieldedViewingKeys {
+    pub fn new(sapling: &[SaplingIvk], sprout: &[SproutViewingKey]) -> Self {
+        Self {
+            sapling: sapling
+                .iter()
+                .map(PreparedIncomingViewingKey::new)
+                .collect(),
+            sprout: sprout
+                .iter()
+                .map(|key| ZCNoteDecryption::new(key.sk_enc))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sapling.is_empty() && self.sprout.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShieldedNoteLocation {
+    Sapling { output: u32 },
+    Sprout { joinsplit: u32, output: u8 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShieldedReceipt {
+    pub height: i32,
+    pub txid: Hash256,
+    pub location: ShieldedNoteLocation,
+    /// Index into the Sapling or Sprout key list, depending on `location`.
+    pub key_index: usize,
+    pub value: u64,
+    pub memo: [u8; 512],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShieldedScanProgress {
+    pub height: i32,
+    pub end_height: i32,
+    pub receipts: usize,
+}
+
+#[derive(Default)]
+pub struct ShieldedScanOptions<'a> {
+    /// Threads used for trial decryption; 0 uses the global rayon pool.
+    pub workers: usize,
+    /// Checked between chunks of blocks; once set the scan stops with
+    /// [`ChainStateError::Interrupted`].
+    pub cancel: Option<&'a AtomicBool>,
+    /// Called after each chunk of blocks with the last height scanned.
+    pub progress: Option<&'a (dyn Fn(ShieldedScanProgress) + Sync)>,
+}
+
+struct SaplingOutputRef<'a> {
+    output: &'a OutputDescription,
+}
+
+impl ShieldedOutput<SaplingDomain, ENC_CIPHERTEXT_SIZE> for SaplingOutputRef<'_> {
+    fn ephemeral_key(&self) -> EphemeralKeyBytes {
+        EphemeralKeyBytes(self.output.ephemeral_key)
+    }
+
+    fn cmstar_bytes(
+        &self,
+    ) -> <SaplingDomain as zcash_note_encryption::Domain>::ExtractedCommitmentBytes {
+        self.output.cm
+    }
+
+    fn enc_ciphertext(&self) -> &[u8; ENC_CIPHERTEXT_SIZE] {
+        &self.output.enc_ciphertext
+    }
+}
+
+/// Every note in `block` that one of `keys` can decrypt, in transaction order.
+pub fn scan_block(
+    block: &Block,
+    height: i32,
+    keys: &ShieldedViewingKeys,
+) -> Result<Vec<ShieldedReceipt>, ChainStateError> {
+    let mut receipts = Vec::new();
+    for tx in &block.transactions {
+        if tx.shielded_outputs.is_empty() && tx.join_splits.is_empty() {
+            continue;
+        }
+        let found = scan_transaction(tx, keys);
+        if found.is_empty() {
+            continue;
+        }
+        let txid = tx.txid()?;
+        receipts.extend(found.into_iter().map(|(location, key_index, value, memo)| {
+            ShieldedReceipt {
+                height,
+                txid,
+                location,
+                key_index,
+                value,
+                memo,
+            }
+        }));
+    }
+    Ok(receipts)
+}
+
+fn scan_transaction(
+    tx: &Transaction,
+    keys: &ShieldedViewingKeys,
+) -> Vec<(ShieldedNoteLocation, usize, u64, [u8; 512])> {
+    let mut found = Vec::new();
+    if !keys.sapling.is_empty() {
+        for (output_index, output) in tx.shielded_outputs.iter().enumerate() {
+            let output_ref = SaplingOutputRef { output };
+            for (key_index, ivk) in keys.sapling.iter().enumerate() {
+                let Some((note, _recipient, memo)) =
+                    try_sapling_note_decryption(ivk, &output_ref, Zip212Enforcement::GracePeriod)
+                else {
+                    continue;
+                };
+                found.push((
+                    ShieldedNoteLocation::Sapling {
+                        output: output_index as u32,
+                    },
+                    key_index,
+                    note.value().inner(),
+                    memo,
+                ));
+                break;
+            }
+        }
+    }
+    if !keys.sprout.is_empty() {
+        for (js_index, joinsplit) in tx.join_splits.iter().enumerate() {
+            let h_sig = joinsplit_hsig(
+                &joinsplit.random_seed,
+                &joinsplit.nullifiers,
+                &tx.join_split_pub_key,
+            );
+            for (output, ciphertext) in joinsplit.ciphertexts.iter().enumerate() {
+                for (key_index, decryptor) in keys.sprout.iter().enumerate() {
+                    let Ok(plaintext) = SproutNotePlaintext::decrypt(
+                        decryptor,
+                        ciphertext,
+                        &joinsplit.ephemeral_key,
+                        &h_sig,
+                        output as u8,
+                    ) else {
+                        continue;
+                    };
+                    found.push((
+                        ShieldedNoteLocation::Sprout {
+                            joinsplit: js_index as u32,
+                            output: output as u8,
+                        },
+                        key_index,
+                        plaintext.value,
+                        plaintext.memo,
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluxd_primitives::block::{BlockHeader, CURRENT_VERSION};
+    use fluxd_primitives::outpoint::OutPoint;
+    use fluxd_primitives::transaction::{JoinSplit, SproutProof, PHGR_PROOF_SIZE};
+    use fluxd_shielded::{SproutNote, SproutSpendingKey, ZCNoteEncryption};
+
+    fn sprout_block(recipient: SproutSpendingKey, other: SproutSpendingKey, value: u64) -> Block {
+        let random_seed = [3u8; 32];
+        let nullifiers = [[4u8; 32], [5u8; 32]];
+        let join_split_pub_key = [6u8; 32];
+        let h_sig = joinsplit_hsig(&random_seed, &nullifiers, &join_split_pub_key);
+        let mut encryptor = ZCNoteEncryption::new(h_sig);
+        let mut memo = [0u8; 512];
+        memo[..6].copy_from_slice(b"sprout");
+        let paid = SproutNotePlaintext::new(
+            SproutNote {
+                a_pk: recipient.address().a_pk,
+                value,
+                rho: [7u8; 32],
+                r: [8u8; 32],
+            },
+            memo,
+        );
+        let change = SproutNotePlaintext::new(
+            SproutNote {
+                a_pk: other.address().a_pk,
+                value: 1,
+                rho: [9u8; 32],
+                r: [10u8; 32],
+            },
+            [0u8; 512],
+        );
+        let ciphertexts = [
+            change
+                .encrypt(&mut encryptor, &other.address().pk_enc)
+                .expect("encrypt"),
+            paid.encrypt(&mut encryptor, &recipient.address().pk_enc)
+                .expect("encrypt"),
+        ];
+        let joinsplit = JoinSplit {
+            vpub_old: 0,
+            vpub_new: 0,
+            anchor: [0u8; 32],
+            nullifiers,
+            commitments: [[0u8; 32]; 2],
+            ephemeral_key: encryptor.get_epk(),
+            random_seed,
+            macs: [[0u8; 32]; 2],
+            proof: SproutProof::Phgr([0u8; PHGR_PROOF_SIZE]),
+            ciphertexts,
+        };
+        let tx = Transaction {
+            f_overwintered: false,
+            version: 2,
+            version_group_id: 0,
+            vin: Vec::new(),
+            vout: Vec::new(),
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: vec![joinsplit],
+            join_split_pub_key,
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+        Block {
+            header: BlockHeader {
+                version: CURRENT_VERSION,
+                prev_block: [0u8; 32],
+                merkle_root: [0u8; 32],
+                final_sapling_root: [0u8; 32],
+                time: 1_700_000_000,
+                bits: 0,
+                nonce: [0u8; 32],
+                solution: Vec::new(),
+                nodes_collateral: OutPoint::null(),
+                block_sig: Vec::new(),
+            },
+            transactions: vec![tx],
+        }
+    }
+
+    #[test]
+    fn scan_block_decrypts_sprout_output_for_viewing_key() {
+        let recipient = SproutSpendingKey::random();
+        let other = SproutSpendingKey::random();
+        let block = sprout_block(recipient, other, 42_000);
+        let txid = block.transactions[0].txid().expect("txid");
+
+        let keys = ShieldedViewingKeys::new(&[], &[recipient.viewing_key()]);
+        let receipts = scan_block(&block, 10, &keys).expect("scan");
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].height, 10);
+        assert_eq!(receipts[0].txid, txid);
+        assert_eq!(
+            receipts[0].location,
+            ShieldedNoteLocation::Sprout {
+                joinsplit: 0,
+                output: 1,
+            }
+        );
+        assert_eq!(receipts[0].key_index, 0);
+        assert_eq!(receipts[0].value, 42_000);
+        assert_eq!(&receipts[0].memo[..6], b"sprout");
+
+        let unrelated = ShieldedViewingKeys::new(&[], &[SproutSpendingKey::random().viewing_key()]);
+        assert!(scan_block(&block, 10, &unrelated).expect("scan").is_empty());
+    }
+}
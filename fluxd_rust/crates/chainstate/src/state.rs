@@ -1,36 +1,36 @@
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use smallvec::SmallVec;
 
 use fluxd_consensus::constants::{
-    max_reorg_depth, COINBASE_MATURITY, FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1,
-    FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2, FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3,
-    FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4, FLUXNODE_DOS_REMOVE_AMOUNT,
-    FLUXNODE_DOS_REMOVE_AMOUNT_V2, FLUXNODE_MIN_CONFIRMATION_DETERMINISTIC, MAX_SCRIPT_SIZE,
-    MIN_BLOCK_VERSION, MIN_PON_BLOCK_VERSION,
+    max_reorg_depth, FLUXNODE_DOS_REMOVE_AMOUNT, FLUXNODE_DOS_REMOVE_AMOUNT_V2,
+    FLUXNODE_MIN_CONFIRMATION_DETERMINISTIC, MAX_BLOCK_SIGOPS, MAX_SCRIPT_SIZE, MIN_BLOCK_VERSION,
+    MIN_PON_BLOCK_VERSION,
 };
 use fluxd_consensus::money::MAX_MONEY;
-use fluxd_consensus::upgrades::{current_epoch_branch_id, network_upgrade_active, UpgradeIndex};
+use fluxd_consensus::upgrades::{branch_id_for_height, network_upgrade_active, UpgradeIndex};
 use fluxd_consensus::{
-    block_subsidy, exchange_fund_amount, fluxnode_collateral_kind,
-    fluxnode_collateral_matches_tier, fluxnode_subsidy, fluxnode_tier_from_collateral,
-    foundation_fund_amount, is_swap_pool_interval, min_dev_fund_amount, swap_pool_amount,
-    ChainParams, ConsensusParams, FluxnodeCollateralKind, Hash256,
+    block_subsidy, exchange_fund_amount, fluxnode_collateral_kind, fluxnode_subsidy,
+    fluxnode_tier_from_collateral, foundation_fund_amount, min_dev_fund_amount,
+    required_funding_outputs, swap_pool_amount, ChainParams, ConsensusParams,
+    FluxnodeCollateralKind, FundingOutputKind, Hash256,
 };
 use fluxd_fluxnode::cache::{apply_fluxnode_tx, FluxnodeStartMeta};
 use fluxd_fluxnode::storage::{FluxnodeRecord, KeyId};
 use fluxd_primitives::{address_to_script_pubkey, script_pubkey_to_address};
 use fluxd_primitives::block::Block;
 use fluxd_primitives::encoding::{Decodable, DecodeError, Decoder, Encoder};
-use fluxd_primitives::hash::hash160;
+use fluxd_primitives::hash::{hash160, hash256_to_hex};
+use fluxd_primitives::ids::{BlockHash, Txid};
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::transaction::{
     FluxnodeConfirmTx, FluxnodeDelegates, FluxnodeStartVariantV6, FluxnodeTx, FluxnodeTxV5,
-    FluxnodeTxV6, Transaction, TransactionEncodeError,
+    FluxnodeTxV6, Transaction, TransactionEncodeError, TxOut,
 };
-use fluxd_storage::{Column, KeyValueStore, StoreError, WriteBatch, WriteOp};
+use fluxd_storage::{Column, KeyValueStore, ReadSnapshot, StoreError, WriteBatch, WriteOp};
 use rayon::prelude::*;
 use sha2::Digest as _;
 use sha2::Sha256;
@@ -41,31 +41,53 @@ use crate::address_index::AddressIndex;
 use crate::address_tx_index::{AddressTxCursor, AddressTxIndex, DEFAULT_CHECKPOINT_INTERVAL};
 use crate::anchors::{AnchorSet, NullifierSet};
 use crate::blockindex::{BlockIndexEntry, STATUS_HAVE_DATA, STATUS_HAVE_UNDO};
+use crate::consistency::{outpoint_from_key, ConsistencyIssue, ConsistencyReport};
 use crate::filemeta::{
     block_file_info_key, parse_block_file_info_key, parse_undo_file_info_key, undo_file_info_key,
     FlatFileInfo, META_BLOCK_FILES_LAST_FILE_KEY, META_BLOCK_FILES_LAST_LEN_KEY,
     META_UNDO_FILES_LAST_FILE_KEY, META_UNDO_FILES_LAST_LEN_KEY,
 };
 use crate::flatfiles::{FileLocation, FlatFileError, FlatFileStore};
+use crate::fluxnode::{
+    check_payee_candidate, fluxnode_confirm_expire_height, payee_script, select_payee,
+    FluxnodePayee, FluxnodePayeeKey,
+};
+use crate::fluxnode_index::{
+    fluxnode_index_key, fluxnode_index_outpoint, fluxnode_index_prefix, p2pkh_script, p2sh_script,
+    FLUXNODE_INDEX_KEY_LEN,
+};
 use crate::index::{
     decode_header_entry, has_block as index_has_block, status_with_block, status_with_failed,
-    status_with_header, ChainIndex, ChainTip, HeaderEntry,
+    status_with_header, status_without_block, status_without_failed, ChainIndex, ChainTip,
+    HeaderEntry,
 };
 use crate::metrics::{ConnectMetrics, ConnectMetricsDelta};
+use crate::muhash::MuHash3072;
+use crate::reconcile::FlatfileReconcileReport;
 use crate::shielded::{
     empty_sapling_tree, empty_sprout_tree, sapling_empty_root_hash, sapling_node_from_hash,
     sapling_root_hash, sapling_tree_from_bytes, sapling_tree_to_bytes, sprout_empty_root_hash,
     sprout_root_hash, sprout_tree_from_bytes, sprout_tree_to_bytes, SaplingTree, SproutTree,
 };
+use crate::shielded_scan::{
+    scan_block as scan_shielded_block, ShieldedReceipt, ShieldedScanOptions, ShieldedScanProgress,
+    ShieldedViewingKeys, SCAN_CHUNK_BLOCKS,
+};
 use crate::spentindex::{SpentIndex, SpentIndexDetails, SpentIndexValue};
 use crate::txindex::{TxIndex, TxLocation};
 use crate::undo::{BlockUndo, FluxnodeUndo, SpentOutput};
-use crate::utxo::{outpoint_key_bytes, OutPointKey, UtxoEntry, UtxoSet};
-use crate::validation::{validate_block_with_txids, ValidationError, ValidationFlags};
+use crate::utxo::{
+    outpoint_key_bytes, OutPointKey, ScriptMatcher, UtxoEntry, UtxoSet, OUTPOINT_KEY_LEN,
+};
+use crate::validation::{
+    collect_script_failures, count_sigops, p2sh_sigops, validate_block_collecting,
+    validate_block_with_txids, verify_script_inputs, BlockValidationReport, ScriptCheckInput,
+    ValidationError, ValidationFlags,
+};
 use fluxd_pon::validation as pon_validation;
 use fluxd_pow::difficulty::{block_proof, HeaderInfo};
 use fluxd_pow::validation as pow_validation;
-use fluxd_script::interpreter::{verify_script, BLOCK_SCRIPT_VERIFY_FLAGS};
+use fluxd_script::interpreter::{BLOCK_SCRIPT_VERIFY_FLAGS, SCRIPT_VERIFY_NONE};
 use fluxd_script::message::verify_signed_message;
 
 struct ScriptCheck {
@@ -190,6 +212,11 @@ fn record_address_balance_delta_no_address(
     entry.apply(amount, kind, is_add);
 }
 
+/// `InvalidHeader` message for a fork that starts too far below the active tip.
+pub const FORK_TOO_DEEP: &str = "forked chain older than max reorganization depth";
+/// `InvalidHeader` message for a fork that would replace blocks below the last checkpoint.
+pub const FORK_BEFORE_CHECKPOINT: &str = "forked chain older than last checkpoint";
+
 #[derive(Debug)]
 pub enum ChainStateError {
     Validation(ValidationError),
@@ -200,6 +227,7 @@ pub enum ChainStateError {
     ValueOutOfRange,
     CorruptIndex(&'static str),
     InvalidHeader(&'static str),
+    Interrupted,
 }
 
 impl std::fmt::Display for ChainStateError {
@@ -213,6 +241,7 @@ impl std::fmt::Display for ChainStateError {
             ChainStateError::ValueOutOfRange => write!(f, "value out of range"),
             ChainStateError::CorruptIndex(message) => write!(f, "{message}"),
             ChainStateError::InvalidHeader(message) => write!(f, "{message}"),
+            ChainStateError::Interrupted => write!(f, "interrupted"),
         }
     }
 }
@@ -258,7 +287,8 @@ impl From<pon_validation::PonError> for ChainStateError {
 const HEADER_CACHE_CAPACITY: usize = 200_000;
 const UTXO_CACHE_CAPACITY: usize = 200_000;
 const FLUXNODE_KEY_CACHE_CAPACITY: usize = 50_000;
-const MTP_WINDOW_SIZE: usize = 11;
+const CONSISTENCY_BLOCK_CACHE_SIZE: usize = 64;
+pub const MTP_WINDOW_SIZE: usize = 11;
 
 static HEX_BYTES_CACHE: OnceLock<Mutex<HashMap<&'static str, Arc<[u8]>>>> = OnceLock::new();
 
@@ -295,6 +325,10 @@ impl HeaderCache {
         self.entries.get(hash).cloned()
     }
 
+    fn contains(&self, hash: &Hash256) -> bool {
+        self.entries.contains_key(hash)
+    }
+
     fn insert(&mut self, hash: Hash256, entry: HeaderEntry) {
         if self.entries.insert(hash, entry).is_some() {
             return;
@@ -320,6 +354,10 @@ struct UtxoCache {
     order: VecDeque<(OutPointKey, u64)>,
     capacity: usize,
     clock: u64,
+    /// UTXO writes committed but not yet in the store; `None` is a delete. Never evicted.
+    dirty: HashMap<OutPointKey, Option<Vec<u8>>>,
+    dirty_bytes: usize,
+    flush_policy: UtxoFlushPolicy,
 }
 
 impl UtxoCache {
@@ -329,9 +367,34 @@ impl UtxoCache {
             order: VecDeque::new(),
             capacity,
             clock: 0,
+            dirty: HashMap::new(),
+            dirty_bytes: 0,
+            flush_policy: UtxoFlushPolicy::default(),
         }
     }
 
+    fn stage_dirty(&mut self, key: OutPointKey, value: Option<Vec<u8>>) {
+        let added = OUTPOINT_KEY_LEN + value.as_ref().map_or(0, Vec::len);
+        let replaced = self
+            .dirty
+            .insert(key, value)
+            .map_or(0, |old| OUTPOINT_KEY_LEN + old.as_ref().map_or(0, Vec::len));
+        self.dirty_bytes = self.dirty_bytes.saturating_sub(replaced) + added;
+    }
+
+    /// Whether staging `ops` on top of the dirty set would cross a flush threshold.
+    fn flush_due_with(&self, ops: &[(OutPointKey, Option<Vec<u8>>)]) -> bool {
+        let policy = self.flush_policy;
+        let entries = self.dirty.len() + ops.len();
+        let bytes = self.dirty_bytes
+            + ops
+                .iter()
+                .map(|(_, value)| OUTPOINT_KEY_LEN + value.as_ref().map_or(0, Vec::len))
+                .sum::<usize>();
+        (policy.max_dirty_entries > 0 && entries > policy.max_dirty_entries)
+            || (policy.max_dirty_bytes > 0 && bytes > policy.max_dirty_bytes)
+    }
+
     fn get(&mut self, key: &OutPointKey) -> Option<&[u8]> {
         if self.capacity == 0 {
             return None;
@@ -459,56 +522,6 @@ struct FluxnodePaymentMeta {
     p2sh_script: Option<KeyId>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-struct FluxnodePayeeKey {
-    comparator_height: u32,
-    has_last_paid: bool,
-    hash_le: [u8; 32],
-    index: u32,
-    outpoint: OutPointKey,
-}
-
-impl FluxnodePayeeKey {
-    fn from_record(record: &FluxnodeRecord) -> Option<Self> {
-        if !(1..=3).contains(&record.tier) {
-            return None;
-        }
-        if record.confirmed_height == 0 {
-            return None;
-        }
-        let has_last_paid = record.last_paid_height > 0;
-        let comparator_height = if record.last_paid_height > 0 {
-            record.last_paid_height
-        } else {
-            record.confirmed_height
-        };
-        let hash_le = record.collateral.hash;
-        Some(Self {
-            comparator_height,
-            has_last_paid,
-            hash_le,
-            index: record.collateral.index,
-            outpoint: outpoint_key_bytes(&record.collateral),
-        })
-    }
-}
-
-impl Ord for FluxnodePayeeKey {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.comparator_height
-            .cmp(&other.comparator_height)
-            .then_with(|| self.has_last_paid.cmp(&other.has_last_paid))
-            .then_with(|| self.hash_le.cmp(&other.hash_le))
-            .then_with(|| self.index.cmp(&other.index))
-    }
-}
-
-impl PartialOrd for FluxnodePayeeKey {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 struct FluxnodePaymentsCache {
     initialized: bool,
     by_tier: [BTreeSet<FluxnodePayeeKey>; 3],
@@ -715,11 +728,14 @@ pub struct ChainState<S> {
     blocks: FlatFileStore,
     undo: FlatFileStore,
     header_cache: Mutex<HeaderCache>,
+    header_tips: Mutex<Option<HashSet<Hash256>>>,
     utxo_cache: Mutex<UtxoCache>,
     fluxnode_key_cache: Mutex<FluxnodeKeyCache>,
     shielded_cache: Mutex<Option<ShieldedTreesCache>>,
     file_meta: Mutex<FlatFileMetaCache>,
     fluxnode_payments: Mutex<FluxnodePaymentsCache>,
+    connect_metrics: OnceLock<Arc<ConnectMetrics>>,
+    max_reorg_depth: AtomicU32,
 }
 
 impl<S: KeyValueStore> ChainState<S> {
@@ -750,12 +766,410 @@ impl<S: KeyValueStore> ChainState<S> {
             blocks,
             undo,
             header_cache: Mutex::new(HeaderCache::new(HEADER_CACHE_CAPACITY)),
+            header_tips: Mutex::new(None),
             utxo_cache: Mutex::new(UtxoCache::new(utxo_cache_capacity)),
             fluxnode_key_cache: Mutex::new(FluxnodeKeyCache::new(FLUXNODE_KEY_CACHE_CAPACITY)),
             shielded_cache: Mutex::new(None),
             file_meta: Mutex::new(FlatFileMetaCache::default()),
             fluxnode_payments: Mutex::new(FluxnodePaymentsCache::new()),
+            connect_metrics: OnceLock::new(),
+            max_reorg_depth: AtomicU32::new(0),
+        }
+    }
+
+    /// Records UTXO flush counts and timings in `metrics` from now on.
+    pub fn attach_connect_metrics(&self, metrics: Arc<ConnectMetrics>) {
+        let _ = self.connect_metrics.set(metrics);
+    }
+
+    /// Changes when UTXO writes are deferred. Turning deferral off flushes what is pending.
+    pub fn set_utxo_flush_policy(&self, policy: UtxoFlushPolicy) -> Result<(), ChainStateError> {
+        self.lock_utxo_cache()?.flush_policy = policy;
+        if !policy.defers() {
+            self.flush_utxos()?;
+        }
+        Ok(())
+    }
+
+    /// Refuses reorgs that would disconnect more than `depth` blocks, on top of the consensus
+    /// limit; `0` leaves only the consensus limit. A setting above the consensus limit has no
+    /// effect.
+    pub fn set_max_reorg_depth(&self, depth: u32) {
+        self.max_reorg_depth.store(depth, Ordering::Relaxed);
+    }
+
+    /// Forks starting this many blocks or more below a tip at `tip_height` are refused.
+    pub fn reorg_depth_limit(&self, tip_height: i32) -> i64 {
+        let consensus = max_reorg_depth(i64::from(tip_height));
+        match self.max_reorg_depth.load(Ordering::Relaxed) {
+            0 => consensus,
+            configured => consensus.min(i64::from(configured) + 1),
+        }
+    }
+
+    /// Checks that a chain forking from the active chain at `fork_height` (the last shared
+    /// block) may replace the tip at `tip_height`: the fork must be shallower than
+    /// [`ChainState::reorg_depth_limit`] and must not replace blocks below the last checkpoint.
+    pub fn check_fork_point(
+        &self,
+        params: &ConsensusParams,
+        tip_height: i32,
+        fork_height: i32,
+    ) -> Result<(), ChainStateError> {
+        let reorg_depth = i64::from(tip_height) - i64::from(fork_height);
+        if reorg_depth >= self.reorg_depth_limit(tip_height) {
+            return Err(ChainStateError::InvalidHeader(FORK_TOO_DEEP));
+        }
+        if let Some(checkpoint) = last_checkpoint_on_chain(self, params, tip_height) {
+            if fork_height + 1 < checkpoint.height {
+                return Err(ChainStateError::InvalidHeader(FORK_BEFORE_CHECKPOINT));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of UTXO writes held in memory and not yet flushed to the store.
+    pub fn utxo_dirty_entries(&self) -> usize {
+        self.utxo_cache
+            .lock()
+            .map(|cache| cache.dirty.len())
+            .unwrap_or(0)
+    }
+
+    /// Approximate size of the pending UTXO writes (keys plus encoded entries).
+    pub fn utxo_dirty_bytes(&self) -> usize {
+        self.utxo_cache
+            .lock()
+            .map(|cache| cache.dirty_bytes)
+            .unwrap_or(0)
+    }
+
+    /// Writes every pending UTXO change to the store and returns how many there were. Call
+    /// before shutting down; a crash with writes pending is repaired by
+    /// [`ChainState::recover_unflushed_utxos`].
+    pub fn flush_utxos(&self) -> Result<usize, ChainStateError> {
+        let mut cache = self.lock_utxo_cache()?;
+        self.flush_utxos_locked(&mut cache)
+    }
+
+    fn flush_utxos_locked(&self, cache: &mut UtxoCache) -> Result<usize, ChainStateError> {
+        if cache.dirty.is_empty() {
+            return Ok(0);
+        }
+        let start = Instant::now();
+        let mut batch = WriteBatch::new();
+        batch.reserve(cache.dirty.len() + 1);
+        for (key, value) in &cache.dirty {
+            match value {
+                Some(bytes) => batch.put(Column::Utxo, key.as_bytes(), bytes.as_slice()),
+                None => batch.delete(Column::Utxo, key.as_bytes()),
+            }
+        }
+        batch.delete(Column::Meta, UTXO_FLUSH_TIP_KEY);
+        self.store.write_batch(&batch)?;
+
+        let dirty = std::mem::take(&mut cache.dirty);
+        cache.dirty_bytes = 0;
+        let entries = dirty.len();
+        for (key, value) in dirty {
+            match value {
+                Some(bytes) => cache.insert(key, bytes),
+                None => cache.remove(&key),
+            }
+        }
+        self.record_utxo_flush(entries, start.elapsed());
+        Ok(entries)
+    }
+
+    /// Undoes a crash that lost deferred UTXO writes: disconnects the active chain back to the
+    /// last block whose UTXO changes reached the store, so those blocks get connected again.
+    /// Returns the number of blocks disconnected. Run once at startup, before connecting blocks.
+    pub fn recover_unflushed_utxos(&self) -> Result<u32, ChainStateError> {
+        let Some(bytes) = self.store.get(Column::Meta, UTXO_FLUSH_TIP_KEY)? else {
+            return Ok(0);
+        };
+        let flushed: Hash256 = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| ChainStateError::CorruptIndex("invalid utxo flush tip"))?;
+        let flushed_height = self
+            .header_entry(&flushed)?
+            .ok_or(ChainStateError::CorruptIndex(
+                "utxo flush tip missing from header index",
+            ))?
+            .height;
+        let mut rewound = 0u32;
+        while let Some(tip) = self.index.best_block()? {
+            if tip.hash == flushed {
+                break;
+            }
+            if tip.height <= flushed_height {
+                return Err(ChainStateError::CorruptIndex(
+                    "utxo flush tip is not on the active chain",
+                ));
+            }
+            // Disconnect only writes UTXOs (restores spent entries, deletes created ones), so
+            // it is safe against a UTXO column that never saw this block's changes.
+            let batch = self.disconnect_block(&tip.hash)?;
+            self.commit_batch(batch)?;
+            rewound += 1;
+        }
+        let mut batch = WriteBatch::new();
+        batch.delete(Column::Meta, UTXO_FLUSH_TIP_KEY);
+        self.store.write_batch(&batch)?;
+        Ok(rewound)
+    }
+
+    /// Repairs what a crash between a flatfile append and the commit that indexes it leaves
+    /// behind; run at startup before blocks are served or connected.
+    ///
+    /// Records past the committed end of the files (the `flatfiles:*:last_*` meta keys) were
+    /// never indexed. A half-written one at the very end is truncated; complete block records
+    /// whose header is known but not connected are parked as unconnected blocks. When a file is
+    /// shorter than its committed end, index entries pointing at missing records are dropped:
+    /// a block off the active chain loses its index entry and `has_block`, a missing undo record
+    /// is cleared as if pruned. A missing block on the active chain cannot be disconnected
+    /// without its bytes, so that is an error and needs a reindex.
+    pub fn reconcile_flatfiles_and_index(
+        &self,
+    ) -> Result<FlatfileReconcileReport, ChainStateError> {
+        let mut report = FlatfileReconcileReport::default();
+        let mut batch = WriteBatch::new();
+
+        let blocks_end = self.committed_flatfile_end(FlatFileKind::Blocks)?;
+        let undo_end = self.committed_flatfile_end(FlatFileKind::Undo)?;
+        let mut parked = HashSet::new();
+        for location in trim_flatfile_tail(&self.blocks, blocks_end, &mut report)? {
+            let bytes = self.blocks.read(location)?;
+            let Ok(block) = Block::consensus_decode(&bytes) else {
+                continue;
+            };
+            let hash = block.header.hash();
+            if parked.contains(&hash)
+                || self.block_index_entry(&hash)?.is_some()
+                || self.unconnected_block_bytes(&hash)?.is_some()
+            {
+                continue;
+            }
+            match self.header_entry(&hash)? {
+                Some(entry) if !entry.has_block() && !entry.is_failed() => {
+                    self.store_unconnected_block_bytes(&mut batch, &hash, &bytes);
+                    parked.insert(hash);
+                }
+                _ => {}
+            }
+        }
+        report.parked_blocks = parked.len();
+        trim_flatfile_tail(&self.undo, undo_end, &mut report)?;
+
+        if flatfile_short_of(&self.blocks, blocks_end)? || flatfile_short_of(&self.undo, undo_end)?
+        {
+            self.drop_dangling_block_index(&mut batch, &mut report)?;
+        }
+
+        if !report.is_clean() {
+            self.commit_batch(batch)?;
+        }
+        Ok(report)
+    }
+
+    /// The `(file_id, len)` the last committed append of `kind` ended at.
+    fn committed_flatfile_end(&self, kind: FlatFileKind) -> Result<(u32, u64), ChainStateError> {
+        let (file_key, len_key) = match kind {
+            FlatFileKind::Blocks => (
+                META_BLOCK_FILES_LAST_FILE_KEY,
+                META_BLOCK_FILES_LAST_LEN_KEY,
+            ),
+            FlatFileKind::Undo => (META_UNDO_FILES_LAST_FILE_KEY, META_UNDO_FILES_LAST_LEN_KEY),
+        };
+        let file_id = self.meta_u32(file_key)?.unwrap_or(0);
+        let len = match self.store.get(Column::Meta, len_key)? {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| ChainStateError::CorruptIndex("invalid flatfile length meta"))?;
+                u64::from_le_bytes(bytes)
+            }
+            None => 0,
+        };
+        Ok((file_id, len))
+    }
+
+    fn drop_dangling_block_index(
+        &self,
+        batch: &mut WriteBatch,
+        report: &mut FlatfileReconcileReport,
+    ) -> Result<(), ChainStateError> {
+        let mut block_lens = HashMap::new();
+        let mut undo_lens = HashMap::new();
+        let mut dangling = Vec::new();
+        self.store
+            .for_each_prefix(Column::BlockIndex, &[], &mut |key, value| {
+                let (Ok(hash), Some(entry)) =
+                    (Hash256::try_from(key), BlockIndexEntry::decode(value))
+                else {
+                    return Ok(());
+                };
+                let block_present =
+                    flatfile_record_present(&self.blocks, entry.block, &mut block_lens);
+                let undo_present = entry.undo.is_none_or(|location| {
+                    flatfile_record_present(&self.undo, location, &mut undo_lens)
+                });
+                if !block_present || !undo_present {
+                    dangling.push((hash, entry, block_present));
+                }
+                Ok(())
+            })?;
+
+        for (hash, mut entry, block_present) in dangling {
+            if block_present {
+                entry.undo = None;
+                entry.status &= !STATUS_HAVE_UNDO;
+                batch.put(Column::BlockIndex, hash, entry.encode());
+                batch.delete(Column::BlockUndo, hash);
+                report.dropped_undo += 1;
+                continue;
+            }
+            let header = self.header_entry(&hash)?;
+            if let Some(header) = &header {
+                if self.index.height_hash(header.height)? == Some(hash) {
+                    return Err(ChainStateError::CorruptIndex(
+                        "active chain block is missing from the flatfiles; reindex required",
+                    ));
+                }
+            }
+            batch.delete(Column::BlockIndex, hash);
+            batch.delete(Column::BlockUndo, hash);
+            if let Some(mut header) = header {
+                header.status = status_without_block(header.status);
+                self.index.put_header(batch, &hash, &header);
+                if let Ok(mut cache) = self.header_cache.lock() {
+                    cache.insert(hash, header);
+                }
+            }
+            report.dropped_blocks += 1;
+        }
+        Ok(())
+    }
+
+    fn lock_utxo_cache(&self) -> Result<std::sync::MutexGuard<'_, UtxoCache>, ChainStateError> {
+        self.utxo_cache
+            .lock()
+            .map_err(|_| ChainStateError::CorruptIndex("utxo cache lock poisoned"))
+    }
+
+    fn record_utxo_flush(&self, entries: usize, elapsed: Duration) {
+        if let Some(metrics) = self.connect_metrics.get() {
+            metrics.record_utxo_flush(entries as u64, elapsed);
+        }
+    }
+
+    /// Writes `batch` and returns what actually reached the store. While the flush policy
+    /// allows it and the batch only extends the best block, its UTXO ops are held back in the
+    /// cache; any other batch that moves the tip or touches UTXOs (a disconnect, a crossed
+    /// threshold) writes them together with every pending UTXO change. The meta key
+    /// `UTXO_FLUSH_TIP_KEY` names the last block whose UTXO changes are all in the store and
+    /// exists exactly while changes are pending.
+    fn write_batch_deferring_utxos(
+        &self,
+        batch: WriteBatch,
+    ) -> Result<WriteBatch, ChainStateError> {
+        let mut cache = self.lock_utxo_cache()?;
+        let new_tip = batch_best_block(&batch);
+        let defer_from = match new_tip {
+            Some(tip) if cache.flush_policy.defers() => self.extended_best_block(&tip)?,
+            _ => None,
+        };
+        let passthrough = cache.dirty.is_empty()
+            || (new_tip.is_none()
+                && !batch.iter().any(|op| {
+                    matches!(
+                        op,
+                        WriteOp::Put {
+                            column: Column::Utxo,
+                            ..
+                        } | WriteOp::Delete {
+                            column: Column::Utxo,
+                            ..
+                        }
+                    )
+                }));
+        if defer_from.is_none() && passthrough {
+            self.store.write_batch(&batch)?;
+            return Ok(batch);
+        }
+
+        let mut out = WriteBatch::new();
+        let mut utxo_ops: Vec<(OutPointKey, Option<Vec<u8>>)> = Vec::new();
+        for op in batch.into_ops() {
+            match op {
+                WriteOp::Put { column, key, value } => {
+                    match (column, OutPointKey::from_slice(key.as_slice())) {
+                        (Column::Utxo, Some(outpoint_key)) => {
+                            utxo_ops.push((outpoint_key, Some(value.into_vec())))
+                        }
+                        _ => out.put(column, key, value),
+                    }
+                }
+                WriteOp::Delete { column, key } => {
+                    match (column, OutPointKey::from_slice(key.as_slice())) {
+                        (Column::Utxo, Some(outpoint_key)) => utxo_ops.push((outpoint_key, None)),
+                        _ => out.delete(column, key),
+                    }
+                }
+            }
+        }
+
+        if let Some(flushed_tip) = defer_from {
+            if !cache.flush_due_with(&utxo_ops) {
+                if cache.dirty.is_empty() {
+                    out.put(Column::Meta, UTXO_FLUSH_TIP_KEY, flushed_tip);
+                }
+                self.store.write_batch(&out)?;
+                for (key, value) in utxo_ops {
+                    cache.stage_dirty(key, value);
+                }
+                return Ok(out);
+            }
+        }
+
+        let start = Instant::now();
+        let overridden: HashSet<OutPointKey> = utxo_ops.iter().map(|(key, _)| *key).collect();
+        let mut entries = utxo_ops.len();
+        for (key, value) in &cache.dirty {
+            if overridden.contains(key) {
+                continue;
+            }
+            entries += 1;
+            match value {
+                Some(bytes) => out.put(Column::Utxo, key.as_bytes(), bytes.as_slice()),
+                None => out.delete(Column::Utxo, key.as_bytes()),
+            }
+        }
+        for (key, value) in utxo_ops {
+            match value {
+                Some(bytes) => out.put(Column::Utxo, key.as_bytes(), bytes),
+                None => out.delete(Column::Utxo, key.as_bytes()),
+            }
         }
+        out.delete(Column::Meta, UTXO_FLUSH_TIP_KEY);
+        self.store.write_batch(&out)?;
+        cache.dirty.clear();
+        cache.dirty_bytes = 0;
+        self.record_utxo_flush(entries, start.elapsed());
+        Ok(out)
+    }
+
+    /// The current best block, if `tip` is its direct child.
+    fn extended_best_block(&self, tip: &Hash256) -> Result<Option<Hash256>, ChainStateError> {
+        let Some(best) = self.index.best_block()? else {
+            return Ok(None);
+        };
+        Ok(self
+            .header_entry(tip)?
+            .filter(|entry| entry.prev_hash == best.hash)
+            .map(|_| best.hash))
     }
 
     pub fn best_header(&self) -> Result<Option<ChainTip>, ChainStateError> {
@@ -766,6 +1180,12 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(self.index.best_block()?)
     }
 
+    /// Cumulative chainwork up to and including the header `hash`; compare with
+    /// `index::compare_work`.
+    pub fn chainwork_at(&self, hash: &Hash256) -> Result<Option<[u8; 32]>, ChainStateError> {
+        Ok(self.header_entry(hash)?.map(|entry| entry.chainwork))
+    }
+
     pub fn header_entry(
         &self,
         hash: &fluxd_consensus::Hash256,
@@ -784,6 +1204,29 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(entry)
     }
 
+    /// Whether a header entry exists for `hash`, without decoding it.
+    pub fn has_header_entry(
+        &self,
+        hash: &fluxd_consensus::Hash256,
+    ) -> Result<bool, ChainStateError> {
+        if let Ok(cache) = self.header_cache.lock() {
+            if cache.contains(hash) {
+                return Ok(true);
+            }
+        }
+        Ok(self.index.has_header(hash)?)
+    }
+
+    /// Median timestamp of the `window` headers ending at `hash` (fewer near genesis).
+    /// Consensus uses a window of [`MTP_WINDOW_SIZE`].
+    pub fn median_time_past(&self, hash: &Hash256, window: usize) -> Result<u32, ChainStateError> {
+        let headers = collect_headers(self, hash, window, None)?;
+        if headers.is_empty() {
+            return Ok(0);
+        }
+        u32::try_from(median_time_past(&headers)).map_err(|_| ChainStateError::ValueOutOfRange)
+    }
+
     pub fn mark_header_failed(
         &self,
         batch: &mut WriteBatch,
@@ -838,6 +1281,47 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(Some(current_hash))
     }
 
+    /// Rejects a header whose hash contradicts the checkpoint pinned at its height.
+    pub fn check_against_checkpoints(
+        &self,
+        params: &ConsensusParams,
+        height: i32,
+        hash: &Hash256,
+    ) -> Result<(), ChainStateError> {
+        match params.checkpoint_at(height) {
+            Some(checkpoint) if checkpoint.hash != *hash => {
+                Err(ChainStateError::InvalidHeader("checkpoint mismatch"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns true when `hash` at `height` is the assume-valid block or one of its
+    /// ancestors, and the assume-valid block is on the best header chain.
+    pub fn is_assumed_valid(
+        &self,
+        assume_valid: Option<&Hash256>,
+        height: i32,
+        hash: &Hash256,
+    ) -> Result<bool, ChainStateError> {
+        let Some(assume_valid) = assume_valid else {
+            return Ok(false);
+        };
+        let Some(entry) = self.header_entry(assume_valid)? else {
+            return Ok(false);
+        };
+        if entry.height < height {
+            return Ok(false);
+        }
+        let Some(best) = self.index.best_header()? else {
+            return Ok(false);
+        };
+        if self.header_ancestor_hash(&best.hash, entry.height)? != Some(*assume_valid) {
+            return Ok(false);
+        }
+        Ok(self.header_ancestor_hash(assume_valid, height)? == Some(*hash))
+    }
+
     pub fn insert_header(
         &self,
         header: &fluxd_primitives::block::BlockHeader,
@@ -957,6 +1441,9 @@ impl<S: KeyValueStore> ChainState<S> {
             return Ok(existing.clone());
         }
         if let Some(existing) = self.index.get_header(&hash)? {
+            if existing.is_failed() {
+                return Ok(existing);
+            }
             batch.put(
                 Column::BlockHeader,
                 hash.to_vec(),
@@ -1024,32 +1511,12 @@ impl<S: KeyValueStore> ChainState<S> {
             Some(entry) => (entry.height + 1, entry.chainwork_value()),
             None => (0, primitive_types::U256::zero()),
         };
+        let prev_failed = prev_entry.as_ref().is_some_and(HeaderEntry::is_failed);
 
-        if let Some(checkpoint) = params
-            .checkpoints
-            .iter()
-            .find(|checkpoint| checkpoint.height == height)
-        {
-            if checkpoint.hash != hash {
-                return Err(ChainStateError::InvalidHeader("checkpoint mismatch"));
-            }
-        }
+        self.check_against_checkpoints(params, height, &hash)?;
 
         if let Some(best_block) = self.index.best_block()? {
-            let reorg_depth = best_block.height as i64 - (height as i64 - 1);
-            if reorg_depth >= max_reorg_depth(best_block.height as i64) {
-                return Err(ChainStateError::InvalidHeader(
-                    "forked chain older than max reorganization depth",
-                ));
-            }
-
-            if let Some(checkpoint) = last_checkpoint_on_chain(self, params, best_block.height) {
-                if height < checkpoint.height {
-                    return Err(ChainStateError::InvalidHeader(
-                        "forked chain older than last checkpoint",
-                    ));
-                }
-            }
+            self.check_fork_point(params, best_block.height, height - 1)?;
         }
 
         let pon_active = network_upgrade_active(height, &params.upgrades, UpgradeIndex::Pon);
@@ -1102,7 +1569,8 @@ impl<S: KeyValueStore> ChainState<S> {
                     ));
                 }
             } else {
-                let mtp_headers = collect_headers(self, &prev_hash, 11, Some(pending))?;
+                let mtp_headers =
+                    collect_headers(self, &prev_hash, MTP_WINDOW_SIZE, Some(pending))?;
                 let mtp = median_time_past(&mtp_headers);
                 if header.time as i64 <= mtp {
                     return Err(ChainStateError::InvalidHeader("block timestamp too early"));
@@ -1175,7 +1643,11 @@ impl<S: KeyValueStore> ChainState<S> {
             time: header.time,
             bits: header.bits,
             chainwork,
-            status: status_with_header(0),
+            status: if prev_failed {
+                status_with_failed(status_with_header(0))
+            } else {
+                status_with_header(0)
+            },
         };
 
         self.index.put_header(batch, &hash, &entry);
@@ -1189,11 +1661,15 @@ impl<S: KeyValueStore> ChainState<S> {
             cache.insert(hash, entry.clone());
         }
 
+        // Only strictly more work replaces the best header. On an equal-work tie the incumbent
+        // stays, so the first-received chain wins (zcashd orders candidates by nSequenceId the
+        // same way); headers later in the same batch count as received later.
         let new_work = primitive_types::U256::from_big_endian(&entry.chainwork);
-        let should_update_best = match best {
-            Some((_, best_work)) => new_work > *best_work,
-            None => true,
-        };
+        let should_update_best = !prev_failed
+            && match best {
+                Some((_, best_work)) => new_work > *best_work,
+                None => true,
+            };
         if should_update_best {
             *best = Some((hash, new_work));
             self.index.set_best_header(batch, &hash);
@@ -1269,20 +1745,12 @@ impl<S: KeyValueStore> ChainState<S> {
             None => (0, primitive_types::U256::zero()),
         };
 
-        if let Some(checkpoint) = params
-            .checkpoints
-            .iter()
-            .find(|checkpoint| checkpoint.height == height)
-        {
-            if checkpoint.hash != hash {
-                return Err(ChainStateError::InvalidHeader("checkpoint mismatch"));
-            }
-        }
+        self.check_against_checkpoints(params, height, &hash)?;
 
         if let Some(best_block) = self.index.best_block()? {
             let reorg_depth = best_block.height as i64 - (height as i64 - 1);
-            if reorg_depth >= max_reorg_depth(best_block.height as i64) {
-                return Err(ChainStateError::InvalidHeader("reorg too deep"));
+            if reorg_depth >= self.reorg_depth_limit(best_block.height) {
+                return Err(ChainStateError::InvalidHeader(FORK_TOO_DEEP));
             }
         }
 
@@ -1986,30 +2454,14 @@ impl<S: KeyValueStore> ChainState<S> {
         utxo: &UtxoEntry,
         meta: FluxnodePaymentMeta,
         params: &ChainParams,
-    ) -> Result<Vec<u8>, ChainStateError> {
-        let p2sh_script = |hash: &[u8; 20]| {
-            let mut script = Vec::with_capacity(23);
-            script.extend_from_slice(&[0xa9, 0x14]);
-            script.extend_from_slice(hash);
-            script.push(0x87);
-            script
-        };
-        let p2pkh_script = |hash: &[u8; 20]| {
-            let mut script = Vec::with_capacity(25);
-            script.extend_from_slice(&[0x76, 0xa9, 0x14]);
-            script.extend_from_slice(hash);
-            script.extend_from_slice(&[0x88, 0xac]);
-            script
-        };
-
+    ) -> Result<(Vec<u8>, bool), ChainStateError> {
         if let Some(redeem_key) = meta.p2sh_script {
             let redeem_script =
                 self.fluxnode_key_bytes(redeem_key)?
                     .ok_or(ChainStateError::CorruptIndex(
                         "missing fluxnode redeem script",
                     ))?;
-            let script_hash = hash160(&redeem_script);
-            return Ok(p2sh_script(&script_hash));
+            return payee_script(Some(&redeem_script), None, &utxo.script_pubkey, params);
         }
 
         let collateral_key = meta.collateral_pubkey.ok_or(ChainStateError::CorruptIndex(
@@ -2020,24 +2472,17 @@ impl<S: KeyValueStore> ChainState<S> {
                 .ok_or(ChainStateError::CorruptIndex(
                     "missing fluxnode collateral pubkey bytes",
                 ))?;
-
-        let is_p2sh_signing_key = params.fluxnode.p2sh_public_keys.iter().any(|key| {
-            hex_to_bytes_cached(key.key)
-                .is_some_and(|expected| expected.as_ref() == pubkey_bytes.as_ref())
-        });
-        if is_p2sh_signing_key {
-            return Ok(utxo.script_pubkey.clone());
-        }
-
-        Ok(p2pkh_script(&hash160(pubkey_bytes.as_ref())))
+        payee_script(None, Some(&pubkey_bytes), &utxo.script_pubkey, params)
     }
 
-    fn next_fluxnode_payee(
+    /// Walks the tier's payment queue from the front, dropping candidates that can no longer be
+    /// paid. Use [`select_payee`] rather than calling this directly.
+    pub(crate) fn next_fluxnode_payee(
         &self,
         tier: u8,
         pay_height: i32,
         params: &ChainParams,
-    ) -> Result<Option<(OutPoint, Vec<u8>)>, ChainStateError> {
+    ) -> Result<Option<FluxnodePayee>, ChainStateError> {
         if !(1..=3).contains(&tier) {
             return Ok(None);
         }
@@ -2047,8 +2492,6 @@ impl<S: KeyValueStore> ChainState<S> {
         self.ensure_fluxnode_payments_cache_loaded()?;
 
         let tier_index = (tier - 1) as usize;
-        let pay_height_u32 =
-            u32::try_from(pay_height).map_err(|_| ChainStateError::ValueOutOfRange)?;
         let mut removed = 0usize;
         let mut last_reason = "none";
 
@@ -2086,21 +2529,6 @@ impl<S: KeyValueStore> ChainState<S> {
                 continue;
             }
 
-            let expired = fluxnode_confirm_expired_for_pay_height(
-                pay_height_u32,
-                meta.last_confirmed_height,
-                &params.consensus,
-            );
-            if expired {
-                let mut cache = self.fluxnode_payments.lock().map_err(|_| {
-                    ChainStateError::CorruptIndex("fluxnode payments cache lock poisoned")
-                })?;
-                cache.remove_outpoint(&key.outpoint);
-                removed = removed.saturating_add(1);
-                last_reason = "expired";
-                continue;
-            }
-
             let bytes = key.outpoint.as_bytes();
             let mut hash = [0u8; 32];
             hash.copy_from_slice(&bytes[..32]);
@@ -2111,26 +2539,32 @@ impl<S: KeyValueStore> ChainState<S> {
                 index: u32::from_le_bytes(index_bytes),
             };
 
-            let Some(utxo) = self.utxo_entry(&outpoint)? else {
-                let mut cache = self.fluxnode_payments.lock().map_err(|_| {
-                    ChainStateError::CorruptIndex("fluxnode payments cache lock poisoned")
-                })?;
-                cache.remove_outpoint(&key.outpoint);
-                removed = removed.saturating_add(1);
-                last_reason = "missing_utxo";
-                continue;
-            };
-            if !fluxnode_collateral_matches_tier(pay_height, utxo.value, tier, &params.fluxnode) {
+            let utxo = self.utxo_entry(&outpoint.txid(), outpoint.index)?;
+            if let Err(reason) = check_payee_candidate(
+                tier,
+                pay_height,
+                meta.last_confirmed_height,
+                utxo.as_ref(),
+                params,
+            ) {
                 let mut cache = self.fluxnode_payments.lock().map_err(|_| {
                     ChainStateError::CorruptIndex("fluxnode payments cache lock poisoned")
                 })?;
                 cache.remove_outpoint(&key.outpoint);
                 removed = removed.saturating_add(1);
-                last_reason = "collateral_mismatch";
+                last_reason = reason.as_str();
                 continue;
             }
-            let script_pubkey = self.fluxnode_payee_script(&utxo, meta, params)?;
-            return Ok(Some((outpoint, script_pubkey)));
+            let Some(utxo) = utxo else {
+                continue;
+            };
+            let (script_pubkey, is_p2sh) = self.fluxnode_payee_script(&utxo, meta, params)?;
+            return Ok(Some(FluxnodePayee {
+                tier,
+                outpoint,
+                script_pubkey,
+                is_p2sh,
+            }));
         }
     }
 
@@ -2142,18 +2576,15 @@ impl<S: KeyValueStore> ChainState<S> {
         if (height as i64) < params.fluxnode.start_payments_height {
             return Ok(Vec::new());
         }
-        let pay_height = height.saturating_sub(1);
         let block_value = block_subsidy(height, &params.consensus);
         let mut payouts = Vec::new();
         for tier in 1u8..=3u8 {
-            if let Some((outpoint, script_pubkey)) =
-                self.next_fluxnode_payee(tier, pay_height, params)?
-            {
+            if let Some(payee) = select_payee(tier, height, self, params)? {
                 let amount = fluxnode_subsidy(height, block_value, tier as i32, &params.consensus);
                 payouts.push(FluxnodePayout {
                     tier,
-                    outpoint,
-                    script_pubkey,
+                    outpoint: payee.outpoint,
+                    script_pubkey: payee.script_pubkey,
                     amount,
                 });
             }
@@ -2310,7 +2741,29 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(payouts.into_iter().map(|p| p.outpoint).collect())
     }
 
-    pub fn connect_block(
+    /// Checks that a PON header is signed by the operator key of the fluxnode registered for
+    /// its `nodes_collateral`. PoW headers and headers below PON activation pass unchecked.
+    /// Uses the current fluxnode set, so `height` must be the next block to connect.
+    pub fn validate_pon_block_signature(
+        &self,
+        header: &fluxd_primitives::block::BlockHeader,
+        height: i32,
+        params: &ConsensusParams,
+    ) -> Result<(), ChainStateError> {
+        if !header.is_pon() || !network_upgrade_active(height, &params.upgrades, UpgradeIndex::Pon)
+        {
+            return Ok(());
+        }
+        let operator_pubkey = self
+            .operator_pubkey_bytes(&header.nodes_collateral)?
+            .ok_or(ChainStateError::InvalidHeader(
+                "missing fluxnode entry for pon signature",
+            ))?;
+        pon_validation::validate_pon_signature(header, params, operator_pubkey.as_ref())?;
+        Ok(())
+    }
+
+    pub fn connect_block(
         &self,
         block: &Block,
         height: i32,
@@ -2322,6 +2775,15 @@ impl<S: KeyValueStore> ChainState<S> {
         block_bytes: Option<&[u8]>,
         block_location: Option<FileLocation>,
     ) -> Result<WriteBatch, ChainStateError> {
+        if flags.check_canonical_encoding {
+            if let Some(bytes) = block_bytes {
+                if !Block::reencode_matches(bytes) {
+                    return Err(
+                        ValidationError::InvalidBlock("non-canonical block encoding").into(),
+                    );
+                }
+            }
+        }
         let consensus = &params.consensus;
         let mut batch = WriteBatch::new();
         let header_entry = self.insert_header(&block.header, consensus, &mut batch)?;
@@ -2384,20 +2846,13 @@ impl<S: KeyValueStore> ChainState<S> {
             && network_upgrade_active(height, &consensus.upgrades, UpgradeIndex::Pon)
         {
             let pon_sig_start = Instant::now();
-            let operator_pubkey = self
-                .operator_pubkey_bytes(&block.header.nodes_collateral)?
-                .ok_or(ChainStateError::InvalidHeader(
-                    "missing fluxnode entry for pon signature",
-                ))?;
-            pon_validation::validate_pon_signature(
-                &block.header,
-                consensus,
-                operator_pubkey.as_ref(),
-            )?;
+            self.validate_pon_block_signature(&block.header, height, consensus)?;
             pon_sig_time = pon_sig_start.elapsed();
             pon_sig_blocks = 1;
         }
         check_coinbase_funding(&block.transactions[0], height, params)?;
+        let check_script = flags.check_script
+            && !self.is_assumed_valid(flags.assume_valid.as_ref(), height, &block.header.hash())?;
         let payout_start = Instant::now();
         let paid_fluxnodes =
             self.check_deterministic_fluxnode_payouts(&block.transactions[0], height, params)?;
@@ -2470,7 +2925,8 @@ impl<S: KeyValueStore> ChainState<S> {
         let mut address_balance_deltas: HashMap<Hash256, AddressBalanceDelta> = HashMap::new();
         let mut spent_outpoints: HashSet<OutPointKey> = HashSet::with_capacity(estimated_inputs);
         let mut block_script_checks: Vec<ScriptCheck> = Vec::new();
-        let branch_id = current_epoch_branch_id(height, &consensus.upgrades);
+        let mut block_sigops = 0usize;
+        let branch_id = branch_id_for_height(height, &consensus.upgrades);
         let flux_rebrand_active =
             network_upgrade_active(height, &consensus.upgrades, UpgradeIndex::Flux);
         let mut total_fees = 0i64;
@@ -2573,6 +3029,7 @@ impl<S: KeyValueStore> ChainState<S> {
                 }
             }
 
+            block_sigops = block_sigops.saturating_add(count_sigops(tx, SCRIPT_VERIFY_NONE, &[]));
             if !is_coinbase {
                 let mut transparent_in = 0i64;
                 for (input_index, input) in tx.vin.iter().enumerate() {
@@ -2661,7 +3118,7 @@ impl<S: KeyValueStore> ChainState<S> {
                         .ok_or(ChainStateError::ValueOutOfRange)?;
                     if entry.is_coinbase {
                         let spend_height = height as i64 - entry.height as i64;
-                        if spend_height < COINBASE_MATURITY as i64 {
+                        if spend_height < i64::from(consensus.coinbase_maturity) {
                             return Err(ChainStateError::Validation(
                                 ValidationError::InvalidTransaction("premature spend of coinbase"),
                             ));
@@ -2677,7 +3134,9 @@ impl<S: KeyValueStore> ChainState<S> {
                             ));
                         }
                     }
-                    if flags.check_script {
+                    block_sigops = block_sigops
+                        .saturating_add(p2sh_sigops(&input.script_sig, &entry.script_pubkey));
+                    if check_script {
                         let spent_index = undo.spent.len();
                         block_script_checks.push(ScriptCheck {
                             tx_index: index,
@@ -2742,6 +3201,11 @@ impl<S: KeyValueStore> ChainState<S> {
                     .checked_add(fee)
                     .ok_or(ChainStateError::ValueOutOfRange)?;
             }
+            if block_sigops > MAX_BLOCK_SIGOPS as usize {
+                return Err(ChainStateError::Validation(ValidationError::InvalidBlock(
+                    "block sigops limit exceeded",
+                )));
+            }
 
             if let Some(entry) = fluxnode_undo_entry(&self.store, tx)? {
                 undo.fluxnode.push(entry);
@@ -2958,41 +3422,35 @@ impl<S: KeyValueStore> ChainState<S> {
             }
         }
 
-        if flags.check_script && !block_script_checks.is_empty() {
+        if check_script && !block_script_checks.is_empty() {
             let script_start = Instant::now();
-            let result = block_script_checks.par_iter().try_for_each(|check| {
-                let tx = &block.transactions[check.tx_index];
-                let script_sig = &tx.vin[check.input_index].script_sig;
-                let script_pubkey = &undo.spent[check.spent_index].entry.script_pubkey;
-                verify_script(
-                    script_sig,
-                    script_pubkey,
-                    tx,
-                    check.input_index,
-                    check.value,
-                    BLOCK_SCRIPT_VERIFY_FLAGS,
-                    branch_id,
-                )
-                .map_err(|err| (check.tx_index, check.input_index, err))
-            });
+            let inputs: Vec<ScriptCheckInput<'_>> = block_script_checks
+                .iter()
+                .map(|check| ScriptCheckInput {
+                    tx: &block.transactions[check.tx_index],
+                    tx_index: check.tx_index,
+                    input_index: check.input_index,
+                    script_pubkey: &undo.spent[check.spent_index].entry.script_pubkey,
+                    amount: check.value,
+                })
+                .collect();
+            let result = verify_script_inputs(
+                &inputs,
+                BLOCK_SCRIPT_VERIFY_FLAGS,
+                branch_id,
+                flags.script_pool.as_deref(),
+            );
             if let Some(metrics) = flags.metrics.as_ref() {
                 metrics.record_script(script_start.elapsed());
             }
-            if let Err((tx_index, input_index, err)) = result {
-                if let Ok(txid) = block.transactions[tx_index].txid() {
-                    fluxd_log::log_warn!(
-                        "script validation failed for tx {} input {}: {}",
-                        hash256_to_hex(&txid),
-                        input_index,
-                        err
-                    );
-                } else {
-                    fluxd_log::log_warn!(
-                        "script validation failed for input {}: {}",
-                        input_index,
-                        err
-                    );
-                }
+            if let Err(failure) = result {
+                let txid = txids[failure.tx_index];
+                fluxd_log::log_warn!(
+                    "script validation failed for tx {} input {}: {}",
+                    hash256_to_hex(&txid),
+                    failure.input_index,
+                    failure.error
+                );
                 return Err(ChainStateError::Validation(
                     ValidationError::InvalidTransaction("script validation failed"),
                 ));
@@ -3238,6 +3696,11 @@ impl<S: KeyValueStore> ChainState<S> {
             ));
         }
         batch.put(Column::Meta, VALUE_POOLS_KEY, value_pools.encode());
+        batch.put(
+            Column::Meta,
+            value_pools_height_key(height as u32),
+            value_pools.encode(),
+        );
 
         let undo_encode_start = Instant::now();
         let undo_bytes = undo.encode();
@@ -3609,6 +4072,7 @@ impl<S: KeyValueStore> ChainState<S> {
             ));
         }
         batch.put(Column::Meta, VALUE_POOLS_KEY, value_pools.encode());
+        batch.delete(Column::Meta, value_pools_height_key(height_u32));
 
         if !address_tx_events.is_empty() {
             for (script_hash, keys) in address_tx_events.iter_mut() {
@@ -3660,10 +4124,190 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(())
     }
 
-    pub fn commit_batch(&self, batch: WriteBatch) -> Result<(), ChainStateError> {
+    /// Marks `hash` and every known descendant invalid, disconnects the active chain back to
+    /// its parent and points the best header at the most-work chain that is still valid.
+    ///
+    /// The flag lives in the header index, so it survives restarts until `reconsider_block`.
+    pub fn invalidate_block(&self, hash: &Hash256) -> Result<(), ChainStateError> {
+        let entry = self
+            .header_entry(hash)?
+            .ok_or(ChainStateError::MissingHeader)?;
+        if entry.height == 0 {
+            return Err(ChainStateError::InvalidHeader(
+                "cannot invalidate genesis block",
+            ));
+        }
+
+        let mut batch = WriteBatch::new();
+        self.mark_header_failed(&mut batch, hash)?;
+        for (descendant, _) in self.descendant_headers(hash, entry.height)? {
+            self.mark_header_failed(&mut batch, &descendant)?;
+        }
+        self.commit_batch(batch)?;
+
+        while let Some(tip) = self.index.best_block()? {
+            if tip.height < entry.height
+                || self.header_ancestor_hash(&tip.hash, entry.height)? != Some(*hash)
+            {
+                break;
+            }
+            let batch = self.disconnect_block(&tip.hash)?;
+            self.commit_batch(batch)?;
+        }
+
+        self.reselect_best_header()
+    }
+
+    /// Clears the invalid flag set by `invalidate_block` from `hash`, its descendants and the
+    /// failed ancestors leading up to it, then re-selects the best header.
+    pub fn reconsider_block(&self, hash: &Hash256) -> Result<(), ChainStateError> {
+        let entry = self
+            .header_entry(hash)?
+            .ok_or(ChainStateError::MissingHeader)?;
+
+        let mut batch = WriteBatch::new();
+        let mut clear = |hash: Hash256, mut entry: HeaderEntry| {
+            if entry.is_failed() {
+                entry.status = status_without_failed(entry.status);
+                self.index.put_header(&mut batch, &hash, &entry);
+                if let Ok(mut cache) = self.header_cache.lock() {
+                    cache.insert(hash, entry);
+                }
+            }
+        };
+        for (descendant, descendant_entry) in self.descendant_headers(hash, entry.height)? {
+            clear(descendant, descendant_entry);
+        }
+        let mut current = *hash;
+        let mut current_entry = entry;
+        while current_entry.is_failed() {
+            let prev_hash = current_entry.prev_hash;
+            let height = current_entry.height;
+            clear(current, current_entry);
+            if height == 0 {
+                break;
+            }
+            current = prev_hash;
+            current_entry = self
+                .header_entry(&current)?
+                .ok_or(ChainStateError::MissingHeader)?;
+        }
+        self.commit_batch(batch)?;
+
+        self.reselect_best_header()
+    }
+
+    fn descendant_headers(
+        &self,
+        hash: &Hash256,
+        height: i32,
+    ) -> Result<Vec<(Hash256, HeaderEntry)>, ChainStateError> {
+        let mut seen = HashSet::new();
+        let mut descendants = Vec::new();
+        for tip in self.header_tips()? {
+            let Some(mut entry) = self.header_entry(&tip)? else {
+                continue;
+            };
+            if entry.height <= height || self.header_ancestor_hash(&tip, height)? != Some(*hash) {
+                continue;
+            }
+            let mut current = tip;
+            while entry.height > height && seen.insert(current) {
+                let prev_hash = entry.prev_hash;
+                descendants.push((current, entry));
+                current = prev_hash;
+                entry = self
+                    .header_entry(&current)?
+                    .ok_or(ChainStateError::MissingHeader)?;
+            }
+        }
+        Ok(descendants)
+    }
+
+    /// Re-points the best header at the most-work header that is not marked failed. Only chain
+    /// tips are candidates; a failed tip stands in for its last valid ancestor.
+    ///
+    /// Ties keep the incumbent, as in `insert_header_with_pending`: the active tip beats any
+    /// equal-work header, then the current best header does. The header index keeps no receive
+    /// order, so a tie between two headers that are neither is broken arbitrarily.
+    fn reselect_best_header(&self) -> Result<(), ChainStateError> {
+        let mut best = self.index.best_block()?.map(|tip| {
+            (
+                tip.hash,
+                primitive_types::U256::from_big_endian(&tip.chainwork),
+            )
+        });
+        let incumbent = match self.index.best_header()? {
+            Some(tip) => self
+                .index
+                .get_header(&tip.hash)?
+                .map(|entry| (tip.hash, entry)),
+            None => None,
+        };
+        let mut candidates: Vec<(Hash256, HeaderEntry)> = incumbent.into_iter().collect();
+        for tip in self.header_tips()? {
+            let Some(mut entry) = self.header_entry(&tip)? else {
+                continue;
+            };
+            let mut hash = tip;
+            while entry.is_failed() && entry.height > 0 {
+                hash = entry.prev_hash;
+                entry = self
+                    .header_entry(&hash)?
+                    .ok_or(ChainStateError::MissingHeader)?;
+            }
+            candidates.push((hash, entry));
+        }
+        for (hash, entry) in candidates {
+            if entry.is_failed() {
+                continue;
+            }
+            let work = entry.chainwork_value();
+            let is_better = match best.as_ref() {
+                Some((_, best_work)) => work > *best_work,
+                None => true,
+            };
+            if is_better {
+                best = Some((hash, work));
+            }
+        }
+        let Some((hash, _)) = best else {
+            return Ok(());
+        };
+        let mut batch = WriteBatch::new();
+        self.index.set_best_header(&mut batch, &hash);
+        self.commit_batch(batch)
+    }
+
+    /// Headers with no known child. Built from one header index scan on first use and kept
+    /// current by `commit_batch` afterwards.
+    fn header_tips(&self) -> Result<Vec<Hash256>, ChainStateError> {
+        let mut guard = self
+            .header_tips
+            .lock()
+            .map_err(|_| ChainStateError::CorruptIndex("header tips lock poisoned"))?;
+        if guard.is_none() {
+            let headers = self.index.scan_headers()?;
+            let parents: HashSet<Hash256> =
+                headers.iter().map(|(_, entry)| entry.prev_hash).collect();
+            *guard = Some(
+                headers
+                    .into_iter()
+                    .map(|(hash, _)| hash)
+                    .filter(|hash| !parents.contains(hash))
+                    .collect(),
+            );
+        }
+        Ok(guard.iter().flatten().copied().collect())
+    }
+
+    pub fn commit_batch(&self, mut batch: WriteBatch) -> Result<(), ChainStateError> {
+        self.stage_fluxnode_index_updates(&mut batch)?;
         let mut sprout_bytes: Option<Vec<u8>> = None;
         let mut sapling_bytes: Option<Vec<u8>> = None;
         let mut header_cache_updates: Vec<(Hash256, HeaderEntry)> = Vec::new();
+        let mut header_links: Vec<(Hash256, Hash256)> = Vec::new();
+        let mut header_bytes_written: HashSet<Vec<u8>> = HashSet::new();
         for op in batch.iter() {
             if let WriteOp::Put { column, key, value } = op {
                 match *column {
@@ -3677,23 +4321,34 @@ impl<S: KeyValueStore> ChainState<S> {
                     Column::HeaderIndex => {
                         const STATUS_OFFSET: usize = 32 + 4 + 4 + 4 + 32;
                         let value_bytes = value.as_slice();
-                        if key.as_slice().len() == 32
-                            && value_bytes.len() > STATUS_OFFSET
-                            && index_has_block(value_bytes[STATUS_OFFSET])
-                        {
-                            let mut hash = [0u8; 32];
-                            hash.copy_from_slice(key.as_slice());
-                            let entry = decode_header_entry(value_bytes).map_err(|_| {
-                                ChainStateError::CorruptIndex("invalid header index entry in batch")
-                            })?;
-                            header_cache_updates.push((hash, entry));
+                        if key.as_slice().len() == 32 && value_bytes.len() > STATUS_OFFSET {
+                            let status = value_bytes[STATUS_OFFSET];
+                            let is_new_header =
+                                status_without_failed(status) == status_with_header(0);
+                            if index_has_block(status) || is_new_header {
+                                let mut hash = [0u8; 32];
+                                hash.copy_from_slice(key.as_slice());
+                                let entry = decode_header_entry(value_bytes).map_err(|_| {
+                                    ChainStateError::CorruptIndex(
+                                        "invalid header index entry in batch",
+                                    )
+                                })?;
+                                if is_new_header {
+                                    header_links.push((hash, entry.prev_hash));
+                                } else {
+                                    header_cache_updates.push((hash, entry));
+                                }
+                            }
                         }
                     }
+                    Column::BlockHeader => {
+                        header_bytes_written.insert(key.as_slice().to_vec());
+                    }
                     _ => {}
                 }
             }
         }
-        self.store.write_batch(&batch)?;
+        let batch = self.write_batch_deferring_utxos(batch)?;
         if sprout_bytes.is_some() || sapling_bytes.is_some() {
             self.update_shielded_cache(sprout_bytes, sapling_bytes)?;
         }
@@ -3704,6 +4359,19 @@ impl<S: KeyValueStore> ChainState<S> {
                 }
             }
         }
+        // A header-only index entry written together with its raw header is a newly stored
+        // header; status rewrites (invalidate/reconsider) never carry the raw bytes.
+        header_links.retain(|(hash, _)| header_bytes_written.contains(hash.as_slice()));
+        if !header_links.is_empty() {
+            if let Ok(mut guard) = self.header_tips.lock() {
+                if let Some(tips) = guard.as_mut() {
+                    for (hash, prev_hash) in header_links {
+                        tips.remove(&prev_hash);
+                        tips.insert(hash);
+                    }
+                }
+            }
+        }
         let ops = batch.into_ops();
         if let Ok(mut meta_cache) = self.file_meta.lock() {
             for op in &ops {
@@ -3825,6 +4493,10 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(self.blocks.read(location)?)
     }
 
+    pub fn block_stored_len(&self, location: FileLocation) -> Result<u32, ChainStateError> {
+        Ok(self.blocks.read_stored_len(location)?)
+    }
+
     pub fn block_location(&self, hash: &[u8; 32]) -> Result<Option<FileLocation>, ChainStateError> {
         Ok(self.block_index_entry(hash)?.map(|entry| entry.block))
     }
@@ -3833,22 +4505,96 @@ impl<S: KeyValueStore> ChainState<S> {
         &self,
         hash: &Hash256,
     ) -> Result<Option<Vec<u8>>, ChainStateError> {
-        Ok(self.store.get(Column::UnconnectedBlock, hash)?)
+        Ok(self
+            .store
+            .get(Column::UnconnectedBlock, hash)?
+            .map(|value| decode_unconnected_entry(&value).1.to_vec()))
     }
 
+    /// Parks `bytes` until the block's parent connects, stamped with the current time so
+    /// `prune_unconnected` can age it out.
     pub fn store_unconnected_block_bytes(
         &self,
         batch: &mut WriteBatch,
         hash: &Hash256,
         bytes: &[u8],
     ) {
-        batch.put(Column::UnconnectedBlock, *hash, bytes);
+        self.store_unconnected_block_bytes_at(batch, hash, bytes, current_time_secs());
+    }
+
+    pub fn store_unconnected_block_bytes_at(
+        &self,
+        batch: &mut WriteBatch,
+        hash: &Hash256,
+        bytes: &[u8],
+        parked_at: i64,
+    ) {
+        let mut value = Vec::with_capacity(UNCONNECTED_ENTRY_PREFIX_LEN + bytes.len());
+        value.push(UNCONNECTED_ENTRY_TAG);
+        value.extend_from_slice(&parked_at.to_le_bytes());
+        value.extend_from_slice(bytes);
+        batch.put(Column::UnconnectedBlock, *hash, value);
     }
 
     pub fn delete_unconnected_block(&self, batch: &mut WriteBatch, hash: &Hash256) {
         batch.delete(Column::UnconnectedBlock, *hash);
     }
 
+    /// Drops parked unconnected blocks that are no longer worth keeping.
+    ///
+    /// An entry is removed once its block has been stored, when its header is unknown or failed,
+    /// or when it was parked more than `max_age_secs` ago. If more than `max_entries` remain,
+    /// the longest-parked go as well. Returns the number of entries removed.
+    pub fn prune_unconnected(
+        &self,
+        max_age_secs: u64,
+        max_entries: usize,
+    ) -> Result<usize, ChainStateError> {
+        let mut parked = Vec::new();
+        self.store
+            .for_each_prefix(Column::UnconnectedBlock, &[], &mut |key, value| {
+                if let Ok(hash) = Hash256::try_from(key) {
+                    parked.push((hash, decode_unconnected_entry(value).0));
+                }
+                Ok(())
+            })?;
+        if parked.is_empty() {
+            return Ok(0);
+        }
+
+        let cutoff = current_time_secs().saturating_sub(max_age_secs.min(i64::MAX as u64) as i64);
+        let mut stale = Vec::new();
+        let mut kept = Vec::new();
+        for (hash, parked_at) in parked {
+            // Entries written before park times were recorded have no age; treat them as expired.
+            let Some(parked_at) = parked_at.filter(|parked_at| *parked_at >= cutoff) else {
+                stale.push(hash);
+                continue;
+            };
+            match self.header_entry(&hash)? {
+                Some(entry) if !entry.has_block() && !entry.is_failed() => {
+                    kept.push((parked_at, hash));
+                }
+                _ => stale.push(hash),
+            }
+        }
+        if kept.len() > max_entries {
+            kept.sort();
+            let excess = kept.len() - max_entries;
+            stale.extend(kept.drain(..excess).map(|(_, hash)| hash));
+        }
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let mut batch = WriteBatch::new();
+        for hash in &stale {
+            batch.delete(Column::UnconnectedBlock, *hash);
+        }
+        self.commit_batch(batch)?;
+        Ok(stale.len())
+    }
+
     pub fn block_index_entry(
         &self,
         hash: &Hash256,
@@ -3971,8 +4717,11 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(())
     }
 
-    pub fn height_hash(&self, height: i32) -> Result<Option<Hash256>, ChainStateError> {
-        Ok(self.index.height_hash(height)?)
+    pub fn height_hash(&self, height: i32) -> Result<Option<BlockHash>, ChainStateError> {
+        Ok(self
+            .index
+            .height_hash(height)?
+            .map(BlockHash::from_internal))
     }
 
     pub fn sprout_witness_paths(
@@ -4025,6 +4774,7 @@ impl<S: KeyValueStore> ChainState<S> {
 
         for height in 0..=tip.height {
             let hash = self
+                .index
                 .height_hash(height)?
                 .ok_or(ChainStateError::CorruptIndex(
                     "missing height index entry while scanning sprout witness",
@@ -4134,9 +4884,9 @@ impl<S: KeyValueStore> ChainState<S> {
     }
 
     pub fn scan_timestamp_index(&self) -> Result<Vec<(u32, Hash256)>, ChainStateError> {
-        let entries = self.store.scan_prefix(Column::TimestampIndex, &[])?;
-        let mut out = Vec::with_capacity(entries.len());
-        for (key, _) in entries {
+        let mut out = Vec::new();
+        for entry in self.store.scan_prefix_iter(Column::TimestampIndex, &[])? {
+            let (key, _) = entry?;
             if key.len() != 36 {
                 continue;
             }
@@ -4150,8 +4900,10 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(out)
     }
 
-    pub fn tx_location(&self, txid: &[u8; 32]) -> Result<Option<TxLocation>, ChainStateError> {
-        self.tx_index.get(txid).map_err(ChainStateError::from)
+    pub fn tx_location(&self, txid: &Txid) -> Result<Option<TxLocation>, ChainStateError> {
+        self.tx_index
+            .get(txid.as_internal())
+            .map_err(ChainStateError::from)
     }
 
     pub fn spent_info(
@@ -4163,6 +4915,255 @@ impl<S: KeyValueStore> ChainState<S> {
             .map_err(ChainStateError::from)
     }
 
+    /// Cross-checks the txindex, spent index and UTXO set against each other and the block
+    /// files.
+    ///
+    /// `columns` selects which of `Column::TxIndex`, `Column::SpentIndex` and `Column::Utxo` are
+    /// walked; other columns are ignored. With `sample` only the first `n` entries of each
+    /// column are checked. Keys start with a txid, so that prefix is an effectively random
+    /// sample. `None` checks every entry.
+    pub fn verify_index_consistency(
+        &self,
+        columns: &[Column],
+        sample: Option<usize>,
+    ) -> Result<ConsistencyReport, ChainStateError> {
+        let limit = sample.unwrap_or(usize::MAX);
+        let mut report = ConsistencyReport::default();
+        let mut blocks = HashMap::new();
+        self.flush_utxos()?;
+
+        if columns.contains(&Column::TxIndex) {
+            for entry in self
+                .store
+                .scan_prefix_iter(Column::TxIndex, &[])?
+                .take(limit)
+            {
+                let (key, value) = entry?;
+                let (Ok(txid), Some(location)) = (
+                    <Hash256>::try_from(key.as_slice()),
+                    TxLocation::decode(&value),
+                ) else {
+                    report.record(ConsistencyIssue::UndecodableEntry {
+                        column: Column::TxIndex,
+                        key,
+                    });
+                    continue;
+                };
+                report.tx_index_checked += 1;
+                let txid = Txid::from_internal(txid);
+                let transactions = match self.consistency_block_txs(&mut blocks, location.block) {
+                    Ok(transactions) => transactions,
+                    Err(error) => {
+                        report.record(ConsistencyIssue::TxIndexUnreadableBlock {
+                            txid,
+                            location,
+                            error,
+                        });
+                        continue;
+                    }
+                };
+                let Some(tx) = transactions.get(location.index as usize) else {
+                    report.record(ConsistencyIssue::TxIndexMissingTx { txid, location });
+                    continue;
+                };
+                match tx.txid() {
+                    Ok(found) if found == *txid.as_internal() => {}
+                    Ok(found) => report.record(ConsistencyIssue::TxIndexTxidMismatch {
+                        txid,
+                        location,
+                        found: Txid::from_internal(found),
+                    }),
+                    Err(err) => report.record(ConsistencyIssue::TxIndexUnreadableBlock {
+                        txid,
+                        location,
+                        error: err.to_string(),
+                    }),
+                }
+            }
+        }
+
+        if columns.contains(&Column::SpentIndex) {
+            for entry in self
+                .store
+                .scan_prefix_iter(Column::SpentIndex, &[])?
+                .take(limit)
+            {
+                let (key, value) = entry?;
+                let (Some(outpoint), Some(spent)) =
+                    (outpoint_from_key(&key), SpentIndexValue::decode(&value))
+                else {
+                    report.record(ConsistencyIssue::UndecodableEntry {
+                        column: Column::SpentIndex,
+                        key,
+                    });
+                    continue;
+                };
+                report.spent_index_checked += 1;
+                let spender = Txid::from_internal(spent.txid);
+                let Some(location) = self.tx_location(&spender)? else {
+                    report.record(ConsistencyIssue::SpentIndexMissingSpender { outpoint, spender });
+                    continue;
+                };
+                let transactions = match self.consistency_block_txs(&mut blocks, location.block) {
+                    Ok(transactions) => transactions,
+                    Err(error) => {
+                        report.record(ConsistencyIssue::TxIndexUnreadableBlock {
+                            txid: spender,
+                            location,
+                            error,
+                        });
+                        continue;
+                    }
+                };
+                let found = transactions
+                    .get(location.index as usize)
+                    .and_then(|tx| tx.vin.get(spent.input_index as usize))
+                    .map(|input| input.prevout.clone());
+                if found.as_ref() != Some(&outpoint) {
+                    report.record(ConsistencyIssue::SpentIndexInputMismatch {
+                        outpoint,
+                        spender,
+                        input_index: spent.input_index,
+                        found,
+                    });
+                }
+            }
+        }
+
+        if columns.contains(&Column::Utxo) {
+            for entry in self.store.scan_prefix_iter(Column::Utxo, &[])?.take(limit) {
+                let (key, _) = entry?;
+                let Some(outpoint) = outpoint_from_key(&key) else {
+                    report.record(ConsistencyIssue::UndecodableEntry {
+                        column: Column::Utxo,
+                        key,
+                    });
+                    continue;
+                };
+                report.utxo_checked += 1;
+                if let Some(spent) = self.spent_info(&outpoint)? {
+                    report.record(ConsistencyIssue::UtxoMarkedSpent {
+                        outpoint,
+                        spender: Txid::from_internal(spent.txid),
+                    });
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn consistency_block_txs<'c>(
+        &self,
+        cache: &'c mut HashMap<(u32, u64, u32), Vec<Transaction>>,
+        location: FileLocation,
+    ) -> Result<&'c [Transaction], String> {
+        let key = (location.file_id, location.offset, location.len);
+        if !cache.contains_key(&key) {
+            if cache.len() >= CONSISTENCY_BLOCK_CACHE_SIZE {
+                cache.clear();
+            }
+            let bytes = self.read_block(location).map_err(|err| err.to_string())?;
+            let block = Block::consensus_decode(&bytes).map_err(|err| err.to_string())?;
+            cache.insert(key, block.transactions);
+        }
+        Ok(cache.get(&key).map(Vec::as_slice).unwrap_or_default())
+    }
+
+    /// Re-validates `block` at `height` and reports every failure instead of stopping at the
+    /// first. Read-only: nothing is connected or written.
+    ///
+    /// With `check_script`, prevouts are resolved from earlier outputs in the same block, then
+    /// the UTXO set, then the txindex, so blocks that are already connected can be re-checked.
+    /// Inputs whose prevout is found nowhere are listed in `missing_prevouts`.
+    pub fn validate_block_report(
+        &self,
+        block: &Block,
+        height: i32,
+        params: &ConsensusParams,
+        flags: &ValidationFlags,
+    ) -> Result<BlockValidationReport, ChainStateError> {
+        let mut report = validate_block_collecting(block, height, params, flags);
+        if !flags.check_script {
+            return Ok(report);
+        }
+
+        let mut block_outputs: HashMap<OutPoint, (usize, usize)> = HashMap::new();
+        let mut spent: Vec<(usize, usize, TxOut)> = Vec::new();
+        let mut blocks = HashMap::new();
+        for (tx_index, tx) in block.transactions.iter().enumerate() {
+            if tx_index > 0 {
+                for (input_index, input) in tx.vin.iter().enumerate() {
+                    let prevout = match block_outputs.get(&input.prevout) {
+                        Some(&(out_tx, out_index)) => {
+                            Some(block.transactions[out_tx].vout[out_index].clone())
+                        }
+                        None => self.prevout_for_report(&mut blocks, &input.prevout)?,
+                    };
+                    match prevout {
+                        Some(output) => spent.push((tx_index, input_index, output)),
+                        None => report.txs[tx_index].missing_prevouts.push(input_index),
+                    }
+                }
+            }
+            if let Some(txid) = report.txs[tx_index].txid {
+                for index in 0..tx.vout.len() {
+                    let outpoint = OutPoint {
+                        hash: txid,
+                        index: index as u32,
+                    };
+                    block_outputs.insert(outpoint, (tx_index, index));
+                }
+            }
+        }
+
+        let inputs: Vec<ScriptCheckInput<'_>> = spent
+            .iter()
+            .map(|(tx_index, input_index, output)| ScriptCheckInput {
+                tx: &block.transactions[*tx_index],
+                tx_index: *tx_index,
+                input_index: *input_index,
+                script_pubkey: &output.script_pubkey,
+                amount: output.value,
+            })
+            .collect();
+        let branch_id = branch_id_for_height(height, &params.upgrades);
+        for failure in collect_script_failures(
+            &inputs,
+            BLOCK_SCRIPT_VERIFY_FLAGS,
+            branch_id,
+            flags.script_pool.as_deref(),
+        ) {
+            report.txs[failure.tx_index]
+                .script_failures
+                .push((failure.input_index, failure.error.to_string()));
+        }
+        Ok(report)
+    }
+
+    fn prevout_for_report(
+        &self,
+        blocks: &mut HashMap<(u32, u64, u32), Vec<Transaction>>,
+        outpoint: &OutPoint,
+    ) -> Result<Option<TxOut>, ChainStateError> {
+        if let Some(entry) = self.utxo_entry(&outpoint.txid(), outpoint.index)? {
+            return Ok(Some(TxOut {
+                value: entry.value,
+                script_pubkey: entry.script_pubkey,
+            }));
+        }
+        let Some(location) = self.tx_location(&outpoint.txid())? else {
+            return Ok(None);
+        };
+        let Ok(transactions) = self.consistency_block_txs(blocks, location.block) else {
+            return Ok(None);
+        };
+        Ok(transactions
+            .get(location.index as usize)
+            .and_then(|tx| tx.vout.get(outpoint.index as usize))
+            .cloned())
+    }
+
     pub fn address_neighbor_index(&self) -> crate::address_neighbors::AddressNeighborIndex<Arc<S>> {
         crate::address_neighbors::AddressNeighborIndex::new(Arc::clone(&self.store))
     }
@@ -4225,7 +5226,7 @@ impl<S: KeyValueStore> ChainState<S> {
     ) -> Result<bool, ChainStateError> {
         let key =
             crate::address_index::address_outpoint_key_with_script_hash(script_hash, outpoint);
-        Ok(self.store.get(Column::AddressOutpoint, &key)?.is_some())
+        Ok(self.store.contains(Column::AddressOutpoint, &key)?)
     }
 
     pub fn address_tx_total(&self, script_hash: &Hash256) -> Result<Option<u64>, ChainStateError> {
@@ -4263,23 +5264,55 @@ impl<S: KeyValueStore> ChainState<S> {
 
     pub fn utxo_exists(&self, outpoint: &OutPoint) -> Result<bool, ChainStateError> {
         let key = outpoint_key_bytes(outpoint);
-        Ok(self.store.get(Column::Utxo, key.as_bytes())?.is_some())
+        if let Ok(cache) = self.utxo_cache.lock() {
+            if let Some(staged) = cache.dirty.get(&key) {
+                return Ok(staged.is_some());
+            }
+        }
+        Ok(self.store.contains(Column::Utxo, key.as_bytes())?)
     }
 
-    pub fn utxo_entry(&self, outpoint: &OutPoint) -> Result<Option<UtxoEntry>, ChainStateError> {
-        let key = outpoint_key_bytes(outpoint);
+    pub fn utxo_entry(
+        &self,
+        txid: &Txid,
+        index: u32,
+    ) -> Result<Option<UtxoEntry>, ChainStateError> {
+        let key = outpoint_key_bytes(&OutPoint {
+            hash: txid.to_internal(),
+            index,
+        });
         self.utxo_entry_cached(key)
     }
 
-    fn utxo_entry_cached(&self, key: OutPointKey) -> Result<Option<UtxoEntry>, ChainStateError> {
-        self.utxo_entry_cached_inner(key, None)
-    }
-
-    fn utxo_entry_cached_tracked(
+    /// Blocks until the unspent coinbase output `outpoint` may be spent on top of `tip_height`,
+    /// or `None` when `outpoint` is not an unspent coinbase output.
+    pub fn coinbase_maturity_remaining(
         &self,
-        key: OutPointKey,
-        cache_stats: &mut UtxoCacheStats,
-    ) -> Result<Option<UtxoEntry>, ChainStateError> {
+        outpoint: &OutPoint,
+        tip_height: i32,
+        params: &ConsensusParams,
+    ) -> Result<Option<i32>, ChainStateError> {
+        let Some(entry) = self.utxo_entry(&outpoint.txid(), outpoint.index)? else {
+            return Ok(None);
+        };
+        if !entry.is_coinbase {
+            return Ok(None);
+        }
+        let confirmations = tip_height
+            .saturating_sub(entry.height as i32)
+            .saturating_add(1);
+        Ok(Some(params.coinbase_maturity_remaining(confirmations)))
+    }
+
+    fn utxo_entry_cached(&self, key: OutPointKey) -> Result<Option<UtxoEntry>, ChainStateError> {
+        self.utxo_entry_cached_inner(key, None)
+    }
+
+    fn utxo_entry_cached_tracked(
+        &self,
+        key: OutPointKey,
+        cache_stats: &mut UtxoCacheStats,
+    ) -> Result<Option<UtxoEntry>, ChainStateError> {
         self.utxo_entry_cached_inner(key, Some(cache_stats))
     }
 
@@ -4289,6 +5322,17 @@ impl<S: KeyValueStore> ChainState<S> {
         cache_stats: Option<&mut UtxoCacheStats>,
     ) -> Result<Option<UtxoEntry>, ChainStateError> {
         if let Ok(mut cache) = self.utxo_cache.lock() {
+            if let Some(staged) = cache.dirty.get(&key) {
+                let Some(bytes) = staged else {
+                    return Ok(None);
+                };
+                if let Some(stats) = cache_stats {
+                    stats.hits = stats.hits.saturating_add(1);
+                }
+                let entry =
+                    UtxoEntry::decode(bytes).map_err(|err| StoreError::Backend(err.to_string()))?;
+                return Ok(Some(entry));
+            }
             if let Some(bytes) = cache.get(&key) {
                 if let Some(stats) = cache_stats {
                     stats.hits = stats.hits.saturating_add(1);
@@ -4341,6 +5385,19 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(Some(height))
     }
 
+    /// Reads a little-endian `u32` meta value, such as the node's secondary index versions.
+    pub fn meta_u32(&self, key: &[u8]) -> Result<Option<u32>, ChainStateError> {
+        let bytes = match self.store.get(Column::Meta, key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let bytes: [u8; 4] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| ChainStateError::CorruptIndex("invalid u32 meta value"))?;
+        Ok(Some(u32::from_le_bytes(bytes)))
+    }
+
     pub fn update_address_balance_meta(&self, batch: &mut WriteBatch, height: i64) {
         batch.put(Column::Meta, ADDRESS_BALANCE_META_KEY, height.to_le_bytes());
     }
@@ -4408,19 +5465,170 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(stats)
     }
 
+    /// Visits every unspent output from one snapshot and returns the tip that snapshot was
+    /// taken at (all zero before genesis is connected).
+    pub fn for_each_utxo(
+        &self,
+        visitor: &mut dyn FnMut(&OutPoint, &UtxoEntry) -> Result<(), StoreError>,
+    ) -> Result<Hash256, ChainStateError> {
+        let snapshot = self.read_snapshot()?;
+        let best_hash =
+            crate::index::snapshot_best_block_hash(snapshot.as_ref())?.unwrap_or([0u8; 32]);
+        let mut adapter = |key: &[u8], value: &[u8]| -> Result<(), StoreError> {
+            let outpoint = outpoint_from_key(key)
+                .ok_or_else(|| StoreError::Backend("invalid utxo key length".to_string()))?;
+            let entry =
+                UtxoEntry::decode(value).map_err(|err| StoreError::Backend(err.to_string()))?;
+            visitor(&outpoint, &entry)
+        };
+        snapshot.for_each_prefix(Column::Utxo, &[], &mut adapter)?;
+        Ok(best_hash)
+    }
+
     pub fn for_each_utxo_entry(
         &self,
         visitor: &mut dyn FnMut(&UtxoEntry) -> Result<(), StoreError>,
     ) -> Result<(), ChainStateError> {
-        let mut adapter = |_: &[u8], value: &[u8]| -> Result<(), StoreError> {
-            let entry = UtxoEntry::decode(value)
-                .map_err(|err| StoreError::Backend(err.to_string()))?;
-            visitor(&entry)
-        };
-        self.store.for_each_prefix(Column::Utxo, &[], &mut adapter)?;
+        self.for_each_utxo(&mut |_, entry| visitor(entry))?;
         Ok(())
     }
 
+    /// bitcoind's `scantxoutset`: one pass over the UTXO set collecting every output whose
+    /// scriptPubKey matches one of `matchers`. Works without the address index.
+    pub fn scan_txout_set(
+        &self,
+        matchers: &[ScriptMatcher],
+    ) -> Result<ScanResult, ChainStateError> {
+        let mut targets: HashMap<Vec<u8>, usize> = HashMap::with_capacity(matchers.len());
+        for (index, matcher) in matchers.iter().enumerate() {
+            targets.entry(matcher.script_pubkey()).or_insert(index);
+        }
+
+        let mut txouts = 0u64;
+        let mut total_amount = 0i64;
+        let mut unspents = Vec::new();
+        let best_block = self.for_each_utxo(&mut |outpoint, entry| {
+            txouts += 1;
+            let Some(&matcher) = targets.get(&entry.script_pubkey) else {
+                return Ok(());
+            };
+            total_amount = total_amount
+                .checked_add(entry.value)
+                .ok_or_else(|| StoreError::Backend("scan total overflow".to_string()))?;
+            unspents.push(ScannedOutput {
+                outpoint: outpoint.clone(),
+                entry: entry.clone(),
+                matcher,
+            });
+            Ok(())
+        })?;
+
+        let height = self
+            .header_entry(&best_block)?
+            .map(|entry| entry.height)
+            .unwrap_or(0);
+        Ok(ScanResult {
+            height: height.max(0),
+            best_block,
+            txouts,
+            total_amount,
+            unspents,
+        })
+    }
+
+    /// Trial-decrypts the Sapling outputs and Sprout joinsplits of every block in
+    /// `start_height..=end_height` with `keys`. Blocks are read on the calling thread and
+    /// decrypted on the worker pool, [`SCAN_CHUNK_BLOCKS`] at a time.
+    pub fn scan_shielded(
+        &self,
+        start_height: i32,
+        end_height: i32,
+        keys: &ShieldedViewingKeys,
+        options: &ShieldedScanOptions<'_>,
+    ) -> Result<Vec<ShieldedReceipt>, ChainStateError> {
+        if start_height < 0 || start_height > end_height {
+            return Err(ChainStateError::InvalidHeader(
+                "invalid shielded scan height range",
+            ));
+        }
+        let tip_height = self.best_block()?.map(|tip| tip.height).unwrap_or(-1);
+        if end_height > tip_height {
+            return Err(ChainStateError::MissingHeader);
+        }
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pool = if options.workers > 0 {
+            match rayon::ThreadPoolBuilder::new()
+                .num_threads(options.workers)
+                .thread_name(|index| format!("shielded-scan-{index}"))
+                .build()
+            {
+                Ok(pool) => Some(pool),
+                Err(err) => {
+                    fluxd_log::log_warn!(
+                        "Shielded scan worker pool unavailable ({err}); using the global pool"
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut receipts = Vec::new();
+        let mut height = start_height;
+        while height <= end_height {
+            if options
+                .cancel
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            {
+                return Err(ChainStateError::Interrupted);
+            }
+            let chunk_end = end_height.min(height.saturating_add(SCAN_CHUNK_BLOCKS - 1));
+            let mut blocks = Vec::with_capacity((chunk_end - height + 1) as usize);
+            for block_height in height..=chunk_end {
+                let hash = self
+                    .index
+                    .height_hash(block_height)?
+                    .ok_or(ChainStateError::CorruptIndex("missing height index entry"))?;
+                let location = self
+                    .block_location(&hash)?
+                    .ok_or(ChainStateError::CorruptIndex("missing block index entry"))?;
+                let bytes = self.read_block(location)?;
+                let block = Block::consensus_decode(&bytes)
+                    .map_err(|_| ChainStateError::CorruptIndex("invalid block bytes"))?;
+                blocks.push((block_height, block));
+            }
+
+            let scan = || {
+                blocks
+                    .par_iter()
+                    .map(|(block_height, block)| scan_shielded_block(block, *block_height, keys))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let found = match pool.as_ref() {
+                Some(pool) => pool.install(scan),
+                None => scan(),
+            }?;
+            receipts.extend(found.into_iter().flatten());
+
+            if let Some(progress) = options.progress {
+                progress(ShieldedScanProgress {
+                    height: chunk_end,
+                    end_height,
+                    receipts: receipts.len(),
+                });
+            }
+            if chunk_end == end_height {
+                break;
+            }
+            height = chunk_end + 1;
+        }
+        Ok(receipts)
+    }
+
     pub fn address_balance_entry(
         &self,
         script_hash: &Hash256,
@@ -4608,8 +5816,22 @@ impl<S: KeyValueStore> ChainState<S> {
         })
     }
 
+    /// Consistent view of the store for readers that combine several columns while blocks are
+    /// being connected; see [`KeyValueStore::snapshot`].
+    pub fn read_snapshot(&self) -> Result<Box<dyn ReadSnapshot>, ChainStateError> {
+        // Block commits write under the cache lock, so holding it until the snapshot is taken
+        // keeps a commit from deferring new UTXO writes in between.
+        let mut cache = self.lock_utxo_cache()?;
+        self.flush_utxos_locked(&mut cache)?;
+        Ok(self.store.snapshot()?)
+    }
+
     pub fn utxo_set_info(&self) -> Result<UtxoSetInfo, ChainStateError> {
-        let best_hash = self.best_block()?.map(|tip| tip.hash).unwrap_or([0u8; 32]);
+        // The tip hash and the UTXO scan must come from the same state, or a block connected
+        // mid-scan would hash a set that matches neither block.
+        let snapshot = self.read_snapshot()?;
+        let best_hash =
+            crate::index::snapshot_best_block_hash(snapshot.as_ref())?.unwrap_or([0u8; 32]);
         let mut hasher = Sha256::new();
         hasher.update(best_hash);
 
@@ -4671,9 +5893,13 @@ impl<S: KeyValueStore> ChainState<S> {
             Ok(())
         }
 
-        let mut info = UtxoSetInfo::default();
+        let mut info = UtxoSetInfo {
+            best_block: best_hash,
+            ..UtxoSetInfo::default()
+        };
         let mut current_txid: Option<[u8; 32]> = None;
         let mut current_outputs: Vec<UtxoOutput> = Vec::new();
+        let mut muhash = MuHash3072::new();
 
         let mut visitor = |key: &[u8], value: &[u8]| -> Result<(), StoreError> {
             if key.len() != crate::utxo::OUTPOINT_KEY_LEN {
@@ -4698,6 +5924,14 @@ impl<S: KeyValueStore> ChainState<S> {
                 .checked_add(entry.value)
                 .ok_or_else(|| StoreError::Backend("utxo total overflow".to_string()))?;
 
+            // bitcoind's MuHash element: outpoint, height * 2 + coinbase, then the txout.
+            let mut element = Encoder::new();
+            element.write_bytes(key);
+            element.write_u32_le(entry.height.wrapping_mul(2) | u32::from(entry.is_coinbase));
+            element.write_i64_le(entry.value);
+            element.write_var_bytes(&entry.script_pubkey);
+            muhash.insert(&element.into_inner());
+
             current_outputs.push(UtxoOutput {
                 vout,
                 value: entry.value,
@@ -4706,18 +5940,39 @@ impl<S: KeyValueStore> ChainState<S> {
             Ok(())
         };
 
-        self.store
-            .for_each_prefix(Column::Utxo, &[], &mut visitor)?;
+        snapshot.for_each_prefix(Column::Utxo, &[], &mut visitor)?;
         finalize_tx_group(&mut hasher, &mut current_outputs, &mut info)
             .map_err(ChainStateError::Store)?;
 
         let first = hasher.finalize();
         let second = Sha256::digest(first);
         info.hash_serialized.copy_from_slice(&second);
+        info.muhash = muhash.finalize();
 
         Ok(info)
     }
 
+    /// Everything `gettxoutsetinfo` reports, from one consistent UTXO scan. `disk_size` is
+    /// passed through because only the caller knows where the database lives.
+    pub fn txout_set_info(&self, disk_size: u64) -> Result<TxoutSetInfo, ChainStateError> {
+        let utxo = self.utxo_set_info()?;
+        let height = self
+            .header_entry(&utxo.best_block)?
+            .map(|entry| entry.height)
+            .unwrap_or(0);
+        Ok(TxoutSetInfo {
+            height: height.max(0),
+            best_block: utxo.best_block,
+            transactions: utxo.transactions,
+            txouts: utxo.txouts,
+            bytes_serialized: utxo.bytes_serialized,
+            hash_serialized: utxo.hash_serialized,
+            total_amount: utxo.total_amount,
+            disk_size,
+            muhash: utxo.muhash,
+        })
+    }
+
     pub fn value_pools(&self) -> Result<Option<ValuePools>, ChainStateError> {
         let bytes = match self.store.get(Column::Meta, VALUE_POOLS_KEY)? {
             Some(bytes) => bytes,
@@ -4746,7 +6001,81 @@ impl<S: KeyValueStore> ChainState<S> {
         self.compute_value_pools()
     }
 
+    /// Pool totals after the active-chain block at `height`, or `None` above the tip.
+    ///
+    /// Blocks connected since per-height snapshots were introduced answer from the snapshot;
+    /// older heights are rebuilt by unwinding block deltas from the lowest snapshot (or the
+    /// current totals when there is none).
+    pub fn value_pools_at(&self, height: i32) -> Result<Option<ValuePools>, ChainStateError> {
+        let Some(best) = self.best_block()? else {
+            return Ok(None);
+        };
+        if height < 0 || height > best.height {
+            return Ok(None);
+        }
+        if let Some(bytes) = self
+            .store
+            .get(Column::Meta, &value_pools_height_key(height as u32))?
+        {
+            return ValuePools::decode(&bytes)
+                .map(Some)
+                .map_err(|_| ChainStateError::CorruptIndex("invalid value pools"));
+        }
+
+        let lowest_snapshot = self
+            .store
+            .scan_prefix_iter(Column::Meta, VALUE_POOLS_HEIGHT_PREFIX)?
+            .next()
+            .transpose()?;
+        let snapshot = match lowest_snapshot {
+            Some((key, bytes)) => {
+                let snapshot_height = key[VALUE_POOLS_HEIGHT_PREFIX.len()..]
+                    .try_into()
+                    .map(u32::from_be_bytes)
+                    .map_err(|_| ChainStateError::CorruptIndex("invalid value pools key"))?;
+                let pools = ValuePools::decode(&bytes)
+                    .map_err(|_| ChainStateError::CorruptIndex("invalid value pools"))?;
+                Some((pools, snapshot_height as i32))
+            }
+            None => None,
+        };
+        let (mut pools, mut current) = match snapshot {
+            Some((pools, snapshot_height)) if snapshot_height > height => (pools, snapshot_height),
+            _ => (self.value_pools_or_compute()?, best.height),
+        };
+        while current > height {
+            let hash = self
+                .index
+                .height_hash(current)?
+                .ok_or(ChainStateError::CorruptIndex("missing height index entry"))?;
+            let delta = self.value_pool_delta(&hash)?;
+            pools.sprout = pools
+                .sprout
+                .checked_sub(delta.sprout)
+                .ok_or(ChainStateError::ValueOutOfRange)?;
+            pools.sapling = pools
+                .sapling
+                .checked_sub(delta.sapling)
+                .ok_or(ChainStateError::ValueOutOfRange)?;
+            current -= 1;
+        }
+        Ok(Some(pools))
+    }
+
+    /// Net value a block moved into the shielded pools (negative when value left them).
+    pub fn value_pool_delta(&self, hash: &Hash256) -> Result<PoolDelta, ChainStateError> {
+        let location = self
+            .block_location(hash)?
+            .ok_or(ChainStateError::MissingHeader)?;
+        let bytes = self.read_block(location)?;
+        let block = Block::consensus_decode(&bytes)
+            .map_err(|_| ChainStateError::CorruptIndex("invalid block bytes"))?;
+        let (sprout, sapling) = value_pool_deltas(&block)?;
+        Ok(PoolDelta { sprout, sapling })
+    }
+
     fn compute_utxo_stats(&self) -> Result<UtxoStats, ChainStateError> {
+        self.flush_utxos()?;
         let mut txouts = 0u64;
         let mut total_amount = 0i64;
         let mut visitor = |_: &[u8], value: &[u8]| -> Result<(), StoreError> {
@@ -4781,6 +6110,7 @@ impl<S: KeyValueStore> ChainState<S> {
         let mut last_progress = Instant::now();
         for height in 0..=best.height {
             let hash = self
+                .index
                 .height_hash(height)?
                 .ok_or(ChainStateError::CorruptIndex("missing height index entry"))?;
             let location = self
@@ -4826,6 +6156,204 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(records)
     }
 
+    pub fn fluxnode_by_collateral(
+        &self,
+        outpoint: &OutPoint,
+    ) -> Result<Option<FluxnodeRecord>, ChainStateError> {
+        self.fluxnode_record(outpoint)
+    }
+
+    pub fn fluxnodes_by_tier(&self, tier: u8) -> Result<Vec<FluxnodeRecord>, ChainStateError> {
+        self.fluxnode_records_by_index_prefix(&[tier])
+    }
+
+    /// Nodes paid to `script_pubkey`. Nodes whose collateral pubkey is one of the P2SH signing
+    /// keys are paid to their collateral script, so those are matched on the collateral utxo.
+    pub fn fluxnode_by_payout_script(
+        &self,
+        script_pubkey: &[u8],
+        params: &ChainParams,
+    ) -> Result<Vec<FluxnodeRecord>, ChainStateError> {
+        let signing_scripts: Vec<Vec<u8>> = params
+            .fluxnode
+            .p2sh_public_keys
+            .iter()
+            .filter_map(|key| hex_to_bytes_cached(key.key))
+            .map(|bytes| p2pkh_script(&hash160(bytes.as_ref())))
+            .collect();
+
+        let mut candidates = vec![script_pubkey.to_vec()];
+        candidates.extend(
+            signing_scripts
+                .iter()
+                .filter(|script| script.as_slice() != script_pubkey)
+                .cloned(),
+        );
+
+        let mut seen = HashSet::new();
+        let mut records = Vec::new();
+        for candidate in candidates {
+            let via_signing_key = signing_scripts.contains(&candidate);
+            for tier in FLUXNODE_INDEX_TIERS {
+                let prefix = fluxnode_index_prefix(tier, &candidate);
+                for record in self.fluxnode_records_by_index_prefix(&prefix)? {
+                    if via_signing_key {
+                        let Some(utxo) =
+                            self.utxo_entry(&record.collateral.txid(), record.collateral.index)?
+                        else {
+                            continue;
+                        };
+                        if utxo.script_pubkey != script_pubkey {
+                            continue;
+                        }
+                    }
+                    if seen.insert(outpoint_key_bytes(&record.collateral)) {
+                        records.push(record);
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    fn fluxnode_records_by_index_prefix(
+        &self,
+        prefix: &[u8],
+    ) -> Result<Vec<FluxnodeRecord>, ChainStateError> {
+        let keys = self.store.scan_prefix(Column::FluxnodeIndex, prefix)?;
+        let mut records = Vec::with_capacity(keys.len());
+        for (key, _) in keys {
+            let outpoint = fluxnode_index_outpoint(&key)
+                .ok_or(ChainStateError::CorruptIndex("invalid fluxnode index key"))?;
+            if let Some(record) = self.fluxnode_record(&outpoint)? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    pub fn fluxnode_index_ready(&self) -> Result<bool, ChainStateError> {
+        Ok(self.store.contains(Column::Meta, FLUXNODE_INDEX_META_KEY)?)
+    }
+
+    /// Rebuilds the fluxnode tier/payout index from the Fluxnode column.
+    pub fn rebuild_fluxnode_index(&self) -> Result<usize, ChainStateError> {
+        let mut batch = WriteBatch::new();
+        for (key, _) in self.store.scan_prefix(Column::FluxnodeIndex, &[])? {
+            batch.delete(Column::FluxnodeIndex, key);
+        }
+        let mut indexed = 0usize;
+        for record in self.fluxnode_records()? {
+            if let Some(key) = self.fluxnode_index_entry(&record, &HashMap::new())? {
+                batch.put(Column::FluxnodeIndex, key, Vec::new());
+                indexed += 1;
+            }
+        }
+        batch.put(Column::Meta, FLUXNODE_INDEX_META_KEY, [1u8]);
+        self.commit_batch(batch)?;
+        Ok(indexed)
+    }
+
+    fn fluxnode_index_entry(
+        &self,
+        record: &FluxnodeRecord,
+        staged_keys: &HashMap<Hash256, Vec<u8>>,
+    ) -> Result<Option<[u8; FLUXNODE_INDEX_KEY_LEN]>, ChainStateError> {
+        let key_bytes = |key: KeyId| -> Result<Option<Vec<u8>>, ChainStateError> {
+            if let Some(bytes) = staged_keys.get(&key.0) {
+                return Ok(Some(bytes.clone()));
+            }
+            Ok(self
+                .fluxnode_key_bytes(key)?
+                .map(|bytes| bytes.as_ref().to_vec()))
+        };
+        let script = if let Some(redeem_key) = record.p2sh_script {
+            match key_bytes(redeem_key)? {
+                Some(redeem_script) => p2sh_script(&hash160(&redeem_script)),
+                None => return Ok(None),
+            }
+        } else if let Some(collateral_key) = record.collateral_pubkey {
+            match key_bytes(collateral_key)? {
+                Some(pubkey) => p2pkh_script(&hash160(&pubkey)),
+                None => return Ok(None),
+            }
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(fluxnode_index_key(
+            record.tier,
+            &script,
+            &record.collateral,
+        )))
+    }
+
+    /// Adds FluxnodeIndex ops for every Fluxnode put/delete in `batch`, using the record already
+    /// in the store to find the entry being replaced.
+    fn stage_fluxnode_index_updates(&self, batch: &mut WriteBatch) -> Result<(), ChainStateError> {
+        let mut staged_keys: HashMap<Hash256, Vec<u8>> = HashMap::new();
+        let mut touched: Vec<OutPointKey> = Vec::new();
+        let mut final_records: HashMap<OutPointKey, Option<FluxnodeRecord>> = HashMap::new();
+        for op in batch.iter() {
+            match op {
+                WriteOp::Put { column, key, value } if *column == Column::Fluxnode => {
+                    let Some(outpoint_key) = OutPointKey::from_slice(key.as_slice()) else {
+                        continue;
+                    };
+                    let record = FluxnodeRecord::decode(value.as_slice())
+                        .map_err(|_| ChainStateError::CorruptIndex("invalid fluxnode record"))?;
+                    if final_records.insert(outpoint_key, Some(record)).is_none() {
+                        touched.push(outpoint_key);
+                    }
+                }
+                WriteOp::Delete { column, key } if *column == Column::Fluxnode => {
+                    let Some(outpoint_key) = OutPointKey::from_slice(key.as_slice()) else {
+                        continue;
+                    };
+                    if final_records.insert(outpoint_key, None).is_none() {
+                        touched.push(outpoint_key);
+                    }
+                }
+                WriteOp::Put { column, key, value } if *column == Column::FluxnodeKey => {
+                    if let Ok(hash) = <Hash256>::try_from(key.as_slice()) {
+                        staged_keys.insert(hash, value.as_slice().to_vec());
+                    }
+                }
+                _ => {}
+            }
+        }
+        if touched.is_empty() {
+            return Ok(());
+        }
+
+        for outpoint_key in touched {
+            let previous = match self.store.get(Column::Fluxnode, outpoint_key.as_bytes())? {
+                Some(bytes) => Some(
+                    FluxnodeRecord::decode(&bytes)
+                        .map_err(|_| ChainStateError::CorruptIndex("invalid fluxnode record"))?,
+                ),
+                None => None,
+            };
+            let previous_entry = match previous {
+                Some(record) => self.fluxnode_index_entry(&record, &HashMap::new())?,
+                None => None,
+            };
+            let next_entry = match final_records.get(&outpoint_key).and_then(Option::as_ref) {
+                Some(record) => self.fluxnode_index_entry(record, &staged_keys)?,
+                None => None,
+            };
+            if previous_entry == next_entry {
+                continue;
+            }
+            if let Some(entry) = previous_entry {
+                batch.delete(Column::FluxnodeIndex, entry);
+            }
+            if let Some(entry) = next_entry {
+                batch.put(Column::FluxnodeIndex, entry, Vec::new());
+            }
+        }
+        Ok(())
+    }
+
     pub fn fluxnode_key(&self, key: KeyId) -> Result<Option<Vec<u8>>, ChainStateError> {
         Ok(self
             .fluxnode_key_bytes(key)?
@@ -4960,6 +6488,7 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(self.anchors_sapling.contains(anchor)?)
     }
 
+    /// Whether a connected block already revealed this Sprout nullifier.
     pub fn sprout_nullifier_spent(
         &self,
         nullifier: &fluxd_consensus::Hash256,
@@ -4967,6 +6496,7 @@ impl<S: KeyValueStore> ChainState<S> {
         Ok(self.nullifiers_sprout.contains(nullifier)?)
     }
 
+    /// Whether a connected block already revealed this Sapling nullifier.
     pub fn sapling_nullifier_spent(
         &self,
         nullifier: &fluxd_consensus::Hash256,
@@ -4978,9 +6508,13 @@ impl<S: KeyValueStore> ChainState<S> {
 const SPROUT_TREE_KEY: &[u8] = b"sprout_tree";
 const SAPLING_TREE_KEY: &[u8] = b"sapling_tree";
 const UTXO_STATS_KEY: &[u8] = b"utxo_stats_v1";
+const UTXO_FLUSH_TIP_KEY: &[u8] = b"utxo_flush_tip";
 const VALUE_POOLS_KEY: &[u8] = b"value_pools_v1";
+const VALUE_POOLS_HEIGHT_PREFIX: &[u8] = b"value_pools_at:";
 const INDEX_STATS_KEY: &[u8] = b"index_stats_v1";
 const ADDRESS_BALANCE_META_KEY: &[u8] = b"address_balance_v1";
+const FLUXNODE_INDEX_META_KEY: &[u8] = b"fluxnode_index_v1";
+const FLUXNODE_INDEX_TIERS: [u8; 3] = [1, 2, 3];
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct UtxoStats {
@@ -5010,13 +6544,67 @@ impl UtxoStats {
     }
 }
 
+/// When `commit_batch` may hold UTXO writes in memory instead of writing them with their
+/// block. Writes are only deferred while blocks extend the tip; both limits at 0 (the default)
+/// writes every block's UTXO changes through.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UtxoFlushPolicy {
+    /// Flush once more than this many UTXO writes are pending (0: no entry limit).
+    pub max_dirty_entries: usize,
+    /// Flush once pending UTXO writes exceed this many bytes (0: no size limit).
+    pub max_dirty_bytes: usize,
+}
+
+impl UtxoFlushPolicy {
+    pub fn defers(&self) -> bool {
+        self.max_dirty_entries > 0 || self.max_dirty_bytes > 0
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct UtxoSetInfo {
+    /// Tip the set was read at (all zero before genesis is connected).
+    pub best_block: Hash256,
+    pub transactions: u64,
+    pub txouts: u64,
+    pub bytes_serialized: u64,
+    pub hash_serialized: Hash256,
+    pub total_amount: i64,
+    /// MuHash3072 of the set, comparable with bitcoind's `gettxoutsetinfo muhash`.
+    pub muhash: Hash256,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TxoutSetInfo {
+    pub height: i32,
+    pub best_block: Hash256,
     pub transactions: u64,
     pub txouts: u64,
     pub bytes_serialized: u64,
     pub hash_serialized: Hash256,
+    /// Transparent value only; shielded pools live in [`ValuePools`].
+    pub total_amount: i64,
+    pub disk_size: u64,
+    pub muhash: Hash256,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScanResult {
+    pub height: i32,
+    pub best_block: Hash256,
+    /// Outputs examined, matching or not.
+    pub txouts: u64,
+    /// Sum of the matching outputs.
     pub total_amount: i64,
+    pub unspents: Vec<ScannedOutput>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScannedOutput {
+    pub outpoint: OutPoint,
+    pub entry: UtxoEntry,
+    /// Index into the `matchers` slice of the first pattern this output matched.
+    pub matcher: usize,
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
@@ -5025,6 +6613,13 @@ pub struct ValuePools {
     pub sapling: i64,
 }
 
+/// Per-block change of the shielded pools; positive values were shielded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PoolDelta {
+    pub sprout: i64,
+    pub sapling: i64,
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct IndexStats {
     pub spent_index_entries: u64,
@@ -5450,6 +7045,77 @@ fn tx_shielded_value_in(tx: &Transaction) -> Result<i64, ChainStateError> {
     Ok(total)
 }
 
+/// Collects the complete records in `files` past the committed `end`, truncating a half-written
+/// record at the end of a file.
+fn trim_flatfile_tail(
+    files: &FlatFileStore,
+    end: (u32, u64),
+    report: &mut FlatfileReconcileReport,
+) -> Result<Vec<FileLocation>, ChainStateError> {
+    let (mut file_id, mut offset) = end;
+    let mut records = Vec::new();
+    while file_id <= files.current_file() {
+        let (found, torn_at) = files.scan_records(file_id, offset)?;
+        records.extend(found);
+        if let Some(torn_at) = torn_at {
+            files.truncate(file_id, torn_at)?;
+            report.truncated_records += 1;
+        }
+        file_id += 1;
+        offset = 0;
+    }
+    Ok(records)
+}
+
+/// Whether the file holding the committed `end` is shorter than it, so index entries may point
+/// at records that never reached disk.
+fn flatfile_short_of(files: &FlatFileStore, end: (u32, u64)) -> Result<bool, ChainStateError> {
+    let (file_id, len) = end;
+    Ok(files.file_len(file_id)?.unwrap_or(0) < len)
+}
+
+/// Whether the record at `location` is fully on disk with a matching length prefix. File
+/// lengths are looked up once per file through `lens`.
+fn flatfile_record_present(
+    files: &FlatFileStore,
+    location: FileLocation,
+    lens: &mut HashMap<u32, Option<u64>>,
+) -> bool {
+    let file_len = *lens
+        .entry(location.file_id)
+        .or_insert_with(|| files.file_len(location.file_id).ok().flatten());
+    let end = location.offset + 4 + u64::from(location.len);
+    match file_len {
+        Some(file_len) if end <= file_len => {
+            matches!(files.read_stored_len(location), Ok(len) if len == location.len)
+        }
+        _ => false,
+    }
+}
+
+/// The best block `batch` sets, if any.
+fn batch_best_block(batch: &WriteBatch) -> Option<Hash256> {
+    batch
+        .iter()
+        .filter_map(|op| match op {
+            WriteOp::Put { column, key, value }
+                if *column == Column::Meta
+                    && key.as_slice() == crate::index::META_BEST_BLOCK_KEY =>
+            {
+                value.as_slice().try_into().ok()
+            }
+            _ => None,
+        })
+        .last()
+}
+
+fn value_pools_height_key(height: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(VALUE_POOLS_HEIGHT_PREFIX.len() + 4);
+    key.extend_from_slice(VALUE_POOLS_HEIGHT_PREFIX);
+    key.extend_from_slice(&height.to_be_bytes());
+    key
+}
+
 fn value_pool_deltas(block: &Block) -> Result<(i64, i64), ChainStateError> {
     let mut sprout_delta = 0i64;
     let mut sapling_delta = 0i64;
@@ -5471,6 +7137,22 @@ fn block_bits_from_params(params: &ConsensusParams) -> u32 {
     fluxd_pow::difficulty::target_to_compact(&params.pow_limit)
 }
 
+/// Marks an `UnconnectedBlock` value as `[tag][parked_at: i64 LE][block bytes]`. Values without
+/// it predate park times and hold the block bytes alone; no block version starts with this byte.
+const UNCONNECTED_ENTRY_TAG: u8 = 0xfe;
+const UNCONNECTED_ENTRY_PREFIX_LEN: usize = 1 + 8;
+
+fn decode_unconnected_entry(value: &[u8]) -> (Option<i64>, &[u8]) {
+    match value.split_first() {
+        Some((&UNCONNECTED_ENTRY_TAG, rest)) if rest.len() >= 8 => {
+            let (parked_at, bytes) = rest.split_at(8);
+            let parked_at = i64::from_le_bytes(parked_at.try_into().unwrap_or([0u8; 8]));
+            (Some(parked_at), bytes)
+        }
+        _ => (None, value),
+    }
+}
+
 fn current_time_secs() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -5607,16 +7289,6 @@ fn header_entry_with_pending<S: KeyValueStore>(
     state.header_entry(hash)
 }
 
-fn hash256_to_hex(hash: &Hash256) -> String {
-    use std::fmt::Write;
-
-    let mut out = String::with_capacity(64);
-    for byte in hash.iter().rev() {
-        let _ = write!(out, "{:02x}", byte);
-    }
-    out
-}
-
 fn bytes_to_hex(bytes: &[u8]) -> String {
     use std::fmt::Write;
 
@@ -5636,87 +7308,38 @@ fn check_coinbase_funding(
     height: i32,
     params: &ChainParams,
 ) -> Result<(), ChainStateError> {
-    let consensus = &params.consensus;
-    let network = params.network;
-
-    if network_upgrade_active(height, &consensus.upgrades, UpgradeIndex::Pon) {
-        let min_dev = min_dev_fund_amount(height, consensus);
-        if min_dev > 0 {
-            let dev_script = address_to_script_pubkey(params.funding.dev_fund_address, network)
-                .map_err(|_| {
-                    ChainStateError::Validation(ValidationError::InvalidTransaction(
-                        "invalid dev fund address",
-                    ))
-                })?;
-            let found = tx
-                .vout
-                .iter()
-                .any(|out| out.script_pubkey == dev_script && out.value >= min_dev);
-            if !found {
-                return Err(ChainStateError::Validation(
-                    ValidationError::InvalidTransaction("coinbase missing dev fund payment"),
-                ));
-            }
-        }
-    }
-
-    let exchange_amount = exchange_fund_amount(height, &params.funding);
-    if exchange_amount > 0 {
-        let exchange_script = address_to_script_pubkey(params.funding.exchange_address, network)
-            .map_err(|_| {
-                ChainStateError::Validation(ValidationError::InvalidTransaction(
-                    "invalid exchange address",
-                ))
-            })?;
-        let found = tx
-            .vout
-            .iter()
-            .any(|out| out.script_pubkey == exchange_script && out.value == exchange_amount);
-        if !found {
-            return Err(ChainStateError::Validation(
-                ValidationError::InvalidTransaction("coinbase missing exchange funding"),
-            ));
-        }
-    }
-
-    let foundation_amount = foundation_fund_amount(height, &params.funding);
-    if foundation_amount > 0 {
-        let foundation_script =
-            address_to_script_pubkey(params.funding.foundation_address, network).map_err(|_| {
-                ChainStateError::Validation(ValidationError::InvalidTransaction(
-                    "invalid foundation address",
-                ))
-            })?;
-        let found = tx
-            .vout
-            .iter()
-            .any(|out| out.script_pubkey == foundation_script && out.value == foundation_amount);
-        if !found {
-            return Err(ChainStateError::Validation(
-                ValidationError::InvalidTransaction("coinbase missing foundation funding"),
-            ));
-        }
-    }
-
-    if is_swap_pool_interval(height as i64, &params.swap_pool) {
-        let swap_amount = swap_pool_amount(height as i64, &params.swap_pool);
-        let swap_script =
-            address_to_script_pubkey(params.swap_pool.address, network).map_err(|_| {
-                ChainStateError::Validation(ValidationError::InvalidTransaction(
-                    "invalid swap pool address",
-                ))
-            })?;
+    for required in required_funding_outputs(height, params) {
+        let (invalid_address, missing) = match required.kind {
+            FundingOutputKind::DevFund => (
+                "invalid dev fund address",
+                "coinbase missing dev fund payment",
+            ),
+            FundingOutputKind::Exchange => (
+                "invalid exchange address",
+                "coinbase missing exchange funding",
+            ),
+            FundingOutputKind::Foundation => (
+                "invalid foundation address",
+                "coinbase missing foundation funding",
+            ),
+            FundingOutputKind::SwapPool => (
+                "invalid swap pool address",
+                "coinbase missing swap pool funding",
+            ),
+        };
+        let script = address_to_script_pubkey(required.address, params.network).map_err(|_| {
+            ChainStateError::Validation(ValidationError::InvalidTransaction(invalid_address))
+        })?;
         let found = tx
             .vout
             .iter()
-            .any(|out| out.script_pubkey == swap_script && out.value == swap_amount);
+            .any(|out| out.script_pubkey == script && required.accepts(out.value));
         if !found {
             return Err(ChainStateError::Validation(
-                ValidationError::InvalidTransaction("coinbase missing swap pool funding"),
+                ValidationError::InvalidTransaction(missing),
             ));
         }
     }
-
     Ok(())
 }
 
@@ -5738,53 +7361,6 @@ fn ensure_fluxnode_collateral_mature(
     Ok(())
 }
 
-fn fluxnode_confirm_expiration_count(height: i32, consensus: &ConsensusParams) -> u32 {
-    let upgrades = &consensus.upgrades;
-    let count = if network_upgrade_active(height, upgrades, UpgradeIndex::Pon) {
-        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4
-    } else if network_upgrade_active(height, upgrades, UpgradeIndex::Halving) {
-        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3
-    } else if network_upgrade_active(height, upgrades, UpgradeIndex::Flux) {
-        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2
-    } else {
-        FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1
-    };
-    u32::try_from(count).unwrap_or_default()
-}
-
-fn fluxnode_confirm_expire_height(last_confirmed_height: u32, consensus: &ConsensusParams) -> u32 {
-    let mut expiration = fluxnode_confirm_expiration_count(
-        i32::try_from(last_confirmed_height).unwrap_or(i32::MAX),
-        consensus,
-    );
-    let mut expire_height = last_confirmed_height
-        .saturating_add(expiration)
-        .saturating_add(1);
-    loop {
-        let candidate_height = i32::try_from(expire_height).unwrap_or(i32::MAX);
-        let next_expiration = fluxnode_confirm_expiration_count(candidate_height, consensus);
-        if next_expiration == expiration {
-            break;
-        }
-        expiration = next_expiration;
-        expire_height = last_confirmed_height
-            .saturating_add(expiration)
-            .saturating_add(1);
-    }
-    expire_height
-}
-
-fn fluxnode_confirm_expired_for_pay_height(
-    pay_height: u32,
-    last_confirmed_height: u32,
-    consensus: &ConsensusParams,
-) -> bool {
-    if pay_height <= last_confirmed_height {
-        return false;
-    }
-    pay_height >= fluxnode_confirm_expire_height(last_confirmed_height, consensus)
-}
-
 fn validate_fluxnode_collateral_script(
     script_pubkey: &[u8],
     _height: i32,
@@ -5900,7 +7476,7 @@ fn hex_to_bytes(input: &str) -> Option<Vec<u8>> {
     Some(bytes)
 }
 
-fn hex_to_bytes_cached(input: &'static str) -> Option<Arc<[u8]>> {
+pub(crate) fn hex_to_bytes_cached(input: &'static str) -> Option<Arc<[u8]>> {
     let cache = HEX_BYTES_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
     if let Ok(mut cache) = cache.lock() {
         if let Some(bytes) = cache.get(input) {
@@ -6026,16 +7602,18 @@ fn fluxnode_benchmark_message(confirm: &FluxnodeConfirmTx) -> Vec<u8> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index::{chainwork_hex, compare_work};
     use fluxd_consensus::money::COIN;
     use fluxd_consensus::params::{chain_params, Network};
     use fluxd_consensus::rewards::min_dev_fund_amount;
     use fluxd_consensus::upgrades::UpgradeIndex;
     use fluxd_consensus::TimedPublicKey;
-    use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
+    use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION, PON_VERSION};
     use fluxd_primitives::outpoint::OutPoint;
     use fluxd_primitives::transaction::{
-        FluxnodeConfirmTx, FluxnodeStartV5, FluxnodeTx, FluxnodeTxV5, Transaction, TxIn, TxOut,
-        FLUXNODE_TX_VERSION, SAPLING_VERSION_GROUP_ID,
+        FluxnodeConfirmTx, FluxnodeStartV5, FluxnodeTx, FluxnodeTxV5, JoinSplit, SproutProof,
+        Transaction, TxIn, TxOut, FLUXNODE_TX_VERSION, PHGR_PROOF_SIZE, SAPLING_VERSION_GROUP_ID,
+        ZC_NOTE_CIPHERTEXT_SIZE,
     };
     use fluxd_script::message::signed_message_hash;
     use fluxd_storage::memory::MemoryStore;
@@ -6082,6 +7660,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         }
     }
@@ -6110,73 +7689,6 @@ mod tests {
         script
     }
 
-    #[test]
-    fn fluxnode_payee_ordering_matches_cpp_unpaid_before_paid_on_equal_height() {
-        fn record(
-            hash_byte: u8,
-            index: u32,
-            confirmed_height: u32,
-            last_paid_height: u32,
-        ) -> FluxnodeRecord {
-            FluxnodeRecord {
-                collateral: OutPoint {
-                    hash: [hash_byte; 32],
-                    index,
-                },
-                tier: 1,
-                start_height: 1,
-                confirmed_height,
-                last_confirmed_height: confirmed_height,
-                last_paid_height,
-                collateral_value: 0,
-                operator_pubkey: KeyId([0u8; 32]),
-                collateral_pubkey: None,
-                p2sh_script: None,
-                delegates: None,
-                ip: String::new(),
-            }
-        }
-
-        let unpaid = record(0xff, 1, 100, 0);
-        let paid = record(0x00, 1, 50, 100);
-        let unpaid_key = FluxnodePayeeKey::from_record(&unpaid).expect("unpaid key");
-        let paid_key = FluxnodePayeeKey::from_record(&paid).expect("paid key");
-        assert!(unpaid_key < paid_key);
-
-        let unpaid_a = record(0x01, 1, 100, 0);
-        let unpaid_b = record(0x02, 1, 100, 0);
-        let unpaid_a_key = FluxnodePayeeKey::from_record(&unpaid_a).expect("unpaid a key");
-        let unpaid_b_key = FluxnodePayeeKey::from_record(&unpaid_b).expect("unpaid b key");
-        assert!(unpaid_a_key < unpaid_b_key);
-    }
-
-    #[test]
-    fn fluxnode_confirm_expiration_respects_upgrade_changes() {
-        let params = chain_params(Network::Mainnet);
-        let pon_height =
-            params.consensus.upgrades[UpgradeIndex::Pon.as_usize()].activation_height as u32;
-
-        let pre_pon_old = pon_height.saturating_sub(344);
-        let pre_pon_recent = pon_height.saturating_sub(10);
-        let pay_height = pon_height.saturating_add(264);
-
-        let expire_old = fluxnode_confirm_expire_height(pre_pon_old, &params.consensus);
-        assert!(expire_old < pon_height);
-        assert!(fluxnode_confirm_expired_for_pay_height(
-            pay_height,
-            pre_pon_old,
-            &params.consensus
-        ));
-
-        let expire_recent = fluxnode_confirm_expire_height(pre_pon_recent, &params.consensus);
-        assert!(expire_recent > pon_height);
-        assert!(!fluxnode_confirm_expired_for_pay_height(
-            pay_height,
-            pre_pon_recent,
-            &params.consensus
-        ));
-    }
-
     #[test]
     fn coinbase_funding_does_not_require_dev_fund_pre_pon() {
         let params = chain_params(Network::Mainnet);
@@ -6456,24 +7968,398 @@ mod tests {
                 sequence: u32::MAX,
             }],
             vec![TxOut {
-                value: 0,
+                value: 0,
+                script_pubkey: vec![0x51],
+            }],
+        );
+        let block1 = Block {
+            header: header1,
+            transactions: vec![coinbase1, spend_tx],
+        };
+
+        let err = chainstate
+            .connect_block(&block1, 1, &params, &flags, true, None, None, None, None)
+            .expect_err("premature spend rejected");
+        match err {
+            ChainStateError::Validation(ValidationError::InvalidTransaction(message)) => {
+                assert_eq!(message, "premature spend of coinbase");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn connected_joinsplit_nullifiers_read_back_as_spent() {
+        let store = Arc::new(MemoryStore::new());
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+        let mut params = chain_params(Network::Regtest);
+        params.funding.exchange_height = i64::MAX;
+        params.funding.foundation_height = i64::MAX;
+        params.swap_pool.start_height = i64::MAX;
+        params.fluxnode.start_payments_height = i64::MAX;
+        let now = current_time_secs() as u32;
+
+        let header0 = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: [0u8; 32],
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: now,
+            bits: block_bits_from_params(&params.consensus),
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let hash0 = header0.hash();
+        params.consensus.hash_genesis_block = hash0;
+        params.consensus.checkpoints = vec![fluxd_consensus::params::Checkpoint {
+            height: 0,
+            hash: hash0,
+        }];
+        let header1 = BlockHeader {
+            prev_block: hash0,
+            time: now + 1,
+            ..header0.clone()
+        };
+        let hash1 = header1.hash();
+
+        let mut header_batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                &[header0.clone(), header1.clone()],
+                &params.consensus,
+                &mut header_batch,
+                false,
+            )
+            .expect("insert headers");
+        chainstate
+            .commit_batch(header_batch)
+            .expect("commit headers");
+
+        let coinbase = |height: u8| {
+            make_tx(
+                vec![TxIn {
+                    prevout: OutPoint::null(),
+                    script_sig: vec![height, 0],
+                    sequence: u32::MAX,
+                }],
+                vec![TxOut {
+                    value: 0,
+                    script_pubkey: vec![0x51],
+                }],
+            )
+        };
+        let flags = ValidationFlags::default();
+        let block0 = Block {
+            header: header0,
+            transactions: vec![coinbase(0)],
+        };
+        let batch = chainstate
+            .connect_block(&block0, 0, &params, &flags, true, None, None, None, None)
+            .expect("connect block 0");
+        chainstate.commit_batch(batch).expect("commit block 0");
+
+        let nullifiers = [[0x11u8; 32], [0x22u8; 32]];
+        let mut shielded_tx = make_tx(Vec::new(), Vec::new());
+        shielded_tx.version = 2;
+        shielded_tx.join_splits = vec![JoinSplit {
+            vpub_old: 0,
+            vpub_new: 0,
+            anchor: sprout_empty_root_hash(),
+            nullifiers,
+            commitments: [[0x33u8; 32], [0x44u8; 32]],
+            ephemeral_key: [0u8; 32],
+            random_seed: [0u8; 32],
+            macs: [[0u8; 32]; 2],
+            proof: SproutProof::Phgr([0u8; PHGR_PROOF_SIZE]),
+            ciphertexts: [[0u8; ZC_NOTE_CIPHERTEXT_SIZE]; 2],
+        }];
+        for nullifier in &nullifiers {
+            assert!(!chainstate.sprout_nullifier_spent(nullifier).expect("query"));
+        }
+
+        let block1 = Block {
+            header: header1,
+            transactions: vec![coinbase(1), shielded_tx],
+        };
+        let batch = chainstate
+            .connect_block(&block1, 1, &params, &flags, true, None, None, None, None)
+            .expect("connect block 1");
+        chainstate.commit_batch(batch).expect("commit block 1");
+
+        for nullifier in &nullifiers {
+            assert!(chainstate.sprout_nullifier_spent(nullifier).expect("query"));
+            assert!(!chainstate
+                .sapling_nullifier_spent(nullifier)
+                .expect("query"));
+        }
+        assert!(!chainstate
+            .sprout_nullifier_spent(&[0x33u8; 32])
+            .expect("query"));
+
+        let batch = chainstate.disconnect_block(&hash1).expect("disconnect");
+        chainstate.commit_batch(batch).expect("commit disconnect");
+        for nullifier in &nullifiers {
+            assert!(!chainstate.sprout_nullifier_spent(nullifier).expect("query"));
+        }
+    }
+
+    #[test]
+    fn assume_valid_skips_scripts_only_for_ancestors() {
+        let store = Arc::new(MemoryStore::new());
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+        let seed_outpoint = OutPoint {
+            hash: [0x22; 32],
+            index: 0,
+        };
+        let seed_entry = UtxoEntry {
+            value: 50,
+            script_pubkey: vec![0x00],
+            height: 0,
+            is_coinbase: false,
+        };
+        let mut seed_batch = WriteBatch::new();
+        chainstate
+            .utxos
+            .put(&mut seed_batch, &seed_outpoint, &seed_entry);
+        chainstate.commit_batch(seed_batch).expect("seed utxo");
+
+        let mut params = chain_params(Network::Regtest);
+        let now = current_time_secs() as u32;
+        let header0 = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: [0u8; 32],
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: now,
+            bits: block_bits_from_params(&params.consensus),
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let hash0 = header0.hash();
+        params.consensus.hash_genesis_block = hash0;
+        params.consensus.checkpoints = vec![fluxd_consensus::params::Checkpoint {
+            height: 0,
+            hash: hash0,
+        }];
+        let header1 = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: hash0,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: now + 1,
+            bits: header0.bits,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let hash1 = header1.hash();
+
+        let mut header_batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                &[header0.clone(), header1.clone()],
+                &params.consensus,
+                &mut header_batch,
+                false,
+            )
+            .expect("insert headers");
+        chainstate
+            .commit_batch(header_batch)
+            .expect("commit headers");
+
+        assert!(chainstate
+            .is_assumed_valid(Some(&hash1), 0, &hash0)
+            .expect("ancestor check"));
+        assert!(!chainstate
+            .is_assumed_valid(Some(&hash0), 1, &hash1)
+            .expect("descendant check"));
+        assert!(!chainstate
+            .is_assumed_valid(Some(&[0x33; 32]), 0, &hash0)
+            .expect("unknown hash check"));
+
+        let coinbase = |value: i64| {
+            make_tx(
+                vec![TxIn {
+                    prevout: OutPoint::null(),
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                }],
+                vec![TxOut {
+                    value,
+                    script_pubkey: vec![0x51],
+                }],
+            )
+        };
+        let block0 = Block {
+            header: header0,
+            transactions: vec![coinbase(50)],
+        };
+        let mut flags = ValidationFlags {
+            check_script: true,
+            assume_valid: Some(hash0),
+            ..ValidationFlags::default()
+        };
+        let batch = chainstate
+            .connect_block(&block0, 0, &params, &flags, true, None, None, None, None)
+            .expect("connect block 0");
+        chainstate.commit_batch(batch).expect("commit block 0");
+
+        let spend_tx = make_tx(
+            vec![TxIn {
+                prevout: seed_outpoint.clone(),
+                script_sig: Vec::new(),
+                sequence: 0,
+            }],
+            vec![TxOut {
+                value: 50,
+                script_pubkey: vec![0x52],
+            }],
+        );
+        let block1 = Block {
+            header: header1,
+            transactions: vec![coinbase(0), spend_tx],
+        };
+
+        let err = chainstate
+            .connect_block(&block1, 1, &params, &flags, true, None, None, None, None)
+            .expect_err("post-assumevalid block still verifies scripts");
+        match err {
+            ChainStateError::Validation(ValidationError::InvalidTransaction(message)) => {
+                assert_eq!(message, "script validation failed");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        flags.assume_valid = Some(hash1);
+        chainstate
+            .connect_block(&block1, 1, &params, &flags, true, None, None, None, None)
+            .expect("assumed-valid block skips scripts");
+    }
+
+    #[test]
+    fn validate_block_report_collects_every_failure() {
+        let store = Arc::new(MemoryStore::new());
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+        let seed_outpoint = OutPoint {
+            hash: [0x22; 32],
+            index: 0,
+        };
+        let seed_entry = UtxoEntry {
+            value: 50,
+            script_pubkey: vec![0x00],
+            height: 0,
+            is_coinbase: false,
+        };
+        let mut seed_batch = WriteBatch::new();
+        chainstate
+            .utxos
+            .put(&mut seed_batch, &seed_outpoint, &seed_entry);
+        chainstate.commit_batch(seed_batch).expect("seed utxo");
+
+        let params = chain_params(Network::Regtest);
+        let coinbase = make_tx(
+            vec![TxIn {
+                prevout: OutPoint::null(),
+                script_sig: vec![0x51, 0x51],
+                sequence: u32::MAX,
+            }],
+            vec![TxOut {
+                value: 0,
+                script_pubkey: vec![0x51],
+            }],
+        );
+        let bad_script = make_tx(
+            vec![TxIn {
+                prevout: seed_outpoint.clone(),
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            vec![TxOut {
+                value: 50,
                 script_pubkey: vec![0x51],
             }],
         );
-        let block1 = Block {
-            header: header1,
-            transactions: vec![coinbase1, spend_tx],
+        let in_block = OutPoint {
+            hash: bad_script.txid().expect("txid"),
+            index: 0,
+        };
+        let missing = make_tx(
+            vec![
+                TxIn {
+                    prevout: OutPoint {
+                        hash: [0x44; 32],
+                        index: 0,
+                    },
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                },
+                TxIn {
+                    prevout: in_block,
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                },
+            ],
+            vec![TxOut {
+                value: 50,
+                script_pubkey: vec![0x51],
+            }],
+        );
+        let block = Block {
+            header: BlockHeader {
+                version: CURRENT_VERSION,
+                prev_block: [0u8; 32],
+                merkle_root: [0u8; 32],
+                final_sapling_root: [0u8; 32],
+                time: current_time_secs() as u32,
+                bits: block_bits_from_params(&params.consensus),
+                nonce: [0u8; 32],
+                solution: Vec::new(),
+                nodes_collateral: OutPoint::null(),
+                block_sig: Vec::new(),
+            },
+            transactions: vec![coinbase, bad_script, missing],
+        };
+        let flags = ValidationFlags {
+            check_script: true,
+            ..ValidationFlags::default()
         };
 
-        let err = chainstate
-            .connect_block(&block1, 1, &params, &flags, true, None, None, None, None)
-            .expect_err("premature spend rejected");
-        match err {
-            ChainStateError::Validation(ValidationError::InvalidTransaction(message)) => {
-                assert_eq!(message, "premature spend of coinbase");
-            }
-            other => panic!("unexpected error: {other:?}"),
-        }
+        let report = chainstate
+            .validate_block_report(&block, 1, &params.consensus, &flags)
+            .expect("report");
+        assert!(!report.is_valid());
+        assert!(report
+            .block_errors
+            .iter()
+            .any(|error| error == "merkle root mismatch"));
+        assert_eq!(report.txs.len(), 3);
+        assert_eq!(report.txs[1].script_failures.len(), 1);
+        assert_eq!(report.txs[1].script_failures[0].0, 0);
+        assert_eq!(report.txs[2].missing_prevouts, vec![0]);
+        assert!(report.txs[2].script_failures.is_empty());
+        assert!(chainstate
+            .utxo_exists(&seed_outpoint)
+            .expect("seed utxo lookup"));
     }
 
     fn test_hash(height: u8) -> Hash256 {
@@ -6787,6 +8673,81 @@ mod tests {
         assert_eq!(entry1.chainwork_value(), expected_1);
     }
 
+    #[test]
+    fn chainwork_at_orders_competing_forks() {
+        let store = Arc::new(MemoryStore::new());
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+        let mut consensus = chain_params(Network::Regtest).consensus;
+        let pow_bits = fluxd_pow::difficulty::target_to_compact(&consensus.pow_limit);
+        let header = |prev_block: Hash256, time: u32| BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time,
+            bits: pow_bits,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+
+        let header0 = header([0u8; 32], 1_000_000);
+        let hash0 = header0.hash();
+        consensus.hash_genesis_block = hash0;
+        consensus.checkpoints = vec![fluxd_consensus::params::Checkpoint {
+            height: 0,
+            hash: hash0,
+        }];
+        let a1 = header(hash0, 1_000_120);
+        let hash_a1 = a1.hash();
+        let a2 = header(hash_a1, 1_000_240);
+        let hash_a2 = a2.hash();
+        let b1 = header(hash0, 1_000_060);
+        let hash_b1 = b1.hash();
+
+        let mut batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(&[header0, a1, a2], &consensus, &mut batch, false)
+            .expect("insert fork a");
+        chainstate.commit_batch(batch).expect("commit fork a");
+        let mut batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(&[b1], &consensus, &mut batch, false)
+            .expect("insert fork b");
+        chainstate.commit_batch(batch).expect("commit fork b");
+
+        let work = |hash: &Hash256| {
+            chainstate
+                .chainwork_at(hash)
+                .expect("chainwork")
+                .expect("known header")
+        };
+        let work_a1 = work(&hash_a1);
+        let work_a2 = work(&hash_a2);
+        let work_b1 = work(&hash_b1);
+        assert_eq!(
+            compare_work(&work_a2, &work_b1),
+            std::cmp::Ordering::Greater
+        );
+        assert_eq!(compare_work(&work_b1, &work_a2), std::cmp::Ordering::Less);
+        assert_eq!(compare_work(&work_a1, &work_b1), std::cmp::Ordering::Equal);
+        assert_eq!(
+            primitive_types::U256::from_big_endian(&work_a2),
+            fluxd_pow::difficulty::block_proof(pow_bits).expect("block proof") * 3
+        );
+        assert_eq!(chainwork_hex(&work_b1).len(), 64);
+        assert!(chainstate
+            .chainwork_at(&[0x55; 32])
+            .expect("chainwork")
+            .is_none());
+    }
+
     #[test]
     fn pon_chainwork_uses_fixed_work() {
         let store = Arc::new(MemoryStore::new());
@@ -7064,11 +9025,164 @@ mod tests {
             .is_none());
 
         assert!(chainstate
-            .tx_location(&tx1id)
+            .tx_location(&Txid::from_internal(tx1id))
             .expect("tx location query")
             .is_none());
     }
 
+    #[test]
+    fn verify_index_consistency_reports_corrupt_entries() {
+        let store = Arc::new(MemoryStore::new());
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+        let seed_outpoint = OutPoint {
+            hash: [0x11; 32],
+            index: 0,
+        };
+        let seed_entry = UtxoEntry {
+            value: 50,
+            script_pubkey: vec![0x51],
+            height: 0,
+            is_coinbase: false,
+        };
+        let mut seed_batch = WriteBatch::new();
+        chainstate
+            .utxos
+            .put(&mut seed_batch, &seed_outpoint, &seed_entry);
+        chainstate.commit_batch(seed_batch).expect("seed utxo");
+
+        let mut params = chain_params(Network::Regtest);
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: [0u8; 32],
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: current_time_secs() as u32,
+            bits: block_bits_from_params(&params.consensus),
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let block_hash = header.hash();
+        params.consensus.hash_genesis_block = block_hash;
+        params.consensus.checkpoints = vec![fluxd_consensus::params::Checkpoint {
+            height: 0,
+            hash: block_hash,
+        }];
+        let mut header_batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                std::slice::from_ref(&header),
+                &params.consensus,
+                &mut header_batch,
+                false,
+            )
+            .expect("insert header");
+        chainstate
+            .commit_batch(header_batch)
+            .expect("commit header");
+
+        let coinbase = make_tx(
+            vec![TxIn {
+                prevout: OutPoint::null(),
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            vec![TxOut {
+                value: 0,
+                script_pubkey: vec![0x51],
+            }],
+        );
+        let tx1 = make_tx(
+            vec![TxIn {
+                prevout: seed_outpoint.clone(),
+                script_sig: Vec::new(),
+                sequence: 0,
+            }],
+            vec![TxOut {
+                value: seed_entry.value,
+                script_pubkey: vec![0x52],
+            }],
+        );
+        let tx1id = tx1.txid().expect("txid1");
+        let outpoint1 = OutPoint {
+            hash: tx1id,
+            index: 0,
+        };
+        let block = Block {
+            header,
+            transactions: vec![coinbase, tx1],
+        };
+        let flags = ValidationFlags::default();
+        let batch = chainstate
+            .connect_block(&block, 0, &params, &flags, true, None, None, None, None)
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit connect");
+
+        let columns = [Column::TxIndex, Column::SpentIndex, Column::Utxo];
+        let report = chainstate
+            .verify_index_consistency(&columns, None)
+            .expect("consistency check");
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.tx_index_checked, 2);
+        assert_eq!(report.spent_index_checked, 1);
+        assert!(report.utxo_checked >= 1);
+
+        let tx1_location = chainstate
+            .tx_location(&Txid::from_internal(tx1id))
+            .expect("tx location query")
+            .expect("tx1 location");
+        let wrong_location = TxLocation {
+            index: 0,
+            ..tx1_location
+        };
+        let mut corrupt = WriteBatch::new();
+        chainstate
+            .tx_index
+            .insert(&mut corrupt, &tx1id, wrong_location);
+        chainstate.spent_index.insert(
+            &mut corrupt,
+            &outpoint1,
+            SpentIndexValue {
+                txid: tx1id,
+                input_index: 0,
+                block_height: 0,
+                details: None,
+            },
+        );
+        chainstate.commit_batch(corrupt).expect("commit corruption");
+
+        let report = chainstate
+            .verify_index_consistency(&columns, None)
+            .expect("consistency check");
+        assert!(!report.is_consistent());
+        assert_eq!(report.issue_count, report.issues.len() as u64);
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            ConsistencyIssue::TxIndexTxidMismatch { txid, .. }
+                if *txid == Txid::from_internal(tx1id)
+        )));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            ConsistencyIssue::UtxoMarkedSpent { outpoint, .. } if *outpoint == outpoint1
+        )));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            ConsistencyIssue::SpentIndexInputMismatch { outpoint, .. } if *outpoint == outpoint1
+        )));
+
+        let sampled = chainstate
+            .verify_index_consistency(&[Column::TxIndex], Some(1))
+            .expect("sampled check");
+        assert_eq!(sampled.tx_index_checked, 1);
+        assert_eq!(sampled.spent_index_checked, 0);
+    }
+
     #[test]
     fn insert_headers_persists_header_bytes() {
         let store = Arc::new(MemoryStore::new());
@@ -7210,6 +9324,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -7243,6 +9358,33 @@ mod tests {
                 sapling: 10
             }
         );
+        assert_eq!(
+            chainstate.value_pools_at(0).expect("pools at 0"),
+            Some(pools)
+        );
+        assert_eq!(chainstate.value_pools_at(1).expect("pools at 1"), None);
+        assert_eq!(
+            chainstate
+                .value_pool_delta(&block_hash)
+                .expect("pool delta"),
+            PoolDelta {
+                sprout: 0,
+                sapling: 10
+            }
+        );
+
+        // Without a snapshot the height is rebuilt from the current totals.
+        assert!(store
+            .get(Column::Meta, &value_pools_height_key(0))
+            .expect("snapshot")
+            .is_some());
+        store
+            .delete(Column::Meta, &value_pools_height_key(0))
+            .expect("drop snapshot");
+        assert_eq!(
+            chainstate.value_pools_at(0).expect("rebuilt pools at 0"),
+            Some(pools)
+        );
 
         let batch = chainstate
             .disconnect_block(&block_hash)
@@ -7318,6 +9460,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
                 collateral: collateral_outpoint.clone(),
                 collateral_pubkey: collateral_pubkey.serialize().to_vec(),
@@ -7423,6 +9566,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
                 collateral: collateral_outpoint.clone(),
                 collateral_pubkey: collateral_pubkey.serialize().to_vec(),
@@ -7458,6 +9602,108 @@ mod tests {
             .expect("start tx valid after expiration");
     }
 
+    #[test]
+    fn validate_pon_block_signature_checks_operator_key() {
+        let store = Arc::new(MemoryStore::new());
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+        let mut params = chain_params(Network::Regtest);
+        let height = 100;
+        params.consensus.upgrades[UpgradeIndex::Pon.as_usize()].activation_height = height;
+
+        let operator_secret = make_test_secret_key(2);
+        let secp = Secp256k1::signing_only();
+        let operator_pubkey = secp256k1::PublicKey::from_secret_key(&secp, &operator_secret);
+        let collateral = OutPoint {
+            hash: [0x44; 32],
+            index: 0,
+        };
+        let record = FluxnodeRecord {
+            collateral: collateral.clone(),
+            tier: 1,
+            start_height: 0,
+            confirmed_height: 1,
+            last_confirmed_height: 1,
+            last_paid_height: 0,
+            collateral_value: 1_000 * COIN,
+            operator_pubkey: KeyId([0x11; 32]),
+            collateral_pubkey: None,
+            p2sh_script: None,
+            delegates: None,
+            ip: String::new(),
+        };
+        store
+            .put(
+                Column::Fluxnode,
+                outpoint_key_bytes(&collateral).as_bytes(),
+                &record.encode(),
+            )
+            .expect("store record");
+        store
+            .put(
+                Column::FluxnodeKey,
+                &[0x11; 32],
+                &operator_pubkey.serialize(),
+            )
+            .expect("store operator key");
+
+        let mut header = BlockHeader {
+            version: PON_VERSION,
+            prev_block: [0x22; 32],
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: 1_700_000_000,
+            bits: 0x1f07ffff,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: collateral,
+            block_sig: Vec::new(),
+        };
+        let sign = |header: &BlockHeader, secret: &SecretKey| {
+            let msg = Message::from_digest_slice(&header.hash()).expect("msg");
+            secp.sign_ecdsa(&msg, secret).serialize_der().to_vec()
+        };
+
+        header.block_sig = sign(&header, &operator_secret);
+        chainstate
+            .validate_pon_block_signature(&header, height, &params.consensus)
+            .expect("operator signature accepted");
+
+        header.block_sig = sign(&header, &make_test_secret_key(3));
+        let err = chainstate
+            .validate_pon_block_signature(&header, height, &params.consensus)
+            .expect_err("foreign signature rejected");
+        assert!(
+            err.to_string()
+                .contains("pon signature verification failed"),
+            "{err}"
+        );
+
+        header.nodes_collateral = OutPoint {
+            hash: [0x55; 32],
+            index: 0,
+        };
+        header.block_sig = sign(&header, &operator_secret);
+        let err = chainstate
+            .validate_pon_block_signature(&header, height, &params.consensus)
+            .expect_err("unknown collateral rejected");
+        assert!(
+            matches!(
+                err,
+                ChainStateError::InvalidHeader("missing fluxnode entry for pon signature")
+            ),
+            "{err}"
+        );
+
+        header.version = CURRENT_VERSION;
+        chainstate
+            .validate_pon_block_signature(&header, height, &params.consensus)
+            .expect("pow header skips pon signature");
+    }
+
     #[test]
     fn fluxnode_start_rejects_restart_when_still_confirmed() {
         let store = Arc::new(MemoryStore::new());
@@ -7528,6 +9774,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
                 collateral: collateral_outpoint.clone(),
                 collateral_pubkey: collateral_pubkey.serialize().to_vec(),
@@ -7640,6 +9887,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
                 collateral: collateral_outpoint.clone(),
                 collateral_pubkey: collateral_pubkey.serialize().to_vec(),
@@ -7739,6 +9987,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Confirm(FluxnodeConfirmTx {
                 collateral: collateral_outpoint.clone(),
                 sig_time: 1,
@@ -1,6 +1,7 @@
 //! UTXO set logic backed by the storage trait.
 
 use fluxd_primitives::encoding::{DecodeError, Decoder, Encoder};
+use fluxd_primitives::hash::hash160;
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_storage::{Column, KeyValueStore, StoreError, WriteBatch};
 
@@ -75,6 +76,38 @@ pub fn outpoint_key_bytes(outpoint: &OutPoint) -> OutPointKey {
     OutPointKey::new(outpoint)
 }
 
+/// A script pattern for [`crate::state::ChainState::scan_txout_set`]. Every variant names
+/// exactly one scriptPubKey, so a scan compares each output against a fixed set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScriptMatcher {
+    /// This exact scriptPubKey.
+    Script(Vec<u8>),
+    /// Pay-to-pubkey-hash of this serialized public key.
+    P2pkh(Vec<u8>),
+    /// Pay-to-script-hash of this redeem script.
+    P2sh(Vec<u8>),
+}
+
+impl ScriptMatcher {
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        match self {
+            Self::Script(script) => script.clone(),
+            Self::P2pkh(pubkey) => {
+                let mut script = vec![0x76, 0xa9, 0x14];
+                script.extend_from_slice(&hash160(pubkey));
+                script.extend_from_slice(&[0x88, 0xac]);
+                script
+            }
+            Self::P2sh(redeem_script) => {
+                let mut script = vec![0xa9, 0x14];
+                script.extend_from_slice(&hash160(redeem_script));
+                script.push(0x87);
+                script
+            }
+        }
+    }
+}
+
 pub struct UtxoSet<S> {
     store: S,
 }
@@ -12,7 +12,9 @@ use fluxd_consensus::constants::{
 };
 use fluxd_consensus::money::MAX_MONEY;
 use fluxd_consensus::params::ConsensusParams;
-use fluxd_consensus::upgrades::{current_epoch_branch_id, network_upgrade_active, UpgradeIndex};
+use fluxd_consensus::upgrades::{
+    branch_id_for_height, consensus_rules, network_upgrade_active, UpgradeIndex,
+};
 use fluxd_consensus::Hash256;
 use fluxd_fluxnode::validation::{self as fluxnode_validation};
 use fluxd_pon::validation as pon_validation;
@@ -25,8 +27,12 @@ use fluxd_primitives::transaction::{
     Transaction, TransactionEncodeError, FLUXNODE_INTERNAL_NORMAL_TX_VERSION,
     FLUXNODE_INTERNAL_P2SH_TX_VERSION, FLUXNODE_TX_UPGRADEABLE_VERSION, FLUXNODE_TX_VERSION,
 };
+use fluxd_script::interpreter::{
+    verify_script, ScriptError, ScriptFlags, SCRIPT_VERIFY_NONE, SCRIPT_VERIFY_P2SH,
+};
 use fluxd_shielded::{verify_transaction, ShieldedError, ShieldedParams};
 use rayon::prelude::*;
+use rayon::ThreadPool;
 
 #[derive(Clone, Debug, Default)]
 pub struct ValidationFlags {
@@ -34,8 +40,81 @@ pub struct ValidationFlags {
     pub check_pon: bool,
     pub check_script: bool,
     pub check_shielded: bool,
+    /// Ancestors of this block (inclusive) skip transparent script verification.
+    pub assume_valid: Option<Hash256>,
     pub shielded_params: Option<Arc<ShieldedParams>>,
+    /// Dedicated pool for per-input script checks; falls back to the global rayon pool.
+    pub script_pool: Option<Arc<ThreadPool>>,
     pub metrics: Option<Arc<ValidationMetrics>>,
+    /// Debug check: `connect_block` rejects raw block bytes that do not re-encode identically.
+    pub check_canonical_encoding: bool,
+}
+
+/// A transparent input whose prevout has been resolved and is ready for script checks.
+#[derive(Clone, Copy, Debug)]
+pub struct ScriptCheckInput<'a> {
+    pub tx: &'a Transaction,
+    pub tx_index: usize,
+    pub input_index: usize,
+    pub script_pubkey: &'a [u8],
+    pub amount: i64,
+}
+
+#[derive(Debug)]
+pub struct ScriptCheckFailure {
+    pub tx_index: usize,
+    pub input_index: usize,
+    pub error: ScriptError,
+}
+
+pub fn build_script_pool(workers: usize) -> Result<Arc<ThreadPool>, String> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(workers.max(1))
+        .thread_name(|index| format!("script-verify-{index}"))
+        .build()
+        .map(Arc::new)
+        .map_err(|err| err.to_string())
+}
+
+/// Verifies all queued inputs in parallel, short-circuiting on failure.
+///
+/// `inputs` must be ordered by (tx_index, input_index); the reported failure is always the
+/// first failing input in that order regardless of which worker found it.
+pub fn verify_script_inputs(
+    inputs: &[ScriptCheckInput<'_>],
+    script_flags: ScriptFlags,
+    branch_id: u32,
+    pool: Option<&ThreadPool>,
+) -> Result<(), ScriptCheckFailure> {
+    let run = || {
+        inputs
+            .par_iter()
+            .map(|input| {
+                verify_script(
+                    &input.tx.vin[input.input_index].script_sig,
+                    input.script_pubkey,
+                    input.tx,
+                    input.input_index,
+                    input.amount,
+                    script_flags,
+                    branch_id,
+                )
+                .map_err(|error| ScriptCheckFailure {
+                    tx_index: input.tx_index,
+                    input_index: input.input_index,
+                    error,
+                })
+            })
+            .find_first(|result| result.is_err())
+    };
+    let failure = match pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    };
+    match failure {
+        Some(Err(failure)) => Err(failure),
+        _ => Ok(()),
+    }
 }
 
 #[derive(Debug, Default)]
@@ -206,7 +285,7 @@ pub fn validate_block_with_txids_and_size(
         return Err(ValidationError::InvalidBlock("coinbase height mismatch"));
     }
 
-    let branch_id = current_epoch_branch_id(height, &params.upgrades);
+    let branch_id = branch_id_for_height(height, &params.upgrades);
     let mut seen_txids = HashSet::with_capacity(txids.len());
     let mut fluxnode_outpoints = HashSet::new();
     let mut shielded_txs: Vec<&Transaction> = Vec::new();
@@ -249,7 +328,7 @@ pub fn validate_block_with_txids_and_size(
         }
     }
     let sigops = block_sigops(block);
-    if sigops > MAX_BLOCK_SIGOPS {
+    if sigops > MAX_BLOCK_SIGOPS as usize {
         return Err(ValidationError::InvalidBlock("block sigops limit exceeded"));
     }
 
@@ -279,6 +358,201 @@ pub fn validate_block_with_txids_and_size(
     Ok(txids)
 }
 
+/// Validation outcome for a single transaction, see `validate_block_collecting`.
+#[derive(Clone, Debug, Default)]
+pub struct TxValidationReport {
+    /// `None` when the transaction could not be encoded to compute its txid.
+    pub txid: Option<Hash256>,
+    pub errors: Vec<String>,
+    /// Failing inputs as (input index, script error).
+    pub script_failures: Vec<(usize, String)>,
+    /// Inputs whose prevout could not be found, so their scripts were not checked.
+    pub missing_prevouts: Vec<usize>,
+}
+
+impl TxValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+            && self.script_failures.is_empty()
+            && self.missing_prevouts.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BlockValidationReport {
+    pub block_errors: Vec<String>,
+    /// One entry per transaction, in block order.
+    pub txs: Vec<TxValidationReport>,
+}
+
+impl BlockValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.block_errors.is_empty() && self.txs.iter().all(TxValidationReport::is_valid)
+    }
+}
+
+/// Runs the same context-free checks as `validate_block_with_txids_and_size` but keeps going
+/// after a failure, recording every error against the block or the transaction it belongs to.
+///
+/// Script checks need the spent outputs and are left to the caller, see
+/// `collect_script_failures`. Metrics are not recorded.
+pub fn validate_block_collecting(
+    block: &Block,
+    height: i32,
+    params: &ConsensusParams,
+    flags: &ValidationFlags,
+) -> BlockValidationReport {
+    let mut report = BlockValidationReport {
+        block_errors: Vec::new(),
+        txs: vec![TxValidationReport::default(); block.transactions.len()],
+    };
+    if block.transactions.is_empty() {
+        report
+            .block_errors
+            .push("block must contain at least one transaction".to_string());
+        return report;
+    }
+    if block.transactions.len() as u32 > MAX_BLOCK_SIZE {
+        report
+            .block_errors
+            .push("block transaction count too large".to_string());
+    }
+    match block.consensus_encode() {
+        Ok(bytes) if bytes.len() as u32 > MAX_BLOCK_SIZE => {
+            report.block_errors.push("block size too large".to_string());
+        }
+        Ok(_) => {}
+        Err(err) => report.block_errors.push(err.to_string()),
+    }
+    if let Err(err) = validate_header(block, height, params, flags) {
+        report.block_errors.push(err.to_string());
+    }
+
+    for (tx, tx_report) in block.transactions.iter().zip(report.txs.iter_mut()) {
+        match tx.txid() {
+            Ok(txid) => tx_report.txid = Some(txid),
+            Err(err) => tx_report.errors.push(err.to_string()),
+        }
+    }
+    let txids: Option<Vec<Hash256>> = report.txs.iter().map(|tx| tx.txid).collect();
+    if let Some(txids) = txids.as_ref() {
+        if let Err(err) = validate_merkle_root(block, txids) {
+            report.block_errors.push(err.to_string());
+        }
+    }
+    if height > 20 && !coinbase_height_matches(&block.transactions[0], height) {
+        report
+            .block_errors
+            .push("coinbase height mismatch".to_string());
+    }
+
+    let branch_id = branch_id_for_height(height, &params.upgrades);
+    let block_time = block.header.time as i64;
+    let mut seen_txids = HashSet::with_capacity(block.transactions.len());
+    let mut fluxnode_outpoints = HashSet::new();
+    let mut shielded_txs: Vec<usize> = Vec::new();
+    let mut tx_flags = flags.clone();
+    tx_flags.check_shielded = false;
+    for (index, tx) in block.transactions.iter().enumerate() {
+        let tx_report = &mut report.txs[index];
+        if !is_final_tx(tx, height, block_time) {
+            tx_report
+                .errors
+                .push("transaction is not final".to_string());
+        }
+        if let Err(err) = validate_transaction(tx, index == 0, height, params, branch_id, &tx_flags)
+        {
+            tx_report.errors.push(err.to_string());
+        }
+        if let Some(txid) = tx_report.txid {
+            if !seen_txids.insert(txid) {
+                tx_report
+                    .errors
+                    .push(ValidationError::DuplicateTransaction.to_string());
+            }
+        }
+        if index > 0 {
+            if let Some(outpoint) = fluxnode_collateral_outpoint(tx) {
+                if !fluxnode_outpoints.insert(outpoint) {
+                    tx_report
+                        .errors
+                        .push("duplicate fluxnode collateral outpoint".to_string());
+                }
+            }
+        }
+        if flags.check_shielded
+            && (!tx.join_splits.is_empty()
+                || !tx.shielded_spends.is_empty()
+                || !tx.shielded_outputs.is_empty())
+        {
+            shielded_txs.push(index);
+        }
+    }
+    if block_sigops(block) > MAX_BLOCK_SIGOPS as usize {
+        report
+            .block_errors
+            .push("block sigops limit exceeded".to_string());
+    }
+
+    if !shielded_txs.is_empty() {
+        match flags.shielded_params.as_ref() {
+            Some(shielded_params) => {
+                let failures: Vec<(usize, ShieldedError)> = shielded_txs
+                    .par_iter()
+                    .filter_map(|&index| {
+                        verify_transaction(&block.transactions[index], branch_id, shielded_params)
+                            .err()
+                            .map(|err| (index, err))
+                    })
+                    .collect();
+                for (index, err) in failures {
+                    report.txs[index].errors.push(err.to_string());
+                }
+            }
+            None => report
+                .block_errors
+                .push("shielded parameters not loaded".to_string()),
+        }
+    }
+    report
+}
+
+/// Verifies every queued input without short-circuiting and returns all failures, ordered
+/// like `inputs`.
+pub fn collect_script_failures(
+    inputs: &[ScriptCheckInput<'_>],
+    script_flags: ScriptFlags,
+    branch_id: u32,
+    pool: Option<&ThreadPool>,
+) -> Vec<ScriptCheckFailure> {
+    let run = || {
+        inputs
+            .par_iter()
+            .filter_map(|input| {
+                verify_script(
+                    &input.tx.vin[input.input_index].script_sig,
+                    input.script_pubkey,
+                    input.tx,
+                    input.input_index,
+                    input.amount,
+                    script_flags,
+                    branch_id,
+                )
+                .err()
+                .map(|error| ScriptCheckFailure {
+                    tx_index: input.tx_index,
+                    input_index: input.input_index,
+                    error,
+                })
+            })
+            .collect()
+    };
+    match pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    }
+}
+
 pub fn validate_mempool_transaction(
     tx: &Transaction,
     height: i32,
@@ -291,7 +565,7 @@ pub fn validate_mempool_transaction(
             "transaction is not final",
         ));
     }
-    let branch_id = current_epoch_branch_id(height, &params.upgrades);
+    let branch_id = branch_id_for_height(height, &params.upgrades);
     validate_transaction(tx, false, height, params, branch_id, flags)?;
     Ok(())
 }
@@ -415,7 +689,7 @@ fn validate_transaction(
     }
 
     let tx_size = tx.consensus_encode()?.len() as u32;
-    let sapling_active = network_upgrade_active(height, &params.upgrades, UpgradeIndex::Acadia);
+    let sapling_active = consensus_rules(height, params).sapling_active;
     if !sapling_active && tx_size > MAX_TX_SIZE_BEFORE_SAPLING {
         return Err(ValidationError::InvalidTransaction("transaction too large"));
     }
@@ -792,94 +1066,132 @@ fn push_data(script: &mut Vec<u8>, data: &[u8]) {
     script.extend_from_slice(data);
 }
 
-fn block_sigops(block: &Block) -> u32 {
+fn block_sigops(block: &Block) -> usize {
     block
         .transactions
         .iter()
-        .map(|tx| {
-            let input_ops: u32 = tx
-                .vin
-                .iter()
-                .map(|input| legacy_sigops(&input.script_sig))
-                .sum();
-            let output_ops: u32 = tx
-                .vout
-                .iter()
-                .map(|output| legacy_sigops(&output.script_pubkey))
-                .sum();
-            input_ops + output_ops
-        })
+        .map(|tx| count_sigops(tx, SCRIPT_VERIFY_NONE, &[]))
         .sum()
 }
 
-fn legacy_sigops(script: &[u8]) -> u32 {
+/// Signature operations `tx` counts against [`MAX_BLOCK_SIGOPS`].
+///
+/// Every scriptSig and scriptPubKey is counted the legacy way, where each `OP_CHECKMULTISIG`
+/// costs 20. With `SCRIPT_VERIFY_P2SH` in `flags`, inputs spending a P2SH output also count their
+/// redeem script, using the multisig key count when it is given. `prevouts` holds the spent
+/// scriptPubKey of each input, in input order; it is not consulted for coinbase transactions.
+pub fn count_sigops(tx: &Transaction, flags: ScriptFlags, prevouts: &[&[u8]]) -> usize {
+    let mut count = tx
+        .vin
+        .iter()
+        .map(|input| script_sigops(&input.script_sig, false))
+        .chain(
+            tx.vout
+                .iter()
+                .map(|output| script_sigops(&output.script_pubkey, false)),
+        )
+        .fold(0usize, usize::saturating_add);
+    if flags & SCRIPT_VERIFY_P2SH != 0 && !is_coinbase_tx(tx) {
+        for (input, prev_script_pubkey) in tx.vin.iter().zip(prevouts) {
+            count = count.saturating_add(p2sh_sigops(&input.script_sig, prev_script_pubkey));
+        }
+    }
+    count
+}
+
+/// Sigops of the redeem script that `script_sig` pushes last, when `prev_script_pubkey` is P2SH.
+pub fn p2sh_sigops(script_sig: &[u8], prev_script_pubkey: &[u8]) -> usize {
+    const OP_HASH160: u8 = 0xa9;
+    const OP_EQUAL: u8 = 0x87;
+    const OP_16: u8 = 0x60;
+
+    let is_p2sh = prev_script_pubkey.len() == 23
+        && prev_script_pubkey[0] == OP_HASH160
+        && prev_script_pubkey[1] == 0x14
+        && prev_script_pubkey[22] == OP_EQUAL;
+    if !is_p2sh {
+        return 0;
+    }
+    let mut redeem_script: &[u8] = &[];
+    let mut cursor = 0usize;
+    while cursor < script_sig.len() {
+        let opcode = script_sig[cursor];
+        cursor += 1;
+        if opcode > OP_16 {
+            return 0;
+        }
+        let Some((data, next)) = read_push(script_sig, opcode, cursor) else {
+            return 0;
+        };
+        redeem_script = data;
+        cursor = next;
+    }
+    script_sigops(redeem_script, true)
+}
+
+fn script_sigops(script: &[u8], accurate: bool) -> usize {
+    const OP_1: u8 = 0x51;
+    const OP_16: u8 = 0x60;
     const OP_CHECKSIG: u8 = 0xac;
     const OP_CHECKSIGVERIFY: u8 = 0xad;
     const OP_CHECKMULTISIG: u8 = 0xae;
     const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
-    const OP_PUSHDATA1: u8 = 0x4c;
-    const OP_PUSHDATA2: u8 = 0x4d;
-    const OP_PUSHDATA4: u8 = 0x4e;
 
-    let mut count = 0u32;
+    let mut count = 0usize;
     let mut cursor = 0usize;
+    let mut last_opcode = 0xffu8;
     while cursor < script.len() {
         let opcode = script[cursor];
         cursor += 1;
         match opcode {
             OP_CHECKSIG | OP_CHECKSIGVERIFY => count += 1,
-            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => count += 20,
-            0x01..=0x4b => {
-                let len = opcode as usize;
-                if cursor + len > script.len() {
-                    break;
-                }
-                cursor += len;
+            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                count += if accurate && (OP_1..=OP_16).contains(&last_opcode) {
+                    usize::from(last_opcode - OP_1 + 1)
+                } else {
+                    20
+                };
             }
-            OP_PUSHDATA1 => {
-                if cursor >= script.len() {
-                    break;
-                }
-                let len = script[cursor] as usize;
-                cursor += 1;
-                if cursor + len > script.len() {
-                    break;
-                }
-                cursor += len;
-            }
-            OP_PUSHDATA2 => {
-                if cursor + 2 > script.len() {
-                    break;
-                }
-                let len = u16::from_le_bytes([script[cursor], script[cursor + 1]]) as usize;
-                cursor += 2;
-                if cursor + len > script.len() {
-                    break;
-                }
-                cursor += len;
-            }
-            OP_PUSHDATA4 => {
-                if cursor + 4 > script.len() {
-                    break;
-                }
-                let len = u32::from_le_bytes([
-                    script[cursor],
-                    script[cursor + 1],
-                    script[cursor + 2],
-                    script[cursor + 3],
-                ]) as usize;
-                cursor += 4;
-                if cursor + len > script.len() {
-                    break;
-                }
-                cursor += len;
-            }
-            _ => {}
+            _ => match read_push(script, opcode, cursor) {
+                Some((_, next)) => cursor = next,
+                None => break,
+            },
         }
+        last_opcode = opcode;
     }
     count
 }
 
+/// Data pushed by `opcode` and the cursor just past it; `None` if the push runs off the end.
+/// Non-push opcodes push nothing.
+fn read_push(script: &[u8], opcode: u8, cursor: usize) -> Option<(&[u8], usize)> {
+    const OP_PUSHDATA1: u8 = 0x4c;
+    const OP_PUSHDATA2: u8 = 0x4d;
+    const OP_PUSHDATA4: u8 = 0x4e;
+
+    let (len, start) = match opcode {
+        0x01..=0x4b => (opcode as usize, cursor),
+        OP_PUSHDATA1 => (*script.get(cursor)? as usize, cursor + 1),
+        OP_PUSHDATA2 => {
+            let bytes = script.get(cursor..cursor + 2)?;
+            (
+                u16::from_le_bytes([bytes[0], bytes[1]]) as usize,
+                cursor + 2,
+            )
+        }
+        OP_PUSHDATA4 => {
+            let bytes = script.get(cursor..cursor + 4)?;
+            (
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize,
+                cursor + 4,
+            )
+        }
+        _ => return Some((&[], cursor)),
+    };
+    let end = start.checked_add(len)?;
+    Some((script.get(start..end)?, end))
+}
+
 fn fluxnode_start_version(tx: &Transaction) -> Option<i32> {
     match tx.fluxnode.as_ref()? {
         FluxnodeTx::V6(FluxnodeTxV6::Start(start)) => Some(start.flux_tx_version),
@@ -903,7 +1215,13 @@ fn fluxnode_collateral_outpoint(tx: &Transaction) -> Option<(Hash256, u32)> {
 
 #[cfg(test)]
 mod tests {
-    use super::merkle_root;
+    use super::{
+        build_script_pool, count_sigops, merkle_root, verify_script_inputs, ScriptCheckInput,
+    };
+    use fluxd_primitives::hash::hash160;
+    use fluxd_primitives::outpoint::OutPoint;
+    use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+    use fluxd_script::interpreter::{BLOCK_SCRIPT_VERIFY_FLAGS, SCRIPT_VERIFY_NONE};
 
     fn hash(byte: u8) -> [u8; 32] {
         [byte; 32]
@@ -929,4 +1247,101 @@ mod tests {
         let (_, mutated) = merkle_root(&txids);
         assert!(!mutated, "odd-length duplication should not mark mutation");
     }
+
+    fn spend_tx(inputs: usize) -> Transaction {
+        Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: (0..inputs)
+                .map(|index| TxIn {
+                    prevout: OutPoint {
+                        hash: hash(index as u8),
+                        index: 0,
+                    },
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                })
+                .collect(),
+            vout: vec![TxOut {
+                value: 1,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        }
+    }
+
+    #[test]
+    fn script_inputs_report_first_failure_in_block_order() {
+        let tx0 = spend_tx(1);
+        let tx1 = spend_tx(3);
+        let op_true = [0x51u8];
+        let op_false = [0x00u8];
+        let mut inputs = vec![ScriptCheckInput {
+            tx: &tx0,
+            tx_index: 1,
+            input_index: 0,
+            script_pubkey: &op_true,
+            amount: 1,
+        }];
+        for (input_index, script_pubkey) in [&op_true[..], &op_false[..], &op_false[..]]
+            .into_iter()
+            .enumerate()
+        {
+            inputs.push(ScriptCheckInput {
+                tx: &tx1,
+                tx_index: 2,
+                input_index,
+                script_pubkey,
+                amount: 1,
+            });
+        }
+
+        let pool = build_script_pool(4).expect("script pool");
+        for _ in 0..16 {
+            let failure =
+                verify_script_inputs(&inputs, BLOCK_SCRIPT_VERIFY_FLAGS, 0, Some(pool.as_ref()))
+                    .expect_err("false script fails");
+            assert_eq!((failure.tx_index, failure.input_index), (2, 1));
+        }
+
+        assert!(verify_script_inputs(&inputs[..2], BLOCK_SCRIPT_VERIFY_FLAGS, 0, None).is_ok());
+    }
+    #[test]
+    fn count_sigops_adds_p2sh_redeem_scripts() {
+        // 2-of-3 multisig: legacy counting charges 20, the redeem script charges 3.
+        let mut redeem = vec![0x52];
+        for key in 0..3u8 {
+            redeem.push(33);
+            redeem.extend_from_slice(&[key + 2; 33]);
+        }
+        redeem.extend_from_slice(&[0x53, 0xae]);
+        let mut p2sh = vec![0xa9, 0x14];
+        p2sh.extend_from_slice(&hash160(&redeem));
+        p2sh.push(0x87);
+
+        let mut tx = spend_tx(2);
+        tx.vin[0].script_sig = vec![0x00, 0x4c, redeem.len() as u8];
+        tx.vin[0].script_sig.extend_from_slice(&redeem);
+        tx.vin[1].script_sig = vec![0x01, 0xac];
+        tx.vout[0].script_pubkey = vec![0xae];
+        let prevouts: [&[u8]; 2] = [&p2sh, &[0xac]];
+
+        assert_eq!(count_sigops(&tx, SCRIPT_VERIFY_NONE, &prevouts), 20);
+        assert_eq!(count_sigops(&tx, BLOCK_SCRIPT_VERIFY_FLAGS, &prevouts), 23);
+
+        // A scriptSig that is not push-only contributes no redeem script.
+        tx.vin[0].script_sig.insert(0, 0x61);
+        assert_eq!(count_sigops(&tx, BLOCK_SCRIPT_VERIFY_FLAGS, &prevouts), 20);
+    }
 }
@@ -0,0 +1,130 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{connect, make_header, regtest_params};
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::ChainState;
+use fluxd_consensus::params::{ChainParams, Checkpoint};
+use fluxd_consensus::Hash256;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::BlockHeader;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+const NOW: u32 = 1_700_000_000;
+
+struct Fixture {
+    chainstate: ChainState<MemoryStore>,
+    params: ChainParams,
+    bits: u32,
+    genesis: BlockHeader,
+    _dir: tempfile::TempDir,
+}
+
+fn setup() -> Fixture {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(store, blocks, undo);
+
+    let mut params = regtest_params();
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let genesis = make_header([0u8; 32], NOW, bits, 0);
+    let genesis_hash = genesis.hash();
+    params.consensus.hash_genesis_block = genesis_hash;
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: genesis_hash,
+    }];
+    let fixture = Fixture {
+        chainstate,
+        params,
+        bits,
+        genesis,
+        _dir: dir,
+    };
+    receive(&fixture, std::slice::from_ref(&fixture.genesis));
+    fixture
+}
+
+fn receive(fixture: &Fixture, headers: &[BlockHeader]) {
+    let mut batch = WriteBatch::new();
+    fixture
+        .chainstate
+        .insert_headers_batch_with_pow(headers, &fixture.params.consensus, &mut batch, false)
+        .expect("insert headers");
+    fixture
+        .chainstate
+        .commit_batch(batch)
+        .expect("commit headers");
+}
+
+fn best_header(fixture: &Fixture) -> Hash256 {
+    fixture
+        .chainstate
+        .best_header()
+        .expect("best header")
+        .expect("tip")
+        .hash
+}
+
+#[test]
+fn equal_work_tie_keeps_first_received_header() {
+    for first_tag in [0xa1u8, 0xb1] {
+        let fixture = setup();
+        let genesis_hash = fixture.genesis.hash();
+        let header_a = make_header(genesis_hash, NOW + 1, fixture.bits, 0xa1);
+        let header_b = make_header(genesis_hash, NOW + 1, fixture.bits, 0xb1);
+        let (first, second) = if first_tag == 0xa1 {
+            (header_a, header_b)
+        } else {
+            (header_b, header_a)
+        };
+
+        receive(&fixture, std::slice::from_ref(&first));
+        receive(&fixture, std::slice::from_ref(&second));
+        assert_eq!(best_header(&fixture), first.hash());
+
+        // Re-announcing the later header must not flip the choice either.
+        receive(&fixture, std::slice::from_ref(&second));
+        assert_eq!(best_header(&fixture), first.hash());
+
+        let fixture = setup();
+        receive(&fixture, &[first.clone(), second.clone()]);
+        assert_eq!(best_header(&fixture), first.hash());
+    }
+}
+
+#[test]
+fn equal_work_competitor_does_not_displace_connected_tip() {
+    let fixture = setup();
+    let genesis_hash = fixture.genesis.hash();
+    let header_1a = make_header(genesis_hash, NOW + 1, fixture.bits, 0xa1);
+    let header_1b = make_header(genesis_hash, NOW + 1, fixture.bits, 0xb1);
+    let header_2b = make_header(header_1b.hash(), NOW + 2, fixture.bits, 0xb2);
+
+    receive(&fixture, std::slice::from_ref(&header_1a));
+    connect(&fixture.chainstate, &fixture.params, &fixture.genesis, 0);
+    connect(&fixture.chainstate, &fixture.params, &header_1a, 1);
+    receive(&fixture, std::slice::from_ref(&header_1b));
+    assert_eq!(best_header(&fixture), header_1a.hash());
+
+    // Invalidating the tip hands the best header to the competitor; reconsidering re-runs
+    // selection, and the incumbent keeps the equal-work tie.
+    fixture
+        .chainstate
+        .invalidate_block(&header_1a.hash())
+        .expect("invalidate");
+    assert_eq!(best_header(&fixture), header_1b.hash());
+    fixture
+        .chainstate
+        .reconsider_block(&header_1a.hash())
+        .expect("reconsider");
+    assert_eq!(best_header(&fixture), header_1b.hash());
+
+    // Strictly more work always wins.
+    receive(&fixture, std::slice::from_ref(&header_2b));
+    assert_eq!(best_header(&fixture), header_2b.hash());
+}
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::{ChainState, ChainStateError};
+use fluxd_chainstate::validation::{ValidationError, ValidationFlags};
+use fluxd_consensus::constants::MAX_BLOCK_SIGOPS;
+use fluxd_consensus::params::{chain_params, ChainParams, Checkpoint, Network};
+use fluxd_consensus::rewards::block_subsidy;
+use fluxd_consensus::upgrades::UpgradeIndex;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
+use fluxd_primitives::hash::hash160;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+const P2SH_OUTPUTS: usize = 5;
+const OUTPUT_VALUE: i64 = 1_000;
+
+/// 260 x `OP_16 OP_CHECKMULTISIG`: 520 bytes, the largest redeem script, counting 4160 sigops.
+fn heavy_redeem_script() -> Vec<u8> {
+    [0x60, 0xae].repeat(260)
+}
+
+fn p2sh_script(redeem_script: &[u8]) -> Vec<u8> {
+    let mut script = vec![0xa9, 0x14];
+    script.extend_from_slice(&hash160(redeem_script));
+    script.push(0x87);
+    script
+}
+
+fn tx(vin: Vec<TxIn>, vout: Vec<TxOut>) -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version: 1,
+        version_group_id: 0,
+        vin,
+        vout,
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+fn coinbase_tx(height: u32, vout: Vec<TxOut>) -> Transaction {
+    tx(
+        vec![TxIn {
+            prevout: OutPoint::null(),
+            script_sig: height.to_le_bytes().to_vec(),
+            sequence: u32::MAX,
+        }],
+        vout,
+    )
+}
+
+fn test_params() -> ChainParams {
+    let mut params = chain_params(Network::Regtest);
+    params.funding.exchange_height = i64::MAX;
+    params.funding.foundation_height = i64::MAX;
+    params.swap_pool.start_height = i64::MAX;
+    params.fluxnode.start_payments_height = i64::MAX;
+    params.consensus.coinbase_maturity = 1;
+    params.consensus.digishield_averaging_window = 10_000;
+    params.consensus.upgrades[UpgradeIndex::Lwma.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Equi144_5.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Kamiooka.as_usize()].activation_height = i32::MAX;
+    params
+}
+
+#[test]
+fn connect_block_counts_p2sh_sigops_against_block_limit() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::new(MemoryStore::new()), blocks, undo);
+    let mut params = test_params();
+
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let mut headers = Vec::new();
+    let mut prev = [0u8; 32];
+    for height in 0..=2u32 {
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: prev,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: 1_700_000_000 + height,
+            bits,
+            nonce: [height as u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+    let mut batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+        .expect("insert headers");
+    chainstate.commit_batch(batch).expect("commit headers");
+
+    let flags = ValidationFlags::default();
+    let redeem_script = heavy_redeem_script();
+    let funding = coinbase_tx(
+        1,
+        vec![
+            TxOut {
+                value: OUTPUT_VALUE,
+                script_pubkey: p2sh_script(&redeem_script),
+            };
+            P2SH_OUTPUTS
+        ],
+    );
+    let funding_txid = funding.txid().expect("txid");
+    let genesis = Block {
+        header: headers[0].clone(),
+        transactions: vec![coinbase_tx(
+            0,
+            vec![TxOut {
+                value: block_subsidy(0, &params.consensus),
+                script_pubkey: vec![0x51],
+            }],
+        )],
+    };
+    let funding_block = Block {
+        header: headers[1].clone(),
+        transactions: vec![funding],
+    };
+    for (height, block) in [genesis, funding_block].iter().enumerate() {
+        let batch = chainstate
+            .connect_block(
+                block,
+                height as i32,
+                &params,
+                &flags,
+                true,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+    }
+
+    let mut script_sig = vec![0x4d];
+    script_sig.extend_from_slice(&(redeem_script.len() as u16).to_le_bytes());
+    script_sig.extend_from_slice(&redeem_script);
+    let spend_block = |inputs: usize| Block {
+        header: headers[2].clone(),
+        transactions: vec![
+            coinbase_tx(
+                2,
+                vec![TxOut {
+                    value: 1,
+                    script_pubkey: vec![0x51],
+                }],
+            ),
+            tx(
+                (0..inputs)
+                    .map(|index| TxIn {
+                        prevout: OutPoint {
+                            hash: funding_txid,
+                            index: index as u32,
+                        },
+                        script_sig: script_sig.clone(),
+                        sequence: u32::MAX,
+                    })
+                    .collect(),
+                vec![TxOut {
+                    value: OUTPUT_VALUE,
+                    script_pubkey: vec![0x51],
+                }],
+            ),
+        ],
+    };
+
+    // Five inputs carry 5 * 4160 = 20800 P2SH sigops, over the limit even though the block has
+    // no legacy sigops at all.
+    assert!(4160 * P2SH_OUTPUTS > MAX_BLOCK_SIGOPS as usize);
+    let err = chainstate
+        .connect_block(
+            &spend_block(P2SH_OUTPUTS),
+            2,
+            &params,
+            &flags,
+            true,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect_err("block over the sigop limit");
+    assert!(matches!(
+        err,
+        ChainStateError::Validation(ValidationError::InvalidBlock("block sigops limit exceeded"))
+    ));
+
+    // Four inputs (16640 sigops) stay under it.
+    let batch = chainstate
+        .connect_block(
+            &spend_block(P2SH_OUTPUTS - 1),
+            2,
+            &params,
+            &flags,
+            true,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("block under the sigop limit");
+    chainstate.commit_batch(batch).expect("commit block");
+    assert_eq!(
+        chainstate.best_block().expect("best").expect("tip").height,
+        2
+    );
+}
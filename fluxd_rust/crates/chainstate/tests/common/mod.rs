@@ -0,0 +1,94 @@
+//! Block and chain fixtures shared by the chainstate integration tests.
+#![allow(dead_code)]
+
+use fluxd_chainstate::state::ChainState;
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::{chain_params, ChainParams, Network};
+use fluxd_consensus::upgrades::UpgradeIndex;
+use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+use fluxd_storage::memory::MemoryStore;
+
+pub fn coinbase_tx(height: u32) -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version: 1,
+        version_group_id: 0,
+        vin: vec![TxIn {
+            prevout: OutPoint::null(),
+            script_sig: height.to_le_bytes().to_vec(),
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value: 0,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+pub fn make_header(prev_block: [u8; 32], time: u32, bits: u32, nonce_tag: u8) -> BlockHeader {
+    BlockHeader {
+        version: CURRENT_VERSION,
+        prev_block,
+        merkle_root: [0u8; 32],
+        final_sapling_root: [0u8; 32],
+        time,
+        bits,
+        nonce: [nonce_tag; 32],
+        solution: Vec::new(),
+        nodes_collateral: OutPoint::null(),
+        block_sig: Vec::new(),
+    }
+}
+
+pub fn regtest_params() -> ChainParams {
+    let mut params = chain_params(Network::Regtest);
+    params.funding.exchange_height = i64::MAX;
+    params.funding.foundation_height = i64::MAX;
+    params.swap_pool.start_height = i64::MAX;
+    params.fluxnode.start_payments_height = i64::MAX;
+    params.consensus.digishield_averaging_window = 10_000;
+    params.consensus.upgrades[UpgradeIndex::Lwma.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Equi144_5.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Kamiooka.as_usize()].activation_height = i32::MAX;
+    params
+}
+
+pub fn connect(
+    chainstate: &ChainState<MemoryStore>,
+    params: &ChainParams,
+    header: &BlockHeader,
+    height: u32,
+) {
+    let block = Block {
+        header: header.clone(),
+        transactions: vec![coinbase_tx(height)],
+    };
+    let batch = chainstate
+        .connect_block(
+            &block,
+            height as i32,
+            params,
+            &ValidationFlags::default(),
+            true,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("connect block");
+    chainstate.commit_batch(batch).expect("commit block");
+}
@@ -36,6 +36,7 @@ fn make_tx(vin: Vec<TxIn>, vout: Vec<TxOut>) -> Transaction {
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     }
 }
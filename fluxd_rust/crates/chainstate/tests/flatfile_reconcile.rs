@@ -0,0 +1,348 @@
+mod common;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use common::{coinbase_tx, connect, make_header, regtest_params};
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::reconcile::FlatfileReconcileReport;
+use fluxd_chainstate::state::{ChainState, ChainStateError, UtxoFlushPolicy};
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::{ChainParams, Checkpoint};
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{Block, BlockHeader};
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+/// Opens the flatfiles afresh over the same store, as a restart does.
+fn open(store: &Arc<MemoryStore>, dir: &Path) -> ChainState<MemoryStore> {
+    let blocks = FlatFileStore::new(dir, 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir, "undo", 10_000_000).expect("flatfiles");
+    ChainState::new(Arc::clone(store), blocks, undo)
+}
+
+fn connect_batch(
+    chainstate: &ChainState<MemoryStore>,
+    params: &ChainParams,
+    header: &BlockHeader,
+    height: u32,
+) -> WriteBatch {
+    let block = Block {
+        header: header.clone(),
+        transactions: vec![coinbase_tx(height)],
+    };
+    chainstate
+        .connect_block(
+            &block,
+            height as i32,
+            params,
+            &ValidationFlags::default(),
+            true,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("connect block")
+}
+
+/// A chainstate with genesis connected and a known header for block 1.
+fn setup(
+    store: &Arc<MemoryStore>,
+    dir: &Path,
+) -> (ChainState<MemoryStore>, ChainParams, BlockHeader) {
+    let chainstate = open(store, dir);
+    let mut params = regtest_params();
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let header0 = make_header([0u8; 32], 1_700_000_000, bits, 0);
+    let hash0 = header0.hash();
+    params.consensus.hash_genesis_block = hash0;
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: hash0,
+    }];
+    let header1 = make_header(hash0, 1_700_000_001, bits, 1);
+
+    let mut header_batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(
+            &[header0.clone(), header1.clone()],
+            &params.consensus,
+            &mut header_batch,
+            false,
+        )
+        .expect("insert headers");
+    chainstate
+        .commit_batch(header_batch)
+        .expect("commit headers");
+    connect(&chainstate, &params, &header0, 0);
+    (chainstate, params, header1)
+}
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).expect("flatfile metadata").len()
+}
+
+fn truncate(path: &Path, len: u64) {
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .expect("open flatfile")
+        .set_len(len)
+        .expect("truncate flatfile");
+}
+
+#[test]
+fn unindexed_block_record_is_parked_for_reconnection() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let (chainstate, params, header1) = setup(&store, dir.path());
+    let hash1 = header1.hash();
+
+    // The block and undo records are appended, but the crash comes before the batch commits.
+    drop(connect_batch(&chainstate, &params, &header1, 1));
+    drop(chainstate);
+
+    let reopened = open(&store, dir.path());
+    let report = reopened.reconcile_flatfiles_and_index().expect("reconcile");
+    assert_eq!(
+        report,
+        FlatfileReconcileReport {
+            parked_blocks: 1,
+            ..FlatfileReconcileReport::default()
+        }
+    );
+    assert!(reopened.block_location(&hash1).expect("location").is_none());
+    let parked = reopened
+        .unconnected_block_bytes(&hash1)
+        .expect("unconnected bytes")
+        .expect("parked block");
+    assert_eq!(
+        Block::consensus_decode(&parked)
+            .expect("decode parked")
+            .header
+            .hash(),
+        hash1
+    );
+
+    assert!(reopened
+        .reconcile_flatfiles_and_index()
+        .expect("reconcile again")
+        .is_clean());
+}
+
+#[test]
+fn torn_records_are_truncated() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let (chainstate, params, header1) = setup(&store, dir.path());
+    drop(chainstate);
+
+    let data_path = dir.path().join("data00000.dat");
+    let undo_path = dir.path().join("undo00000.dat");
+    let data_len = file_len(&data_path);
+    let undo_len = file_len(&undo_path);
+    let mut data = OpenOptions::new()
+        .append(true)
+        .open(&data_path)
+        .expect("open data file");
+    data.write_all(&100u32.to_le_bytes()).expect("write length");
+    data.write_all(&[0xab; 10]).expect("write partial body");
+    let mut undo = OpenOptions::new()
+        .append(true)
+        .open(&undo_path)
+        .expect("open undo file");
+    undo.write_all(&[0x01, 0x00]).expect("write partial length");
+
+    let reopened = open(&store, dir.path());
+    let report = reopened.reconcile_flatfiles_and_index().expect("reconcile");
+    assert_eq!(
+        report,
+        FlatfileReconcileReport {
+            truncated_records: 2,
+            ..FlatfileReconcileReport::default()
+        }
+    );
+    assert_eq!(file_len(&data_path), data_len);
+    assert_eq!(file_len(&undo_path), undo_len);
+
+    // Appends continue from the truncated end.
+    connect(&reopened, &params, &header1, 1);
+    let location = reopened
+        .block_location(&header1.hash())
+        .expect("location")
+        .expect("indexed block");
+    assert_eq!(location.offset, data_len);
+    reopened.read_block(location).expect("read block");
+}
+
+#[test]
+fn missing_block_records_drop_stale_entries_and_reject_active_ones() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let (chainstate, params, header1) = setup(&store, dir.path());
+    let hash1 = header1.hash();
+    let data_path = dir.path().join("data00000.dat");
+
+    // Block 1 is connected and then disconnected, so its index entry outlives the active chain.
+    connect(&chainstate, &params, &header1, 1);
+    let location = chainstate
+        .block_location(&hash1)
+        .expect("location")
+        .expect("indexed block");
+    let batch = chainstate.disconnect_block(&hash1).expect("disconnect");
+    chainstate.commit_batch(batch).expect("commit disconnect");
+    drop(chainstate);
+
+    // The store kept the index entry but the block bytes never reached disk.
+    truncate(&data_path, location.offset);
+    let reopened = open(&store, dir.path());
+    let report = reopened.reconcile_flatfiles_and_index().expect("reconcile");
+    assert_eq!(
+        report,
+        FlatfileReconcileReport {
+            dropped_blocks: 1,
+            ..FlatfileReconcileReport::default()
+        }
+    );
+    assert!(reopened.block_location(&hash1).expect("location").is_none());
+    assert!(!reopened
+        .header_entry(&hash1)
+        .expect("header entry")
+        .expect("known header")
+        .has_block());
+
+    // The same loss on the active chain cannot be repaired without the block bytes.
+    connect(&reopened, &params, &header1, 1);
+    let location = reopened
+        .block_location(&hash1)
+        .expect("location")
+        .expect("indexed block");
+    drop(reopened);
+    truncate(&data_path, location.offset);
+    let reopened = open(&store, dir.path());
+    let err = reopened.reconcile_flatfiles_and_index().unwrap_err();
+    assert!(matches!(err, ChainStateError::CorruptIndex(_)), "{err}");
+}
+
+#[test]
+fn missing_undo_record_is_cleared() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let (chainstate, params, header1) = setup(&store, dir.path());
+    let hash1 = header1.hash();
+
+    connect(&chainstate, &params, &header1, 1);
+    let undo_location = chainstate
+        .block_index_entry(&hash1)
+        .expect("index entry")
+        .expect("indexed block")
+        .undo
+        .expect("undo location");
+    drop(chainstate);
+
+    truncate(&dir.path().join("undo00000.dat"), undo_location.offset);
+    let reopened = open(&store, dir.path());
+    let report = reopened.reconcile_flatfiles_and_index().expect("reconcile");
+    assert_eq!(
+        report,
+        FlatfileReconcileReport {
+            dropped_undo: 1,
+            ..FlatfileReconcileReport::default()
+        }
+    );
+    let entry = reopened
+        .block_index_entry(&hash1)
+        .expect("index entry")
+        .expect("indexed block");
+    assert!(entry.undo.is_none());
+    reopened
+        .read_block(entry.block)
+        .expect("block still readable");
+    assert_eq!(
+        reopened
+            .best_block()
+            .expect("best block")
+            .expect("tip")
+            .hash,
+        hash1
+    );
+}
+
+#[test]
+fn unflushed_utxos_are_recovered_before_reconciling() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let (chainstate, params, header1) = setup(&store, dir.path());
+    let genesis = chainstate
+        .best_block()
+        .expect("best block")
+        .expect("tip")
+        .hash;
+    let header2 = make_header(header1.hash(), 1_700_000_002, header1.bits, 2);
+    let mut header_batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(
+            std::slice::from_ref(&header2),
+            &params.consensus,
+            &mut header_batch,
+            false,
+        )
+        .expect("insert header");
+    chainstate
+        .commit_batch(header_batch)
+        .expect("commit header");
+    chainstate
+        .set_utxo_flush_policy(UtxoFlushPolicy {
+            max_dirty_entries: 1_000,
+            max_dirty_bytes: 0,
+        })
+        .expect("policy");
+
+    // Block 1 commits with its UTXO writes deferred; block 2 is appended but never indexed.
+    connect(&chainstate, &params, &header1, 1);
+    drop(connect_batch(&chainstate, &params, &header2, 2));
+    drop(chainstate);
+    let mut data = OpenOptions::new()
+        .append(true)
+        .open(dir.path().join("data00000.dat"))
+        .expect("open data file");
+    data.write_all(&[0x01, 0x00]).expect("write partial length");
+
+    // Same order as node startup.
+    let reopened = open(&store, dir.path());
+    assert_eq!(reopened.recover_unflushed_utxos().expect("recover"), 1);
+    let report = reopened.reconcile_flatfiles_and_index().expect("reconcile");
+    assert_eq!(
+        report,
+        FlatfileReconcileReport {
+            parked_blocks: 1,
+            truncated_records: 1,
+            ..FlatfileReconcileReport::default()
+        }
+    );
+    assert_eq!(
+        reopened
+            .best_block()
+            .expect("best block")
+            .expect("tip")
+            .hash,
+        genesis
+    );
+    assert_eq!(
+        reopened.recover_unflushed_utxos().expect("recover again"),
+        0
+    );
+
+    connect(&reopened, &params, &header1, 1);
+    assert_eq!(
+        reopened
+            .best_block()
+            .expect("best block")
+            .expect("tip")
+            .hash,
+        header1.hash()
+    );
+}
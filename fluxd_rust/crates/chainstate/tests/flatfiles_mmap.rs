@@ -0,0 +1,55 @@
+#![cfg(feature = "mmap")]
+
+use fluxd_chainstate::flatfiles::{FileLocation, FlatFileError, FlatFileStore};
+
+#[test]
+fn read_after_append_remaps_a_grown_file() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+
+    let first = store.append(&[0x11; 64]).expect("append first");
+    // Maps the file at its current length.
+    assert_eq!(store.read(first).expect("read first"), vec![0x11; 64]);
+
+    let second = store.append(&[0x22; 128]).expect("append second");
+    assert_eq!(second.file_id, first.file_id);
+    // The cached mapping ends before `second`, so the read remaps the file.
+    assert_eq!(store.read(second).expect("read second"), vec![0x22; 128]);
+    assert_eq!(store.read_stored_len(second).expect("stored len"), 128);
+    assert_eq!(store.read(first).expect("read first again"), vec![0x11; 64]);
+}
+
+#[test]
+fn mapped_read_reports_length_mismatch() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+
+    let location = store.append(&[0x33; 32]).expect("append");
+    store.read(location).expect("map file");
+
+    // Ends inside the mapping, so the mapped path sees the stored prefix disagree.
+    let short = FileLocation {
+        len: location.len - 1,
+        ..location
+    };
+    let err = store.read(short).unwrap_err();
+    assert!(matches!(err, FlatFileError::LengthMismatch), "{err:?}");
+}
+
+#[test]
+fn truncate_drops_the_mapping_before_appends_reuse_the_space() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+
+    let first = store.append(&[0x44; 16]).expect("append first");
+    let second = store.append(&[0x55; 48]).expect("append second");
+    store.read(second).expect("map file");
+
+    store
+        .truncate(second.file_id, second.offset)
+        .expect("truncate");
+    let third = store.append(&[0x66; 24]).expect("append third");
+    assert_eq!(third.offset, second.offset);
+    assert_eq!(store.read(third).expect("read third"), vec![0x66; 24]);
+    assert_eq!(store.read(first).expect("read first"), vec![0x44; 16]);
+}
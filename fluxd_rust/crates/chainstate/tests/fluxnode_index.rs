@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::fluxnode_index::{p2pkh_script, p2sh_script};
+use fluxd_chainstate::state::ChainState;
+use fluxd_chainstate::utxo::outpoint_key_bytes;
+use fluxd_consensus::params::{chain_params, Network};
+use fluxd_fluxnode::storage::{dedupe_key, FluxnodeRecord};
+use fluxd_primitives::hash::hash160;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, KeyValueStore, WriteBatch};
+
+fn record(
+    tag: u8,
+    tier: u8,
+    collateral_pubkey: &[u8],
+    p2sh_script: Option<&[u8]>,
+) -> (FluxnodeRecord, WriteBatch) {
+    let mut batch = WriteBatch::new();
+    let operator = dedupe_key(&[0x02, tag]);
+    batch.put(Column::FluxnodeKey, operator.0, vec![0x02, tag]);
+    let collateral_key = dedupe_key(collateral_pubkey);
+    batch.put(
+        Column::FluxnodeKey,
+        collateral_key.0,
+        collateral_pubkey.to_vec(),
+    );
+    let p2sh_key = p2sh_script.map(|script| {
+        let key = dedupe_key(script);
+        batch.put(Column::FluxnodeKey, key.0, script.to_vec());
+        key
+    });
+    let record = FluxnodeRecord {
+        collateral: OutPoint {
+            hash: [tag; 32],
+            index: u32::from(tag),
+        },
+        tier,
+        start_height: 10,
+        confirmed_height: 0,
+        last_confirmed_height: 10,
+        last_paid_height: 0,
+        collateral_value: 1_000 * 100_000_000,
+        operator_pubkey: operator,
+        collateral_pubkey: Some(collateral_key),
+        p2sh_script: p2sh_key,
+        delegates: None,
+        ip: String::new(),
+    };
+    batch.put(
+        Column::Fluxnode,
+        outpoint_key_bytes(&record.collateral).as_bytes().to_vec(),
+        record.encode(),
+    );
+    (record, batch)
+}
+
+fn outpoints(records: &[FluxnodeRecord]) -> Vec<OutPoint> {
+    let mut outpoints: Vec<OutPoint> = records
+        .iter()
+        .map(|record| record.collateral.clone())
+        .collect();
+    outpoints.sort_by_key(|outpoint| outpoint.index);
+    outpoints
+}
+
+#[test]
+fn fluxnode_lookups_follow_commits_and_rebuild() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+    let params = chain_params(Network::Regtest);
+
+    let pubkey_a = [0x03; 33];
+    let redeem = [0x52, 0x21, 0xaa, 0x53, 0xae];
+    let (node_a, batch) = record(1, 1, &pubkey_a, None);
+    chainstate.commit_batch(batch).expect("commit a");
+    let (node_b, batch) = record(2, 2, &[0x02; 33], Some(&redeem));
+    chainstate.commit_batch(batch).expect("commit b");
+    let (node_c, batch) = record(3, 1, &pubkey_a, None);
+    chainstate.commit_batch(batch).expect("commit c");
+
+    let script_a = p2pkh_script(&hash160(&pubkey_a));
+    let script_b = p2sh_script(&hash160(&redeem));
+
+    assert_eq!(
+        chainstate
+            .fluxnode_by_collateral(&node_b.collateral)
+            .expect("by collateral"),
+        Some(node_b.clone())
+    );
+    assert_eq!(
+        outpoints(&chainstate.fluxnodes_by_tier(1).expect("tier 1")),
+        vec![node_a.collateral.clone(), node_c.collateral.clone()]
+    );
+    assert_eq!(
+        outpoints(
+            &chainstate
+                .fluxnode_by_payout_script(&script_a, &params)
+                .expect("payout a")
+        ),
+        vec![node_a.collateral.clone(), node_c.collateral.clone()]
+    );
+    assert_eq!(
+        outpoints(
+            &chainstate
+                .fluxnode_by_payout_script(&script_b, &params)
+                .expect("payout b")
+        ),
+        vec![node_b.collateral.clone()]
+    );
+
+    let mut batch = WriteBatch::new();
+    batch.delete(
+        Column::Fluxnode,
+        outpoint_key_bytes(&node_a.collateral).as_bytes().to_vec(),
+    );
+    chainstate.commit_batch(batch).expect("remove a");
+    assert_eq!(
+        outpoints(&chainstate.fluxnodes_by_tier(1).expect("tier 1")),
+        vec![node_c.collateral.clone()]
+    );
+    assert_eq!(
+        store
+            .scan_prefix(Column::FluxnodeIndex, &[])
+            .expect("scan")
+            .len(),
+        2
+    );
+
+    assert!(!chainstate.fluxnode_index_ready().expect("ready"));
+    assert_eq!(chainstate.rebuild_fluxnode_index().expect("rebuild"), 2);
+    assert!(chainstate.fluxnode_index_ready().expect("ready"));
+    assert_eq!(
+        outpoints(&chainstate.fluxnodes_by_tier(2).expect("tier 2")),
+        vec![node_b.collateral]
+    );
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{connect, make_header, regtest_params};
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::ChainState;
+use fluxd_consensus::params::Checkpoint;
+use fluxd_consensus::Hash256;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+fn is_failed(chainstate: &ChainState<MemoryStore>, hash: &Hash256) -> bool {
+    chainstate
+        .header_entry(hash)
+        .expect("header entry")
+        .expect("known header")
+        .is_failed()
+}
+
+#[test]
+fn invalidate_block_reorgs_to_fork_and_reconsider_clears_flag() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+    let mut params = regtest_params();
+    let now = 1_700_000_000u32;
+    let bits = target_to_compact(&params.consensus.pow_limit);
+
+    let header0 = make_header([0u8; 32], now, bits, 0);
+    let hash0 = header0.hash();
+    params.consensus.hash_genesis_block = hash0;
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: hash0,
+    }];
+    let header1 = make_header(hash0, now + 1, bits, 1);
+    let hash1 = header1.hash();
+    let header2a = make_header(hash1, now + 2, bits, 0xa2);
+    let hash2a = header2a.hash();
+    let header3a = make_header(hash2a, now + 3, bits, 0xa3);
+    let hash3a = header3a.hash();
+    let header2b = make_header(hash1, now + 2, bits, 0xb2);
+    let hash2b = header2b.hash();
+    let header3b = make_header(hash2b, now + 3, bits, 0xb3);
+    let hash3b = header3b.hash();
+
+    let mut header_batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(
+            &[
+                header0.clone(),
+                header1.clone(),
+                header2a.clone(),
+                header3a.clone(),
+                header2b.clone(),
+                header3b.clone(),
+            ],
+            &params.consensus,
+            &mut header_batch,
+            false,
+        )
+        .expect("insert headers");
+    chainstate
+        .commit_batch(header_batch)
+        .expect("commit headers");
+
+    connect(&chainstate, &params, &header0, 0);
+    connect(&chainstate, &params, &header1, 1);
+    connect(&chainstate, &params, &header2a, 2);
+    connect(&chainstate, &params, &header3a, 3);
+    let tip = chainstate.best_block().expect("best block").expect("tip");
+    assert_eq!(tip.hash, hash3a);
+
+    chainstate.invalidate_block(&hash2a).expect("invalidate");
+
+    let tip = chainstate.best_block().expect("best block").expect("tip");
+    assert_eq!(tip.hash, hash1);
+    assert!(is_failed(&chainstate, &hash2a));
+    assert!(is_failed(&chainstate, &hash3a));
+    assert!(!is_failed(&chainstate, &hash2b));
+    let best_header = chainstate.best_header().expect("best header").expect("tip");
+    assert_eq!(best_header.hash, hash3b);
+
+    let mut header_batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(
+            std::slice::from_ref(&header3a),
+            &params.consensus,
+            &mut header_batch,
+            false,
+        )
+        .expect("reinsert invalid header");
+    chainstate
+        .commit_batch(header_batch)
+        .expect("commit headers");
+    let best_header = chainstate.best_header().expect("best header").expect("tip");
+    assert_eq!(best_header.hash, hash3b);
+
+    connect(&chainstate, &params, &header2b, 2);
+    connect(&chainstate, &params, &header3b, 3);
+    let tip = chainstate.best_block().expect("best block").expect("tip");
+    assert_eq!(tip.hash, hash3b);
+
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let reopened = ChainState::new(Arc::clone(&store), blocks, undo);
+    assert!(is_failed(&reopened, &hash2a));
+    assert!(is_failed(&reopened, &hash3a));
+
+    reopened.reconsider_block(&hash3a).expect("reconsider");
+    assert!(!is_failed(&reopened, &hash2a));
+    assert!(!is_failed(&reopened, &hash3a));
+    let tip = reopened.best_block().expect("best block").expect("tip");
+    assert_eq!(tip.hash, hash3b);
+    let best_header = reopened.best_header().expect("best header").expect("tip");
+    assert_eq!(best_header.hash, hash3b);
+
+    let header4a = make_header(hash3a, now + 4, bits, 0xa4);
+    let hash4a = header4a.hash();
+    let mut header_batch = WriteBatch::new();
+    reopened
+        .insert_headers_batch_with_pow(
+            std::slice::from_ref(&header4a),
+            &params.consensus,
+            &mut header_batch,
+            false,
+        )
+        .expect("insert header");
+    reopened.commit_batch(header_batch).expect("commit headers");
+    reopened.invalidate_block(&hash2a).expect("invalidate");
+    assert!(is_failed(&reopened, &hash4a));
+    let best_header = reopened.best_header().expect("best header").expect("tip");
+    assert_eq!(best_header.hash, hash3b);
+
+    assert!(reopened.invalidate_block(&hash0).is_err());
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{connect, make_header, regtest_params};
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::{ChainState, ChainStateError, FORK_BEFORE_CHECKPOINT, FORK_TOO_DEEP};
+use fluxd_consensus::constants::MAX_REORG_LENGTH;
+use fluxd_consensus::params::{Checkpoint, ConsensusParams};
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::BlockHeader;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+const TIP_HEIGHT: u32 = 10;
+
+fn insert_header(
+    chainstate: &ChainState<MemoryStore>,
+    params: &ConsensusParams,
+    header: &BlockHeader,
+) -> Result<(), ChainStateError> {
+    let mut batch = WriteBatch::new();
+    chainstate.insert_headers_batch_with_pow(
+        std::slice::from_ref(header),
+        params,
+        &mut batch,
+        false,
+    )?;
+    chainstate.commit_batch(batch)
+}
+
+fn is_invalid_header(result: Result<(), ChainStateError>, expected: &str) -> bool {
+    matches!(result, Err(ChainStateError::InvalidHeader(message)) if message == expected)
+}
+
+#[test]
+fn fork_past_max_reorg_depth_is_rejected() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::new(MemoryStore::new()), blocks, undo);
+
+    let mut params = regtest_params();
+
+    let now = 1_700_000_000u32;
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let mut headers = Vec::new();
+    let mut prev = [0u8; 32];
+    for height in 0..=TIP_HEIGHT {
+        let header = make_header(prev, now + height, bits, height as u8);
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+    let mut batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+        .expect("insert headers");
+    chainstate.commit_batch(batch).expect("commit headers");
+
+    for (height, header) in headers.iter().enumerate() {
+        connect(&chainstate, &params, header, height as u32);
+    }
+
+    let tip = TIP_HEIGHT as i32;
+    assert_eq!(chainstate.reorg_depth_limit(tip), MAX_REORG_LENGTH);
+    chainstate.set_max_reorg_depth(10_000);
+    assert_eq!(chainstate.reorg_depth_limit(tip), MAX_REORG_LENGTH);
+
+    // Replacing 3 blocks is allowed, 4 is not.
+    chainstate.set_max_reorg_depth(3);
+    let fork_from = |height: u32, tag: u8| {
+        make_header(headers[height as usize].hash(), now + height + 1, bits, tag)
+    };
+    let too_deep = fork_from(TIP_HEIGHT - 4, 0xa0);
+    assert!(is_invalid_header(
+        insert_header(&chainstate, &params.consensus, &too_deep),
+        FORK_TOO_DEEP
+    ));
+    assert!(chainstate
+        .header_entry(&too_deep.hash())
+        .expect("header entry")
+        .is_none());
+    assert!(chainstate
+        .check_fork_point(&params.consensus, tip, tip - 4)
+        .is_err());
+    let allowed = fork_from(TIP_HEIGHT - 3, 0xb0);
+    insert_header(&chainstate, &params.consensus, &allowed).expect("shallow fork");
+
+    // Without the setting only the consensus limit applies.
+    chainstate.set_max_reorg_depth(0);
+    insert_header(&chainstate, &params.consensus, &too_deep).expect("consensus allows fork");
+
+    // The last checkpoint is a hard floor whatever the configured depth.
+    params.consensus.checkpoints.push(Checkpoint {
+        height: 8,
+        hash: headers[8].hash(),
+    });
+    let below_checkpoint = fork_from(6, 0xc0);
+    assert!(is_invalid_header(
+        insert_header(&chainstate, &params.consensus, &below_checkpoint),
+        FORK_BEFORE_CHECKPOINT
+    ));
+    assert!(chainstate
+        .check_fork_point(&params.consensus, tip, 8)
+        .is_ok());
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::{ChainState, MTP_WINDOW_SIZE};
+use fluxd_consensus::params::{chain_params, Checkpoint, Network};
+use fluxd_consensus::upgrades::UpgradeIndex;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{BlockHeader, CURRENT_VERSION};
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+const NOW: u32 = 1_700_000_000;
+
+// Non-monotonic timestamps, each still above the median of the headers before it.
+const TIME_OFFSETS: [u32; 13] = [0, 10, 20, 15, 30, 25, 40, 35, 50, 45, 60, 55, 70];
+
+fn make_header(prev_block: [u8; 32], time: u32, bits: u32, nonce_tag: u8) -> BlockHeader {
+    BlockHeader {
+        version: CURRENT_VERSION,
+        prev_block,
+        merkle_root: [0u8; 32],
+        final_sapling_root: [0u8; 32],
+        time,
+        bits,
+        nonce: [nonce_tag; 32],
+        solution: Vec::new(),
+        nodes_collateral: OutPoint::null(),
+        block_sig: Vec::new(),
+    }
+}
+
+#[test]
+fn median_time_past_walks_back_the_header_chain() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(store, blocks, undo);
+
+    let mut params = chain_params(Network::Regtest);
+    params.consensus.digishield_averaging_window = 10_000;
+    params.consensus.upgrades[UpgradeIndex::Lwma.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Equi144_5.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Kamiooka.as_usize()].activation_height = i32::MAX;
+    let bits = target_to_compact(&params.consensus.pow_limit);
+
+    let mut headers = Vec::new();
+    let mut prev = [0u8; 32];
+    for (tag, offset) in TIME_OFFSETS.iter().enumerate() {
+        let header = make_header(prev, NOW + offset, bits, tag as u8);
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+    for header in &headers {
+        let mut batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                std::slice::from_ref(header),
+                &params.consensus,
+                &mut batch,
+                false,
+            )
+            .expect("insert header");
+        chainstate.commit_batch(batch).expect("commit header");
+    }
+
+    let tip = headers[12].hash();
+    // Heights 2..=12 sorted: 15 20 25 30 35 [40] 45 50 55 60 70.
+    assert_eq!(
+        chainstate
+            .median_time_past(&tip, MTP_WINDOW_SIZE)
+            .expect("mtp"),
+        NOW + 40
+    );
+    // Heights 10..=12: 60 55 70.
+    assert_eq!(chainstate.median_time_past(&tip, 3).expect("mtp"), NOW + 60);
+    // Near genesis the window is truncated: 0 10 [15] 20 (upper median).
+    assert_eq!(
+        chainstate
+            .median_time_past(&headers[3].hash(), MTP_WINDOW_SIZE)
+            .expect("mtp"),
+        NOW + 15
+    );
+    assert_eq!(
+        chainstate
+            .median_time_past(&headers[0].hash(), MTP_WINDOW_SIZE)
+            .expect("mtp"),
+        NOW
+    );
+    assert!(chainstate
+        .median_time_past(&[0x5a; 32], MTP_WINDOW_SIZE)
+        .is_err());
+}
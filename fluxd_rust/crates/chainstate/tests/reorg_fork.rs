@@ -36,6 +36,7 @@ fn make_tx(vin: Vec<TxIn>, vout: Vec<TxOut>) -> Transaction {
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     }
 }
@@ -201,7 +202,7 @@ fn reorg_reverts_utxo_spent_and_address_indexes() {
 
     assert_eq!(
         chainstate
-            .utxo_entry(&fund_outpoint)
+            .utxo_entry(&fund_outpoint.txid(), fund_outpoint.index)
             .expect("utxo")
             .is_some(),
         true
@@ -260,7 +261,7 @@ fn reorg_reverts_utxo_spent_and_address_indexes() {
 
     assert_eq!(
         chainstate
-            .utxo_entry(&fund_outpoint)
+            .utxo_entry(&fund_outpoint.txid(), fund_outpoint.index)
             .expect("utxo")
             .is_some(),
         false
@@ -319,7 +320,7 @@ fn reorg_reverts_utxo_spent_and_address_indexes() {
 
     assert_eq!(
         chainstate
-            .utxo_entry(&fund_outpoint)
+            .utxo_entry(&fund_outpoint.txid(), fund_outpoint.index)
             .expect("utxo")
             .is_some(),
         true
@@ -388,7 +389,7 @@ fn reorg_reverts_utxo_spent_and_address_indexes() {
 
     assert_eq!(
         chainstate
-            .utxo_entry(&fund_outpoint)
+            .utxo_entry(&fund_outpoint.txid(), fund_outpoint.index)
             .expect("utxo")
             .is_some(),
         false
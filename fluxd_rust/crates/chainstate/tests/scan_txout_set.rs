@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::ChainState;
+use fluxd_chainstate::utxo::ScriptMatcher;
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::{chain_params, Checkpoint, Network};
+use fluxd_consensus::rewards::block_subsidy;
+use fluxd_consensus::upgrades::UpgradeIndex;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
+use fluxd_primitives::hash::hash160;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+const TIP_HEIGHT: u32 = 6;
+const PUBKEY: [u8; 33] = [0x02; 33];
+
+fn p2pkh_script(pubkey: &[u8]) -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, 0x14];
+    script.extend_from_slice(&hash160(pubkey));
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+fn coinbase_tx(height: u32, value: i64, script_pubkey: Vec<u8>) -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version: 1,
+        version_group_id: 0,
+        vin: vec![TxIn {
+            prevout: OutPoint::null(),
+            script_sig: height.to_le_bytes().to_vec(),
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value,
+            script_pubkey,
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+#[test]
+fn scan_collects_outputs_paying_a_p2pkh() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::new(MemoryStore::new()), blocks, undo);
+
+    let mut params = chain_params(Network::Regtest);
+    params.funding.exchange_height = i64::MAX;
+    params.funding.foundation_height = i64::MAX;
+    params.swap_pool.start_height = i64::MAX;
+    params.fluxnode.start_payments_height = i64::MAX;
+    params.consensus.digishield_averaging_window = 10_000;
+    params.consensus.upgrades[UpgradeIndex::Lwma.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Equi144_5.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Kamiooka.as_usize()].activation_height = i32::MAX;
+
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let mut headers = Vec::new();
+    let mut prev = [0u8; 32];
+    for height in 0..=TIP_HEIGHT {
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: prev,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: 1_700_000_000 + height,
+            bits,
+            nonce: [height as u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+    let mut batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+        .expect("insert headers");
+    chainstate.commit_batch(batch).expect("commit headers");
+
+    // Odd heights pay the watched key; even heights pay an anyone-can-spend script.
+    let flags = ValidationFlags::default();
+    let mut expected = Vec::new();
+    for (height, header) in headers.into_iter().enumerate() {
+        let height = height as i32;
+        let subsidy = block_subsidy(height, &params.consensus);
+        let script_pubkey = if height % 2 == 1 {
+            p2pkh_script(&PUBKEY)
+        } else {
+            vec![0x51]
+        };
+        let coinbase = coinbase_tx(height as u32, subsidy, script_pubkey);
+        if height % 2 == 1 {
+            let outpoint = OutPoint {
+                hash: coinbase.txid().expect("txid"),
+                index: 0,
+            };
+            expected.push((outpoint, subsidy));
+        }
+        let block = Block {
+            header,
+            transactions: vec![coinbase],
+        };
+        let batch = chainstate
+            .connect_block(
+                &block, height, &params, &flags, true, None, None, None, None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+    }
+
+    let result = chainstate
+        .scan_txout_set(&[
+            ScriptMatcher::P2sh(vec![0x52]),
+            ScriptMatcher::P2pkh(PUBKEY.to_vec()),
+        ])
+        .expect("scan");
+    let tip = chainstate.best_block().expect("best block").expect("tip");
+    assert_eq!(result.height, TIP_HEIGHT as i32);
+    assert_eq!(result.best_block, tip.hash);
+    assert_eq!(result.txouts, u64::from(TIP_HEIGHT) + 1);
+
+    let mut found: Vec<(OutPoint, i64)> = result
+        .unspents
+        .iter()
+        .map(|output| {
+            assert_eq!(output.matcher, 1);
+            assert_eq!(output.entry.script_pubkey, p2pkh_script(&PUBKEY));
+            (output.outpoint.clone(), output.entry.value)
+        })
+        .collect();
+    found.sort_by_key(|(outpoint, _)| (outpoint.hash, outpoint.index));
+    expected.sort_by_key(|(outpoint, _)| (outpoint.hash, outpoint.index));
+    assert_eq!(found, expected);
+    assert_eq!(
+        result.total_amount,
+        expected.iter().map(|(_, value)| value).sum::<i64>()
+    );
+
+    let exact = chainstate
+        .scan_txout_set(&[ScriptMatcher::Script(vec![0x51])])
+        .expect("scan");
+    assert_eq!(exact.unspents.len(), (TIP_HEIGHT as usize) / 2 + 1);
+
+    let none = chainstate
+        .scan_txout_set(&[ScriptMatcher::P2pkh(vec![0x03; 33])])
+        .expect("scan");
+    assert!(none.unspents.is_empty());
+    assert_eq!(none.total_amount, 0);
+}
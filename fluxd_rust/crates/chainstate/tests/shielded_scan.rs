@@ -0,0 +1,227 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::shielded_scan::{
+    ShieldedNoteLocation, ShieldedScanOptions, ShieldedScanProgress, ShieldedViewingKeys,
+};
+use fluxd_chainstate::state::{ChainState, ChainStateError};
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::{chain_params, Checkpoint, Network};
+use fluxd_consensus::rewards::block_subsidy;
+use fluxd_consensus::upgrades::UpgradeIndex;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{
+    OutputDescription, Transaction, TxIn, TxOut, GROTH_PROOF_SIZE, SAPLING_OUT_CIPHERTEXT_SIZE,
+    SAPLING_VERSION_GROUP_ID,
+};
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+use rand::RngCore;
+use sapling_crypto::note::Rseed;
+use sapling_crypto::note_encryption::{sapling_note_encryption, SaplingDomain};
+use sapling_crypto::value::NoteValue;
+use sapling_crypto::zip32::ExtendedSpendingKey;
+use zcash_note_encryption::Domain;
+
+const TIP_HEIGHT: u32 = 6;
+const SHIELDED_HEIGHT: u32 = 4;
+const NOTE_VALUE: u64 = 12_345_678;
+
+fn coinbase_tx(height: u32, value: i64, shielded_outputs: Vec<OutputDescription>) -> Transaction {
+    let sapling = !shielded_outputs.is_empty();
+    Transaction {
+        f_overwintered: sapling,
+        version: if sapling { 4 } else { 1 },
+        version_group_id: if sapling { SAPLING_VERSION_GROUP_ID } else { 0 },
+        vin: vec![TxIn {
+            prevout: OutPoint::null(),
+            script_sig: height.to_le_bytes().to_vec(),
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs,
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+#[test]
+fn scan_shielded_finds_output_for_viewing_key() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::new(MemoryStore::new()), blocks, undo);
+
+    let mut params = chain_params(Network::Regtest);
+    params.funding.exchange_height = i64::MAX;
+    params.funding.foundation_height = i64::MAX;
+    params.swap_pool.start_height = i64::MAX;
+    params.fluxnode.start_payments_height = i64::MAX;
+    params.consensus.digishield_averaging_window = 10_000;
+    params.consensus.upgrades[UpgradeIndex::Lwma.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Equi144_5.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Kamiooka.as_usize()].activation_height = i32::MAX;
+
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let mut headers = Vec::new();
+    let mut prev = [0u8; 32];
+    for height in 0..=TIP_HEIGHT {
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: prev,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: 1_700_000_000 + height,
+            bits,
+            nonce: [height as u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+    let mut batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+        .expect("insert headers");
+    chainstate.commit_batch(batch).expect("commit headers");
+
+    let watched = ExtendedSpendingKey::master(&[7u8; 32]).to_diversifiable_full_viewing_key();
+    let other = ExtendedSpendingKey::master(&[9u8; 32]).to_diversifiable_full_viewing_key();
+    let (_, to) = watched.default_address();
+    let mut rng = rand::rngs::OsRng;
+    let mut rseed = [0u8; 32];
+    rng.fill_bytes(&mut rseed);
+    let note = to.create_note(NoteValue::from_raw(NOTE_VALUE), Rseed::AfterZip212(rseed));
+    let mut memo = [0u8; 512];
+    memo[..5].copy_from_slice(b"audit");
+    let enc = sapling_note_encryption(None, note.clone(), memo, &mut rng);
+    let output = OutputDescription {
+        cv: [0u8; 32],
+        cm: note.cmu().to_bytes(),
+        ephemeral_key: <SaplingDomain as Domain>::epk_bytes(enc.epk()).0,
+        enc_ciphertext: enc.encrypt_note_plaintext(),
+        out_ciphertext: [0u8; SAPLING_OUT_CIPHERTEXT_SIZE],
+        zkproof: [0u8; GROTH_PROOF_SIZE],
+    };
+
+    let flags = ValidationFlags::default();
+    let mut shielded_txid = None;
+    for (height, header) in headers.into_iter().enumerate() {
+        let height = height as i32;
+        let subsidy = block_subsidy(height, &params.consensus);
+        let outputs = if height as u32 == SHIELDED_HEIGHT {
+            vec![output.clone()]
+        } else {
+            Vec::new()
+        };
+        let coinbase = coinbase_tx(height as u32, subsidy, outputs);
+        if height as u32 == SHIELDED_HEIGHT {
+            shielded_txid = Some(coinbase.txid().expect("txid"));
+        }
+        let block = Block {
+            header,
+            transactions: vec![coinbase],
+        };
+        let batch = chainstate
+            .connect_block(
+                &block, height, &params, &flags, true, None, None, None, None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+    }
+
+    let keys = ShieldedViewingKeys::new(&[other.fvk().vk.ivk(), watched.fvk().vk.ivk()], &[]);
+    let progress_seen = Mutex::new(Vec::new());
+    let progress = |update: ShieldedScanProgress| progress_seen.lock().unwrap().push(update);
+    let options = ShieldedScanOptions {
+        workers: 2,
+        cancel: None,
+        progress: Some(&progress),
+    };
+    let receipts = chainstate
+        .scan_shielded(0, TIP_HEIGHT as i32, &keys, &options)
+        .expect("scan");
+    assert_eq!(receipts.len(), 1);
+    let receipt = &receipts[0];
+    assert_eq!(receipt.height, SHIELDED_HEIGHT as i32);
+    assert_eq!(Some(receipt.txid), shielded_txid);
+    assert_eq!(
+        receipt.location,
+        ShieldedNoteLocation::Sapling { output: 0 }
+    );
+    assert_eq!(receipt.key_index, 1);
+    assert_eq!(receipt.value, NOTE_VALUE);
+    assert_eq!(&receipt.memo[..5], b"audit");
+    assert_eq!(
+        progress_seen.lock().unwrap().last().copied(),
+        Some(ShieldedScanProgress {
+            height: TIP_HEIGHT as i32,
+            end_height: TIP_HEIGHT as i32,
+            receipts: 1,
+        })
+    );
+
+    let before = chainstate
+        .scan_shielded(
+            0,
+            SHIELDED_HEIGHT as i32 - 1,
+            &keys,
+            &ShieldedScanOptions::default(),
+        )
+        .expect("scan before");
+    assert!(before.is_empty());
+
+    let unrelated = ShieldedViewingKeys::new(&[other.fvk().vk.ivk()], &[]);
+    let none = chainstate
+        .scan_shielded(
+            0,
+            TIP_HEIGHT as i32,
+            &unrelated,
+            &ShieldedScanOptions::default(),
+        )
+        .expect("scan unrelated");
+    assert!(none.is_empty());
+
+    let cancel = AtomicBool::new(true);
+    let cancelled = chainstate.scan_shielded(
+        0,
+        TIP_HEIGHT as i32,
+        &keys,
+        &ShieldedScanOptions {
+            cancel: Some(&cancel),
+            ..ShieldedScanOptions::default()
+        },
+    );
+    assert!(matches!(cancelled, Err(ChainStateError::Interrupted)));
+
+    assert!(chainstate
+        .scan_shielded(
+            0,
+            TIP_HEIGHT as i32 + 1,
+            &keys,
+            &ShieldedScanOptions::default()
+        )
+        .is_err());
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::ChainState;
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::{chain_params, Checkpoint, Network};
+use fluxd_consensus::rewards::block_subsidy;
+use fluxd_consensus::upgrades::UpgradeIndex;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::WriteBatch;
+
+const TIP_HEIGHT: u32 = 25;
+
+fn coinbase_tx(height: u32, value: i64) -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version: 1,
+        version_group_id: 0,
+        vin: vec![TxIn {
+            prevout: OutPoint::null(),
+            script_sig: height.to_le_bytes().to_vec(),
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+#[test]
+fn txout_set_total_matches_mined_subsidy() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::new(MemoryStore::new()), blocks, undo);
+
+    let mut params = chain_params(Network::Regtest);
+    params.funding.exchange_height = i64::MAX;
+    params.funding.foundation_height = i64::MAX;
+    params.swap_pool.start_height = i64::MAX;
+    params.fluxnode.start_payments_height = i64::MAX;
+    params.consensus.digishield_averaging_window = 10_000;
+    params.consensus.upgrades[UpgradeIndex::Lwma.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Equi144_5.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height = i32::MAX;
+    params.consensus.upgrades[UpgradeIndex::Kamiooka.as_usize()].activation_height = i32::MAX;
+
+    let empty = chainstate.txout_set_info(0).expect("empty set info");
+    assert_eq!((empty.txouts, empty.total_amount), (0, 0));
+
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let mut headers = Vec::new();
+    let mut prev = [0u8; 32];
+    for height in 0..=TIP_HEIGHT {
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: prev,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time: 1_700_000_000 + height,
+            bits,
+            nonce: [height as u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+    let mut batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+        .expect("insert headers");
+    chainstate.commit_batch(batch).expect("commit headers");
+
+    let flags = ValidationFlags::default();
+    let mut expected_supply = 0i64;
+    let mut before_tip = None;
+    for (height, header) in headers.into_iter().enumerate() {
+        let height = height as i32;
+        let subsidy = block_subsidy(height, &params.consensus);
+        expected_supply += subsidy;
+        let block = Block {
+            header,
+            transactions: vec![coinbase_tx(height as u32, subsidy)],
+        };
+        if height == TIP_HEIGHT as i32 {
+            before_tip = Some(chainstate.txout_set_info(0).expect("set info"));
+        }
+        let batch = chainstate
+            .connect_block(
+                &block, height, &params, &flags, true, None, None, None, None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+    }
+
+    let info = chainstate.txout_set_info(4_096).expect("set info");
+    let tip = chainstate.best_block().expect("best block").expect("tip");
+    assert_eq!(info.height, TIP_HEIGHT as i32);
+    assert_eq!(info.best_block, tip.hash);
+    assert_eq!(info.txouts, u64::from(TIP_HEIGHT) + 1);
+    assert_eq!(info.transactions, u64::from(TIP_HEIGHT) + 1);
+    assert_eq!(info.total_amount, expected_supply);
+    assert!(info.total_amount > 0);
+    assert_eq!(info.disk_size, 4_096);
+
+    // The set hash follows the UTXO set: undoing the tip brings back the previous value.
+    let before_tip = before_tip.expect("pre-tip info");
+    assert_ne!(info.muhash, before_tip.muhash);
+    let batch = chainstate.disconnect_block(&tip.hash).expect("disconnect");
+    chainstate.commit_batch(batch).expect("commit disconnect");
+    let after = chainstate.txout_set_info(0).expect("set info");
+    assert_eq!(after.muhash, before_tip.muhash);
+    assert_eq!(after.hash_serialized, before_tip.hash_serialized);
+    assert_eq!(after.total_amount, before_tip.total_amount);
+}
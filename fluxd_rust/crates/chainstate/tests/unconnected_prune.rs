@@ -0,0 +1,125 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{connect, make_header, regtest_params};
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::state::ChainState;
+use fluxd_consensus::params::Checkpoint;
+use fluxd_consensus::Hash256;
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, WriteBatch};
+
+fn unconnected_count(chainstate: &ChainState<MemoryStore>, hashes: &[Hash256]) -> usize {
+    hashes
+        .iter()
+        .filter(|hash| {
+            chainstate
+                .unconnected_block_bytes(hash)
+                .expect("unconnected bytes")
+                .is_some()
+        })
+        .count()
+}
+
+#[test]
+fn prune_unconnected_drops_stale_connected_and_excess_entries() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let blocks = FlatFileStore::new(dir.path(), 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir.path(), "undo", 10_000_000).expect("flatfiles");
+    let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+    let mut params = regtest_params();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time")
+        .as_secs() as u32;
+    let ten_days_ago = now - 10 * 24 * 60 * 60;
+    let bits = target_to_compact(&params.consensus.pow_limit);
+
+    let header0 = make_header([0u8; 32], ten_days_ago, bits, 0);
+    let hash0 = header0.hash();
+    params.consensus.hash_genesis_block = hash0;
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: hash0,
+    }];
+    let header1 = make_header(hash0, ten_days_ago + 1, bits, 1);
+    let hash1 = header1.hash();
+    // Ages come from park time, not header time: 2a carries an old timestamp but was parked
+    // recently, while 1 has been parked for days.
+    let header2a = make_header(hash1, ten_days_ago + 2, bits, 0xa2);
+    let hash2a = header2a.hash();
+    let header2b = make_header(hash1, now - 1, bits, 0xb2);
+    let hash2b = header2b.hash();
+    let unknown = [0x77u8; 32];
+
+    let mut header_batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(
+            &[
+                header0.clone(),
+                header1.clone(),
+                header2a.clone(),
+                header2b.clone(),
+            ],
+            &params.consensus,
+            &mut header_batch,
+            false,
+        )
+        .expect("insert headers");
+    chainstate
+        .commit_batch(header_batch)
+        .expect("commit headers");
+    connect(&chainstate, &params, &header0, 0);
+
+    let parked = [hash0, hash1, hash2a, hash2b, unknown];
+    let legacy = [0x88u8; 32];
+    let mut batch = WriteBatch::new();
+    chainstate.store_unconnected_block_bytes_at(&mut batch, &hash0, &[1u8], i64::from(now));
+    chainstate.store_unconnected_block_bytes_at(
+        &mut batch,
+        &hash1,
+        &[1u8],
+        i64::from(ten_days_ago),
+    );
+    chainstate.store_unconnected_block_bytes_at(&mut batch, &hash2a, &[1u8], i64::from(now - 2));
+    chainstate.store_unconnected_block_bytes(&mut batch, &hash2b, &[1u8]);
+    chainstate.store_unconnected_block_bytes_at(&mut batch, &unknown, &[1u8], i64::from(now));
+    batch.put(Column::UnconnectedBlock, legacy, vec![1u8]);
+    chainstate.commit_batch(batch).expect("commit unconnected");
+    assert_eq!(unconnected_count(&chainstate, &parked), 5);
+    assert_eq!(
+        chainstate
+            .unconnected_block_bytes(&hash2b)
+            .expect("unconnected bytes"),
+        Some(vec![1u8])
+    );
+
+    let day = 24 * 60 * 60;
+    let removed = chainstate
+        .prune_unconnected(day, 16)
+        .expect("prune unconnected");
+    assert_eq!(removed, 4);
+    for hash in [hash0, hash1, unknown, legacy] {
+        assert!(chainstate
+            .unconnected_block_bytes(&hash)
+            .expect("unconnected bytes")
+            .is_none());
+    }
+    assert_eq!(unconnected_count(&chainstate, &parked), 2);
+
+    assert_eq!(chainstate.prune_unconnected(day, 16).expect("prune"), 0);
+    assert_eq!(chainstate.prune_unconnected(day, 1).expect("prune"), 1);
+    assert!(chainstate
+        .unconnected_block_bytes(&hash2a)
+        .expect("unconnected bytes")
+        .is_none());
+    assert!(chainstate
+        .unconnected_block_bytes(&hash2b)
+        .expect("unconnected bytes")
+        .is_some());
+}
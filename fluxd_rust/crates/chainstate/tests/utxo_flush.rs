@@ -0,0 +1,219 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use common::{coinbase_tx, make_header, regtest_params};
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::metrics::ConnectMetrics;
+use fluxd_chainstate::state::{ChainState, UtxoFlushPolicy};
+use fluxd_chainstate::utxo::outpoint_key_bytes;
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::{ChainParams, Checkpoint};
+use fluxd_pow::difficulty::target_to_compact;
+use fluxd_primitives::block::{Block, BlockHeader};
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, KeyValueStore, WriteBatch};
+
+const TIP_HEIGHT: u32 = 40;
+
+fn coinbase_outpoint(height: u32) -> OutPoint {
+    OutPoint {
+        hash: coinbase_tx(height).txid().expect("coinbase txid"),
+        index: 0,
+    }
+}
+
+fn open_chainstate(store: &Arc<MemoryStore>, dir: &Path) -> ChainState<MemoryStore> {
+    let blocks = FlatFileStore::new(dir, 10_000_000).expect("flatfiles");
+    let undo = FlatFileStore::new_with_prefix(dir, "undo", 10_000_000).expect("flatfiles");
+    ChainState::new(Arc::clone(store), blocks, undo)
+}
+
+/// Regtest params with a fresh genesis, plus the blocks of a coinbase-only chain.
+fn build_chain(chainstate: &ChainState<MemoryStore>) -> (ChainParams, Vec<Block>) {
+    let mut params = regtest_params();
+
+    let now = 1_700_000_000u32;
+    let bits = target_to_compact(&params.consensus.pow_limit);
+    let mut headers: Vec<BlockHeader> = Vec::new();
+    let mut prev = [0u8; 32];
+    for height in 0..=TIP_HEIGHT {
+        let header = make_header(prev, now + height, bits, height as u8);
+        prev = header.hash();
+        headers.push(header);
+    }
+    params.consensus.hash_genesis_block = headers[0].hash();
+    params.consensus.checkpoints = vec![Checkpoint {
+        height: 0,
+        hash: headers[0].hash(),
+    }];
+
+    let mut batch = WriteBatch::new();
+    chainstate
+        .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+        .expect("insert headers");
+    chainstate.commit_batch(batch).expect("commit headers");
+
+    let blocks = headers
+        .into_iter()
+        .enumerate()
+        .map(|(height, header)| Block {
+            header,
+            transactions: vec![coinbase_tx(height as u32)],
+        })
+        .collect();
+    (params, blocks)
+}
+
+fn connect(chainstate: &ChainState<MemoryStore>, params: &ChainParams, blocks: &[Block]) {
+    let flags = ValidationFlags::default();
+    let start = chainstate
+        .best_block()
+        .expect("best block")
+        .map_or(0, |tip| tip.height + 1);
+    for (height, block) in blocks.iter().enumerate().skip(start as usize) {
+        let batch = chainstate
+            .connect_block(
+                block,
+                height as i32,
+                params,
+                &flags,
+                true,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+    }
+}
+
+fn stored(store: &MemoryStore, outpoint: &OutPoint) -> bool {
+    store
+        .get(Column::Utxo, outpoint_key_bytes(outpoint).as_bytes())
+        .expect("store get")
+        .is_some()
+}
+
+#[test]
+fn deferred_utxo_writes_are_visible_before_flush() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let chainstate = open_chainstate(&store, dir.path());
+    chainstate
+        .set_utxo_flush_policy(UtxoFlushPolicy {
+            max_dirty_entries: 1_000,
+            max_dirty_bytes: 0,
+        })
+        .expect("policy");
+    let (params, blocks) = build_chain(&chainstate);
+    connect(&chainstate, &params, &blocks);
+
+    // Genesis is connected on top of nothing, so its outputs are written straight through.
+    let outpoint = coinbase_outpoint(TIP_HEIGHT);
+    assert!(chainstate.utxo_dirty_entries() >= TIP_HEIGHT as usize);
+    assert!(!stored(&store, &outpoint));
+    assert!(chainstate.utxo_exists(&outpoint).expect("utxo exists"));
+    assert!(chainstate
+        .utxo_entry(&outpoint.txid(), outpoint.index)
+        .expect("utxo")
+        .is_some());
+
+    let flushed = chainstate.flush_utxos().expect("flush");
+    assert_eq!(flushed, TIP_HEIGHT as usize);
+    assert_eq!(chainstate.utxo_dirty_entries(), 0);
+    assert_eq!(chainstate.utxo_dirty_bytes(), 0);
+    assert!(stored(&store, &outpoint));
+    assert_eq!(chainstate.flush_utxos().expect("second flush"), 0);
+}
+
+#[test]
+fn flush_threshold_bounds_pending_writes_and_records_metrics() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let chainstate = open_chainstate(&store, dir.path());
+    let metrics = Arc::new(ConnectMetrics::default());
+    chainstate.attach_connect_metrics(Arc::clone(&metrics));
+    chainstate
+        .set_utxo_flush_policy(UtxoFlushPolicy {
+            max_dirty_entries: 8,
+            max_dirty_bytes: 0,
+        })
+        .expect("policy");
+    let (params, blocks) = build_chain(&chainstate);
+    connect(&chainstate, &params, &blocks);
+
+    assert!(chainstate.utxo_dirty_entries() <= 8);
+    let snapshot = metrics.snapshot();
+    assert!(snapshot.utxo_flushes >= 4, "{snapshot:?}");
+    assert_eq!(
+        snapshot.utxo_flush_entries as usize + chainstate.utxo_dirty_entries(),
+        TIP_HEIGHT as usize
+    );
+
+    // Turning deferral off writes out whatever is still pending.
+    chainstate
+        .set_utxo_flush_policy(UtxoFlushPolicy::default())
+        .expect("disable");
+    assert_eq!(chainstate.utxo_dirty_entries(), 0);
+    assert!(stored(&store, &coinbase_outpoint(TIP_HEIGHT)));
+}
+
+#[test]
+fn restart_after_lost_utxo_writes_rewinds_to_last_flush() {
+    let store = Arc::new(MemoryStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let chainstate = open_chainstate(&store, dir.path());
+    chainstate
+        .set_utxo_flush_policy(UtxoFlushPolicy {
+            max_dirty_entries: 1_000,
+            max_dirty_bytes: 0,
+        })
+        .expect("policy");
+    let (params, blocks) = build_chain(&chainstate);
+    connect(&chainstate, &params, &blocks[..=25]);
+    chainstate.flush_utxos().expect("flush");
+    connect(&chainstate, &params, &blocks);
+    assert_eq!(
+        chainstate.best_block().expect("best").expect("tip").height,
+        TIP_HEIGHT as i32
+    );
+    // Crash: the pending writes for blocks 26..=TIP_HEIGHT never reach the store.
+    drop(chainstate);
+
+    let chainstate = open_chainstate(&store, dir.path());
+    assert_eq!(
+        chainstate.recover_unflushed_utxos().expect("recover"),
+        TIP_HEIGHT - 25
+    );
+    assert_eq!(
+        chainstate.best_block().expect("best").expect("tip").height,
+        25
+    );
+    assert!(chainstate
+        .utxo_exists(&coinbase_outpoint(25))
+        .expect("utxo"));
+    assert!(!chainstate
+        .utxo_exists(&coinbase_outpoint(26))
+        .expect("utxo"));
+    assert_eq!(
+        chainstate.recover_unflushed_utxos().expect("recover again"),
+        0
+    );
+
+    connect(&chainstate, &params, &blocks);
+    assert!(chainstate
+        .utxo_exists(&coinbase_outpoint(TIP_HEIGHT))
+        .expect("utxo"));
+    assert_eq!(
+        chainstate
+            .utxo_stats()
+            .expect("stats")
+            .expect("stats")
+            .txouts,
+        u64::from(TIP_HEIGHT) + 1
+    );
+}
@@ -28,6 +28,10 @@ pub const OVERWINTER_MAX_TX_VERSION: i32 = 3;
 pub const SAPLING_MIN_TX_VERSION: i32 = 4;
 /// The maximum allowed Sapling transaction version (network rule).
 pub const SAPLING_MAX_TX_VERSION: i32 = 4;
+/// Version group id of Overwinter (v3) transactions.
+pub const OVERWINTER_VERSION_GROUP_ID: u32 = 0x03C4_8270;
+/// Version group id of Sapling (v4) transactions.
+pub const SAPLING_VERSION_GROUP_ID: u32 = 0x892F_2085;
 /// The maximum allowed size for a serialized block, in bytes (network rule).
 pub const MAX_BLOCK_SIZE: u32 = 2_000_000;
 /// The maximum allowed number of signature check operations in a block (network rule).
@@ -56,6 +60,8 @@ pub const TX_EXPIRY_HEIGHT_THRESHOLD: u32 = 500_000_000;
 /// The number of blocks within expiry height when a tx is considered to be expiring soon.
 pub const TX_EXPIRING_SOON_THRESHOLD: u32 = 3;
 
+/// Interpret sequence numbers as relative lock-time constraints (BIP68).
+pub const LOCKTIME_VERIFY_SEQUENCE: u32 = 1 << 0;
 /// Use GetMedianTimePast() instead of nTime for end point timestamp.
 pub const LOCKTIME_MEDIAN_TIME_PAST: u32 = 1 << 1;
 /// Standard locktime verify flags used by non-consensus code.
@@ -7,12 +7,14 @@ pub mod rewards;
 pub mod upgrades;
 
 pub use params::{
-    chain_params, consensus_params, ChainParams, ConsensusParams, EquihashParams, FluxnodeParams,
-    Network, TimedPublicKey,
+    chain_params, consensus_params, ChainParams, Checkpoint, ConsensusParams, EquihashParams,
+    FluxnodeParams, Network, TimedPublicKey,
 };
 pub use rewards::{
-    block_subsidy, exchange_fund_amount, fluxnode_collateral_kind, fluxnode_collateral_matches_tier,
-    fluxnode_subsidy, fluxnode_tier_from_collateral, foundation_fund_amount,
-    is_swap_pool_interval, min_dev_fund_amount, swap_pool_amount, FluxnodeCollateralKind,
+    block_subsidy, exchange_fund_amount, exchange_fund_schedule, fluxnode_collateral_kind,
+    fluxnode_collateral_matches_tier, fluxnode_subsidy, fluxnode_tier_from_collateral,
+    foundation_fund_amount, foundation_fund_schedule, is_swap_pool_interval, min_dev_fund_amount,
+    required_funding_outputs, schedule_total, subsidy_schedule, swap_pool_amount,
+    swap_pool_schedule, FluxnodeCollateralKind, FundingOutput, FundingOutputKind,
 };
 pub use upgrades::Hash256;
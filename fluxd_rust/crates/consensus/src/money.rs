@@ -11,3 +11,140 @@ pub const MAX_MONEY: Amount = 440_000_000 * COIN;
 pub fn money_range(value: Amount) -> bool {
     (0..=MAX_MONEY).contains(&value)
 }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoneyError {
+    Empty,
+    Invalid,
+    TooPrecise,
+    OutOfRange,
+}
+
+impl std::fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoneyError::Empty => write!(f, "amount is empty"),
+            MoneyError::Invalid => write!(f, "invalid amount"),
+            MoneyError::TooPrecise => write!(f, "amount has too many decimal places"),
+            MoneyError::OutOfRange => write!(f, "amount out of range"),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// Formats an amount in coins, keeping at least two decimals (`1.5` -> `"1.50"`).
+pub fn format(amount: Amount) -> String {
+    let abs = (amount as i128).abs();
+    let whole = abs / COIN as i128;
+    let frac = abs % COIN as i128;
+    let mut out = format!("{whole}.{frac:08}");
+
+    while out.len() >= 3 {
+        let len = out.len();
+        let bytes = out.as_bytes();
+        if bytes[len - 1] == b'0' && bytes[len - 3].is_ascii_digit() {
+            out.pop();
+        } else {
+            break;
+        }
+    }
+
+    if amount < 0 {
+        out.insert(0, '-');
+    }
+    out
+}
+
+/// Parses a decimal coin amount into satoshis without going through floating point.
+///
+/// At most eight fractional digits are accepted and the result must be within
+/// [`money_range`].
+pub fn parse(text: &str) -> Result<Amount, MoneyError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(MoneyError::Empty);
+    }
+
+    let negative = text.starts_with('-');
+    let text = text.strip_prefix('-').unwrap_or(text);
+
+    let (whole, fractional) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fractional.is_empty() {
+        return Err(MoneyError::Invalid);
+    }
+    if !whole.chars().all(|ch| ch.is_ascii_digit())
+        || !fractional.chars().all(|ch| ch.is_ascii_digit())
+    {
+        return Err(MoneyError::Invalid);
+    }
+    if fractional.len() > 8 {
+        return Err(MoneyError::TooPrecise);
+    }
+
+    let whole_value = if whole.is_empty() {
+        0
+    } else {
+        whole
+            .parse::<Amount>()
+            .map_err(|_| MoneyError::OutOfRange)?
+    };
+    let fractional_value = if fractional.is_empty() {
+        0
+    } else {
+        let parsed = fractional
+            .parse::<Amount>()
+            .map_err(|_| MoneyError::Invalid)?;
+        parsed * 10i64.pow(8 - fractional.len() as u32)
+    };
+
+    let amount = whole_value
+        .checked_mul(COIN)
+        .and_then(|value| value.checked_add(fractional_value))
+        .ok_or(MoneyError::OutOfRange)?;
+    if negative && amount != 0 {
+        return Err(MoneyError::OutOfRange);
+    }
+    if !money_range(amount) {
+        return Err(MoneyError::OutOfRange);
+    }
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_canonical_amounts() {
+        assert_eq!(parse("0.00000001"), Ok(1));
+        assert_eq!(parse("21000000.0"), Ok(21_000_000 * COIN));
+        assert_eq!(parse("1"), Ok(COIN));
+        assert_eq!(parse(".1"), Ok(COIN / 10));
+        assert_eq!(parse("1."), Ok(COIN));
+        assert_eq!(parse(" 2.5 "), Ok(2 * COIN + COIN / 2));
+    }
+
+    #[test]
+    fn parse_rejects_bad_amounts() {
+        assert_eq!(parse("-1"), Err(MoneyError::OutOfRange));
+        assert_eq!(parse("1.000000001"), Err(MoneyError::TooPrecise));
+        assert_eq!(parse(""), Err(MoneyError::Empty));
+        assert_eq!(parse("."), Err(MoneyError::Invalid));
+        assert_eq!(parse("1e-8"), Err(MoneyError::Invalid));
+        assert_eq!(parse("1.2.3"), Err(MoneyError::Invalid));
+        assert_eq!(parse("440000000.00000001"), Err(MoneyError::OutOfRange));
+        assert_eq!(parse("99999999999999999999"), Err(MoneyError::OutOfRange));
+    }
+
+    #[test]
+    fn format_round_trips() {
+        assert_eq!(format(1), "0.00000001");
+        assert_eq!(format(COIN), "1.00");
+        assert_eq!(format(COIN + COIN / 2), "1.50");
+        assert_eq!(format(-COIN), "-1.00");
+        for amount in [0, 1, 12_345_678, COIN, 21_000_000 * COIN, MAX_MONEY] {
+            assert_eq!(parse(&format(amount)), Ok(amount));
+        }
+    }
+}
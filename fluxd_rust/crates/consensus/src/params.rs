@@ -1,5 +1,6 @@
 //! Consensus parameter definitions.
 
+use crate::constants::COINBASE_MATURITY;
 use crate::money::{Amount, COIN};
 use crate::upgrades::{Hash256, NetworkUpgrade, MAX_NETWORK_UPGRADES};
 
@@ -16,6 +17,8 @@ pub struct ConsensusParams {
     pub hash_genesis_block: Hash256,
     pub genesis_time: u32,
     pub coinbase_must_be_protected: bool,
+    /// Blocks a coinbase output must be buried under before it may be spent.
+    pub coinbase_maturity: i32,
     pub subsidy_slow_start_interval: i32,
     pub subsidy_halving_interval: i32,
     pub majority_enforce_block_upgrade: i32,
@@ -66,15 +69,44 @@ impl ConsensusParams {
         (self.digishield_averaging_window_timespan() * (100 + self.digishield_max_adjust_down))
             / 100
     }
+
+    /// Blocks still to be connected before a coinbase output with `confirmations` may be spent
+    /// in the next block; 0 once it is mature.
+    pub fn coinbase_maturity_remaining(&self, confirmations: i32) -> i32 {
+        self.coinbase_maturity.saturating_sub(confirmations).max(0)
+    }
+
+    /// Hardcoded checkpoints, ordered by ascending height.
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Returns the checkpoint pinned at `height`, if any.
+    pub fn checkpoint_at(&self, height: i32) -> Option<&Checkpoint> {
+        self.checkpoints
+            .iter()
+            .find(|checkpoint| checkpoint.height == height)
+    }
+
+    /// Height of the highest checkpoint, or 0 when none are configured.
+    ///
+    /// Blocks at or below this height are buried under a checkpoint and can use
+    /// assume-valid style shortcuts.
+    pub fn last_checkpoint_height(&self) -> i32 {
+        self.checkpoints
+            .last()
+            .map(|checkpoint| checkpoint.height)
+            .unwrap_or(0)
+    }
 }
 
 #[derive(Debug)]
-pub enum HexError {
+pub(crate) enum HexError {
     InvalidLength,
     InvalidHex,
 }
 
-pub fn hash256_from_hex(input: &str) -> Result<Hash256, HexError> {
+pub(crate) fn hash256_from_hex(input: &str) -> Result<Hash256, HexError> {
     let mut hex = input.trim();
     if let Some(stripped) = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")) {
         hex = stripped;
@@ -185,6 +217,13 @@ pub struct EquihashParams {
     pub solution_size: u16,
 }
 
+/// Bundled peer address, with the chain height it was serving when the list was last refreshed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FixedSeed {
+    pub addr: &'static str,
+    pub last_height: i32,
+}
+
 #[derive(Clone, Debug)]
 pub struct ChainParams {
     pub network: Network,
@@ -196,7 +235,19 @@ pub struct ChainParams {
     pub message_start: [u8; 4],
     pub default_port: u16,
     pub dns_seeds: &'static [&'static str],
-    pub fixed_seeds: &'static [&'static str],
+    pub fixed_seeds: &'static [FixedSeed],
+    /// Block whose ancestors skip script verification unless overridden.
+    pub default_assume_valid: Option<Hash256>,
+}
+
+impl ChainParams {
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        self.consensus.checkpoints()
+    }
+
+    pub fn last_checkpoint_height(&self) -> i32 {
+        self.consensus.last_checkpoint_height()
+    }
 }
 
 pub fn chain_params(network: Network) -> ChainParams {
@@ -319,6 +370,7 @@ fn mainnet_consensus_params() -> ConsensusParams {
         .expect("mainnet genesis hash"),
         genesis_time: 1_516_980_000,
         coinbase_must_be_protected: true,
+        coinbase_maturity: COINBASE_MATURITY,
         subsidy_slow_start_interval: 5_000,
         subsidy_halving_interval: 655_350,
         majority_enforce_block_upgrade: 750,
@@ -433,6 +485,7 @@ fn testnet_consensus_params() -> ConsensusParams {
         .expect("testnet genesis hash"),
         genesis_time: 1_582_228_940,
         coinbase_must_be_protected: true,
+        coinbase_maturity: COINBASE_MATURITY,
         subsidy_slow_start_interval: 1,
         subsidy_halving_interval: 655_350,
         majority_enforce_block_upgrade: 51,
@@ -554,6 +607,7 @@ fn regtest_consensus_params() -> ConsensusParams {
         .expect("regtest genesis hash"),
         genesis_time: 1_296_688_602,
         coinbase_must_be_protected: false,
+        coinbase_maturity: COINBASE_MATURITY,
         subsidy_slow_start_interval: 0,
         subsidy_halving_interval: 150,
         majority_enforce_block_upgrade: 750,
@@ -673,18 +727,54 @@ const MAINNET_DNS_SEEDS: [&str; 3] = [
     "dnsseed.runonflux.io",
 ];
 
-const MAINNET_FIXED_SEEDS: [&str; 11] = [
-    "46.36.38.23:16125",
-    "46.36.39.93:16125",
-    "45.63.86.148:16125",
-    "45.63.83.125:16125",
-    "52.171.140.27:16125",
-    "142.44.143.182:16125",
-    "173.212.207.13:16125",
-    "173.249.13.224:16125",
-    "136.33.111.57:16125",
-    "35.194.136.53:16125",
-    "35.205.124.144:16125",
+/// Height the mainnet fixed seed list was last refreshed at (the newest checkpoint).
+const MAINNET_FIXED_SEEDS_HEIGHT: i32 = 2_029_000;
+
+const MAINNET_FIXED_SEEDS: [FixedSeed; 11] = [
+    FixedSeed {
+        addr: "46.36.38.23:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "46.36.39.93:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "45.63.86.148:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "45.63.83.125:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "52.171.140.27:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "142.44.143.182:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "173.212.207.13:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "173.249.13.224:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "136.33.111.57:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "35.194.136.53:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
+    FixedSeed {
+        addr: "35.205.124.144:16125",
+        last_height: MAINNET_FIXED_SEEDS_HEIGHT,
+    },
 ];
 
 const TESTNET_DNS_SEEDS: [&str; 3] = [
@@ -693,10 +783,10 @@ const TESTNET_DNS_SEEDS: [&str; 3] = [
     "test.dnsseed.runonflux.io",
 ];
 
-const TESTNET_FIXED_SEEDS: [&str; 0] = [];
+const TESTNET_FIXED_SEEDS: [FixedSeed; 0] = [];
 
 const REGTEST_DNS_SEEDS: [&str; 0] = [];
-const REGTEST_FIXED_SEEDS: [&str; 0] = [];
+const REGTEST_FIXED_SEEDS: [FixedSeed; 0] = [];
 
 fn mainnet_emergency_params() -> EmergencyParams {
     EmergencyParams {
@@ -902,6 +992,10 @@ fn mainnet_chain_params() -> ChainParams {
         default_port: 16_125,
         dns_seeds: &MAINNET_DNS_SEEDS,
         fixed_seeds: &MAINNET_FIXED_SEEDS,
+        default_assume_valid: Some(
+            hash256_from_hex("4856dc788a973db4cc537465c9ef80288e1eb065898993d72371b1ee48c248b4")
+                .expect("mainnet assumevalid hash"),
+        ),
     }
 }
 
@@ -941,6 +1035,7 @@ fn testnet_chain_params() -> ChainParams {
         default_port: 26_125,
         dns_seeds: &TESTNET_DNS_SEEDS,
         fixed_seeds: &TESTNET_FIXED_SEEDS,
+        default_assume_valid: None,
     }
 }
 
@@ -980,6 +1075,7 @@ fn regtest_chain_params() -> ChainParams {
         default_port: 26_126,
         dns_seeds: &REGTEST_DNS_SEEDS,
         fixed_seeds: &REGTEST_FIXED_SEEDS,
+        default_assume_valid: None,
     }
 }
 
@@ -1261,4 +1357,34 @@ mod tests {
             assert!(window[0].height < window[1].height);
         }
     }
+
+    #[test]
+    fn checkpoint_lookup_helpers() {
+        let params = chain_params(Network::Mainnet);
+
+        assert_eq!(
+            params.checkpoints().len(),
+            params.consensus.checkpoints.len()
+        );
+        assert_eq!(params.last_checkpoint_height(), 2_029_000);
+        let checkpoint = params
+            .consensus
+            .checkpoint_at(1_000_000)
+            .expect("checkpoint at 1000000");
+        assert_eq!(
+            hash256_to_hex(&checkpoint.hash),
+            "0000001a80e7f30d21fb14116cd01d51e1fad8ac84cc960896f4691a57368a47"
+        );
+        assert!(params.consensus.checkpoint_at(1_000_001).is_none());
+    }
+
+    #[test]
+    fn mainnet_default_assume_valid_is_last_checkpoint() {
+        let params = chain_params(Network::Mainnet);
+        let last = params.checkpoints().last().expect("checkpoint");
+        assert_eq!(params.default_assume_valid, Some(last.hash));
+        assert!(chain_params(Network::Regtest)
+            .default_assume_valid
+            .is_none());
+    }
 }
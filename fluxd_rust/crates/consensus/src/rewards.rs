@@ -1,7 +1,7 @@
 //! Subsidy and funding schedule helpers.
 
 use crate::money::{Amount, COIN};
-use crate::params::{ConsensusParams, FluxnodeParams, FundingParams, SwapPoolParams};
+use crate::params::{ChainParams, ConsensusParams, FluxnodeParams, FundingParams, SwapPoolParams};
 use crate::upgrades::{network_upgrade_active, UpgradeIndex};
 
 const V1_FLUXNODE_COLLAT_CUMULUS: Amount = 10_000 * COIN;
@@ -109,6 +109,74 @@ pub fn fluxnode_subsidy(
     ((block_value as f64) * (percentage * multiple)) as Amount
 }
 
+/// Collateral requirement for one fluxnode tier at a given height.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TierSpec {
+    pub tier: u8,
+    pub name: &'static str,
+    /// Amount a new collateral output must hold.
+    pub collateral: Amount,
+    /// Pre-transition amount that is still accepted while the tier's transition window is open.
+    pub legacy_collateral: Option<Amount>,
+}
+
+impl FluxnodeParams {
+    /// Returns the collateral table for tiers 1 through 3 as it applies at `height`.
+    pub fn tiers_at(&self, height: i32) -> Vec<TierSpec> {
+        let height = height as i64;
+        let spec = |tier, name, start, end, v1, v2| {
+            if height < start {
+                TierSpec {
+                    tier,
+                    name,
+                    collateral: v1,
+                    legacy_collateral: None,
+                }
+            } else if height < end {
+                TierSpec {
+                    tier,
+                    name,
+                    collateral: v2,
+                    legacy_collateral: Some(v1),
+                }
+            } else {
+                TierSpec {
+                    tier,
+                    name,
+                    collateral: v2,
+                    legacy_collateral: None,
+                }
+            }
+        };
+        vec![
+            spec(
+                1,
+                "CUMULUS",
+                self.cumulus_transition_start,
+                self.cumulus_transition_end,
+                V1_FLUXNODE_COLLAT_CUMULUS,
+                V2_FLUXNODE_COLLAT_CUMULUS,
+            ),
+            spec(
+                2,
+                "NIMBUS",
+                self.nimbus_transition_start,
+                self.nimbus_transition_end,
+                V1_FLUXNODE_COLLAT_NIMBUS,
+                V2_FLUXNODE_COLLAT_NIMBUS,
+            ),
+            spec(
+                3,
+                "STRATUS",
+                self.stratus_transition_start,
+                self.stratus_transition_end,
+                V1_FLUXNODE_COLLAT_STRATUS,
+                V2_FLUXNODE_COLLAT_STRATUS,
+            ),
+        ]
+    }
+}
+
 pub fn fluxnode_tier_from_collateral(
     height: i32,
     amount: Amount,
@@ -210,6 +278,177 @@ pub fn swap_pool_amount(height: i64, swap_pool: &SwapPoolParams) -> Amount {
     }
 }
 
+/// [`block_subsidy`] as piecewise-constant segments: `(start_height, subsidy)` for every height
+/// where the value changes, ascending from 0. A segment lasts until the next start and the last
+/// one never ends. Slow-start heights change every block, so each is its own segment.
+pub fn subsidy_schedule(params: &ConsensusParams) -> impl Iterator<Item = (i32, Amount)> + '_ {
+    let mut starts: Vec<i32> = (0..=params.subsidy_slow_start_interval.max(2)).collect();
+    if params.subsidy_halving_interval > 0 {
+        let shift = params.subsidy_slow_start_shift();
+        starts.extend((1..=64i32).filter_map(|halvings| {
+            halvings
+                .checked_mul(params.subsidy_halving_interval)?
+                .checked_add(shift)
+        }));
+    }
+    let pon_height = params.upgrades[UpgradeIndex::Pon.as_usize()].activation_height;
+    if pon_height >= 0 {
+        starts.push(pon_height);
+        if params.pon_subsidy_reduction_interval > 0 {
+            starts.extend((1..=params.pon_max_reductions).filter_map(|reductions| {
+                reductions
+                    .checked_mul(params.pon_subsidy_reduction_interval)?
+                    .checked_add(pon_height)
+            }));
+        }
+    }
+    segments(starts, move |height| block_subsidy(height, params))
+}
+
+/// [`exchange_fund_amount`] as segments, in the form of [`subsidy_schedule`].
+pub fn exchange_fund_schedule(funding: &FundingParams) -> impl Iterator<Item = (i32, Amount)> + '_ {
+    segments(payment_starts([funding.exchange_height]), move |height| {
+        exchange_fund_amount(height, funding)
+    })
+}
+
+/// [`foundation_fund_amount`] as segments, in the form of [`subsidy_schedule`].
+pub fn foundation_fund_schedule(
+    funding: &FundingParams,
+) -> impl Iterator<Item = (i32, Amount)> + '_ {
+    segments(payment_starts([funding.foundation_height]), move |height| {
+        foundation_fund_amount(height, funding)
+    })
+}
+
+/// [`swap_pool_amount`] as segments, in the form of [`subsidy_schedule`].
+pub fn swap_pool_schedule(swap_pool: &SwapPoolParams) -> impl Iterator<Item = (i32, Amount)> + '_ {
+    let heights = (0..swap_pool.max_times).map(|index| {
+        swap_pool
+            .start_height
+            .saturating_add(swap_pool.interval * index as i64)
+    });
+    segments(payment_starts(heights), move |height| {
+        swap_pool_amount(height as i64, swap_pool)
+    })
+}
+
+/// Sum of a segment schedule over heights `0..=end_height`.
+pub fn schedule_total(schedule: impl IntoIterator<Item = (i32, Amount)>, end_height: i32) -> i128 {
+    let mut total = 0i128;
+    let mut current: Option<(i32, Amount)> = None;
+    for (start, amount) in schedule {
+        if start > end_height {
+            break;
+        }
+        if let Some((previous_start, previous_amount)) = current {
+            total += i128::from(start - previous_start) * i128::from(previous_amount);
+        }
+        current = Some((start, amount));
+    }
+    if let Some((start, amount)) = current {
+        total += (i128::from(end_height) - i128::from(start) + 1) * i128::from(amount);
+    }
+    total
+}
+
+/// Heights where a one-block payment starts and stops; out-of-range heights are dropped.
+fn payment_starts(heights: impl IntoIterator<Item = i64>) -> Vec<i32> {
+    heights
+        .into_iter()
+        .flat_map(|height| [height, height.saturating_add(1)])
+        .filter_map(|height| i32::try_from(height).ok())
+        .collect()
+}
+
+/// Evaluates `amount_at` at every candidate start and keeps those where the value changes.
+/// Candidates must include every height where it can change; extra ones are harmless.
+fn segments<F>(mut starts: Vec<i32>, amount_at: F) -> impl Iterator<Item = (i32, Amount)>
+where
+    F: Fn(i32) -> Amount,
+{
+    starts.retain(|height| *height >= 0);
+    starts.push(0);
+    starts.sort_unstable();
+    starts.dedup();
+    let mut previous = None;
+    starts.into_iter().filter_map(move |height| {
+        let amount = amount_at(height);
+        if previous == Some(amount) {
+            return None;
+        }
+        previous = Some(amount);
+        Some((height, amount))
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FundingOutputKind {
+    /// Pays at least `amount`; unpaid fluxnode tiers also fall through to the dev fund.
+    DevFund,
+    Exchange,
+    Foundation,
+    SwapPool,
+}
+
+/// A payment the coinbase at some height must contain. Addresses are kept as strings because
+/// script encoding lives in `fluxd-primitives`; use `address_to_script_pubkey` to match outputs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FundingOutput {
+    pub kind: FundingOutputKind,
+    pub address: &'static str,
+    pub amount: Amount,
+}
+
+impl FundingOutput {
+    /// Whether an output of `value` to this address satisfies the requirement.
+    pub fn accepts(&self, value: Amount) -> bool {
+        match self.kind {
+            FundingOutputKind::DevFund => value >= self.amount,
+            _ => value == self.amount,
+        }
+    }
+}
+
+/// Mandatory dev fund, exchange, foundation and swap pool outputs for the coinbase at `height`.
+pub fn required_funding_outputs(height: i32, params: &ChainParams) -> Vec<FundingOutput> {
+    let mut outputs = Vec::new();
+    if network_upgrade_active(height, &params.consensus.upgrades, UpgradeIndex::Pon) {
+        let amount = min_dev_fund_amount(height, &params.consensus);
+        if amount > 0 {
+            outputs.push(FundingOutput {
+                kind: FundingOutputKind::DevFund,
+                address: params.funding.dev_fund_address,
+                amount,
+            });
+        }
+    }
+    let exchange = exchange_fund_amount(height, &params.funding);
+    if exchange > 0 {
+        outputs.push(FundingOutput {
+            kind: FundingOutputKind::Exchange,
+            address: params.funding.exchange_address,
+            amount: exchange,
+        });
+    }
+    let foundation = foundation_fund_amount(height, &params.funding);
+    if foundation > 0 {
+        outputs.push(FundingOutput {
+            kind: FundingOutputKind::Foundation,
+            address: params.funding.foundation_address,
+            amount: foundation,
+        });
+    }
+    if is_swap_pool_interval(height as i64, &params.swap_pool) {
+        outputs.push(FundingOutput {
+            kind: FundingOutputKind::SwapPool,
+            address: params.swap_pool.address,
+            amount: swap_pool_amount(height as i64, &params.swap_pool),
+        });
+    }
+    outputs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,6 +471,59 @@ mod tests {
         assert_ne!(at, 150 * COIN / 8);
     }
 
+    #[test]
+    fn schedule_segments_sum_to_per_block_totals() {
+        let params = chain_params(Network::Mainnet);
+        let consensus = &params.consensus;
+        let pon = consensus.upgrades[UpgradeIndex::Pon.as_usize()].activation_height;
+        let end = pon + 2 * consensus.pon_subsidy_reduction_interval + 10;
+
+        let mut per_block_subsidy = 0i128;
+        let mut per_block_funding = 0i128;
+        for height in 0..=end {
+            per_block_subsidy += i128::from(block_subsidy(height, consensus));
+            per_block_funding += i128::from(exchange_fund_amount(height, &params.funding))
+                + i128::from(foundation_fund_amount(height, &params.funding))
+                + i128::from(swap_pool_amount(height as i64, &params.swap_pool));
+        }
+        assert_eq!(
+            schedule_total(subsidy_schedule(consensus), end),
+            per_block_subsidy
+        );
+        let funding = schedule_total(exchange_fund_schedule(&params.funding), end)
+            + schedule_total(foundation_fund_schedule(&params.funding), end)
+            + schedule_total(swap_pool_schedule(&params.swap_pool), end);
+        assert_eq!(funding, per_block_funding);
+        assert!(funding > 0);
+
+        // Segments are ascending, never repeat a value, and start where the value changes.
+        let segments: Vec<_> = subsidy_schedule(consensus).collect();
+        assert_eq!(segments[0], (0, 0));
+        for pair in segments.windows(2) {
+            let ((start, amount), (next, next_amount)) = (pair[0], pair[1]);
+            assert!(start < next);
+            assert_ne!(amount, next_amount);
+            assert_eq!(block_subsidy(next - 1, consensus), amount);
+            assert_eq!(block_subsidy(next, consensus), next_amount);
+        }
+        let (last_start, last_amount) = *segments.last().expect("segments");
+        assert_eq!(
+            block_subsidy(
+                last_start + 10 * consensus.pon_subsidy_reduction_interval,
+                consensus
+            ),
+            last_amount
+        );
+        assert!(segments.len() < consensus.subsidy_slow_start_interval as usize + 64);
+
+        assert_eq!(schedule_total(subsidy_schedule(consensus), -1), 0);
+        let regtest = consensus_params(Network::Regtest);
+        let per_block: i128 = (0..=1_000)
+            .map(|height| i128::from(block_subsidy(height, &regtest)))
+            .sum();
+        assert_eq!(schedule_total(subsidy_schedule(&regtest), 1_000), per_block);
+    }
+
     #[test]
     fn mainnet_pon_activation_switches_subsidy() {
         let params = consensus_params(Network::Mainnet);
@@ -258,6 +550,58 @@ mod tests {
         assert_eq!(min_dev_fund_amount(activation_height, &params), COIN / 2);
     }
 
+    #[test]
+    fn mainnet_required_funding_outputs_follow_schedule() {
+        let params = chain_params(Network::Mainnet);
+        let kinds = |height: i32| {
+            required_funding_outputs(height, &params)
+                .into_iter()
+                .map(|output| (output.kind, output.amount))
+                .collect::<Vec<_>>()
+        };
+
+        let exchange = params.funding.exchange_height as i32;
+        assert!(kinds(exchange - 1).is_empty());
+        assert_eq!(
+            kinds(exchange),
+            vec![(FundingOutputKind::Exchange, 7_500_000 * COIN)]
+        );
+        assert!(kinds(exchange + 1).is_empty());
+
+        let foundation = params.funding.foundation_height as i32;
+        let outputs = required_funding_outputs(foundation, &params);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].kind, FundingOutputKind::Foundation);
+        assert_eq!(outputs[0].address, params.funding.foundation_address);
+        assert_eq!(outputs[0].amount, 2_500_000 * COIN);
+
+        let swap_start = params.swap_pool.start_height as i32;
+        let swap_interval = params.swap_pool.interval as i32;
+        let swap_last = swap_start + swap_interval * (params.swap_pool.max_times - 1);
+        for height in [swap_start, swap_start + swap_interval, swap_last] {
+            assert_eq!(
+                kinds(height),
+                vec![(FundingOutputKind::SwapPool, 22_000_000 * COIN)]
+            );
+        }
+        assert!(kinds(swap_start + 1).is_empty());
+        assert!(kinds(swap_last + swap_interval).is_empty());
+
+        let pon = params.consensus.upgrades[UpgradeIndex::Pon.as_usize()].activation_height;
+        assert!(kinds(pon - 1).is_empty());
+        let outputs = required_funding_outputs(pon, &params);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].kind, FundingOutputKind::DevFund);
+        assert_eq!(outputs[0].address, params.funding.dev_fund_address);
+        assert_eq!(outputs[0].amount, COIN / 2);
+        assert!(outputs[0].accepts(COIN));
+        assert!(!outputs[0].accepts(COIN / 2 - 1));
+
+        let swap = required_funding_outputs(swap_start, &params)[0];
+        assert!(swap.accepts(swap.amount));
+        assert!(!swap.accepts(swap.amount + 1));
+    }
+
     #[test]
     fn mainnet_pon_subsidy_reduces_on_schedule_and_caps() {
         let params = consensus_params(Network::Mainnet);
@@ -383,4 +727,38 @@ mod tests {
             Some(1)
         );
     }
+
+    #[test]
+    fn tiers_at_reports_collateral_before_during_and_after_transition() {
+        let params = chain_params(Network::Mainnet);
+        let flux = &params.fluxnode;
+
+        let before = flux.tiers_at(flux.cumulus_transition_start as i32 - 1);
+        let names: Vec<_> = before.iter().map(|spec| spec.name).collect();
+        assert_eq!(names, ["CUMULUS", "NIMBUS", "STRATUS"]);
+        assert_eq!(before[0].collateral, 10_000 * COIN);
+        assert_eq!(before[0].legacy_collateral, None);
+        assert_eq!(before[2].collateral, 100_000 * COIN);
+
+        let during = flux.tiers_at(flux.cumulus_transition_start as i32);
+        assert_eq!(during[0].collateral, 1_000 * COIN);
+        assert_eq!(during[0].legacy_collateral, Some(10_000 * COIN));
+
+        let last = flux
+            .cumulus_transition_end
+            .max(flux.nimbus_transition_end)
+            .max(flux.stratus_transition_end);
+        let after = flux.tiers_at(last as i32);
+        let collateral: Vec<_> = after.iter().map(|spec| spec.collateral).collect();
+        assert_eq!(collateral, [1_000 * COIN, 12_500 * COIN, 40_000 * COIN]);
+        assert!(after.iter().all(|spec| spec.legacy_collateral.is_none()));
+        for spec in &after {
+            assert!(fluxnode_collateral_matches_tier(
+                last as i32,
+                spec.collateral,
+                spec.tier,
+                flux
+            ));
+        }
+    }
 }
@@ -1,5 +1,8 @@
 //! Network upgrade schedule and branch IDs.
 
+use crate::constants::{SAPLING_MIN_TX_VERSION, SAPLING_VERSION_GROUP_ID, SPROUT_MIN_TX_VERSION};
+use crate::params::ConsensusParams;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(u8)]
 pub enum UpgradeIndex {
@@ -161,14 +164,46 @@ pub fn current_epoch(
     UpgradeIndex::BaseSprout
 }
 
-pub fn current_epoch_branch_id(
-    height: i32,
-    upgrades: &[NetworkUpgrade; MAX_NETWORK_UPGRADES],
-) -> u32 {
+/// Consensus branch id committed to by signatures in a block at `height`.
+pub fn branch_id_for_height(height: i32, upgrades: &[NetworkUpgrade; MAX_NETWORK_UPGRADES]) -> u32 {
     let idx = current_epoch(height, upgrades);
     NETWORK_UPGRADE_INFO[idx.as_usize()].branch_id
 }
 
+/// Transaction-format rules in force for a block at a given height.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConsensusRules {
+    /// Branch id committed to by ZIP 143/243 signatures.
+    pub branch_id: u32,
+    /// Lowest transaction version accepted, and the version new transactions are built with.
+    pub min_tx_version: i32,
+    /// Version group id new overwintered transactions carry; `0` before Overwinter.
+    pub version_group_id: u32,
+    pub overwinter_active: bool,
+    pub sapling_active: bool,
+}
+
+/// The rules for a block at `height`. Flux activated Overwinter and Sapling together with the
+/// Acadia upgrade, so both flags flip at the same height.
+pub fn consensus_rules(height: i32, params: &ConsensusParams) -> ConsensusRules {
+    let sapling_active = network_upgrade_active(height, &params.upgrades, UpgradeIndex::Acadia);
+    ConsensusRules {
+        branch_id: branch_id_for_height(height, &params.upgrades),
+        min_tx_version: if sapling_active {
+            SAPLING_MIN_TX_VERSION
+        } else {
+            SPROUT_MIN_TX_VERSION
+        },
+        version_group_id: if sapling_active {
+            SAPLING_VERSION_GROUP_ID
+        } else {
+            0
+        },
+        overwinter_active: sapling_active,
+        sapling_active,
+    }
+}
+
 pub fn is_consensus_branch_id(branch_id: u32) -> bool {
     NETWORK_UPGRADE_INFO
         .iter()
@@ -268,15 +303,69 @@ mod tests {
     fn branch_id_selection() {
         let params = consensus_params(Network::Mainnet);
 
+        assert_eq!(branch_id_for_height(0, &params.upgrades), SPROUT_BRANCH_ID);
+
+        let lwma_branch = NETWORK_UPGRADE_INFO[UpgradeIndex::Lwma as usize].branch_id;
         assert_eq!(
-            current_epoch_branch_id(0, &params.upgrades),
+            branch_id_for_height(124_999, &params.upgrades),
             SPROUT_BRANCH_ID
         );
+        assert_eq!(branch_id_for_height(125_000, &params.upgrades), lwma_branch);
+    }
 
-        let lwma_branch = NETWORK_UPGRADE_INFO[UpgradeIndex::Lwma as usize].branch_id;
+    #[test]
+    fn consensus_rules_flip_at_each_activation_height() {
+        for network in [Network::Mainnet, Network::Testnet, Network::Regtest] {
+            let params = consensus_params(network);
+            for idx in ALL_UPGRADES.iter().skip(1) {
+                let height = params.upgrades[idx.as_usize()].activation_height;
+                if height <= 0 {
+                    continue;
+                }
+                let before = consensus_rules(height - 1, &params);
+                let at = consensus_rules(height, &params);
+                assert_eq!(
+                    at.branch_id,
+                    branch_id_for_height(height, &params.upgrades),
+                    "{network:?} {idx:?}"
+                );
+                assert_eq!(
+                    before.branch_id,
+                    branch_id_for_height(height - 1, &params.upgrades),
+                    "{network:?} {idx:?}"
+                );
+
+                let sapling_height =
+                    params.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height;
+                for (rules, h) in [(before, height - 1), (at, height)] {
+                    let sapling = sapling_height != NetworkUpgrade::NO_ACTIVATION_HEIGHT
+                        && h >= sapling_height;
+                    assert_eq!(rules.sapling_active, sapling, "{network:?} {idx:?} {h}");
+                    assert_eq!(rules.overwinter_active, sapling);
+                    if sapling {
+                        assert_eq!(rules.min_tx_version, SAPLING_MIN_TX_VERSION);
+                        assert_eq!(rules.version_group_id, SAPLING_VERSION_GROUP_ID);
+                    } else {
+                        assert_eq!(rules.min_tx_version, SPROUT_MIN_TX_VERSION);
+                        assert_eq!(rules.version_group_id, 0);
+                    }
+                }
+            }
+        }
+
+        let params = consensus_params(Network::Mainnet);
+        let acadia = params.upgrades[UpgradeIndex::Acadia.as_usize()].activation_height;
+        assert!(!consensus_rules(acadia - 1, &params).sapling_active);
+        assert!(consensus_rules(acadia, &params).sapling_active);
         assert_eq!(
-            current_epoch_branch_id(125_000, &params.upgrades),
-            lwma_branch
+            consensus_rules(0, &params),
+            ConsensusRules {
+                branch_id: SPROUT_BRANCH_ID,
+                min_tx_version: SPROUT_MIN_TX_VERSION,
+                version_group_id: 0,
+                overwinter_active: false,
+                sapling_active: false,
+            }
         );
     }
 
@@ -266,6 +266,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
                 collateral: outpoint,
                 collateral_pubkey: vec![0x02, 0x01],
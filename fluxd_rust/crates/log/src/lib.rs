@@ -2,7 +2,7 @@ use std::collections::VecDeque;
 use std::fmt;
 use std::io::{self, Write};
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Mutex, OnceLock, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde_json::json;
@@ -27,6 +27,21 @@ impl Level {
         }
     }
 
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Error,
+            2 => Self::Warn,
+            3 => Self::Info,
+            4 => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+
+    /// The next more verbose level, saturating at `Trace`.
+    pub fn more_verbose(self) -> Self {
+        Self::from_u8((self as u8).saturating_add(1))
+    }
+
     pub fn parse(raw: &str) -> Option<Self> {
         match raw.trim().to_ascii_lowercase().as_str() {
             "error" => Some(Self::Error),
@@ -63,6 +78,10 @@ pub struct LogConfig {
 }
 
 static LOG_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static LOG_CONFIGURED_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+/// Set while `LOG_TARGET_LEVELS` is non-empty so `enabled_for` can skip the lock otherwise.
+static LOG_TARGET_OVERRIDES: AtomicBool = AtomicBool::new(false);
+static LOG_TARGET_LEVELS: RwLock<Vec<(String, Level)>> = RwLock::new(Vec::new());
 static LOG_FORMAT: AtomicU8 = AtomicU8::new(Format::Text as u8);
 static LOG_TIMESTAMPS: AtomicBool = AtomicBool::new(true);
 static LOG_STDERR_ENABLED: AtomicBool = AtomicBool::new(true);
@@ -83,6 +102,7 @@ static LOG_CAPTURE: OnceLock<Mutex<VecDeque<CapturedLog>>> = OnceLock::new();
 
 pub fn init(config: LogConfig) {
     LOG_LEVEL.store(config.level as u8, Ordering::Relaxed);
+    LOG_CONFIGURED_LEVEL.store(config.level as u8, Ordering::Relaxed);
     LOG_FORMAT.store(config.format as u8, Ordering::Relaxed);
     LOG_TIMESTAMPS.store(config.timestamps, Ordering::Relaxed);
 }
@@ -126,10 +146,90 @@ pub fn capture_snapshot(limit: usize) -> Vec<CapturedLog> {
     guard.iter().skip(start).cloned().collect()
 }
 
+pub fn level() -> Level {
+    Level::from_u8(LOG_LEVEL.load(Ordering::Relaxed))
+}
+
+/// The level passed to `init`, restored by `reset_levels`.
+pub fn configured_level() -> Level {
+    Level::from_u8(LOG_CONFIGURED_LEVEL.load(Ordering::Relaxed))
+}
+
+pub fn set_level(level: Level) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Makes the global level one step more verbose and returns the new level.
+pub fn raise_level() -> Level {
+    let update = LOG_LEVEL.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |value| {
+        Some(Level::from_u8(value).more_verbose() as u8)
+    });
+    let (Ok(previous) | Err(previous)) = update;
+    Level::from_u8(previous).more_verbose()
+}
+
+/// Restores the configured global level and drops every target override.
+pub fn reset_levels() {
+    LOG_LEVEL.store(
+        LOG_CONFIGURED_LEVEL.load(Ordering::Relaxed),
+        Ordering::Relaxed,
+    );
+    if let Ok(mut overrides) = LOG_TARGET_LEVELS.write() {
+        overrides.clear();
+        LOG_TARGET_OVERRIDES.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Overrides the level for `target` and every module below it (`fluxd::p2p` also covers
+/// `fluxd::p2p::addr`); `None` removes the override. The longest matching target wins over the
+/// global level, in either direction.
+pub fn set_target_level(target: &str, level: Option<Level>) {
+    let Ok(mut overrides) = LOG_TARGET_LEVELS.write() else {
+        return;
+    };
+    overrides.retain(|(existing, _)| existing != target);
+    if let Some(level) = level {
+        overrides.push((target.to_string(), level));
+    }
+    LOG_TARGET_OVERRIDES.store(!overrides.is_empty(), Ordering::Relaxed);
+}
+
+pub fn target_levels() -> Vec<(String, Level)> {
+    LOG_TARGET_LEVELS
+        .read()
+        .map(|overrides| overrides.clone())
+        .unwrap_or_default()
+}
+
 pub fn enabled(level: Level) -> bool {
     level as u8 <= LOG_LEVEL.load(Ordering::Relaxed)
 }
 
+/// Like `enabled`, but honours the override for `target` set by `set_target_level`.
+pub fn enabled_for(level: Level, target: &str) -> bool {
+    if !LOG_TARGET_OVERRIDES.load(Ordering::Relaxed) {
+        return enabled(level);
+    }
+    let Ok(overrides) = LOG_TARGET_LEVELS.read() else {
+        return enabled(level);
+    };
+    let matched = overrides
+        .iter()
+        .filter(|(prefix, _)| target_matches(target, prefix))
+        .max_by_key(|(prefix, _)| prefix.len());
+    match matched {
+        Some((_, max)) => level <= *max,
+        None => enabled(level),
+    }
+}
+
+fn target_matches(target: &str, prefix: &str) -> bool {
+    match target.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
 pub fn log(
     level: Level,
     target: &'static str,
@@ -137,7 +237,7 @@ pub fn log(
     line: u32,
     args: fmt::Arguments<'_>,
 ) {
-    if !enabled(level) {
+    if !enabled_for(level, target) {
         return;
     }
 
@@ -214,7 +314,7 @@ pub fn log(
 #[macro_export]
 macro_rules! log_at {
     ($level:expr, $($arg:tt)*) => {{
-        if $crate::enabled($level) {
+        if $crate::enabled_for($level, module_path!()) {
             $crate::log($level, module_path!(), file!(), line!(), format_args!($($arg)*));
         }
     }};
@@ -305,6 +405,34 @@ mod tests {
         assert_eq!(Level::parse("nope"), None);
     }
 
+    #[test]
+    fn target_overrides_and_reset() {
+        init(LogConfig {
+            level: Level::Info,
+            format: Format::Text,
+            timestamps: true,
+        });
+        assert!(!enabled_for(Level::Debug, "fluxd::p2p"));
+
+        set_target_level("fluxd::p2p", Some(Level::Debug));
+        set_target_level("fluxd::p2p::addr", Some(Level::Error));
+        assert!(enabled_for(Level::Debug, "fluxd::p2p"));
+        assert!(enabled_for(Level::Debug, "fluxd::p2p::peer"));
+        assert!(!enabled_for(Level::Warn, "fluxd::p2p::addr"));
+        assert!(!enabled_for(Level::Debug, "fluxd::p2p_server"));
+        assert!(!enabled_for(Level::Debug, "fluxd::rpc"));
+
+        assert_eq!(raise_level(), Level::Debug);
+        assert_eq!(raise_level(), Level::Trace);
+        assert_eq!(raise_level(), Level::Trace);
+        assert!(enabled_for(Level::Trace, "fluxd::rpc"));
+
+        reset_levels();
+        assert_eq!(level(), Level::Info);
+        assert!(target_levels().is_empty());
+        assert!(!enabled_for(Level::Debug, "fluxd::p2p"));
+    }
+
     #[test]
     fn parse_format() {
         assert_eq!(Format::parse("text"), Some(Format::Text));
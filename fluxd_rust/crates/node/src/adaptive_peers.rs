@@ -0,0 +1,155 @@
+//! Adaptive block-peer target for `--adaptive-peers`.
+//!
+//! During initial sync the target grows by one peer per sample window while block throughput
+//! stays below `SLOW_BLOCKS_PER_SEC`, up to the configured maximum. Once the node is within
+//! `TIP_GAP` blocks of the best header the target drops back to the minimum.
+
+use std::time::{Duration, Instant};
+
+pub const SAMPLE_SECS: u64 = 30;
+pub const SLOW_BLOCKS_PER_SEC: f64 = 25.0;
+pub const TIP_GAP: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptivePeerBounds {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl AdaptivePeerBounds {
+    /// Parses `MIN:MAX`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (min, max) = value
+            .split_once(':')
+            .ok_or_else(|| format!("adaptive peers must be MIN:MAX, got '{value}'"))?;
+        let min = min
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid adaptive peers minimum '{min}'"))?;
+        let max = max
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid adaptive peers maximum '{max}'"))?;
+        if max == 0 {
+            return Err("adaptive peers maximum must be > 0".to_string());
+        }
+        if min > max {
+            return Err(format!(
+                "adaptive peers minimum {min} exceeds maximum {max}"
+            ));
+        }
+        Ok(Self { min, max })
+    }
+
+    pub fn clamp(&self, target: usize) -> usize {
+        target.clamp(self.min, self.max)
+    }
+}
+
+#[derive(Debug)]
+pub struct AdaptivePeerTarget {
+    bounds: AdaptivePeerBounds,
+    target: usize,
+    sample: Option<(Instant, u64)>,
+}
+
+impl AdaptivePeerTarget {
+    pub fn new(bounds: AdaptivePeerBounds, initial: usize) -> Self {
+        Self {
+            bounds,
+            target: bounds.clamp(initial),
+            sample: None,
+        }
+    }
+
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Feeds the running committed-block count and the current header gap. Returns the new
+    /// target when it changes.
+    pub fn update(&mut self, now: Instant, committed_blocks: u64, gap: i32) -> Option<usize> {
+        if gap <= TIP_GAP {
+            self.sample = None;
+            return self.set(self.bounds.min);
+        }
+        let (started_at, started_blocks) = match self.sample {
+            Some(sample) => sample,
+            None => {
+                self.sample = Some((now, committed_blocks));
+                return None;
+            }
+        };
+        let elapsed = now.saturating_duration_since(started_at);
+        if elapsed < Duration::from_secs(SAMPLE_SECS) {
+            return None;
+        }
+        self.sample = Some((now, committed_blocks));
+        let blocks = committed_blocks.saturating_sub(started_blocks);
+        let blocks_per_sec = blocks as f64 / elapsed.as_secs_f64();
+        if blocks_per_sec < SLOW_BLOCKS_PER_SEC {
+            self.set(self.bounds.clamp(self.target.saturating_add(1)))
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, target: usize) -> Option<usize> {
+        if target == self.target {
+            return None;
+        }
+        self.target = target;
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: AdaptivePeerBounds = AdaptivePeerBounds { min: 1, max: 4 };
+
+    #[test]
+    fn parse_bounds() {
+        assert_eq!(
+            AdaptivePeerBounds::parse("2:8").unwrap(),
+            AdaptivePeerBounds { min: 2, max: 8 }
+        );
+        assert!(AdaptivePeerBounds::parse("8").is_err());
+        assert!(AdaptivePeerBounds::parse("8:2").is_err());
+        assert!(AdaptivePeerBounds::parse("0:0").is_err());
+        assert!(AdaptivePeerBounds::parse("a:2").is_err());
+    }
+
+    #[test]
+    fn raises_target_while_sync_is_slow() {
+        let start = Instant::now();
+        let window = Duration::from_secs(SAMPLE_SECS);
+        let mut target = AdaptivePeerTarget::new(BOUNDS, 3);
+        assert_eq!(target.update(start, 0, 5_000), None);
+        assert_eq!(target.update(start + window / 2, 10, 5_000), None);
+        assert_eq!(target.update(start + window, 20, 5_000), Some(4));
+        assert_eq!(target.update(start + window * 2, 40, 5_000), None);
+        assert_eq!(target.target(), 4);
+    }
+
+    #[test]
+    fn holds_target_when_sync_is_fast() {
+        let start = Instant::now();
+        let window = Duration::from_secs(SAMPLE_SECS);
+        let fast = (SLOW_BLOCKS_PER_SEC as u64 + 1) * SAMPLE_SECS;
+        let mut target = AdaptivePeerTarget::new(BOUNDS, 2);
+        assert_eq!(target.update(start, 0, 5_000), None);
+        assert_eq!(target.update(start + window, fast, 5_000), None);
+        assert_eq!(target.target(), 2);
+    }
+
+    #[test]
+    fn drops_to_minimum_near_tip() {
+        let start = Instant::now();
+        let mut target = AdaptivePeerTarget::new(BOUNDS, 9);
+        assert_eq!(target.target(), 4);
+        assert_eq!(target.update(start, 0, TIP_GAP), Some(1));
+        assert_eq!(target.update(start, 0, 0), None);
+    }
+}
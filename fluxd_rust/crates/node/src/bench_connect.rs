@@ -0,0 +1,366 @@
+//! `--benchmark-connect <start> <end>`: replays stored blocks into a throwaway chainstate.
+//!
+//! Blocks are read back from the node's own flatfiles and pushed through the same verify,
+//! shielded and connect pipeline that block sync uses, against a fresh in-memory store whose
+//! flatfiles live in a scratch directory under the data dir. Heights below `start` are connected
+//! first without being measured so the timed range runs against a realistic UTXO set. Nothing
+//! touches the network and the source chainstate is only read, which makes it a repeatable way to
+//! compare `--verify-workers`, `--shielded-workers` and the UTXO cache settings.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use fluxd_chainstate::flatfiles::FlatFileStore;
+use fluxd_chainstate::metrics::ConnectMetrics;
+use fluxd_chainstate::state::{ChainState, UtxoFlushPolicy};
+use fluxd_chainstate::validation::{ValidationFlags, ValidationMetrics};
+use fluxd_consensus::params::ChainParams;
+use fluxd_primitives::block::Block;
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{KeyValueStore, WriteBatch};
+
+use crate::stats::{snapshot_stats, StatsSnapshot, SyncMetrics};
+use crate::{
+    fee_estimator, mempool, Backend, Config, HeaderCursor, ReceivedBlock, VerifySettings,
+    DEFAULT_MAX_FLATFILE_SIZE,
+};
+
+/// Blocks handed to the connect pipeline per round, matching a typical sync download window.
+const BENCHMARK_BATCH_BLOCKS: i32 = 512;
+const BENCHMARK_DIR_NAME: &str = "benchmark-connect";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BenchmarkRange {
+    pub(crate) start: i32,
+    pub(crate) end: i32,
+}
+
+pub(crate) fn parse_range(
+    flag: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<BenchmarkRange, String> {
+    let mut next = |what: &str| {
+        args.next()
+            .ok_or_else(|| format!("missing {what} for {flag}\n{}", crate::usage()))
+    };
+    let start = next("start height")?;
+    let end = next("end height")?;
+    let start = start
+        .parse::<i32>()
+        .map_err(|_| format!("invalid start height for {flag}\n{}", crate::usage()))?;
+    let end = end
+        .parse::<i32>()
+        .map_err(|_| format!("invalid end height for {flag}\n{}", crate::usage()))?;
+    if start < 0 || end < start {
+        return Err(format!(
+            "invalid height range {start}..={end} for {flag}\n{}",
+            crate::usage()
+        ));
+    }
+    Ok(BenchmarkRange { start, end })
+}
+
+/// Removes the scratch directory however the run ends.
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Per-run pipeline state that `connect_pending` expects from the sync loop.
+struct Pipeline<'a> {
+    params: &'a ChainParams,
+    flags: ValidationFlags,
+    verify_settings: &'a VerifySettings,
+    sync_metrics: SyncMetrics,
+    connect_metrics: ConnectMetrics,
+    mempool: Mutex<mempool::Mempool>,
+    fee_estimator: Mutex<fee_estimator::FeeEstimator>,
+    write_lock: Mutex<()>,
+    header_cursor: Arc<Mutex<HeaderCursor>>,
+}
+
+impl<'a> Pipeline<'a> {
+    fn new(
+        config: &Config,
+        params: &'a ChainParams,
+        flags: ValidationFlags,
+        verify_settings: &'a VerifySettings,
+    ) -> Self {
+        Self {
+            params,
+            flags,
+            verify_settings,
+            sync_metrics: SyncMetrics::default(),
+            connect_metrics: ConnectMetrics::default(),
+            mempool: Mutex::new(mempool::Mempool::new(config.mempool_max_bytes)),
+            fee_estimator: Mutex::new(fee_estimator::FeeEstimator::new(
+                config.min_relay_fee_per_kb,
+            )),
+            write_lock: Mutex::new(()),
+            header_cursor: Arc::new(Mutex::new(HeaderCursor::default())),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReplayTotals {
+    blocks: u64,
+    txs: u64,
+    connect_time: Duration,
+}
+
+pub(crate) fn run<S: KeyValueStore>(
+    source: &ChainState<S>,
+    config: &Config,
+    params: &ChainParams,
+    flags: &ValidationFlags,
+    verify_settings: &VerifySettings,
+    range: BenchmarkRange,
+) -> Result<(), String> {
+    let tip = source
+        .best_block()
+        .map_err(|err| err.to_string())?
+        .map(|tip| tip.height)
+        .unwrap_or(-1);
+    if range.end > tip {
+        return Err(format!(
+            "--benchmark-connect end height {} is above the local block tip {tip}",
+            range.end
+        ));
+    }
+
+    let scratch = ScratchDir {
+        path: config.data_dir.join(BENCHMARK_DIR_NAME),
+    };
+    if scratch.path.exists() {
+        fs::remove_dir_all(&scratch.path)
+            .map_err(|err| format!("failed to clear {}: {err}", scratch.path.display()))?;
+    }
+    fs::create_dir_all(&scratch.path)
+        .map_err(|err| format!("failed to create {}: {err}", scratch.path.display()))?;
+    let blocks = FlatFileStore::new(&scratch.path, DEFAULT_MAX_FLATFILE_SIZE)
+        .map_err(|err| err.to_string())?;
+    let undo = FlatFileStore::new_with_prefix(&scratch.path, "undo", DEFAULT_MAX_FLATFILE_SIZE)
+        .map_err(|err| err.to_string())?;
+    let target = ChainState::new_with_utxo_cache_capacity(
+        Arc::new(MemoryStore::new()),
+        blocks,
+        undo,
+        config.utxo_cache_entries,
+    );
+    target
+        .set_utxo_flush_policy(UtxoFlushPolicy {
+            max_dirty_entries: config.utxo_flush_entries,
+            max_dirty_bytes: config.utxo_flush_mb.saturating_mul(1024 * 1024),
+        })
+        .map_err(|err| err.to_string())?;
+
+    if range.start > 0 {
+        log_info!(
+            "Benchmark: connecting heights 0..{} unmeasured to build the UTXO set",
+            range.start
+        );
+        let mut warmup_flags = flags.clone();
+        warmup_flags.metrics = None;
+        let warmup = Pipeline::new(config, params, warmup_flags, verify_settings);
+        replay(source, &target, &warmup, 0, range.start - 1)?;
+    }
+
+    log_info!(
+        "Benchmark: connecting heights {}..={}",
+        range.start,
+        range.end
+    );
+    let validation_metrics = Arc::new(ValidationMetrics::default());
+    let mut measured_flags = flags.clone();
+    measured_flags.metrics = Some(Arc::clone(&validation_metrics));
+    let measured = Pipeline::new(config, params, measured_flags, verify_settings);
+    let start_time = Instant::now();
+    let totals = replay(source, &target, &measured, range.start, range.end)?;
+    let snapshot = snapshot_stats(
+        &target,
+        None,
+        config.network,
+        Backend::Memory,
+        start_time,
+        Some(&measured.sync_metrics),
+        None,
+        Some(validation_metrics.as_ref()),
+        Some(&measured.connect_metrics),
+        None,
+        None,
+    )?;
+    print_report(config, verify_settings, range, &totals, &snapshot);
+    Ok(())
+}
+
+/// Copies heights `start..=end` from `source` into `target` in batches, timing only the connect
+/// pipeline itself.
+fn replay<S: KeyValueStore>(
+    source: &ChainState<S>,
+    target: &ChainState<MemoryStore>,
+    pipeline: &Pipeline<'_>,
+    start: i32,
+    end: i32,
+) -> Result<ReplayTotals, String> {
+    let mut totals = ReplayTotals::default();
+    let mut batch_start = start;
+    while batch_start <= end {
+        let batch_end = batch_start
+            .saturating_add(BENCHMARK_BATCH_BLOCKS - 1)
+            .min(end);
+        let mut pending = VecDeque::new();
+        let mut received = HashMap::new();
+        let mut headers = Vec::new();
+        for height in batch_start..=batch_end {
+            let hash = source
+                .height_hash(height)
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| format!("missing block hash at height {height}"))?
+                .to_internal();
+            let location = source
+                .block_location(&hash)
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| format!("missing block data at height {height}"))?;
+            let bytes = source.read_block(location).map_err(|err| err.to_string())?;
+            let block = Block::consensus_decode(&bytes)
+                .map_err(|_| format!("invalid block encoding at height {height}"))?;
+            totals.txs += block.transactions.len() as u64;
+            headers.push(block.header.clone());
+            pending.push_back(hash);
+            received.insert(hash, ReceivedBlock { block, bytes });
+        }
+
+        let mut batch = WriteBatch::new();
+        target
+            .insert_headers_batch_with_pow(&headers, &pipeline.params.consensus, &mut batch, false)
+            .map_err(|err| format!("benchmark header insert failed: {err}"))?;
+        target.commit_batch(batch).map_err(|err| err.to_string())?;
+
+        let connect_start = Instant::now();
+        crate::connect_pending(
+            target,
+            &pipeline.mempool,
+            &pipeline.fee_estimator,
+            pipeline.params,
+            &pipeline.flags,
+            &pipeline.sync_metrics,
+            pipeline.verify_settings,
+            &pipeline.connect_metrics,
+            &pipeline.write_lock,
+            &pipeline.header_cursor,
+            &mut pending,
+            &mut received,
+        )?;
+        totals.connect_time += connect_start.elapsed();
+        if !pending.is_empty() {
+            return Err(format!(
+                "benchmark stopped with {} block(s) of heights {batch_start}..={batch_end} unconnected",
+                pending.len()
+            ));
+        }
+        totals.blocks += u64::try_from(batch_end - batch_start + 1).unwrap_or(0);
+        batch_start = batch_end.saturating_add(1);
+    }
+    Ok(totals)
+}
+
+fn print_report(
+    config: &Config,
+    verify_settings: &VerifySettings,
+    range: BenchmarkRange,
+    totals: &ReplayTotals,
+    snapshot: &StatsSnapshot,
+) {
+    let secs = totals.connect_time.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "Benchmark heights {}..={}: {} blocks, {} txs in {:.3}s",
+        range.start, range.end, totals.blocks, totals.txs, secs
+    );
+    println!(
+        "  {:.1} blocks/s, {:.1} txs/s",
+        totals.blocks as f64 / secs,
+        totals.txs as f64 / secs
+    );
+    println!(
+        "  verify_workers={} shielded_workers={} verify_queue={} utxo_cache_entries={} utxo_flush_entries={} utxo_flush_mb={}",
+        verify_settings.verify_workers,
+        verify_settings.shielded_workers,
+        verify_settings.verify_queue,
+        config.utxo_cache_entries,
+        config.utxo_flush_entries,
+        config.utxo_flush_mb
+    );
+    println!("  {:<14} {:>12} {:>10}", "stage", "total_ms", "count");
+    for (stage, micros, count) in stage_breakdown(snapshot) {
+        println!("  {stage:<14} {:>12.1} {count:>10}", micros as f64 / 1000.0);
+    }
+}
+
+/// Per-stage `(name, total microseconds, count)` rows from the connect and validation metrics.
+fn stage_breakdown(snapshot: &StatsSnapshot) -> Vec<(&'static str, u64, u64)> {
+    vec![
+        ("verify", snapshot.verify_us, snapshot.verify_blocks),
+        ("commit", snapshot.commit_us, snapshot.commit_blocks),
+        ("validate", snapshot.validate_us, snapshot.validate_blocks),
+        ("script", snapshot.script_us, snapshot.script_blocks),
+        ("shielded", snapshot.shielded_us, snapshot.shielded_txs),
+        ("utxo", snapshot.utxo_us, snapshot.utxo_blocks),
+        ("index", snapshot.index_us, snapshot.index_blocks),
+        ("anchor", snapshot.anchor_us, snapshot.anchor_blocks),
+        ("flatfile", snapshot.flatfile_us, snapshot.flatfile_blocks),
+        (
+            "undo_encode",
+            snapshot.undo_encode_us,
+            snapshot.commit_blocks,
+        ),
+        (
+            "undo_append",
+            snapshot.undo_append_us,
+            snapshot.commit_blocks,
+        ),
+        (
+            "fluxnode_tx",
+            snapshot.fluxnode_tx_us,
+            snapshot.fluxnode_tx_count,
+        ),
+        ("pon_sig", snapshot.pon_sig_us, snapshot.pon_sig_blocks),
+        ("payout", snapshot.payout_us, snapshot.payout_blocks),
+        ("utxo_flush", snapshot.utxo_flush_us, snapshot.utxo_flushes),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<BenchmarkRange, String> {
+        parse_range(
+            "--benchmark-connect",
+            &mut args.iter().map(|arg| arg.to_string()),
+        )
+    }
+
+    #[test]
+    fn parse_range_validates_heights() {
+        assert_eq!(
+            parse(&["10", "20"]).expect("range"),
+            BenchmarkRange { start: 10, end: 20 }
+        );
+        assert!(parse(&["20", "10"])
+            .unwrap_err()
+            .contains("invalid height range"));
+        assert!(parse(&["-1", "10"])
+            .unwrap_err()
+            .contains("invalid height range"));
+        assert!(parse(&["10"]).unwrap_err().contains("missing end height"));
+    }
+}
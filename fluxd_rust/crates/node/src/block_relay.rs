@@ -0,0 +1,230 @@
+//! Outbound block-relay-only peers (`--block-relay-peers`).
+//!
+//! These connections advertise `relay=false`, never send `feefilter`, `mempool` or `getaddr`, and
+//! ignore tx and addr traffic. They only follow block announcements: an unknown block `inv`
+//! triggers a `getheaders`, and headers that extend the index are validated and handed to the
+//! header commit pipeline. Keeping a few of them connected preserves block propagation even if
+//! the tx relay peers are flooded or eclipsed, and leaks nothing about our mempool.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use fluxd_chainstate::state::{ChainState, HeaderValidationCache};
+use fluxd_consensus::params::ChainParams;
+use fluxd_consensus::Hash256;
+use fluxd_primitives::block::BlockHeader;
+use fluxd_primitives::hash::hash256_to_hex;
+use fluxd_storage::KeyValueStore;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::p2p::{parse_headers, parse_inv, Peer, MSG_BLOCK};
+
+const BLOCK_RELAY_RECONNECT_DELAY_SECS: u64 = 5;
+
+pub async fn block_relay_loop<S: KeyValueStore + Send + Sync + 'static>(
+    chainstate: Arc<ChainState<S>>,
+    params: Arc<ChainParams>,
+    addr_book: Arc<crate::AddrBook>,
+    peer_ctx: crate::PeerContext,
+    header_tx: mpsc::Sender<Vec<BlockHeader>>,
+    peer_target: usize,
+) -> Result<(), String> {
+    if peer_target == 0 {
+        return Ok(());
+    }
+
+    let mut join_set: JoinSet<Result<(), String>> = JoinSet::new();
+    loop {
+        while join_set.len() < peer_target {
+            let start_height = crate::start_height(chainstate.as_ref())?;
+            let min_height = chainstate
+                .best_header()
+                .map_err(|err| err.to_string())?
+                .map(|tip| tip.height)
+                .unwrap_or(start_height);
+            let need = peer_target - join_set.len();
+            match crate::connect_to_peers(
+                params.as_ref(),
+                need,
+                start_height,
+                min_height,
+                Some(addr_book.as_ref()),
+                &peer_ctx,
+                None,
+            )
+            .await
+            {
+                Ok(peers) => {
+                    for peer in peers {
+                        let chainstate = Arc::clone(&chainstate);
+                        let params = Arc::clone(&params);
+                        let addr_book = Arc::clone(&addr_book);
+                        let header_tx = header_tx.clone();
+                        join_set.spawn(async move {
+                            let addr = peer.addr();
+                            let result =
+                                block_relay_peer(peer, chainstate, params, header_tx).await;
+                            if let Err(err) = &result {
+                                addr_book.record_failure(addr);
+                                log_warn!("block relay peer {addr} stopped: {err}");
+                            }
+                            result
+                        });
+                    }
+                }
+                Err(err) => {
+                    log_warn!("block relay connect failed: {err}");
+                    tokio::time::sleep(Duration::from_secs(BLOCK_RELAY_RECONNECT_DELAY_SECS)).await;
+                    break;
+                }
+            }
+        }
+
+        match join_set.join_next().await {
+            Some(Ok(_)) => {}
+            Some(Err(err)) => {
+                log_warn!("block relay join failed: {err}");
+            }
+            None => {
+                tokio::time::sleep(Duration::from_secs(BLOCK_RELAY_RECONNECT_DELAY_SECS)).await;
+            }
+        }
+    }
+}
+
+async fn block_relay_peer<S: KeyValueStore>(
+    mut peer: Peer,
+    chainstate: Arc<ChainState<S>>,
+    params: Arc<ChainParams>,
+    header_tx: mpsc::Sender<Vec<BlockHeader>>,
+) -> Result<(), String> {
+    loop {
+        if peer.take_disconnect_request() {
+            let addr = peer.addr();
+            log_info!("Disconnect requested for block relay peer {addr}");
+            return Ok(());
+        }
+        peer.maybe_send_ping().await?;
+        let (command, payload) = peer.read_message().await?;
+        match command.as_str() {
+            "inv" => {
+                let vectors = parse_inv(&payload)?;
+                let mut unknown = false;
+                for vector in vectors.iter().filter(|vector| vector.inv_type == MSG_BLOCK) {
+                    if !header_known(chainstate.as_ref(), &vector.hash)? {
+                        unknown = true;
+                        break;
+                    }
+                }
+                if unknown {
+                    let locator = crate::build_locator(
+                        chainstate.as_ref(),
+                        &params.consensus.hash_genesis_block,
+                    )?;
+                    peer.send_getheaders(&locator).await?;
+                }
+            }
+            "headers" => {
+                let headers = parse_headers(&payload)?;
+                let headers = trim_known_headers(headers, |hash| {
+                    header_known(chainstate.as_ref(), hash).unwrap_or(false)
+                });
+                let Some(first) = headers.first() else {
+                    continue;
+                };
+                if !header_known(chainstate.as_ref(), &first.prev_block)? {
+                    let locator = crate::build_locator(
+                        chainstate.as_ref(),
+                        &params.consensus.hash_genesis_block,
+                    )?;
+                    peer.send_getheaders(&locator).await?;
+                    continue;
+                }
+                chainstate
+                    .validate_headers_batch_with_cache(
+                        &headers,
+                        &params.consensus,
+                        &mut HashMap::new(),
+                        true,
+                        &mut HeaderValidationCache::default(),
+                    )
+                    .map_err(|err| format!("invalid announced headers: {err}"))?;
+                if let Some(last) = headers.last() {
+                    log_debug!(
+                        "Block relay peer {} announced {} header(s) up to {}",
+                        peer.addr(),
+                        headers.len(),
+                        hash256_to_hex(&last.hash())
+                    );
+                }
+                if header_tx.send(headers).await.is_err() {
+                    return Ok(());
+                }
+            }
+            // Tx, addr and mempool traffic is deliberately ignored on these connections.
+            _ => crate::handle_aux_message(&mut peer, &command, &payload).await?,
+        }
+    }
+}
+
+fn header_known<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    hash: &Hash256,
+) -> Result<bool, String> {
+    chainstate
+        .has_header_entry(hash)
+        .map_err(|err| err.to_string())
+}
+
+/// Drops the leading headers we already have, so the batch starts at the first new header.
+fn trim_known_headers(
+    headers: Vec<BlockHeader>,
+    is_known: impl Fn(&Hash256) -> bool,
+) -> Vec<BlockHeader> {
+    let skip = headers
+        .iter()
+        .take_while(|header| is_known(&header.hash()))
+        .count();
+    headers.into_iter().skip(skip).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluxd_primitives::outpoint::OutPoint;
+    use std::collections::HashSet;
+
+    fn header(prev_block: Hash256, time: u32) -> BlockHeader {
+        BlockHeader {
+            version: 4,
+            prev_block,
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time,
+            bits: 0x1f07ffff,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn trims_only_leading_known_headers() {
+        let first = header([0u8; 32], 1);
+        let second = header(first.hash(), 2);
+        let third = header(second.hash(), 3);
+        let known: HashSet<Hash256> = [first.hash(), third.hash()].into_iter().collect();
+
+        let trimmed = trim_known_headers(vec![first, second.clone(), third.clone()], |hash| {
+            known.contains(hash)
+        });
+        assert_eq!(
+            trimmed.iter().map(BlockHeader::hash).collect::<Vec<_>>(),
+            vec![second.hash(), third.hash()]
+        );
+        assert!(trim_known_headers(vec![third], |hash| known.contains(hash)).is_empty());
+    }
+}
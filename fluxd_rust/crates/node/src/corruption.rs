@@ -0,0 +1,185 @@
+//! `--reindex-on-corruption`: turns a fatal store inconsistency into an automatic reindex.
+//!
+//! Errors reach `run_entry` as strings, so detection matches the messages raised by the
+//! chainstate and flatfile readers. Damage confined to `db/` (index entries, undo data, tree
+//! state, UTXO bookkeeping) is rebuilt by replaying `blocks/`, so the node writes `reindex.flag`
+//! and exits for its supervisor to start it again. Damage to the block flatfiles themselves
+//! cannot be repaired that way: a reindex would replay the same bad bytes, so the node stops and
+//! asks for operator action.
+
+use std::fs;
+use std::path::Path;
+
+/// Written next to `reindex.flag` and removed once the flatfile replay finishes, so corruption
+/// that comes back during the rebuild is not retried.
+pub(crate) const AUTO_REINDEX_MARKER_FILE_NAME: &str = "reindex-on-corruption.marker";
+
+/// Messages that mean `db/` disagrees with itself or with the flatfiles it indexes.
+const RECOVERABLE_MARKERS: &[&str] = &[
+    "invalid header index entry",
+    "invalid block index entry",
+    "missing block index entry",
+    "missing height index entry",
+    "invalid block undo entry",
+    "block undo input mismatch",
+    "invalid sprout undo tree",
+    "invalid sapling undo tree",
+    "invalid sprout anchor tree",
+    "invalid sprout tree",
+    "invalid sapling tree",
+    "missing sprout tree state",
+    "missing sapling tree state",
+    "invalid utxo",
+    "utxo stats mismatch",
+    "invalid value pools",
+    "invalid index stats",
+    "invalid fluxnode record",
+    "invalid fluxnode index key",
+    "invalid fluxnode delegates entry",
+    "invalid flatfile info entry",
+    "invalid address delta entry",
+    "invalid address balance meta",
+    "invalid address tx cursor",
+];
+
+/// Messages that mean the block flatfiles are missing or damaged.
+const UNRECOVERABLE_MARKERS: &[&str] = &[
+    "invalid block bytes",
+    "block bytes missing",
+    "block bytes length mismatch",
+    "flatfile length mismatch",
+    "invalid flatfile location",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Corruption {
+    /// `db/` can be rebuilt from `blocks/`.
+    Recoverable,
+    /// `blocks/` itself is damaged.
+    Unrecoverable,
+}
+
+pub(crate) fn classify(err: &str) -> Option<Corruption> {
+    let err = err.to_lowercase();
+    if UNRECOVERABLE_MARKERS
+        .iter()
+        .any(|marker| err.contains(marker))
+    {
+        return Some(Corruption::Unrecoverable);
+    }
+    if RECOVERABLE_MARKERS
+        .iter()
+        .any(|marker| err.contains(marker))
+    {
+        return Some(Corruption::Recoverable);
+    }
+    None
+}
+
+/// Decides what to do with the error a node run stopped on. Returns `Ok(true)` once
+/// `reindex.flag` has been written and the process should exit so it is started again.
+///
+/// No second reindex is scheduled while an automatic one is unfinished: corruption that survives
+/// a rebuild points at hardware or flatfile trouble that another pass will not fix.
+pub(crate) fn schedule_reindex(data_dir: &Path, err: &str) -> Result<bool, String> {
+    let marker_path = data_dir.join(AUTO_REINDEX_MARKER_FILE_NAME);
+    match classify(err) {
+        None => Ok(false),
+        Some(Corruption::Unrecoverable) => {
+            log_error!("==== BLOCK DATA CORRUPTION DETECTED: {err} ====");
+            log_error!(
+                "Block flatfiles under {} are missing or damaged; an automatic reindex would replay the same data. Restore blocks/ from a backup or restart with --resync.",
+                data_dir.join("blocks").display()
+            );
+            Ok(false)
+        }
+        Some(Corruption::Recoverable) if marker_path.exists() => {
+            log_error!("==== DATABASE CORRUPTION DETECTED AGAIN: {err} ====");
+            log_error!(
+                "Corruption came back during an automatic reindex; not retrying. Check the disk and memory of this host, then remove {} and restart with --reindex or --resync.",
+                marker_path.display()
+            );
+            Ok(false)
+        }
+        Some(Corruption::Recoverable) => {
+            let flag_path = data_dir.join(crate::REINDEX_REQUEST_FILE_NAME);
+            log_error!("==== DATABASE CORRUPTION DETECTED: {err} ====");
+            log_error!(
+                "--reindex-on-corruption: writing {} and exiting; the next start rebuilds db/ from the block flatfiles (blocks/ is preserved).",
+                flag_path.display()
+            );
+            for path in [&marker_path, &flag_path] {
+                fs::write(path, b"reindex\n").map_err(|write_err| {
+                    format!("failed to write {}: {write_err}", path.display())
+                })?;
+            }
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    fn classifies_db_and_flatfile_damage() {
+        assert_eq!(
+            classify("invalid block index entry"),
+            Some(Corruption::Recoverable)
+        );
+        assert_eq!(
+            classify("connect failed: block undo input mismatch"),
+            Some(Corruption::Recoverable)
+        );
+        assert_eq!(
+            classify("Invalid header index entry in batch"),
+            Some(Corruption::Recoverable)
+        );
+        assert_eq!(
+            classify("invalid block bytes"),
+            Some(Corruption::Unrecoverable)
+        );
+        assert_eq!(
+            classify("flatfile length mismatch"),
+            Some(Corruption::Unrecoverable)
+        );
+        assert_eq!(classify("peer stalled"), None);
+        assert_eq!(classify("missing input"), None);
+        assert_eq!(classify("write lock poisoned"), None);
+    }
+
+    #[test]
+    fn schedules_no_second_reindex_until_the_first_completes() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "fluxd-reindex-on-corruption-{}-{nanos}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).expect("create dir");
+        let flag_path = dir.join(crate::REINDEX_REQUEST_FILE_NAME);
+        let marker_path = dir.join(AUTO_REINDEX_MARKER_FILE_NAME);
+
+        assert!(!schedule_reindex(&dir, "peer stalled").expect("schedule"));
+        assert!(!schedule_reindex(&dir, "invalid block bytes").expect("schedule"));
+        assert!(!flag_path.exists());
+
+        assert!(schedule_reindex(&dir, "invalid utxo flush tip").expect("schedule"));
+        assert!(flag_path.exists());
+        assert!(marker_path.exists());
+
+        // The restarted node consumes the flag; corruption during its rebuild is not retried.
+        fs::remove_file(&flag_path).expect("consume flag");
+        assert!(!schedule_reindex(&dir, "invalid utxo flush tip").expect("schedule"));
+        assert!(!flag_path.exists());
+
+        fs::remove_file(&marker_path).expect("finish reindex");
+        assert!(schedule_reindex(&dir, "invalid utxo flush tip").expect("schedule"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,4 @@
 use std::collections::BTreeMap;
-use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
@@ -8,12 +7,12 @@ use fluxd_chainstate::state::ChainState;
 use fluxd_consensus::params::Network;
 use fluxd_storage::KeyValueStore;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpListener;
 
 use fluxd_chainstate::validation::ValidationMetrics;
 
+use crate::listen::{self, ListenAddr};
 use crate::p2p::{NetTotals, PeerKind, PeerRegistry};
-use crate::stats::{snapshot_stats, HeaderMetrics, SyncMetrics};
+use crate::stats::{snapshot_health, snapshot_stats, HeaderMetrics, SyncMetrics};
 use crate::Backend;
 use crate::Store;
 use crate::{mempool::Mempool, stats::MempoolMetrics};
@@ -23,7 +22,7 @@ const MAX_REQUEST_BYTES: usize = 8192;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn serve_dashboard<S: KeyValueStore + Send + Sync + 'static>(
-    addr: SocketAddr,
+    addr: ListenAddr,
     chainstate: Arc<ChainState<S>>,
     store: Arc<Store>,
     metrics: Arc<SyncMetrics>,
@@ -37,11 +36,16 @@ pub async fn serve_dashboard<S: KeyValueStore + Send + Sync + 'static>(
     network: Network,
     backend: Backend,
     start_time: Instant,
+    ready_max_lag: u32,
 ) -> Result<(), String> {
-    let listener = TcpListener::bind(addr)
+    let listener = addr
+        .bind()
         .await
-        .map_err(|err| format!("dashboard bind failed: {err}"))?;
-    log_info!("Dashboard listening on http://{addr}");
+        .map_err(|err| format!("dashboard bind {addr} failed: {err}"))?;
+    match &addr {
+        ListenAddr::Tcp(tcp_addr) => log_info!("Dashboard listening on http://{tcp_addr}"),
+        ListenAddr::Unix(_) => log_info!("Dashboard listening on {addr}"),
+    }
 
     loop {
         let (stream, _) = listener
@@ -74,6 +78,7 @@ pub async fn serve_dashboard<S: KeyValueStore + Send + Sync + 'static>(
                 network,
                 backend,
                 start_time,
+                ready_max_lag,
             )
             .await
             {
@@ -85,7 +90,7 @@ pub async fn serve_dashboard<S: KeyValueStore + Send + Sync + 'static>(
 
 #[allow(clippy::too_many_arguments)]
 async fn handle_connection<S: KeyValueStore + Send + Sync + 'static>(
-    mut stream: tokio::net::TcpStream,
+    mut stream: listen::Stream,
     chainstate: Arc<ChainState<S>>,
     store: Arc<Store>,
     metrics: Arc<SyncMetrics>,
@@ -99,6 +104,7 @@ async fn handle_connection<S: KeyValueStore + Send + Sync + 'static>(
     network: Network,
     backend: Backend,
     start_time: Instant,
+    ready_max_lag: u32,
 ) -> Result<(), String> {
     let mut buffer = vec![0u8; MAX_REQUEST_BYTES];
     let bytes_read = stream
@@ -194,6 +200,10 @@ async fn handle_connection<S: KeyValueStore + Send + Sync + 'static>(
                 version: i32,
                 start_height: i32,
                 user_agent: String,
+                last_ping_ms: Option<u64>,
+                min_ping_ms: Option<u64>,
+                bytes_sent: u64,
+                bytes_recv: u64,
             }
             let peers = peer_registry.snapshot();
             let view = peers
@@ -204,12 +214,17 @@ async fn handle_connection<S: KeyValueStore + Send + Sync + 'static>(
                         PeerKind::Block => "block",
                         PeerKind::Header => "header",
                         PeerKind::Relay => "relay",
+                        PeerKind::BlockRelayOnly => "block-relay-only",
                     }
                     .to_string(),
                     inbound: peer.inbound,
                     version: peer.version,
                     start_height: peer.start_height,
                     user_agent: peer.user_agent,
+                    last_ping_ms: peer.last_ping_ms,
+                    min_ping_ms: peer.min_ping_ms,
+                    bytes_sent: peer.bytes_sent,
+                    bytes_recv: peer.bytes_recv,
                 })
                 .collect::<Vec<_>>();
             match serde_json::to_string(&view) {
@@ -302,6 +317,34 @@ async fn handle_connection<S: KeyValueStore + Send + Sync + 'static>(
             }
         },
         ("GET", "/healthz") => ("200 OK", "text/plain; charset=utf-8", "ok".to_string()),
+        ("GET", "/health") | ("GET", "/ready") => {
+            let health = snapshot_health(
+                &chainstate,
+                store.as_ref(),
+                &header_metrics,
+                peer_registry.count(),
+                start_time,
+                ready_max_lag,
+            );
+            let passing = if path == "/ready" {
+                health.ready
+            } else {
+                health.healthy
+            };
+            let status = if passing {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            match serde_json::to_string(&health) {
+                Ok(body) => (status, "application/json", body),
+                Err(err) => (
+                    "500 Internal Server Error",
+                    "text/plain; charset=utf-8",
+                    format!("health error: {err}"),
+                ),
+            }
+        }
         _ => (
             "404 Not Found",
             "text/plain; charset=utf-8",
@@ -549,6 +592,10 @@ const DASHBOARD_HTML: &str = r#"<!doctype html>
           <div class="label">Sync Gap</div>
           <div class="value" id="headerGap">0</div>
         </div>
+        <div class="card">
+          <div class="label">Network Hashrate</div>
+          <div class="value" id="networkHashrate">-</div>
+        </div>
 
         <div class="card">
           <div class="label">Headers / Sec</div>
@@ -575,6 +622,18 @@ const DASHBOARD_HTML: &str = r#"<!doctype html>
           <div class="label">Storage (ms)</div>
           <div class="value" id="commitMs">0</div>
         </div>
+        <div class="card">
+          <div class="label">Download p50 / p95 (ms)</div>
+          <div class="value" id="downloadPct">-</div>
+        </div>
+        <div class="card">
+          <div class="label">Verify p50 / p95 (ms)</div>
+          <div class="value" id="verifyPct">-</div>
+        </div>
+        <div class="card">
+          <div class="label">Storage p50 / p95 (ms)</div>
+          <div class="value" id="commitPct">-</div>
+        </div>
         
         <div class="card">
           <div class="label">Validation (ms)</div>
@@ -622,6 +681,10 @@ const DASHBOARD_HTML: &str = r#"<!doctype html>
 	          <div class="value" id="payoutMs">0</div>
 	        </div>
 
+        <div class="card wide" id="dbLatencyCard" style="display: none">
+          <div class="label">DB Latency p50 / p95 (ms)</div>
+          <div class="value mono" id="dbLatency">--</div>
+        </div>
         <div class="card wide">
           <div class="label">Best Header Hash</div>
           <div class="value mono" id="bestHeaderHash">--</div>
@@ -713,6 +776,9 @@ const DASHBOARD_HTML: &str = r#"<!doctype html>
         $("bestBlockHeight").textContent = data.best_block_height.toLocaleString();
         $("headerGap").textContent = data.header_gap.toLocaleString();
         $("headerCount").textContent = data.header_count.toLocaleString();
+        $("networkHashrate").textContent = data.network_hashrate > 0
+          ? data.network_hashrate.toLocaleString(undefined, { maximumFractionDigits: 2 }) + " Sol/s"
+          : "-";
         
         $("blocksPerSec").textContent = blocksPerSec;
         $("headersPerSec").textContent = headersPerSec;
@@ -734,6 +800,26 @@ const DASHBOARD_HTML: &str = r#"<!doctype html>
         setMs("ponSigMs", ponSigMs);
         setMs("payoutMs", payoutMs);
 
+        const setPct = (id, prefix) => {
+          const p50 = data[`${prefix}_p50_us`];
+          const p95 = data[`${prefix}_p95_us`];
+          $(id).textContent = p50 == null || p95 == null
+            ? "-"
+            : `${(p50 / 1000).toFixed(2)} / ${(p95 / 1000).toFixed(2)}`;
+        };
+        setPct("downloadPct", "download");
+        setPct("verifyPct", "verify");
+        setPct("commitPct", "commit");
+
+        const dbLatency = data.db_latency || [];
+        $("dbLatencyCard").style.display = dbLatency.length ? "" : "none";
+        const fmtOp = (op) => op.count
+          ? `${(op.p50_us / 1000).toFixed(2)}/${(op.p95_us / 1000).toFixed(2)}`
+          : "-";
+        $("dbLatency").innerHTML = dbLatency
+          .map((row) => `${row.column}: get ${fmtOp(row.get)} · put ${fmtOp(row.put)} · scan ${fmtOp(row.scan)}`)
+          .join("<br>");
+
         $("bestHeaderHash").textContent = data.best_header_hash || "-";
         $("bestBlockHash").textContent = data.best_block_hash || "-";
 
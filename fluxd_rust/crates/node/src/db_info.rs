@@ -78,11 +78,8 @@ pub(crate) fn collect_db_info<S: KeyValueStore>(
     let blocks_dir_size = dir_size_cached(&blocks_dir, Duration::from_secs(30))?;
 
     let mut partitions = Vec::new();
-    let partitions_dir = db_dir.join("partitions");
     let mut partitions_total_bytes = 0u64;
-    for column in Column::ALL {
-        let path = partitions_dir.join(column.as_str());
-        let size_bytes = dir_size_cached(&path, Duration::from_secs(30))?;
+    for (column, size_bytes) in column_sizes(&db_dir)? {
         partitions_total_bytes = partitions_total_bytes.saturating_add(size_bytes);
         let mut entry = json!({
             "column": column.as_str(),
@@ -166,6 +163,13 @@ pub(crate) fn collect_db_info<S: KeyValueStore>(
             "address_delta_flushes_completed": snapshot.address_delta_flushes_completed,
             "header_index_segments": snapshot.header_index_segments,
             "header_index_flushes_completed": snapshot.header_index_flushes_completed,
+            "l0_segments": snapshot
+                .l0_segments
+                .iter()
+                .map(|(name, count)| (name.to_string(), json!(count)))
+                .collect::<serde_json::Map<String, Value>>(),
+            "max_l0_segments": snapshot.max_l0_segments,
+            "write_stalled_partitions": snapshot.write_stalled_partitions,
         })
     });
 
@@ -250,6 +254,7 @@ pub(crate) fn collect_db_info<S: KeyValueStore>(
         "db_partitions": partitions,
         "files": files,
         "fjall": fjall,
+        "latency": store.latency().map(crate::stats::db_latency_rows),
         "integrity": {
             "ok": integrity_ok,
             "issues": integrity_issues,
@@ -279,6 +284,21 @@ fn column_key_stats<S: KeyValueStore>(store: &S, column: Column) -> Result<Colum
     Ok(stats)
 }
 
+/// Compares the flatfile metadata recorded in the store with the files under `blocks_dir`.
+pub(crate) fn flatfile_integrity(
+    store: &Store,
+    blocks_dir: &Path,
+) -> Result<(bool, Vec<String>, Vec<String>), String> {
+    let block_meta = scan_flatfile_meta_blocks(store)?;
+    let undo_meta = scan_flatfile_meta_undo(store)?;
+    let blocks_fs = scan_blocks_dir_fs(blocks_dir)?;
+    Ok(flatfile_integrity_summary(
+        &block_meta,
+        &undo_meta,
+        &blocks_fs,
+    ))
+}
+
 fn flatfile_integrity_summary(
     blocks: &FlatfileMetaSummary,
     undo: &FlatfileMetaSummary,
@@ -462,6 +482,29 @@ fn parse_flatfile_id(name: &str, prefix: &str) -> Option<u32> {
     numeric.parse::<u32>().ok()
 }
 
+/// Bytes on disk per column partition under `db_dir`. Backends without partition directories
+/// report zero for every column.
+pub(crate) fn column_sizes(db_dir: &Path) -> Result<Vec<(Column, u64)>, String> {
+    let partitions_dir = db_dir.join("partitions");
+    Column::ALL
+        .into_iter()
+        .map(|column| {
+            let path = partitions_dir.join(column.as_str());
+            dir_size_cached(&path, Duration::from_secs(30)).map(|size| (column, size))
+        })
+        .collect()
+}
+
+/// The `size_on_disk` reported by `getblockchaininfo`: the column partitions plus the block and
+/// undo flatfiles.
+pub(crate) fn size_on_disk(data_dir: &Path) -> Result<u64, String> {
+    let columns_bytes = column_sizes(&data_dir.join("db"))?
+        .into_iter()
+        .fold(0u64, |total, (_, size)| total.saturating_add(size));
+    let blocks_bytes = dir_size_cached(&data_dir.join("blocks"), Duration::from_secs(30))?;
+    Ok(columns_bytes.saturating_add(blocks_bytes))
+}
+
 pub(crate) fn dir_size_cached(path: &Path, ttl: Duration) -> Result<u64, String> {
     static CACHE: OnceLock<Mutex<BTreeMap<PathBuf, (Instant, u64)>>> = OnceLock::new();
     let cache = CACHE.get_or_init(|| Mutex::new(BTreeMap::new()));
@@ -0,0 +1,408 @@
+//! `--doctor`: validates the environment up front and reports pass/fail per check.
+//!
+//! Runs the same checks startup performs (data dir lock, shielded params, store open, schema
+//! versions, flatfile metadata) without stopping at the first failure and without touching the
+//! network. Schema versions are only read here; startup is still the one that stamps them.
+
+use std::fs;
+use std::path::Path;
+
+use fluxd_consensus::params::Network;
+use fluxd_shielded::verify_params;
+use fluxd_storage::Column;
+
+use crate::{db_info, Config, DataDirLock, Store};
+
+/// Rough on-disk size of a fully synced node (flatfiles, undo data, db and indexes).
+const ESTIMATED_MAINNET_CHAIN_BYTES: u64 = 80 * GIB;
+const ESTIMATED_TESTNET_CHAIN_BYTES: u64 = 16 * GIB;
+const ESTIMATED_REGTEST_CHAIN_BYTES: u64 = GIB;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+const WRITE_PROBE_FILE_NAME: &str = ".doctor-write-probe";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CheckStatus {
+    Pass,
+    Warn,
+    Skip,
+    Fail,
+}
+
+impl CheckStatus {
+    fn label(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Skip => "SKIP",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CheckResult {
+    pub(crate) name: &'static str,
+    pub(crate) status: CheckStatus,
+    pub(crate) detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Runs every check, prints the report and fails if any critical check failed.
+pub(crate) fn run(config: &Config) -> Result<(), String> {
+    let results = run_checks(config);
+    for result in &results {
+        println!(
+            "[{}] {}: {}",
+            result.status.label(),
+            result.name,
+            result.detail
+        );
+    }
+    let failed = results
+        .iter()
+        .filter(|result| result.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        return Err(format!("doctor: {failed} critical check(s) failed"));
+    }
+    println!("doctor: all critical checks passed");
+    Ok(())
+}
+
+pub(crate) fn run_checks(config: &Config) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    let data_dir = &config.data_dir;
+
+    let lock = match check_data_dir(data_dir) {
+        Ok(lock) => {
+            results.push(CheckResult::new(
+                "data dir writable and lockable",
+                CheckStatus::Pass,
+                data_dir.display().to_string(),
+            ));
+            Some(lock)
+        }
+        Err(err) => {
+            results.push(CheckResult::new(
+                "data dir writable and lockable",
+                CheckStatus::Fail,
+                err,
+            ));
+            None
+        }
+    };
+
+    results.push(match verify_params(&config.params_dir, config.network) {
+        Ok(_) => CheckResult::new(
+            "shielded params",
+            CheckStatus::Pass,
+            format!("checksums match in {}", config.params_dir.display()),
+        ),
+        Err(err) => CheckResult::new("shielded params", CheckStatus::Fail, err.to_string()),
+    });
+
+    results.push(check_disk_space(data_dir, config.network));
+
+    if lock.is_none() {
+        for name in ["database open", "schema versions", "flatfile consistency"] {
+            results.push(CheckResult::new(
+                name,
+                CheckStatus::Skip,
+                "data dir is not lockable",
+            ));
+        }
+        return results;
+    }
+
+    let store = match crate::open_store(config.backend, &data_dir.join("db"), config) {
+        Ok(store) => {
+            results.push(CheckResult::new(
+                "database open",
+                CheckStatus::Pass,
+                format!("{:?} backend", config.backend),
+            ));
+            store
+        }
+        Err(err) => {
+            results.push(CheckResult::new("database open", CheckStatus::Fail, err));
+            for name in ["schema versions", "flatfile consistency"] {
+                results.push(CheckResult::new(
+                    name,
+                    CheckStatus::Skip,
+                    "database did not open",
+                ));
+            }
+            return results;
+        }
+    };
+
+    results.push(
+        check_schema_versions(&store)
+            .unwrap_or_else(|err| CheckResult::new("schema versions", CheckStatus::Fail, err)),
+    );
+
+    results.push(
+        match db_info::flatfile_integrity(&store, &data_dir.join("blocks")) {
+            Ok((true, _, warnings)) if warnings.is_empty() => CheckResult::new(
+                "flatfile consistency",
+                CheckStatus::Pass,
+                "metadata matches blocks dir",
+            ),
+            Ok((true, _, warnings)) => CheckResult::new(
+                "flatfile consistency",
+                CheckStatus::Warn,
+                warnings.join("; "),
+            ),
+            Ok((false, issues, _)) => {
+                CheckResult::new("flatfile consistency", CheckStatus::Fail, issues.join("; "))
+            }
+            Err(err) => CheckResult::new("flatfile consistency", CheckStatus::Fail, err),
+        },
+    );
+
+    results
+}
+
+fn check_data_dir(data_dir: &Path) -> Result<DataDirLock, String> {
+    fs::create_dir_all(data_dir)
+        .map_err(|err| format!("failed to create {}: {err}", data_dir.display()))?;
+    let probe_path = data_dir.join(WRITE_PROBE_FILE_NAME);
+    fs::write(&probe_path, b"ok")
+        .map_err(|err| format!("{} is not writable: {err}", data_dir.display()))?;
+    let _ = fs::remove_file(&probe_path);
+    crate::lock_data_dir(data_dir)
+}
+
+fn check_disk_space(data_dir: &Path, network: Network) -> CheckResult {
+    let estimate = match network {
+        Network::Mainnet => ESTIMATED_MAINNET_CHAIN_BYTES,
+        Network::Testnet => ESTIMATED_TESTNET_CHAIN_BYTES,
+        Network::Regtest => ESTIMATED_REGTEST_CHAIN_BYTES,
+    };
+    let used = db_info::dir_size(&data_dir.join("db"))
+        .unwrap_or(0)
+        .saturating_add(db_info::dir_size(&data_dir.join("blocks")).unwrap_or(0));
+    let needed = estimate.saturating_sub(used);
+    match fs2::available_space(data_dir) {
+        Ok(available) if available >= needed => CheckResult::new(
+            "disk space",
+            CheckStatus::Pass,
+            format!(
+                "{} free, ~{} more needed for a full chain",
+                format_gib(available),
+                format_gib(needed)
+            ),
+        ),
+        Ok(available) => CheckResult::new(
+            "disk space",
+            CheckStatus::Warn,
+            format!(
+                "only {} free, ~{} more needed for a full chain",
+                format_gib(available),
+                format_gib(needed)
+            ),
+        ),
+        Err(err) => CheckResult::new(
+            "disk space",
+            CheckStatus::Warn,
+            format!("failed to query free space: {err}"),
+        ),
+    }
+}
+
+/// A versioned schema, the meta key holding its version and the columns that hold its data.
+struct SchemaCheck {
+    name: &'static str,
+    key: &'static [u8],
+    expected: u32,
+    rebuild_hint: &'static str,
+    columns: &'static [Column],
+}
+
+fn check_schema_versions(store: &Store) -> Result<CheckResult, String> {
+    let checks = [
+        SchemaCheck {
+            name: "db",
+            key: crate::DB_SCHEMA_VERSION_KEY,
+            expected: crate::DB_SCHEMA_VERSION,
+            rebuild_hint: "--reindex",
+            columns: &Column::ALL,
+        },
+        SchemaCheck {
+            name: "txindex",
+            key: crate::TXINDEX_VERSION_KEY,
+            expected: crate::TXINDEX_VERSION,
+            rebuild_hint: "--reindex-txindex",
+            columns: &[Column::TxIndex],
+        },
+        SchemaCheck {
+            name: "spentindex",
+            key: crate::SPENTINDEX_VERSION_KEY,
+            expected: crate::SPENTINDEX_VERSION,
+            rebuild_hint: "--reindex-spentindex",
+            columns: &[Column::SpentIndex],
+        },
+        SchemaCheck {
+            name: "addressindex",
+            key: crate::ADDRESSINDEX_VERSION_KEY,
+            expected: crate::ADDRESSINDEX_VERSION,
+            rebuild_hint: "--reindex-addressindex",
+            columns: &[Column::AddressOutpoint, Column::AddressDelta],
+        },
+    ];
+
+    let mut status = CheckStatus::Pass;
+    let mut details = Vec::new();
+    for SchemaCheck {
+        name,
+        key,
+        expected,
+        rebuild_hint,
+        columns,
+    } in checks
+    {
+        match crate::meta_u32(store, key)? {
+            Some(found) if found == expected => details.push(format!("{name}={found}")),
+            Some(found) => {
+                status = CheckStatus::Fail;
+                details.push(format!(
+                    "{name} found {found}, expected {expected} (rebuild with {rebuild_hint})"
+                ));
+            }
+            None => {
+                let mut has_data = false;
+                for column in columns {
+                    if *column != Column::Meta && crate::store_column_has_any(store, *column)? {
+                        has_data = true;
+                        break;
+                    }
+                }
+                if !has_data {
+                    details.push(format!("{name} unset (empty)"));
+                } else if expected == 1 {
+                    if status == CheckStatus::Pass {
+                        status = CheckStatus::Warn;
+                    }
+                    details.push(format!("{name} unset on a pre-versioned data dir"));
+                } else {
+                    status = CheckStatus::Fail;
+                    details.push(format!(
+                        "{name} missing, expected {expected} (rebuild with {rebuild_hint})"
+                    ));
+                }
+            }
+        }
+    }
+    Ok(CheckResult::new(
+        "schema versions",
+        status,
+        details.join(", "),
+    ))
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / GIB as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+
+    struct TempDirGuard {
+        path: PathBuf,
+    }
+
+    impl Drop for TempDirGuard {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn temp_dir(prefix: &str) -> TempDirGuard {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        TempDirGuard {
+            path: std::env::temp_dir().join(format!("{prefix}-{}-{nanos}", std::process::id())),
+        }
+    }
+
+    fn doctor_config(data_dir: &Path, params_dir: &Path) -> Config {
+        Config::from_args(
+            [
+                "--network",
+                "regtest",
+                "--backend",
+                "memory",
+                "--data-dir",
+                data_dir.to_str().expect("utf8 path"),
+                "--params-dir",
+                params_dir.to_str().expect("utf8 path"),
+            ]
+            .iter()
+            .map(|arg| arg.to_string()),
+        )
+        .expect("config")
+    }
+
+    fn status_of(results: &[CheckResult], name: &str) -> CheckStatus {
+        results
+            .iter()
+            .find(|result| result.name == name)
+            .map(|result| result.status)
+            .expect("check present")
+    }
+
+    #[test]
+    fn reports_every_check_without_stopping_at_missing_params() {
+        let dir = temp_dir("fluxd-doctor-params");
+        let data_dir = dir.path.join("data");
+        let config = doctor_config(&data_dir, &dir.path.join("params"));
+
+        let results = run_checks(&config);
+        assert_eq!(
+            status_of(&results, "data dir writable and lockable"),
+            CheckStatus::Pass
+        );
+        assert_eq!(status_of(&results, "shielded params"), CheckStatus::Fail);
+        assert_eq!(status_of(&results, "database open"), CheckStatus::Pass);
+        assert_eq!(status_of(&results, "schema versions"), CheckStatus::Pass);
+        assert_eq!(
+            status_of(&results, "flatfile consistency"),
+            CheckStatus::Pass
+        );
+        assert!(!data_dir.join(WRITE_PROBE_FILE_NAME).exists());
+        assert!(run(&config).is_err());
+    }
+
+    #[test]
+    fn locked_data_dir_fails_and_skips_database_checks() {
+        let dir = temp_dir("fluxd-doctor-lock");
+        let data_dir = dir.path.join("data");
+        fs::create_dir_all(&data_dir).expect("data dir");
+        let _held = crate::lock_data_dir(&data_dir).expect("lock");
+        let config = doctor_config(&data_dir, &dir.path.join("params"));
+
+        let results = run_checks(&config);
+        assert_eq!(
+            status_of(&results, "data dir writable and lockable"),
+            CheckStatus::Fail
+        );
+        assert_eq!(status_of(&results, "database open"), CheckStatus::Skip);
+        assert_eq!(status_of(&results, "schema versions"), CheckStatus::Skip);
+    }
+}
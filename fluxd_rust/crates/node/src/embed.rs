@@ -0,0 +1,191 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crossbeam_channel::{bounded, Sender};
+use fluxd_chainstate::state::ChainState;
+use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_consensus::params::ChainParams;
+use fluxd_consensus::Hash256;
+use tokio::sync::{broadcast, watch};
+
+use crate::fee_estimator::FeeEstimator;
+use crate::mempool::{Mempool, MempoolPolicy};
+use crate::stats::MempoolMetrics;
+use crate::{rpc, run_with_config, Config, Store};
+
+/// Shared state handed back by `run_with_config` once the node is ready to sync.
+pub(crate) struct NodeContext {
+    pub(crate) chainstate: Arc<ChainState<Store>>,
+    pub(crate) params: Arc<ChainParams>,
+    pub(crate) mempool: Arc<Mutex<Mempool>>,
+    pub(crate) mempool_policy: Arc<MempoolPolicy>,
+    pub(crate) mempool_metrics: Arc<MempoolMetrics>,
+    pub(crate) fee_estimator: Arc<Mutex<FeeEstimator>>,
+    pub(crate) flags: ValidationFlags,
+    pub(crate) tx_announce: broadcast::Sender<Hash256>,
+    pub(crate) shutdown_tx: watch::Sender<bool>,
+}
+
+pub(crate) type NodeReadySender = Sender<NodeContext>;
+
+/// A node running on its own runtime thread inside the current process.
+pub struct NodeHandle {
+    context: NodeContext,
+    thread: Option<thread::JoinHandle<Result<(), String>>>,
+}
+
+/// Starts a node in the background and returns once its chainstate, mempool and RPC are up.
+///
+/// The TUI is always disabled for embedded nodes.
+pub fn start_node(mut config: Config) -> Result<NodeHandle, String> {
+    config.tui = false;
+    let (ready_tx, ready_rx) = bounded::<NodeContext>(1);
+    let thread = thread::Builder::new()
+        .name("fluxd-node".to_string())
+        .spawn(move || {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .map_err(|err| format!("failed to build tokio runtime: {err}"))?;
+            runtime.block_on(run_with_config(Instant::now(), config, Some(ready_tx)))
+        })
+        .map_err(|err| format!("failed to spawn node thread: {err}"))?;
+
+    match ready_rx.recv() {
+        Ok(context) => Ok(NodeHandle {
+            context,
+            thread: Some(thread),
+        }),
+        Err(_) => match thread.join() {
+            Ok(Ok(())) => Err("node exited before startup completed".to_string()),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err("node thread panicked during startup".to_string()),
+        },
+    }
+}
+
+impl NodeHandle {
+    pub fn best_height(&self) -> Result<i32, String> {
+        let tip = self
+            .context
+            .chainstate
+            .best_block()
+            .map_err(|err| err.to_string())?;
+        Ok(tip.map(|tip| tip.height).unwrap_or(-1))
+    }
+
+    /// Validates `raw` against the mempool policy, admits it and announces it to peers.
+    pub fn submit_transaction(&self, raw: Vec<u8>) -> Result<Hash256, String> {
+        let context = &self.context;
+        rpc::submit_raw_transaction(
+            context.chainstate.as_ref(),
+            context.mempool.as_ref(),
+            context.mempool_policy.as_ref(),
+            context.mempool_metrics.as_ref(),
+            context.fee_estimator.as_ref(),
+            &context.flags,
+            context.params.as_ref(),
+            &context.tx_announce,
+            true,
+            raw,
+        )
+        .map_err(|err| err.to_string())
+    }
+
+    /// Requests shutdown and waits for the node to flush its state and exit.
+    pub fn shutdown(mut self) -> Result<(), String> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        let _ = self.context.shutdown_tx.send(true);
+        thread
+            .join()
+            .map_err(|_| "node thread panicked".to_string())?
+    }
+}
+
+impl Drop for NodeHandle {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use fluxd_shielded::default_params_dir;
+
+    use crate::{parse_args_from, CliAction};
+
+    struct TempDirGuard {
+        path: PathBuf,
+    }
+
+    impl Drop for TempDirGuard {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn temp_dir(prefix: &str) -> TempDirGuard {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        TempDirGuard {
+            path: std::env::temp_dir().join(format!("{prefix}-{}-{nanos}", std::process::id())),
+        }
+    }
+
+    fn regtest_config(data_dir: &Path, params_dir: &Path) -> Config {
+        let args = [
+            "--network",
+            "regtest",
+            "--backend",
+            "memory",
+            "--data-dir",
+            data_dir.to_str().expect("utf8 path"),
+            "--params-dir",
+            params_dir.to_str().expect("utf8 path"),
+            "--no-p2p-listen",
+            "--rpc-addr",
+            "127.0.0.1:0",
+        ];
+        let action =
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false).expect("parse args");
+        let CliAction::Run(config) = action else {
+            panic!("expected run action");
+        };
+        config
+    }
+
+    #[test]
+    fn start_node_reports_startup_errors() {
+        let dir = temp_dir("fluxd-embed-missing-params");
+        let config = regtest_config(&dir.path.join("data"), &dir.path.join("params"));
+        let err = match start_node(config) {
+            Ok(_) => panic!("node started without shielded params"),
+            Err(err) => err,
+        };
+        assert!(err.contains("params"), "{err}");
+    }
+
+    #[test]
+    #[ignore = "requires shielded params in the default params dir; run with --ignored"]
+    fn start_query_and_shutdown_regtest_node() {
+        let dir = temp_dir("fluxd-embed-regtest");
+        let config = regtest_config(&dir.path, &default_params_dir());
+        let node = start_node(config).expect("start node");
+        assert_eq!(node.best_height().expect("best height"), 0);
+        assert!(node.submit_transaction(vec![0x00]).is_err());
+        node.shutdown().expect("shutdown");
+    }
+}
@@ -2,9 +2,13 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+use fluxd_chainstate::state::ChainState;
 use fluxd_consensus::money::{COIN, MAX_MONEY};
 use fluxd_consensus::Hash256;
+use fluxd_primitives::block::Block;
 use fluxd_primitives::encoding::{Decoder, Encoder};
+use fluxd_primitives::transaction::Transaction;
+use fluxd_storage::KeyValueStore;
 
 const FEE_ESTIMATES_FILE_VERSION: u32 = 2;
 
@@ -51,6 +55,60 @@ pub struct BlockTxInfo {
     pub was_clear_at_entry: bool,
 }
 
+/// Confirmation counts over a contiguous run of fee-rate buckets, as reported by
+/// `estimaterawfee`. Counts are decayed moving averages, not raw transaction totals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawBucketRange {
+    /// Lower fee-rate boundary (exclusive) in satoshis per kB.
+    pub start_range: f64,
+    /// Upper fee-rate boundary (inclusive) in satoshis per kB.
+    pub end_range: f64,
+    /// Transactions in the range that confirmed within the target.
+    pub within_target: f64,
+    /// All transactions in the range that confirmed.
+    pub total_confirmed: f64,
+    /// Transactions in the range still unconfirmed after at least the target.
+    pub in_mempool: f64,
+}
+
+/// Internal fee estimator state for one confirmation target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawEstimate {
+    pub target: u32,
+    /// Required fee rate in satoshis per kB, or `None` when no bucket range passed.
+    pub fee_per_kb: Option<i64>,
+    /// Share of `within_target / (total_confirmed + in_mempool)` a range needs to pass.
+    pub success_threshold: f64,
+    /// Per-block decay applied to every moving average.
+    pub decay: f64,
+    /// Height of the last block the estimator processed.
+    pub best_seen_height: u32,
+    /// Decayed count of confirmed fee data points across all buckets.
+    pub data_points: f64,
+    /// Decayed count a bucket range needs before its success rate is judged.
+    pub sufficient_data_points: f64,
+    /// The lowest-fee bucket range that met the threshold.
+    pub pass: Option<RawBucketRange>,
+    /// The range just below `pass` that missed the threshold or lacked data.
+    pub fail: Option<RawBucketRange>,
+}
+
+/// Result of one bucket walk, with the bucket index ranges it judged.
+struct MedianEstimate {
+    value: f64,
+    pass: Option<BucketSpan>,
+    fail: Option<BucketSpan>,
+}
+
+#[derive(Clone, Copy)]
+struct BucketSpan {
+    first: usize,
+    last: usize,
+    within_target: f64,
+    total_confirmed: f64,
+    in_mempool: f64,
+}
+
 #[derive(Clone, Copy, Debug)]
 enum TrackedStats {
     Fee,
@@ -109,6 +167,10 @@ impl TxConfirmStats {
         u32::try_from(self.conf_avg.len()).unwrap_or(u32::MAX)
     }
 
+    fn is_empty(&self) -> bool {
+        self.tx_ct_avg.iter().all(|count| *count <= 0.0)
+    }
+
     fn find_bucket_index(&self, val: f64) -> usize {
         if !val.is_finite() {
             return self.buckets.len().saturating_sub(1);
@@ -229,14 +291,37 @@ impl TxConfirmStats {
         require_greater: bool,
         block_height: u32,
     ) -> f64 {
+        self.estimate_median(
+            conf_target,
+            sufficient_tx_val,
+            success_break_point,
+            require_greater,
+            block_height,
+        )
+        .value
+    }
+
+    fn estimate_median(
+        &self,
+        conf_target: u32,
+        sufficient_tx_val: f64,
+        success_break_point: f64,
+        require_greater: bool,
+        block_height: u32,
+    ) -> MedianEstimate {
+        let mut result = MedianEstimate {
+            value: -1.0,
+            pass: None,
+            fail: None,
+        };
         let Some(conf_target) = usize::try_from(conf_target).ok() else {
-            return -1.0;
+            return result;
         };
         if conf_target == 0 || conf_target > self.conf_avg.len() {
-            return -1.0;
+            return result;
         }
         if self.buckets.is_empty() {
-            return -1.0;
+            return result;
         }
 
         let mut n_conf = 0.0;
@@ -254,9 +339,20 @@ impl TxConfirmStats {
         let mut found_answer = false;
         let bins = self.unconf_txs.len();
 
+        let span =
+            |near: usize, far: usize, n_conf: f64, total_num: f64, extra_num: i32| BucketSpan {
+                first: near.min(far),
+                last: near.max(far),
+                within_target: n_conf,
+                total_confirmed: total_num,
+                in_mempool: f64::from(extra_num),
+            };
+        let mut last_bucket = start_bucket;
+
         let mut bucket = start_bucket as i32;
         while bucket >= 0 && (bucket as usize) <= max_bucket_index {
             let bucket_usize = bucket as usize;
+            last_bucket = bucket_usize;
             n_conf += self.conf_avg[conf_target - 1][bucket_usize];
             total_num += self.tx_ct_avg[bucket_usize];
 
@@ -272,13 +368,26 @@ impl TxConfirmStats {
             if total_num >= sufficient_tx_val / (1.0 - self.decay) {
                 let cur_pct = n_conf / (total_num + extra_num as f64);
 
-                if require_greater && cur_pct < success_break_point {
-                    break;
-                }
-                if !require_greater && cur_pct > success_break_point {
+                if (require_greater && cur_pct < success_break_point)
+                    || (!require_greater && cur_pct > success_break_point)
+                {
+                    result.fail = Some(span(
+                        cur_near_bucket,
+                        bucket_usize,
+                        n_conf,
+                        total_num,
+                        extra_num,
+                    ));
                     break;
                 }
 
+                result.pass = Some(span(
+                    cur_near_bucket,
+                    bucket_usize,
+                    n_conf,
+                    total_num,
+                    extra_num,
+                ));
                 found_answer = true;
                 n_conf = 0.0;
                 total_num = 0.0;
@@ -294,6 +403,16 @@ impl TxConfirmStats {
 
             bucket = bucket.saturating_add(step);
         }
+        // Ran out of buckets while accumulating: the leftover range never had enough data.
+        if result.fail.is_none() && found_answer && total_num > 0.0 {
+            result.fail = Some(span(
+                cur_near_bucket,
+                last_bucket,
+                n_conf,
+                total_num,
+                extra_num,
+            ));
+        }
 
         let min_bucket = best_near_bucket.min(best_far_bucket);
         let max_bucket = best_near_bucket.max(best_far_bucket);
@@ -304,7 +423,7 @@ impl TxConfirmStats {
         }
 
         if !found_answer || tx_sum == 0.0 {
-            return -1.0;
+            return result;
         }
 
         tx_sum /= 2.0;
@@ -312,11 +431,26 @@ impl TxConfirmStats {
             if self.tx_ct_avg[idx] < tx_sum {
                 tx_sum -= self.tx_ct_avg[idx];
             } else {
-                return self.avg[idx] / self.tx_ct_avg[idx];
+                result.value = self.avg[idx] / self.tx_ct_avg[idx];
+                return result;
             }
         }
 
-        -1.0
+        result
+    }
+
+    fn bucket_range(&self, span: BucketSpan, inf_value: f64) -> RawBucketRange {
+        let boundary = |value: f64| if value.is_finite() { value } else { inf_value };
+        RawBucketRange {
+            start_range: span
+                .first
+                .checked_sub(1)
+                .map_or(0.0, |idx| boundary(self.buckets[idx])),
+            end_range: boundary(self.buckets[span.last]),
+            within_target: span.within_target,
+            total_confirmed: span.total_confirmed,
+            in_mempool: span.in_mempool,
+        }
     }
 
     fn encode(&self, encoder: &mut Encoder) {
@@ -620,6 +754,74 @@ impl FeeEstimator {
         self.revision = self.revision.saturating_add(1);
     }
 
+    /// True when no confirmed transaction has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.fee_stats.is_empty() && self.pri_stats.is_empty()
+    }
+
+    /// Replays the last `blocks` connected blocks into the estimator so `estimatefee` has data
+    /// right after a fresh sync or a lost `fee_estimates.dat`.
+    ///
+    /// When a transaction entered the mempool is unknown, so each one is recorded as confirming
+    /// one block after it was seen. Fees are rebuilt from prevout values in the spent index;
+    /// zero-fee transactions and transactions with an input missing from the index are skipped.
+    /// Returns the number of transactions recorded.
+    pub fn seed_from_blocks<S: KeyValueStore>(
+        &mut self,
+        chainstate: &ChainState<S>,
+        blocks: usize,
+    ) -> Result<usize, String> {
+        let Some(tip) = chainstate.best_block().map_err(|err| err.to_string())? else {
+            return Ok(0);
+        };
+        let blocks = i32::try_from(blocks).unwrap_or(i32::MAX);
+        let start = tip.height.saturating_sub(blocks).saturating_add(1).max(1);
+        if start > tip.height {
+            return Ok(0);
+        }
+        self.best_seen_height = self.best_seen_height.min(start as u32 - 1);
+
+        let mut seeded = 0usize;
+        for height in start..=tip.height {
+            let hash = chainstate
+                .height_hash(height)
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| format!("missing block hash at height {height}"))?;
+            let location = chainstate
+                .block_location(hash.as_internal())
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| format!("missing block data at height {height}"))?;
+            let bytes = chainstate
+                .read_block(location)
+                .map_err(|err| err.to_string())?;
+            let block = Block::consensus_decode(&bytes).map_err(|err| err.to_string())?;
+
+            let mut entries = Vec::new();
+            for tx in block.transactions.iter().skip(1) {
+                if tx.fluxnode.is_some() {
+                    continue;
+                }
+                let Some(fee) = block_tx_fee(chainstate, tx)? else {
+                    continue;
+                };
+                if fee <= 0 {
+                    continue;
+                }
+                let size = tx.consensus_encode().map_err(|err| err.to_string())?.len();
+                entries.push(BlockTxInfo {
+                    fee,
+                    size,
+                    height: height as u32 - 1,
+                    priority: 0.0,
+                    was_clear_at_entry: true,
+                });
+            }
+            seeded += entries.len();
+            self.process_block(height as u32, &entries, true);
+        }
+        Ok(seeded)
+    }
+
     pub fn estimate_fee_per_kb(&self, target_blocks: u32) -> Option<i64> {
         if target_blocks < 1 || target_blocks > self.fee_stats.max_confirms() {
             return None;
@@ -638,6 +840,44 @@ impl FeeEstimator {
         Some(median as i64)
     }
 
+    /// Highest confirmation target the estimator tracks.
+    pub fn max_target(&self) -> u32 {
+        self.fee_stats.max_confirms()
+    }
+
+    /// The fee bucket data behind [`FeeEstimator::estimate_fee_per_kb`] for `target_blocks`,
+    /// in the spirit of bitcoind's `estimaterawfee`.
+    pub fn raw_estimate(&self, target_blocks: u32) -> RawEstimate {
+        let median = self.fee_stats.estimate_median(
+            target_blocks,
+            SUFFICIENT_FEETXS,
+            MIN_SUCCESS_PCT,
+            true,
+            self.best_seen_height,
+        );
+        let fee_per_kb = if median.value > 0.0 && median.value.is_finite() {
+            Some(median.value.min(i64::MAX as f64) as i64)
+        } else {
+            None
+        };
+        let decay = self.fee_stats.decay;
+        RawEstimate {
+            target: target_blocks,
+            fee_per_kb,
+            success_threshold: MIN_SUCCESS_PCT,
+            decay,
+            best_seen_height: self.best_seen_height,
+            data_points: self.fee_stats.tx_ct_avg.iter().sum(),
+            sufficient_data_points: SUFFICIENT_FEETXS / (1.0 - decay),
+            pass: median
+                .pass
+                .map(|span| self.fee_stats.bucket_range(span, INF_FEERATE_PER_KB)),
+            fail: median
+                .fail
+                .map(|span| self.fee_stats.bucket_range(span, INF_FEERATE_PER_KB)),
+        }
+    }
+
     pub fn estimate_priority(&self, target_blocks: u32) -> Option<f64> {
         if target_blocks < 1 || target_blocks > self.pri_stats.max_confirms() {
             return None;
@@ -688,6 +928,33 @@ impl FeeEstimator {
     }
 }
 
+/// Fee paid by a confirmed transaction, or `None` when a prevout is not in the spent index.
+fn block_tx_fee<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    tx: &Transaction,
+) -> Result<Option<i64>, String> {
+    let mut value_in = tx.value_balance;
+    for joinsplit in &tx.join_splits {
+        value_in = value_in
+            .saturating_add(joinsplit.vpub_new)
+            .saturating_sub(joinsplit.vpub_old);
+    }
+    for input in &tx.vin {
+        let spent = chainstate
+            .spent_info(&input.prevout)
+            .map_err(|err| err.to_string())?;
+        let Some(details) = spent.and_then(|spent| spent.details) else {
+            return Ok(None);
+        };
+        value_in = value_in.saturating_add(details.satoshis);
+    }
+    let value_out = tx
+        .vout
+        .iter()
+        .fold(0i64, |total, output| total.saturating_add(output.value));
+    Ok(Some(value_in.saturating_sub(value_out)))
+}
+
 fn write_f64(encoder: &mut Encoder, value: f64) {
     encoder.write_u64_le(value.to_bits());
 }
@@ -0,0 +1,264 @@
+//! Listen addresses for the RPC and dashboard servers.
+//!
+//! Besides `IP:PORT`, both accept `unix:<path>` to serve on a Unix domain socket. A socket has
+//! no peer IP, so access is controlled by its filesystem permissions (owner only) instead of
+//! `rpcallowip`.
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+
+const UNIX_PREFIX: &str = "unix:";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl ListenAddr {
+    /// Parses `IP:PORT` or `unix:<path>`. Socket paths are refused on platforms without Unix
+    /// domain sockets.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        if let Some(path) = raw.strip_prefix(UNIX_PREFIX) {
+            return Self::unix(path);
+        }
+        raw.parse::<SocketAddr>()
+            .map(Self::Tcp)
+            .map_err(|_| format!("invalid address '{raw}'"))
+    }
+
+    /// `unix:<path>` only; `None` when `raw` is not a socket path.
+    pub fn parse_unix(raw: &str) -> Option<Result<Self, String>> {
+        raw.strip_prefix(UNIX_PREFIX).map(Self::unix)
+    }
+
+    fn unix(path: &str) -> Result<Self, String> {
+        if path.is_empty() {
+            return Err("unix socket path is empty".to_string());
+        }
+        if !cfg!(unix) {
+            return Err(format!(
+                "unix socket '{UNIX_PREFIX}{path}' is not supported on this platform"
+            ));
+        }
+        Ok(Self::Unix(PathBuf::from(path)))
+    }
+
+    pub fn tcp(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(addr) => Some(*addr),
+            Self::Unix(_) => None,
+        }
+    }
+
+    pub async fn bind(&self) -> io::Result<Listener> {
+        match self {
+            Self::Tcp(addr) => TcpListener::bind(addr).await.map(Listener::Tcp),
+            #[cfg(unix)]
+            Self::Unix(path) => bind_unix(path).map(Listener::Unix),
+            #[cfg(not(unix))]
+            Self::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unix sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+impl From<SocketAddr> for ListenAddr {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Tcp(addr)
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            Self::Unix(path) => write!(f, "{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+/// Replaces a socket file left behind by a previous run, then restricts the new one to its owner.
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path) -> io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+    /// The peer address is `None` for Unix socket connections.
+    pub async fn accept(&self) -> io::Result<(Stream, Option<SocketAddr>)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                Ok((Stream::Tcp(stream), Some(addr)))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Stream::Unix(stream), None))
+            }
+        }
+    }
+}
+
+pub enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tcp_and_unix_addresses() {
+        assert_eq!(
+            ListenAddr::parse("127.0.0.1:16124").expect("tcp"),
+            ListenAddr::Tcp("127.0.0.1:16124".parse().expect("addr"))
+        );
+        assert!(ListenAddr::parse("localhost").is_err());
+        assert!(ListenAddr::parse("unix:").is_err());
+        assert!(ListenAddr::parse_unix("127.0.0.1").is_none());
+        let unix = ListenAddr::parse("unix:/run/fluxd/rpc.sock");
+        if cfg!(unix) {
+            let unix = unix.expect("unix");
+            assert_eq!(unix, ListenAddr::Unix(PathBuf::from("/run/fluxd/rpc.sock")));
+            assert_eq!(unix.to_string(), "unix:/run/fluxd/rpc.sock");
+            assert_eq!(unix.tcp(), None);
+        } else {
+            assert!(unix.expect_err("unsupported").contains("not supported"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_smoke_test() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir =
+            std::env::temp_dir().join(format!("fluxd-listen-unix-{}-{nanos}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join("rpc.sock");
+        let addr = ListenAddr::Unix(path.clone());
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        runtime.block_on(async {
+            // A stale socket from an earlier run is replaced rather than failing the bind.
+            drop(addr.bind().await.expect("first bind"));
+            let listener = addr.bind().await.expect("rebind");
+            let mode = std::fs::metadata(&path)
+                .expect("metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            let server = tokio::spawn(async move {
+                let (mut stream, peer) = listener.accept().await.expect("accept");
+                assert!(peer.is_none());
+                let mut request = [0u8; 4];
+                stream.read_exact(&mut request).await.expect("read");
+                assert_eq!(&request, b"ping");
+                stream.write_all(b"pong").await.expect("write");
+            });
+
+            let mut client = tokio::net::UnixStream::connect(&path)
+                .await
+                .expect("connect");
+            client.write_all(b"ping").await.expect("write");
+            let mut reply = [0u8; 4];
+            client.read_exact(&mut reply).await.expect("read");
+            assert_eq!(&reply, b"pong");
+            server.await.expect("server");
+        });
+
+        std::fs::write(dir.join("plain"), b"").expect("write file");
+        let plain = ListenAddr::Unix(dir.join("plain"));
+        assert!(runtime.block_on(plain.bind()).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -2,7 +2,7 @@ use fluxd_log as logging;
 
 macro_rules! log_at {
     ($level:expr, $($arg:tt)*) => {{
-        if crate::logging::enabled($level) {
+        if crate::logging::enabled_for($level, module_path!()) {
             crate::logging::log($level, module_path!(), file!(), line!(), format_args!($($arg)*));
         }
     }};
@@ -39,13 +39,23 @@ macro_rules! log_trace {
     }};
 }
 
+mod adaptive_peers;
+mod bench_connect;
+mod block_relay;
+mod corruption;
 mod dashboard;
 mod db_info;
+mod doctor;
+mod embed;
 mod fee_estimator;
+mod listen;
 mod mempool;
+mod migrate_datadir;
+mod notify;
 mod p2p;
 mod p2p_server;
 mod peer_book;
+mod psbt;
 mod rpc;
 mod stats;
 mod tui;
@@ -53,6 +63,8 @@ mod tx_relay;
 mod verify_chain;
 mod wallet;
 
+pub use embed::{start_node, NodeHandle};
+
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File, OpenOptions};
@@ -60,29 +72,38 @@ use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crossbeam_channel::{bounded, unbounded};
 use fluxd_chainstate::flatfiles::{FileLocation, FlatFileStore};
-use fluxd_chainstate::index::HeaderEntry;
+use fluxd_chainstate::fluxnode::{
+    check_payee_candidate, fluxnode_confirm_expiration_count, select_payee, FluxnodePayeeKey,
+};
+use fluxd_chainstate::index::{chainwork_hex, compare_work, HeaderEntry};
 use fluxd_chainstate::metrics::ConnectMetrics;
-use fluxd_chainstate::state::{ChainState, HeaderValidationCache};
+use fluxd_chainstate::state::{
+    ChainState, ChainStateError, HeaderValidationCache, UtxoFlushPolicy, FORK_BEFORE_CHECKPOINT,
+    FORK_TOO_DEEP,
+};
 use fluxd_chainstate::validation::{
-    validate_block_with_txids_and_size, ValidationFlags, ValidationMetrics,
+    build_script_pool, validate_block_with_txids_and_size, ValidationFlags, ValidationMetrics,
 };
-use fluxd_consensus::money::{money_range, COIN, MAX_MONEY};
-use fluxd_consensus::params::{chain_params, hash256_from_hex, ChainParams, Network};
-use fluxd_consensus::upgrades::{current_epoch_branch_id, network_upgrade_active, UpgradeIndex};
+use fluxd_consensus::money::{self, money_range, MAX_MONEY};
+use fluxd_consensus::params::{chain_params, ChainParams, ConsensusParams, Network};
+use fluxd_consensus::upgrades::{branch_id_for_height, UpgradeIndex};
 use fluxd_consensus::Hash256;
 use fluxd_consensus::{
-    block_subsidy, exchange_fund_amount, foundation_fund_amount, swap_pool_amount,
+    exchange_fund_schedule, foundation_fund_schedule, schedule_total, subsidy_schedule,
+    swap_pool_schedule,
 };
 use fluxd_fluxnode::storage::FluxnodeRecord;
 use fluxd_pow::validation as pow_validation;
 use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION};
 use fluxd_primitives::encoding::{Decoder, Encoder};
+use fluxd_primitives::hash::{hash256_from_hex, hash256_to_hex};
+use fluxd_primitives::ids::BlockHash;
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
 use fluxd_primitives::{address_to_script_pubkey, AddressError};
@@ -90,20 +111,28 @@ use fluxd_shielded::{
     default_params_dir, fetch_params, load_params, verify_transaction, ShieldedError,
     ShieldedParams,
 };
+use fluxd_storage::compress::Compression;
 use fluxd_storage::fjall::{FjallOptions, FjallStore};
+use fluxd_storage::instrumented::StoreLatency;
 use fluxd_storage::memory::MemoryStore;
-use fluxd_storage::{KeyValueStore, StoreError, WriteBatch};
+use fluxd_storage::{
+    BackendOptions, BackendUri, InstrumentedStore, KeyValueStore, OpenedBackend, StoreError,
+    WriteBatch,
+};
 use fs2::FileExt;
 use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, mpsc, watch};
 use tokio::task::JoinSet;
 
+use crate::adaptive_peers::{AdaptivePeerBounds, AdaptivePeerTarget};
+use crate::listen::ListenAddr;
 use crate::p2p::{
-    parse_addr, parse_headers, parse_inv, parse_reject, NetTotals, Peer, PeerKind, PeerRegistry,
+    filter_gossiped_addrs, parse_addr, parse_headers, parse_inv, parse_reject, NetTotals, Peer,
+    PeerInfoSnapshot, PeerKind, PeerRegistry,
 };
-use crate::peer_book::HeaderPeerBook;
-use crate::stats::{hash256_to_hex, snapshot_stats, HeaderMetrics, SyncMetrics};
+use crate::peer_book::{BanPolicy, BanReason, HeaderPeerBook, DEFAULT_BAN_SECS};
+use crate::stats::{snapshot_stats, HeaderMetrics, SyncMetrics};
 
 const DEFAULT_DATA_DIR: &str = "data";
 const DATADIR_POINTER_FILE_NAME: &str = "fluxd.datadir";
@@ -116,11 +145,19 @@ const DEFAULT_HEADER_PEERS: usize = 4;
 const DEFAULT_HEADER_LEAD: i32 = 20000;
 const DEFAULT_INFLIGHT_PER_PEER: usize = 1;
 const DEFAULT_TX_PEERS: usize = 2;
+const DEFAULT_TX_REJECT_WAIT_SECS: u64 = 2;
+const DEFAULT_BLOCK_RELAY_PEERS: usize = 2;
 const DEFAULT_MAX_CONNECTIONS: usize = 125;
 const DEFAULT_MEMPOOL_MAX_MB: u64 = 300;
 const DEFAULT_MEMPOOL_PERSIST_INTERVAL_SECS: u64 = 60;
+const DEFAULT_MEMPOOL_EXPIRY_HOURS: u64 = 336;
+const MEMPOOL_EXPIRY_SWEEP_SECS: u64 = 60;
+const DEFAULT_UNCONNECTED_EXPIRY_HOURS: u64 = 24;
+const UNCONNECTED_PRUNE_SWEEP_SECS: u64 = 300;
+const MAX_UNCONNECTED_BLOCKS: usize = 256;
 const DEFAULT_TX_CONFIRM_TARGET: u32 = 2;
 const DEFAULT_UTXO_CACHE_ENTRIES: usize = 200_000;
+const DEFAULT_READY_MAX_LAG: u32 = 2;
 const DEFAULT_DB_CACHE_MB: u64 = 256;
 const DEFAULT_DB_WRITE_BUFFER_MB: u64 = 2048;
 const DEFAULT_DB_JOURNAL_MB: u64 = 2048;
@@ -143,14 +180,9 @@ const HEADER_STALL_SECS_IDLE: u64 = 90;
 const HEADER_IDLE_REPROBE_SECS: u64 = 120;
 const BLOCK_STALL_SECS: u64 = 90;
 const BLOCK_PEER_REFILL_SECS: u64 = 30;
-const BLOCK_PEER_BAN_SECS_NOTFOUND: u64 = 300;
-const BLOCK_PEER_BAN_SECS_TIMEOUT: u64 = 120;
-const BLOCK_PEER_BAN_SECS_PROTOCOL: u64 = 900;
 const HEADER_PEER_PROBE_COUNT: usize = 40;
 const HEADER_BATCH_QUEUE: usize = 32;
 const HEADER_LOCATOR_MAX_WALK: usize = 1024;
-const HEADER_BAD_CHAIN_BAN_SECS: u64 = 900;
-const HEADER_BEHIND_BAN_SECS: u64 = 300;
 const HEADER_BEHIND_BAN_THRESHOLD: i32 = 1000;
 const TX_ANNOUNCE_QUEUE: usize = 4096;
 const ADDR_BOOK_MAX: usize = 5000;
@@ -161,8 +193,12 @@ const ADDR_DISCOVERY_INTERVAL_SECS: u64 = 120;
 const ADDR_DISCOVERY_TIMEOUT_SECS: u64 = 6;
 const PEERS_FILE_NAME: &str = "peers.dat";
 const BANLIST_FILE_NAME: &str = "banlist.dat";
+const ANCHORS_FILE_NAME: &str = "anchors.dat";
+/// Outbound peers remembered across a clean restart and dialled first on the next start.
+const MAX_ANCHORS: usize = 2;
 const MEMPOOL_FILE_NAME: &str = "mempool.dat";
 const FEE_ESTIMATES_FILE_NAME: &str = "fee_estimates.dat";
+const FEE_ESTIMATES_SEED_BLOCKS: usize = 100;
 const REINDEX_REQUEST_FILE_NAME: &str = "reindex.flag";
 const DATA_DIR_LOCK_FILE_NAME: &str = ".lock";
 pub(crate) const DB_SCHEMA_VERSION_KEY: &[u8] = b"db_schema_version";
@@ -173,8 +209,13 @@ pub(crate) const SPENTINDEX_VERSION_KEY: &[u8] = b"spentindex_version";
 pub(crate) const SPENTINDEX_VERSION: u32 = 1;
 pub(crate) const ADDRESSINDEX_VERSION_KEY: &[u8] = b"addressindex_version";
 pub(crate) const ADDRESSINDEX_VERSION: u32 = 1;
+/// Height an unfinished secondary index rebuild has reached; removed once the rebuild completes.
+pub(crate) const TXINDEX_REBUILD_HEIGHT_KEY: &[u8] = b"txindex_rebuild_height";
+pub(crate) const SPENTINDEX_REBUILD_HEIGHT_KEY: &[u8] = b"spentindex_rebuild_height";
+pub(crate) const ADDRESSINDEX_REBUILD_HEIGHT_KEY: &[u8] = b"addressindex_rebuild_height";
 const PEERS_FILE_VERSION: u32 = 2;
 const PEERS_FILE_VERSION_V1: u32 = 1;
+const ANCHORS_FILE_VERSION: u32 = 1;
 const MEMPOOL_FILE_VERSION: u32 = 1;
 const PEERS_PERSIST_INTERVAL_SECS: u64 = 60;
 const BANLIST_PERSIST_INTERVAL_SECS: u64 = 60;
@@ -222,6 +263,13 @@ impl Backend {
             _ => None,
         }
     }
+
+    fn uri(self, db_path: &Path) -> BackendUri {
+        match self {
+            Self::Memory => BackendUri::Memory,
+            Self::Fjall => BackendUri::Fjall(db_path.to_path_buf()),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -250,7 +298,7 @@ impl RunProfile {
     }
 }
 
-struct Config {
+pub struct Config {
     backend: Backend,
     data_dir: PathBuf,
     conf_path: PathBuf,
@@ -259,6 +307,7 @@ struct Config {
     fetch_params: bool,
     reindex: bool,
     resync: bool,
+    reindex_on_corruption: bool,
     reindex_txindex: bool,
     reindex_spentindex: bool,
     reindex_addressindex: bool,
@@ -274,35 +323,60 @@ struct Config {
     debug_fluxnode_payee_script: Option<Vec<u8>>,
     debug_fluxnode_payout_height: Option<i32>,
     debug_fluxnode_payee_candidates: Option<DebugFluxnodePayeeCandidates>,
+    dump_blocks: Option<DumpRange>,
+    dump_headers: Option<DumpRange>,
+    import_blocks: Option<PathBuf>,
+    benchmark_connect: Option<bench_connect::BenchmarkRange>,
     check_script: bool,
+    check_canonical_encoding: bool,
+    assume_valid: Option<Hash256>,
+    /// Refuse reorgs disconnecting more than this many blocks; 0 leaves the consensus limit.
+    max_reorg_depth: u32,
     log_level: logging::Level,
     log_format: logging::Format,
     log_timestamps: bool,
     p2p_listen: bool,
     p2p_addr: Option<SocketAddr>,
-    rpc_addr: Option<SocketAddr>,
+    external_ip: Option<SocketAddr>,
+    rpc_addr: Option<ListenAddr>,
     rpc_user: Option<String>,
     rpc_pass: Option<String>,
     rpc_allow_ips: Vec<String>,
+    ban_policy: BanPolicy,
+    whitelist: Vec<String>,
     getdata_batch: usize,
     block_peers: usize,
+    adaptive_peers: Option<AdaptivePeerBounds>,
     header_peers: usize,
     header_lead: i32,
+    /// Max blocks requested ahead of the connected tip per fetch round; 0 derives it from peers.
+    block_download_window: usize,
     header_peer_addrs: Vec<String>,
     addnode_nodes: Vec<String>,
     max_connections: usize,
+    max_upload_target_mb: u64,
+    max_inbound_send_rate_kb: u64,
     tx_peers: usize,
+    /// How long `sendrawtransaction` waits for a peer `reject` before answering; 0 disables.
+    tx_reject_wait_secs: u64,
+    block_relay_peers: usize,
     inflight_per_peer: usize,
     require_standard: bool,
     min_relay_fee_per_kb: i64,
     limit_free_relay_kb_per_minute: u64,
     mempool_max_bytes: usize,
     mempool_persist_interval_secs: u64,
+    mempool_expiry_hours: u64,
+    unconnected_expiry_hours: u64,
     fee_estimates_persist_interval_secs: u64,
     status_interval_secs: u64,
     tui: bool,
     tui_start_in_setup: bool,
-    dashboard_addr: Option<SocketAddr>,
+    dashboard_addr: Option<ListenAddr>,
+    /// Blocks the tip may trail the best header while the dashboard `/ready` probe passes.
+    ready_max_lag: u32,
+    notify_addr: Option<SocketAddr>,
+    notify_topics: notify::NotifyTopics,
     db_cache_bytes: Option<u64>,
     db_write_buffer_bytes: Option<u64>,
     db_journal_bytes: Option<u64>,
@@ -310,7 +384,12 @@ struct Config {
     db_flush_workers: Option<usize>,
     db_compaction_workers: Option<usize>,
     db_fsync_ms: Option<u16>,
+    db_compress_columns: Vec<fluxd_storage::Column>,
+    db_latency: bool,
     utxo_cache_entries: usize,
+    /// Deferred UTXO write limits (`--utxo-flush-entries`, `--utxo-flush-mb`); 0 means none.
+    utxo_flush_entries: usize,
+    utxo_flush_mb: usize,
     header_verify_workers: usize,
     verify_workers: usize,
     verify_queue: usize,
@@ -319,6 +398,9 @@ struct Config {
 
 enum CliAction {
     Run(Config),
+    PrintConfig(Config),
+    Doctor(Config),
+    MigrateDatadir { config: Config, target: PathBuf },
     TuiAttach { endpoint: String },
     PrintHelp,
     PrintVersion,
@@ -331,6 +413,13 @@ struct DebugFluxnodePayeeCandidates {
     limit: usize,
 }
 
+#[derive(Clone, Debug)]
+struct DumpRange {
+    start: i32,
+    end: i32,
+    path: PathBuf,
+}
+
 #[derive(Clone)]
 struct PeerContext {
     net_totals: Arc<NetTotals>,
@@ -401,6 +490,8 @@ struct AddrBookEntry {
 struct AddrBook {
     entries: Mutex<HashMap<SocketAddr, AddrBookEntry>>,
     revision: AtomicU64,
+    /// Anchors loaded from `anchors.dat`, handed out once to the first peer connect.
+    anchors: Mutex<Vec<SocketAddr>>,
 }
 
 impl AddrBook {
@@ -408,6 +499,19 @@ impl AddrBook {
         self.revision.load(AtomicOrdering::Relaxed)
     }
 
+    fn set_anchors(&self, anchors: Vec<SocketAddr>) {
+        if let Ok(mut guard) = self.anchors.lock() {
+            *guard = anchors;
+        }
+    }
+
+    fn take_anchors(&self) -> Vec<SocketAddr> {
+        match self.anchors.lock() {
+            Ok(mut guard) => std::mem::take(&mut *guard),
+            Err(_) => Vec::new(),
+        }
+    }
+
     fn record_attempt(&self, addr: SocketAddr) {
         let now = unix_now_secs();
         if let Ok(mut book) = self.entries.lock() {
@@ -705,6 +809,52 @@ fn addr_bucket_key(addr: &SocketAddr) -> u64 {
     }
 }
 
+/// Reorders `addrs` round-robin across netgroups (see `addr_bucket_key`), keeping the relative
+/// order within each group, so the first connection attempts land on distinct networks.
+fn spread_by_netgroup(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let mut order: Vec<u64> = Vec::new();
+    let mut groups: HashMap<u64, VecDeque<SocketAddr>> = HashMap::new();
+    for addr in addrs {
+        let key = addr_bucket_key(&addr);
+        if !groups.contains_key(&key) {
+            order.push(key);
+        }
+        groups.entry(key).or_default().push_back(addr);
+    }
+    let mut out = Vec::new();
+    loop {
+        let before = out.len();
+        for key in &order {
+            if let Some(addr) = groups.get_mut(key).and_then(VecDeque::pop_front) {
+                out.push(addr);
+            }
+        }
+        if out.len() == before {
+            return out;
+        }
+    }
+}
+
+/// Address book entries for the bundled fixed seeds, used when no `peers.dat` was loaded.
+fn fixed_seed_entries(params: &ChainParams) -> Vec<(SocketAddr, AddrBookEntry)> {
+    let now = unix_now_secs();
+    params
+        .fixed_seeds
+        .iter()
+        .filter_map(|seed| {
+            let addr = seed.addr.parse::<SocketAddr>().ok()?;
+            Some((
+                addr,
+                AddrBookEntry {
+                    last_seen: now,
+                    last_height: seed.last_height,
+                    ..AddrBookEntry::default()
+                },
+            ))
+        })
+        .collect()
+}
+
 fn merge_addr_entry(existing: &mut AddrBookEntry, incoming: &AddrBookEntry) {
     existing.last_seen = existing.last_seen.max(incoming.last_seen);
     existing.last_success = existing.last_success.max(incoming.last_success);
@@ -770,6 +920,12 @@ struct PeersFileV2 {
     peers: Vec<PeersFileV2Entry>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct AnchorsFile {
+    version: u32,
+    anchors: Vec<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct PeersFileV2Entry {
     addr: String,
@@ -837,6 +993,8 @@ enum PipelineEvent {
 pub(crate) enum Store {
     Memory(MemoryStore),
     Fjall(FjallStore),
+    /// `--db-latency`: the opened backend wrapped in per-column latency histograms.
+    Instrumented(Box<InstrumentedStore<Store>>),
 }
 
 impl Store {
@@ -844,6 +1002,46 @@ impl Store {
         match self {
             Store::Fjall(store) => Some(store.telemetry_snapshot()),
             Store::Memory(_) => None,
+            Store::Instrumented(store) => store.inner().fjall_telemetry_snapshot(),
+        }
+    }
+
+    pub fn latency(&self) -> Option<&StoreLatency> {
+        match self {
+            Store::Instrumented(store) => Some(store.latency().as_ref()),
+            Store::Memory(_) | Store::Fjall(_) => None,
+        }
+    }
+
+    pub fn is_fjall(&self) -> bool {
+        match self {
+            Store::Fjall(_) => true,
+            Store::Memory(_) => false,
+            Store::Instrumented(store) => store.inner().is_fjall(),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            Store::Fjall(_) => Backend::Fjall,
+            Store::Memory(_) => Backend::Memory,
+            Store::Instrumented(store) => store.inner().backend(),
+        }
+    }
+
+    pub fn persist(&self) -> Result<(), StoreError> {
+        match self {
+            Store::Fjall(store) => store.persist(),
+            Store::Memory(_) => Ok(()),
+            Store::Instrumented(store) => store.inner().persist(),
+        }
+    }
+
+    pub fn compact(&self, column: fluxd_storage::Column) -> Result<(), StoreError> {
+        match self {
+            Store::Fjall(store) => store.compact(column),
+            Store::Memory(_) => Ok(()),
+            Store::Instrumented(store) => store.inner().compact(column),
         }
     }
 }
@@ -857,6 +1055,15 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.get(column, key),
             Store::Fjall(store) => store.get(column, key),
+            Store::Instrumented(store) => store.get(column, key),
+        }
+    }
+
+    fn contains(&self, column: fluxd_storage::Column, key: &[u8]) -> Result<bool, StoreError> {
+        match self {
+            Store::Memory(store) => store.contains(column, key),
+            Store::Fjall(store) => store.contains(column, key),
+            Store::Instrumented(store) => store.contains(column, key),
         }
     }
 
@@ -869,6 +1076,7 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.put(column, key, value),
             Store::Fjall(store) => store.put(column, key, value),
+            Store::Instrumented(store) => store.put(column, key, value),
         }
     }
 
@@ -876,6 +1084,33 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.delete(column, key),
             Store::Fjall(store) => store.delete(column, key),
+            Store::Instrumented(store) => store.delete(column, key),
+        }
+    }
+
+    fn compare_and_swap(
+        &self,
+        column: fluxd_storage::Column,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, StoreError> {
+        match self {
+            Store::Memory(store) => store.compare_and_swap(column, key, expected, new),
+            Store::Fjall(store) => store.compare_and_swap(column, key, expected, new),
+            Store::Instrumented(store) => store.compare_and_swap(column, key, expected, new),
+        }
+    }
+
+    fn scan_prefix_iter<'a>(
+        &'a self,
+        column: fluxd_storage::Column,
+        prefix: &[u8],
+    ) -> Result<fluxd_storage::ScanIter<'a>, StoreError> {
+        match self {
+            Store::Memory(store) => store.scan_prefix_iter(column, prefix),
+            Store::Fjall(store) => store.scan_prefix_iter(column, prefix),
+            Store::Instrumented(store) => store.scan_prefix_iter(column, prefix),
         }
     }
 
@@ -887,6 +1122,7 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.scan_prefix(column, prefix),
             Store::Fjall(store) => store.scan_prefix(column, prefix),
+            Store::Instrumented(store) => store.scan_prefix(column, prefix),
         }
     }
 
@@ -899,6 +1135,7 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.scan_prefix_limited(column, prefix, limit),
             Store::Fjall(store) => store.scan_prefix_limited(column, prefix, limit),
+            Store::Instrumented(store) => store.scan_prefix_limited(column, prefix, limit),
         }
     }
 
@@ -911,6 +1148,7 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.for_each_prefix(column, prefix, visitor),
             Store::Fjall(store) => store.for_each_prefix(column, prefix, visitor),
+            Store::Instrumented(store) => store.for_each_prefix(column, prefix, visitor),
         }
     }
 
@@ -923,6 +1161,7 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.scan_range(column, start, end),
             Store::Fjall(store) => store.scan_range(column, start, end),
+            Store::Instrumented(store) => store.scan_range(column, start, end),
         }
     }
 
@@ -936,6 +1175,7 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.for_each_range(column, start, end, visitor),
             Store::Fjall(store) => store.for_each_range(column, start, end, visitor),
+            Store::Instrumented(store) => store.for_each_range(column, start, end, visitor),
         }
     }
 
@@ -943,6 +1183,15 @@ impl KeyValueStore for Store {
         match self {
             Store::Memory(store) => store.write_batch(batch),
             Store::Fjall(store) => store.write_batch(batch),
+            Store::Instrumented(store) => store.write_batch(batch),
+        }
+    }
+
+    fn snapshot(&self) -> Result<Box<dyn fluxd_storage::ReadSnapshot>, StoreError> {
+        match self {
+            Store::Memory(store) => store.snapshot(),
+            Store::Fjall(store) => store.snapshot(),
+            Store::Instrumented(store) => store.snapshot(),
         }
     }
 }
@@ -975,6 +1224,71 @@ impl Drop for TuiThreadGuard {
     }
 }
 
+/// Tracks background persist threads so shutdown can wake them for a final save and join them.
+#[derive(Default)]
+struct ShutdownCoordinator {
+    requested: Mutex<bool>,
+    wake: Condvar,
+    tasks: Mutex<Vec<(&'static str, thread::JoinHandle<()>)>>,
+}
+
+impl ShutdownCoordinator {
+    fn spawn<F>(self: &Arc<Self>, name: &'static str, task: F)
+    where
+        F: FnOnce(Arc<ShutdownCoordinator>) + Send + 'static,
+    {
+        let coordinator = Arc::clone(self);
+        let handle = match thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || task(coordinator))
+        {
+            Ok(handle) => handle,
+            Err(err) => {
+                log_warn!("failed to spawn {name} thread: {err}");
+                return;
+            }
+        };
+        match self.tasks.lock() {
+            Ok(mut tasks) => tasks.push((name, handle)),
+            Err(_) => log_warn!("shutdown task list poisoned; {name} will not be joined"),
+        }
+    }
+
+    /// Sleeps for `interval` or until shutdown is requested; returns true once shutdown is pending.
+    fn wait(&self, interval: Duration) -> bool {
+        let Ok(requested) = self.requested.lock() else {
+            return true;
+        };
+        match self
+            .wake
+            .wait_timeout_while(requested, interval, |requested| !*requested)
+        {
+            Ok((requested, _)) => *requested,
+            Err(_) => true,
+        }
+    }
+
+    fn request(&self) {
+        if let Ok(mut requested) = self.requested.lock() {
+            *requested = true;
+        }
+        self.wake.notify_all();
+    }
+
+    fn drain(&self) {
+        self.request();
+        let tasks = match self.tasks.lock() {
+            Ok(mut tasks) => std::mem::take(&mut *tasks),
+            Err(_) => return,
+        };
+        for (name, handle) in tasks {
+            if handle.join().is_err() {
+                log_warn!("{name} thread panicked during shutdown");
+            }
+        }
+    }
+}
+
 fn lock_data_dir(data_dir: &Path) -> Result<DataDirLock, String> {
     let lock_path = data_dir.join(DATA_DIR_LOCK_FILE_NAME);
     let mut file = OpenOptions::new()
@@ -1020,29 +1334,65 @@ fn lock_data_dir(data_dir: &Path) -> Result<DataDirLock, String> {
     }
 }
 
+impl Config {
+    /// Builds a run configuration from fluxd command-line arguments (without the program name).
+    pub fn from_args<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        match parse_args_from(args, false)? {
+            CliAction::Run(config) => Ok(config),
+            _ => Err("arguments do not describe a node run".to_string()),
+        }
+    }
+}
+
 pub async fn run_entry(default_tui: bool) -> Result<(), String> {
     let cli = parse_args(default_tui)?;
     match cli {
         CliAction::PrintHelp => {
             println!("{}", usage());
-            return Ok(());
+            Ok(())
         }
         CliAction::PrintVersion => {
             println!("fluxd-rust {}", env!("CARGO_PKG_VERSION"));
-            return Ok(());
+            Ok(())
+        }
+        CliAction::PrintConfig(config) => {
+            let json = serde_json::to_string_pretty(&resolved_config_json(&config))
+                .map_err(|err| err.to_string())?;
+            println!("{json}");
+            Ok(())
         }
+        CliAction::Doctor(config) => doctor::run(&config),
+        CliAction::MigrateDatadir { config, target } => migrate_datadir::run(&config, &target),
         CliAction::TuiAttach { endpoint } => {
             tui::run_remote_tui(endpoint)?;
-            return Ok(());
+            Ok(())
         }
         CliAction::Run(config) => {
-            let start_time = Instant::now();
-            return run_with_config(start_time, config).await;
+            let data_dir = config.data_dir.clone();
+            let reindex_on_corruption = config.reindex_on_corruption;
+            let Err(err) = run_with_config(Instant::now(), config, None).await else {
+                return Ok(());
+            };
+            // The failed run's listeners and background tasks still hold the store, so the
+            // reindex happens in a fresh process started by the supervisor.
+            if reindex_on_corruption && corruption::schedule_reindex(&data_dir, &err)? {
+                return Err(format!(
+                    "{err}\nexiting to reindex on the next start (--reindex-on-corruption)"
+                ));
+            }
+            Err(err)
         }
     }
 }
 
-async fn run_with_config(start_time: Instant, config: Config) -> Result<(), String> {
+async fn run_with_config(
+    start_time: Instant,
+    config: Config,
+    ready_tx: Option<embed::NodeReadySender>,
+) -> Result<(), String> {
     logging::init(logging::LogConfig {
         level: config.log_level,
         format: config.log_format,
@@ -1058,13 +1408,25 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         && !config.scan_fluxnodes
         && config.debug_fluxnode_payee_script.is_none()
         && config.debug_fluxnode_payout_height.is_none()
-        && config.debug_fluxnode_payee_candidates.is_none();
+        && config.debug_fluxnode_payee_candidates.is_none()
+        && config.dump_blocks.is_none()
+        && config.dump_headers.is_none()
+        && config.benchmark_connect.is_none();
 
     if spawn_tui {
         logging::enable_capture(4096);
     }
 
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let shutdown = Arc::new(ShutdownCoordinator::default());
+    {
+        let shutdown = Arc::clone(&shutdown);
+        let mut shutdown_rx = shutdown_rx.clone();
+        tokio::spawn(async move {
+            let _ = shutdown_rx.wait_for(|requested| *requested).await;
+            shutdown.request();
+        });
+    }
 
     log_info!(
         "Startup: begin (tui={}, backend={:?}, network={:?}, data_dir={})",
@@ -1077,11 +1439,15 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
     let network = config.network;
     let backend = config.backend;
     let status_interval_secs = config.status_interval_secs;
-    let dashboard_addr = config.dashboard_addr;
+    let dashboard_addr = config.dashboard_addr.clone();
+    let ready_max_lag = config.ready_max_lag;
     let getdata_batch = config.getdata_batch;
-    let block_peers_target = config.block_peers;
+    let block_peers_target = config.adaptive_peers.map_or(config.block_peers, |bounds| {
+        bounds.clamp(config.block_peers)
+    });
     let header_peers_target = config.header_peers;
     let header_lead = config.header_lead;
+    let block_download_window = config.block_download_window;
     let header_verify_workers = resolve_header_verify_workers(&config);
     let inflight_per_peer = config.inflight_per_peer;
     let data_dir = &config.data_dir;
@@ -1093,7 +1459,18 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
     let _data_dir_lock = lock_data_dir(data_dir)?;
 
     let net_totals = Arc::new(NetTotals::default());
+    if config.max_upload_target_mb > 0 {
+        net_totals.set_max_upload_target(config.max_upload_target_mb.saturating_mul(1024 * 1024));
+        log_info!(
+            "Upload target: {} MB per rolling 24h; historical blocks are not served once reached",
+            config.max_upload_target_mb
+        );
+    }
     let peer_registry = Arc::new(PeerRegistry::default());
+    if let Some(external_ip) = config.external_ip {
+        log_info!("Advertising external address {external_ip}");
+        peer_registry.set_local_addr(Some(external_ip));
+    }
 
     let mut tui_thread = TuiThreadGuard::new(shutdown_tx.clone());
     let mut tui_init_tx: Option<crossbeam_channel::Sender<tui::TuiInit>> = None;
@@ -1279,6 +1656,37 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         undo,
         config.utxo_cache_entries,
     ));
+    chainstate
+        .set_utxo_flush_policy(UtxoFlushPolicy {
+            max_dirty_entries: config.utxo_flush_entries,
+            max_dirty_bytes: config.utxo_flush_mb.saturating_mul(1024 * 1024),
+        })
+        .map_err(|err| err.to_string())?;
+    chainstate.set_max_reorg_depth(config.max_reorg_depth);
+    // UTXO recovery goes first: it rewinds to the flush tip using only indexed block and undo
+    // records. Reconciliation never moves the tip, so running it on the rewound chain cannot
+    // leave the tip below the flush height.
+    let rewound = chainstate
+        .recover_unflushed_utxos()
+        .map_err(|err| format!("failed to recover unflushed UTXO writes: {err}"))?;
+    if rewound > 0 {
+        log_warn!("Disconnected {rewound} block(s) whose UTXO changes were lost in a crash; they will be connected again");
+    }
+    // A reindex rebuilds the index from the flatfiles, so there is nothing to reconcile yet.
+    if !reindex_from_flatfiles {
+        let reconciled = chainstate
+            .reconcile_flatfiles_and_index()
+            .map_err(|err| format!("failed to reconcile flatfiles with the block index: {err}"))?;
+        if !reconciled.is_clean() {
+            log_warn!(
+                "Reconciled flatfiles after an unclean shutdown: {} block(s) parked for reconnection, {} torn record(s) truncated, {} missing block(s) and {} missing undo record(s) dropped from the index",
+                reconciled.parked_blocks,
+                reconciled.truncated_records,
+                reconciled.dropped_blocks,
+                reconciled.dropped_undo
+            );
+        }
+    }
 
     if config.db_info {
         let info = db_info::collect_db_info(
@@ -1293,7 +1701,10 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         println!("{json}");
         return Ok(());
     }
-    let header_peer_book = Arc::new(HeaderPeerBook::default());
+    let header_peer_book = Arc::new(HeaderPeerBook::new(
+        config.ban_policy.clone(),
+        &config.whitelist,
+    )?);
     let addr_book = Arc::new(AddrBook::default());
     let added_nodes = Arc::new(Mutex::new(HashSet::<String>::new()));
     let peers_path = data_dir.join(PEERS_FILE_NAME);
@@ -1309,6 +1720,29 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         }
         Err(err) => log_warn!("failed to load peers file: {err}"),
     }
+    if addr_book.len() == 0 {
+        let seeded = addr_book.load_entries(fixed_seed_entries(&params));
+        if seeded > 0 {
+            log_info!("No known peers; seeded the address book with {seeded} fixed seed(s)");
+        }
+    }
+    let anchors_path = data_dir.join(ANCHORS_FILE_NAME);
+    match load_anchors_file(&anchors_path) {
+        Ok(anchors) => {
+            if !anchors.is_empty() {
+                log_info!(
+                    "Loaded {} anchor peer(s) from {}",
+                    anchors.len(),
+                    anchors_path.display()
+                );
+                addr_book.set_anchors(anchors);
+            }
+        }
+        Err(err) => log_warn!("failed to load anchors file: {err}"),
+    }
+    // Anchors are single-use: after a crash we fall back to normal selection rather than
+    // re-dialling whatever was saved at the last clean shutdown.
+    let _ = fs::remove_file(&anchors_path);
 
     if !config.addnode_nodes.is_empty() {
         if let Ok(mut guard) = added_nodes.lock() {
@@ -1337,12 +1771,22 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
     {
         let addr_book = Arc::clone(&addr_book);
         let peers_path = peers_path.clone();
-        thread::spawn(move || persist_peers_loop(addr_book, peers_path));
+        shutdown.spawn("persist-peers", move |shutdown| {
+            persist_peers_loop(addr_book, peers_path, shutdown)
+        });
+    }
+    {
+        let peer_registry = Arc::clone(&peer_registry);
+        shutdown.spawn("persist-anchors", move |shutdown| {
+            persist_anchors_loop(peer_registry, anchors_path, shutdown)
+        });
     }
     {
         let header_peer_book = Arc::clone(&header_peer_book);
         let banlist_path = banlist_path.clone();
-        thread::spawn(move || persist_banlist_loop(header_peer_book, banlist_path));
+        shutdown.spawn("persist-banlist", move |shutdown| {
+            persist_banlist_loop(header_peer_book, banlist_path, shutdown)
+        });
     }
 
     log_info!(
@@ -1402,7 +1846,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
     }
 
     if config.scan_flatfiles {
-        scan_flatfiles(chainstate.as_ref(), &blocks_path)?;
+        scan_flatfiles(chainstate.as_ref())?;
         return Ok(());
     }
 
@@ -1437,7 +1881,20 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         return Ok(());
     }
 
-    let rpc_addr = config.rpc_addr.unwrap_or_else(|| default_rpc_addr(network));
+    if let Some(range) = config.dump_blocks.as_ref() {
+        dump_blocks(chainstate.as_ref(), range)?;
+        return Ok(());
+    }
+
+    if let Some(range) = config.dump_headers.as_ref() {
+        dump_headers(chainstate.as_ref(), range)?;
+        return Ok(());
+    }
+
+    let rpc_addr = config
+        .rpc_addr
+        .clone()
+        .unwrap_or_else(|| default_rpc_addr(network).into());
     let rpc_auth =
         rpc::load_or_create_auth(config.rpc_user.clone(), config.rpc_pass.clone(), data_dir)?;
     let rpc_allowlist =
@@ -1471,21 +1928,60 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
             let _ = shutdown_tx.send(true);
         });
     }
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+        let (mut sigusr1, mut sigusr2) = match (
+            signal(SignalKind::user_defined1()),
+            signal(SignalKind::user_defined2()),
+        ) {
+            (Ok(sigusr1), Ok(sigusr2)) => (sigusr1, sigusr2),
+            (Err(err), _) | (_, Err(err)) => {
+                log_warn!("failed to install SIGUSR1/SIGUSR2 log level handlers: {err}");
+                return;
+            }
+        };
+        loop {
+            tokio::select! {
+                _ = sigusr1.recv() => {
+                    let level = logging::raise_level();
+                    log_warn!("SIGUSR1: log level raised to {}", level.as_str());
+                }
+                _ = sigusr2.recv() => {
+                    logging::reset_levels();
+                    log_warn!(
+                        "SIGUSR2: log level reset to {}",
+                        logging::configured_level().as_str()
+                    );
+                }
+            }
+        }
+    });
 
-    if !rpc_addr.ip().is_loopback() && config.rpc_allow_ips.is_empty() {
+    if let Some(tcp_addr) = rpc_addr.tcp() {
+        if !tcp_addr.ip().is_loopback() && config.rpc_allow_ips.is_empty() {
+            log_warn!(
+                "Warning: RPC is bound to {} but no rpcallowip is set; only localhost connections will be permitted",
+                tcp_addr
+            );
+        }
+    } else if !config.rpc_allow_ips.is_empty() {
         log_warn!(
-            "Warning: RPC is bound to {} but no rpcallowip is set; only localhost connections will be permitted",
+            "Warning: rpcallowip does not apply to {}; access is controlled by the socket file permissions",
             rpc_addr
         );
     }
 
     let validation_metrics = Arc::new(ValidationMetrics::default());
     let connect_metrics = Arc::new(ConnectMetrics::default());
+    chainstate.attach_connect_metrics(Arc::clone(&connect_metrics));
     let write_lock = Arc::new(Mutex::new(()));
     let sync_metrics = Arc::new(SyncMetrics::default());
     let header_metrics = Arc::new(HeaderMetrics::default());
 
-    let mempool = Arc::new(Mutex::new(mempool::Mempool::new(config.mempool_max_bytes)));
+    let mut mempool = mempool::Mempool::new(config.mempool_max_bytes);
+    mempool.set_min_fee_increment_per_kb(config.min_relay_fee_per_kb);
+    let mempool = Arc::new(Mutex::new(mempool));
     let mut mempool_policy =
         mempool::MempoolPolicy::standard(config.min_relay_fee_per_kb, config.require_standard);
     mempool_policy.limit_free_relay_kb_per_minute = config.limit_free_relay_kb_per_minute;
@@ -1493,7 +1989,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
     let mempool_metrics = Arc::new(stats::MempoolMetrics::default());
 
     let fee_estimates_path = data_dir.join(FEE_ESTIMATES_FILE_NAME);
-    let fee_estimator =
+    let mut fee_estimator =
         match fee_estimator::FeeEstimator::load(&fee_estimates_path, config.min_relay_fee_per_kb) {
             Ok(estimator) => estimator,
             Err(err) => {
@@ -1504,9 +2000,22 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
                 fee_estimator::FeeEstimator::new(config.min_relay_fee_per_kb)
             }
         };
+    if fee_estimator.is_empty() {
+        match fee_estimator.seed_from_blocks(chainstate.as_ref(), FEE_ESTIMATES_SEED_BLOCKS) {
+            Ok(0) => {}
+            Ok(seeded) => log_info!(
+                "Seeded fee estimates from {seeded} transaction(s) in the last {FEE_ESTIMATES_SEED_BLOCKS} blocks"
+            ),
+            Err(err) => log_warn!("failed to seed fee estimates from recent blocks: {err}"),
+        }
+    }
     let fee_estimator = Arc::new(Mutex::new(fee_estimator));
 
     let (tx_announce, _) = broadcast::channel::<Hash256>(TX_ANNOUNCE_QUEUE);
+    let tx_feedback = Arc::new(tx_relay::TxFeedbackWaiters::new(
+        tx_relay::TX_FEEDBACK_CAP,
+        Duration::from_secs(config.tx_reject_wait_secs),
+    ));
 
     let wallet_start = Instant::now();
     let wallet =
@@ -1536,6 +2045,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
                 mempool_flags_rx,
                 wallet: Arc::clone(&wallet),
                 tx_announce: tx_announce.clone(),
+                header_peer_book: Arc::clone(&header_peer_book),
             });
         }
     }
@@ -1567,15 +2077,47 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         "Startup: loaded shielded params in {}ms",
         params_start.elapsed().as_millis()
     );
+    let verify_settings = resolve_verify_settings(
+        &config,
+        getdata_batch,
+        inflight_per_peer,
+        config
+            .adaptive_peers
+            .map_or(block_peers_target, |bounds| bounds.max),
+    );
+    log_info!(
+        "Worker settings: header_verify_workers={} verify_workers={} shielded_workers={} verify_queue={}",
+        header_verify_workers,
+        verify_settings.verify_workers,
+        verify_settings.shielded_workers,
+        verify_settings.verify_queue
+    );
+
+    let script_pool = build_script_pool(verify_settings.verify_workers)?;
     let flags = validation_flags(
         Arc::new(shielded_params),
         config.check_script,
+        config.check_canonical_encoding,
+        config.assume_valid,
+        Some(script_pool),
         Some(Arc::clone(&validation_metrics)),
     );
     if let Some(tx) = mempool_flags_tx.as_ref() {
         let _ = tx.send(flags.clone());
     }
 
+    if let Some(range) = config.benchmark_connect {
+        bench_connect::run(
+            chainstate.as_ref(),
+            &config,
+            params.as_ref(),
+            &flags,
+            &verify_settings,
+            range,
+        )?;
+        return Ok(());
+    }
+
     if reindex_from_flatfiles {
         reindex_blocks_from_flatfiles(
             chainstate.as_ref(),
@@ -1584,16 +2126,34 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
             &flags,
             write_lock.as_ref(),
         )?;
+        compact_after_bulk_load(store.as_ref())?;
+        let _ = fs::remove_file(data_dir.join(corruption::AUTO_REINDEX_MARKER_FILE_NAME));
+    }
+
+    if let Some(path) = config.import_blocks.as_deref() {
+        import_blocks(
+            chainstate.as_ref(),
+            path,
+            params.as_ref(),
+            &flags,
+            write_lock.as_ref(),
+        )?;
+        compact_after_bulk_load(store.as_ref())?;
     }
 
     if !reindex_from_flatfiles {
-        if config.reindex_txindex {
+        let targets = IndexRebuildTargets {
+            txindex: config.reindex_txindex,
+            spentindex: config.reindex_spentindex,
+            addressindex: config.reindex_addressindex,
+        };
+        if targets.names().len() > 1 {
+            rebuild_all_indexes(chainstate.as_ref(), write_lock.as_ref(), targets)?;
+        } else if targets.txindex {
             rebuild_txindex(chainstate.as_ref(), write_lock.as_ref())?;
-        }
-        if config.reindex_spentindex {
+        } else if targets.spentindex {
             rebuild_spentindex(chainstate.as_ref(), write_lock.as_ref())?;
-        }
-        if config.reindex_addressindex {
+        } else if targets.addressindex {
             rebuild_addressindex(chainstate.as_ref(), write_lock.as_ref())?;
         }
         if config.reindex_addresstx {
@@ -1603,6 +2163,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
             rebuild_addresstxindex(chainstate.as_ref(), write_lock.as_ref())?;
         }
     }
+    warn_unfinished_index_rebuilds(store.as_ref())?;
 
     {
         let chainstate = Arc::clone(&chainstate);
@@ -1625,6 +2186,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         let addr_book = Arc::clone(&addr_book);
         let added_nodes = Arc::clone(&added_nodes);
         let tx_announce = tx_announce.clone();
+        let tx_feedback = Arc::clone(&tx_feedback);
         let wallet = Arc::clone(&wallet);
         let shutdown_tx = shutdown_tx.clone();
         thread::spawn(move || {
@@ -1657,6 +2219,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
                     addr_book,
                     added_nodes,
                     tx_announce,
+                    tx_feedback,
                     wallet,
                     shutdown_tx,
                 )
@@ -1670,20 +2233,6 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
 
     rpc::spawn_address_neighbors_catchup_task(Arc::clone(&chainstate), shutdown_rx.clone());
 
-    let verify_settings = resolve_verify_settings(
-        &config,
-        getdata_batch,
-        inflight_per_peer,
-        block_peers_target,
-    );
-    log_info!(
-        "Worker settings: header_verify_workers={} verify_workers={} shielded_workers={} verify_queue={}",
-        header_verify_workers,
-        verify_settings.verify_workers,
-        verify_settings.shielded_workers,
-        verify_settings.verify_queue
-    );
-
     ensure_genesis(
         &chainstate,
         params.as_ref(),
@@ -1722,6 +2271,20 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         log_info!("Shielded value pools rebuilt.");
     }
 
+    if !chainstate
+        .fluxnode_index_ready()
+        .map_err(|err| err.to_string())?
+    {
+        log_info!("Fluxnode index missing; rebuilding from fluxnode records (one-time).");
+        let _guard = write_lock
+            .lock()
+            .map_err(|_| "write lock poisoned".to_string())?;
+        let indexed = chainstate
+            .rebuild_fluxnode_index()
+            .map_err(|err| err.to_string())?;
+        log_info!("Fluxnode index rebuilt ({indexed} record(s)).");
+    }
+
     if config.mempool_persist_interval_secs > 0 {
         match load_mempool_file(&mempool_path) {
             Ok(raws) => {
@@ -1862,8 +2425,39 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         let mempool_metrics = Arc::clone(&mempool_metrics);
         let mempool_path = mempool_path.clone();
         let interval_secs = config.mempool_persist_interval_secs;
-        thread::spawn(move || {
-            persist_mempool_loop(mempool, mempool_metrics, mempool_path, interval_secs)
+        shutdown.spawn("persist-mempool", move |shutdown| {
+            persist_mempool_loop(
+                mempool,
+                mempool_metrics,
+                mempool_path,
+                interval_secs,
+                shutdown,
+            )
+        });
+    }
+
+    if config.mempool_expiry_hours > 0 {
+        let mempool = Arc::clone(&mempool);
+        let mempool_metrics = Arc::clone(&mempool_metrics);
+        let fee_estimator = Arc::clone(&fee_estimator);
+        let max_age_secs = config.mempool_expiry_hours.saturating_mul(60 * 60);
+        shutdown.spawn("mempool-expiry", move |shutdown| {
+            mempool_expiry_loop(
+                mempool,
+                mempool_metrics,
+                fee_estimator,
+                max_age_secs,
+                shutdown,
+            )
+        });
+    }
+
+    if config.unconnected_expiry_hours > 0 {
+        let chainstate = Arc::clone(&chainstate);
+        let write_lock = Arc::clone(&write_lock);
+        let max_age_secs = config.unconnected_expiry_hours.saturating_mul(60 * 60);
+        shutdown.spawn("unconnected-prune", move |shutdown| {
+            unconnected_prune_loop(chainstate, write_lock, max_age_secs, shutdown)
         });
     }
 
@@ -1871,8 +2465,8 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         let fee_estimator = Arc::clone(&fee_estimator);
         let fee_estimates_path = fee_estimates_path.clone();
         let interval_secs = config.fee_estimates_persist_interval_secs;
-        thread::spawn(move || {
-            persist_fee_estimates_loop(fee_estimator, fee_estimates_path, interval_secs)
+        shutdown.spawn("persist-fee-estimates", move |shutdown| {
+            persist_fee_estimates_loop(fee_estimator, fee_estimates_path, interval_secs, shutdown)
         });
     }
 
@@ -1923,6 +2517,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
                     network,
                     backend,
                     start_time,
+                    ready_max_lag,
                 )
                 .await
                 {
@@ -1943,12 +2538,18 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         let peer_registry = Arc::clone(&peer_registry);
         let net_totals = Arc::clone(&net_totals);
         let max_connections = config.max_connections;
+        let send_throttle = (config.max_inbound_send_rate_kb > 0).then(|| {
+            Arc::new(p2p_server::InboundSendThrottle::new(
+                config.max_inbound_send_rate_kb.saturating_mul(1024),
+            ))
+        });
         let mempool = Arc::clone(&mempool);
         let mempool_policy = Arc::clone(&mempool_policy);
         let mempool_metrics = Arc::clone(&mempool_metrics);
         let fee_estimator = Arc::clone(&fee_estimator);
         let flags = flags.clone();
         let tx_announce = tx_announce.clone();
+        let tx_feedback = Arc::clone(&tx_feedback);
         tokio::spawn(async move {
             if let Err(err) = p2p_server::serve_inbound_p2p(
                 listener,
@@ -1964,6 +2565,8 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
                 fee_estimator,
                 flags,
                 tx_announce,
+                tx_feedback,
+                send_throttle,
             )
             .await
             {
@@ -1974,9 +2577,28 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         log_info!("P2P listener disabled (--no-p2p-listen)");
     }
 
-    let start_height = start_height(&chainstate)?;
-    let min_peer_height = chainstate
-        .best_header()
+    if let Some(addr) = config.notify_addr {
+        let listener = notify::bind_notify(addr).await?;
+        let publisher = Arc::new(notify::NotifyPublisher::new(config.notify_topics));
+        tokio::spawn(notify::notify_tip_loop(
+            Arc::clone(&chainstate),
+            Arc::clone(&publisher),
+        ));
+        tokio::spawn(notify::notify_tx_loop(
+            tx_announce.subscribe(),
+            Arc::clone(&mempool),
+            Arc::clone(&publisher),
+        ));
+        tokio::spawn(async move {
+            if let Err(err) = notify::serve_notifications(listener, publisher).await {
+                log_warn!("notify listener stopped: {err}");
+            }
+        });
+    }
+
+    let start_height = start_height(&chainstate)?;
+    let min_peer_height = chainstate
+        .best_header()
         .map_err(|err| err.to_string())?
         .map(|tip| tip.height)
         .unwrap_or(start_height);
@@ -2125,6 +2747,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
             log_error!("header commit stopped: {err}");
         }
     });
+    let block_relay_header_tx = header_tx.clone();
     let header_sync_metrics = Arc::clone(&header_metrics);
     let header_peer_ctx_task = header_peer_ctx.clone();
     tokio::spawn(async move {
@@ -2147,6 +2770,32 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         }
     });
 
+    if config.block_relay_peers > 0 {
+        let block_relay_peer_ctx = PeerContext {
+            net_totals: Arc::clone(&net_totals),
+            registry: Arc::clone(&peer_registry),
+            kind: PeerKind::BlockRelayOnly,
+        };
+        let block_relay_chainstate = Arc::clone(&chainstate);
+        let block_relay_params = Arc::clone(&params);
+        let block_relay_addr_book = Arc::clone(&addr_book);
+        let block_relay_target = config.block_relay_peers;
+        tokio::spawn(async move {
+            if let Err(err) = block_relay::block_relay_loop(
+                block_relay_chainstate,
+                block_relay_params,
+                block_relay_addr_book,
+                block_relay_peer_ctx,
+                block_relay_header_tx,
+                block_relay_target,
+            )
+            .await
+            {
+                log_warn!("block relay stopped: {err}");
+            }
+        });
+    }
+
     if config.tx_peers > 0 {
         let relay_peer_ctx = PeerContext {
             net_totals: Arc::clone(&net_totals),
@@ -2162,6 +2811,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         let relay_fee_estimator = Arc::clone(&fee_estimator);
         let relay_flags = flags.clone();
         let relay_tx_announce = tx_announce.clone();
+        let relay_tx_feedback = Arc::clone(&tx_feedback);
         let relay_target = config.tx_peers;
         tokio::spawn(async move {
             if let Err(err) = tx_relay::tx_relay_loop(
@@ -2175,6 +2825,7 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
                 relay_fee_estimator,
                 relay_flags,
                 relay_tx_announce,
+                relay_tx_feedback,
                 relay_target,
             )
             .await
@@ -2184,10 +2835,25 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         });
     }
 
-    sync_chain(
+    if let Some(ready_tx) = ready_tx {
+        let _ = ready_tx.send(embed::NodeContext {
+            chainstate: Arc::clone(&chainstate),
+            params: Arc::clone(&params),
+            mempool: Arc::clone(&mempool),
+            mempool_policy: Arc::clone(&mempool_policy),
+            mempool_metrics: Arc::clone(&mempool_metrics),
+            fee_estimator: Arc::clone(&fee_estimator),
+            flags: flags.clone(),
+            tx_announce: tx_announce.clone(),
+            shutdown_tx: shutdown_tx.clone(),
+        });
+    }
+
+    let sync_result = sync_chain(
         &mut block_peer,
         &mut block_peers,
         block_peers_target,
+        config.adaptive_peers,
         Arc::clone(&chainstate),
         Arc::clone(&mempool),
         Arc::clone(&fee_estimator),
@@ -2204,15 +2870,26 @@ async fn run_with_config(start_time: Instant, config: Config) -> Result<(), Stri
         header_lead,
         getdata_batch,
         inflight_per_peer,
+        block_download_window,
         shutdown_rx.clone(),
     )
-    .await?;
+    .await;
 
     if *shutdown_rx.borrow() {
         tokio::time::sleep(Duration::from_millis(200)).await;
     }
 
-    Ok(())
+    log_info!("Shutdown: flushing persisted state");
+    let _ = shutdown_tx.send(true);
+    shutdown.drain();
+    if let Err(err) = chainstate.flush_utxos() {
+        log_warn!("failed to flush pending UTXO writes on shutdown: {err}");
+    }
+    if let Err(err) = store.persist() {
+        log_warn!("failed to sync database on shutdown: {err}");
+    }
+
+    sync_result
 }
 
 fn scan_supply<S: KeyValueStore>(
@@ -2230,13 +2907,13 @@ fn scan_supply<S: KeyValueStore>(
     }
 
     let mut total_coinbase: i128 = 0;
-    let mut total_expected: i128 = 0;
     let mut last_progress = Instant::now();
 
     for height in 0..=best.height {
         let hash = chainstate
             .height_hash(height)
             .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| format!("missing height index for height {height}"))?;
         let location = chainstate
             .block_location(&hash)
@@ -2253,12 +2930,6 @@ fn scan_supply<S: KeyValueStore>(
         let coinbase_value = tx_value_out_for_supply(coinbase)?;
         total_coinbase += coinbase_value as i128;
 
-        let expected = block_subsidy(height, &params.consensus) as i128
-            + exchange_fund_amount(height, &params.funding) as i128
-            + foundation_fund_amount(height, &params.funding) as i128
-            + swap_pool_amount(height as i64, &params.swap_pool) as i128;
-        total_expected += expected;
-
         if height > 0 && height % 100_000 == 0 {
             println!(
                 "Scanned height {} (elapsed {:?})",
@@ -2269,22 +2940,31 @@ fn scan_supply<S: KeyValueStore>(
         }
     }
 
+    let total_expected = schedule_total(subsidy_schedule(&params.consensus), best.height)
+        + schedule_total(exchange_fund_schedule(&params.funding), best.height)
+        + schedule_total(foundation_fund_schedule(&params.funding), best.height)
+        + schedule_total(swap_pool_schedule(&params.swap_pool), best.height);
     let delta = total_coinbase - total_expected;
+    let format_amount = |amount: i128| {
+        i64::try_from(amount)
+            .map(money::format)
+            .map_err(|_| format!("supply total {amount} does not fit in an amount"))
+    };
     println!("Supply scan complete at height {}", best.height);
     println!(
         "Total coinbase out: {} ({})",
         total_coinbase,
-        format_amount(total_coinbase)
+        format_amount(total_coinbase)?
     );
     println!(
         "Expected subsidy+funds: {} ({})",
         total_expected,
-        format_amount(total_expected)
+        format_amount(total_expected)?
     );
     println!(
         "Coinbase minus expected: {} ({})",
         delta,
-        format_amount(delta)
+        format_amount(delta)?
     );
     Ok(())
 }
@@ -2395,7 +3075,7 @@ fn debug_find_fluxnode_payee_script<S: KeyValueStore>(
     let mut matches = 0usize;
     for record in &records {
         let utxo = chainstate
-            .utxo_entry(&record.collateral)
+            .utxo_entry(&record.collateral.txid(), record.collateral.index)
             .map_err(|err| err.to_string())?;
 
         let operator_pubkey = chainstate
@@ -2557,234 +3237,46 @@ fn debug_print_fluxnode_payee_candidates<S: KeyValueStore>(
     }
 
     let pay_height = height.saturating_sub(1);
-    let pay_height_u32 =
-        u32::try_from(pay_height).map_err(|_| "height out of range".to_string())?;
-    let expiration = {
-        use fluxd_consensus::constants::{
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1,
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2,
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3,
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4,
-        };
-
-        let upgrades = &params.consensus.upgrades;
-        let count = if network_upgrade_active(pay_height, upgrades, UpgradeIndex::Pon) {
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4
-        } else if network_upgrade_active(pay_height, upgrades, UpgradeIndex::Halving) {
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3
-        } else if network_upgrade_active(pay_height, upgrades, UpgradeIndex::Flux) {
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2
-        } else {
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1
-        };
-        u32::try_from(count).unwrap_or_default()
-    };
-    let expire_height_for_last_confirmed = |last_confirmed_height: u32| -> u32 {
-        use fluxd_consensus::constants::{
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1,
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2,
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3,
-            FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4,
-        };
-
-        let expiration_for_height = |height: u32| -> u32 {
-            let height_i32 = i32::try_from(height).unwrap_or(i32::MAX);
-            let upgrades = &params.consensus.upgrades;
-            let count = if network_upgrade_active(height_i32, upgrades, UpgradeIndex::Pon) {
-                FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V4
-            } else if network_upgrade_active(height_i32, upgrades, UpgradeIndex::Halving) {
-                FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V3
-            } else if network_upgrade_active(height_i32, upgrades, UpgradeIndex::Flux) {
-                FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V2
-            } else {
-                FLUXNODE_CONFIRM_UPDATE_EXPIRATION_HEIGHT_V1
-            };
-            u32::try_from(count).unwrap_or_default()
-        };
-
-        let mut expiration = expiration_for_height(last_confirmed_height);
-        let mut expire_height = last_confirmed_height
-            .saturating_add(expiration)
-            .saturating_add(1);
-        loop {
-            let next_expiration = expiration_for_height(expire_height);
-            if next_expiration == expiration {
-                break;
-            }
-            expiration = next_expiration;
-            expire_height = last_confirmed_height
-                .saturating_add(expiration)
-                .saturating_add(1);
-        }
-        expire_height
-    };
+    let expiration = fluxnode_confirm_expiration_count(pay_height, &params.consensus);
 
-    let mut candidates: Vec<FluxnodeRecord> = chainstate
+    let mut candidates: Vec<(FluxnodePayeeKey, FluxnodeRecord)> = chainstate
         .fluxnode_records()
         .map_err(|err| err.to_string())?
         .into_iter()
-        .filter(|record| record.tier == tier && record.confirmed_height > 0)
+        .filter(|record| record.tier == tier)
+        .filter_map(|record| FluxnodePayeeKey::from_record(&record).map(|key| (key, record)))
         .collect();
-
-    candidates.sort_by(|a, b| {
-        let a_has_last_paid = a.last_paid_height > 0;
-        let b_has_last_paid = b.last_paid_height > 0;
-        let a_comparator_height = if a_has_last_paid {
-            a.last_paid_height
-        } else {
-            a.confirmed_height
-        };
-        let b_comparator_height = if b_has_last_paid {
-            b.last_paid_height
-        } else {
-            b.confirmed_height
-        };
-        a_comparator_height
-            .cmp(&b_comparator_height)
-            .then_with(|| a_has_last_paid.cmp(&b_has_last_paid))
-            .then_with(|| a.collateral.hash.cmp(&b.collateral.hash))
-            .then_with(|| a.collateral.index.cmp(&b.collateral.index))
-    });
+    candidates.sort_by_key(|(key, _)| *key);
 
     println!(
         "Fluxnode payee candidates tier={tier} height={height} (pay_height={pay_height} expiration={expiration})",
     );
     println!("Candidates scanned: {}", candidates.len());
 
-    #[derive(Clone)]
-    struct EligibleCandidate {
-        idx: usize,
-        outpoint: OutPoint,
-        comparator_height: u32,
-        has_last_paid: bool,
-        confirmed_height: u32,
-        last_confirmed_height: u32,
-        last_paid_height: u32,
-        collateral_value: i64,
-        script: Vec<u8>,
-        utxo_value: i64,
-        utxo_script: Vec<u8>,
-        is_p2sh: bool,
-    }
-
-    let mut eligible: Vec<EligibleCandidate> = Vec::new();
-    for (idx, record) in candidates.iter().enumerate() {
-        let expired =
-            pay_height_u32 >= expire_height_for_last_confirmed(record.last_confirmed_height);
-        if expired {
-            continue;
-        }
-        let Some(utxo) = chainstate
-            .utxo_entry(&record.collateral)
-            .map_err(|err| err.to_string())?
-        else {
-            continue;
-        };
-        if !fluxd_consensus::fluxnode_collateral_matches_tier(
-            pay_height,
-            utxo.value,
-            tier,
-            &params.fluxnode,
-        ) {
-            continue;
-        }
-
-        let (script, is_p2sh) = if let Some(key) = record.p2sh_script {
-            let redeem_script = chainstate
-                .fluxnode_key(key)
-                .map_err(|err| err.to_string())?
-                .ok_or_else(|| "missing fluxnode redeem script".to_string())?;
-            let script_hash = fluxd_primitives::hash::hash160(&redeem_script);
-            let mut script = Vec::with_capacity(23);
-            script.extend_from_slice(&[0xa9, 0x14]);
-            script.extend_from_slice(&script_hash);
-            script.push(0x87);
-            (script, true)
-        } else {
-            let collateral_key = record
-                .collateral_pubkey
-                .ok_or_else(|| "missing fluxnode collateral pubkey key".to_string())?;
-            let pubkey_bytes = chainstate
-                .fluxnode_key(collateral_key)
-                .map_err(|err| err.to_string())?
-                .ok_or_else(|| "missing fluxnode collateral pubkey bytes".to_string())?;
-            let is_p2sh_signing_key = params.fluxnode.p2sh_public_keys.iter().any(|key| {
-                parse_hex_bytes(key.key)
-                    .as_ref()
-                    .is_some_and(|expected| expected.as_slice() == pubkey_bytes.as_slice())
-            });
-            if is_p2sh_signing_key {
-                (utxo.script_pubkey.clone(), true)
-            } else {
-                let pubkey_hash = fluxd_primitives::hash::hash160(&pubkey_bytes);
-                let mut script = Vec::with_capacity(25);
-                script.extend_from_slice(&[0x76, 0xa9, 0x14]);
-                script.extend_from_slice(&pubkey_hash);
-                script.extend_from_slice(&[0x88, 0xac]);
-                (script, false)
-            }
-        };
-
-        let has_last_paid = record.last_paid_height > 0;
-        let comparator_height = if has_last_paid {
-            record.last_paid_height
-        } else {
-            record.confirmed_height
-        };
-        eligible.push(EligibleCandidate {
-            idx,
-            outpoint: record.collateral.clone(),
-            comparator_height,
-            has_last_paid,
-            confirmed_height: record.confirmed_height,
-            last_confirmed_height: record.last_confirmed_height,
-            last_paid_height: record.last_paid_height,
-            collateral_value: record.collateral_value,
-            script,
-            utxo_value: utxo.value,
-            utxo_script: utxo.script_pubkey.clone(),
-            is_p2sh,
-        });
-        if eligible.len() >= 10 {
-            break;
-        }
-    }
+    let winner = select_payee(tier, height, chainstate, params).map_err(|err| err.to_string())?;
 
-    for (idx, record) in candidates.iter().enumerate().take(limit) {
-        let has_last_paid = record.last_paid_height > 0;
-        let comparator_height = if has_last_paid {
-            record.last_paid_height
-        } else {
-            record.confirmed_height
-        };
+    for (idx, (key, record)) in candidates.iter().enumerate().take(limit) {
+        let comparator_height = key.comparator_height;
+        let has_last_paid = key.has_last_paid;
         let outpoint_str = outpoint_to_string(&record.collateral);
 
-        let expired =
-            pay_height_u32 >= expire_height_for_last_confirmed(record.last_confirmed_height);
         let utxo = chainstate
-            .utxo_entry(&record.collateral)
+            .utxo_entry(&record.collateral.txid(), record.collateral.index)
             .map_err(|err| err.to_string())?;
-        let collateral_matches = utxo.as_ref().is_some_and(|utxo| {
-            fluxd_consensus::fluxnode_collateral_matches_tier(
-                pay_height,
-                utxo.value,
-                tier,
-                &params.fluxnode,
-            )
-        });
+        let check = check_payee_candidate(
+            tier,
+            pay_height,
+            record.last_confirmed_height,
+            utxo.as_ref(),
+            params,
+        );
 
         let mut status = Vec::new();
-        if eligible.first().map(|entry| entry.idx) == Some(idx) {
+        if winner.as_ref().map(|payee| &payee.outpoint) == Some(&record.collateral) {
             status.push("WINNER");
         }
-        if expired {
-            status.push("expired");
-        }
-        if utxo.is_none() {
-            status.push("missing_utxo");
-        }
-        if !collateral_matches {
-            status.push("collateral_mismatch");
+        if let Err(reason) = check {
+            status.push(reason.as_str());
         }
         let status = if status.is_empty() {
             "ok".to_string()
@@ -2811,36 +3303,20 @@ fn debug_print_fluxnode_payee_candidates<S: KeyValueStore>(
         }
     }
 
-    if eligible.is_empty() {
-        println!("Selected payee: none (no eligible candidates)");
-        return Ok(());
-    }
-    println!("Eligible candidates (first {}):", eligible.len());
-    for entry in &eligible {
-        println!(
-            "- idx={} outpoint={} comparator_height={} has_last_paid={} confirmed_height={} last_confirmed_height={} last_paid_height={} collateral_value={} script={} utxo_value={} utxo_script={} p2sh={}",
-            entry.idx,
-            outpoint_to_string(&entry.outpoint),
-            entry.comparator_height,
-            entry.has_last_paid,
-            entry.confirmed_height,
-            entry.last_confirmed_height,
-            entry.last_paid_height,
-            entry.collateral_value,
-            hex_encode(&entry.script),
-            entry.utxo_value,
-            hex_encode(&entry.utxo_script),
-            entry.is_p2sh,
-        );
+    match winner {
+        Some(payee) => println!(
+            "Selected payee: outpoint={} script={} p2sh={}",
+            outpoint_to_string(&payee.outpoint),
+            hex_encode(&payee.script_pubkey),
+            payee.is_p2sh,
+        ),
+        None => println!("Selected payee: none (no eligible candidates)"),
     }
 
     Ok(())
 }
 
-fn scan_flatfiles<S: KeyValueStore>(
-    chainstate: &ChainState<S>,
-    blocks_path: &std::path::Path,
-) -> Result<(), String> {
+fn scan_flatfiles<S: KeyValueStore>(chainstate: &ChainState<S>) -> Result<(), String> {
     let best = chainstate.best_block().map_err(|err| err.to_string())?;
     let Some(best) = best else {
         println!("No blocks found in the local database.");
@@ -2856,12 +3332,13 @@ fn scan_flatfiles<S: KeyValueStore>(
         let hash = chainstate
             .height_hash(height)
             .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| format!("missing height index for height {height}"))?;
         let location = chainstate
             .block_location(&hash)
             .map_err(|err| err.to_string())?
             .ok_or_else(|| format!("missing block location for height {height}"))?;
-        match read_flatfile_len(blocks_path, location.file_id, location.offset) {
+        match chainstate.block_stored_len(location) {
             Ok(stored_len) => {
                 if stored_len != location.len {
                     return Err(format!(
@@ -2898,21 +3375,6 @@ fn scan_flatfiles<S: KeyValueStore>(
     Ok(())
 }
 
-fn read_flatfile_len(
-    blocks_path: &std::path::Path,
-    file_id: u32,
-    offset: u64,
-) -> Result<u32, String> {
-    let path = blocks_path.join(format!("data{file_id:05}.dat"));
-    let mut file = File::open(&path).map_err(|err| err.to_string())?;
-    file.seek(SeekFrom::Start(offset))
-        .map_err(|err| err.to_string())?;
-    let mut len_bytes = [0u8; 4];
-    file.read_exact(&mut len_bytes)
-        .map_err(|err| err.to_string())?;
-    Ok(u32::from_le_bytes(len_bytes))
-}
-
 fn tx_value_out_for_supply(tx: &Transaction) -> Result<i64, String> {
     let mut total = 0i64;
     for output in &tx.vout {
@@ -2949,60 +3411,105 @@ fn tx_value_out_for_supply(tx: &Transaction) -> Result<i64, String> {
     Ok(total)
 }
 
-fn format_amount(amount: i128) -> String {
-    let sign = if amount < 0 { "-" } else { "" };
-    let abs = amount.abs();
-    let whole = abs / COIN as i128;
-    let frac = abs % COIN as i128;
-    format!("{sign}{whole}.{frac:08}")
-}
-
-fn open_store(backend: Backend, db_path: &PathBuf, config: &Config) -> Result<Store, String> {
-    match backend {
-        Backend::Memory => Ok(Store::Memory(MemoryStore::new())),
-        Backend::Fjall => {
-            let options = FjallOptions {
-                cache_bytes: config.db_cache_bytes,
-                write_buffer_bytes: config.db_write_buffer_bytes,
-                journal_bytes: config.db_journal_bytes,
-                memtable_bytes: config.db_memtable_bytes,
-                flush_workers: config.db_flush_workers,
-                compaction_workers: config.db_compaction_workers,
-                fsync_ms: config.db_fsync_ms,
-            };
-            let partition_count = fluxd_storage::Column::ALL.len() as u64;
-            if let (Some(write_buffer), Some(memtable)) =
-                (options.write_buffer_bytes, options.memtable_bytes)
-            {
-                let max_memtables = u64::from(memtable).saturating_mul(partition_count);
-                if write_buffer < max_memtables {
-                    log_warn!(
-                        "Warning: --db-write-buffer-mb ({}) is below partitions ({}) × --db-memtable-mb ({}); expect frequent flushes / L0 stalls",
-                        write_buffer / (1024 * 1024),
-                        partition_count,
-                        u64::from(memtable) / (1024 * 1024),
-                    );
-                }
+/// Parses a comma-separated `--db-compress-columns` list of column names.
+fn parse_compress_columns(value: &str) -> Result<Vec<fluxd_storage::Column>, String> {
+    let mut columns = Vec::new();
+    for name in value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let column = fluxd_storage::Column::from_name(name)
+            .ok_or_else(|| format!("unknown db column '{name}'"))?;
+        if column == fluxd_storage::Column::Meta {
+            return Err("the meta column cannot be compressed".to_string());
+        }
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    Ok(columns)
+}
+
+fn open_store(backend: Backend, db_path: &Path, config: &Config) -> Result<Store, String> {
+    let uri = backend.uri(db_path);
+    let mut options = BackendOptions::default();
+    if matches!(uri, BackendUri::Fjall(_)) {
+        options.fjall = FjallOptions {
+            cache_bytes: config.db_cache_bytes,
+            write_buffer_bytes: config.db_write_buffer_bytes,
+            journal_bytes: config.db_journal_bytes,
+            memtable_bytes: config.db_memtable_bytes,
+            flush_workers: config.db_flush_workers,
+            compaction_workers: config.db_compaction_workers,
+            fsync_ms: config.db_fsync_ms,
+            compression: Compression::new(&config.db_compress_columns),
+        };
+        let partition_count = fluxd_storage::Column::ALL.len() as u64;
+        if let (Some(write_buffer), Some(memtable)) = (
+            options.fjall.write_buffer_bytes,
+            options.fjall.memtable_bytes,
+        ) {
+            let max_memtables = u64::from(memtable).saturating_mul(partition_count);
+            if write_buffer < max_memtables {
+                log_warn!(
+                    "Warning: --db-write-buffer-mb ({}) is below partitions ({}) × --db-memtable-mb ({}); expect frequent flushes / L0 stalls",
+                    write_buffer / (1024 * 1024),
+                    partition_count,
+                    u64::from(memtable) / (1024 * 1024),
+                );
             }
-            if let (Some(journal), Some(memtable)) = (options.journal_bytes, options.memtable_bytes)
-            {
-                let min_journal = u64::from(memtable)
-                    .saturating_mul(partition_count)
-                    .saturating_mul(2);
-                if journal < min_journal {
-                    log_warn!(
-                        "Warning: --db-journal-mb ({}) is below 2 × partitions ({}) × --db-memtable-mb ({}); Fjall may halt writes when journals fill",
-                        journal / (1024 * 1024),
-                        partition_count,
-                        u64::from(memtable) / (1024 * 1024),
-                    );
-                }
+        }
+        if let (Some(journal), Some(memtable)) =
+            (options.fjall.journal_bytes, options.fjall.memtable_bytes)
+        {
+            let min_journal = u64::from(memtable)
+                .saturating_mul(partition_count)
+                .saturating_mul(2);
+            if journal < min_journal {
+                log_warn!(
+                    "Warning: --db-journal-mb ({}) is below 2 × partitions ({}) × --db-memtable-mb ({}); Fjall may halt writes when journals fill",
+                    journal / (1024 * 1024),
+                    partition_count,
+                    u64::from(memtable) / (1024 * 1024),
+                );
             }
-            Ok(Store::Fjall(
-                FjallStore::open_with_options(db_path, options).map_err(|err| err.to_string())?,
-            ))
         }
     }
+    let store = match OpenedBackend::open(&uri, options).map_err(|err| err.to_string())? {
+        OpenedBackend::Memory(store) => Store::Memory(store),
+        OpenedBackend::Fjall(store) => {
+            let telemetry = store.telemetry_snapshot();
+            if !telemetry.write_stalled_partitions.is_empty() {
+                log_warn!(
+                    "Warning: Fjall partitions {} have >= {} L0 segments and will throttle writes until compaction catches up",
+                    telemetry.write_stalled_partitions.join(", "),
+                    fluxd_storage::fjall::L0_WRITE_STALL_SEGMENTS,
+                );
+            }
+            Store::Fjall(store)
+        }
+    };
+    if config.db_latency {
+        return Ok(Store::Instrumented(Box::new(InstrumentedStore::new(store))));
+    }
+    Ok(store)
+}
+
+fn compact_after_bulk_load(store: &Store) -> Result<(), String> {
+    if !store.is_fjall() {
+        return Ok(());
+    }
+    let started = Instant::now();
+    log_info!("Compacting database after reindex");
+    for column in fluxd_storage::Column::ALL {
+        store.compact(column).map_err(|err| err.to_string())?;
+    }
+    log_info!(
+        "Database compaction complete in {:.1}s",
+        started.elapsed().as_secs_f64()
+    );
+    Ok(())
 }
 
 fn ensure_db_schema_version(store: &Store) -> Result<u32, String> {
@@ -3028,23 +3535,8 @@ fn ensure_db_schema_version(store: &Store) -> Result<u32, String> {
                 log_warn!(
                     "Database schema version missing; assuming version 1 for a pre-versioned data dir"
                 );
-                store
-                    .put(
-                        fluxd_storage::Column::Meta,
-                        DB_SCHEMA_VERSION_KEY,
-                        &DB_SCHEMA_VERSION.to_le_bytes(),
-                    )
-                    .map_err(|err| err.to_string())?;
-                return Ok(DB_SCHEMA_VERSION);
-            }
-            store
-                .put(
-                    fluxd_storage::Column::Meta,
-                    DB_SCHEMA_VERSION_KEY,
-                    &DB_SCHEMA_VERSION.to_le_bytes(),
-                )
-                .map_err(|err| err.to_string())?;
-            DB_SCHEMA_VERSION
+            }
+            init_meta_u32(store, DB_SCHEMA_VERSION_KEY, DB_SCHEMA_VERSION)?
         }
     };
 
@@ -3100,11 +3592,21 @@ fn meta_u32(store: &Store, key: &[u8]) -> Result<Option<u32>, String> {
     }
 }
 
-fn set_meta_u32(store: &Store, key: &[u8], value: u32) -> Result<(), String> {
-    let bytes = value.to_le_bytes();
-    store
-        .put(fluxd_storage::Column::Meta, key, &bytes)
-        .map_err(|err| err.to_string())
+/// Writes `value` under `key` if the key is still absent and returns whatever is stored
+/// afterwards, so a concurrent initializer's value wins instead of being overwritten.
+fn init_meta_u32(store: &Store, key: &[u8], value: u32) -> Result<u32, String> {
+    let swapped = store
+        .compare_and_swap(fluxd_storage::Column::Meta, key, None, &value.to_le_bytes())
+        .map_err(|err| err.to_string())?;
+    if swapped {
+        return Ok(value);
+    }
+    meta_u32(store, key)?.ok_or_else(|| {
+        format!(
+            "meta key {} was removed while being initialized",
+            String::from_utf8_lossy(key)
+        )
+    })
 }
 
 fn ensure_index_schema_version(
@@ -3115,15 +3617,8 @@ fn ensure_index_schema_version(
     rebuild_hint: &str,
     columns: &[fluxd_storage::Column],
 ) -> Result<u32, String> {
-    match meta_u32(store, key)? {
-        Some(found) => {
-            if found != expected {
-                return Err(format!(
-                    "{name} schema version mismatch (found {found}, expected {expected}); rebuild with {rebuild_hint}",
-                ));
-            }
-            Ok(found)
-        }
+    let found = match meta_u32(store, key)? {
+        Some(found) => found,
         None => {
             let mut has_data = false;
             for column in columns {
@@ -3143,14 +3638,16 @@ fn ensure_index_schema_version(
                     ));
                 }
                 log_warn!("{name} schema version missing; assuming version 1 for a pre-versioned data dir");
-                set_meta_u32(store, key, expected)?;
-                return Ok(expected);
             }
-
-            set_meta_u32(store, key, expected)?;
-            Ok(expected)
+            init_meta_u32(store, key, expected)?
         }
+    };
+    if found != expected {
+        return Err(format!(
+            "{name} schema version mismatch (found {found}, expected {expected}); rebuild with {rebuild_hint}",
+        ));
     }
+    Ok(found)
 }
 
 fn ensure_secondary_index_versions(store: &Store) -> Result<(), String> {
@@ -3184,6 +3681,31 @@ fn ensure_secondary_index_versions(store: &Store) -> Result<(), String> {
     Ok(())
 }
 
+/// Warns about index rebuilds that stopped before reaching the tip; those indexes stay partial
+/// (and `getindexinfo` reports them unsynced) until the rebuild is rerun.
+fn warn_unfinished_index_rebuilds(store: &Store) -> Result<(), String> {
+    for (name, key, rebuild_hint) in [
+        ("txindex", TXINDEX_REBUILD_HEIGHT_KEY, "--reindex-txindex"),
+        (
+            "spentindex",
+            SPENTINDEX_REBUILD_HEIGHT_KEY,
+            "--reindex-spentindex",
+        ),
+        (
+            "addressindex",
+            ADDRESSINDEX_REBUILD_HEIGHT_KEY,
+            "--reindex-addressindex",
+        ),
+    ] {
+        if let Some(height) = meta_u32(store, key)? {
+            log_warn!(
+                "{name} rebuild stopped at height {height} and the index is incomplete; rerun with {rebuild_hint}"
+            );
+        }
+    }
+    Ok(())
+}
+
 fn load_peers_file(path: &Path) -> Result<Vec<(SocketAddr, AddrBookEntry)>, String> {
     let bytes = match fs::read(path) {
         Ok(bytes) => bytes,
@@ -3287,6 +3809,89 @@ fn save_peers_file(path: &Path, peers: &[(SocketAddr, AddrBookEntry)]) -> Result
     write_file_atomic(path, &json)
 }
 
+fn load_anchors_file(path: &Path) -> Result<Vec<SocketAddr>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.to_string()),
+    };
+    let file: AnchorsFile =
+        serde_json::from_slice(&bytes).map_err(|err| format!("invalid anchors file: {err}"))?;
+    if file.version != ANCHORS_FILE_VERSION {
+        return Err(format!(
+            "unsupported anchors file version {} (expected {ANCHORS_FILE_VERSION})",
+            file.version
+        ));
+    }
+    let mut anchors = Vec::new();
+    for raw in file.anchors {
+        let Ok(addr) = raw.parse::<SocketAddr>() else {
+            continue;
+        };
+        if addr.port() != 0 && !anchors.contains(&addr) && anchors.len() < MAX_ANCHORS {
+            anchors.push(addr);
+        }
+    }
+    Ok(anchors)
+}
+
+fn save_anchors_file(path: &Path, anchors: &[SocketAddr]) -> Result<(), String> {
+    let file = AnchorsFile {
+        version: ANCHORS_FILE_VERSION,
+        anchors: anchors.iter().map(SocketAddr::to_string).collect(),
+    };
+    let json = serde_json::to_vec_pretty(&file).map_err(|err| err.to_string())?;
+    write_file_atomic(path, &json)
+}
+
+/// Picks the longest-connected outbound peers, block-relay-only first, as restart anchors.
+fn select_anchors(peers: &[PeerInfoSnapshot]) -> Vec<SocketAddr> {
+    let rank = |kind: PeerKind| match kind {
+        PeerKind::BlockRelayOnly => 0,
+        PeerKind::Block => 1,
+        PeerKind::Header => 2,
+        PeerKind::Relay => 3,
+    };
+    let mut outbound: Vec<&PeerInfoSnapshot> = peers.iter().filter(|peer| !peer.inbound).collect();
+    outbound.sort_by_key(|peer| (rank(peer.kind), peer.connected_since));
+    let mut anchors = Vec::new();
+    for peer in outbound {
+        if anchors.len() >= MAX_ANCHORS {
+            break;
+        }
+        if !anchors.contains(&peer.addr) {
+            anchors.push(peer.addr);
+        }
+    }
+    anchors
+}
+
+/// Tracks the current anchors while running and writes the last non-empty set on shutdown, since
+/// peers may already be gone by the time the stop signal arrives.
+fn persist_anchors_loop(
+    peer_registry: Arc<PeerRegistry>,
+    path: PathBuf,
+    shutdown: Arc<ShutdownCoordinator>,
+) {
+    let mut anchors = Vec::new();
+    loop {
+        let stopping = shutdown.wait(Duration::from_secs(PEERS_PERSIST_INTERVAL_SECS));
+        let current = select_anchors(&peer_registry.snapshot());
+        if !current.is_empty() {
+            anchors = current;
+        }
+        if stopping {
+            break;
+        }
+    }
+    if anchors.is_empty() {
+        return;
+    }
+    if let Err(err) = save_anchors_file(&path, &anchors) {
+        log_warn!("failed to persist {}: {err}", path.display());
+    }
+}
+
 fn load_mempool_file(path: &Path) -> Result<Vec<Vec<u8>>, String> {
     let bytes = match fs::read(path) {
         Ok(bytes) => bytes,
@@ -3346,36 +3951,42 @@ fn write_file_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
     Ok(())
 }
 
-fn persist_peers_loop(addr_book: Arc<AddrBook>, path: PathBuf) {
+fn persist_peers_loop(addr_book: Arc<AddrBook>, path: PathBuf, shutdown: Arc<ShutdownCoordinator>) {
     let mut last_revision = addr_book.revision().saturating_sub(1);
     loop {
-        thread::sleep(Duration::from_secs(PEERS_PERSIST_INTERVAL_SECS));
+        let stopping = shutdown.wait(Duration::from_secs(PEERS_PERSIST_INTERVAL_SECS));
         let revision = addr_book.revision();
-        if revision == last_revision {
-            continue;
+        if revision != last_revision {
+            let snapshot = addr_book.snapshot();
+            match save_peers_file(&path, &snapshot) {
+                Ok(()) => last_revision = revision,
+                Err(err) => log_warn!("failed to persist {}: {err}", path.display()),
+            }
         }
-        let snapshot = addr_book.snapshot();
-        if let Err(err) = save_peers_file(&path, &snapshot) {
-            log_warn!("failed to persist {}: {err}", path.display());
-            continue;
+        if stopping {
+            break;
         }
-        last_revision = revision;
     }
 }
 
-fn persist_banlist_loop(peer_book: Arc<HeaderPeerBook>, path: PathBuf) {
+fn persist_banlist_loop(
+    peer_book: Arc<HeaderPeerBook>,
+    path: PathBuf,
+    shutdown: Arc<ShutdownCoordinator>,
+) {
     let mut last_revision = peer_book.banlist_revision();
     loop {
-        thread::sleep(Duration::from_secs(BANLIST_PERSIST_INTERVAL_SECS));
+        let stopping = shutdown.wait(Duration::from_secs(BANLIST_PERSIST_INTERVAL_SECS));
         let revision = peer_book.banlist_revision();
-        if revision == last_revision {
-            continue;
+        if revision != last_revision {
+            match peer_book.save_banlist(&path) {
+                Ok(()) => last_revision = revision,
+                Err(err) => log_warn!("failed to persist {}: {err}", path.display()),
+            }
         }
-        if let Err(err) = peer_book.save_banlist(&path) {
-            log_warn!("failed to persist {}: {err}", path.display());
-            continue;
+        if stopping {
+            break;
         }
-        last_revision = revision;
     }
 }
 
@@ -3384,6 +3995,7 @@ fn persist_mempool_loop(
     mempool_metrics: Arc<stats::MempoolMetrics>,
     path: PathBuf,
     interval_secs: u64,
+    shutdown: Arc<ShutdownCoordinator>,
 ) {
     if interval_secs == 0 {
         return;
@@ -3391,36 +4003,91 @@ fn persist_mempool_loop(
     let mut last_revision = mempool.lock().map(|guard| guard.revision()).unwrap_or(0);
 
     loop {
-        thread::sleep(Duration::from_secs(interval_secs));
-        let (revision, mut snapshot) = {
-            let guard = match mempool.lock() {
-                Ok(guard) => guard,
-                Err(_) => {
-                    log_warn!("mempool lock poisoned");
-                    continue;
-                }
-            };
-            let revision = guard.revision();
-            if revision == last_revision {
-                continue;
+        let stopping = shutdown.wait(Duration::from_secs(interval_secs));
+        let pending = match mempool.lock() {
+            Ok(guard) => {
+                let revision = guard.revision();
+                (revision != last_revision).then(|| {
+                    let snapshot: Vec<(Hash256, Vec<u8>)> = guard
+                        .entries()
+                        .map(|entry| (entry.txid, entry.raw.clone()))
+                        .collect();
+                    (revision, snapshot)
+                })
+            }
+            Err(_) => {
+                log_warn!("mempool lock poisoned");
+                None
             }
-            let snapshot: Vec<(Hash256, Vec<u8>)> = guard
-                .entries()
-                .map(|entry| (entry.txid, entry.raw.clone()))
-                .collect();
-            (revision, snapshot)
         };
 
-        snapshot.sort_by(|a, b| a.0.cmp(&b.0));
-        let persisted = match save_mempool_file(&path, &snapshot) {
-            Ok(bytes) => bytes as u64,
-            Err(err) => {
-                log_warn!("failed to persist {}: {err}", path.display());
+        if let Some((revision, mut snapshot)) = pending {
+            snapshot.sort_by(|a, b| a.0.cmp(&b.0));
+            match save_mempool_file(&path, &snapshot) {
+                Ok(bytes) => {
+                    mempool_metrics.note_persisted(bytes as u64);
+                    last_revision = revision;
+                }
+                Err(err) => log_warn!("failed to persist {}: {err}", path.display()),
+            }
+        }
+        if stopping {
+            break;
+        }
+    }
+}
+
+fn mempool_expiry_loop(
+    mempool: Arc<Mutex<mempool::Mempool>>,
+    mempool_metrics: Arc<stats::MempoolMetrics>,
+    fee_estimator: Arc<Mutex<fee_estimator::FeeEstimator>>,
+    max_age_secs: u64,
+    shutdown: Arc<ShutdownCoordinator>,
+) {
+    while !shutdown.wait(Duration::from_secs(MEMPOOL_EXPIRY_SWEEP_SECS)) {
+        let outcome = match mempool.lock() {
+            Ok(mut guard) => guard.expire(unix_now_secs(), max_age_secs),
+            Err(_) => {
+                log_warn!("mempool lock poisoned");
                 continue;
             }
         };
-        mempool_metrics.note_persisted(persisted);
-        last_revision = revision;
+        if outcome.evicted == 0 {
+            continue;
+        }
+        mempool_metrics.note_expired(outcome.evicted);
+        mempool_metrics.note_evicted(outcome.evicted, outcome.evicted_bytes);
+        if let Ok(mut estimator) = fee_estimator.lock() {
+            for txid in &outcome.evicted_txids {
+                estimator.remove_transaction(txid);
+            }
+        }
+        log_info!(
+            "Mempool expiry: evicted {} tx(s) ({} bytes) older than {}h",
+            outcome.evicted,
+            outcome.evicted_bytes,
+            max_age_secs / 3600
+        );
+    }
+}
+
+fn unconnected_prune_loop(
+    chainstate: Arc<ChainState<Store>>,
+    write_lock: Arc<Mutex<()>>,
+    max_age_secs: u64,
+    shutdown: Arc<ShutdownCoordinator>,
+) {
+    while !shutdown.wait(Duration::from_secs(UNCONNECTED_PRUNE_SWEEP_SECS)) {
+        // submitblock parks and connects these entries under the same lock.
+        let Ok(_guard) = write_lock.lock() else {
+            log_warn!("write lock poisoned");
+            continue;
+        };
+        match chainstate.prune_unconnected(max_age_secs, MAX_UNCONNECTED_BLOCKS) {
+            Ok(0) => {}
+            Ok(removed) => log_info!("Pruned {removed} unconnected block(s)"),
+            Err(err) => log_warn!("failed to prune unconnected blocks: {err}"),
+        }
     }
 }
 
@@ -3428,6 +4095,7 @@ fn persist_fee_estimates_loop(
     fee_estimator: Arc<Mutex<fee_estimator::FeeEstimator>>,
     path: PathBuf,
     interval_secs: u64,
+    shutdown: Arc<ShutdownCoordinator>,
 ) {
     if interval_secs == 0 {
         return;
@@ -3438,28 +4106,22 @@ fn persist_fee_estimates_loop(
         .unwrap_or(0);
 
     loop {
-        thread::sleep(Duration::from_secs(interval_secs));
-        let revision = {
-            let guard = match fee_estimator.lock() {
-                Ok(guard) => guard,
-                Err(_) => {
-                    log_warn!("fee estimator lock poisoned");
-                    continue;
-                }
-            };
-            let revision = guard.revision();
-            if revision == last_revision {
-                continue;
-            }
-            match guard.save(&path) {
-                Ok(_) => revision,
-                Err(err) => {
-                    log_warn!("failed to persist {}: {err}", path.display());
-                    continue;
+        let stopping = shutdown.wait(Duration::from_secs(interval_secs));
+        match fee_estimator.lock() {
+            Ok(guard) => {
+                let revision = guard.revision();
+                if revision != last_revision {
+                    match guard.save(&path) {
+                        Ok(_) => last_revision = revision,
+                        Err(err) => log_warn!("failed to persist {}: {err}", path.display()),
+                    }
                 }
             }
-        };
-        last_revision = revision;
+            Err(_) => log_warn!("fee estimator lock poisoned"),
+        }
+        if stopping {
+            break;
+        }
     }
 }
 
@@ -3563,6 +4225,15 @@ async fn connect_to_peers(
     let mut candidates = Vec::new();
     let mut seen = HashSet::new();
 
+    if let Some(addr_book) = addr_book {
+        for addr in addr_book.take_anchors() {
+            if seen.insert(addr) && is_allowed(addr) {
+                log_debug!("Dialling anchor peer {addr}");
+                candidates.push(addr);
+            }
+        }
+    }
+
     if let Some(peer_book) = peer_book {
         let mut preferred = peer_book.preferred(HEADER_PEER_PROBE_COUNT);
         preferred.shuffle(&mut rand::thread_rng());
@@ -3580,7 +4251,7 @@ async fn connect_to_peers(
         }
     }
     addrs.shuffle(&mut rand::thread_rng());
-    for addr in addrs {
+    for addr in spread_by_netgroup(addrs) {
         if seen.insert(addr) && is_allowed(addr) {
             candidates.push(addr);
         }
@@ -3596,6 +4267,10 @@ async fn connect_to_peers(
     let mut behind_peers = 0usize;
     let mut behind_logged = 0usize;
     const MAX_BEHIND_LOGGED: usize = 8;
+    // Block sync needs historical blocks, so peers without NODE_NETWORK are only a fallback.
+    let require_full_chain = peer_ctx.kind == PeerKind::Block;
+    let mut limited = Vec::new();
+    let mut limited_logged = 0usize;
     let mut failures = 0usize;
     let mut failures_logged = 0usize;
     const MAX_CONNECT_ERRORS_LOGGED: usize = 8;
@@ -3627,7 +4302,12 @@ async fn connect_to_peers(
                 let remote_height = peer.remote_height();
                 let remote_version = peer.remote_version();
                 let remote_agent = peer.remote_user_agent().to_string();
-                if min_height > 0 && remote_height >= 0 && remote_height < min_height {
+                let whitelisted = peer_book.is_some_and(|book| book.is_whitelisted(addr));
+                if min_height > 0
+                    && remote_height >= 0
+                    && remote_height < min_height
+                    && !whitelisted
+                {
                     behind_peers = behind_peers.saturating_add(1);
                     if behind_logged < MAX_BEHIND_LOGGED {
                         log_debug!(
@@ -3640,6 +4320,17 @@ async fn connect_to_peers(
                         behind_logged += 1;
                     }
                     behind.push(peer);
+                } else if require_full_chain && !peer.serves_full_chain() {
+                    if limited_logged < MAX_BEHIND_LOGGED {
+                        log_debug!(
+                            "Peer {addr} lacks NODE_NETWORK (services {:#x}), keeping as fallback (ver {} ua {})",
+                            peer.remote_services(),
+                            remote_version,
+                            remote_agent
+                        );
+                        limited_logged += 1;
+                    }
+                    limited.push(peer);
                 } else {
                     log_debug!(
                         "Connected to {addr} (height {} ver {} ua {})",
@@ -3696,6 +4387,19 @@ async fn connect_to_peers(
         );
     }
 
+    if peers.is_empty() && !limited.is_empty() {
+        let fallback = limited
+            .into_iter()
+            .max_by_key(|peer| peer.remote_height())
+            .expect("limited checked to be non-empty");
+        log_warn!(
+            "No peers advertise NODE_NETWORK; using {} (services {:#x}) for block sync",
+            fallback.addr(),
+            fallback.remote_services()
+        );
+        peers.push(fallback);
+    }
+
     if peers.is_empty() && !behind.is_empty() {
         let fallback = behind
             .into_iter()
@@ -3757,6 +4461,7 @@ async fn resolve_seed_addresses(params: &ChainParams) -> Result<Vec<SocketAddr>,
     let mut addrs = Vec::new();
     let mut seen = HashSet::new();
     for seed in params.fixed_seeds {
+        let seed = seed.addr;
         if let Ok(addr) = seed.parse::<SocketAddr>() {
             if seen.insert(addr) {
                 addrs.push(addr);
@@ -3883,8 +4588,11 @@ async fn discover_addrs_from_peer(
         match message {
             Ok(Ok((command, payload))) => match command.as_str() {
                 "addr" => match parse_addr(&payload) {
-                    Ok(addrs) => new_addrs
-                        .extend(addrs.into_iter().filter(|addr| addr.port() == default_port)),
+                    Ok(addrs) => new_addrs.extend(
+                        filter_gossiped_addrs(addrs)
+                            .into_iter()
+                            .filter(|addr| addr.port() == default_port),
+                    ),
                     Err(err) => return Err(err),
                 },
                 "ping" => {
@@ -3937,6 +4645,9 @@ fn parse_peer_addrs(values: &[String], default_port: u16) -> Result<Vec<SocketAd
 fn validation_flags(
     shielded_params: Arc<ShieldedParams>,
     check_script: bool,
+    check_canonical_encoding: bool,
+    assume_valid: Option<Hash256>,
+    script_pool: Option<Arc<rayon::ThreadPool>>,
     metrics: Option<Arc<ValidationMetrics>>,
 ) -> ValidationFlags {
     ValidationFlags {
@@ -3944,8 +4655,11 @@ fn validation_flags(
         check_pon: true,
         check_script,
         check_shielded: true,
+        assume_valid,
         shielded_params: Some(shielded_params),
+        script_pool,
         metrics,
+        check_canonical_encoding,
     }
 }
 
@@ -4017,46 +4731,8 @@ fn reindex_blocks_from_flatfiles<S: KeyValueStore>(
         }
         let mut file = File::open(&path).map_err(|err| err.to_string())?;
         let mut offset: u64 = 0;
-        loop {
-            let mut len_bytes = [0u8; 4];
-            match file.read_exact(&mut len_bytes) {
-                Ok(()) => {}
-                Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
-                Err(err) => {
-                    return Err(format!(
-                        "flatfile read failed for {} at offset {}: {err}",
-                        path.display(),
-                        offset
-                    ));
-                }
-            }
-            let len = u32::from_le_bytes(len_bytes);
-            if len == 0 {
-                return Err(format!(
-                    "flatfile record has invalid length 0 ({} offset {})",
-                    path.display(),
-                    offset
-                ));
-            }
-            if len > fluxd_consensus::constants::MAX_BLOCK_SIZE {
-                return Err(format!(
-                    "flatfile record length {} exceeds MAX_BLOCK_SIZE ({} offset {})",
-                    len,
-                    path.display(),
-                    offset
-                ));
-            }
-
-            let mut bytes = vec![0u8; len as usize];
-            file.read_exact(&mut bytes).map_err(|err| {
-                format!(
-                    "flatfile read failed for {} payload (len {}) at offset {}: {err}",
-                    path.display(),
-                    len,
-                    offset
-                )
-            })?;
-
+        while let Some(bytes) = read_flatfile_record(&mut file, &path, offset)? {
+            let len = bytes.len() as u32;
             let location = FileLocation {
                 file_id,
                 offset,
@@ -4122,25 +4798,16 @@ fn reindex_blocks_from_flatfiles<S: KeyValueStore>(
                 ));
             }
 
-            let batch = chainstate
-                .connect_block(
-                    &block,
-                    height,
-                    params,
-                    flags,
-                    false,
-                    None,
-                    None,
-                    Some(bytes.as_slice()),
-                    Some(location),
-                )
-                .map_err(|err| err.to_string())?;
-            let _guard = write_lock
-                .lock()
-                .map_err(|_| "write lock poisoned".to_string())?;
-            chainstate
-                .commit_batch(batch)
-                .map_err(|err| err.to_string())?;
+            connect_and_commit_block(
+                chainstate,
+                &block,
+                height,
+                &bytes,
+                Some(location),
+                params,
+                flags,
+                write_lock,
+            )?;
 
             connected_blocks = connected_blocks.saturating_add(1);
             if connected_blocks > 0 && connected_blocks % 100_000 == 0 {
@@ -4172,22 +4839,400 @@ fn reindex_blocks_from_flatfiles<S: KeyValueStore>(
     Ok(())
 }
 
+/// Reads one length-prefixed record, returning `None` at a clean end of file.
+fn read_flatfile_record(
+    file: &mut File,
+    path: &Path,
+    offset: u64,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut len_bytes = [0u8; 4];
+    match file.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => {
+            return Err(format!(
+                "flatfile read failed for {} at offset {}: {err}",
+                path.display(),
+                offset
+            ));
+        }
+    }
+    let len = u32::from_le_bytes(len_bytes);
+    if len == 0 {
+        return Err(format!(
+            "flatfile record has invalid length 0 ({} offset {})",
+            path.display(),
+            offset
+        ));
+    }
+    if len > fluxd_consensus::constants::MAX_BLOCK_SIZE {
+        return Err(format!(
+            "flatfile record length {} exceeds MAX_BLOCK_SIZE ({} offset {})",
+            len,
+            path.display(),
+            offset
+        ));
+    }
+
+    let mut bytes = vec![0u8; len as usize];
+    file.read_exact(&mut bytes).map_err(|err| {
+        format!(
+            "flatfile read failed for {} payload (len {}) at offset {}: {err}",
+            path.display(),
+            len,
+            offset
+        )
+    })?;
+    Ok(Some(bytes))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn connect_and_commit_block<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    block: &Block,
+    height: i32,
+    bytes: &[u8],
+    location: Option<FileLocation>,
+    params: &ChainParams,
+    flags: &ValidationFlags,
+    write_lock: &Mutex<()>,
+) -> Result<(), String> {
+    let batch = chainstate
+        .connect_block(
+            block,
+            height,
+            params,
+            flags,
+            false,
+            None,
+            None,
+            Some(bytes),
+            location,
+        )
+        .map_err(|err| err.to_string())?;
+    let _guard = write_lock
+        .lock()
+        .map_err(|_| "write lock poisoned".to_string())?;
+    chainstate
+        .commit_batch(batch)
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+fn dump_blocks<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    range: &DumpRange,
+) -> Result<(), String> {
+    dump_range(chainstate, range, "blocks", |hash| {
+        let location = chainstate
+            .block_location(hash)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("missing block data for {}", hash256_to_hex(hash)))?;
+        chainstate
+            .read_block(location)
+            .map_err(|err| err.to_string())
+    })
+}
+
+fn dump_headers<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    range: &DumpRange,
+) -> Result<(), String> {
+    dump_range(chainstate, range, "headers", |hash| {
+        chainstate
+            .block_header_bytes(hash)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("missing header for {}", hash256_to_hex(hash)))
+    })
+}
+
+fn dump_range<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    range: &DumpRange,
+    label: &str,
+    mut record: impl FnMut(&Hash256) -> Result<Vec<u8>, String>,
+) -> Result<(), String> {
+    let tip_height = chainstate
+        .best_block()
+        .map_err(|err| err.to_string())?
+        .map(|tip| tip.height)
+        .unwrap_or(-1);
+    if range.end > tip_height {
+        return Err(format!(
+            "dump end height {} is above the best block height {}",
+            range.end, tip_height
+        ));
+    }
+
+    let file = File::create(&range.path)
+        .map_err(|err| format!("failed to create {}: {err}", range.path.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let mut bytes_written: u64 = 0;
+    for height in range.start..=range.end {
+        let hash = chainstate
+            .height_hash(height)
+            .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
+            .ok_or_else(|| format!("missing main-chain hash at height {height}"))?;
+        let payload = record(&hash)?;
+        let len = u32::try_from(payload.len())
+            .map_err(|_| format!("record at height {height} is too large"))?;
+        writer
+            .write_all(&len.to_le_bytes())
+            .and_then(|()| writer.write_all(&payload))
+            .map_err(|err| format!("failed to write {}: {err}", range.path.display()))?;
+        bytes_written = bytes_written.saturating_add(4 + u64::from(len));
+    }
+    writer
+        .flush()
+        .map_err(|err| format!("failed to write {}: {err}", range.path.display()))?;
+
+    log_info!(
+        "Dumped {} {} (heights {}..={}, {} bytes) to {}",
+        range.end - range.start + 1,
+        label,
+        range.start,
+        range.end,
+        bytes_written,
+        range.path.display()
+    );
+    Ok(())
+}
+
+/// Connects blocks from a `--dump-blocks` export on top of the current tip.
+///
+/// Blocks already on the main chain are skipped, so an export that overlaps the local
+/// chain can be imported as-is. Block bytes are appended to the local flatfiles.
+fn import_blocks<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    path: &Path,
+    params: &ChainParams,
+    flags: &ValidationFlags,
+    write_lock: &Mutex<()>,
+) -> Result<(), String> {
+    log_info!("Importing blocks from {}", path.display());
+
+    let mut file =
+        File::open(path).map_err(|err| format!("failed to open {}: {err}", path.display()))?;
+    let mut offset: u64 = 0;
+    let mut connected_blocks: u64 = 0;
+    let mut skipped_blocks: u64 = 0;
+    while let Some(bytes) = read_flatfile_record(&mut file, path, offset)? {
+        let record_offset = offset;
+        offset = offset.saturating_add(4 + bytes.len() as u64);
+
+        let block = Block::consensus_decode(&bytes).map_err(|_| {
+            format!(
+                "invalid block encoding in {} at offset {}",
+                path.display(),
+                record_offset
+            )
+        })?;
+        let hash = block.header.hash();
+
+        if let Some(entry) = chainstate
+            .header_entry(&hash)
+            .map_err(|err| err.to_string())?
+        {
+            let on_main_chain = chainstate
+                .height_hash(entry.height)
+                .map_err(|err| err.to_string())?
+                == Some(BlockHash::from_internal(hash));
+            if on_main_chain {
+                skipped_blocks = skipped_blocks.saturating_add(1);
+                continue;
+            }
+        }
+
+        let (tip_hash, height) = match chainstate.best_block().map_err(|err| err.to_string())? {
+            Some(tip) => (tip.hash, tip.height.saturating_add(1)),
+            None => ([0u8; 32], 0),
+        };
+        if block.header.prev_block != tip_hash {
+            return Err(format!(
+                "block {} in {} at offset {} does not extend the current tip {}",
+                hash256_to_hex(&hash),
+                path.display(),
+                record_offset,
+                hash256_to_hex(&tip_hash)
+            ));
+        }
+        if height == 0 && hash != params.consensus.hash_genesis_block {
+            return Err(format!(
+                "genesis hash mismatch in {} at offset {}: got {} expected {}",
+                path.display(),
+                record_offset,
+                hash256_to_hex(&hash),
+                hash256_to_hex(&params.consensus.hash_genesis_block),
+            ));
+        }
+
+        connect_and_commit_block(
+            chainstate, &block, height, &bytes, None, params, flags, write_lock,
+        )?;
+        connected_blocks = connected_blocks.saturating_add(1);
+    }
+
+    let tip = chainstate
+        .best_block()
+        .map_err(|err| err.to_string())?
+        .map(|tip| tip.height)
+        .unwrap_or(-1);
+    log_info!(
+        "Import complete: connected {} blocks, skipped {} already on the main chain (tip {})",
+        connected_blocks,
+        skipped_blocks,
+        tip
+    );
+    Ok(())
+}
+
+fn parse_dump_range(
+    flag: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<DumpRange, String> {
+    let mut next = |what: &str| {
+        args.next()
+            .ok_or_else(|| format!("missing {what} for {flag}\n{}", usage()))
+    };
+    let start = next("start height")?;
+    let end = next("end height")?;
+    let path = next("path")?;
+    let start = start
+        .parse::<i32>()
+        .map_err(|_| format!("invalid start height for {flag}\n{}", usage()))?;
+    let end = end
+        .parse::<i32>()
+        .map_err(|_| format!("invalid end height for {flag}\n{}", usage()))?;
+    if start < 0 || end < start {
+        return Err(format!(
+            "invalid height range {start}..={end} for {flag}\n{}",
+            usage()
+        ));
+    }
+    Ok(DumpRange {
+        start,
+        end,
+        path: PathBuf::from(path),
+    })
+}
+
+/// Secondary indexes that `rebuild_all_indexes` should regenerate.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct IndexRebuildTargets {
+    txindex: bool,
+    spentindex: bool,
+    addressindex: bool,
+}
+
+impl IndexRebuildTargets {
+    fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::with_capacity(3);
+        if self.txindex {
+            names.push("tx index");
+        }
+        if self.spentindex {
+            names.push("spent index");
+        }
+        if self.addressindex {
+            names.push("address index");
+        }
+        names
+    }
+
+    fn needs_prevouts(&self) -> bool {
+        self.spentindex || self.addressindex
+    }
+
+    fn rebuild_height_keys(&self) -> Vec<&'static [u8]> {
+        let mut keys = Vec::with_capacity(3);
+        if self.txindex {
+            keys.push(TXINDEX_REBUILD_HEIGHT_KEY);
+        }
+        if self.spentindex {
+            keys.push(SPENTINDEX_REBUILD_HEIGHT_KEY);
+        }
+        if self.addressindex {
+            keys.push(ADDRESSINDEX_REBUILD_HEIGHT_KEY);
+        }
+        keys
+    }
+}
+
 fn rebuild_txindex<S: KeyValueStore>(
     chainstate: &ChainState<S>,
     write_lock: &Mutex<()>,
 ) -> Result<(), String> {
+    rebuild_all_indexes(
+        chainstate,
+        write_lock,
+        IndexRebuildTargets {
+            txindex: true,
+            ..IndexRebuildTargets::default()
+        },
+    )
+}
+
+fn rebuild_spentindex<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+) -> Result<(), String> {
+    rebuild_all_indexes(
+        chainstate,
+        write_lock,
+        IndexRebuildTargets {
+            spentindex: true,
+            ..IndexRebuildTargets::default()
+        },
+    )
+}
+
+fn rebuild_addressindex<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+) -> Result<(), String> {
+    rebuild_all_indexes(
+        chainstate,
+        write_lock,
+        IndexRebuildTargets {
+            addressindex: true,
+            ..IndexRebuildTargets::default()
+        },
+    )
+}
+
+/// Rebuilds the requested indexes in one pass over the main chain, decoding each block once and
+/// sharing the prevout cache between the spent and address index builders.
+fn rebuild_all_indexes<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    targets: IndexRebuildTargets,
+) -> Result<(), String> {
+    const TX_CACHE_CAPACITY: usize = 50_000;
+
+    let label = targets.names().join(", ");
+    if label.is_empty() {
+        return Ok(());
+    }
     let Some(best) = chainstate.best_block().map_err(|err| err.to_string())? else {
-        log_info!("Tx index rebuild requested but no blocks are present");
+        log_info!("Rebuild of {label} requested but no blocks are present");
         return Ok(());
     };
 
-    log_info!("Rebuilding tx index up to height {}", best.height);
+    log_info!("Rebuilding {label} up to height {}", best.height);
+    let rebuild_height_keys = targets.rebuild_height_keys();
     let mut last_progress = Instant::now();
+    let mut tx_cache = TxOutCache::new(if targets.needs_prevouts() {
+        TX_CACHE_CAPACITY
+    } else {
+        0
+    });
 
     for height in 0..=best.height {
         let hash = chainstate
             .height_hash(height)
             .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| format!("missing height index for height {height}"))?;
         let block_location = chainstate
             .block_location(&hash)
@@ -4200,14 +5245,21 @@ fn rebuild_txindex<S: KeyValueStore>(
             Block::consensus_decode(&bytes).map_err(|_| "invalid block encoding".to_string())?;
 
         let mut batch = WriteBatch::new();
-        batch.reserve(block.transactions.len());
-        for (tx_index, tx) in block.transactions.iter().enumerate() {
-            let txid = tx.txid().map_err(|err| err.to_string())?;
-            let location = fluxd_chainstate::txindex::TxLocation {
-                block: block_location,
-                index: tx_index as u32,
-            };
-            batch.put(fluxd_storage::Column::TxIndex, txid, location.encode());
+        stage_block_index_entries(
+            chainstate,
+            &mut tx_cache,
+            targets,
+            height,
+            block_location,
+            &block,
+            &mut batch,
+        )?;
+        for key in &rebuild_height_keys {
+            batch.put(
+                fluxd_storage::Column::Meta,
+                *key,
+                (height as u32).to_le_bytes(),
+            );
         }
 
         let _guard = write_lock
@@ -4219,7 +5271,7 @@ fn rebuild_txindex<S: KeyValueStore>(
 
         if height > 0 && height % 100_000 == 0 {
             log_info!(
-                "Rebuilt tx index at height {} (elapsed {:?})",
+                "Rebuilt {label} at height {} (elapsed {:?})",
                 height,
                 last_progress.elapsed()
             );
@@ -4228,95 +5280,134 @@ fn rebuild_txindex<S: KeyValueStore>(
     }
 
     let mut version_batch = WriteBatch::new();
-    version_batch.put(
-        fluxd_storage::Column::Meta,
-        TXINDEX_VERSION_KEY,
-        TXINDEX_VERSION.to_le_bytes(),
-    );
-    let _guard = write_lock
-        .lock()
-        .map_err(|_| "write lock poisoned".to_string())?;
-    chainstate
-        .commit_batch(version_batch)
+    if targets.txindex {
+        version_batch.put(
+            fluxd_storage::Column::Meta,
+            TXINDEX_VERSION_KEY,
+            TXINDEX_VERSION.to_le_bytes(),
+        );
+    }
+    if targets.spentindex {
+        version_batch.put(
+            fluxd_storage::Column::Meta,
+            SPENTINDEX_VERSION_KEY,
+            SPENTINDEX_VERSION.to_le_bytes(),
+        );
+    }
+    if targets.addressindex {
+        version_batch.put(
+            fluxd_storage::Column::Meta,
+            ADDRESSINDEX_VERSION_KEY,
+            ADDRESSINDEX_VERSION.to_le_bytes(),
+        );
+    }
+    for key in &rebuild_height_keys {
+        version_batch.delete(fluxd_storage::Column::Meta, *key);
+    }
+    let _guard = write_lock
+        .lock()
+        .map_err(|_| "write lock poisoned".to_string())?;
+    chainstate
+        .commit_batch(version_batch)
         .map_err(|err| err.to_string())?;
 
-    log_info!("Tx index rebuild complete at height {}", best.height);
+    log_info!("Rebuild of {label} complete at height {}", best.height);
     Ok(())
 }
 
-fn rebuild_spentindex<S: KeyValueStore>(
+fn stage_block_index_entries<S: KeyValueStore>(
     chainstate: &ChainState<S>,
-    write_lock: &Mutex<()>,
+    tx_cache: &mut TxOutCache,
+    targets: IndexRebuildTargets,
+    height: i32,
+    block_location: FileLocation,
+    block: &Block,
+    batch: &mut WriteBatch,
 ) -> Result<(), String> {
-    const TX_CACHE_CAPACITY: usize = 50_000;
-
-    let Some(best) = chainstate.best_block().map_err(|err| err.to_string())? else {
-        log_info!("Spent index rebuild requested but no blocks are present");
-        return Ok(());
-    };
+    #[derive(Clone, Debug)]
+    struct CreatedOutput {
+        outpoint: OutPoint,
+        out: CachedTxOut,
+    }
 
-    log_info!("Rebuilding spent index up to height {}", best.height);
-    let mut last_progress = Instant::now();
-    let mut tx_cache = TxOutCache::new(TX_CACHE_CAPACITY);
+    let estimated_inputs = block
+        .transactions
+        .iter()
+        .skip(1)
+        .map(|tx| tx.vin.len())
+        .sum::<usize>();
+    let estimated_outputs = block
+        .transactions
+        .iter()
+        .map(|tx| tx.vout.len())
+        .sum::<usize>();
+    let mut reserve = 0usize;
+    if targets.txindex {
+        reserve = reserve.saturating_add(block.transactions.len());
+    }
+    if targets.spentindex {
+        reserve = reserve.saturating_add(estimated_inputs);
+    }
+    if targets.addressindex {
+        reserve = reserve
+            .saturating_add(estimated_inputs.saturating_mul(2))
+            .saturating_add(estimated_outputs.saturating_mul(2));
+    }
+    batch.reserve(reserve);
 
-    for height in 0..=best.height {
-        let hash = chainstate
-            .height_hash(height)
-            .map_err(|err| err.to_string())?
-            .ok_or_else(|| format!("missing height index for height {height}"))?;
-        let block_location = chainstate
-            .block_location(&hash)
-            .map_err(|err| err.to_string())?
-            .ok_or_else(|| format!("missing block index entry for height {height}"))?;
-        let bytes = chainstate
-            .read_block(block_location)
-            .map_err(|err| err.to_string())?;
-        let block =
-            Block::consensus_decode(&bytes).map_err(|_| "invalid block encoding".to_string())?;
+    let mut created: HashMap<fluxd_chainstate::utxo::OutPointKey, CreatedOutput> =
+        HashMap::with_capacity(if targets.needs_prevouts() {
+            estimated_outputs
+        } else {
+            0
+        });
+    let mut spent_outpoints: HashSet<fluxd_chainstate::utxo::OutPointKey> =
+        HashSet::with_capacity(if targets.needs_prevouts() {
+            estimated_inputs
+        } else {
+            0
+        });
 
-        let mut batch = WriteBatch::new();
-        let estimated_inputs = block
-            .transactions
-            .iter()
-            .skip(1)
-            .map(|tx| tx.vin.len())
-            .sum::<usize>();
-        let estimated_outputs = block
-            .transactions
-            .iter()
-            .map(|tx| tx.vout.len())
-            .sum::<usize>();
-        batch.reserve(estimated_inputs.saturating_add(4));
+    for (tx_index, tx) in block.transactions.iter().enumerate() {
+        let txid = tx.txid().map_err(|err| err.to_string())?;
 
-        let mut created: HashMap<fluxd_chainstate::utxo::OutPointKey, CachedTxOut> =
-            HashMap::with_capacity(estimated_outputs);
-        let mut spent_outpoints: HashSet<fluxd_chainstate::utxo::OutPointKey> =
-            HashSet::with_capacity(estimated_inputs);
+        if targets.txindex {
+            let location = fluxd_chainstate::txindex::TxLocation {
+                block: block_location,
+                index: tx_index as u32,
+            };
+            batch.put(fluxd_storage::Column::TxIndex, txid, location.encode());
+        }
+        if !targets.needs_prevouts() {
+            continue;
+        }
 
-        for (tx_index, tx) in block.transactions.iter().enumerate() {
-            let txid = tx.txid().map_err(|err| err.to_string())?;
+        if tx_index != 0 {
+            for (input_index, input) in tx.vin.iter().enumerate() {
+                let outpoint_key = fluxd_chainstate::utxo::outpoint_key_bytes(&input.prevout);
+                if !spent_outpoints.insert(outpoint_key) {
+                    return Err(format!(
+                        "duplicate prevout {}:{} at height {} (tx {})",
+                        hash256_to_hex(&input.prevout.hash),
+                        input.prevout.index,
+                        height,
+                        hash256_to_hex(&txid)
+                    ));
+                }
 
-            if tx_index != 0 {
-                for (input_index, input) in tx.vin.iter().enumerate() {
-                    let outpoint_key = fluxd_chainstate::utxo::outpoint_key_bytes(&input.prevout);
-                    if !spent_outpoints.insert(outpoint_key) {
-                        return Err(format!(
-                            "duplicate prevout {}:{} at height {} (tx {})",
-                            hash256_to_hex(&input.prevout.hash),
-                            input.prevout.index,
-                            height,
-                            hash256_to_hex(&txid)
-                        ));
-                    }
+                let (prev, was_created_in_block) = match created.remove(&outpoint_key) {
+                    Some(created) => (created.out, true),
+                    None => (
+                        resolve_prevout_txout(chainstate, tx_cache, &input.prevout)?,
+                        false,
+                    ),
+                };
 
-                    let prevout = match created.remove(&outpoint_key) {
-                        Some(prevout) => prevout,
-                        None => resolve_prevout_txout(chainstate, &mut tx_cache, &input.prevout)?,
-                    };
+                if targets.spentindex {
                     let details = fluxd_chainstate::spentindex::SpentIndexDetails {
-                        satoshis: prevout.value,
-                        address_type: prevout.address_type,
-                        address_hash: prevout.address_hash,
+                        satoshis: prev.value,
+                        address_type: prev.address_type,
+                        address_hash: prev.address_hash,
                     };
                     let value = fluxd_chainstate::spentindex::SpentIndexValue {
                         txid,
@@ -4330,62 +5421,97 @@ fn rebuild_spentindex<S: KeyValueStore>(
                         value.encode(),
                     );
                 }
-            }
 
-            let mut outputs = Vec::with_capacity(tx.vout.len());
-            for (out_index, output) in tx.vout.iter().enumerate() {
-                let script_hash =
-                    fluxd_chainstate::address_index::script_hash(&output.script_pubkey);
-                let (address_type, address_hash) = spent_address_info(&output.script_pubkey);
-                let out = CachedTxOut {
-                    value: output.value,
-                    script_hash,
-                    address_type,
-                    address_hash,
+                if !targets.addressindex {
+                    continue;
+                }
+                let Some(script_hash) = prev.script_hash else {
+                    continue;
                 };
-                outputs.push(out);
+                if !was_created_in_block {
+                    let key =
+                        fluxd_chainstate::address_index::address_outpoint_key_with_script_hash(
+                            &script_hash,
+                            &input.prevout,
+                        );
+                    batch.delete(fluxd_storage::Column::AddressOutpoint, key);
+                }
+                let satoshis = prev
+                    .value
+                    .checked_neg()
+                    .ok_or_else(|| "prevout value out of range".to_string())?;
+                let delta_key = address_delta_key(
+                    &script_hash,
+                    height as u32,
+                    tx_index as u32,
+                    &txid,
+                    input_index as u32,
+                    true,
+                );
+                batch.put(
+                    fluxd_storage::Column::AddressDelta,
+                    delta_key,
+                    satoshis.to_le_bytes(),
+                );
+            }
+        }
 
-                let outpoint = OutPoint {
-                    hash: txid,
-                    index: out_index as u32,
-                };
-                created.insert(fluxd_chainstate::utxo::outpoint_key_bytes(&outpoint), out);
+        let mut outputs = Vec::with_capacity(tx.vout.len());
+        for (out_index, output) in tx.vout.iter().enumerate() {
+            let script_hash = fluxd_chainstate::address_index::script_hash(&output.script_pubkey);
+            let (address_type, address_hash) = spent_address_info(&output.script_pubkey);
+            let out = CachedTxOut {
+                value: output.value,
+                script_hash,
+                address_type,
+                address_hash,
+            };
+            outputs.push(out);
+
+            if targets.addressindex {
+                if let Some(script_hash) = script_hash {
+                    let delta_key = address_delta_key(
+                        &script_hash,
+                        height as u32,
+                        tx_index as u32,
+                        &txid,
+                        out_index as u32,
+                        false,
+                    );
+                    batch.put(
+                        fluxd_storage::Column::AddressDelta,
+                        delta_key,
+                        output.value.to_le_bytes(),
+                    );
+                }
             }
 
-            tx_cache.insert(txid, outputs);
+            let outpoint = OutPoint {
+                hash: txid,
+                index: out_index as u32,
+            };
+            created.insert(
+                fluxd_chainstate::utxo::outpoint_key_bytes(&outpoint),
+                CreatedOutput { outpoint, out },
+            );
         }
 
-        let _guard = write_lock
-            .lock()
-            .map_err(|_| "write lock poisoned".to_string())?;
-        chainstate
-            .commit_batch(batch)
-            .map_err(|err| err.to_string())?;
+        tx_cache.insert(txid, outputs);
+    }
 
-        if height > 0 && height % 100_000 == 0 {
-            log_info!(
-                "Rebuilt spent index at height {} (elapsed {:?})",
-                height,
-                last_progress.elapsed()
+    if targets.addressindex {
+        for created_output in created.values() {
+            let Some(script_hash) = created_output.out.script_hash else {
+                continue;
+            };
+            let key = fluxd_chainstate::address_index::address_outpoint_key_with_script_hash(
+                &script_hash,
+                &created_output.outpoint,
             );
-            last_progress = Instant::now();
+            batch.put(fluxd_storage::Column::AddressOutpoint, key, []);
         }
     }
 
-    let mut version_batch = WriteBatch::new();
-    version_batch.put(
-        fluxd_storage::Column::Meta,
-        SPENTINDEX_VERSION_KEY,
-        SPENTINDEX_VERSION.to_le_bytes(),
-    );
-    let _guard = write_lock
-        .lock()
-        .map_err(|_| "write lock poisoned".to_string())?;
-    chainstate
-        .commit_batch(version_batch)
-        .map_err(|err| err.to_string())?;
-
-    log_info!("Spent index rebuild complete at height {}", best.height);
     Ok(())
 }
 
@@ -4478,210 +5604,6 @@ fn address_delta_key(
     key
 }
 
-fn rebuild_addressindex<S: KeyValueStore>(
-    chainstate: &ChainState<S>,
-    write_lock: &Mutex<()>,
-) -> Result<(), String> {
-    const TX_CACHE_CAPACITY: usize = 50_000;
-
-    let Some(best) = chainstate.best_block().map_err(|err| err.to_string())? else {
-        log_info!("Address index rebuild requested but no blocks are present");
-        return Ok(());
-    };
-
-    log_info!("Rebuilding address indexes up to height {}", best.height);
-    let mut last_progress = Instant::now();
-    let mut tx_cache = TxOutCache::new(TX_CACHE_CAPACITY);
-
-    #[derive(Clone, Debug)]
-    struct CreatedOutput {
-        outpoint: OutPoint,
-        out: CachedTxOut,
-    }
-
-    for height in 0..=best.height {
-        let hash = chainstate
-            .height_hash(height)
-            .map_err(|err| err.to_string())?
-            .ok_or_else(|| format!("missing height index for height {height}"))?;
-        let block_location = chainstate
-            .block_location(&hash)
-            .map_err(|err| err.to_string())?
-            .ok_or_else(|| format!("missing block index entry for height {height}"))?;
-        let bytes = chainstate
-            .read_block(block_location)
-            .map_err(|err| err.to_string())?;
-        let block =
-            Block::consensus_decode(&bytes).map_err(|_| "invalid block encoding".to_string())?;
-
-        let estimated_inputs = block
-            .transactions
-            .iter()
-            .skip(1)
-            .map(|tx| tx.vin.len())
-            .sum::<usize>();
-        let estimated_outputs = block
-            .transactions
-            .iter()
-            .map(|tx| tx.vout.len())
-            .sum::<usize>();
-
-        let mut batch = WriteBatch::new();
-        batch.reserve(
-            estimated_inputs
-                .saturating_mul(2)
-                .saturating_add(estimated_outputs.saturating_mul(2))
-                .saturating_add(block.transactions.len()),
-        );
-
-        let mut created: HashMap<fluxd_chainstate::utxo::OutPointKey, CreatedOutput> =
-            HashMap::with_capacity(estimated_outputs);
-        let mut spent_outpoints: HashSet<fluxd_chainstate::utxo::OutPointKey> =
-            HashSet::with_capacity(estimated_inputs);
-
-        for (tx_index, tx) in block.transactions.iter().enumerate() {
-            let txid = tx.txid().map_err(|err| err.to_string())?;
-
-            if tx_index != 0 {
-                for (input_index, input) in tx.vin.iter().enumerate() {
-                    let outpoint_key = fluxd_chainstate::utxo::outpoint_key_bytes(&input.prevout);
-                    if !spent_outpoints.insert(outpoint_key) {
-                        return Err(format!(
-                            "duplicate prevout {}:{} at height {} (tx {})",
-                            hash256_to_hex(&input.prevout.hash),
-                            input.prevout.index,
-                            height,
-                            hash256_to_hex(&txid)
-                        ));
-                    }
-
-                    let created_in_block = created.remove(&outpoint_key);
-                    let (prev, was_created_in_block) = match created_in_block {
-                        Some(created) => (created.out, true),
-                        None => (
-                            resolve_prevout_txout(chainstate, &mut tx_cache, &input.prevout)?,
-                            false,
-                        ),
-                    };
-
-                    let Some(script_hash) = prev.script_hash else {
-                        continue;
-                    };
-                    if !was_created_in_block {
-                        let key =
-                            fluxd_chainstate::address_index::address_outpoint_key_with_script_hash(
-                                &script_hash,
-                                &input.prevout,
-                            );
-                        batch.delete(fluxd_storage::Column::AddressOutpoint, key);
-                    }
-                    let satoshis = prev
-                        .value
-                        .checked_neg()
-                        .ok_or_else(|| "prevout value out of range".to_string())?;
-                    let delta_key = address_delta_key(
-                        &script_hash,
-                        height as u32,
-                        tx_index as u32,
-                        &txid,
-                        input_index as u32,
-                        true,
-                    );
-                    batch.put(
-                        fluxd_storage::Column::AddressDelta,
-                        delta_key,
-                        satoshis.to_le_bytes(),
-                    );
-                }
-            }
-
-            let mut outputs = Vec::with_capacity(tx.vout.len());
-            for (out_index, output) in tx.vout.iter().enumerate() {
-                let script_hash =
-                    fluxd_chainstate::address_index::script_hash(&output.script_pubkey);
-                let (address_type, address_hash) = spent_address_info(&output.script_pubkey);
-                let out = CachedTxOut {
-                    value: output.value,
-                    script_hash,
-                    address_type,
-                    address_hash,
-                };
-                outputs.push(out);
-
-                if let Some(script_hash) = script_hash {
-                    let delta_key = address_delta_key(
-                        &script_hash,
-                        height as u32,
-                        tx_index as u32,
-                        &txid,
-                        out_index as u32,
-                        false,
-                    );
-                    batch.put(
-                        fluxd_storage::Column::AddressDelta,
-                        delta_key,
-                        output.value.to_le_bytes(),
-                    );
-                }
-
-                let outpoint = OutPoint {
-                    hash: txid,
-                    index: out_index as u32,
-                };
-                created.insert(
-                    fluxd_chainstate::utxo::outpoint_key_bytes(&outpoint),
-                    CreatedOutput { outpoint, out },
-                );
-            }
-
-            tx_cache.insert(txid, outputs);
-        }
-
-        for created_output in created.values() {
-            let Some(script_hash) = created_output.out.script_hash else {
-                continue;
-            };
-            let key = fluxd_chainstate::address_index::address_outpoint_key_with_script_hash(
-                &script_hash,
-                &created_output.outpoint,
-            );
-            batch.put(fluxd_storage::Column::AddressOutpoint, key, []);
-        }
-
-        let _guard = write_lock
-            .lock()
-            .map_err(|_| "write lock poisoned".to_string())?;
-        chainstate
-            .commit_batch(batch)
-            .map_err(|err| err.to_string())?;
-
-        if height > 0 && height % 100_000 == 0 {
-            log_info!(
-                "Rebuilt address index at height {} (elapsed {:?})",
-                height,
-                last_progress.elapsed()
-            );
-            last_progress = Instant::now();
-        }
-    }
-
-    let mut version_batch = WriteBatch::new();
-    version_batch.put(
-        fluxd_storage::Column::Meta,
-        ADDRESSINDEX_VERSION_KEY,
-        ADDRESSINDEX_VERSION.to_le_bytes(),
-    );
-    let _guard = write_lock
-        .lock()
-        .map_err(|_| "write lock poisoned".to_string())?;
-    chainstate
-        .commit_batch(version_batch)
-        .map_err(|err| err.to_string())?;
-
-    log_info!("Address index rebuild complete at height {}", best.height);
-    Ok(())
-}
-
 fn rebuild_addresstxindex<S: KeyValueStore>(
     chainstate: &ChainState<S>,
     write_lock: &Mutex<()>,
@@ -4701,6 +5623,7 @@ fn rebuild_addresstxindex<S: KeyValueStore>(
         let hash = chainstate
             .height_hash(height)
             .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| format!("missing height index for height {height}"))?;
         let block_location = chainstate
             .block_location(&hash)
@@ -4859,7 +5782,7 @@ fn resolve_prevout_txout<S: KeyValueStore>(
     }
 
     let location = chainstate
-        .tx_location(&prevout.hash)
+        .tx_location(&prevout.txid())
         .map_err(|err| err.to_string())?
         .ok_or_else(|| {
             format!(
@@ -5001,6 +5924,7 @@ fn build_genesis_block(params: &ChainParams) -> Result<Block, String> {
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -5118,15 +6042,6 @@ fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
     Ok(bytes)
 }
 
-fn bytes_to_hex(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        use std::fmt::Write;
-        let _ = write!(&mut out, "{:02x}", byte);
-    }
-    out
-}
-
 #[allow(clippy::too_many_arguments)]
 async fn header_sync_loop<S: KeyValueStore + Send + Sync + 'static>(
     chainstate: Arc<ChainState<S>>,
@@ -5253,10 +6168,12 @@ async fn header_peer_loop<S: KeyValueStore + Send + Sync + 'static>(
             if remote_height > 0 && remote_height < download_state.tip_height {
                 let lag = download_state.tip_height.saturating_sub(remote_height);
                 if lag > HEADER_BEHIND_BAN_THRESHOLD {
-                    peer_book.ban_for(peer_addr, HEADER_BEHIND_BAN_SECS);
+                    peer_book.penalize(peer_addr, BanReason::Behind);
+                }
+                if !peer_book.is_whitelisted(peer_addr) {
+                    peer_book.record_failure(peer_addr);
+                    addr_book.record_failure(peer_addr);
                 }
-                peer_book.record_failure(peer_addr);
-                addr_book.record_failure(peer_addr);
                 log_debug!(
                     "header peer behind (remote {} < tip {}), reconnecting",
                     remote_height,
@@ -5319,7 +6236,7 @@ async fn header_peer_loop<S: KeyValueStore + Send + Sync + 'static>(
                     peer_book.record_success(peer_addr);
                     if !headers_are_contiguous(&headers) {
                         log_warn!("non-continuous headers sequence from peer");
-                        peer_book.record_bad_chain(peer_addr, HEADER_BAD_CHAIN_BAN_SECS);
+                        peer_book.record_bad_chain(peer_addr);
                         addr_book.record_failure(peer_addr);
                         break;
                     }
@@ -5347,7 +6264,7 @@ async fn header_peer_loop<S: KeyValueStore + Send + Sync + 'static>(
                                 hash256_to_hex(&prev)
                             );
                             download_state.reset(chainstate.as_ref(), &params)?;
-                            peer_book.record_bad_chain(peer_addr, HEADER_BAD_CHAIN_BAN_SECS);
+                            peer_book.record_bad_chain(peer_addr);
                             addr_book.record_failure(peer_addr);
                             break;
                         }
@@ -5362,7 +6279,12 @@ async fn header_peer_loop<S: KeyValueStore + Send + Sync + 'static>(
                     ) {
                         log_warn!("header validation failed: {err}");
                         download_state.reset(chainstate.as_ref(), &params)?;
-                        peer_book.record_failure(peer_addr);
+                        if is_bad_fork_error(&err) {
+                            log_warn!("banning header peer {peer_addr}: {err}");
+                            peer_book.record_bad_chain(peer_addr);
+                        } else {
+                            peer_book.record_failure(peer_addr);
+                        }
                         addr_book.record_failure(peer_addr);
                         break;
                     }
@@ -5620,10 +6542,9 @@ fn build_download_locator<S: KeyValueStore>(
 ) -> Result<Vec<Hash256>, String> {
     if state.tip_hash != params.consensus.hash_genesis_block
         && !state.pending.contains_key(&state.tip_hash)
-        && chainstate
-            .header_entry(&state.tip_hash)
+        && !chainstate
+            .has_header_entry(&state.tip_hash)
             .map_err(|err| err.to_string())?
-            .is_none()
     {
         return build_locator(chainstate, &params.consensus.hash_genesis_block);
     }
@@ -5765,7 +6686,7 @@ fn commit_headers_batch<S: KeyValueStore>(
         if let Ok(Some(best)) = chainstate.best_header() {
             if last_entry.height > best.height {
                 let last_hash = headers.last().map(|header| header.hash());
-                let work_cmp = match last_entry.chainwork.cmp(&best.chainwork) {
+                let work_cmp = match compare_work(&last_entry.chainwork, &best.chainwork) {
                     Ordering::Greater => "gt",
                     Ordering::Equal => "eq",
                     Ordering::Less => "lt",
@@ -5778,11 +6699,11 @@ fn commit_headers_batch<S: KeyValueStore>(
                         .map(hash256_to_hex)
                         .unwrap_or_else(|| "-".to_string()),
                     last_entry.bits,
-                    bytes_to_hex(&last_entry.chainwork),
+                    chainwork_hex(&last_entry.chainwork),
                     work_cmp,
                     best.height,
                     hash256_to_hex(&best.hash),
-                    bytes_to_hex(&best.chainwork)
+                    chainwork_hex(&best.chainwork)
                 );
             }
         }
@@ -5950,9 +6871,8 @@ fn queue_or_commit_headers<S: KeyValueStore>(
         true
     } else {
         chainstate
-            .header_entry(&prev)
+            .has_header_entry(&prev)
             .map_err(|err| err.to_string())?
-            .is_some()
     };
     if !prev_exists {
         if pending.is_empty() {
@@ -6006,7 +6926,7 @@ fn drain_ready_header_batches<S: KeyValueStore>(
                 let exists = if *prev == params.consensus.hash_genesis_block {
                     true
                 } else {
-                    chainstate.header_entry(prev).ok().flatten().is_some()
+                    chainstate.has_header_entry(prev).unwrap_or(false)
                 };
                 if exists {
                     Some(*prev)
@@ -6126,7 +7046,8 @@ fn refresh_header_cursor<S: KeyValueStore>(
 async fn sync_chain<S: KeyValueStore + 'static>(
     block_peer: &mut Peer,
     block_peers: &mut Vec<Peer>,
-    block_peers_target: usize,
+    mut block_peers_target: usize,
+    adaptive_peers: Option<AdaptivePeerBounds>,
     chainstate: Arc<ChainState<S>>,
     mempool: Arc<Mutex<mempool::Mempool>>,
     fee_estimator: Arc<Mutex<fee_estimator::FeeEstimator>>,
@@ -6143,6 +7064,7 @@ async fn sync_chain<S: KeyValueStore + 'static>(
     header_lead: i32,
     getdata_batch: usize,
     inflight_per_peer: usize,
+    block_download_window: usize,
     mut shutdown: watch::Receiver<bool>,
 ) -> Result<(), String> {
     let idle_sleep = Duration::from_secs(IDLE_SLEEP_SECS);
@@ -6153,6 +7075,11 @@ async fn sync_chain<S: KeyValueStore + 'static>(
         .unwrap_or(-1);
     let mut last_progress_at = Instant::now();
     let mut last_peer_refill_at = Instant::now() - Duration::from_secs(BLOCK_PEER_REFILL_SECS);
+    let mut adaptive_target =
+        adaptive_peers.map(|bounds| AdaptivePeerTarget::new(bounds, block_peers_target));
+    if let Some(adaptive) = adaptive_target.as_ref() {
+        block_peers_target = adaptive.target();
+    }
     loop {
         if *shutdown.borrow() {
             log_info!("Shutdown requested; stopping sync loop.");
@@ -6164,7 +7091,7 @@ async fn sync_chain<S: KeyValueStore + 'static>(
             write_lock.as_ref(),
             &header_cursor,
         )?;
-        reorg_to_best_header(chainstate.as_ref(), write_lock.as_ref())?;
+        reorg_to_best_header(chainstate.as_ref(), &params.consensus, write_lock.as_ref())?;
         let best_block_height = chainstate
             .best_block()
             .map_err(|err| err.to_string())?
@@ -6176,6 +7103,21 @@ async fn sync_chain<S: KeyValueStore + 'static>(
         }
         let (gap, best_header_height) = header_gap(chainstate.as_ref())?;
 
+        if let Some(adaptive) = adaptive_target.as_mut() {
+            if let Some(target) = adaptive.update(Instant::now(), metrics.committed_blocks(), gap) {
+                log_info!(
+                    "Adaptive block peers: target {block_peers_target} -> {target} (header gap {gap})"
+                );
+                if target > block_peers_target {
+                    last_peer_refill_at =
+                        Instant::now() - Duration::from_secs(BLOCK_PEER_REFILL_SECS);
+                } else {
+                    block_peers.truncate(target);
+                }
+                block_peers_target = target;
+            }
+        }
+
         if peer_ctx.registry.take_disconnect_request(block_peer.addr()) {
             let addr = block_peer.addr();
             log_info!("Disconnect requested for block peer {addr}; reconnecting");
@@ -6249,11 +7191,14 @@ async fn sync_chain<S: KeyValueStore + 'static>(
             }
             last_peer_refill_at = Instant::now();
         }
-        let max_fetch = max_fetch_blocks(
+        let mut max_fetch = max_fetch_blocks(
             block_peers.len().saturating_add(1),
             getdata_batch,
             inflight_per_peer,
         );
+        if block_download_window > 0 {
+            max_fetch = max_fetch.min(block_download_window);
+        }
         let missing = collect_missing_blocks(chainstate.as_ref(), max_fetch)?;
         if !missing.is_empty() {
             if gap > 0 && last_progress_at.elapsed() > Duration::from_secs(BLOCK_STALL_SECS) {
@@ -6417,19 +7362,28 @@ fn is_transient_block_error(err: &str) -> bool {
     .any(|marker| err.contains(marker))
 }
 
-fn block_peer_ban_secs(err: &str) -> Option<u64> {
+/// Header errors that mean the peer is serving a chain we refuse to reorg to.
+fn is_bad_fork_error(err: &ChainStateError) -> bool {
+    matches!(
+        err,
+        ChainStateError::InvalidHeader(message)
+            if *message == FORK_TOO_DEEP || *message == FORK_BEFORE_CHECKPOINT
+    )
+}
+
+fn block_peer_ban_reason(err: &str) -> Option<BanReason> {
     let err = err.to_lowercase();
     if err.contains("notfound") {
-        return Some(BLOCK_PEER_BAN_SECS_NOTFOUND);
+        return Some(BanReason::NotFound);
     }
     if err.contains("reject") {
-        return Some(BLOCK_PEER_BAN_SECS_PROTOCOL);
+        return Some(BanReason::Protocol);
     }
     if err.contains("stalled") || err.contains("timeout") || err.contains("timed out") {
-        return Some(BLOCK_PEER_BAN_SECS_TIMEOUT);
+        return Some(BanReason::Timeout);
     }
     if err.contains("invalid magic") || err.contains("payload") {
-        return Some(BLOCK_PEER_BAN_SECS_PROTOCOL);
+        return Some(BanReason::Protocol);
     }
     None
 }
@@ -6485,7 +7439,7 @@ fn spawn_status_logger<S: KeyValueStore + Send + Sync + 'static>(
                     let (utxo_ms, index_ms, anchor_ms, flat_ms) =
                         connect_ms_from_last(&stats, last.as_ref());
                     log_info!(
-                        "Status: headers {} blocks {} gap {} h/s {} b/s {} dl_ms {} ver_ms {} db_ms {} hdr_req_ms {} hdr_val_ms {} hdr_commit_ms {} hdr_pow_ms {} val_ms {} script_ms {} shield_ms {} utxo_ms {} idx_ms {} anchor_ms {} flat_ms {} header {} block {} uptime {}s",
+                        "Status: headers {} blocks {} gap {} h/s {} b/s {} dl_ms {} ver_ms {} db_ms {} dl_p95_ms {} ver_p95_ms {} db_p95_ms {} hdr_req_ms {} hdr_val_ms {} hdr_commit_ms {} hdr_pow_ms {} val_ms {} script_ms {} shield_ms {} utxo_ms {} idx_ms {} anchor_ms {} flat_ms {} header {} block {} uptime {}s",
                         stats.best_header_height,
                         stats.best_block_height,
                         stats.header_gap,
@@ -6494,6 +7448,9 @@ fn spawn_status_logger<S: KeyValueStore + Send + Sync + 'static>(
                         download_ms,
                         verify_ms,
                         commit_ms,
+                        percentile_ms(stats.download_p95_us),
+                        percentile_ms(stats.verify_p95_us),
+                        percentile_ms(stats.commit_p95_us),
                         header_req_ms,
                         header_val_ms,
                         header_commit_ms,
@@ -6509,6 +7466,15 @@ fn spawn_status_logger<S: KeyValueStore + Send + Sync + 'static>(
                         block_hash,
                         stats.uptime_secs
                     );
+                    if let Some(db) = store.fjall_telemetry_snapshot() {
+                        if !db.write_stalled_partitions.is_empty() {
+                            log_warn!(
+                                "Warning: Fjall write stall on {} (max L0 segments {}); writes are throttled until compaction catches up",
+                                db.write_stalled_partitions.join(", "),
+                                db.max_l0_segments
+                            );
+                        }
+                    }
                     last = Some(stats);
                 }
                 Err(err) => {
@@ -6584,6 +7550,13 @@ fn stage_ms_from_last(
     (download_ms, verify_ms, commit_ms)
 }
 
+fn percentile_ms(value_us: Option<u64>) -> String {
+    match value_us {
+        Some(value_us) => format!("{:.2}", value_us as f64 / 1000.0),
+        None => "-".to_string(),
+    }
+}
+
 fn header_ms_from_last(
     current: &stats::StatsSnapshot,
     last: Option<&stats::StatsSnapshot>,
@@ -6731,7 +7704,7 @@ fn verify_shielded_block(
     shielded_params: &ShieldedParams,
     metrics: Option<&ValidationMetrics>,
 ) -> Result<(), String> {
-    let branch_id = current_epoch_branch_id(height, &consensus.upgrades);
+    let branch_id = branch_id_for_height(height, &consensus.upgrades);
     for tx in &block.transactions {
         if !tx_needs_shielded(tx) {
             continue;
@@ -6921,10 +7894,24 @@ fn find_common_ancestor<S: KeyValueStore>(
     Ok((ancestor, low))
 }
 
+/// Disconnects back to the fork point when the best header has left the active chain.
+///
+/// This never picks a chain itself: the best header only moves on strictly more chainwork, so an
+/// equal-work competitor never triggers a reorg and the first-received chain is kept.
+///
+/// A fork deeper than `--maxreorgdepth` (or reaching below the last checkpoint) is refused: its
+/// first block is marked invalid, as `invalidateblock` would, and the active chain stays put.
+/// `reconsiderblock` lifts the refusal.
 fn reorg_to_best_header<S: KeyValueStore>(
     chainstate: &ChainState<S>,
+    params: &ConsensusParams,
     write_lock: &Mutex<()>,
 ) -> Result<(), String> {
+    // Hold the write lock until the fork point is checked, so the depth is measured against the
+    // tip that is actually current.
+    let guard = write_lock
+        .lock()
+        .map_err(|_| "write lock poisoned".to_string())?;
     let best_block = match chainstate.best_block().map_err(|err| err.to_string())? {
         Some(tip) => tip,
         None => return Ok(()),
@@ -6954,6 +7941,28 @@ fn reorg_to_best_header<S: KeyValueStore>(
         best_block.height,
     )?;
 
+    if let Err(err) = chainstate.check_fork_point(params, best_block.height, ancestor_height) {
+        // Like zcashd, stop rather than mark the fork invalid: on the minority side of a split
+        // that mark would keep this node off the honest chain after a restart.
+        log_error!(
+            "==== REORG OF {} BLOCK(S) REFUSED: {err} ====",
+            best_block.height - ancestor_height
+        );
+        log_error!(
+            "Best header {} at height {} forks from the active chain at height {} (tip {}). Stopping for safety; check which chain is correct, then restart with a larger --maxreorgdepth or invalidateblock the unwanted branch.",
+            hash256_to_hex(&best_header.hash),
+            best_header.height,
+            ancestor_height,
+            best_block.height
+        );
+        return Err(format!(
+            "refusing reorg of {} block(s) to {}: {err}",
+            best_block.height - ancestor_height,
+            hash256_to_hex(&best_header.hash)
+        ));
+    }
+    drop(guard);
+
     let mut disconnected: usize = 0;
     loop {
         let tip = chainstate
@@ -7047,8 +8056,8 @@ async fn fetch_blocks<S: KeyValueStore + 'static>(
                 Ok(()) => peer_book.record_success(addr),
                 Err(err) => {
                     peer_book.record_failure(addr);
-                    if let Some(secs) = block_peer_ban_secs(err) {
-                        peer_book.ban_for(addr, secs);
+                    if let Some(reason) = block_peer_ban_reason(err) {
+                        peer_book.penalize(addr, reason);
                     }
                 }
             }
@@ -7168,20 +8177,17 @@ async fn fetch_blocks_multi<S: KeyValueStore + 'static>(
     let max_rounds = 3usize;
 
     while rounds < max_rounds {
-        let remaining: Vec<fluxd_consensus::Hash256> = hashes
-            .iter()
-            .copied()
-            .filter(|hash| !received.contains_key(hash))
-            .collect();
-        if remaining.is_empty() {
+        if hashes.iter().all(|hash| received.contains_key(hash)) {
             break;
         }
         rounds = rounds.saturating_add(1);
 
-        let chunks: Vec<Vec<fluxd_consensus::Hash256>> = remaining
-            .chunks(getdata_batch)
-            .map(|chunk| chunk.to_vec())
-            .collect();
+        let chunks = schedule_block_chunks(
+            hashes,
+            |hash| received.contains_key(hash),
+            getdata_batch,
+            rounds > 1,
+        );
         let queue = Arc::new(Mutex::new(VecDeque::from(chunks)));
 
         let before = received.len();
@@ -7224,8 +8230,8 @@ async fn fetch_blocks_multi<S: KeyValueStore + 'static>(
             log_debug!("block peer fetch failed: {err}");
             if let Some(peer_book) = peer_book {
                 peer_book.record_failure(block_peer_addr);
-                if let Some(secs) = block_peer_ban_secs(&err) {
-                    peer_book.ban_for(block_peer_addr, secs);
+                if let Some(reason) = block_peer_ban_reason(&err) {
+                    peer_book.penalize(block_peer_addr, reason);
                 }
             }
         }
@@ -7243,8 +8249,8 @@ async fn fetch_blocks_multi<S: KeyValueStore + 'static>(
                 log_debug!("block peer fetch failed: {err}");
                 if let Some(peer_book) = peer_book {
                     peer_book.record_failure(addr);
-                    if let Some(secs) = block_peer_ban_secs(&err) {
-                        peer_book.ban_for(addr, secs);
+                    if let Some(reason) = block_peer_ban_reason(&err) {
+                        peer_book.penalize(addr, reason);
                     }
                 }
                 continue;
@@ -7303,8 +8309,48 @@ async fn fetch_blocks_multi<S: KeyValueStore + 'static>(
         Ok(result) => result?,
         Err(err) => return Err(format!("block connect task failed: {err}")),
     }
-
-    Ok(())
+
+    Ok(())
+}
+
+/// Splits one download round into getdata chunks, most urgent first.
+///
+/// The first `getdata_batch` missing blocks after the already-received prefix are what the
+/// connected tip is waiting on; they go out first in quarter-size chunks so several peers fetch
+/// them at once and one slow peer cannot hold the tip back. Far-ahead blocks follow in full
+/// chunks, except on retry rounds, which only chase the tip-advancing blocks.
+fn schedule_block_chunks(
+    hashes: &[fluxd_consensus::Hash256],
+    is_received: impl Fn(&fluxd_consensus::Hash256) -> bool,
+    getdata_batch: usize,
+    retry: bool,
+) -> Vec<Vec<fluxd_consensus::Hash256>> {
+    let getdata_batch = getdata_batch.max(1);
+    let connected_prefix = hashes.iter().take_while(|hash| is_received(hash)).count();
+    let urgent_end = connected_prefix
+        .saturating_add(getdata_batch)
+        .min(hashes.len());
+    let missing = |range: &[fluxd_consensus::Hash256]| {
+        range
+            .iter()
+            .copied()
+            .filter(|hash| !is_received(hash))
+            .collect::<Vec<_>>()
+    };
+    let urgent = missing(&hashes[connected_prefix..urgent_end]);
+    let mut chunks: Vec<Vec<fluxd_consensus::Hash256>> = urgent
+        .chunks((getdata_batch / 4).max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect();
+    if retry && !urgent.is_empty() {
+        return chunks;
+    }
+    chunks.extend(
+        missing(&hashes[urgent_end..])
+            .chunks(getdata_batch)
+            .map(|chunk| chunk.to_vec()),
+    );
+    chunks
 }
 
 struct BlockPeerFetchOutcome {
@@ -7749,7 +8795,7 @@ fn connect_pending<S: KeyValueStore>(
                         verified_block.height,
                         hash256_to_hex(&hash)
                     );
-                    reorg_to_best_header(chainstate, write_lock)?;
+                    reorg_to_best_header(chainstate, &params.consensus, write_lock)?;
                     return Ok(());
                 }
                 Err(err) => return Err(err.to_string()),
@@ -7783,7 +8829,7 @@ fn connect_pending<S: KeyValueStore>(
                     verified_block.height,
                     hash256_to_hex(&hash)
                 );
-                reorg_to_best_header(chainstate, write_lock)?;
+                reorg_to_best_header(chainstate, &params.consensus, write_lock)?;
                 return Ok(());
             }
             metrics.record_commit(1, commit_start.elapsed());
@@ -7905,6 +8951,10 @@ fn purge_mempool_for_connected_block(
     block: &Block,
     txids: &[Hash256],
 ) -> Result<MempoolPurgeOutcome, String> {
+    let mut guard = mempool
+        .lock()
+        .map_err(|_| "mempool lock poisoned".to_string())?;
+    guard.note_block_connected();
     if block.transactions.len() <= 1 {
         return Ok(MempoolPurgeOutcome::default());
     }
@@ -7912,9 +8962,6 @@ fn purge_mempool_for_connected_block(
         return Err("transaction id cache mismatch".to_string());
     }
 
-    let mut guard = mempool
-        .lock()
-        .map_err(|_| "mempool lock poisoned".to_string())?;
     let mut mined: HashSet<Hash256> = HashSet::new();
     let mut conflicts: HashSet<Hash256> = HashSet::new();
     for (txid, tx) in txids
@@ -7924,11 +8971,9 @@ fn purge_mempool_for_connected_block(
         .zip(block.transactions.iter().skip(1))
     {
         mined.insert(txid);
-        for input in &tx.vin {
-            if let Some(conflict) = guard.spender(&input.prevout) {
-                if !mined.contains(&conflict) {
-                    conflicts.insert(conflict);
-                }
+        for conflict in guard.conflicts_with(tx) {
+            if !mined.contains(&conflict) {
+                conflicts.insert(conflict);
             }
         }
     }
@@ -7970,26 +9015,32 @@ async fn read_message_with_timeout_opts(
     retries: usize,
 ) -> Result<(String, Vec<u8>), String> {
     let retries = retries.max(1);
-    for attempt in 0..retries {
-        let read =
-            tokio::time::timeout(Duration::from_secs(timeout_secs), peer.read_message()).await;
-        match read {
-            Ok(result) => return result,
-            Err(_) if attempt + 1 == retries => {
-                return Err("peer read timed out".to_string());
-            }
-            Err(_) => {
-                log_debug!("peer read timed out (attempt {}/{})", attempt + 1, retries);
+    let mut attempt = 0;
+    let mut read_deadline = tokio::time::Instant::now() + Duration::from_secs(timeout_secs);
+    loop {
+        peer.maybe_send_ping().await?;
+        let ping_at = peer.ping_deadline();
+        tokio::select! {
+            read = tokio::time::timeout_at(read_deadline, peer.read_message()) => {
+                if let Ok(result) = read {
+                    return result;
+                }
+                attempt += 1;
+                if attempt == retries {
+                    return Err("peer read timed out".to_string());
+                }
+                log_debug!("peer read timed out (attempt {}/{})", attempt, retries);
+                read_deadline = tokio::time::Instant::now() + Duration::from_secs(timeout_secs);
             }
+            _ = tokio::time::sleep_until(ping_at) => {}
         }
     }
-
-    Err("peer read timed out".to_string())
 }
 
 async fn handle_aux_message(peer: &mut Peer, command: &str, payload: &[u8]) -> Result<(), String> {
     match command {
         "ping" => peer.send_message("pong", payload).await?,
+        "pong" => peer.handle_pong(payload),
         "version" => peer.send_message("verack", &[]).await?,
         _ => {}
     }
@@ -8027,7 +9078,8 @@ fn read_datadir_pointer(pointer: &Path) -> Option<PathBuf> {
     None
 }
 
-fn resolve_default_data_dir() -> PathBuf {
+/// `fluxd.datadir` locations consulted when `--data-dir` is not given, in priority order.
+fn datadir_pointer_candidates() -> Vec<PathBuf> {
     let mut candidates: Vec<PathBuf> = Vec::new();
     if let Ok(cwd) = std::env::current_dir() {
         candidates.push(cwd.join(DATADIR_POINTER_FILE_NAME));
@@ -8035,7 +9087,11 @@ fn resolve_default_data_dir() -> PathBuf {
     if let Some(root) = project_root_from_exe() {
         candidates.push(root.join(DATADIR_POINTER_FILE_NAME));
     }
-    for candidate in candidates {
+    candidates
+}
+
+fn resolve_default_data_dir() -> PathBuf {
+    for candidate in datadir_pointer_candidates() {
         if let Some(dir) = read_datadir_pointer(&candidate) {
             return dir;
         }
@@ -8059,11 +9115,16 @@ where
     let mut fetch_params = false;
     let mut reindex = false;
     let mut resync = false;
+    let mut reindex_on_corruption = false;
+    let mut reindex_on_corruption_set = false;
     let mut reindex_txindex = false;
     let mut reindex_spentindex = false;
     let mut reindex_addressindex = false;
     let mut reindex_addresstx = false;
     let mut db_info = false;
+    let mut print_config = false;
+    let mut doctor = false;
+    let mut migrate_datadir: Option<PathBuf> = None;
     let mut db_info_keys = false;
     let mut db_integrity = false;
     let mut scan_flatfiles = false;
@@ -8072,7 +9133,15 @@ where
     let mut debug_fluxnode_payee_script: Option<Vec<u8>> = None;
     let mut debug_fluxnode_payout_height: Option<i32> = None;
     let mut debug_fluxnode_payee_candidates: Option<DebugFluxnodePayeeCandidates> = None;
+    let mut dump_blocks: Option<DumpRange> = None;
+    let mut dump_headers: Option<DumpRange> = None;
+    let mut import_blocks: Option<PathBuf> = None;
+    let mut benchmark_connect: Option<bench_connect::BenchmarkRange> = None;
     let mut check_script = true;
+    let mut check_canonical_encoding = false;
+    let mut assume_valid: Option<Option<Hash256>> = None;
+    let mut max_reorg_depth: u32 = 0;
+    let mut max_reorg_depth_set = false;
     let mut log_level = logging::Level::Info;
     let mut log_level_set = false;
     let mut log_level_explicit = false;
@@ -8084,30 +9153,47 @@ where
     let mut p2p_listen_set = false;
     let mut p2p_addr: Option<SocketAddr> = None;
     let mut p2p_addr_set = false;
-    let mut rpc_addr: Option<SocketAddr> = None;
+    let mut external_ip: Option<String> = None;
+    let mut external_ip_set = false;
+    let mut rpc_addr: Option<ListenAddr> = None;
     let mut rpc_addr_set = false;
     let mut rpc_user: Option<String> = None;
     let mut rpc_user_set = false;
     let mut rpc_pass: Option<String> = None;
     let mut rpc_pass_set = false;
     let mut rpc_allow_ips: Vec<String> = Vec::new();
+    let mut bantime: u64 = DEFAULT_BAN_SECS;
+    let mut bantime_set = false;
+    let mut ban_policy_overrides: Vec<String> = Vec::new();
+    let mut whitelist: Vec<String> = Vec::new();
     let mut network = Network::Mainnet;
     let mut network_set = false;
     let mut getdata_batch: usize = DEFAULT_GETDATA_BATCH;
     let mut getdata_batch_set = false;
     let mut block_peers: usize = DEFAULT_BLOCK_PEERS;
     let mut block_peers_set = false;
+    let mut adaptive_peers: Option<AdaptivePeerBounds> = None;
     let mut header_peers: usize = DEFAULT_HEADER_PEERS;
     let mut header_peers_set = false;
     let mut header_lead: i32 = DEFAULT_HEADER_LEAD;
     let mut header_lead_set = false;
+    let mut block_download_window: usize = 0;
+    let mut block_download_window_set = false;
     let mut header_peer_addrs: Vec<String> = Vec::new();
     let mut addnode_nodes: Vec<String> = Vec::new();
     let mut addnode_nodes_seen: HashSet<String> = HashSet::new();
     let mut max_connections: usize = DEFAULT_MAX_CONNECTIONS;
     let mut max_connections_set = false;
+    let mut max_upload_target_mb: u64 = 0;
+    let mut max_upload_target_mb_set = false;
+    let mut max_inbound_send_rate_kb: u64 = 0;
+    let mut max_inbound_send_rate_kb_set = false;
     let mut tx_peers: usize = DEFAULT_TX_PEERS;
     let mut tx_peers_set = false;
+    let mut tx_reject_wait_secs: u64 = DEFAULT_TX_REJECT_WAIT_SECS;
+    let mut tx_reject_wait_secs_set = false;
+    let mut block_relay_peers: usize = DEFAULT_BLOCK_RELAY_PEERS;
+    let mut block_relay_peers_set = false;
     let mut inflight_per_peer: usize = DEFAULT_INFLIGHT_PER_PEER;
     let mut inflight_per_peer_set = false;
     let mut require_standard: Option<bool> = None;
@@ -8123,13 +9209,22 @@ where
     let mut mempool_max_mb_set = false;
     let mut mempool_persist_interval_secs: u64 = DEFAULT_MEMPOOL_PERSIST_INTERVAL_SECS;
     let mut mempool_persist_interval_set = false;
+    let mut mempool_expiry_hours: u64 = DEFAULT_MEMPOOL_EXPIRY_HOURS;
+    let mut mempool_expiry_hours_set = false;
+    let mut unconnected_expiry_hours: u64 = DEFAULT_UNCONNECTED_EXPIRY_HOURS;
+    let mut unconnected_expiry_hours_set = false;
     let mut fee_estimates_persist_interval_secs: u64 = DEFAULT_FEE_ESTIMATES_PERSIST_INTERVAL_SECS;
     let mut fee_estimates_persist_interval_set = false;
     let mut status_interval_secs: u64 = 15;
     let mut status_interval_set = false;
     let mut tui = default_tui;
     let mut tui_attach: Option<String> = None;
-    let mut dashboard_addr: Option<SocketAddr> = None;
+    let mut dashboard_addr: Option<ListenAddr> = None;
+    let mut ready_max_lag = DEFAULT_READY_MAX_LAG;
+    let mut notify_addr: Option<SocketAddr> = None;
+    let mut notify_addr_set = false;
+    let mut notify_topics = notify::NotifyTopics::default();
+    let mut notify_topics_set = false;
     let mut db_cache_mb: u64 = DEFAULT_DB_CACHE_MB;
     let mut db_cache_set = false;
     let mut db_write_buffer_mb: u64 = DEFAULT_DB_WRITE_BUFFER_MB;
@@ -8144,8 +9239,13 @@ where
     let mut db_compaction_workers_set = false;
     let mut db_fsync_ms_set = false;
     let mut db_fsync_ms: Option<u16> = None;
+    let mut db_compress_columns: Vec<fluxd_storage::Column> = Vec::new();
+    let mut db_latency = false;
     let mut utxo_cache_entries: usize = DEFAULT_UTXO_CACHE_ENTRIES;
     let mut utxo_cache_entries_set = false;
+    let mut utxo_flush_entries: usize = 0;
+    let mut utxo_flush_entries_set = false;
+    let mut utxo_flush_mb: usize = 0;
     let mut header_verify_workers: usize = 0;
     let mut header_verify_workers_set = false;
     let mut verify_workers: usize = 0;
@@ -8177,6 +9277,10 @@ where
                 scan_flatfiles = true;
                 let _ = args.next();
             }
+            "doctor" => {
+                doctor = true;
+                let _ = args.next();
+            }
             "scan-supply" => {
                 scan_supply = true;
                 let _ = args.next();
@@ -8235,6 +9339,10 @@ where
             "--resync" => {
                 resync = true;
             }
+            "--reindex-on-corruption" => {
+                reindex_on_corruption = true;
+                reindex_on_corruption_set = true;
+            }
             "--reindex-txindex" => {
                 reindex_txindex = true;
             }
@@ -8253,6 +9361,18 @@ where
             "--db-info" => {
                 db_info = true;
             }
+            "--print-config" => {
+                print_config = true;
+            }
+            "--doctor" => {
+                doctor = true;
+            }
+            "--migrate-datadir" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --migrate-datadir\n{}", usage()))?;
+                migrate_datadir = Some(PathBuf::from(value));
+            }
             "--db-info-keys" => {
                 db_info = true;
                 db_info_keys = true;
@@ -8322,6 +9442,24 @@ where
                     limit: 50,
                 });
             }
+            "--dump-blocks" => {
+                dump_blocks = Some(parse_dump_range("--dump-blocks", &mut args)?);
+            }
+            "--dump-headers" => {
+                dump_headers = Some(parse_dump_range("--dump-headers", &mut args)?);
+            }
+            "--benchmark-connect" => {
+                benchmark_connect = Some(bench_connect::parse_range(
+                    "--benchmark-connect",
+                    &mut args,
+                )?);
+            }
+            "--import-blocks" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --import-blocks\n{}", usage()))?;
+                import_blocks = Some(PathBuf::from(value));
+            }
             "--log-level" | "--loglevel" => {
                 let value = args
                     .next()
@@ -8350,6 +9488,27 @@ where
             "--skip-script" => {
                 check_script = false;
             }
+            "--debug-canonical-encoding" => {
+                check_canonical_encoding = true;
+            }
+            "--assumevalid" | "--assume-valid" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --assumevalid\n{}", usage()))?;
+                assume_valid = Some(
+                    parse_assume_valid(&value)
+                        .ok_or_else(|| format!("invalid assumevalid '{value}'\n{}", usage()))?,
+                );
+            }
+            "--maxreorgdepth" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --maxreorgdepth\n{}", usage()))?;
+                max_reorg_depth = value
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid maxreorgdepth '{value}'\n{}", usage()))?;
+                max_reorg_depth_set = true;
+            }
             "--miner-address" | "--mineraddress" => {
                 let value = args
                     .next()
@@ -8379,6 +9538,13 @@ where
                 );
                 p2p_addr_set = true;
             }
+            "--externalip" | "--external-ip" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --externalip\n{}", usage()))?;
+                external_ip = Some(value);
+                external_ip_set = true;
+            }
             "--no-p2p-listen" | "--no-listen" => {
                 p2p_listen = false;
                 p2p_listen_set = true;
@@ -8388,9 +9554,8 @@ where
                     .next()
                     .ok_or_else(|| format!("missing value for --rpc-addr\n{}", usage()))?;
                 rpc_addr = Some(
-                    value
-                        .parse::<SocketAddr>()
-                        .map_err(|_| format!("invalid rpc addr '{value}'\n{}", usage()))?,
+                    ListenAddr::parse(&value)
+                        .map_err(|err| format!("invalid rpc addr: {err}\n{}", usage()))?,
                 );
                 rpc_addr_set = true;
             }
@@ -8414,6 +9579,30 @@ where
                     .ok_or_else(|| format!("missing value for --rpc-allow-ip\n{}", usage()))?;
                 rpc_allow_ips.push(value);
             }
+            "--bantime" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --bantime\n{}", usage()))?;
+                bantime = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid bantime '{value}'\n{}", usage()))?;
+                if bantime == 0 {
+                    return Err(format!("bantime must be > 0\n{}", usage()));
+                }
+                bantime_set = true;
+            }
+            "--banpolicy" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --banpolicy\n{}", usage()))?;
+                ban_policy_overrides.push(value);
+            }
+            "--whitelist" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --whitelist\n{}", usage()))?;
+                whitelist.push(value);
+            }
             "--network" => {
                 let value = args
                     .next()
@@ -8447,6 +9636,14 @@ where
                     .map_err(|_| format!("invalid block peers '{value}'\n{}", usage()))?;
                 block_peers_set = true;
             }
+            "--adaptive-peers" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --adaptive-peers\n{}", usage()))?;
+                let bounds = AdaptivePeerBounds::parse(&value)
+                    .map_err(|err| format!("{err}\n{}", usage()))?;
+                adaptive_peers = Some(bounds);
+            }
             "--maxconnections" => {
                 let value = args
                     .next()
@@ -8459,6 +9656,24 @@ where
                     return Err(format!("maxconnections must be > 0\n{}", usage()));
                 }
             }
+            "--maxuploadtarget" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --maxuploadtarget\n{}", usage()))?;
+                max_upload_target_mb = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid maxuploadtarget '{value}'\n{}", usage()))?;
+                max_upload_target_mb_set = true;
+            }
+            "--maxinboundsendrate" => {
+                let value = args.next().ok_or_else(|| {
+                    format!("missing value for --maxinboundsendrate\n{}", usage())
+                })?;
+                max_inbound_send_rate_kb = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid maxinboundsendrate '{value}'\n{}", usage()))?;
+                max_inbound_send_rate_kb_set = true;
+            }
             "--header-peers" => {
                 let value = args
                     .next()
@@ -8498,6 +9713,15 @@ where
                     return Err(format!("header lead must be >= 0\n{}", usage()));
                 }
             }
+            "--block-download-window" => {
+                let value = args.next().ok_or_else(|| {
+                    format!("missing value for --block-download-window\n{}", usage())
+                })?;
+                block_download_window = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid block download window '{value}'\n{}", usage()))?;
+                block_download_window_set = true;
+            }
             "--tx-peers" => {
                 let value = args
                     .next()
@@ -8507,6 +9731,24 @@ where
                     .map_err(|_| format!("invalid tx peers '{value}'\n{}", usage()))?;
                 tx_peers_set = true;
             }
+            "--txrejectwait" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --txrejectwait\n{}", usage()))?;
+                tx_reject_wait_secs = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid txrejectwait '{value}'\n{}", usage()))?;
+                tx_reject_wait_secs_set = true;
+            }
+            "--block-relay-peers" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --block-relay-peers\n{}", usage()))?;
+                block_relay_peers = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid block relay peers '{value}'\n{}", usage()))?;
+                block_relay_peers_set = true;
+            }
             "--inflight-per-peer" => {
                 let value = args
                     .next()
@@ -8560,6 +9802,24 @@ where
                 })?;
                 mempool_persist_interval_set = true;
             }
+            "--mempool-expiry-hours" | "--mempoolexpiry" => {
+                let value = args.next().ok_or_else(|| {
+                    format!("missing value for --mempool-expiry-hours\n{}", usage())
+                })?;
+                mempool_expiry_hours = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid mempool expiry hours '{value}'\n{}", usage()))?;
+                mempool_expiry_hours_set = true;
+            }
+            "--unconnected-expiry-hours" | "--unconnectedexpiry" => {
+                let value = args.next().ok_or_else(|| {
+                    format!("missing value for --unconnected-expiry-hours\n{}", usage())
+                })?;
+                unconnected_expiry_hours = value.parse::<u64>().map_err(|_| {
+                    format!("invalid unconnected expiry hours '{value}'\n{}", usage())
+                })?;
+                unconnected_expiry_hours_set = true;
+            }
             "--fee-estimates-persist-interval" => {
                 let value = args.next().ok_or_else(|| {
                     format!(
@@ -8677,6 +9937,16 @@ where
                 db_fsync_ms = if ms == 0 { None } else { Some(ms) };
                 db_fsync_ms_set = true;
             }
+            "--db-compress-columns" => {
+                let value = args.next().ok_or_else(|| {
+                    format!("missing value for --db-compress-columns\n{}", usage())
+                })?;
+                db_compress_columns =
+                    parse_compress_columns(&value).map_err(|err| format!("{err}\n{}", usage()))?;
+            }
+            "--db-latency" => {
+                db_latency = true;
+            }
             "--utxo-cache-entries" => {
                 let value = args.next().ok_or_else(|| {
                     format!("missing value for --utxo-cache-entries\n{}", usage())
@@ -8686,6 +9956,23 @@ where
                     .map_err(|_| format!("invalid utxo cache entries '{value}'\n{}", usage()))?;
                 utxo_cache_entries_set = true;
             }
+            "--utxo-flush-entries" => {
+                let value = args.next().ok_or_else(|| {
+                    format!("missing value for --utxo-flush-entries\n{}", usage())
+                })?;
+                utxo_flush_entries = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid utxo flush entries '{value}'\n{}", usage()))?;
+                utxo_flush_entries_set = true;
+            }
+            "--utxo-flush-mb" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --utxo-flush-mb\n{}", usage()))?;
+                utxo_flush_mb = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid utxo flush mb '{value}'\n{}", usage()))?;
+            }
             "--header-verify-workers" => {
                 let value = args.next().ok_or_else(|| {
                     format!("missing value for --header-verify-workers\n{}", usage())
@@ -8727,10 +10014,36 @@ where
                     .next()
                     .ok_or_else(|| format!("missing value for --dashboard-addr\n{}", usage()))?;
                 dashboard_addr = Some(
+                    ListenAddr::parse(&value)
+                        .map_err(|err| format!("invalid dashboard addr: {err}\n{}", usage()))?,
+                );
+            }
+            "--ready-max-lag" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --ready-max-lag\n{}", usage()))?;
+                ready_max_lag = value
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid ready max lag '{value}'\n{}", usage()))?;
+            }
+            "--notify-addr" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --notify-addr\n{}", usage()))?;
+                notify_addr = Some(
                     value
                         .parse::<SocketAddr>()
-                        .map_err(|_| format!("invalid dashboard addr '{value}'\n{}", usage()))?,
+                        .map_err(|_| format!("invalid notify addr '{value}'\n{}", usage()))?,
                 );
+                notify_addr_set = true;
+            }
+            "--notify-topics" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for --notify-topics\n{}", usage()))?;
+                notify_topics = notify::NotifyTopics::parse_list(&value)
+                    .map_err(|err| format!("{err}\n{}", usage()))?;
+                notify_topics_set = true;
             }
             "--help" | "-h" => return Ok(CliAction::PrintHelp),
             "--version" | "-V" => return Ok(CliAction::PrintVersion),
@@ -8807,14 +10120,38 @@ where
         if let Some(values) = conf.get("rpcallowip") {
             rpc_allow_ips.extend(values.iter().cloned());
         }
+        if !bantime_set {
+            if let Some(values) = conf.get("bantime") {
+                if let Some(raw) = values.last() {
+                    bantime = raw
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|secs| *secs > 0)
+                        .ok_or_else(|| {
+                            format!("invalid bantime '{raw}' in {}", conf_file.display())
+                        })?;
+                }
+            }
+        }
+        if let Some(values) = conf.get("banpolicy") {
+            // Config file overrides apply first so CLI values win.
+            ban_policy_overrides.splice(0..0, values.iter().cloned());
+        }
+        if let Some(values) = conf.get("whitelist") {
+            whitelist.extend(values.iter().cloned());
+        }
 
         if !rpc_addr_set {
-            let mut bind_socket: Option<SocketAddr> = None;
+            let mut bind_socket: Option<ListenAddr> = None;
             let mut bind_ip: Option<IpAddr> = None;
             if let Some(values) = conf.get("rpcbind") {
                 if let Some(raw) = values.last() {
-                    if let Ok(addr) = raw.parse::<SocketAddr>() {
-                        bind_socket = Some(addr);
+                    if let Some(unix) = ListenAddr::parse_unix(raw) {
+                        bind_socket = Some(unix.map_err(|err| {
+                            format!("invalid rpcbind '{raw}' in {}: {err}", conf_file.display())
+                        })?);
+                    } else if let Ok(addr) = raw.parse::<SocketAddr>() {
+                        bind_socket = Some(addr.into());
                     } else if let Ok(ip) = raw.parse::<IpAddr>() {
                         bind_ip = Some(ip);
                     } else {
@@ -8840,7 +10177,7 @@ where
             } else if bind_ip.is_some() || port.is_some() {
                 let ip = bind_ip.unwrap_or(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
                 let default_port = default_rpc_addr(network).port();
-                rpc_addr = Some(SocketAddr::new(ip, port.unwrap_or(default_port)));
+                rpc_addr = Some(SocketAddr::new(ip, port.unwrap_or(default_port)).into());
             }
         }
 
@@ -8872,6 +10209,14 @@ where
             }
         }
 
+        if !external_ip_set {
+            if let Some(values) = conf.get("externalip") {
+                if let Some(value) = values.last() {
+                    external_ip = Some(value.clone());
+                }
+            }
+        }
+
         if !miner_address_set {
             if let Some(values) = conf.get("mineraddress") {
                 if let Some(value) = values.last() {
@@ -8880,6 +10225,26 @@ where
             }
         }
 
+        if assume_valid.is_none() {
+            if let Some(values) = conf.get("assumevalid") {
+                if let Some(raw) = values.last() {
+                    assume_valid = Some(parse_assume_valid(raw).ok_or_else(|| {
+                        format!("invalid assumevalid '{raw}' in {}", conf_file.display())
+                    })?);
+                }
+            }
+        }
+
+        if !max_reorg_depth_set {
+            if let Some(values) = conf.get("maxreorgdepth") {
+                if let Some(raw) = values.last() {
+                    max_reorg_depth = raw.parse::<u32>().map_err(|_| {
+                        format!("invalid maxreorgdepth '{raw}' in {}", conf_file.display())
+                    })?;
+                }
+            }
+        }
+
         if !tx_confirm_target_set {
             if let Some(values) = conf.get("txconfirmtarget") {
                 if let Some(raw) = values.last() {
@@ -8928,6 +10293,47 @@ where
             }
         }
 
+        if !mempool_expiry_hours_set {
+            if let Some(values) = conf.get("mempoolexpiry") {
+                if let Some(raw) = values.last() {
+                    mempool_expiry_hours = raw.parse::<u64>().map_err(|_| {
+                        format!("invalid mempoolexpiry '{raw}' in {}", conf_file.display())
+                    })?;
+                }
+            }
+        }
+
+        if !unconnected_expiry_hours_set {
+            if let Some(values) = conf.get("unconnectedexpiry") {
+                if let Some(raw) = values.last() {
+                    unconnected_expiry_hours = raw.parse::<u64>().map_err(|_| {
+                        format!(
+                            "invalid unconnectedexpiry '{raw}' in {}",
+                            conf_file.display()
+                        )
+                    })?;
+                }
+            }
+        }
+
+        if !notify_addr_set {
+            if let Some(values) = conf.get("notifyaddr") {
+                if let Some(raw) = values.last() {
+                    notify_addr = Some(raw.parse::<SocketAddr>().map_err(|_| {
+                        format!("invalid notifyaddr '{raw}' in {}", conf_file.display())
+                    })?);
+                }
+            }
+        }
+        if !notify_topics_set {
+            if let Some(values) = conf.get("notifytopics") {
+                if let Some(raw) = values.last() {
+                    notify_topics = notify::NotifyTopics::parse_list(raw)
+                        .map_err(|err| format!("{err} in {}", conf_file.display()))?;
+                }
+            }
+        }
+
         if !max_connections_set {
             if let Some(values) = conf.get("maxconnections") {
                 if let Some(raw) = values.last() {
@@ -8944,6 +10350,39 @@ where
             }
         }
 
+        if !max_upload_target_mb_set {
+            if let Some(values) = conf.get("maxuploadtarget") {
+                if let Some(raw) = values.last() {
+                    max_upload_target_mb = raw.parse::<u64>().map_err(|_| {
+                        format!("invalid maxuploadtarget '{raw}' in {}", conf_file.display())
+                    })?;
+                }
+            }
+        }
+
+        if !max_inbound_send_rate_kb_set {
+            if let Some(values) = conf.get("maxinboundsendrate") {
+                if let Some(raw) = values.last() {
+                    max_inbound_send_rate_kb = raw.parse::<u64>().map_err(|_| {
+                        format!(
+                            "invalid maxinboundsendrate '{raw}' in {}",
+                            conf_file.display()
+                        )
+                    })?;
+                }
+            }
+        }
+
+        if !tx_reject_wait_secs_set {
+            if let Some(values) = conf.get("txrejectwait") {
+                if let Some(raw) = values.last() {
+                    tx_reject_wait_secs = raw.parse::<u64>().map_err(|_| {
+                        format!("invalid txrejectwait '{raw}' in {}", conf_file.display())
+                    })?;
+                }
+            }
+        }
+
         if !min_relay_fee_per_kb_set {
             if let Some(values) = conf.get("minrelaytxfee") {
                 if let Some(raw) = values.last() {
@@ -9021,6 +10460,35 @@ where
             }
         }
 
+        if !block_download_window_set {
+            if let Some(values) = conf.get("blockdownloadwindow") {
+                if let Some(raw) = values.last() {
+                    block_download_window = raw.parse::<usize>().map_err(|_| {
+                        format!(
+                            "invalid blockdownloadwindow '{raw}' in {}",
+                            conf_file.display()
+                        )
+                    })?;
+                }
+            }
+        }
+
+        if !reindex_on_corruption_set {
+            if let Some(values) = conf.get("reindexoncorruption") {
+                if let Some(raw) = values.last() {
+                    match parse_conf_bool(raw) {
+                        Some(value) => reindex_on_corruption = value,
+                        None => {
+                            return Err(format!(
+                                "invalid reindexoncorruption value '{raw}' in {}",
+                                conf_file.display()
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
         if let Some(values) = conf.get("addnode") {
             for raw in values {
                 let node = raw.trim().to_string();
@@ -9032,8 +10500,13 @@ where
 
         let supported_keys = [
             "addnode",
+            "assumevalid",
+            "banpolicy",
+            "bantime",
             "bind",
+            "blockdownloadwindow",
             "dbcache",
+            "externalip",
             "headerlead",
             "limitfreerelay",
             "logformat",
@@ -9041,9 +10514,15 @@ where
             "logtimestamps",
             "listen",
             "maxconnections",
+            "maxinboundsendrate",
             "maxmempool",
+            "maxreorgdepth",
+            "maxuploadtarget",
+            "mempoolexpiry",
             "mineraddress",
             "minrelaytxfee",
+            "notifyaddr",
+            "notifytopics",
             "profile",
             "rpcallowip",
             "rpcbind",
@@ -9051,8 +10530,12 @@ where
             "rpcport",
             "rpcuser",
             "regtest",
+            "reindexoncorruption",
             "testnet",
             "txconfirmtarget",
+            "txrejectwait",
+            "unconnectedexpiry",
+            "whitelist",
         ];
         let mut unsupported: Vec<String> = conf
             .keys()
@@ -9091,6 +10574,7 @@ where
                 set_default!(block_peers, block_peers_set, 1);
                 set_default!(header_peers, header_peers_set, 2);
                 set_default!(tx_peers, tx_peers_set, 0);
+                set_default!(block_relay_peers, block_relay_peers_set, 1);
                 set_default!(inflight_per_peer, inflight_per_peer_set, 1);
                 set_default!(header_lead, header_lead_set, DEFAULT_HEADER_LEAD);
 
@@ -9141,6 +10625,7 @@ where
                 set_default!(db_fsync_ms, db_fsync_ms_set, None);
 
                 set_default!(utxo_cache_entries, utxo_cache_entries_set, 1_000_000);
+                set_default!(utxo_flush_entries, utxo_flush_entries_set, 500_000);
             }
         }
 
@@ -9166,7 +10651,26 @@ where
         })?;
     }
 
+    let external_ip = match external_ip {
+        Some(value) => {
+            let default_port = p2p_addr
+                .map(|addr| addr.port())
+                .unwrap_or_else(|| default_p2p_addr(network).port());
+            let addr = parse_socket_addr_with_default_port(value.trim(), default_port)
+                .ok_or_else(|| format!("invalid externalip '{value}'\n{}", usage()))?;
+            if !p2p::is_routable(addr.ip()) {
+                return Err(format!(
+                    "externalip '{value}' is not a publicly routable address\n{}",
+                    usage()
+                ));
+            }
+            Some(addr)
+        }
+        None => None,
+    };
+
     let require_standard = require_standard.unwrap_or(network != Network::Regtest);
+    let assume_valid = assume_valid.unwrap_or_else(|| chain_params(network).default_assume_valid);
     let partition_count = fluxd_storage::Column::ALL.len() as u64;
     if !db_memtable_set && db_memtable_mb == 0 {
         db_memtable_mb = DEFAULT_DB_MEMTABLE_MB;
@@ -9229,7 +10733,18 @@ where
 
     let tui_start_in_setup = tui && !conf_exists;
 
-    Ok(CliAction::Run(Config {
+    let mut ban_policy = BanPolicy::default();
+    ban_policy.default_secs = bantime;
+    for raw in &ban_policy_overrides {
+        ban_policy
+            .apply_override(raw)
+            .map_err(|err| format!("{err}\n{}", usage()))?;
+    }
+    for raw in &whitelist {
+        rpc::IpNet::parse_named(raw, "whitelist").map_err(|err| format!("{err}\n{}", usage()))?;
+    }
+
+    let config = Config {
         backend,
         data_dir,
         conf_path: conf_file,
@@ -9238,6 +10753,7 @@ where
         fetch_params,
         reindex,
         resync,
+        reindex_on_corruption,
         reindex_txindex,
         reindex_spentindex,
         reindex_addressindex,
@@ -9253,35 +10769,56 @@ where
         debug_fluxnode_payee_script,
         debug_fluxnode_payout_height,
         debug_fluxnode_payee_candidates,
+        dump_blocks,
+        dump_headers,
+        import_blocks,
+        benchmark_connect,
         check_script,
+        check_canonical_encoding,
+        assume_valid,
+        max_reorg_depth,
         log_level,
         log_format,
         log_timestamps,
         p2p_listen,
         p2p_addr,
+        external_ip,
         rpc_addr,
         rpc_user,
         rpc_pass,
         rpc_allow_ips,
+        ban_policy,
+        whitelist,
         getdata_batch,
         block_peers,
+        adaptive_peers,
         header_peers,
         header_lead,
+        block_download_window,
         header_peer_addrs,
         addnode_nodes,
         max_connections,
+        max_upload_target_mb,
+        max_inbound_send_rate_kb,
         tx_peers,
+        tx_reject_wait_secs,
+        block_relay_peers,
         inflight_per_peer,
         require_standard,
         min_relay_fee_per_kb,
         limit_free_relay_kb_per_minute,
         mempool_max_bytes: mb_to_bytes(mempool_max_mb).try_into().unwrap_or(usize::MAX),
         mempool_persist_interval_secs,
+        mempool_expiry_hours,
+        unconnected_expiry_hours,
         fee_estimates_persist_interval_secs,
         status_interval_secs,
         tui,
         tui_start_in_setup,
         dashboard_addr,
+        ready_max_lag,
+        notify_addr,
+        notify_topics,
         db_cache_bytes,
         db_write_buffer_bytes,
         db_journal_bytes,
@@ -9289,12 +10826,27 @@ where
         db_flush_workers,
         db_compaction_workers,
         db_fsync_ms,
+        db_compress_columns,
+        db_latency,
         utxo_cache_entries,
+        utxo_flush_entries,
+        utxo_flush_mb,
         header_verify_workers,
         verify_workers,
         verify_queue,
         shielded_workers,
-    }))
+    };
+
+    if print_config {
+        return Ok(CliAction::PrintConfig(config));
+    }
+    if doctor {
+        return Ok(CliAction::Doctor(config));
+    }
+    if let Some(target) = migrate_datadir {
+        return Ok(CliAction::MigrateDatadir { config, target });
+    }
+    Ok(CliAction::Run(config))
 }
 
 fn load_flux_conf(path: &Path) -> Result<Option<HashMap<String, Vec<String>>>, String> {
@@ -9338,6 +10890,15 @@ fn load_flux_conf(path: &Path) -> Result<Option<HashMap<String, Vec<String>>>, S
     Ok(Some(out))
 }
 
+/// Parses an assume-valid setting: `0` disables it, otherwise a block hash.
+fn parse_assume_valid(value: &str) -> Option<Option<Hash256>> {
+    let value = value.trim();
+    if value == "0" {
+        return Some(None);
+    }
+    hash256_from_hex(value).ok().map(Some)
+}
+
 fn parse_conf_bool(value: &str) -> Option<bool> {
     let value = value.trim();
     if value.is_empty() {
@@ -9418,11 +10979,7 @@ fn hex_encode(bytes: &[u8]) -> String {
 }
 
 fn outpoint_to_string(outpoint: &OutPoint) -> String {
-    format!(
-        "{}:{}",
-        stats::hash256_to_hex(&outpoint.hash),
-        outpoint.index
-    )
+    format!("{}:{}", hash256_to_hex(&outpoint.hash), outpoint.index)
 }
 
 fn parse_fee_rate_per_kb(value: &str) -> Result<i64, String> {
@@ -9441,54 +10998,10 @@ fn parse_fee_rate_per_kb(value: &str) -> Result<i64, String> {
 }
 
 fn parse_amount_zat(value: &str) -> Result<i64, String> {
-    let value = value.trim();
-    if value.is_empty() {
-        return Err("amount is empty".to_string());
-    }
-    if value.starts_with('-') {
-        return Err("amount must be >= 0".to_string());
-    }
-
-    let (whole, frac) = match value.split_once('.') {
-        Some((whole, frac)) => (whole, Some(frac)),
-        None => (value, None),
-    };
-    if whole.is_empty() && frac.is_none() {
-        return Err(format!("invalid amount '{value}'"));
-    }
-
-    let whole = if whole.is_empty() {
-        0i64
-    } else {
-        whole
-            .parse::<i64>()
-            .map_err(|_| format!("invalid amount '{value}'"))?
-    };
-    if whole < 0 {
-        return Err("amount must be >= 0".to_string());
-    }
-
-    let mut frac_value = 0i64;
-    if let Some(frac) = frac {
-        if frac.len() > 8 {
-            return Err(format!("amount has too many decimal places '{value}'"));
-        }
-        if !frac.chars().all(|ch| ch.is_ascii_digit()) {
-            return Err(format!("invalid amount '{value}'"));
-        }
-        let mut frac_str = frac.to_string();
-        while frac_str.len() < 8 {
-            frac_str.push('0');
-        }
-        frac_value = frac_str
-            .parse::<i64>()
-            .map_err(|_| format!("invalid amount '{value}'"))?;
-    }
-
-    whole
-        .checked_mul(COIN)
-        .and_then(|whole_zat| whole_zat.checked_add(frac_value))
-        .ok_or_else(|| format!("amount out of range '{value}'"))
+    money::parse(value).map_err(|err| match err {
+        money::MoneyError::Empty => err.to_string(),
+        _ => format!("{err} '{}'", value.trim()),
+    })
 }
 
 fn default_rpc_addr(network: Network) -> SocketAddr {
@@ -9546,6 +11059,174 @@ fn resolve_verify_settings(
     }
 }
 
+fn resolved_config_json(config: &Config) -> serde_json::Value {
+    use serde_json::json;
+
+    let verify_settings = resolve_verify_settings(
+        config,
+        config.getdata_batch,
+        config.inflight_per_peer,
+        config
+            .adaptive_peers
+            .map_or(config.block_peers, |bounds| bounds.max),
+    );
+    let network = match config.network {
+        Network::Mainnet => "mainnet",
+        Network::Testnet => "testnet",
+        Network::Regtest => "regtest",
+    };
+    let backend = match config.backend {
+        Backend::Memory => "memory",
+        Backend::Fjall => "fjall",
+    };
+    let log_format = match config.log_format {
+        logging::Format::Text => "text",
+        logging::Format::Json => "json",
+    };
+    let rpc_addr = config
+        .rpc_addr
+        .clone()
+        .unwrap_or_else(|| default_rpc_addr(config.network).into());
+    let p2p_addr = config
+        .p2p_addr
+        .unwrap_or_else(|| default_p2p_addr(config.network));
+
+    // Built from pairs: a single `json!` literal this long exceeds the macro recursion limit.
+    let entries: Vec<(&str, serde_json::Value)> = vec![
+        ("backend", json!(backend)),
+        ("data_dir", json!(config.data_dir.display().to_string())),
+        ("conf_path", json!(config.conf_path.display().to_string())),
+        ("network", json!(network)),
+        ("params_dir", json!(config.params_dir.display().to_string())),
+        ("fetch_params", json!(config.fetch_params)),
+        ("reindex", json!(config.reindex)),
+        ("resync", json!(config.resync)),
+        ("reindex_on_corruption", json!(config.reindex_on_corruption)),
+        ("reindex_txindex", json!(config.reindex_txindex)),
+        ("reindex_spentindex", json!(config.reindex_spentindex)),
+        ("reindex_addressindex", json!(config.reindex_addressindex)),
+        ("reindex_addresstx", json!(config.reindex_addresstx)),
+        ("miner_address", json!(config.miner_address)),
+        ("tx_confirm_target", json!(config.tx_confirm_target)),
+        ("check_script", json!(config.check_script)),
+        (
+            "check_canonical_encoding",
+            json!(config.check_canonical_encoding),
+        ),
+        (
+            "assume_valid",
+            json!(config.assume_valid.as_ref().map(hash256_to_hex)),
+        ),
+        ("max_reorg_depth", json!(config.max_reorg_depth)),
+        ("log_level", json!(config.log_level.as_str())),
+        ("log_format", json!(log_format)),
+        ("log_timestamps", json!(config.log_timestamps)),
+        ("p2p_listen", json!(config.p2p_listen)),
+        ("p2p_addr", json!(p2p_addr.to_string())),
+        (
+            "external_ip",
+            json!(config.external_ip.map(|addr| addr.to_string())),
+        ),
+        ("rpc_addr", json!(rpc_addr.to_string())),
+        ("rpc_user", json!(config.rpc_user)),
+        (
+            "rpc_pass",
+            json!(config.rpc_pass.as_ref().map(|_| "<redacted>")),
+        ),
+        ("rpc_allow_ips", json!(config.rpc_allow_ips)),
+        ("ban_policy", json!(config.ban_policy.to_json())),
+        ("whitelist", json!(config.whitelist)),
+        ("getdata_batch", json!(config.getdata_batch)),
+        ("block_peers", json!(config.block_peers)),
+        (
+            "adaptive_peers",
+            json!(config.adaptive_peers.map(|bounds| serde_json::json!({
+                "min": bounds.min,
+                "max": bounds.max,
+            }))),
+        ),
+        ("header_peers", json!(config.header_peers)),
+        ("header_lead", json!(config.header_lead)),
+        ("block_download_window", json!(config.block_download_window)),
+        ("header_peer_addrs", json!(config.header_peer_addrs)),
+        ("addnode_nodes", json!(config.addnode_nodes)),
+        ("max_connections", json!(config.max_connections)),
+        ("max_upload_target_mb", json!(config.max_upload_target_mb)),
+        (
+            "max_inbound_send_rate_kb",
+            json!(config.max_inbound_send_rate_kb),
+        ),
+        ("tx_peers", json!(config.tx_peers)),
+        ("tx_reject_wait_secs", json!(config.tx_reject_wait_secs)),
+        ("block_relay_peers", json!(config.block_relay_peers)),
+        ("inflight_per_peer", json!(config.inflight_per_peer)),
+        ("require_standard", json!(config.require_standard)),
+        ("min_relay_fee_per_kb", json!(config.min_relay_fee_per_kb)),
+        (
+            "limit_free_relay_kb_per_minute",
+            json!(config.limit_free_relay_kb_per_minute),
+        ),
+        ("mempool_max_bytes", json!(config.mempool_max_bytes)),
+        (
+            "mempool_persist_interval_secs",
+            json!(config.mempool_persist_interval_secs),
+        ),
+        ("mempool_expiry_hours", json!(config.mempool_expiry_hours)),
+        (
+            "unconnected_expiry_hours",
+            json!(config.unconnected_expiry_hours),
+        ),
+        (
+            "fee_estimates_persist_interval_secs",
+            json!(config.fee_estimates_persist_interval_secs),
+        ),
+        ("status_interval_secs", json!(config.status_interval_secs)),
+        ("tui", json!(config.tui)),
+        (
+            "dashboard_addr",
+            json!(config.dashboard_addr.as_ref().map(|addr| addr.to_string())),
+        ),
+        ("ready_max_lag", json!(config.ready_max_lag)),
+        (
+            "notify_addr",
+            json!(config.notify_addr.map(|addr| addr.to_string())),
+        ),
+        ("notify_topics", json!(config.notify_topics.names())),
+        ("db_cache_bytes", json!(config.db_cache_bytes)),
+        ("db_write_buffer_bytes", json!(config.db_write_buffer_bytes)),
+        ("db_journal_bytes", json!(config.db_journal_bytes)),
+        ("db_memtable_bytes", json!(config.db_memtable_bytes)),
+        ("db_flush_workers", json!(config.db_flush_workers)),
+        ("db_compaction_workers", json!(config.db_compaction_workers)),
+        ("db_fsync_ms", json!(config.db_fsync_ms)),
+        (
+            "db_compress_columns",
+            json!(config
+                .db_compress_columns
+                .iter()
+                .map(|column| column.as_str())
+                .collect::<Vec<_>>()),
+        ),
+        ("db_latency", json!(config.db_latency)),
+        ("utxo_cache_entries", json!(config.utxo_cache_entries)),
+        ("utxo_flush_entries", json!(config.utxo_flush_entries)),
+        ("utxo_flush_mb", json!(config.utxo_flush_mb)),
+        (
+            "header_verify_workers",
+            json!(resolve_header_verify_workers(config)),
+        ),
+        ("verify_workers", json!(verify_settings.verify_workers)),
+        ("verify_queue", json!(verify_settings.verify_queue)),
+        ("shielded_workers", json!(verify_settings.shielded_workers)),
+    ];
+    serde_json::Value::Object(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
 fn resolve_header_verify_workers(config: &Config) -> usize {
     if config.header_verify_workers > 0 {
         return config.header_verify_workers;
@@ -9576,6 +11257,7 @@ fn usage() -> String {
         "  scan-flatfiles  Alias for --scan-flatfiles",
         "  scan-supply     Alias for --scan-supply",
         "  scan-fluxnodes  Alias for --scan-fluxnodes",
+        "  doctor          Alias for --doctor",
         "",
         "Options:",
         "  --help, -h  Print this help and exit",
@@ -9592,10 +11274,14 @@ fn usage() -> String {
         "  --fetch-params  Download shielded params into --params-dir",
         "  --reindex  Rebuild db/ indexes from existing flatfiles under --data-dir/blocks (no network)",
         "  --resync  Wipe db/ and blocks/ under --data-dir and restart from genesis",
+        "  --reindex-on-corruption  On a fatal db/ inconsistency, write reindex.flag and restart into a reindex from blocks/ (once per process)",
         "  --reindex-txindex  Rebuild txindex from blocks under --data-dir/blocks",
         "  --reindex-spentindex  Rebuild spent index from blocks under --data-dir/blocks",
         "  --reindex-addressindex  Rebuild address index (outpoints + deltas) from blocks under --data-dir/blocks",
         "  --reindex-addresstx, --reindex-addresstxindex  Rebuild address tx totals/checkpoints from blocks under --data-dir/blocks",
+        "  --print-config  Print the resolved configuration (flux.conf + flags + profile) as JSON, then exit",
+        "  --doctor  Check data dir lock, shielded params, DB open, schema versions, flatfiles and disk space, then exit nonzero on failure (no networking)",
+        "  --migrate-datadir <path>  Move a stopped node's data dir (db, flatfiles, state files) to <path>, verify the copy, repoint fluxd.datadir and remove the old dir, then exit",
         "  --db-info  Print DB/flatfile size breakdown and fjall telemetry, then exit",
         "  --db-info-keys  Like --db-info, but also counts keys/bytes in each DB partition (slow)",
         "  --db-integrity  Print DB/flatfile sanity + verify last 288 blocks (checklevel 5), then exit nonzero on failure",
@@ -9605,24 +11291,41 @@ fn usage() -> String {
         "  --debug-fluxnode-payee-script  Scan fluxnode records for a matching payee script, then exit",
         "  --debug-fluxnode-payouts  Print expected deterministic fluxnode payouts at a height, then exit",
         "  --debug-fluxnode-payee-candidates  Print ordered deterministic payee candidates for a tier+height, then exit",
+        "  --dump-blocks <start> <end> <path>  Write raw main-chain blocks in [start, end] to <path> (flatfile framing), then exit",
+        "  --dump-headers <start> <end> <path>  Write raw main-chain headers in [start, end] to <path> (flatfile framing), then exit",
+        "  --import-blocks <path>  Connect blocks from a --dump-blocks export before starting sync",
+        "  --benchmark-connect <start> <end>  Replay stored blocks [start, end] into a scratch in-memory chainstate and report connect throughput, then exit",
         "  --skip-script  Disable script validation (testing only)",
+        "  --debug-canonical-encoding  Reject blocks whose raw bytes do not re-encode identically (debugging only)",
+        "  --assumevalid <hash|0>  Skip script checks for ancestors of this block (0 disables; default: built-in per network)",
+        "  --maxreorgdepth <n>  Refuse reorgs disconnecting more than n blocks and ban peers serving them (0 = consensus limit only, default: 0)",
         "  --network   Network selection (default: mainnet)",
         "  --miner-address  Default miner address for getblocktemplate when wallet is not available",
         "  --txconfirmtarget  Fee estimation target in blocks when paytxfee is unset (default: 2)",
         "  --p2p-addr  Bind P2P listener (default: 0.0.0.0:16125 mainnet, 26125 testnet)",
         "  --no-p2p-listen  Disable inbound P2P listener",
+        "  --externalip <ip[:port]>  Public address advertised to peers (must be routable; port defaults to the P2P port)",
         "  --addnode  Add a manual peer (HOST[:PORT], repeatable)",
-        "  --rpc-addr  Bind JSON-RPC server (default: 127.0.0.1:16124 mainnet, 26124 testnet)",
+        "  --bantime <secs>  Default ban duration for setban without an explicit bantime (default: 86400)",
+        "  --banpolicy <reason>=<secs>  Automatic ban duration per reason: notfound, timeout, protocol, badchain, behind (repeatable; 0 disables)",
+        "  --whitelist <ip/cidr>  Never ban or drop-as-behind peers from this range (repeatable)",
+        "  --rpc-addr  Bind JSON-RPC server to IP:PORT or unix:PATH (default: 127.0.0.1:16124 mainnet, 26124 testnet)",
         "  --rpc-user  JSON-RPC basic auth username (required unless cookie exists)",
         "  --rpc-pass  JSON-RPC basic auth password (required unless cookie exists)",
         "  --rpc-allow-ip  Allow JSON-RPC requests from IP/CIDR (repeatable; default: localhost only)",
         "  --getdata-batch  Max blocks per getdata request (default: 128)",
         "  --block-peers  Number of parallel peers for block download (default: 3)",
+        "  --adaptive-peers <min>:<max>  Raise the block peer target while sync is slow and drop to <min> near the tip (disabled by default)",
         "  --maxconnections  Maintain at most N total peer connections (default: 125)",
+        "  --maxuploadtarget <MB>  Daily upload budget over a rolling 24h window; once spent, blocks older than a week are not served (0 = unlimited; default: 0)",
+        "  --maxinboundsendrate <KB/s>  Cap the combined send rate to all inbound peers (0 = unlimited; default: 0)",
         "  --header-peers  Number of peers to probe for header sync (default: 4)",
         "  --header-peer  Header peer HOST[:PORT] to pin for header sync (repeatable)",
         "  --header-lead  Target header lead over blocks (default: 20000, 0 disables cap)",
+        "  --block-download-window  Max blocks requested ahead of the connected tip (0 = peers x batch x inflight, default: 0)",
         "  --tx-peers  Number of relay peers for tx inventory/tx relay (0 disables, default: 2)",
+        "  --txrejectwait <secs>  How long sendrawtransaction waits for a peer reject before returning (0 disables, default: 2)",
+        "  --block-relay-peers  Outbound block-relay-only peers, no tx/addr relay (0 disables, default: 2)",
         "  --inflight-per-peer  Concurrent getdata requests per peer (default: 1)",
         "  --minrelaytxfee  Minimum relay fee-rate in zatoshis/kB (default: 100)",
         "  --limitfreerelay  Rate-limit free transactions to N*1000 bytes/min (default: 500)",
@@ -9630,6 +11333,8 @@ fn usage() -> String {
         "  --require-standard  Force standardness checks on regtest (default: off)",
         "  --mempool-max-mb  Mempool max size in MiB (0 disables cap, default: 300)",
         "  --mempool-persist-interval  Persist mempool to disk every N seconds (0 disables, default: 60)",
+        "  --mempool-expiry-hours  Evict mempool txs (and descendants) older than N hours (0 disables, default: 336)",
+        "  --unconnected-expiry-hours  Prune parked unconnected blocks older than N hours (0 disables, default: 24)",
         "  --fee-estimates-persist-interval  Persist fee estimates every N seconds (0 disables, default: 300)",
         "  --status-interval  Status log interval in seconds (default: 15, 0 disables)",
         "  --tui  Launch terminal UI monitor (default for fluxd; use fluxd-cli for headless)",
@@ -9641,12 +11346,19 @@ fn usage() -> String {
         "  --db-flush-workers  Fjall flush worker threads (default: 2)",
         "  --db-compaction-workers  Fjall compaction worker threads (default: 4)",
         "  --db-fsync-ms  Fjall async fsync interval in ms (0 disables, optional)",
+        "  --db-compress-columns  Comma-separated columns to zstd compress, e.g. utxo,block_undo (new databases only)",
+        "  --db-latency  Record per-column get/put/scan latency histograms (shown by --db-info and /stats)",
         "  --utxo-cache-entries  In-memory UTXO entry cache size (0 disables, default: 200000)",
+        "  --utxo-flush-entries  Defer UTXO writes until N are pending (0 writes every block, default: 0)",
+        "  --utxo-flush-mb  Defer UTXO writes until they hold N MiB (0 = no size limit, default: 0)",
         "  --header-verify-workers  POW header verification threads (0 = auto)",
         "  --verify-workers  Pre-validation worker threads (0 = auto)",
         "  --verify-queue  Pre-validation queue depth (0 = auto)",
         "  --shielded-workers  Shielded verification threads (0 = auto)",
-        "  --dashboard-addr  Bind dashboard HTTP server (disabled by default)",
+        "  --dashboard-addr  Bind dashboard HTTP server to IP:PORT or unix:PATH (disabled by default)",
+        "  --ready-max-lag  Blocks behind the best header the dashboard /ready probe allows (default: 2)",
+        "  --notify-addr <addr>  Publish newline-JSON block/tx notifications on this TCP address (disabled by default)",
+        "  --notify-topics <list>  Comma-separated notification topics: hashblock, hashtx, rawblock, rawtx (default: hashblock,hashtx)",
     ]
     .join("\n")
 }
@@ -9655,6 +11367,9 @@ fn usage() -> String {
 mod tests {
     use super::*;
     use fluxd_consensus::constants::COINBASE_MATURITY;
+    use fluxd_consensus::{
+        block_subsidy, exchange_fund_amount, foundation_fund_amount, swap_pool_amount,
+    };
     use fluxd_primitives::hash::sha256d;
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -9669,6 +11384,410 @@ mod tests {
         }
     }
 
+    #[test]
+    fn shutdown_coordinator_drain_runs_final_save() {
+        let shutdown = Arc::new(ShutdownCoordinator::default());
+        let saves = Arc::new(AtomicUsize::new(0));
+        {
+            let saves = Arc::clone(&saves);
+            shutdown.spawn("persist-test", move |shutdown| loop {
+                let stopping = shutdown.wait(Duration::from_secs(3600));
+                saves.fetch_add(1, AtomicOrdering::SeqCst);
+                if stopping {
+                    break;
+                }
+            });
+        }
+        shutdown.drain();
+        assert_eq!(saves.load(AtomicOrdering::SeqCst), 1);
+        assert!(shutdown.wait(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn parse_compress_columns_accepts_column_names() {
+        use fluxd_storage::Column;
+
+        assert_eq!(
+            parse_compress_columns("utxo, block_undo,utxo,").unwrap(),
+            vec![Column::Utxo, Column::BlockUndo]
+        );
+        assert!(parse_compress_columns("").unwrap().is_empty());
+        assert!(parse_compress_columns("undo").is_err());
+        assert!(parse_compress_columns("meta").is_err());
+    }
+
+    #[test]
+    fn print_config_dumps_resolved_settings() {
+        let data_dir =
+            std::env::temp_dir().join(format!("fluxd-print-config-{}-missing", std::process::id()));
+        let args = [
+            "--network",
+            "regtest",
+            "--data-dir",
+            data_dir.to_str().expect("utf8 path"),
+            "--profile",
+            "high",
+            "--print-config",
+        ];
+        let action =
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false).expect("parse args");
+        let CliAction::PrintConfig(config) = action else {
+            panic!("expected print-config action");
+        };
+        let json = resolved_config_json(&config);
+        assert_eq!(json["network"], "regtest");
+        assert_eq!(json["rpc_addr"], "127.0.0.1:26124");
+        assert_eq!(json["getdata_batch"], config.getdata_batch);
+        assert!(json["verify_workers"].as_u64().expect("verify_workers") > 0);
+        assert!(json["rpc_pass"].is_null());
+    }
+
+    #[test]
+    fn schedule_block_chunks_puts_tip_blocks_first() {
+        let hashes: Vec<fluxd_consensus::Hash256> = (0u8..20).map(|seed| [seed; 32]).collect();
+        let received: HashSet<fluxd_consensus::Hash256> =
+            [hashes[0], hashes[1], hashes[4], hashes[15]]
+                .into_iter()
+                .collect();
+        let heights = |chunks: &[Vec<fluxd_consensus::Hash256>]| {
+            chunks
+                .iter()
+                .map(|chunk| chunk.iter().map(|hash| hash[0]).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        let chunks = schedule_block_chunks(&hashes, |hash| received.contains(hash), 8, false);
+        assert_eq!(
+            heights(&chunks),
+            vec![
+                vec![2, 3],
+                vec![5, 6],
+                vec![7, 8],
+                vec![9],
+                vec![10, 11, 12, 13, 14, 16, 17, 18],
+                vec![19],
+            ]
+        );
+
+        let retry = schedule_block_chunks(&hashes, |hash| received.contains(hash), 8, true);
+        assert_eq!(
+            heights(&retry),
+            vec![vec![2, 3], vec![5, 6], vec![7, 8], vec![9]]
+        );
+
+        let all_urgent_received: HashSet<fluxd_consensus::Hash256> =
+            hashes[..10].iter().copied().collect();
+        let retry =
+            schedule_block_chunks(&hashes, |hash| all_urgent_received.contains(hash), 8, true);
+        assert_eq!(
+            heights(&retry),
+            vec![vec![10, 11], vec![12, 13], vec![14, 15], vec![16, 17]]
+        );
+    }
+
+    #[test]
+    fn block_download_window_flag_parses() {
+        let args = [
+            "--network",
+            "regtest",
+            "--block-download-window",
+            "512",
+            "--print-config",
+        ];
+        let action =
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false).expect("parse args");
+        let CliAction::PrintConfig(config) = action else {
+            panic!("expected print-config action");
+        };
+        assert_eq!(config.block_download_window, 512);
+        assert_eq!(resolved_config_json(&config)["block_download_window"], 512);
+    }
+
+    #[test]
+    fn doctor_flag_and_command_parse() {
+        for args in [
+            ["--network", "regtest", "--doctor"],
+            ["doctor", "--network", "regtest"],
+        ] {
+            let action =
+                parse_args_from(args.iter().map(|arg| arg.to_string()), false).expect("parse args");
+            let CliAction::Doctor(config) = action else {
+                panic!("expected doctor action");
+            };
+            assert_eq!(config.network, Network::Regtest);
+        }
+    }
+
+    #[test]
+    fn migrate_datadir_flag_parses() {
+        let action = parse_args_from(
+            ["--network", "regtest", "--migrate-datadir", "/srv/fluxd"]
+                .iter()
+                .map(|arg| arg.to_string()),
+            false,
+        )
+        .expect("parse args");
+        let CliAction::MigrateDatadir { config, target } = action else {
+            panic!("expected migrate action");
+        };
+        assert_eq!(config.network, Network::Regtest);
+        assert_eq!(target, PathBuf::from("/srv/fluxd"));
+        assert!(parse_args_from(["--migrate-datadir".to_string()], false).is_err());
+    }
+
+    #[test]
+    fn dump_and_import_flags_parse() {
+        let data_dir =
+            std::env::temp_dir().join(format!("fluxd-dump-args-{}-missing", std::process::id()));
+        let data_dir = data_dir.to_str().expect("utf8 path");
+        let args = [
+            "--data-dir",
+            data_dir,
+            "--dump-blocks",
+            "10",
+            "20",
+            "blocks.bin",
+            "--import-blocks",
+            "import.bin",
+        ];
+        let action =
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false).expect("parse args");
+        let CliAction::Run(config) = action else {
+            panic!("expected run action");
+        };
+        let range = config.dump_blocks.expect("dump range");
+        assert_eq!((range.start, range.end), (10, 20));
+        assert_eq!(range.path, PathBuf::from("blocks.bin"));
+        assert_eq!(config.import_blocks, Some(PathBuf::from("import.bin")));
+
+        let args = ["--data-dir", data_dir, "--dump-headers", "5", "4", "out"];
+        assert!(parse_args_from(args.iter().map(|arg| arg.to_string()), false).is_err());
+
+        let args = ["--data-dir", data_dir, "--benchmark-connect", "0", "99"];
+        let action =
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false).expect("parse args");
+        let CliAction::Run(config) = action else {
+            panic!("expected run action");
+        };
+        assert_eq!(
+            config.benchmark_connect,
+            Some(bench_connect::BenchmarkRange { start: 0, end: 99 })
+        );
+    }
+
+    #[test]
+    fn dump_blocks_then_import_blocks_rebuilds_the_chain() {
+        struct TempDirGuard {
+            path: PathBuf,
+        }
+
+        impl Drop for TempDirGuard {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.path);
+            }
+        }
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!(
+            "fluxd-dump-import-test-{}-{nanos}",
+            std::process::id()
+        ));
+        let _guard = TempDirGuard { path: root.clone() };
+        let open_chainstate = |name: &str| {
+            let blocks_dir = root.join(name).join("blocks");
+            std::fs::create_dir_all(&blocks_dir).expect("create blocks dir");
+            let blocks = FlatFileStore::new(&blocks_dir, 10_000_000).expect("flatfiles");
+            let undo =
+                FlatFileStore::new_with_prefix(&blocks_dir, "undo", 10_000_000).expect("flatfiles");
+            ChainState::new(Arc::new(MemoryStore::new()), blocks, undo)
+        };
+
+        let params = chain_params(Network::Regtest);
+        let flags = ValidationFlags::default();
+        let write_lock = Mutex::new(());
+        let source = open_chainstate("source");
+        ensure_genesis(&source, &params, &flags, None, &write_lock).expect("genesis");
+        for height in 1..=10 {
+            // The import fully validates blocks, so give each coinbase a scriptSig of legal length.
+            let mut coinbase = build_coinbase_tx(height, &params, Vec::new());
+            coinbase.vin[0].script_sig = vec![0x01, height as u8];
+            connect_regtest_block(&source, &params, height, vec![coinbase]);
+        }
+        let source_tip = source.best_block().expect("best block").expect("tip");
+
+        let range = DumpRange {
+            start: 0,
+            end: source_tip.height,
+            path: root.join("blocks.bin"),
+        };
+        dump_blocks(&source, &range).expect("dump blocks");
+
+        // The helper's blocks carry no Equihash solution, so index their headers without
+        // proof-of-work checks first, as the helper itself does.
+        let target = open_chainstate("target");
+        let headers: Vec<BlockHeader> = (0..=source_tip.height)
+            .map(|height| {
+                let hash = source
+                    .height_hash(height)
+                    .expect("height hash")
+                    .expect("main-chain hash")
+                    .to_internal();
+                let bytes = source
+                    .block_header_bytes(&hash)
+                    .expect("header bytes")
+                    .expect("header present");
+                BlockHeader::consensus_decode(&bytes).expect("decode header")
+            })
+            .collect();
+        let mut batch = WriteBatch::new();
+        target
+            .insert_headers_batch_with_pow(&headers, &params.consensus, &mut batch, false)
+            .expect("insert headers");
+        target.commit_batch(batch).expect("commit headers");
+
+        import_blocks(&target, &range.path, &params, &flags, &write_lock).expect("import");
+        let target_tip = target.best_block().expect("best block").expect("tip");
+        assert_eq!(target_tip.height, source_tip.height);
+        assert_eq!(target_tip.hash, source_tip.hash);
+        for height in 0..=source_tip.height {
+            assert_eq!(
+                target.height_hash(height).expect("height hash"),
+                source.height_hash(height).expect("height hash")
+            );
+        }
+
+        // Re-importing the same export skips every block instead of failing.
+        import_blocks(&target, &range.path, &params, &flags, &write_lock).expect("re-import");
+        let target_tip = target.best_block().expect("best block").expect("tip");
+        assert_eq!(target_tip.hash, source_tip.hash);
+    }
+
+    #[test]
+    fn ban_policy_and_whitelist_flags_parse() {
+        let data_dir =
+            std::env::temp_dir().join(format!("fluxd-banpolicy-{}-missing", std::process::id()));
+        let data_dir = data_dir.to_str().expect("utf8 path");
+        let parse = |extra: &[&str]| {
+            let mut args = vec!["--data-dir", data_dir];
+            args.extend_from_slice(extra);
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false)
+        };
+
+        let Ok(CliAction::Run(config)) = parse(&[
+            "--bantime",
+            "600",
+            "--banpolicy",
+            "behind=0",
+            "--whitelist",
+            "10.0.0.0/8",
+            "--whitelist",
+            "192.0.2.1",
+        ]) else {
+            panic!("expected run action");
+        };
+        assert_eq!(config.ban_policy.default_secs, 600);
+        assert_eq!(config.ban_policy.secs(BanReason::Behind), 0);
+        assert_eq!(config.ban_policy.secs(BanReason::BadChain), 900);
+        assert_eq!(config.whitelist, vec!["10.0.0.0/8", "192.0.2.1"]);
+
+        assert!(parse(&["--bantime", "0"]).is_err());
+        assert!(parse(&["--banpolicy", "unknown=5"]).is_err());
+        assert!(parse(&["--whitelist", "10.0.0.0/99"]).is_err());
+    }
+
+    #[test]
+    fn notify_flags_parse() {
+        let data_dir =
+            std::env::temp_dir().join(format!("fluxd-notify-{}-missing", std::process::id()));
+        let data_dir = data_dir.to_str().expect("utf8 path");
+        let parse = |extra: &[&str]| {
+            let mut args = vec!["--data-dir", data_dir];
+            args.extend_from_slice(extra);
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false)
+        };
+
+        let Ok(CliAction::Run(config)) = parse(&[]) else {
+            panic!("expected run action");
+        };
+        assert_eq!(config.notify_addr, None);
+        assert_eq!(config.notify_topics.names(), vec!["hashblock", "hashtx"]);
+
+        let Ok(CliAction::Run(config)) = parse(&[
+            "--notify-addr",
+            "127.0.0.1:28332",
+            "--notify-topics",
+            "rawblock,hashtx",
+        ]) else {
+            panic!("expected run action");
+        };
+        assert_eq!(config.notify_addr, Some("127.0.0.1:28332".parse().unwrap()));
+        assert_eq!(config.notify_topics.names(), vec!["hashtx", "rawblock"]);
+
+        assert!(parse(&["--notify-addr", "localhost"]).is_err());
+        assert!(parse(&["--notify-topics", "sequence"]).is_err());
+    }
+
+    #[test]
+    fn externalip_requires_routable_address() {
+        let data_dir =
+            std::env::temp_dir().join(format!("fluxd-externalip-{}-missing", std::process::id()));
+        let data_dir = data_dir.to_str().expect("utf8 path");
+        let parse = |value: &str| {
+            let args = ["--data-dir", data_dir, "--externalip", value];
+            parse_args_from(args.iter().map(|arg| arg.to_string()), false)
+        };
+
+        let Ok(CliAction::Run(config)) = parse("8.8.8.8") else {
+            panic!("expected run action");
+        };
+        assert_eq!(
+            config.external_ip,
+            Some("8.8.8.8:16125".parse().expect("addr"))
+        );
+        let Ok(CliAction::Run(config)) = parse("[2a01:4f8::1]:30000") else {
+            panic!("expected run action");
+        };
+        assert_eq!(
+            config.external_ip,
+            Some("[2a01:4f8::1]:30000".parse().expect("addr"))
+        );
+        assert!(parse("192.168.1.5").is_err());
+        assert!(parse("127.0.0.1:16125").is_err());
+        assert!(parse("not-an-ip").is_err());
+    }
+
+    #[test]
+    fn reindex_on_corruption_reads_flag_and_conf() {
+        let data_dir = std::env::temp_dir().join(format!(
+            "fluxd-reindex-on-corruption-conf-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&data_dir).expect("create data dir");
+        let data_dir_arg = data_dir.to_str().expect("utf8 path");
+        let parse = |args: &[&str]| {
+            let mut all = vec!["--data-dir", data_dir_arg];
+            all.extend_from_slice(args);
+            let Ok(CliAction::Run(config)) =
+                parse_args_from(all.iter().map(|arg| arg.to_string()), false)
+            else {
+                panic!("expected run action");
+            };
+            config
+        };
+
+        assert!(!parse(&[]).reindex_on_corruption);
+        assert!(parse(&["--reindex-on-corruption"]).reindex_on_corruption);
+
+        fs::write(data_dir.join("flux.conf"), "reindexoncorruption=1\n").expect("write conf");
+        assert!(parse(&[]).reindex_on_corruption);
+        fs::write(data_dir.join("flux.conf"), "reindexoncorruption=maybe\n").expect("write conf");
+        assert!(parse_args_from(["--data-dir", data_dir_arg].map(String::from), false).is_err());
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
     fn merkle_hash_pair(left: &Hash256, right: &Hash256) -> Hash256 {
         let mut buf = [0u8; 64];
         buf[0..32].copy_from_slice(left);
@@ -9763,6 +11882,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         }
     }
@@ -9886,6 +12006,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn init_meta_u32_keeps_value_written_first() {
+        let store = Store::Memory(MemoryStore::new());
+        let key = b"test_init_meta";
+        assert_eq!(init_meta_u32(&store, key, 7).expect("init"), 7);
+        assert_eq!(init_meta_u32(&store, key, 9).expect("init"), 7);
+        assert_eq!(meta_u32(&store, key).expect("meta"), Some(7));
+    }
+
     #[test]
     fn index_schema_version_missing_on_empty_column_is_initialized() {
         let store = Store::Memory(MemoryStore::new());
@@ -9944,6 +12073,154 @@ mod tests {
         assert_eq!(meta_u32(&store, key).expect("meta"), None);
     }
 
+    #[test]
+    fn combined_index_rebuild_matches_separate_rebuilds() {
+        struct TempDirGuard {
+            path: PathBuf,
+        }
+
+        impl Drop for TempDirGuard {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.path);
+            }
+        }
+
+        fn spend(prevout: OutPoint, value: i64, script_pubkey: Vec<u8>) -> Transaction {
+            Transaction {
+                f_overwintered: false,
+                version: 1,
+                version_group_id: 0,
+                vin: vec![TxIn {
+                    prevout,
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                }],
+                vout: vec![TxOut {
+                    value,
+                    script_pubkey,
+                }],
+                lock_time: 0,
+                expiry_height: 0,
+                value_balance: 0,
+                shielded_spends: Vec::new(),
+                shielded_outputs: Vec::new(),
+                join_splits: Vec::new(),
+                join_split_pub_key: [0u8; 32],
+                join_split_sig: [0u8; 64],
+                binding_sig: [0u8; 64],
+                raw_tail: Vec::new(),
+                fluxnode: None,
+            }
+        }
+
+        const COLUMNS: [fluxd_storage::Column; 4] = [
+            fluxd_storage::Column::TxIndex,
+            fluxd_storage::Column::SpentIndex,
+            fluxd_storage::Column::AddressDelta,
+            fluxd_storage::Column::AddressOutpoint,
+        ];
+        fn clear_columns(store: &MemoryStore) {
+            let mut batch = WriteBatch::new();
+            for column in COLUMNS {
+                for (key, _) in store.scan_prefix(column, &[]).expect("scan") {
+                    batch.delete(column, key);
+                }
+            }
+            store.write_batch(&batch).expect("clear");
+        }
+        fn snapshot(store: &MemoryStore) -> Vec<(fluxd_storage::Column, Vec<u8>, Vec<u8>)> {
+            let mut out = Vec::new();
+            for column in COLUMNS {
+                let mut entries = store.scan_prefix(column, &[]).expect("scan");
+                entries.sort();
+                out.extend(entries.into_iter().map(|(key, value)| (column, key, value)));
+            }
+            out
+        }
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let data_dir = std::env::temp_dir().join(format!(
+            "fluxd-combined-reindex-test-{}-{nanos}",
+            std::process::id()
+        ));
+        let _guard = TempDirGuard {
+            path: data_dir.clone(),
+        };
+        let blocks_dir = data_dir.join("blocks");
+        std::fs::create_dir_all(&blocks_dir).expect("create blocks dir");
+        let blocks = FlatFileStore::new(&blocks_dir, 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(&blocks_dir, "undo", 10_000_000).expect("flatfiles");
+        let store = Arc::new(MemoryStore::new());
+        let chainstate = ChainState::new(Arc::clone(&store), blocks, undo);
+
+        let params = chain_params(Network::Regtest);
+        let flags = ValidationFlags::default();
+        let write_lock = Mutex::new(());
+        ensure_genesis(&chainstate, &params, &flags, None, &write_lock).expect("genesis");
+
+        let miner_script_pubkey = p2pkh_script([0x11u8; 20]);
+        let miner_value = block_subsidy(1, &params.consensus);
+        let coinbase = build_coinbase_tx(1, &params, miner_script_pubkey);
+        let coinbase_txid = coinbase.txid().expect("coinbase txid");
+        connect_regtest_block(&chainstate, &params, 1, vec![coinbase]);
+        extend_regtest_chain_to_height(&chainstate, &params, COINBASE_MATURITY);
+
+        // The child spends its parent inside the same block.
+        let spend_height = COINBASE_MATURITY + 1;
+        let parent = spend(
+            OutPoint {
+                hash: coinbase_txid,
+                index: 0,
+            },
+            miner_value - 1000,
+            p2pkh_script([0x22u8; 20]),
+        );
+        let child = spend(
+            OutPoint {
+                hash: parent.txid().expect("parent txid"),
+                index: 0,
+            },
+            miner_value - 2000,
+            p2pkh_script([0x33u8; 20]),
+        );
+        let coinbase = build_coinbase_tx(spend_height, &params, Vec::new());
+        connect_regtest_block(
+            &chainstate,
+            &params,
+            spend_height,
+            vec![coinbase, parent, child],
+        );
+
+        // Block connection writes every index; both rebuild paths must reproduce exactly that.
+        let connected = snapshot(&store);
+        assert!(COLUMNS
+            .iter()
+            .all(|column| connected.iter().any(|(col, _, _)| col == column)));
+
+        clear_columns(&store);
+        rebuild_txindex(&chainstate, &write_lock).expect("rebuild tx index");
+        rebuild_spentindex(&chainstate, &write_lock).expect("rebuild spent index");
+        rebuild_addressindex(&chainstate, &write_lock).expect("rebuild address index");
+        assert_eq!(snapshot(&store), connected);
+
+        clear_columns(&store);
+        rebuild_all_indexes(
+            &chainstate,
+            &write_lock,
+            IndexRebuildTargets {
+                txindex: true,
+                spentindex: true,
+                addressindex: true,
+            },
+        )
+        .expect("combined rebuild");
+        assert_eq!(snapshot(&store), connected);
+    }
+
     #[test]
     fn reindex_spentindex_rebuilds_details_from_txindex() {
         struct TempDirGuard {
@@ -10019,6 +12296,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase = build_coinbase_tx(spend_height, &params, Vec::new());
@@ -10048,4 +12326,204 @@ mod tests {
         assert_eq!(details_after.address_type, details_before.address_type);
         assert_eq!(details_after.address_hash, details_before.address_hash);
     }
+
+    #[test]
+    fn reorg_to_best_header_refuses_fork_past_max_reorg_depth() {
+        struct TempDirGuard {
+            path: PathBuf,
+        }
+
+        impl Drop for TempDirGuard {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.path);
+            }
+        }
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let data_dir = std::env::temp_dir().join(format!(
+            "fluxd-max-reorg-depth-test-{}-{nanos}",
+            std::process::id()
+        ));
+        let _guard = TempDirGuard {
+            path: data_dir.clone(),
+        };
+        let blocks_dir = data_dir.join("blocks");
+        std::fs::create_dir_all(&blocks_dir).expect("create blocks dir");
+        let blocks = FlatFileStore::new(&blocks_dir, 10_000_000).expect("flatfiles");
+        let undo =
+            FlatFileStore::new_with_prefix(&blocks_dir, "undo", 10_000_000).expect("flatfiles");
+        let chainstate = ChainState::new(Arc::new(MemoryStore::new()), blocks, undo);
+
+        let params = chain_params(Network::Regtest);
+        let flags = ValidationFlags::default();
+        let write_lock = Mutex::new(());
+        ensure_genesis(&chainstate, &params, &flags, None, &write_lock).expect("genesis");
+        extend_regtest_chain_to_height(&chainstate, &params, 10);
+        let tip = chainstate.best_block().expect("best block").expect("tip");
+
+        // A header-only fork from 4 blocks below the tip that ends with more work.
+        let fork_height = tip.height - 4;
+        let fork_base = chainstate
+            .header_ancestor_hash(&tip.hash, fork_height)
+            .expect("ancestor")
+            .expect("fork base");
+        let spacing = params.consensus.pow_target_spacing.max(1) as u32;
+        let mut prev = fork_base;
+        let mut fork_hashes = Vec::new();
+        for height in fork_height + 1..=tip.height + 1 {
+            let prev_entry = chainstate
+                .header_entry(&prev)
+                .expect("header entry")
+                .expect("prev entry");
+            let time = prev_entry.time + spacing + 1;
+            let bits = chainstate
+                .next_work_required_bits(&prev, height, time as i64, &params.consensus)
+                .expect("next bits");
+            let header = BlockHeader {
+                version: CURRENT_VERSION,
+                prev_block: prev,
+                merkle_root: [0u8; 32],
+                final_sapling_root: [0u8; 32],
+                time,
+                bits,
+                nonce: [0xf0; 32],
+                solution: Vec::new(),
+                nodes_collateral: OutPoint::null(),
+                block_sig: Vec::new(),
+            };
+            let mut batch = WriteBatch::new();
+            chainstate
+                .insert_headers_batch_with_pow(
+                    std::slice::from_ref(&header),
+                    &params.consensus,
+                    &mut batch,
+                    false,
+                )
+                .expect("insert fork header");
+            chainstate.commit_batch(batch).expect("commit fork header");
+            prev = header.hash();
+            fork_hashes.push(prev);
+        }
+        let fork_tip = prev;
+        let best_header = |chainstate: &ChainState<MemoryStore>| {
+            chainstate
+                .best_header()
+                .expect("best header")
+                .expect("header tip")
+                .hash
+        };
+        assert_eq!(best_header(&chainstate), fork_tip);
+
+        chainstate.set_max_reorg_depth(3);
+        let err = reorg_to_best_header(&chainstate, &params.consensus, &write_lock).unwrap_err();
+        assert!(err.contains(FORK_TOO_DEEP), "{err}");
+        let active = chainstate.best_block().expect("best block").expect("tip");
+        assert_eq!(active.hash, tip.hash);
+        // Nothing is persisted: the fork stays the best header and is not marked failed.
+        assert_eq!(best_header(&chainstate), fork_tip);
+        assert!(!chainstate
+            .header_entry(&fork_hashes[0])
+            .expect("header entry")
+            .expect("fork root")
+            .is_failed());
+
+        // Lifting the limit lets the reorg proceed.
+        chainstate.set_max_reorg_depth(0);
+        reorg_to_best_header(&chainstate, &params.consensus, &write_lock).expect("reorg");
+        let active = chainstate.best_block().expect("best block").expect("tip");
+        assert_eq!(active.hash, fork_base);
+    }
+
+    #[test]
+    fn spread_by_netgroup_interleaves_groups() {
+        let addr = |raw: &str| raw.parse::<SocketAddr>().expect("addr");
+        let spread = spread_by_netgroup(vec![
+            addr("10.1.0.1:16125"),
+            addr("10.1.0.2:16125"),
+            addr("10.1.0.3:16125"),
+            addr("10.2.0.1:16125"),
+            addr("[2001:db8::1]:16125"),
+            addr("10.2.0.2:16125"),
+        ]);
+        assert_eq!(
+            spread,
+            vec![
+                addr("10.1.0.1:16125"),
+                addr("10.2.0.1:16125"),
+                addr("[2001:db8::1]:16125"),
+                addr("10.1.0.2:16125"),
+                addr("10.2.0.2:16125"),
+                addr("10.1.0.3:16125"),
+            ]
+        );
+        assert!(spread_by_netgroup(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn fixed_seeds_carry_recorded_height() {
+        let params = chain_params(Network::Mainnet);
+        let entries = fixed_seed_entries(&params);
+        assert_eq!(entries.len(), params.fixed_seeds.len());
+        assert!(entries.iter().all(|(_, entry)| entry.last_height > 0));
+        assert!(fixed_seed_entries(&chain_params(Network::Regtest)).is_empty());
+    }
+
+    #[test]
+    fn anchors_prefer_long_lived_block_relay_outbound_peers() {
+        let peer = |raw: &str, kind: PeerKind, inbound: bool, age_secs: u64| {
+            let connected_since = SystemTime::now() - Duration::from_secs(age_secs);
+            PeerInfoSnapshot {
+                addr: raw.parse().expect("addr"),
+                kind,
+                inbound,
+                version: 0,
+                services: 0,
+                user_agent: String::new(),
+                start_height: 0,
+                connected_since,
+                last_send: connected_since,
+                last_recv: connected_since,
+                bytes_sent: 0,
+                bytes_recv: 0,
+                last_ping_ms: None,
+                min_ping_ms: None,
+            }
+        };
+        let peers = vec![
+            peer("10.0.0.1:16125", PeerKind::Block, false, 600),
+            peer("10.0.0.2:16125", PeerKind::BlockRelayOnly, true, 900),
+            peer("10.0.0.3:16125", PeerKind::BlockRelayOnly, false, 60),
+            peer("10.0.0.4:16125", PeerKind::Block, false, 1200),
+            peer("10.0.0.5:16125", PeerKind::Relay, false, 3600),
+        ];
+        let anchors = select_anchors(&peers);
+        assert_eq!(
+            anchors,
+            vec![
+                "10.0.0.3:16125".parse::<SocketAddr>().expect("addr"),
+                "10.0.0.4:16125".parse::<SocketAddr>().expect("addr"),
+            ]
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "fluxd-anchors-{}-{}.dat",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        assert!(load_anchors_file(&path).expect("missing file").is_empty());
+        save_anchors_file(&path, &anchors).expect("save anchors");
+        assert_eq!(load_anchors_file(&path).expect("load anchors"), anchors);
+        let _ = fs::remove_file(&path);
+
+        let book = AddrBook::default();
+        book.set_anchors(anchors.clone());
+        assert_eq!(book.take_anchors(), anchors);
+        assert!(book.take_anchors().is_empty());
+    }
 }
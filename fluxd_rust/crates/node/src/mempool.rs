@@ -1,16 +1,16 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
 use std::sync::{Mutex, OnceLock};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use fluxd_chainstate::state::ChainState;
-use fluxd_chainstate::validation::{validate_mempool_transaction, ValidationFlags};
-use fluxd_consensus::constants::{
-    COINBASE_MATURITY, MAX_BLOCK_SIGOPS, MAX_BLOCK_SIZE, TX_EXPIRING_SOON_THRESHOLD,
-};
+use fluxd_chainstate::validation::{count_sigops, validate_mempool_transaction, ValidationFlags};
+use fluxd_consensus::constants::{MAX_BLOCK_SIGOPS, MAX_BLOCK_SIZE, TX_EXPIRING_SOON_THRESHOLD};
 use fluxd_consensus::money::{money_range, MAX_MONEY};
 use fluxd_consensus::params::ChainParams;
-use fluxd_consensus::upgrades::{current_epoch_branch_id, network_upgrade_active, UpgradeIndex};
+use fluxd_consensus::upgrades::{consensus_rules, network_upgrade_active, UpgradeIndex};
 use fluxd_consensus::Hash256;
+use fluxd_primitives::hash::hash256_to_hex;
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::transaction::Transaction;
 use fluxd_script::interpreter::{
@@ -19,8 +19,6 @@ use fluxd_script::interpreter::{
 use fluxd_script::standard::{classify_script_pubkey, ScriptType};
 use fluxd_shielded::verify_transaction;
 
-use crate::stats::hash256_to_hex;
-
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum MempoolErrorKind {
     AlreadyInMempool,
@@ -70,6 +68,12 @@ impl std::error::Error for MempoolError {}
 
 const MEMPOOL_HEIGHT: u32 = 0x7fff_ffff;
 const MAX_PRIORITY: f64 = 1e16;
+/// Half-life of the rolling minimum fee while the mempool is at least half full. It decays twice
+/// as fast below half full and four times as fast below a quarter full. Decay only starts once a
+/// block has connected after the last bump.
+const ROLLING_MIN_FEE_HALFLIFE_SECS: f64 = 12.0 * 60.0 * 60.0;
+/// How often relay peers re-check the fee filter they advertised.
+const FEE_FILTER_CHECK_SECS: u64 = 60;
 
 pub struct MempoolEntry {
     pub txid: Hash256,
@@ -86,6 +90,8 @@ pub struct MempoolEntry {
     pub priority_delta: f64,
     pub spent_outpoints: Vec<OutPoint>,
     pub parents: Vec<Hash256>,
+    /// Legacy plus P2SH sigops, as budgeted by block templates.
+    pub sigops: usize,
 }
 
 impl MempoolEntry {
@@ -126,13 +132,15 @@ impl MempoolEntry {
     }
 }
 
+/// A transaction held back because some of its inputs spend outputs we have not seen yet.
 #[derive(Clone, Debug)]
-struct OrphanTx {
-    txid: Hash256,
-    raw: Vec<u8>,
-    received: u64,
-    missing_parents: Vec<Hash256>,
-    limit_free: bool,
+pub struct OrphanTx {
+    pub txid: Hash256,
+    pub raw: Vec<u8>,
+    pub received: u64,
+    pub missing_parents: Vec<Hash256>,
+    pub limit_free: bool,
+    pub from_peer: Option<SocketAddr>,
 }
 
 #[derive(Clone, Debug)]
@@ -151,10 +159,16 @@ pub struct Mempool {
     prioritisations: HashMap<Hash256, Prioritisation>,
     orphans: HashMap<Hash256, OrphanTx>,
     orphans_by_parent: HashMap<Hash256, Vec<Hash256>>,
+    orphans_per_peer: HashMap<SocketAddr, usize>,
     orphan_bytes: usize,
     total_bytes: usize,
     max_bytes: usize,
     revision: u64,
+    /// Fee floor (zatoshis/KB) raised by size-cap evictions; see `min_fee_to_enter`.
+    rolling_min_fee_per_kb: f64,
+    rolling_min_fee_updated: u64,
+    block_since_min_fee_bump: bool,
+    min_fee_increment_per_kb: i64,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -174,11 +188,84 @@ impl Mempool {
             prioritisations: HashMap::new(),
             orphans: HashMap::new(),
             orphans_by_parent: HashMap::new(),
+            orphans_per_peer: HashMap::new(),
             orphan_bytes: 0,
             total_bytes: 0,
             max_bytes,
             revision: 0,
+            rolling_min_fee_per_kb: 0.0,
+            rolling_min_fee_updated: 0,
+            block_since_min_fee_bump: false,
+            min_fee_increment_per_kb: 0,
+        }
+    }
+
+    /// Sets how far above the fee-rate of an evicted transaction the entry floor is raised
+    /// (zatoshis/KB). The daemon uses the minimum relay fee.
+    pub fn set_min_fee_increment_per_kb(&mut self, fee_per_kb: i64) {
+        self.min_fee_increment_per_kb = fee_per_kb.max(0);
+    }
+
+    /// Minimum fee-rate (zatoshis/KB) a transaction needs to enter the mempool. Zero unless the
+    /// size cap has recently forced evictions; once a block connects the floor then decays back
+    /// towards zero.
+    pub fn min_fee_to_enter(&self) -> i64 {
+        self.min_fee_to_enter_at(now_secs())
+    }
+
+    pub fn min_fee_to_enter_at(&self, now: u64) -> i64 {
+        let fee = self.decayed_min_fee(now);
+        if fee <= 0.0 {
+            0
+        } else {
+            fee.ceil() as i64
+        }
+    }
+
+    fn decayed_min_fee(&self, now: u64) -> f64 {
+        if self.rolling_min_fee_per_kb <= 0.0 {
+            return 0.0;
+        }
+        if !self.block_since_min_fee_bump {
+            return self.rolling_min_fee_per_kb;
+        }
+        let mut halflife = ROLLING_MIN_FEE_HALFLIFE_SECS;
+        if self.max_bytes > 0 {
+            if self.total_bytes < self.max_bytes / 4 {
+                halflife /= 4.0;
+            } else if self.total_bytes < self.max_bytes / 2 {
+                halflife /= 2.0;
+            }
+        }
+        let elapsed = now.saturating_sub(self.rolling_min_fee_updated) as f64;
+        let fee = self.rolling_min_fee_per_kb * 0.5f64.powf(elapsed / halflife);
+        let cutoff = (self.min_fee_increment_per_kb as f64 / 2.0).max(1.0);
+        if fee < cutoff {
+            0.0
+        } else {
+            fee
+        }
+    }
+
+    fn bump_min_fee(&mut self, evicted_fee_per_kb: f64, now: u64) {
+        let floor = evicted_fee_per_kb + self.min_fee_increment_per_kb as f64;
+        let current = self.decayed_min_fee(now);
+        self.rolling_min_fee_per_kb = current.max(floor);
+        self.rolling_min_fee_updated = now;
+        self.block_since_min_fee_bump = false;
+    }
+
+    /// Lets the rolling minimum fee start decaying; called for each connected block.
+    pub fn note_block_connected(&mut self) {
+        self.note_block_connected_at(now_secs());
+    }
+
+    pub fn note_block_connected_at(&mut self, now: u64) {
+        if self.block_since_min_fee_bump {
+            return;
         }
+        self.block_since_min_fee_bump = true;
+        self.rolling_min_fee_updated = now;
     }
 
     pub fn revision(&self) -> u64 {
@@ -205,6 +292,36 @@ impl Mempool {
         self.sapling_nullifiers.get(nullifier).copied()
     }
 
+    /// Mempool txids that spend a prevout, sprout nullifier or sapling nullifier also spent by
+    /// `tx`. A transaction already in the mempool is not reported as conflicting with itself.
+    pub fn conflicts_with(&self, tx: &Transaction) -> Vec<Hash256> {
+        let mut out: Vec<Hash256> = Vec::new();
+        let mut note = |spender: Option<&Hash256>| {
+            if let Some(spender) = spender {
+                if !out.contains(spender) {
+                    out.push(*spender);
+                }
+            }
+        };
+        for input in &tx.vin {
+            note(self.spent.get(&input.prevout));
+        }
+        for joinsplit in &tx.join_splits {
+            for nullifier in &joinsplit.nullifiers {
+                note(self.sprout_nullifiers.get(nullifier));
+            }
+        }
+        for spend in &tx.shielded_spends {
+            note(self.sapling_nullifiers.get(&spend.nullifier));
+        }
+        if !out.is_empty() {
+            if let Ok(txid) = tx.txid() {
+                out.retain(|spender| *spender != txid);
+            }
+        }
+        out
+    }
+
     pub fn size(&self) -> usize {
         self.entries.len()
     }
@@ -272,21 +389,42 @@ impl Mempool {
         raw: Vec<u8>,
         missing_inputs: Vec<OutPoint>,
         limit_free: bool,
+        from_peer: Option<SocketAddr>,
+    ) {
+        self.store_orphan_at(txid, raw, missing_inputs, limit_free, from_peer, now_secs());
+    }
+
+    pub fn store_orphan_at(
+        &mut self,
+        txid: Hash256,
+        raw: Vec<u8>,
+        missing_inputs: Vec<OutPoint>,
+        limit_free: bool,
+        from_peer: Option<SocketAddr>,
+        now: u64,
     ) {
         let missing_parents = orphan_parent_txids(&missing_inputs);
         if missing_parents.is_empty() {
             return;
         }
-        self.insert_orphan(OrphanTx {
-            txid,
-            raw,
-            received: now_secs(),
-            missing_parents,
-            limit_free,
-        });
+        self.insert_orphan(
+            OrphanTx {
+                txid,
+                raw,
+                received: now,
+                missing_parents,
+                limit_free,
+                from_peer,
+            },
+            now,
+        );
     }
 
-    fn take_orphans_for_parent(&mut self, parent_txid: &Hash256) -> Vec<OrphanTx> {
+    /// Releases the orphans waiting on `parent_txid` so they can be re-evaluated.
+    ///
+    /// Call this after `parent_txid` has been inserted; orphans that are still missing other
+    /// parents should be handed back to `store_orphan`.
+    pub fn process_orphans_for(&mut self, parent_txid: &Hash256) -> Vec<OrphanTx> {
         let Some(txids) = self.orphans_by_parent.remove(parent_txid) else {
             return Vec::new();
         };
@@ -299,8 +437,8 @@ impl Mempool {
         out
     }
 
-    fn insert_orphan(&mut self, orphan: OrphanTx) {
-        self.prune_orphans();
+    fn insert_orphan(&mut self, orphan: OrphanTx, now: u64) {
+        self.prune_orphans(now);
 
         if DEFAULT_MAX_ORPHANS == 0 || DEFAULT_MAX_ORPHAN_BYTES == 0 {
             return;
@@ -322,6 +460,17 @@ impl Mempool {
             }
         }
 
+        if let Some(peer) = orphan.from_peer {
+            while self.orphans_per_peer.get(&peer).copied().unwrap_or(0)
+                >= DEFAULT_MAX_ORPHANS_PER_PEER
+            {
+                if !self.evict_oldest_orphan_from(peer) {
+                    break;
+                }
+            }
+            *self.orphans_per_peer.entry(peer).or_insert(0) += 1;
+        }
+
         self.orphan_bytes = self.orphan_bytes.saturating_add(orphan.raw.len());
         for parent in &orphan.missing_parents {
             let children = self.orphans_by_parent.entry(*parent).or_default();
@@ -345,11 +494,25 @@ impl Mempool {
         true
     }
 
-    fn prune_orphans(&mut self) {
+    fn evict_oldest_orphan_from(&mut self, peer: SocketAddr) -> bool {
+        let Some(oldest_txid) = self
+            .orphans
+            .values()
+            .filter(|orphan| orphan.from_peer == Some(peer))
+            .min_by_key(|orphan| orphan.received)
+            .map(|orphan| orphan.txid)
+        else {
+            return false;
+        };
+        self.remove_orphan(&oldest_txid);
+        true
+    }
+
+    fn prune_orphans(&mut self, now: u64) {
         if DEFAULT_ORPHAN_TTL_SECS == 0 {
             return;
         }
-        let cutoff = now_secs().saturating_sub(DEFAULT_ORPHAN_TTL_SECS);
+        let cutoff = now.saturating_sub(DEFAULT_ORPHAN_TTL_SECS);
         let stale: Vec<Hash256> = self
             .orphans
             .iter()
@@ -369,6 +532,14 @@ impl Mempool {
     fn remove_orphan(&mut self, txid: &Hash256) -> Option<OrphanTx> {
         let orphan = self.orphans.remove(txid)?;
         self.orphan_bytes = self.orphan_bytes.saturating_sub(orphan.raw.len());
+        if let Some(peer) = orphan.from_peer {
+            if let Some(count) = self.orphans_per_peer.get_mut(&peer) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.orphans_per_peer.remove(&peer);
+                }
+            }
+        }
 
         let mut empty_parents = Vec::new();
         for parent in &orphan.missing_parents {
@@ -399,6 +570,14 @@ impl Mempool {
     }
 
     pub fn insert(&mut self, entry: MempoolEntry) -> Result<MempoolInsertOutcome, MempoolError> {
+        self.insert_at(entry, now_secs())
+    }
+
+    pub fn insert_at(
+        &mut self,
+        entry: MempoolEntry,
+        now: u64,
+    ) -> Result<MempoolInsertOutcome, MempoolError> {
         let mut entry = entry;
         if let Some(priority) = self.prioritisations.get(&entry.txid) {
             entry.priority_delta += priority.priority_delta;
@@ -419,6 +598,16 @@ impl Mempool {
                 "transaction already in mempool",
             ));
         }
+        let min_fee = min_relay_fee_for_size(self.min_fee_to_enter_at(now), entry.size());
+        if min_fee > 0 && entry.modified_fee() < min_fee {
+            return Err(MempoolError::new(
+                MempoolErrorKind::InsufficientFee,
+                format!(
+                    "mempool min fee not met, {} < {min_fee}",
+                    entry.modified_fee()
+                ),
+            ));
+        }
         for outpoint in &entry.spent_outpoints {
             if let Some(conflict) = self.spent.get(outpoint) {
                 return Err(MempoolError::new(
@@ -481,7 +670,7 @@ impl Mempool {
 
         let mut outcome = MempoolInsertOutcome::default();
         if self.max_bytes > 0 && self.total_bytes > self.max_bytes {
-            outcome = self.evict_to_fit();
+            outcome = self.evict_to_fit(now);
         }
 
         if self.max_bytes > 0 && !self.entries.contains_key(&inserted_txid) {
@@ -538,6 +727,42 @@ impl Mempool {
         Some(entry)
     }
 
+    /// In-mempool ancestors of `txid` (parents, their parents, ...), nearest first. Excludes
+    /// `txid` itself.
+    pub fn ancestors(&self, txid: &Hash256) -> Vec<Hash256> {
+        self.related(txid, |entry| entry.parents.clone())
+    }
+
+    /// In-mempool descendants of `txid` (children, their children, ...), nearest first. Excludes
+    /// `txid` itself.
+    pub fn descendants(&self, txid: &Hash256) -> Vec<Hash256> {
+        self.related(txid, |entry| {
+            self.children.get(&entry.txid).cloned().unwrap_or_default()
+        })
+    }
+
+    fn related(
+        &self,
+        txid: &Hash256,
+        next: impl Fn(&MempoolEntry) -> Vec<Hash256>,
+    ) -> Vec<Hash256> {
+        let mut visited: HashSet<Hash256> = HashSet::from([*txid]);
+        let mut queue: VecDeque<Hash256> = VecDeque::from([*txid]);
+        let mut out = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            let Some(entry) = self.entries.get(&current) else {
+                continue;
+            };
+            for related in next(entry) {
+                if self.entries.contains_key(&related) && visited.insert(related) {
+                    out.push(related);
+                    queue.push_back(related);
+                }
+            }
+        }
+        out
+    }
+
     pub fn remove_with_descendants(&mut self, txid: &Hash256) -> Vec<MempoolEntry> {
         let mut visited: HashSet<Hash256> = HashSet::new();
         let mut order: Vec<Hash256> = Vec::new();
@@ -574,7 +799,35 @@ impl Mempool {
         self.max_bytes
     }
 
-    fn evict_to_fit(&mut self) -> MempoolInsertOutcome {
+    /// Removes entries older than `max_age_secs` as of `now`, along with their descendants.
+    pub fn expire(&mut self, now: u64, max_age_secs: u64) -> MempoolInsertOutcome {
+        let cutoff = now.saturating_sub(max_age_secs);
+        let mut expired: Vec<(u64, Hash256)> = self
+            .entries
+            .values()
+            .filter(|entry| entry.time < cutoff)
+            .map(|entry| (entry.time, entry.txid))
+            .collect();
+        expired.sort();
+
+        let mut outcome = MempoolInsertOutcome::default();
+        for (_, txid) in expired {
+            let removed = self.remove_with_descendants(&txid);
+            outcome.evicted = outcome.evicted.saturating_add(removed.len() as u64);
+            outcome.evicted_bytes = outcome.evicted_bytes.saturating_add(
+                removed
+                    .iter()
+                    .map(|entry| entry.raw.len() as u64)
+                    .sum::<u64>(),
+            );
+            outcome
+                .evicted_txids
+                .extend(removed.iter().map(|entry| entry.txid));
+        }
+        outcome
+    }
+
+    fn evict_to_fit(&mut self, now: u64) -> MempoolInsertOutcome {
         let max_bytes = self.max_bytes;
 
         let mut candidates: Vec<EvictCandidate> = self
@@ -615,6 +868,7 @@ impl Mempool {
             if removed.is_empty() {
                 continue;
             }
+            self.bump_min_fee(candidate.fee as f64 * 1000.0 / candidate.size as f64, now);
             evicted_txids.extend(removed.iter().map(|entry| entry.txid));
             evicted = evicted.saturating_add(removed.len() as u64);
             evicted_bytes = evicted_bytes.saturating_add(
@@ -680,6 +934,40 @@ impl MempoolPolicy {
     }
 }
 
+/// Tracks the `feefilter` advertised to one peer: the larger of the minimum relay fee and the
+/// mempool entry floor.
+#[derive(Debug, Default)]
+pub struct FeeFilterAnnouncer {
+    sent_per_kb: Option<i64>,
+    checked_at: Option<Instant>,
+}
+
+impl FeeFilterAnnouncer {
+    /// Returns the filter to send, if one is due: always on first call, then at most once per
+    /// check interval and only when the value changed.
+    pub fn due(&mut self, mempool: &Mutex<Mempool>, policy: &MempoolPolicy) -> Option<i64> {
+        let now = Instant::now();
+        if let Some(checked_at) = self.checked_at {
+            if now.saturating_duration_since(checked_at)
+                < Duration::from_secs(FEE_FILTER_CHECK_SECS)
+            {
+                return None;
+            }
+        }
+        self.checked_at = Some(now);
+        let floor = mempool
+            .lock()
+            .map(|guard| guard.min_fee_to_enter())
+            .unwrap_or(0);
+        let filter = policy.min_relay_fee_per_kb.max(floor);
+        if self.sent_per_kb == Some(filter) {
+            return None;
+        }
+        self.sent_per_kb = Some(filter);
+        Some(filter)
+    }
+}
+
 pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     mempool_prevouts: &HashMap<OutPoint, MempoolPrevout>,
@@ -702,13 +990,9 @@ pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
     let now = now_secs();
     let now_i64 = i64::try_from(now).unwrap_or(i64::MAX);
 
-    if tx.expiry_height > 0
-        && network_upgrade_active(
-            next_height,
-            &chain_params.consensus.upgrades,
-            UpgradeIndex::Acadia,
-        )
-    {
+    let rules = consensus_rules(next_height, &chain_params.consensus);
+
+    if tx.expiry_height > 0 && rules.sapling_active {
         let next_height_u32 = u32::try_from(next_height).unwrap_or(0);
         let min_expiry = next_height_u32.saturating_add(TX_EXPIRING_SOON_THRESHOLD);
         if min_expiry > tx.expiry_height {
@@ -721,7 +1005,7 @@ pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
             ));
         }
     }
-    let branch_id = current_epoch_branch_id(next_height, &chain_params.consensus.upgrades);
+    let branch_id = rules.branch_id;
 
     let mut tx_flags = flags.clone();
     tx_flags.check_shielded = false;
@@ -777,7 +1061,7 @@ pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
         }
 
         let prevout = match chainstate
-            .utxo_entry(&input.prevout)
+            .utxo_entry(&input.prevout.txid(), input.prevout.index)
             .map_err(|err| MempoolError::new(MempoolErrorKind::Internal, err.to_string()))?
         {
             Some(entry) => {
@@ -830,7 +1114,7 @@ pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
 
         if prev_is_coinbase {
             let spend_height = i64::from(next_height).saturating_sub(i64::from(prev_height));
-            if spend_height < COINBASE_MATURITY as i64 {
+            if spend_height < i64::from(chain_params.consensus.coinbase_maturity) {
                 return Err(MempoolError::new(
                     MempoolErrorKind::InvalidTransaction,
                     "premature spend of coinbase",
@@ -890,8 +1174,17 @@ pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
         spent_outpoints.push(input.prevout.clone());
     }
 
+    let prev_script_refs: Vec<&[u8]> = prev_scripts.iter().map(Vec::as_slice).collect();
+    let sigops = count_sigops(&tx, BLOCK_SCRIPT_VERIFY_FLAGS, &prev_script_refs);
+
     if require_standard {
         enforce_standard_inputs(&tx, &prev_scripts, policy)?;
+        if sigops > policy.max_standard_tx_sigops as usize {
+            return Err(MempoolError::new(
+                MempoolErrorKind::NonStandard,
+                "bad-txns-too-many-sigops",
+            ));
+        }
     }
 
     if require_standard {
@@ -986,6 +1279,7 @@ pub fn build_mempool_entry<S: fluxd_storage::KeyValueStore>(
         priority_delta: 0.0,
         spent_outpoints,
         parents,
+        sigops,
     })
 }
 
@@ -994,6 +1288,7 @@ const DEFAULT_BLOCK_PRIORITY_SIZE: usize = (MAX_BLOCK_SIZE as usize) / 2;
 const FREE_TX_SIZE_LIMIT: usize = DEFAULT_BLOCK_PRIORITY_SIZE - 1000;
 const ASYNC_RPC_OPERATION_DEFAULT_MINERS_FEE: i64 = 10_000;
 const DEFAULT_MAX_ORPHANS: usize = 100;
+const DEFAULT_MAX_ORPHANS_PER_PEER: usize = 25;
 const DEFAULT_MAX_ORPHAN_BYTES: usize = 5 * 1024 * 1024;
 const DEFAULT_ORPHAN_TTL_SECS: u64 = 20 * 60;
 
@@ -1043,7 +1338,7 @@ pub fn process_orphans_after_accept<S: fluxd_storage::KeyValueStore>(
         }
 
         let orphans = match mempool.lock() {
-            Ok(mut guard) => guard.take_orphans_for_parent(&parent),
+            Ok(mut guard) => guard.process_orphans_for(&parent),
             Err(_) => return outcome,
         };
         if orphans.is_empty() {
@@ -1057,6 +1352,7 @@ pub fn process_orphans_after_accept<S: fluxd_storage::KeyValueStore>(
                 received: _,
                 missing_parents: _,
                 limit_free,
+                from_peer,
             } = orphan;
 
             let tx = match Transaction::consensus_decode(&orphan_raw) {
@@ -1083,7 +1379,13 @@ pub fn process_orphans_after_accept<S: fluxd_storage::KeyValueStore>(
                 Err(err) => {
                     if err.kind == MempoolErrorKind::MissingInput {
                         if let Ok(mut guard) = mempool.lock() {
-                            guard.store_orphan(txid, orphan_raw, err.missing_inputs, limit_free);
+                            guard.store_orphan(
+                                txid,
+                                orphan_raw,
+                                err.missing_inputs,
+                                limit_free,
+                                from_peer,
+                            );
                         }
                     }
                     continue;
@@ -1195,7 +1497,7 @@ fn enforce_standard_outputs(tx: &Transaction, policy: &MempoolPolicy) -> Result<
                     "witness-program",
                 ));
             }
-            ScriptType::Unknown => {
+            ScriptType::Witness | ScriptType::Unknown => {
                 return Err(MempoolError::new(
                     MempoolErrorKind::NonStandard,
                     "scriptpubkey",
@@ -1227,8 +1529,6 @@ fn enforce_standard_inputs(
     prev_scripts: &[Vec<u8>],
     policy: &MempoolPolicy,
 ) -> Result<(), MempoolError> {
-    let mut sigops: u32 = 0;
-
     for (input, prev_script) in tx.vin.iter().zip(prev_scripts.iter()) {
         let stack = parse_push_only_stack(&input.script_sig)
             .ok_or_else(|| MempoolError::new(MempoolErrorKind::NonStandard, "scriptsig"))?;
@@ -1264,7 +1564,7 @@ fn enforce_standard_inputs(
                         "p2sh-redeem-size",
                     ));
                 }
-                let redeem_sigops = count_sigops(redeem, true).ok_or_else(|| {
+                let redeem_sigops = script_sigops(redeem, true).ok_or_else(|| {
                     MempoolError::new(MempoolErrorKind::NonStandard, "p2sh-redeem")
                 })?;
                 if redeem_sigops > policy.max_p2sh_sigops {
@@ -1273,9 +1573,8 @@ fn enforce_standard_inputs(
                         "p2sh-sigops",
                     ));
                 }
-                sigops = sigops.saturating_add(redeem_sigops);
             }
-            ScriptType::P2Wpkh | ScriptType::P2Wsh | ScriptType::Unknown => {
+            ScriptType::P2Wpkh | ScriptType::P2Wsh | ScriptType::Witness | ScriptType::Unknown => {
                 return Err(MempoolError::new(
                     MempoolErrorKind::NonStandard,
                     "nonstandard-input",
@@ -1285,9 +1584,7 @@ fn enforce_standard_inputs(
     }
 
     for input in &tx.vin {
-        if let Some(value) = count_sigops(&input.script_sig, false) {
-            sigops = sigops.saturating_add(value);
-        } else {
+        if script_sigops(&input.script_sig, false).is_none() {
             return Err(MempoolError::new(
                 MempoolErrorKind::NonStandard,
                 "scriptsig-sigops",
@@ -1295,9 +1592,7 @@ fn enforce_standard_inputs(
         }
     }
     for output in &tx.vout {
-        if let Some(value) = count_sigops(&output.script_pubkey, false) {
-            sigops = sigops.saturating_add(value);
-        } else {
+        if script_sigops(&output.script_pubkey, false).is_none() {
             return Err(MempoolError::new(
                 MempoolErrorKind::NonStandard,
                 "scriptpubkey-sigops",
@@ -1305,13 +1600,6 @@ fn enforce_standard_inputs(
         }
     }
 
-    if sigops > policy.max_standard_tx_sigops {
-        return Err(MempoolError::new(
-            MempoolErrorKind::NonStandard,
-            "bad-txns-too-many-sigops",
-        ));
-    }
-
     Ok(())
 }
 
@@ -1575,7 +1863,7 @@ fn parse_push_only_stack(script: &[u8]) -> Option<Vec<Vec<u8>>> {
     Some(stack)
 }
 
-fn count_sigops(script: &[u8], accurate: bool) -> Option<u32> {
+fn script_sigops(script: &[u8], accurate: bool) -> Option<u32> {
     let mut cursor = 0usize;
     let mut last_opcode = 0u8;
     let mut count = 0u32;
@@ -1629,7 +1917,7 @@ fn count_sigops(script: &[u8], accurate: bool) -> Option<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use fluxd_primitives::transaction::{TxIn, TxOut};
+    use fluxd_primitives::transaction::{SpendDescription, TxIn, TxOut, GROTH_PROOF_SIZE};
 
     fn dummy_tx(vin: Vec<TxIn>, vout: Vec<TxOut>) -> Transaction {
         Transaction {
@@ -1647,6 +1935,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         }
     }
@@ -1681,6 +1970,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let child_entry = MempoolEntry {
             txid: child_txid,
@@ -1707,6 +1997,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: vec![parent_outpoint],
             parents: vec![parent_txid],
+            sigops: 0,
         };
 
         let mut mempool = Mempool::new(0);
@@ -1726,6 +2017,113 @@ mod tests {
         assert!(mempool.children.get(&parent_txid).is_none());
     }
 
+    #[test]
+    fn conflicts_with_reports_shared_outpoint_both_ways() {
+        let outpoint = OutPoint {
+            hash: [9u8; 32],
+            index: 0,
+        };
+        let spend = |marker: u8| {
+            let tx = dummy_tx(
+                vec![TxIn {
+                    prevout: outpoint.clone(),
+                    script_sig: Vec::new(),
+                    sequence: 0,
+                }],
+                vec![TxOut {
+                    value: 1,
+                    script_pubkey: vec![marker],
+                }],
+            );
+            MempoolEntry {
+                txid: tx.txid().expect("txid"),
+                tx,
+                raw: vec![0u8; 10],
+                time: 0,
+                height: 0,
+                fee: 0,
+                value_in: 0,
+                modified_size: 0,
+                priority: 0.0,
+                was_clear_at_entry: true,
+                fee_delta: 0,
+                priority_delta: 0.0,
+                spent_outpoints: vec![outpoint.clone()],
+                parents: Vec::new(),
+                sigops: 0,
+            }
+        };
+        let first = spend(1);
+        let second = spend(2);
+        let (first_txid, second_txid) = (first.txid, second.txid);
+        let (first_tx, second_tx) = (first.tx.clone(), second.tx.clone());
+
+        let mut mempool = Mempool::new(0);
+        mempool.insert(first).expect("insert first");
+        assert_eq!(mempool.conflicts_with(&second_tx), vec![first_txid]);
+        assert!(mempool.conflicts_with(&first_tx).is_empty());
+
+        mempool.remove(&first_txid).expect("remove first");
+        assert!(mempool.conflicts_with(&second_tx).is_empty());
+        mempool.insert(second).expect("insert second");
+        assert_eq!(mempool.conflicts_with(&first_tx), vec![second_txid]);
+    }
+
+    #[test]
+    fn shared_sapling_nullifier_conflicts_until_spender_leaves() {
+        let nullifier: Hash256 = [7u8; 32];
+        let entry = |txid: Hash256| {
+            let mut tx = dummy_tx(
+                Vec::new(),
+                vec![TxOut {
+                    value: 1,
+                    script_pubkey: vec![txid[0]],
+                }],
+            );
+            tx.shielded_spends = vec![SpendDescription {
+                cv: [0u8; 32],
+                anchor: [0u8; 32],
+                nullifier,
+                rk: [0u8; 32],
+                zkproof: [0u8; GROTH_PROOF_SIZE],
+                spend_auth_sig: [0u8; 64],
+            }];
+            MempoolEntry {
+                txid,
+                tx,
+                raw: vec![0u8; 10],
+                time: 0,
+                height: 0,
+                fee: 0,
+                value_in: 0,
+                modified_size: 0,
+                priority: 0.0,
+                was_clear_at_entry: true,
+                fee_delta: 0,
+                priority_delta: 0.0,
+                spent_outpoints: Vec::new(),
+                parents: Vec::new(),
+                sigops: 0,
+            }
+        };
+
+        let mut mempool = Mempool::new(0);
+        mempool.insert(entry([1u8; 32])).expect("insert first");
+        assert_eq!(
+            mempool.sapling_nullifier_spender(&nullifier),
+            Some([1u8; 32])
+        );
+
+        let err = mempool
+            .insert(entry([2u8; 32]))
+            .expect_err("shared nullifier");
+        assert_eq!(err.kind, MempoolErrorKind::ConflictingInput);
+
+        mempool.remove(&[1u8; 32]).expect("remove first");
+        assert_eq!(mempool.sapling_nullifier_spender(&nullifier), None);
+        mempool.insert(entry([2u8; 32])).expect("insert second");
+    }
+
     #[test]
     fn remove_with_descendants_removes_entire_subtree() {
         let parent_txid: Hash256 = [1u8; 32];
@@ -1756,6 +2154,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let child_entry = MempoolEntry {
             txid: child_txid,
@@ -1782,6 +2181,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: vec![parent_outpoint],
             parents: vec![parent_txid],
+            sigops: 0,
         };
 
         let mut mempool = Mempool::new(0);
@@ -1795,6 +2195,282 @@ mod tests {
         assert!(mempool.entries.is_empty());
         assert!(mempool.children.is_empty());
     }
+
+    #[test]
+    fn expire_evicts_aged_entries_and_descendants() {
+        let stale_txid: Hash256 = [1u8; 32];
+        let child_txid: Hash256 = [2u8; 32];
+        let fresh_txid: Hash256 = [3u8; 32];
+        let entry = |txid: Hash256, time: u64, vin: Vec<TxIn>, parents: Vec<Hash256>| {
+            let spent_outpoints = vin.iter().map(|input| input.prevout.clone()).collect();
+            MempoolEntry {
+                txid,
+                tx: dummy_tx(
+                    vin,
+                    vec![TxOut {
+                        value: 50,
+                        script_pubkey: vec![0x51],
+                    }],
+                ),
+                raw: vec![0u8; 10],
+                time,
+                height: 0,
+                fee: 0,
+                value_in: 0,
+                modified_size: 0,
+                priority: 0.0,
+                was_clear_at_entry: true,
+                fee_delta: 0,
+                priority_delta: 0.0,
+                spent_outpoints,
+                parents,
+                sigops: 0,
+            }
+        };
+        let spend_stale = vec![TxIn {
+            prevout: OutPoint {
+                hash: stale_txid,
+                index: 0,
+            },
+            script_sig: Vec::new(),
+            sequence: 0,
+        }];
+
+        let mut mempool = Mempool::new(0);
+        mempool
+            .insert(entry(stale_txid, 1_000, Vec::new(), Vec::new()))
+            .expect("insert stale");
+        mempool
+            .insert(entry(child_txid, 50_000, spend_stale, vec![stale_txid]))
+            .expect("insert child");
+        mempool
+            .insert(entry(fresh_txid, 50_000, Vec::new(), Vec::new()))
+            .expect("insert fresh");
+
+        let max_age = 336 * 60 * 60;
+        let outcome = mempool.expire(1_000 + max_age, max_age);
+        assert_eq!(outcome.evicted, 0);
+        assert_eq!(mempool.size(), 3);
+
+        let outcome = mempool.expire(1_001 + max_age, max_age);
+        assert_eq!(outcome.evicted, 2);
+        assert_eq!(outcome.evicted_bytes, 20);
+        let evicted: HashSet<Hash256> = outcome.evicted_txids.into_iter().collect();
+        assert!(evicted.contains(&stale_txid));
+        assert!(evicted.contains(&child_txid));
+        assert!(mempool.contains(&fresh_txid));
+        assert_eq!(mempool.size(), 1);
+    }
+
+    #[test]
+    fn full_mempool_raises_and_decays_min_fee() {
+        let entry = |seed: u8, fee: i64| MempoolEntry {
+            txid: [seed; 32],
+            tx: dummy_tx(Vec::new(), Vec::new()),
+            raw: vec![0u8; 10],
+            time: 0,
+            height: 0,
+            fee,
+            value_in: 0,
+            modified_size: 0,
+            priority: 0.0,
+            was_clear_at_entry: true,
+            fee_delta: 0,
+            priority_delta: 0.0,
+            spent_outpoints: Vec::new(),
+            parents: Vec::new(),
+            sigops: 0,
+        };
+        let now = 1_000_000;
+        let mut mempool = Mempool::new(30);
+        mempool.set_min_fee_increment_per_kb(100);
+        for (seed, fee) in [(1, 10), (2, 20), (3, 30)] {
+            mempool.insert_at(entry(seed, fee), now).expect("insert");
+        }
+        assert_eq!(mempool.min_fee_to_enter_at(now), 0);
+
+        // 1000 zat/KB entry evicted: the floor becomes its rate plus the increment.
+        let outcome = mempool.insert_at(entry(4, 40), now).expect("insert");
+        assert_eq!(outcome.evicted_txids, vec![[1u8; 32]]);
+        assert_eq!(mempool.min_fee_to_enter_at(now), 1_100);
+
+        let err = mempool.insert_at(entry(5, 10), now).unwrap_err();
+        assert_eq!(err.kind, MempoolErrorKind::InsufficientFee);
+        assert_eq!(mempool.size(), 3);
+
+        // Outbids the 2000 zat/KB entry, which is evicted and lifts the floor above its rate.
+        let outcome = mempool.insert_at(entry(6, 25), now).expect("insert");
+        assert_eq!(outcome.evicted_txids, vec![[2u8; 32]]);
+        assert!(mempool.contains(&[6u8; 32]));
+        assert_eq!(mempool.min_fee_to_enter_at(now), 2_100);
+
+        // No decay until a block connects; it then starts from the block's time.
+        assert_eq!(mempool.min_fee_to_enter_at(now + 24 * 60 * 60), 2_100);
+        let block_time = now + 60 * 60;
+        mempool.note_block_connected_at(block_time);
+        assert_eq!(mempool.min_fee_to_enter_at(block_time), 2_100);
+
+        // Full pool: twelve hour half-life. Empty pool: a quarter of that.
+        assert_eq!(
+            mempool.min_fee_to_enter_at(block_time + 12 * 60 * 60),
+            1_050
+        );
+        for seed in [3u8, 4, 6] {
+            mempool.remove(&[seed; 32]).expect("remove");
+        }
+        assert_eq!(mempool.min_fee_to_enter_at(block_time + 3 * 60 * 60), 1_050);
+        assert_eq!(mempool.min_fee_to_enter_at(block_time + 24 * 60 * 60), 0);
+        mempool
+            .insert_at(entry(5, 0), block_time + 24 * 60 * 60)
+            .expect("insert after decay");
+    }
+
+    #[test]
+    fn orphans_are_capped_per_peer_and_released_for_parent() {
+        let parent_txid: Hash256 = [9u8; 32];
+        let child_txid: Hash256 = [0xaau8; 32];
+        let noisy_peer: SocketAddr = "10.0.0.1:16125".parse().expect("addr");
+        let quiet_peer: SocketAddr = "10.0.0.2:16125".parse().expect("addr");
+        let spend = |parent: Hash256| {
+            vec![OutPoint {
+                hash: parent,
+                index: 0,
+            }]
+        };
+        // Tagged so neither the orphans nor their parents collide with `parent_txid`.
+        let noisy_txid = |index: usize| {
+            let mut txid = [0x11u8; 32];
+            txid[..8].copy_from_slice(&(index as u64).to_le_bytes());
+            txid
+        };
+        let noisy_parent = |index: usize| {
+            let mut txid = [0x22u8; 32];
+            txid[..8].copy_from_slice(&(index as u64).to_le_bytes());
+            txid
+        };
+        let entry = |txid: Hash256, vin: Vec<TxIn>, parents: Vec<Hash256>, now: u64| {
+            let spent_outpoints = vin.iter().map(|input| input.prevout.clone()).collect();
+            MempoolEntry {
+                txid,
+                tx: dummy_tx(
+                    vin,
+                    vec![TxOut {
+                        value: 50,
+                        script_pubkey: vec![0x51],
+                    }],
+                ),
+                raw: vec![0u8; 10],
+                time: now,
+                height: 0,
+                fee: 0,
+                value_in: 0,
+                modified_size: 0,
+                priority: 0.0,
+                was_clear_at_entry: true,
+                fee_delta: 0,
+                priority_delta: 0.0,
+                spent_outpoints,
+                parents,
+                sigops: 0,
+            }
+        };
+
+        let now = 1_000_000;
+        let mut mempool = Mempool::new(0);
+        let extra = 5;
+        for index in 0..(DEFAULT_MAX_ORPHANS_PER_PEER + extra) {
+            // One second apart, so the per-peer cap evicts strictly oldest first.
+            mempool.store_orphan_at(
+                noisy_txid(index),
+                vec![0u8; 10],
+                spend(noisy_parent(index)),
+                true,
+                Some(noisy_peer),
+                now + index as u64,
+            );
+        }
+        assert_eq!(mempool.orphan_count(), DEFAULT_MAX_ORPHANS_PER_PEER);
+        for index in 0..(DEFAULT_MAX_ORPHANS_PER_PEER + extra) {
+            assert_eq!(mempool.has_orphan(&noisy_txid(index)), index >= extra);
+        }
+
+        mempool.store_orphan_at(
+            child_txid,
+            vec![0u8; 10],
+            spend(parent_txid),
+            true,
+            Some(quiet_peer),
+            now,
+        );
+        assert!(mempool.has_orphan(&child_txid));
+
+        let later = now + 60;
+        mempool
+            .insert(entry(parent_txid, Vec::new(), Vec::new(), later))
+            .expect("insert parent");
+        let released = mempool.process_orphans_for(&parent_txid);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].txid, child_txid);
+        assert_eq!(released[0].from_peer, Some(quiet_peer));
+        assert!(!mempool.has_orphan(&child_txid));
+        assert_eq!(mempool.orphan_count(), DEFAULT_MAX_ORPHANS_PER_PEER);
+
+        let vin = spend(parent_txid)
+            .into_iter()
+            .map(|prevout| TxIn {
+                prevout,
+                script_sig: Vec::new(),
+                sequence: 0,
+            })
+            .collect();
+        mempool
+            .insert(entry(released[0].txid, vin, vec![parent_txid], later))
+            .expect("insert released orphan");
+        assert!(mempool.contains(&child_txid));
+        assert_eq!(mempool.spender(&spend(parent_txid)[0]), Some(child_txid));
+    }
+
+    #[test]
+    fn p2sh_redeem_sigops_are_capped_per_input() {
+        let policy = MempoolPolicy::standard(0, false);
+        let spend = |checksigs: usize| {
+            let redeem = vec![0xac; checksigs];
+            let mut script_sig = vec![redeem.len() as u8];
+            script_sig.extend_from_slice(&redeem);
+            let mut prev_script = vec![0xa9, 0x14];
+            prev_script.extend_from_slice(&fluxd_primitives::hash::hash160(&redeem));
+            prev_script.push(0x87);
+            let tx = dummy_tx(
+                vec![TxIn {
+                    prevout: OutPoint {
+                        hash: [1u8; 32],
+                        index: 0,
+                    },
+                    script_sig,
+                    sequence: u32::MAX,
+                }],
+                vec![TxOut {
+                    value: 1,
+                    script_pubkey: vec![0x51],
+                }],
+            );
+            (tx, prev_script)
+        };
+
+        let (tx, prev_script) = spend(15);
+        enforce_standard_inputs(&tx, std::slice::from_ref(&prev_script), &policy)
+            .expect("15 redeem sigops are standard");
+        assert_eq!(
+            count_sigops(&tx, BLOCK_SCRIPT_VERIFY_FLAGS, &[prev_script.as_slice()]),
+            15
+        );
+
+        let (tx, prev_script) = spend(16);
+        let err = enforce_standard_inputs(&tx, std::slice::from_ref(&prev_script), &policy)
+            .expect_err("16 redeem sigops are not");
+        assert_eq!(err.kind, MempoolErrorKind::NonStandard);
+        assert_eq!(err.message, "p2sh-sigops");
+    }
 }
 
 fn decode_op_n(opcode: u8) -> Option<u8> {
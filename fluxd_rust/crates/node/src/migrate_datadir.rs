@@ -0,0 +1,303 @@
+//! `--migrate-datadir <path>`: moves a stopped node's data dir and repoints `fluxd.datadir`.
+//!
+//! The source stays locked for the whole run, so a live node makes the migration fail before
+//! anything is touched. Every file is copied and synced, the copy is opened and its schema
+//! version and flatfile metadata are checked, pointer files that named the source are rewritten
+//! through a temp file + rename, and only then is the source removed. Any failure before that
+//! point leaves the source untouched.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{db_info, Backend, Config, DATADIR_POINTER_FILE_NAME, DATA_DIR_LOCK_FILE_NAME};
+
+#[derive(Debug, Default)]
+pub(crate) struct MigrationReport {
+    pub(crate) files: u64,
+    pub(crate) bytes: u64,
+    /// Pointer files rewritten to the new location.
+    pub(crate) pointers: Vec<PathBuf>,
+}
+
+pub(crate) fn run(config: &Config, target: &Path) -> Result<(), String> {
+    let report = migrate(config, target, &crate::datadir_pointer_candidates())?;
+    println!(
+        "migrated {} file(s), {} bytes from {} to {}",
+        report.files,
+        report.bytes,
+        config.data_dir.display(),
+        target.display()
+    );
+    if report.pointers.is_empty() {
+        println!(
+            "no {DATADIR_POINTER_FILE_NAME} pointed at the old data dir; start fluxd with --data-dir {}",
+            target.display()
+        );
+    }
+    for pointer in &report.pointers {
+        println!("updated {}", pointer.display());
+    }
+    Ok(())
+}
+
+pub(crate) fn migrate(
+    config: &Config,
+    target: &Path,
+    pointers: &[PathBuf],
+) -> Result<MigrationReport, String> {
+    if matches!(config.backend, Backend::Memory) {
+        return Err("the memory backend has no data dir to migrate".to_string());
+    }
+    let source = &config.data_dir;
+    if !source.is_dir() {
+        return Err(format!("data dir {} does not exist", source.display()));
+    }
+    let source_lock = crate::lock_data_dir(source)?;
+    let source_abs = fs::canonicalize(source)
+        .map_err(|err| format!("failed to resolve {}: {err}", source.display()))?;
+    let target_abs = absolute_path(target)?;
+    if target_abs.starts_with(&source_abs) || source_abs.starts_with(&target_abs) {
+        return Err(format!(
+            "target {} must not be inside the data dir or contain it",
+            target.display()
+        ));
+    }
+    if let Ok(mut entries) = fs::read_dir(&target_abs) {
+        if entries.next().is_some() {
+            return Err(format!("target {} is not empty", target.display()));
+        }
+    }
+
+    fs::create_dir_all(&target_abs)
+        .map_err(|err| format!("failed to create {}: {err}", target.display()))?;
+    let mut report = MigrationReport::default();
+    copy_dir(&source_abs, &target_abs, true, &mut report)?;
+    let target_lock = crate::lock_data_dir(&target_abs)?;
+    verify_copy(config, &target_abs)?;
+
+    for pointer in pointers {
+        let Some(dir) = crate::read_datadir_pointer(pointer) else {
+            continue;
+        };
+        if fs::canonicalize(&dir).ok().as_ref() != Some(&source_abs) {
+            continue;
+        }
+        write_pointer(pointer, &target_abs)?;
+        report.pointers.push(pointer.clone());
+    }
+
+    drop(source_lock);
+    fs::remove_dir_all(&source_abs).map_err(|err| {
+        format!(
+            "copy verified but failed to remove {}: {err}",
+            source.display()
+        )
+    })?;
+    drop(target_lock);
+    Ok(report)
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf, String> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|err| format!("failed to read current dir: {err}"))?
+            .join(path)
+    };
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(format!("invalid target {}", path.display()));
+    };
+    fs::create_dir_all(parent)
+        .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+    let parent = fs::canonicalize(parent)
+        .map_err(|err| format!("failed to resolve {}: {err}", parent.display()))?;
+    Ok(parent.join(name))
+}
+
+fn copy_dir(
+    from: &Path,
+    to: &Path,
+    top_level: bool,
+    report: &mut MigrationReport,
+) -> Result<(), String> {
+    let entries =
+        fs::read_dir(from).map_err(|err| format!("failed to read {}: {err}", from.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| format!("failed to read {}: {err}", from.display()))?;
+        if top_level && entry.file_name() == DATA_DIR_LOCK_FILE_NAME {
+            continue;
+        }
+        let src = entry.path();
+        let dst = to.join(entry.file_name());
+        let metadata =
+            fs::metadata(&src).map_err(|err| format!("failed to stat {}: {err}", src.display()))?;
+        if metadata.is_dir() {
+            fs::create_dir(&dst)
+                .map_err(|err| format!("failed to create {}: {err}", dst.display()))?;
+            copy_dir(&src, &dst, false, report)?;
+            continue;
+        }
+        let copied = fs::copy(&src, &dst)
+            .map_err(|err| format!("failed to copy {}: {err}", src.display()))?;
+        if copied != metadata.len() {
+            return Err(format!(
+                "short copy of {} ({copied} of {} bytes)",
+                src.display(),
+                metadata.len()
+            ));
+        }
+        File::open(&dst)
+            .and_then(|file| file.sync_all())
+            .map_err(|err| format!("failed to sync {}: {err}", dst.display()))?;
+        report.files += 1;
+        report.bytes += copied;
+    }
+    Ok(())
+}
+
+fn verify_copy(config: &Config, data_dir: &Path) -> Result<(), String> {
+    let store = crate::open_store(config.backend, &data_dir.join("db"), config)
+        .map_err(|err| format!("copied database does not open: {err}"))?;
+    match crate::meta_u32(&store, crate::DB_SCHEMA_VERSION_KEY)? {
+        Some(version) if version == crate::DB_SCHEMA_VERSION => {}
+        Some(version) => {
+            return Err(format!(
+                "copied database has schema version {version}, expected {}",
+                crate::DB_SCHEMA_VERSION
+            ))
+        }
+        None if crate::store_has_any_data(&store)? => {
+            return Err("copied database is missing its schema version".to_string())
+        }
+        None => {}
+    }
+    let (ok, issues, _) = db_info::flatfile_integrity(&store, &data_dir.join("blocks"))?;
+    if !ok {
+        return Err(format!(
+            "copied flatfiles do not match the database: {}",
+            issues.join("; ")
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces `pointer` with one naming `data_dir`, via a synced temp file and a rename.
+fn write_pointer(pointer: &Path, data_dir: &Path) -> Result<(), String> {
+    let mut tmp = pointer.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file =
+        File::create(&tmp).map_err(|err| format!("failed to create {}: {err}", tmp.display()))?;
+    writeln!(file, "{}", data_dir.display())
+        .and_then(|_| file.sync_all())
+        .map_err(|err| format!("failed to write {}: {err}", tmp.display()))?;
+    fs::rename(&tmp, pointer)
+        .map_err(|err| format!("failed to replace {}: {err}", pointer.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+
+    struct TempDirGuard {
+        path: PathBuf,
+    }
+
+    impl Drop for TempDirGuard {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn temp_dir(prefix: &str) -> TempDirGuard {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        TempDirGuard {
+            path: std::env::temp_dir().join(format!("{prefix}-{}-{nanos}", std::process::id())),
+        }
+    }
+
+    fn migrate_config(data_dir: &Path) -> Config {
+        Config::from_args(
+            [
+                "--network",
+                "regtest",
+                "--data-dir",
+                data_dir.to_str().expect("utf8 path"),
+            ]
+            .iter()
+            .map(|arg| arg.to_string()),
+        )
+        .expect("config")
+    }
+
+    #[test]
+    fn moves_data_dir_and_rewrites_pointer() {
+        let dir = temp_dir("fluxd-migrate");
+        let source = dir.path.join("old");
+        let target = dir.path.join("new");
+        fs::create_dir_all(&source).expect("source");
+        let config = migrate_config(&source);
+        {
+            let store =
+                crate::open_store(config.backend, &source.join("db"), &config).expect("store");
+            crate::ensure_db_schema_version(&store).expect("schema");
+            store.persist().expect("persist");
+        }
+        fs::write(source.join("peers.dat"), b"peers").expect("peers");
+        let pointer = dir.path.join(DATADIR_POINTER_FILE_NAME);
+        fs::write(&pointer, format!("{}\n", source.display())).expect("pointer");
+        let unrelated = dir.path.join("other.datadir");
+        fs::write(&unrelated, "/elsewhere\n").expect("other pointer");
+
+        let report =
+            migrate(&config, &target, &[pointer.clone(), unrelated.clone()]).expect("migrate");
+        assert!(!source.exists());
+        assert_eq!(
+            fs::read(target.join("peers.dat")).expect("peers"),
+            b"peers".to_vec()
+        );
+        assert!(report.files >= 2);
+        assert_eq!(report.pointers, vec![pointer.clone()]);
+        assert_eq!(
+            crate::read_datadir_pointer(&pointer).map(|path| fs::canonicalize(path).unwrap()),
+            Some(fs::canonicalize(&target).unwrap())
+        );
+        assert_eq!(
+            fs::read_to_string(&unrelated).expect("other pointer"),
+            "/elsewhere\n"
+        );
+    }
+
+    #[test]
+    fn refuses_locked_source_and_non_empty_target() {
+        let dir = temp_dir("fluxd-migrate-refuse");
+        let source = dir.path.join("old");
+        let target = dir.path.join("new");
+        fs::create_dir_all(&source).expect("source");
+        fs::write(source.join("peers.dat"), b"peers").expect("peers");
+        let config = migrate_config(&source);
+
+        {
+            let _held = crate::lock_data_dir(&source).expect("lock");
+            let err = migrate(&config, &target, &[]).expect_err("locked source");
+            assert!(err.contains("already locked"), "{err}");
+            assert!(!target.exists());
+        }
+
+        fs::create_dir_all(&target).expect("target");
+        fs::write(target.join("stray"), b"x").expect("stray");
+        let err = migrate(&config, &target, &[]).expect_err("non-empty target");
+        assert!(err.contains("not empty"), "{err}");
+
+        let err = migrate(&config, &source.join("nested"), &[]).expect_err("nested target");
+        assert!(err.contains("must not be inside"), "{err}");
+        assert!(source.join("peers.dat").exists());
+    }
+}
@@ -0,0 +1,417 @@
+//! Push notifications for new blocks and mempool transactions.
+//!
+//! Subscribers connect over TCP and receive one JSON object per line:
+//! `{"type":"hashblock","hash":..,"height":..}`, `{"type":"rawblock",..,"hex":..}`,
+//! `{"type":"hashtx","hash":..}` and `{"type":"rawtx",..,"hex":..}`. Publishing never
+//! blocks the node: a subscriber that falls behind the queue loses the oldest messages
+//! and is told how many with `{"type":"dropped","count":n}`.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use fluxd_chainstate::index::ChainTip;
+use fluxd_chainstate::state::ChainState;
+use fluxd_consensus::Hash256;
+use fluxd_primitives::hash::hash256_to_hex;
+use fluxd_primitives::ids::BlockHash;
+use fluxd_storage::KeyValueStore;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+use crate::mempool::Mempool;
+
+const NOTIFY_QUEUE: usize = 1024;
+const NOTIFY_TIP_POLL_MS: u64 = 250;
+const NOTIFY_MAX_CATCHUP_BLOCKS: i32 = 100;
+const NOTIFY_WRITE_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotifyTopic {
+    HashBlock,
+    HashTx,
+    RawBlock,
+    RawTx,
+}
+
+impl NotifyTopic {
+    pub const ALL: [NotifyTopic; 4] = [
+        NotifyTopic::HashBlock,
+        NotifyTopic::HashTx,
+        NotifyTopic::RawBlock,
+        NotifyTopic::RawTx,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotifyTopic::HashBlock => "hashblock",
+            NotifyTopic::HashTx => "hashtx",
+            NotifyTopic::RawBlock => "rawblock",
+            NotifyTopic::RawTx => "rawtx",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|topic| topic.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+/// Set of topics a publisher emits. Defaults to the hash-only topics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotifyTopics {
+    mask: u8,
+}
+
+impl Default for NotifyTopics {
+    fn default() -> Self {
+        let mut topics = Self { mask: 0 };
+        topics.insert(NotifyTopic::HashBlock);
+        topics.insert(NotifyTopic::HashTx);
+        topics
+    }
+}
+
+impl NotifyTopics {
+    /// Parses a comma-separated topic list such as `hashblock,rawtx`.
+    pub fn parse_list(value: &str) -> Result<Self, String> {
+        let mut topics = Self { mask: 0 };
+        for raw in value.split(',').filter(|raw| !raw.trim().is_empty()) {
+            let topic = NotifyTopic::parse(raw).ok_or_else(|| {
+                format!(
+                    "unknown notify topic '{}' (expected hashblock, hashtx, rawblock or rawtx)",
+                    raw.trim()
+                )
+            })?;
+            topics.insert(topic);
+        }
+        if topics.mask == 0 {
+            return Err("notify topic list is empty".to_string());
+        }
+        Ok(topics)
+    }
+
+    pub fn insert(&mut self, topic: NotifyTopic) {
+        self.mask |= 1 << topic as u8;
+    }
+
+    pub fn contains(&self, topic: NotifyTopic) -> bool {
+        self.mask & (1 << topic as u8) != 0
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        NotifyTopic::ALL
+            .into_iter()
+            .filter(|topic| self.contains(*topic))
+            .map(NotifyTopic::as_str)
+            .collect()
+    }
+}
+
+pub struct NotifyPublisher {
+    topics: NotifyTopics,
+    sender: broadcast::Sender<Arc<str>>,
+}
+
+impl NotifyPublisher {
+    pub fn new(topics: NotifyTopics) -> Self {
+        let (sender, _) = broadcast::channel(NOTIFY_QUEUE);
+        Self { topics, sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<str>> {
+        self.sender.subscribe()
+    }
+
+    /// True when `topic` is enabled and someone is listening, so callers can skip
+    /// reading raw block or transaction bytes nobody will receive.
+    pub fn wants(&self, topic: NotifyTopic) -> bool {
+        self.topics.contains(topic) && self.sender.receiver_count() > 0
+    }
+
+    pub fn publish_block(&self, hash: &Hash256, height: i32, raw: Option<&[u8]>) {
+        let hash = hash256_to_hex(hash);
+        if self.wants(NotifyTopic::HashBlock) {
+            self.send(json!({ "type": "hashblock", "hash": hash, "height": height }));
+        }
+        if let (true, Some(raw)) = (self.wants(NotifyTopic::RawBlock), raw) {
+            self.send(json!({
+                "type": "rawblock",
+                "hash": hash,
+                "height": height,
+                "hex": crate::hex_encode(raw),
+            }));
+        }
+    }
+
+    /// Tells block subscribers that heights `from..=to` were not published, so they can
+    /// backfill them over RPC.
+    pub fn publish_skipped_blocks(&self, from: i32, to: i32) {
+        if self.wants(NotifyTopic::HashBlock) || self.wants(NotifyTopic::RawBlock) {
+            self.send(json!({ "type": "skippedblocks", "from": from, "to": to }));
+        }
+    }
+
+    pub fn publish_tx(&self, txid: &Hash256, raw: Option<&[u8]>) {
+        let hash = hash256_to_hex(txid);
+        if self.wants(NotifyTopic::HashTx) {
+            self.send(json!({ "type": "hashtx", "hash": hash }));
+        }
+        if let (true, Some(raw)) = (self.wants(NotifyTopic::RawTx), raw) {
+            self.send(json!({ "type": "rawtx", "hash": hash, "hex": crate::hex_encode(raw) }));
+        }
+    }
+
+    fn send(&self, message: serde_json::Value) {
+        let _ = self.sender.send(Arc::from(message.to_string()));
+    }
+}
+
+pub async fn bind_notify(bind_addr: SocketAddr) -> Result<TcpListener, String> {
+    TcpListener::bind(bind_addr)
+        .await
+        .map_err(|err| format!("failed to bind notify listener {bind_addr}: {err}"))
+}
+
+pub async fn serve_notifications(
+    listener: TcpListener,
+    publisher: Arc<NotifyPublisher>,
+) -> Result<(), String> {
+    if let Ok(addr) = listener.local_addr() {
+        log_info!(
+            "Notifications listening on {addr} ({})",
+            publisher.topics.names().join(",")
+        );
+    }
+    loop {
+        let (stream, remote_addr) = listener
+            .accept()
+            .await
+            .map_err(|err| format!("notify accept failed: {err}"))?;
+        let receiver = publisher.subscribe();
+        tokio::spawn(async move {
+            if let Err(err) = stream_notifications(stream, receiver).await {
+                log_debug!("notify subscriber {remote_addr} disconnected: {err}");
+            }
+        });
+    }
+}
+
+async fn stream_notifications(
+    mut stream: TcpStream,
+    mut receiver: broadcast::Receiver<Arc<str>>,
+) -> Result<(), String> {
+    let _ = stream.set_nodelay(true);
+    loop {
+        let line = match receiver.recv().await {
+            Ok(message) => format!("{message}\n"),
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                format!("{}\n", json!({ "type": "dropped", "count": count }))
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        tokio::time::timeout(
+            Duration::from_secs(NOTIFY_WRITE_TIMEOUT_SECS),
+            stream.write_all(line.as_bytes()),
+        )
+        .await
+        .map_err(|_| "write timed out".to_string())?
+        .map_err(|err| err.to_string())?;
+    }
+}
+
+/// Polls the active tip and publishes every newly connected block in height order.
+/// After a reorg the blocks from the fork point upwards are published again; at most
+/// `NOTIFY_MAX_CATCHUP_BLOCKS` are sent per poll so initial sync does not flood
+/// subscribers; older heights are skipped and announced as one `skippedblocks` message.
+pub async fn notify_tip_loop<S: KeyValueStore>(
+    chainstate: Arc<ChainState<S>>,
+    publisher: Arc<NotifyPublisher>,
+) {
+    let mut last = chainstate.best_block().ok().flatten();
+    let mut interval = tokio::time::interval(Duration::from_millis(NOTIFY_TIP_POLL_MS));
+    loop {
+        interval.tick().await;
+        let tip = match chainstate.best_block() {
+            Ok(Some(tip)) => tip,
+            Ok(None) => continue,
+            Err(err) => {
+                log_warn!("notify tip poll failed: {err}");
+                continue;
+            }
+        };
+        if last.as_ref().is_some_and(|prev| prev.hash == tip.hash) {
+            continue;
+        }
+        if let Err(err) = publish_new_blocks(&chainstate, &publisher, last.as_ref(), &tip) {
+            log_warn!("notify block publish failed: {err}");
+        }
+        last = Some(tip);
+    }
+}
+
+fn publish_new_blocks<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    publisher: &NotifyPublisher,
+    last: Option<&ChainTip>,
+    tip: &ChainTip,
+) -> Result<(), String> {
+    let wanted = match last {
+        Some(prev) => fork_height(chainstate, prev)? + 1,
+        None => tip.height,
+    };
+    let first = wanted
+        .max(tip.height - NOTIFY_MAX_CATCHUP_BLOCKS + 1)
+        .max(0);
+    if first > wanted.max(0) {
+        log_info!(
+            "Notifications skipped blocks {}..={} (catch-up is capped at {} blocks)",
+            wanted.max(0),
+            first - 1,
+            NOTIFY_MAX_CATCHUP_BLOCKS
+        );
+        publisher.publish_skipped_blocks(wanted.max(0), first - 1);
+    }
+    for height in first..=tip.height {
+        let Some(hash) = chainstate
+            .height_hash(height)
+            .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
+        else {
+            break;
+        };
+        let raw = if publisher.wants(NotifyTopic::RawBlock) {
+            match chainstate
+                .block_location(&hash)
+                .map_err(|err| err.to_string())?
+            {
+                Some(location) => Some(
+                    chainstate
+                        .read_block(location)
+                        .map_err(|err| err.to_string())?,
+                ),
+                None => None,
+            }
+        } else {
+            None
+        };
+        publisher.publish_block(&hash, height, raw.as_deref());
+    }
+    Ok(())
+}
+
+/// Height of the last block of `prev`'s chain that is still on the active chain.
+fn fork_height<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    prev: &ChainTip,
+) -> Result<i32, String> {
+    let mut hash = prev.hash;
+    let mut height = prev.height;
+    for _ in 0..NOTIFY_MAX_CATCHUP_BLOCKS {
+        if height < 0
+            || chainstate
+                .height_hash(height)
+                .map_err(|err| err.to_string())?
+                == Some(BlockHash::from_internal(hash))
+        {
+            return Ok(height);
+        }
+        let Some(entry) = chainstate
+            .header_entry(&hash)
+            .map_err(|err| err.to_string())?
+        else {
+            break;
+        };
+        hash = entry.prev_hash;
+        height -= 1;
+    }
+    Ok(height.min(prev.height - NOTIFY_MAX_CATCHUP_BLOCKS))
+}
+
+/// Publishes every transaction accepted into the mempool, via RPC or relay.
+pub async fn notify_tx_loop(
+    mut tx_announce: broadcast::Receiver<Hash256>,
+    mempool: Arc<Mutex<Mempool>>,
+    publisher: Arc<NotifyPublisher>,
+) {
+    loop {
+        let txid = match tx_announce.recv().await {
+            Ok(txid) => txid,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                log_debug!("notify skipped {count} mempool announcement(s)");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let raw = if publisher.wants(NotifyTopic::RawTx) {
+            mempool
+                .lock()
+                .ok()
+                .and_then(|guard| guard.get(&txid).map(|entry| entry.raw.clone()))
+        } else {
+            None
+        };
+        publisher.publish_tx(&txid, raw.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_list_parses_and_rejects_unknown_topics() {
+        let topics = NotifyTopics::parse_list("rawtx, HashBlock").expect("topics");
+        assert!(topics.contains(NotifyTopic::HashBlock));
+        assert!(topics.contains(NotifyTopic::RawTx));
+        assert!(!topics.contains(NotifyTopic::HashTx));
+        assert_eq!(topics.names(), vec!["hashblock", "rawtx"]);
+        assert_eq!(NotifyTopics::default().names(), vec!["hashblock", "hashtx"]);
+        assert!(NotifyTopics::parse_list("hashblock,blocks").is_err());
+        assert!(NotifyTopics::parse_list(",").is_err());
+    }
+
+    #[test]
+    fn publisher_emits_json_lines_and_reports_lag() {
+        let publisher = NotifyPublisher::new(NotifyTopics::parse_list("hashtx,rawtx").unwrap());
+        assert!(!publisher.wants(NotifyTopic::HashTx));
+        let mut receiver = publisher.subscribe();
+        assert!(!publisher.wants(NotifyTopic::HashBlock));
+
+        publisher.publish_block(&[0x11; 32], 5, None);
+        publisher.publish_skipped_blocks(1, 4);
+        publisher.publish_tx(&[0x22; 32], Some(&[0xab, 0xcd]));
+        let hashtx: serde_json::Value =
+            serde_json::from_str(&receiver.try_recv().expect("hashtx")).expect("json");
+        assert_eq!(hashtx["type"], "hashtx");
+        assert_eq!(hashtx["hash"], hash256_to_hex(&[0x22; 32]));
+        let rawtx: serde_json::Value =
+            serde_json::from_str(&receiver.try_recv().expect("rawtx")).expect("json");
+        assert_eq!(rawtx["type"], "rawtx");
+        assert_eq!(rawtx["hex"], "abcd");
+        assert!(receiver.try_recv().is_err());
+
+        for _ in 0..NOTIFY_QUEUE + 3 {
+            publisher.publish_tx(&[0x33; 32], None);
+        }
+        assert!(matches!(
+            receiver.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(3))
+        ));
+    }
+
+    #[test]
+    fn skipped_blocks_are_announced_to_block_subscribers() {
+        let publisher = NotifyPublisher::new(NotifyTopics::default());
+        let mut receiver = publisher.subscribe();
+        publisher.publish_skipped_blocks(10, 109);
+        let skipped: serde_json::Value =
+            serde_json::from_str(&receiver.try_recv().expect("skippedblocks")).expect("json");
+        assert_eq!(skipped["type"], "skippedblocks");
+        assert_eq!(skipped["from"], 10);
+        assert_eq!(skipped["to"], 109);
+    }
+}
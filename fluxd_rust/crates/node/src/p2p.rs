@@ -1,8 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use fluxd_consensus::constants::PROTOCOL_VERSION;
 use fluxd_primitives::block::BlockHeader;
@@ -15,19 +15,31 @@ use tokio::time::{timeout, Duration};
 const MAX_PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
 const MAX_HEADERS_RESULTS: usize = 160;
 const MAX_ADDR_RESULTS: usize = 1000;
+const ADDR_GOSSIP_WINDOW_SECS: u64 = 60;
+const MAX_ADDR_MESSAGES_PER_WINDOW: u32 = 10;
 const MAX_INV_RESULTS: usize = 50_000;
-const NODE_NETWORK: u64 = 1;
+/// Service bit for peers that can serve the full block chain (not pruned).
+pub const NODE_NETWORK: u64 = 1;
 pub const MSG_TX: u32 = 1;
 pub const MSG_BLOCK: u32 = 2;
 const SEND_TIMEOUT_SECS: u64 = 10;
 const HANDSHAKE_READ_TIMEOUT_SECS: u64 = 30;
+const PING_INTERVAL_SECS: u64 = 60;
+// Below the 120s peer read timeouts so an unresponsive peer is caught by the ping first.
+const PING_TIMEOUT_SECS: u64 = 90;
 const USER_AGENT: &str = concat!("/fluxd-rust:", env!("CARGO_PKG_VERSION"), "/");
+/// Length of the rolling `--maxuploadtarget` window.
+pub const UPLOAD_TARGET_TIMEFRAME_SECS: u64 = 24 * 60 * 60;
+const UPLOAD_WINDOW_BUCKET_SECS: u64 = 60 * 60;
+const UPLOAD_WINDOW_BUCKETS: u64 = UPLOAD_TARGET_TIMEFRAME_SECS / UPLOAD_WINDOW_BUCKET_SECS;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum PeerKind {
     Block,
     Header,
     Relay,
+    /// Outbound peer used only for block announcements; no tx relay or addr gossip.
+    BlockRelayOnly,
 }
 
 #[derive(Clone, Debug)]
@@ -44,6 +56,8 @@ pub struct PeerInfoSnapshot {
     pub last_recv: SystemTime,
     pub bytes_sent: u64,
     pub bytes_recv: u64,
+    pub last_ping_ms: Option<u64>,
+    pub min_ping_ms: Option<u64>,
 }
 
 #[derive(Clone, Debug)]
@@ -60,6 +74,8 @@ struct PeerEntry {
     last_recv: SystemTime,
     bytes_sent: u64,
     bytes_recv: u64,
+    last_ping_ms: Option<u64>,
+    min_ping_ms: Option<u64>,
 }
 
 #[derive(Debug, Default)]
@@ -67,6 +83,7 @@ pub struct PeerRegistry {
     next_id: AtomicU64,
     peers: Mutex<HashMap<u64, PeerEntry>>,
     disconnect_requests: Mutex<HashSet<SocketAddr>>,
+    local_addr: Mutex<Option<SocketAddr>>,
 }
 
 impl PeerRegistry {
@@ -85,6 +102,8 @@ impl PeerRegistry {
             last_recv: now,
             bytes_sent: 0,
             bytes_recv: 0,
+            last_ping_ms: None,
+            min_ping_ms: None,
         };
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
         if let Ok(mut peers) = self.peers.lock() {
@@ -106,6 +125,17 @@ impl PeerRegistry {
             .unwrap_or(false)
     }
 
+    /// Sets the address advertised to peers in `version` and `addr` messages.
+    pub fn set_local_addr(&self, addr: Option<SocketAddr>) {
+        if let Ok(mut local) = self.local_addr.lock() {
+            *local = addr;
+        }
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr.lock().ok().and_then(|local| *local)
+    }
+
     pub fn register(&self, addr: SocketAddr, kind: PeerKind) -> u64 {
         self.register_internal(addr, kind, false)
     }
@@ -152,6 +182,16 @@ impl PeerRegistry {
         }
     }
 
+    pub fn note_ping(&self, id: u64, rtt: Duration) {
+        let ms = rtt.as_millis().min(u128::from(u64::MAX)) as u64;
+        if let Ok(mut peers) = self.peers.lock() {
+            if let Some(entry) = peers.get_mut(&id) {
+                entry.last_ping_ms = Some(ms);
+                entry.min_ping_ms = Some(entry.min_ping_ms.map_or(ms, |min| min.min(ms)));
+            }
+        }
+    }
+
     pub fn remove(&self, id: u64) {
         let (addr, still_connected) = match self.peers.lock() {
             Ok(mut peers) => {
@@ -176,6 +216,17 @@ impl PeerRegistry {
         self.peers.lock().map(|peers| peers.len()).unwrap_or(0)
     }
 
+    /// Returns `(inbound, outbound)` connection counts.
+    pub fn direction_counts(&self) -> (usize, usize) {
+        self.peers
+            .lock()
+            .map(|peers| {
+                let inbound = peers.values().filter(|peer| peer.inbound).count();
+                (inbound, peers.len() - inbound)
+            })
+            .unwrap_or((0, 0))
+    }
+
     pub fn snapshot(&self) -> Vec<PeerInfoSnapshot> {
         let peers = match self.peers.lock() {
             Ok(peers) => peers,
@@ -197,6 +248,8 @@ impl PeerRegistry {
                 last_recv: entry.last_recv,
                 bytes_sent: entry.bytes_sent,
                 bytes_recv: entry.bytes_recv,
+                last_ping_ms: entry.last_ping_ms,
+                min_ping_ms: entry.min_ping_ms,
             })
             .collect()
     }
@@ -209,11 +262,74 @@ pub struct NetTotalsSnapshot {
     pub connections: usize,
 }
 
+#[derive(Clone, Debug)]
+pub struct UploadTargetSnapshot {
+    /// Daily budget in bytes; 0 means unlimited.
+    pub target: u64,
+    /// Bytes sent during the last 24 hours.
+    pub bytes_sent: u64,
+    pub target_reached: bool,
+    pub bytes_left: u64,
+    /// Seconds until the oldest counted hour leaves the window and frees budget.
+    pub secs_until_release: u64,
+}
+
+/// Bytes sent per hour over the last `UPLOAD_TARGET_TIMEFRAME_SECS`.
+#[derive(Debug, Default)]
+struct UploadWindow {
+    buckets: VecDeque<(u64, u64)>,
+}
+
+impl UploadWindow {
+    fn prune(&mut self, now_secs: u64) {
+        let current = now_secs / UPLOAD_WINDOW_BUCKET_SECS;
+        while let Some(&(bucket, _)) = self.buckets.front() {
+            if bucket.saturating_add(UPLOAD_WINDOW_BUCKETS) > current {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+
+    fn record(&mut self, bytes: u64, now_secs: u64) {
+        self.prune(now_secs);
+        let bucket = now_secs / UPLOAD_WINDOW_BUCKET_SECS;
+        match self.buckets.back_mut() {
+            // A clock step backwards lands in the newest bucket rather than reordering.
+            Some((last, total)) if *last >= bucket => *total = total.saturating_add(bytes),
+            _ => self.buckets.push_back((bucket, bytes)),
+        }
+    }
+
+    fn snapshot(&mut self, target: u64, now_secs: u64) -> UploadTargetSnapshot {
+        self.prune(now_secs);
+        let bytes_sent = self
+            .buckets
+            .iter()
+            .fold(0u64, |sum, (_, bytes)| sum.saturating_add(*bytes));
+        let secs_until_release = self.buckets.front().map_or(0, |(bucket, _)| {
+            bucket
+                .saturating_add(UPLOAD_WINDOW_BUCKETS)
+                .saturating_mul(UPLOAD_WINDOW_BUCKET_SECS)
+                .saturating_sub(now_secs)
+        });
+        UploadTargetSnapshot {
+            target,
+            bytes_sent,
+            target_reached: target > 0 && bytes_sent >= target,
+            bytes_left: target.saturating_sub(bytes_sent),
+            secs_until_release,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct NetTotals {
     bytes_recv: AtomicU64,
     bytes_sent: AtomicU64,
     connections: AtomicUsize,
+    max_upload_target: AtomicU64,
+    upload_window: Mutex<UploadWindow>,
 }
 
 impl NetTotals {
@@ -223,6 +339,34 @@ impl NetTotals {
 
     pub fn add_sent(&self, bytes: usize) {
         self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        if let Ok(mut window) = self.upload_window.lock() {
+            window.record(bytes as u64, unix_now_secs());
+        }
+    }
+
+    /// Sets the daily upload budget in bytes (0 disables it).
+    pub fn set_max_upload_target(&self, bytes_per_day: u64) {
+        self.max_upload_target
+            .store(bytes_per_day, Ordering::Relaxed);
+    }
+
+    pub fn upload_target(&self) -> UploadTargetSnapshot {
+        let target = self.max_upload_target.load(Ordering::Relaxed);
+        match self.upload_window.lock() {
+            Ok(mut window) => window.snapshot(target, unix_now_secs()),
+            Err(_) => UploadTargetSnapshot {
+                target,
+                bytes_sent: 0,
+                target_reached: false,
+                bytes_left: target,
+                secs_until_release: 0,
+            },
+        }
+    }
+
+    /// Whether the daily budget is spent, so only recent blocks should still be served.
+    pub fn upload_target_reached(&self) -> bool {
+        self.max_upload_target.load(Ordering::Relaxed) > 0 && self.upload_target().target_reached
     }
 
     pub fn inc_connections(&self) {
@@ -246,6 +390,58 @@ impl NetTotals {
     }
 }
 
+fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|value| value.as_secs())
+        .unwrap_or(0)
+}
+
+/// Keepalive bookkeeping for a single connection: at most one ping is outstanding at a time.
+#[derive(Debug, Default)]
+struct PingState {
+    pending: Option<(u64, Instant)>,
+    last_sent: Option<Instant>,
+}
+
+impl PingState {
+    fn due(&self, now: Instant) -> Result<bool, String> {
+        if let Some((_, sent)) = self.pending {
+            if now.duration_since(sent) >= Duration::from_secs(PING_TIMEOUT_SECS) {
+                return Err("peer ping timed out".to_string());
+            }
+            return Ok(false);
+        }
+        Ok(match self.last_sent {
+            Some(last) => now.duration_since(last) >= Duration::from_secs(PING_INTERVAL_SECS),
+            None => true,
+        })
+    }
+
+    /// When `due` next needs checking: the outstanding ping's timeout, or the next send.
+    fn deadline(&self, now: Instant) -> Instant {
+        match (self.pending, self.last_sent) {
+            (Some((_, sent)), _) => sent + Duration::from_secs(PING_TIMEOUT_SECS),
+            (None, Some(last)) => last + Duration::from_secs(PING_INTERVAL_SECS),
+            (None, None) => now,
+        }
+    }
+
+    fn start(&mut self, nonce: u64, now: Instant) {
+        self.pending = Some((nonce, now));
+        self.last_sent = Some(now);
+    }
+
+    fn complete(&mut self, payload: &[u8], now: Instant) -> Option<Duration> {
+        let (nonce, sent) = self.pending?;
+        if payload.get(..8)? != nonce.to_le_bytes() {
+            return None;
+        }
+        self.pending = None;
+        Some(now.duration_since(sent))
+    }
+}
+
 pub struct Peer {
     stream: TcpStream,
     magic: [u8; 4],
@@ -258,6 +454,8 @@ pub struct Peer {
     registry_id: u64,
     registry: Arc<PeerRegistry>,
     net_totals: Arc<NetTotals>,
+    ping: PingState,
+    read_buf: Vec<u8>,
 }
 
 impl Peer {
@@ -285,6 +483,8 @@ impl Peer {
             registry_id,
             registry,
             net_totals,
+            ping: PingState::default(),
+            read_buf: Vec::new(),
         })
     }
 
@@ -310,6 +510,8 @@ impl Peer {
             registry_id,
             registry,
             net_totals,
+            ping: PingState::default(),
+            read_buf: Vec::new(),
         }
     }
 
@@ -339,44 +541,67 @@ impl Peer {
         Ok(())
     }
 
+    /// Reads the next message. Partial frames stay buffered on the peer, so the future can be
+    /// dropped by a `select!` (e.g. when the ping timer fires) without desyncing the stream.
     pub async fn read_message(&mut self) -> Result<(String, Vec<u8>), String> {
-        let mut header = [0u8; 24];
-        self.stream
-            .read_exact(&mut header)
-            .await
-            .map_err(|err| err.to_string())?;
-        if header[..4] != self.magic {
-            return Err("invalid magic".to_string());
+        loop {
+            let mut needed = 24;
+            if self.read_buf.len() >= 24 {
+                if self.read_buf[..4] != self.magic {
+                    return Err("invalid magic".to_string());
+                }
+                let length = u32::from_le_bytes([
+                    self.read_buf[16],
+                    self.read_buf[17],
+                    self.read_buf[18],
+                    self.read_buf[19],
+                ]) as usize;
+                if length > MAX_PAYLOAD_SIZE {
+                    return Err("payload too large".to_string());
+                }
+                needed = 24 + length;
+                if self.read_buf.len() >= needed {
+                    return self.take_message(needed);
+                }
+            }
+            self.read_buf.reserve(needed - self.read_buf.len());
+            let read = self
+                .stream
+                .read_buf(&mut self.read_buf)
+                .await
+                .map_err(|err| err.to_string())?;
+            if read == 0 {
+                return Err("peer closed connection".to_string());
+            }
         }
-        let command_raw = &header[4..16];
-        let command = command_raw
+    }
+
+    fn take_message(&mut self, len: usize) -> Result<(String, Vec<u8>), String> {
+        let mut payload = if self.read_buf.len() == len {
+            std::mem::take(&mut self.read_buf)
+        } else {
+            let rest = self.read_buf.split_off(len);
+            std::mem::replace(&mut self.read_buf, rest)
+        };
+        let header: Vec<u8> = payload.drain(..24).collect();
+        let command = header[4..16]
             .iter()
             .take_while(|byte| **byte != 0)
             .map(|byte| *byte as char)
             .collect::<String>();
-        let length = u32::from_le_bytes([header[16], header[17], header[18], header[19]]) as usize;
-        if length > MAX_PAYLOAD_SIZE {
-            return Err("payload too large".to_string());
-        }
-        let checksum = &header[20..24];
-        let mut payload = vec![0u8; length];
-        self.stream
-            .read_exact(&mut payload)
-            .await
-            .map_err(|err| err.to_string())?;
         let calc = sha256d(&payload);
-        if checksum != &calc[..4] {
+        if header[20..24] != calc[..4] {
             return Err("invalid payload checksum".to_string());
         }
-        let bytes = 24 + payload.len();
-        self.net_totals.add_recv(bytes);
-        self.registry.note_recv(self.registry_id, bytes);
+        self.net_totals.add_recv(len);
+        self.registry.note_recv(self.registry_id, len);
         Ok((command, payload))
     }
 
     pub async fn handshake(&mut self, start_height: i32) -> Result<(), String> {
         let relay = matches!(self.kind, PeerKind::Relay);
-        let payload = build_version_payload(start_height, relay);
+        let local_addr = self.registry.local_addr();
+        let payload = build_version_payload(start_height, relay, local_addr);
         self.send_message("version", &payload).await?;
 
         let mut got_verack = false;
@@ -415,6 +640,14 @@ impl Peer {
                 _ => {}
             }
         }
+        if let Some(local_addr) = local_addr {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as u32)
+                .unwrap_or(0);
+            let payload = build_addr_payload(&[local_addr], now);
+            self.send_message("addr", &payload).await?;
+        }
         Ok(())
     }
 
@@ -422,6 +655,35 @@ impl Peer {
         self.registry.take_disconnect_request(self.addr)
     }
 
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.registry.local_addr()
+    }
+
+    /// Sends a nonce-tagged `ping` every `PING_INTERVAL_SECS` and errors once an outstanding
+    /// ping has gone unanswered for `PING_TIMEOUT_SECS`, so callers drop the stale peer.
+    pub async fn maybe_send_ping(&mut self) -> Result<(), String> {
+        let now = Instant::now();
+        if !self.ping.due(now)? {
+            return Ok(());
+        }
+        let nonce: u64 = rand::random();
+        self.send_message("ping", &nonce.to_le_bytes()).await?;
+        self.ping.start(nonce, now);
+        Ok(())
+    }
+
+    /// When the caller's read loop should wake to call `maybe_send_ping` again.
+    pub fn ping_deadline(&self) -> tokio::time::Instant {
+        self.ping.deadline(Instant::now()).into()
+    }
+
+    /// Records the round-trip time when `payload` echoes the outstanding ping nonce.
+    pub fn handle_pong(&mut self, payload: &[u8]) {
+        if let Some(rtt) = self.ping.complete(payload, Instant::now()) {
+            self.registry.note_ping(self.registry_id, rtt);
+        }
+    }
+
     pub fn remote_height(&self) -> i32 {
         self.remote_height
     }
@@ -438,6 +700,15 @@ impl Peer {
         &self.remote_user_agent
     }
 
+    pub fn remote_services(&self) -> u64 {
+        self.remote_services
+    }
+
+    /// Whether the peer advertised `NODE_NETWORK`, i.e. can serve historical blocks.
+    pub fn serves_full_chain(&self) -> bool {
+        serves_full_chain(self.remote_services)
+    }
+
     pub fn addr(&self) -> SocketAddr {
         self.addr
     }
@@ -566,6 +837,43 @@ pub fn parse_addr(payload: &[u8]) -> Result<Vec<SocketAddr>, String> {
     Ok(addrs)
 }
 
+/// Drops gossiped addresses not worth keeping: non-routable, zero port, or repeated within the
+/// message. At most `MAX_ADDR_RESULTS` survive.
+pub fn filter_gossiped_addrs(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let mut seen = HashSet::new();
+    addrs
+        .into_iter()
+        .filter(|addr| addr.port() != 0 && is_routable(addr.ip()) && seen.insert(*addr))
+        .take(MAX_ADDR_RESULTS)
+        .collect()
+}
+
+/// Per-peer budget for `addr` messages: at most `MAX_ADDR_MESSAGES_PER_WINDOW` per minute are
+/// processed, the rest are dropped unparsed.
+#[derive(Debug, Default)]
+pub struct AddrGossipLimiter {
+    window_start: Option<Instant>,
+    messages: u32,
+}
+
+impl AddrGossipLimiter {
+    pub fn allow(&mut self, now: Instant) -> bool {
+        let window = Duration::from_secs(ADDR_GOSSIP_WINDOW_SECS);
+        match self.window_start {
+            Some(start) if now.duration_since(start) < window => {}
+            _ => {
+                self.window_start = Some(now);
+                self.messages = 0;
+            }
+        }
+        if self.messages >= MAX_ADDR_MESSAGES_PER_WINDOW {
+            return false;
+        }
+        self.messages += 1;
+        true
+    }
+}
+
 pub fn build_headers_payload(headers: &[Vec<u8>]) -> Vec<u8> {
     let mut encoder = Encoder::new();
     encoder.write_varint(headers.len() as u64);
@@ -581,23 +889,58 @@ pub fn build_addr_payload(addrs: &[SocketAddr], now: u32) -> Vec<u8> {
     encoder.write_varint(addrs.len() as u64);
     for addr in addrs {
         encoder.write_u32_le(now);
-        encoder.write_u64_le(NODE_NETWORK);
-        let ip_bytes = match addr.ip() {
-            IpAddr::V4(ip4) => {
-                let mut out = [0u8; 16];
-                out[10] = 0xff;
-                out[11] = 0xff;
-                out[12..16].copy_from_slice(&ip4.octets());
-                out
-            }
-            IpAddr::V6(ip6) => ip6.octets(),
-        };
-        encoder.write_bytes(&ip_bytes);
-        encoder.write_bytes(&addr.port().to_be_bytes());
+        write_net_addr(
+            &mut encoder,
+            NODE_NETWORK,
+            net_addr_ip_bytes(addr.ip()),
+            addr.port(),
+        );
     }
     encoder.into_inner()
 }
 
+fn net_addr_ip_bytes(ip: IpAddr) -> [u8; 16] {
+    match ip {
+        IpAddr::V4(ip4) => ip4.to_ipv6_mapped().octets(),
+        IpAddr::V6(ip6) => ip6.octets(),
+    }
+}
+
+pub fn serves_full_chain(services: u64) -> bool {
+    services & NODE_NETWORK != 0
+}
+
+/// Whether `ip` is publicly reachable, i.e. worth advertising to other nodes.
+pub fn is_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip4) => {
+            let octets = ip4.octets();
+            !(ip4.is_unspecified()
+                || ip4.is_loopback()
+                || ip4.is_private()
+                || ip4.is_link_local()
+                || ip4.is_broadcast()
+                || ip4.is_documentation()
+                || ip4.is_multicast()
+                || octets[0] == 0
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+                || octets[0] >= 240)
+        }
+        IpAddr::V6(ip6) => {
+            if let Some(ip4) = ip6.to_ipv4_mapped() {
+                return is_routable(IpAddr::V4(ip4));
+            }
+            let segments = ip6.segments();
+            !(ip6.is_unspecified()
+                || ip6.is_loopback()
+                || ip6.is_multicast()
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct InventoryVector {
     pub inv_type: u32,
@@ -669,7 +1012,11 @@ pub fn parse_feefilter(payload: &[u8]) -> Result<i64, String> {
     Ok(fee.max(0))
 }
 
-fn build_version_payload(start_height: i32, relay: bool) -> Vec<u8> {
+fn build_version_payload(
+    start_height: i32,
+    relay: bool,
+    local_addr: Option<SocketAddr>,
+) -> Vec<u8> {
     let mut encoder = Encoder::new();
     encoder.write_i32_le(PROTOCOL_VERSION);
     encoder.write_u64_le(NODE_NETWORK);
@@ -679,7 +1026,15 @@ fn build_version_payload(start_height: i32, relay: bool) -> Vec<u8> {
         .unwrap_or(0);
     encoder.write_i64_le(timestamp);
     write_net_addr(&mut encoder, NODE_NETWORK, [0u8; 16], 0);
-    write_net_addr(&mut encoder, NODE_NETWORK, [0u8; 16], 0);
+    match local_addr {
+        Some(addr) => write_net_addr(
+            &mut encoder,
+            NODE_NETWORK,
+            net_addr_ip_bytes(addr.ip()),
+            addr.port(),
+        ),
+        None => write_net_addr(&mut encoder, NODE_NETWORK, [0u8; 16], 0),
+    }
     encoder.write_u64_le(rand::random());
     encoder.write_var_str(USER_AGENT);
     encoder.write_i32_le(start_height);
@@ -752,3 +1107,200 @@ fn read_net_addr(decoder: &mut Decoder) -> Result<(), String> {
     let _port = decoder.read_bytes(2).map_err(|err| err.to_string())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ping_state_matches_nonce_and_times_out() {
+        let start = Instant::now();
+        let mut state = PingState::default();
+        assert_eq!(state.due(start), Ok(true));
+
+        state.start(0xdead_beef, start);
+        assert_eq!(state.due(start), Ok(false));
+        let later = start + Duration::from_millis(40);
+        assert_eq!(state.complete(&7u64.to_le_bytes(), later), None);
+        assert_eq!(state.complete(&[0xef, 0xbe], later), None);
+        assert_eq!(
+            state.complete(&0xdead_beefu64.to_le_bytes(), later),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(state.complete(&0xdead_beefu64.to_le_bytes(), later), None);
+        assert_eq!(state.due(later), Ok(false));
+        assert_eq!(
+            state.due(start + Duration::from_secs(PING_INTERVAL_SECS)),
+            Ok(true)
+        );
+
+        state.start(1, start);
+        assert_eq!(
+            state.deadline(start),
+            start + Duration::from_secs(PING_TIMEOUT_SECS)
+        );
+        assert!(state
+            .due(start + Duration::from_secs(PING_TIMEOUT_SECS))
+            .is_err());
+    }
+
+    #[test]
+    fn read_message_resumes_after_cancelled_partial_read() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+                .await
+                .expect("bind");
+            let addr = listener.local_addr().expect("addr");
+            let mut client = TcpStream::connect(addr).await.expect("connect");
+            let (stream, remote) = listener.accept().await.expect("accept");
+            let magic = [0xf9, 0xbe, 0xb4, 0xd9];
+            let mut peer = Peer::from_inbound(
+                stream,
+                remote,
+                magic,
+                PeerKind::Relay,
+                Arc::new(PeerRegistry::default()),
+                Arc::new(NetTotals::default()),
+            );
+
+            let payload = 42u64.to_le_bytes();
+            let mut frame = magic.to_vec();
+            let mut command = [0u8; 12];
+            command[..4].copy_from_slice(b"ping");
+            frame.extend_from_slice(&command);
+            frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&sha256d(&payload)[..4]);
+            frame.extend_from_slice(&payload);
+
+            client.write_all(&frame[..30]).await.expect("write");
+            let read = timeout(Duration::from_millis(50), peer.read_message()).await;
+            assert!(read.is_err());
+
+            client.write_all(&frame[30..]).await.expect("write");
+            let (command, received) = peer.read_message().await.expect("message");
+            assert_eq!(command, "ping");
+            assert_eq!(received, payload);
+        });
+    }
+
+    #[test]
+    fn upload_window_rolls_hourly_buckets_over_a_day() {
+        let start = 1_700_000_000u64 / UPLOAD_WINDOW_BUCKET_SECS * UPLOAD_WINDOW_BUCKET_SECS;
+        let mut window = UploadWindow::default();
+        window.record(600, start);
+        window.record(400, start + 10);
+        window.record(500, start + UPLOAD_WINDOW_BUCKET_SECS);
+
+        let snapshot = window.snapshot(1_000, start + UPLOAD_WINDOW_BUCKET_SECS);
+        assert_eq!(snapshot.bytes_sent, 1_500);
+        assert!(snapshot.target_reached);
+        assert_eq!(snapshot.bytes_left, 0);
+        assert_eq!(
+            snapshot.secs_until_release,
+            UPLOAD_TARGET_TIMEFRAME_SECS - UPLOAD_WINDOW_BUCKET_SECS
+        );
+
+        // The first hour drops out exactly a day after it started.
+        let snapshot = window.snapshot(1_000, start + UPLOAD_TARGET_TIMEFRAME_SECS);
+        assert_eq!(snapshot.bytes_sent, 500);
+        assert!(!snapshot.target_reached);
+        assert_eq!(snapshot.bytes_left, 500);
+
+        let unlimited = window.snapshot(0, start + UPLOAD_TARGET_TIMEFRAME_SECS);
+        assert!(!unlimited.target_reached);
+        let empty = window.snapshot(1_000, start + 3 * UPLOAD_TARGET_TIMEFRAME_SECS);
+        assert_eq!((empty.bytes_sent, empty.secs_until_release), (0, 0));
+    }
+
+    #[test]
+    fn routable_rejects_local_and_reserved_ranges() {
+        for raw in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.10",
+            "169.254.0.1",
+            "100.64.0.1",
+            "203.0.113.5",
+            "224.0.0.1",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "2001:db8::1",
+            "::ffff:192.168.0.1",
+        ] {
+            let ip: IpAddr = raw.parse().expect("ip");
+            assert!(!is_routable(ip), "{raw} should not be routable");
+        }
+        for raw in ["8.8.8.8", "2a01:4f8::1", "::ffff:1.1.1.1"] {
+            let ip: IpAddr = raw.parse().expect("ip");
+            assert!(is_routable(ip), "{raw} should be routable");
+        }
+    }
+
+    #[test]
+    fn version_payload_advertises_local_addr() {
+        let local: SocketAddr = "8.8.4.4:16125".parse().expect("addr");
+        let payload = build_version_payload(7, true, Some(local));
+        // version(4) + services(8) + timestamp(8) + addr_recv(26) + addr_from.services(8)
+        let addr_from = &payload[54..72];
+        assert_eq!(&addr_from[..16], &net_addr_ip_bytes(local.ip()));
+        assert_eq!(&addr_from[16..], &16125u16.to_be_bytes());
+        let addrs = parse_addr(&build_addr_payload(&[local], 0)).expect("addr payload");
+        assert_eq!(addrs, vec![local]);
+    }
+
+    #[test]
+    fn addr_gossip_rejects_oversized_and_filters_garbage() {
+        let public: SocketAddr = "8.8.8.8:16125".parse().expect("addr");
+        let oversized = vec![public; MAX_ADDR_RESULTS + 1];
+        assert!(parse_addr(&build_addr_payload(&oversized, 0)).is_err());
+
+        let private: SocketAddr = "192.168.1.10:16125".parse().expect("addr");
+        let other: SocketAddr = "[2a01:4f8::1]:16125".parse().expect("addr");
+        let parsed = parse_addr(&build_addr_payload(&[public, private, public, other], 0))
+            .expect("addr payload");
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(filter_gossiped_addrs(parsed), vec![public, other]);
+    }
+
+    #[test]
+    fn addr_gossip_limiter_caps_messages_per_window() {
+        let start = Instant::now();
+        let mut limiter = AddrGossipLimiter::default();
+        for _ in 0..MAX_ADDR_MESSAGES_PER_WINDOW {
+            assert!(limiter.allow(start));
+        }
+        assert!(!limiter.allow(start + Duration::from_secs(1)));
+        assert!(limiter.allow(start + Duration::from_secs(ADDR_GOSSIP_WINDOW_SECS)));
+    }
+
+    #[test]
+    fn version_services_round_trip_and_gate_full_chain_peers() {
+        let info = parse_version(&build_version_payload(7, false, None)).expect("version");
+        assert_eq!(info.services, NODE_NETWORK);
+        assert_eq!(info.start_height, 7);
+        assert!(serves_full_chain(info.services));
+        assert!(serves_full_chain(NODE_NETWORK | (1 << 10)));
+        assert!(!serves_full_chain(0));
+        assert!(!serves_full_chain(1 << 10));
+    }
+
+    #[test]
+    fn registry_tracks_last_and_min_ping() {
+        let registry = PeerRegistry::default();
+        let addr: SocketAddr = "127.0.0.1:16125".parse().expect("addr");
+        let id = registry.register(addr, PeerKind::Relay);
+        registry.note_ping(id, Duration::from_millis(80));
+        registry.note_ping(id, Duration::from_millis(30));
+        registry.note_ping(id, Duration::from_millis(55));
+        let peers = registry.snapshot();
+        assert_eq!(peers[0].last_ping_ms, Some(55));
+        assert_eq!(peers[0].min_ping_ms, Some(30));
+    }
+}
@@ -7,18 +7,21 @@ use fluxd_chainstate::state::ChainState;
 use fluxd_chainstate::validation::ValidationFlags;
 use fluxd_consensus::params::ChainParams;
 use fluxd_consensus::Hash256;
+use fluxd_primitives::hash::hash256_to_hex;
 use fluxd_primitives::transaction::Transaction;
 use fluxd_storage::KeyValueStore;
 use tokio::net::TcpListener;
 use tokio::sync::broadcast;
-use tokio::time::{timeout, Duration};
+use tokio::time::{timeout, timeout_at, Duration};
 
 use crate::mempool;
 use crate::p2p::{
-    build_addr_payload, build_headers_payload, build_inv_payload, parse_addr, parse_feefilter,
-    parse_getheaders, parse_inv, parse_reject, Peer, PeerKind, MSG_BLOCK, MSG_TX,
+    build_addr_payload, build_headers_payload, build_inv_payload, filter_gossiped_addrs,
+    parse_addr, parse_feefilter, parse_getheaders, parse_inv, parse_reject, AddrGossipLimiter,
+    NetTotals, Peer, PeerKind, MSG_BLOCK, MSG_TX,
 };
 use crate::stats::MempoolMetrics;
+use crate::tx_relay::TxFeedbackWaiters;
 
 const INBOUND_READ_TIMEOUT_SECS: u64 = 120;
 const MAX_INBOUND_GETDATA: usize = 256;
@@ -29,19 +32,65 @@ const MAX_INBOUND_TX_REQUEST: usize = 2048;
 const INBOUND_RATE_WINDOW_SECS: u64 = 10;
 const INBOUND_MAX_BYTES_SENT_PER_WINDOW: usize = 32 * 1024 * 1024;
 const INBOUND_MAX_BYTES_RECV_PER_WINDOW: usize = 16 * 1024 * 1024;
+/// Blocks older than this are not served once `--maxuploadtarget` is spent.
+const HISTORICAL_BLOCK_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Send budget shared by every inbound peer (`--maxinboundsendrate`), so the total serving
+/// rate stays bounded however many peers connect. Sends wait for budget instead of failing.
+pub struct InboundSendThrottle {
+    bytes_per_sec: f64,
+    state: Mutex<ThrottleState>,
+}
+
+struct ThrottleState {
+    /// Bytes that may be sent now; negative once a large message has borrowed ahead.
+    available: f64,
+    updated: Instant,
+}
+
+impl InboundSendThrottle {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1) as f64;
+        Self {
+            bytes_per_sec,
+            state: Mutex::new(ThrottleState {
+                available: bytes_per_sec,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes `bytes` from the budget and returns how long to wait before sending them.
+    /// Up to one second of unused budget carries over as burst.
+    fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let Ok(mut state) = self.state.lock() else {
+            return Duration::ZERO;
+        };
+        let elapsed = now.saturating_duration_since(state.updated).as_secs_f64();
+        state.available = (state.available + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+        state.updated = now;
+        state.available -= bytes as f64;
+        if state.available >= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-state.available / self.bytes_per_sec)
+    }
+}
 
 struct InboundRateLimiter {
     window_start: Instant,
     bytes_sent: usize,
     bytes_recv: usize,
+    throttle: Option<Arc<InboundSendThrottle>>,
 }
 
 impl InboundRateLimiter {
-    fn new() -> Self {
+    fn new(throttle: Option<Arc<InboundSendThrottle>>) -> Self {
         Self {
             window_start: Instant::now(),
             bytes_sent: 0,
             bytes_recv: 0,
+            throttle,
         }
     }
 
@@ -62,13 +111,16 @@ impl InboundRateLimiter {
         Ok(())
     }
 
-    fn note_send(&mut self, bytes: usize) -> Result<(), String> {
+    /// Returns how long the shared throttle wants this send delayed.
+    fn note_send(&mut self, bytes: usize) -> Result<Duration, String> {
         self.reset_if_needed();
         self.bytes_sent = self.bytes_sent.saturating_add(bytes);
         if self.bytes_sent > INBOUND_MAX_BYTES_SENT_PER_WINDOW {
             return Err("inbound peer rate limit exceeded (send)".to_string());
         }
-        Ok(())
+        Ok(self.throttle.as_ref().map_or(Duration::ZERO, |throttle| {
+            throttle.reserve(bytes, Instant::now())
+        }))
     }
 }
 
@@ -78,7 +130,10 @@ async fn send_message_limited(
     command: &str,
     payload: &[u8],
 ) -> Result<(), String> {
-    limiter.note_send(payload.len().saturating_add(24))?;
+    let wait = limiter.note_send(payload.len().saturating_add(24))?;
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
     peer.send_message(command, payload).await
 }
 
@@ -105,6 +160,8 @@ pub async fn serve_inbound_p2p<S: KeyValueStore + Send + Sync + 'static>(
     fee_estimator: Arc<Mutex<crate::fee_estimator::FeeEstimator>>,
     flags: ValidationFlags,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
+    send_throttle: Option<Arc<InboundSendThrottle>>,
 ) -> Result<(), String> {
     let local_addr = listener.local_addr().ok();
     if let Some(addr) = local_addr {
@@ -146,6 +203,8 @@ pub async fn serve_inbound_p2p<S: KeyValueStore + Send + Sync + 'static>(
         let fee_estimator = Arc::clone(&fee_estimator);
         let flags = flags.clone();
         let tx_announce = tx_announce.clone();
+        let tx_feedback = Arc::clone(&tx_feedback);
+        let send_throttle = send_throttle.clone();
 
         tokio::spawn(async move {
             if let Err(err) = handle_inbound_peer(
@@ -163,6 +222,8 @@ pub async fn serve_inbound_p2p<S: KeyValueStore + Send + Sync + 'static>(
                 fee_estimator,
                 flags,
                 tx_announce,
+                tx_feedback,
+                send_throttle,
             )
             .await
             {
@@ -193,6 +254,8 @@ async fn handle_inbound_peer<S: KeyValueStore + Send + Sync + 'static>(
     fee_estimator: Arc<Mutex<crate::fee_estimator::FeeEstimator>>,
     flags: ValidationFlags,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
+    send_throttle: Option<Arc<InboundSendThrottle>>,
 ) -> Result<(), String> {
     let mut peer = Peer::from_inbound(
         stream,
@@ -200,7 +263,7 @@ async fn handle_inbound_peer<S: KeyValueStore + Send + Sync + 'static>(
         magic,
         PeerKind::Relay,
         peer_registry,
-        net_totals,
+        Arc::clone(&net_totals),
     );
 
     let start_height = crate::start_height(chainstate.as_ref()).unwrap_or(0);
@@ -219,24 +282,34 @@ async fn handle_inbound_peer<S: KeyValueStore + Send + Sync + 'static>(
     let mut known: HashSet<Hash256> = HashSet::new();
     let mut requested: HashSet<Hash256> = HashSet::new();
     let mut peer_fee_filter_per_kb: i64 = 0;
-    let mut limiter = InboundRateLimiter::new();
+    let mut limiter = InboundRateLimiter::new(send_throttle);
+    let mut addr_limiter = AddrGossipLimiter::default();
 
-    let _ = peer
-        .send_feefilter(mempool_policy.min_relay_fee_per_kb)
-        .await;
+    let mut fee_filter = mempool::FeeFilterAnnouncer::default();
+    if let Some(filter) = fee_filter.due(mempool.as_ref(), mempool_policy.as_ref()) {
+        let _ = peer.send_feefilter(filter).await;
+    }
 
+    let read_timeout = Duration::from_secs(INBOUND_READ_TIMEOUT_SECS);
+    let mut read_deadline = tokio::time::Instant::now() + read_timeout;
     loop {
         if peer.take_disconnect_request() {
             break;
         }
+        peer.maybe_send_ping().await?;
+        if let Some(filter) = fee_filter.due(mempool.as_ref(), mempool_policy.as_ref()) {
+            let _ = peer.send_feefilter(filter).await;
+        }
+        let ping_at = peer.ping_deadline();
 
         tokio::select! {
-            msg = timeout(Duration::from_secs(INBOUND_READ_TIMEOUT_SECS), peer.read_message()) => {
+            msg = timeout_at(read_deadline, peer.read_message()) => {
                 let (command, payload) = match msg {
                     Ok(Ok(message)) => message,
                     Ok(Err(err)) => return Err(err),
                     Err(_) => return Err("peer read timed out".to_string()),
                 };
+                read_deadline = tokio::time::Instant::now() + read_timeout;
                 limiter.note_recv(payload.len().saturating_add(24))?;
 
                 handle_inbound_message(
@@ -252,11 +325,14 @@ async fn handle_inbound_peer<S: KeyValueStore + Send + Sync + 'static>(
                     mempool_policy.as_ref(),
                     mempool_metrics.as_ref(),
                     fee_estimator.as_ref(),
+                    net_totals.as_ref(),
                     &flags,
                     &tx_announce,
+                    tx_feedback.as_ref(),
                     &mut known,
                     &mut requested,
                     &mut peer_fee_filter_per_kb,
+                    &mut addr_limiter,
                 ).await?;
             }
             announced = announce_rx.recv() => {
@@ -274,6 +350,7 @@ async fn handle_inbound_peer<S: KeyValueStore + Send + Sync + 'static>(
                     Err(broadcast::error::RecvError::Lagged(_)) => continue,
                 }
             }
+            _ = tokio::time::sleep_until(ping_at) => {}
         }
     }
 
@@ -293,16 +370,24 @@ async fn handle_inbound_message<S: KeyValueStore>(
     mempool_policy: &mempool::MempoolPolicy,
     mempool_metrics: &MempoolMetrics,
     fee_estimator: &Mutex<crate::fee_estimator::FeeEstimator>,
+    net_totals: &NetTotals,
     flags: &ValidationFlags,
     tx_announce: &broadcast::Sender<Hash256>,
+    tx_feedback: &TxFeedbackWaiters,
     known: &mut HashSet<Hash256>,
     requested: &mut HashSet<Hash256>,
     peer_fee_filter_per_kb: &mut i64,
+    addr_limiter: &mut AddrGossipLimiter,
 ) -> Result<(), String> {
     match command {
         "ping" => send_message_limited(peer, limiter, "pong", payload).await?,
+        "pong" => peer.handle_pong(payload),
         "getaddr" => {
             let mut sample = addr_book.sample(MAX_INBOUND_ADDR);
+            if let Some(local_addr) = peer.local_addr() {
+                sample.retain(|addr| *addr != local_addr);
+                sample.insert(0, local_addr);
+            }
             if sample.len() > MAX_INBOUND_ADDR {
                 sample.truncate(MAX_INBOUND_ADDR);
             }
@@ -314,8 +399,13 @@ async fn handle_inbound_message<S: KeyValueStore>(
             send_message_limited(peer, limiter, "addr", &payload).await?;
         }
         "addr" => {
-            if let Ok(addrs) = parse_addr(payload) {
-                let inserted = addr_book.insert_many(addrs);
+            if !addr_limiter.allow(Instant::now()) {
+                log_debug!(
+                    "Addr discovery: dropping rate-limited addr from {}",
+                    remote_addr
+                );
+            } else if let Ok(addrs) = parse_addr(payload) {
+                let inserted = addr_book.insert_many(filter_gossiped_addrs(addrs));
                 if inserted > 0 {
                     log_debug!(
                         "Addr discovery: learned {} addrs from {} (inbound)",
@@ -356,12 +446,15 @@ async fn handle_inbound_message<S: KeyValueStore>(
                 *peer_fee_filter_per_kb = filter;
             }
         }
-        "getdata" => handle_getdata(peer, limiter, chainstate, mempool, payload).await?,
+        "getdata" => {
+            handle_getdata(peer, limiter, chainstate, mempool, net_totals, payload).await?
+        }
         "notfound" => {
             if let Ok(vectors) = parse_inv(payload) {
                 for vector in vectors {
                     if vector.inv_type == MSG_TX {
                         let _ = requested.remove(&vector.hash);
+                        tx_feedback.note_notfound(&[vector.hash]);
                     }
                 }
             }
@@ -373,6 +466,13 @@ async fn handle_inbound_message<S: KeyValueStore>(
                         let _ = requested.remove(&txid);
                     }
                 }
+                if tx_feedback.note_reject(&reject) {
+                    log_info!(
+                        "Inbound peer {} rejected submitted tx: {}",
+                        remote_addr,
+                        reject.reason
+                    );
+                }
             }
         }
         "version" => send_message_limited(peer, limiter, "verack", &[]).await?,
@@ -459,7 +559,13 @@ async fn handle_tx<S: KeyValueStore>(
             if err.kind == mempool::MempoolErrorKind::MissingInput {
                 let _ = requested.remove(&txid);
                 if let Ok(mut guard) = mempool.lock() {
-                    guard.store_orphan(txid, payload.to_vec(), err.missing_inputs.clone(), true);
+                    guard.store_orphan(
+                        txid,
+                        payload.to_vec(),
+                        err.missing_inputs.clone(),
+                        true,
+                        Some(peer.addr()),
+                    );
                 }
                 let _ = touch_known(known, txid);
 
@@ -486,11 +592,7 @@ async fn handle_tx<S: KeyValueStore>(
                 return Ok(());
             }
             if err.kind == mempool::MempoolErrorKind::Internal {
-                log_warn!(
-                    "mempool reject {}: {}",
-                    crate::stats::hash256_to_hex(&txid),
-                    err
-                );
+                log_warn!("mempool reject {}: {}", hash256_to_hex(&txid), err);
             }
             mempool_metrics.note_relay_reject();
             return Ok(());
@@ -523,11 +625,7 @@ async fn handle_tx<S: KeyValueStore>(
             }
             Err(err) => {
                 if err.kind == mempool::MempoolErrorKind::Internal {
-                    log_warn!(
-                        "mempool insert failed {}: {}",
-                        crate::stats::hash256_to_hex(&txid),
-                        err
-                    );
+                    log_warn!("mempool insert failed {}: {}", hash256_to_hex(&txid), err);
                 }
                 mempool_metrics.note_relay_reject();
                 return Ok(());
@@ -675,11 +773,18 @@ async fn handle_getdata<S: KeyValueStore>(
     limiter: &mut InboundRateLimiter,
     chainstate: &ChainState<S>,
     mempool: &Mutex<mempool::Mempool>,
+    net_totals: &NetTotals,
     payload: &[u8],
 ) -> Result<(), String> {
     let invs = parse_inv(payload)?;
     let mut processed = 0usize;
     let mut missing_txs: Vec<Hash256> = Vec::new();
+    let mut refused_blocks: Vec<Hash256> = Vec::new();
+    let upload_target_reached = net_totals.upload_target_reached();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|value| value.as_secs())
+        .unwrap_or(0);
     for inv in invs {
         if processed >= MAX_INBOUND_GETDATA {
             break;
@@ -687,6 +792,18 @@ async fn handle_getdata<S: KeyValueStore>(
         processed += 1;
         match inv.inv_type {
             MSG_BLOCK => {
+                if upload_target_reached {
+                    let historical = chainstate
+                        .header_entry(&inv.hash)
+                        .map_err(|err| err.to_string())?
+                        .is_some_and(|entry| {
+                            u64::from(entry.time).saturating_add(HISTORICAL_BLOCK_AGE_SECS) < now
+                        });
+                    if historical {
+                        refused_blocks.push(inv.hash);
+                        continue;
+                    }
+                }
                 let location = match chainstate
                     .block_location(&inv.hash)
                     .map_err(|err| err.to_string())?
@@ -716,6 +833,15 @@ async fn handle_getdata<S: KeyValueStore>(
         }
     }
 
+    if !refused_blocks.is_empty() {
+        log_debug!(
+            "maxuploadtarget reached: not serving {} historical block(s) to {}",
+            refused_blocks.len(),
+            peer.addr()
+        );
+        let payload = build_inv_payload(&refused_blocks, MSG_BLOCK);
+        send_message_limited(peer, limiter, "notfound", &payload).await?;
+    }
     if !missing_txs.is_empty() {
         let payload = build_inv_payload(&missing_txs, MSG_TX);
         send_message_limited(peer, limiter, "notfound", &payload).await?;
@@ -785,3 +911,24 @@ fn fee_rate_per_kb(fee: i64, size: usize) -> i64 {
     let size = i64::try_from(size.max(1)).unwrap_or(i64::MAX);
     fee.saturating_mul(1000).saturating_div(size)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_throttle_delays_once_budget_is_spent() {
+        let throttle = InboundSendThrottle::new(1_000);
+        let start = Instant::now();
+        assert_eq!(throttle.reserve(600, start), Duration::ZERO);
+        assert_eq!(throttle.reserve(400, start), Duration::ZERO);
+        assert_eq!(throttle.reserve(500, start), Duration::from_millis(500));
+
+        // Half a second refills the borrowed 500 bytes; unused budget caps at one second.
+        let later = start + Duration::from_millis(500);
+        assert_eq!(throttle.reserve(0, later), Duration::ZERO);
+        let idle = later + Duration::from_secs(10);
+        assert_eq!(throttle.reserve(1_000, idle), Duration::ZERO);
+        assert_eq!(throttle.reserve(2_000, idle), Duration::from_secs(2));
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::fs;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
@@ -8,16 +8,26 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
+use crate::rpc::IpNet;
+
 #[derive(Clone, Debug)]
 pub struct BannedPeerInfo {
     pub addr: SocketAddr,
     pub banned_until: SystemTime,
 }
 
+#[derive(Clone, Debug)]
+pub struct BannedSubnetInfo {
+    pub subnet: IpNet,
+    pub banned_until: SystemTime,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct BanListFile {
     version: u32,
     banned: Vec<BanListEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    subnets: Vec<BanListSubnetEntry>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -26,16 +36,171 @@ struct BanListEntry {
     banned_until: u64,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct BanListSubnetEntry {
+    subnet: String,
+    banned_until: u64,
+}
+
 const BANLIST_VERSION: u32 = 1;
 
+pub const DEFAULT_BAN_SECS: u64 = 24 * 60 * 60;
+
+/// Misbehaviour categories that carry an automatic ban.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BanReason {
+    NotFound,
+    Timeout,
+    Protocol,
+    BadChain,
+    Behind,
+}
+
+impl BanReason {
+    pub const ALL: [BanReason; 5] = [
+        BanReason::NotFound,
+        BanReason::Timeout,
+        BanReason::Protocol,
+        BanReason::BadChain,
+        BanReason::Behind,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BanReason::NotFound => "notfound",
+            BanReason::Timeout => "timeout",
+            BanReason::Protocol => "protocol",
+            BanReason::BadChain => "badchain",
+            BanReason::Behind => "behind",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        Self::ALL
+            .into_iter()
+            .find(|reason| reason.as_str().eq_ignore_ascii_case(raw))
+    }
+
+    fn default_secs(self) -> u64 {
+        match self {
+            BanReason::NotFound => 300,
+            BanReason::Timeout => 120,
+            BanReason::Protocol => 900,
+            BanReason::BadChain => 900,
+            BanReason::Behind => 300,
+        }
+    }
+}
+
+/// Ban durations per reason, plus the default used by manual `setban`.
+/// A duration of 0 disables automatic bans for that reason.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BanPolicy {
+    pub default_secs: u64,
+    reasons: HashMap<BanReason, u64>,
+}
+
+impl Default for BanPolicy {
+    fn default() -> Self {
+        Self {
+            default_secs: DEFAULT_BAN_SECS,
+            reasons: BanReason::ALL
+                .into_iter()
+                .map(|reason| (reason, reason.default_secs()))
+                .collect(),
+        }
+    }
+}
+
+impl BanPolicy {
+    pub fn secs(&self, reason: BanReason) -> u64 {
+        self.reasons
+            .get(&reason)
+            .copied()
+            .unwrap_or_else(|| reason.default_secs())
+    }
+
+    pub fn set(&mut self, reason: BanReason, secs: u64) {
+        self.reasons.insert(reason, secs);
+    }
+
+    /// Applies a `<reason>=<secs>` override.
+    pub fn apply_override(&mut self, raw: &str) -> Result<(), String> {
+        let (name, secs) = raw
+            .split_once('=')
+            .ok_or_else(|| format!("invalid banpolicy '{raw}' (expected <reason>=<secs>)"))?;
+        let reason = BanReason::parse(name).ok_or_else(|| {
+            let names: Vec<&str> = BanReason::ALL.iter().map(|r| r.as_str()).collect();
+            format!(
+                "unknown banpolicy reason '{}' (expected one of: {})",
+                name.trim(),
+                names.join(", ")
+            )
+        })?;
+        let secs = secs
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| format!("invalid banpolicy '{raw}' (expected <reason>=<secs>)"))?;
+        self.set(reason, secs);
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut reasons = serde_json::Map::new();
+        for reason in BanReason::ALL {
+            reasons.insert(reason.as_str().to_string(), self.secs(reason).into());
+        }
+        serde_json::json!({
+            "bantime": self.default_secs,
+            "reasons": reasons,
+        })
+    }
+}
+
 #[derive(Default)]
 pub struct HeaderPeerBook {
     scores: Mutex<HashMap<SocketAddr, i32>>,
     banned: Mutex<HashMap<SocketAddr, SystemTime>>,
+    banned_subnets: Mutex<HashMap<IpNet, SystemTime>>,
     revision: AtomicU64,
+    policy: BanPolicy,
+    whitelist: Vec<IpNet>,
 }
 
 impl HeaderPeerBook {
+    pub fn new(policy: BanPolicy, whitelist: &[String]) -> Result<Self, String> {
+        let whitelist = whitelist
+            .iter()
+            .map(|raw| IpNet::parse_named(raw, "whitelist"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            policy,
+            whitelist,
+            ..Self::default()
+        })
+    }
+
+    pub fn ban_policy(&self) -> &BanPolicy {
+        &self.policy
+    }
+
+    pub fn is_whitelisted(&self, addr: SocketAddr) -> bool {
+        let ip = canonical_ip(addr.ip());
+        self.whitelist.iter().any(|rule| rule.contains(ip))
+    }
+
+    /// Bans `addr` for the policy duration of `reason`. Whitelisted peers and
+    /// reasons configured with a zero duration are never banned.
+    pub fn penalize(&self, addr: SocketAddr, reason: BanReason) -> bool {
+        let secs = self.policy.secs(reason);
+        if secs == 0 || self.is_whitelisted(addr) {
+            return false;
+        }
+        self.ban_for(addr, secs);
+        true
+    }
+
     pub fn record_success(&self, addr: SocketAddr) {
         if let Ok(mut scores) = self.scores.lock() {
             let entry = scores.entry(addr).or_insert(0);
@@ -50,24 +215,39 @@ impl HeaderPeerBook {
         }
     }
 
-    pub fn record_bad_chain(&self, addr: SocketAddr, ban_secs: u64) {
+    pub fn record_bad_chain(&self, addr: SocketAddr) {
         self.record_failure(addr);
-        self.ban_for(addr, ban_secs);
+        self.penalize(addr, BanReason::BadChain);
     }
 
     pub fn is_banned(&self, addr: SocketAddr) -> bool {
         let now = SystemTime::now();
-        let Ok(mut banned) = self.banned.lock() else {
+        if let Ok(mut banned) = self.banned.lock() {
+            if let Some(until) = banned.get(&addr).copied() {
+                if until > now {
+                    return true;
+                }
+                banned.remove(&addr);
+                self.revision.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.is_subnet_banned(addr.ip(), now)
+    }
+
+    fn is_subnet_banned(&self, ip: IpAddr, now: SystemTime) -> bool {
+        let Ok(mut subnets) = self.banned_subnets.lock() else {
             return false;
         };
-        if let Some(until) = banned.get(&addr).copied() {
-            if until > now {
-                return true;
-            }
-            banned.remove(&addr);
+        if subnets.is_empty() {
+            return false;
+        }
+        let before = subnets.len();
+        subnets.retain(|_, until| *until > now);
+        if subnets.len() != before {
             self.revision.fetch_add(1, Ordering::Relaxed);
         }
-        false
+        let ip = canonical_ip(ip);
+        subnets.keys().any(|subnet| subnet.contains(ip))
     }
 
     pub fn ban_for(&self, addr: SocketAddr, secs: u64) {
@@ -77,6 +257,24 @@ impl HeaderPeerBook {
         }
     }
 
+    pub fn ban_subnet_for(&self, subnet: IpNet, secs: u64) {
+        if let Ok(mut subnets) = self.banned_subnets.lock() {
+            subnets.insert(subnet, SystemTime::now() + Duration::from_secs(secs));
+            self.revision.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn unban_subnet(&self, subnet: IpNet) -> bool {
+        let Ok(mut subnets) = self.banned_subnets.lock() else {
+            return false;
+        };
+        let removed = subnets.remove(&subnet).is_some();
+        if removed {
+            self.revision.fetch_add(1, Ordering::Relaxed);
+        }
+        removed
+    }
+
     pub fn unban(&self, addr: SocketAddr) -> bool {
         let Ok(mut banned) = self.banned.lock() else {
             return false;
@@ -89,12 +287,16 @@ impl HeaderPeerBook {
     }
 
     pub fn clear_banned(&self) -> usize {
-        let Ok(mut banned) = self.banned.lock() else {
-            return 0;
-        };
-        let removed = banned.len();
-        if removed > 0 {
+        let mut removed = 0usize;
+        if let Ok(mut banned) = self.banned.lock() {
+            removed += banned.len();
             banned.clear();
+        }
+        if let Ok(mut subnets) = self.banned_subnets.lock() {
+            removed += subnets.len();
+            subnets.clear();
+        }
+        if removed > 0 {
             self.revision.fetch_add(1, Ordering::Relaxed);
         }
         removed
@@ -142,6 +344,27 @@ impl HeaderPeerBook {
         out
     }
 
+    pub fn banned_subnets(&self) -> Vec<BannedSubnetInfo> {
+        let now = SystemTime::now();
+        let Ok(mut subnets) = self.banned_subnets.lock() else {
+            return Vec::new();
+        };
+        let before = subnets.len();
+        subnets.retain(|_, until| *until > now);
+        if subnets.len() != before {
+            self.revision.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut out: Vec<BannedSubnetInfo> = subnets
+            .iter()
+            .map(|(subnet, until)| BannedSubnetInfo {
+                subnet: *subnet,
+                banned_until: *until,
+            })
+            .collect();
+        out.sort_by_key(|entry| entry.subnet.to_string());
+        out
+    }
+
     pub fn banlist_revision(&self) -> u64 {
         self.revision.load(Ordering::Relaxed)
     }
@@ -172,6 +395,18 @@ impl HeaderPeerBook {
                 inserted += 1;
             }
         }
+        if let Ok(mut subnets) = self.banned_subnets.lock() {
+            for entry in file.subnets {
+                let until = UNIX_EPOCH + Duration::from_secs(entry.banned_until);
+                if until <= now {
+                    continue;
+                }
+                let subnet = IpNet::parse_named(&entry.subnet, "banlist subnet")
+                    .map_err(|err| format!("invalid banlist: {err}"))?;
+                subnets.insert(subnet, until);
+                inserted += 1;
+            }
+        }
         Ok(inserted)
     }
 
@@ -199,9 +434,22 @@ impl HeaderPeerBook {
             }
         }
         entries.sort_by_key(|entry| entry.addr.to_string());
+        let subnets = self
+            .banned_subnets()
+            .into_iter()
+            .map(|entry| BanListSubnetEntry {
+                subnet: entry.subnet.to_string(),
+                banned_until: entry
+                    .banned_until
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            })
+            .collect();
         let file = BanListFile {
             version: BANLIST_VERSION,
             banned: entries,
+            subnets,
         };
         let json = serde_json::to_vec_pretty(&file).map_err(|err| err.to_string())?;
         if let Some(parent) = path.parent() {
@@ -216,3 +464,79 @@ impl HeaderPeerBook {
         Ok(())
     }
 }
+
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4().map(IpAddr::V4).unwrap_or(IpAddr::V6(v6)),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ban_policy_overrides_and_rejects_unknown_reasons() {
+        let mut policy = BanPolicy::default();
+        assert_eq!(policy.secs(BanReason::Protocol), 900);
+        policy.apply_override("behind=0").expect("override");
+        policy.apply_override(" Timeout = 45 ").expect("override");
+        assert_eq!(policy.secs(BanReason::Behind), 0);
+        assert_eq!(policy.secs(BanReason::Timeout), 45);
+        assert!(policy.apply_override("slow=10").is_err());
+        assert!(policy.apply_override("timeout").is_err());
+        assert!(policy.apply_override("timeout=-1").is_err());
+    }
+
+    #[test]
+    fn whitelisted_peers_are_never_penalized() {
+        let mut policy = BanPolicy::default();
+        policy.set(BanReason::Timeout, 0);
+        let book = HeaderPeerBook::new(policy, &["10.1.0.0/16".to_string()]).expect("peer book");
+        let trusted: SocketAddr = "10.1.2.3:16125".parse().unwrap();
+        let mapped: SocketAddr = "[::ffff:10.1.9.9]:16125".parse().unwrap();
+        let other: SocketAddr = "192.0.2.7:16125".parse().unwrap();
+
+        assert!(!book.penalize(trusted, BanReason::Behind));
+        assert!(!book.penalize(mapped, BanReason::BadChain));
+        assert!(!book.is_banned(trusted));
+        assert!(!book.is_banned(mapped));
+
+        assert!(!book.penalize(other, BanReason::Timeout));
+        assert!(!book.is_banned(other));
+        assert!(book.penalize(other, BanReason::Behind));
+        assert!(book.is_banned(other));
+
+        assert!(HeaderPeerBook::new(BanPolicy::default(), &["10.0.0.0/40".to_string()]).is_err());
+    }
+
+    #[test]
+    fn subnet_bans_match_members_and_persist() {
+        let book = HeaderPeerBook::default();
+        let subnet = IpNet::parse_named("198.51.100.0/24", "subnet").expect("subnet");
+        let revision = book.banlist_revision();
+        book.ban_subnet_for(subnet, 60);
+        assert!(book.banlist_revision() > revision);
+        assert!(book.is_banned("198.51.100.9:16125".parse().unwrap()));
+        assert!(book.is_banned("[::ffff:198.51.100.200]:1".parse().unwrap()));
+        assert!(!book.is_banned("198.51.101.9:16125".parse().unwrap()));
+
+        let dir = std::env::temp_dir().join(format!("fluxd-banlist-{}", std::process::id()));
+        let path = dir.join("banlist.dat");
+        book.save_banlist(&path).expect("save");
+        let restored = HeaderPeerBook::default();
+        assert_eq!(restored.load_banlist(&path).expect("load"), 1);
+        let subnets = restored.banned_subnets();
+        assert_eq!(subnets.len(), 1);
+        assert_eq!(subnets[0].subnet.to_string(), "198.51.100.0/24");
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(book.unban_subnet(subnet));
+        assert!(!book.is_banned("198.51.100.9:16125".parse().unwrap()));
+        book.ban_subnet_for(subnet, 60);
+        book.ban_for("192.0.2.1:16125".parse().unwrap(), 60);
+        assert_eq!(book.clear_banned(), 2);
+        assert!(book.banned_subnets().is_empty());
+    }
+}
@@ -0,0 +1,728 @@
+//! Partially signed transactions (a transparent-only subset of BIP174).
+//!
+//! Supported input types are P2PKH, P2SH multisig and P2SH-wrapped P2PKH. Flux has no
+//! segwit, so input key `0x01` carries the spent output (value and scriptPubKey) instead
+//! of a witness UTXO; it is what signers need for the amount-committing sighash when the
+//! full previous transaction (`0x00`) is not available.
+
+use std::collections::BTreeMap;
+
+use fluxd_consensus::Hash256;
+use fluxd_primitives::encoding::{self, DecodeError, Decoder, Encoder};
+use fluxd_primitives::hash::hash160;
+use fluxd_primitives::transaction::{Transaction, TxOut};
+use fluxd_script::sighash::{
+    signature_hash, SighashType, SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_NONE, SIGHASH_SINGLE,
+};
+use fluxd_script::standard::{classify_script_pubkey, ScriptType};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+
+use crate::wallet::parse_multisig_redeem_script_with_required;
+
+pub const PSBT_MAGIC: [u8; 5] = *b"psbt\xff";
+
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_REDEEM_SCRIPT: u8 = 0x04;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_OUT_REDEEM_SCRIPT: u8 = 0x00;
+
+#[derive(Debug, PartialEq)]
+pub enum PsbtError {
+    Decode(DecodeError),
+    InvalidMagic,
+    InvalidData(&'static str),
+    DuplicateKey(Vec<u8>),
+    UnsupportedTransaction(&'static str),
+    Mismatch,
+    InputOutOfRange(usize),
+    MissingUtxo(usize),
+    MissingRedeemScript(usize),
+    RedeemScriptMismatch(usize),
+    UnsupportedScript(usize),
+    UnsupportedSighash(usize, u32),
+    Sighash(String),
+    Incomplete,
+}
+
+impl std::fmt::Display for PsbtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PsbtError::Decode(err) => write!(f, "{err}"),
+            PsbtError::InvalidMagic => write!(f, "invalid PSBT magic bytes"),
+            PsbtError::InvalidData(msg) => write!(f, "{msg}"),
+            PsbtError::DuplicateKey(key) => write!(f, "duplicate PSBT key {key:02x?}"),
+            PsbtError::UnsupportedTransaction(msg) => write!(f, "{msg}"),
+            PsbtError::Mismatch => write!(f, "PSBTs do not refer to the same transaction"),
+            PsbtError::InputOutOfRange(index) => write!(f, "input {index} out of range"),
+            PsbtError::MissingUtxo(index) => write!(f, "input {index} is missing its UTXO"),
+            PsbtError::MissingRedeemScript(index) => {
+                write!(f, "input {index} is missing its redeemScript")
+            }
+            PsbtError::RedeemScriptMismatch(index) => {
+                write!(
+                    f,
+                    "input {index} redeemScript does not match its scriptPubKey"
+                )
+            }
+            PsbtError::UnsupportedScript(index) => {
+                write!(f, "input {index} spends an unsupported script type")
+            }
+            PsbtError::UnsupportedSighash(index, sighash_type) => {
+                write!(
+                    f,
+                    "input {index} has unsupported sighash type {sighash_type:#x}"
+                )
+            }
+            PsbtError::Sighash(err) => write!(f, "sighash failed: {err}"),
+            PsbtError::Incomplete => write!(f, "PSBT is not fully signed"),
+        }
+    }
+}
+
+impl std::error::Error for PsbtError {}
+
+impl From<DecodeError> for PsbtError {
+    fn from(err: DecodeError) -> Self {
+        PsbtError::Decode(err)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PsbtInput {
+    pub non_witness_utxo: Option<Transaction>,
+    pub utxo: Option<TxOut>,
+    /// Signatures (with the sighash byte appended) keyed by serialized pubkey.
+    pub partial_sigs: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub sighash_type: Option<u32>,
+    pub redeem_script: Option<Vec<u8>>,
+    pub final_script_sig: Option<Vec<u8>>,
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PsbtOutput {
+    pub redeem_script: Option<Vec<u8>>,
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psbt {
+    pub tx: Transaction,
+    pub inputs: Vec<PsbtInput>,
+    pub outputs: Vec<PsbtOutput>,
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Psbt {
+    pub fn from_unsigned_tx(tx: Transaction) -> Result<Self, PsbtError> {
+        if tx.vin.iter().any(|input| !input.script_sig.is_empty()) {
+            return Err(PsbtError::UnsupportedTransaction(
+                "unsigned transaction must have empty scriptSigs",
+            ));
+        }
+        if tx.fluxnode.is_some()
+            || !tx.join_splits.is_empty()
+            || !tx.shielded_spends.is_empty()
+            || !tx.shielded_outputs.is_empty()
+        {
+            return Err(PsbtError::UnsupportedTransaction(
+                "PSBT only supports transparent transactions",
+            ));
+        }
+        Ok(Self {
+            inputs: vec![PsbtInput::default(); tx.vin.len()],
+            outputs: vec![PsbtOutput::default(); tx.vout.len()],
+            tx,
+            unknown: BTreeMap::new(),
+        })
+    }
+
+    pub fn txid(&self) -> Result<Hash256, PsbtError> {
+        self.tx
+            .txid()
+            .map_err(|_| PsbtError::InvalidData("unsigned transaction encode failed"))
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, PsbtError> {
+        let tx = self
+            .tx
+            .consensus_encode()
+            .map_err(|_| PsbtError::InvalidData("unsigned transaction encode failed"))?;
+        let mut encoder = Encoder::new();
+        encoder.write_bytes(&PSBT_MAGIC);
+        write_pair(&mut encoder, &[PSBT_GLOBAL_UNSIGNED_TX], &tx);
+        write_unknown(&mut encoder, &self.unknown);
+        encoder.write_u8(0);
+
+        for input in &self.inputs {
+            if let Some(prev_tx) = &input.non_witness_utxo {
+                let bytes = prev_tx
+                    .consensus_encode()
+                    .map_err(|_| PsbtError::InvalidData("previous transaction encode failed"))?;
+                write_pair(&mut encoder, &[PSBT_IN_NON_WITNESS_UTXO], &bytes);
+            }
+            if let Some(utxo) = &input.utxo {
+                write_pair(&mut encoder, &[PSBT_IN_UTXO], &encoding::encode(utxo));
+            }
+            for (pubkey, sig) in &input.partial_sigs {
+                let mut key = vec![PSBT_IN_PARTIAL_SIG];
+                key.extend_from_slice(pubkey);
+                write_pair(&mut encoder, &key, sig);
+            }
+            if let Some(sighash_type) = input.sighash_type {
+                write_pair(
+                    &mut encoder,
+                    &[PSBT_IN_SIGHASH_TYPE],
+                    &sighash_type.to_le_bytes(),
+                );
+            }
+            if let Some(script) = &input.redeem_script {
+                write_pair(&mut encoder, &[PSBT_IN_REDEEM_SCRIPT], script);
+            }
+            if let Some(script) = &input.final_script_sig {
+                write_pair(&mut encoder, &[PSBT_IN_FINAL_SCRIPTSIG], script);
+            }
+            write_unknown(&mut encoder, &input.unknown);
+            encoder.write_u8(0);
+        }
+
+        for output in &self.outputs {
+            if let Some(script) = &output.redeem_script {
+                write_pair(&mut encoder, &[PSBT_OUT_REDEEM_SCRIPT], script);
+            }
+            write_unknown(&mut encoder, &output.unknown);
+            encoder.write_u8(0);
+        }
+        Ok(encoder.into_inner())
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, PsbtError> {
+        if !bytes.starts_with(&PSBT_MAGIC) {
+            return Err(PsbtError::InvalidMagic);
+        }
+        let mut decoder = Decoder::new(&bytes[PSBT_MAGIC.len()..]);
+
+        let mut tx = None;
+        let mut unknown = BTreeMap::new();
+        for (key, value) in read_map(&mut decoder)? {
+            if key == [PSBT_GLOBAL_UNSIGNED_TX] {
+                tx = Some(
+                    Transaction::consensus_decode(&value)
+                        .map_err(|_| PsbtError::InvalidData("invalid unsigned transaction"))?,
+                );
+            } else {
+                unknown.insert(key, value);
+            }
+        }
+        let tx = tx.ok_or(PsbtError::InvalidData(
+            "PSBT is missing the unsigned transaction",
+        ))?;
+        let mut psbt = Self::from_unsigned_tx(tx)?;
+        psbt.unknown = unknown;
+
+        for input in &mut psbt.inputs {
+            for (key, value) in read_map(&mut decoder)? {
+                match key[0] {
+                    PSBT_IN_NON_WITNESS_UTXO if key.len() == 1 => {
+                        input.non_witness_utxo =
+                            Some(Transaction::consensus_decode(&value).map_err(|_| {
+                                PsbtError::InvalidData("invalid previous transaction")
+                            })?);
+                    }
+                    PSBT_IN_UTXO if key.len() == 1 => {
+                        input.utxo = Some(encoding::decode::<TxOut>(&value)?);
+                    }
+                    PSBT_IN_PARTIAL_SIG => {
+                        PublicKey::from_slice(&key[1..])
+                            .map_err(|_| PsbtError::InvalidData("invalid partial sig pubkey"))?;
+                        input.partial_sigs.insert(key[1..].to_vec(), value);
+                    }
+                    PSBT_IN_SIGHASH_TYPE if key.len() == 1 => {
+                        let bytes: [u8; 4] = value
+                            .as_slice()
+                            .try_into()
+                            .map_err(|_| PsbtError::InvalidData("invalid sighash type"))?;
+                        input.sighash_type = Some(u32::from_le_bytes(bytes));
+                    }
+                    PSBT_IN_REDEEM_SCRIPT if key.len() == 1 => input.redeem_script = Some(value),
+                    PSBT_IN_FINAL_SCRIPTSIG if key.len() == 1 => {
+                        input.final_script_sig = Some(value)
+                    }
+                    _ => {
+                        input.unknown.insert(key, value);
+                    }
+                }
+            }
+        }
+
+        for output in &mut psbt.outputs {
+            for (key, value) in read_map(&mut decoder)? {
+                if key == [PSBT_OUT_REDEEM_SCRIPT] {
+                    output.redeem_script = Some(value);
+                } else {
+                    output.unknown.insert(key, value);
+                }
+            }
+        }
+        if !decoder.is_empty() {
+            return Err(DecodeError::TrailingBytes.into());
+        }
+        Ok(psbt)
+    }
+
+    /// Merges signatures and metadata from `other`, which must wrap the same transaction.
+    pub fn combine(&mut self, other: Psbt) -> Result<(), PsbtError> {
+        if self.txid()? != other.txid()? {
+            return Err(PsbtError::Mismatch);
+        }
+        self.unknown.extend(other.unknown);
+        for (input, other) in self.inputs.iter_mut().zip(other.inputs) {
+            input.non_witness_utxo = input.non_witness_utxo.take().or(other.non_witness_utxo);
+            input.utxo = input.utxo.take().or(other.utxo);
+            input.partial_sigs.extend(other.partial_sigs);
+            input.sighash_type = input.sighash_type.or(other.sighash_type);
+            input.redeem_script = input.redeem_script.take().or(other.redeem_script);
+            input.final_script_sig = input.final_script_sig.take().or(other.final_script_sig);
+            input.unknown.extend(other.unknown);
+        }
+        for (output, other) in self.outputs.iter_mut().zip(other.outputs) {
+            output.redeem_script = output.redeem_script.take().or(other.redeem_script);
+            output.unknown.extend(other.unknown);
+        }
+        Ok(())
+    }
+
+    /// Output spent by input `index`, taken from the UTXO record or the full previous
+    /// transaction.
+    pub fn spent_output(&self, index: usize) -> Result<TxOut, PsbtError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or(PsbtError::InputOutOfRange(index))?;
+        if let Some(utxo) = &input.utxo {
+            return Ok(utxo.clone());
+        }
+        let prev_tx = input
+            .non_witness_utxo
+            .as_ref()
+            .ok_or(PsbtError::MissingUtxo(index))?;
+        let prevout = &self.tx.vin[index].prevout;
+        let prev_txid = prev_tx
+            .txid()
+            .map_err(|_| PsbtError::InvalidData("previous transaction encode failed"))?;
+        if prev_txid != prevout.hash {
+            return Err(PsbtError::InvalidData(
+                "previous transaction does not match input",
+            ));
+        }
+        prev_tx
+            .vout
+            .get(prevout.index as usize)
+            .cloned()
+            .ok_or(PsbtError::InvalidData("previous output index out of range"))
+    }
+
+    /// Adds a signature for input `index` made with `secret`, whose serialized public key
+    /// is `pubkey`.
+    pub fn sign_input(
+        &mut self,
+        index: usize,
+        secret: &SecretKey,
+        pubkey: &[u8],
+        branch_id: u32,
+    ) -> Result<(), PsbtError> {
+        let utxo = self.spent_output(index)?;
+        let input = &self.inputs[index];
+        let script_code = match classify_script_pubkey(&utxo.script_pubkey) {
+            ScriptType::P2Pkh => utxo.script_pubkey.clone(),
+            ScriptType::P2Sh => {
+                let redeem_script = input
+                    .redeem_script
+                    .clone()
+                    .ok_or(PsbtError::MissingRedeemScript(index))?;
+                if utxo.script_pubkey.get(2..22) != Some(hash160(&redeem_script).as_slice()) {
+                    return Err(PsbtError::RedeemScriptMismatch(index));
+                }
+                redeem_script
+            }
+            _ => return Err(PsbtError::UnsupportedScript(index)),
+        };
+        let sighash_type = input.sighash_type.unwrap_or(SIGHASH_ALL);
+        // Same set `signrawtransaction` accepts: ALL, NONE or SINGLE, optionally ANYONECANPAY.
+        let base_type = sighash_type & !SIGHASH_ANYONECANPAY;
+        if ![SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE].contains(&base_type) {
+            return Err(PsbtError::UnsupportedSighash(index, sighash_type));
+        }
+        let sighash = signature_hash(
+            &self.tx,
+            Some(index),
+            &script_code,
+            utxo.value,
+            SighashType(sighash_type),
+            branch_id,
+        )
+        .map_err(|err| PsbtError::Sighash(err.to_string()))?;
+        let msg = Message::from_digest_slice(&sighash)
+            .map_err(|_| PsbtError::InvalidData("invalid sighash digest"))?;
+        let mut sig = Secp256k1::signing_only().sign_ecdsa(&msg, secret);
+        sig.normalize_s();
+        let mut sig_bytes = sig.serialize_der().as_ref().to_vec();
+        sig_bytes.push(sighash_type as u8);
+        self.inputs[index]
+            .partial_sigs
+            .insert(pubkey.to_vec(), sig_bytes);
+        Ok(())
+    }
+
+    /// Builds final scriptSigs for every input that has enough signatures. Returns true
+    /// once all inputs are final.
+    pub fn finalize(&mut self) -> Result<bool, PsbtError> {
+        for index in 0..self.inputs.len() {
+            if self.inputs[index].final_script_sig.is_some() {
+                continue;
+            }
+            let utxo = match self.spent_output(index) {
+                Ok(utxo) => utxo,
+                Err(PsbtError::MissingUtxo(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            let Some(script_sig) = self.final_script_sig(index, &utxo)? else {
+                continue;
+            };
+            let input = &mut self.inputs[index];
+            input.final_script_sig = Some(script_sig);
+            input.partial_sigs.clear();
+            input.sighash_type = None;
+            input.redeem_script = None;
+        }
+        Ok(self.is_finalized())
+    }
+
+    pub fn is_finalized(&self) -> bool {
+        self.inputs
+            .iter()
+            .all(|input| input.final_script_sig.is_some())
+    }
+
+    /// Returns the network transaction once every input is finalized.
+    pub fn extract_tx(&self) -> Result<Transaction, PsbtError> {
+        let mut tx = self.tx.clone();
+        for (txin, input) in tx.vin.iter_mut().zip(&self.inputs) {
+            txin.script_sig = input
+                .final_script_sig
+                .clone()
+                .ok_or(PsbtError::Incomplete)?;
+        }
+        Ok(tx)
+    }
+
+    fn final_script_sig(&self, index: usize, utxo: &TxOut) -> Result<Option<Vec<u8>>, PsbtError> {
+        let input = &self.inputs[index];
+        match classify_script_pubkey(&utxo.script_pubkey) {
+            ScriptType::P2Pkh => Ok(p2pkh_script_sig(&utxo.script_pubkey, input)),
+            ScriptType::P2Sh => {
+                let Some(redeem_script) = &input.redeem_script else {
+                    return Ok(None);
+                };
+                if let Some((required, pubkeys)) =
+                    parse_multisig_redeem_script_with_required(redeem_script)
+                {
+                    let mut sigs: Vec<&Vec<u8>> = Vec::new();
+                    for pubkey in &pubkeys {
+                        let sig = input.partial_sigs.iter().find_map(|(key, sig)| {
+                            (PublicKey::from_slice(key).ok().as_ref() == Some(pubkey))
+                                .then_some(sig)
+                        });
+                        if let Some(sig) = sig {
+                            sigs.push(sig);
+                        }
+                        if sigs.len() == required {
+                            break;
+                        }
+                    }
+                    if sigs.len() < required {
+                        return Ok(None);
+                    }
+                    let mut script_sig = vec![0x00];
+                    for sig in sigs {
+                        push_data(&mut script_sig, sig);
+                    }
+                    push_data(&mut script_sig, redeem_script);
+                    return Ok(Some(script_sig));
+                }
+                if classify_script_pubkey(redeem_script) == ScriptType::P2Pkh {
+                    return Ok(
+                        p2pkh_script_sig(redeem_script, input).map(|mut script_sig| {
+                            push_data(&mut script_sig, redeem_script);
+                            script_sig
+                        }),
+                    );
+                }
+                Err(PsbtError::UnsupportedScript(index))
+            }
+            _ => Err(PsbtError::UnsupportedScript(index)),
+        }
+    }
+}
+
+fn p2pkh_script_sig(script_pubkey: &[u8], input: &PsbtInput) -> Option<Vec<u8>> {
+    let key_hash = script_pubkey.get(3..23)?;
+    let (pubkey, sig) = input
+        .partial_sigs
+        .iter()
+        .find(|(pubkey, _)| hash160(pubkey).as_slice() == key_hash)?;
+    let mut script_sig = Vec::new();
+    push_data(&mut script_sig, sig);
+    push_data(&mut script_sig, pubkey);
+    Some(script_sig)
+}
+
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    const OP_PUSHDATA1: u8 = 0x4c;
+    const OP_PUSHDATA2: u8 = 0x4d;
+
+    if data.len() < OP_PUSHDATA1 as usize {
+        script.push(data.len() as u8);
+    } else if data.len() <= u8::MAX as usize {
+        script.extend_from_slice(&[OP_PUSHDATA1, data.len() as u8]);
+    } else {
+        script.push(OP_PUSHDATA2);
+        script.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    }
+    script.extend_from_slice(data);
+}
+
+fn write_pair(encoder: &mut Encoder, key: &[u8], value: &[u8]) {
+    encoder.write_var_bytes(key);
+    encoder.write_var_bytes(value);
+}
+
+fn write_unknown(encoder: &mut Encoder, unknown: &BTreeMap<Vec<u8>, Vec<u8>>) {
+    for (key, value) in unknown {
+        write_pair(encoder, key, value);
+    }
+}
+
+/// One map's key/value pairs, in serialization order.
+type KeyValuePairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+fn read_map(decoder: &mut Decoder) -> Result<KeyValuePairs, PsbtError> {
+    let mut pairs = KeyValuePairs::new();
+    loop {
+        let key = decoder.read_var_bytes()?;
+        if key.is_empty() {
+            return Ok(pairs);
+        }
+        let value = decoder.read_var_bytes()?;
+        if pairs.iter().any(|(existing, _)| *existing == key) {
+            return Err(PsbtError::DuplicateKey(key));
+        }
+        pairs.push((key, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluxd_primitives::outpoint::OutPoint;
+    use fluxd_primitives::transaction::TxIn;
+    use fluxd_script::interpreter::{verify_script, STANDARD_SCRIPT_VERIFY_FLAGS};
+
+    fn key(byte: u8) -> (SecretKey, Vec<u8>) {
+        let secret = SecretKey::from_slice(&[byte; 32]).expect("secret");
+        let pubkey = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret);
+        (secret, pubkey.serialize().to_vec())
+    }
+
+    fn p2pkh(pubkey: &[u8]) -> Vec<u8> {
+        let mut script = vec![0x76, 0xa9, 0x14];
+        script.extend_from_slice(&hash160(pubkey));
+        script.extend_from_slice(&[0x88, 0xac]);
+        script
+    }
+
+    fn p2sh(redeem_script: &[u8]) -> Vec<u8> {
+        let mut script = vec![0xa9, 0x14];
+        script.extend_from_slice(&hash160(redeem_script));
+        script.push(0x87);
+        script
+    }
+
+    fn unsigned_tx(inputs: usize) -> Transaction {
+        Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: (0..inputs)
+                .map(|index| TxIn {
+                    prevout: OutPoint {
+                        hash: [0x42; 32],
+                        index: index as u32,
+                    },
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                })
+                .collect(),
+            vout: vec![TxOut {
+                value: 50_000,
+                script_pubkey: p2pkh(&key(9).1),
+            }],
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        }
+    }
+
+    #[test]
+    fn psbt_round_trips_through_serialization() {
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx(1)).expect("psbt");
+        psbt.inputs[0].utxo = Some(TxOut {
+            value: 70_000,
+            script_pubkey: p2pkh(&key(1).1),
+        });
+        psbt.inputs[0].sighash_type = Some(SIGHASH_ALL);
+        psbt.inputs[0]
+            .partial_sigs
+            .insert(key(1).1, vec![0x30, 0x01]);
+        psbt.inputs[0].unknown.insert(vec![0xfc, 0x01], vec![0xaa]);
+        psbt.outputs[0].redeem_script = Some(vec![0x51]);
+
+        let bytes = psbt.encode().expect("encode");
+        assert!(bytes.starts_with(&PSBT_MAGIC));
+        assert_eq!(Psbt::decode(&bytes).expect("decode"), psbt);
+
+        assert_eq!(Psbt::decode(&bytes[1..]), Err(PsbtError::InvalidMagic));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Psbt::decode(&trailing).is_err());
+    }
+
+    #[test]
+    fn psbt_rejects_signed_transactions() {
+        let mut tx = unsigned_tx(1);
+        tx.vin[0].script_sig = vec![0x51];
+        assert!(Psbt::from_unsigned_tx(tx).is_err());
+    }
+
+    #[test]
+    fn multisig_signatures_combine_and_finalize() {
+        let branch_id = 0;
+        let (secret_a, pubkey_a) = key(1);
+        let (secret_b, pubkey_b) = key(2);
+        let mut redeem_script = vec![0x52];
+        for pubkey in [&pubkey_a, &pubkey_b] {
+            push_data(&mut redeem_script, pubkey);
+        }
+        redeem_script.extend_from_slice(&[0x52, 0xae]);
+        let (secret_c, pubkey_c) = key(3);
+
+        let mut base = Psbt::from_unsigned_tx(unsigned_tx(2)).expect("psbt");
+        let multisig_utxo = TxOut {
+            value: 40_000,
+            script_pubkey: p2sh(&redeem_script),
+        };
+        base.inputs[0].utxo = Some(multisig_utxo.clone());
+        base.inputs[0].redeem_script = Some(redeem_script);
+        let mut prev_tx = unsigned_tx(1);
+        prev_tx.vout = vec![
+            TxOut {
+                value: 1,
+                script_pubkey: vec![0x51],
+            },
+            TxOut {
+                value: 30_000,
+                script_pubkey: p2pkh(&pubkey_c),
+            },
+        ];
+        base.tx.vin[1].prevout = OutPoint {
+            hash: prev_tx.txid().expect("txid"),
+            index: 1,
+        };
+        base.inputs[1].non_witness_utxo = Some(prev_tx);
+
+        let mut signer_a = Psbt::decode(&base.encode().expect("encode")).expect("decode");
+        signer_a
+            .sign_input(0, &secret_a, &pubkey_a, branch_id)
+            .expect("sign a");
+        signer_a
+            .sign_input(1, &secret_c, &pubkey_c, branch_id)
+            .expect("sign c");
+        assert!(!signer_a.clone().finalize().expect("finalize"));
+
+        let mut signer_b = base.clone();
+        signer_b
+            .sign_input(0, &secret_b, &pubkey_b, branch_id)
+            .expect("sign b");
+
+        let mut mismatched = Psbt::from_unsigned_tx(unsigned_tx(1)).expect("psbt");
+        assert_eq!(
+            mismatched.combine(signer_b.clone()),
+            Err(PsbtError::Mismatch)
+        );
+
+        signer_a.combine(signer_b).expect("combine");
+        assert!(signer_a.finalize().expect("finalize"));
+        let tx = signer_a.extract_tx().expect("extract");
+
+        let prevouts = [multisig_utxo, signer_a.spent_output(1).expect("utxo")];
+        for (index, prevout) in prevouts.iter().enumerate() {
+            verify_script(
+                &tx.vin[index].script_sig,
+                &prevout.script_pubkey,
+                &tx,
+                index,
+                prevout.value,
+                STANDARD_SCRIPT_VERIFY_FLAGS,
+                branch_id,
+            )
+            .expect("script verifies");
+        }
+    }
+
+    #[test]
+    fn sign_input_rejects_foreign_redeem_script_and_unknown_sighash() {
+        let (secret, pubkey) = key(1);
+        let mut redeem_script = vec![0x51];
+        push_data(&mut redeem_script, &pubkey);
+        redeem_script.extend_from_slice(&[0x51, 0xae]);
+
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx(1)).expect("psbt");
+        psbt.inputs[0].utxo = Some(TxOut {
+            value: 40_000,
+            script_pubkey: p2sh(&[0x51]),
+        });
+        psbt.inputs[0].redeem_script = Some(redeem_script.clone());
+        assert_eq!(
+            psbt.sign_input(0, &secret, &pubkey, 0),
+            Err(PsbtError::RedeemScriptMismatch(0))
+        );
+
+        psbt.inputs[0].utxo = Some(TxOut {
+            value: 40_000,
+            script_pubkey: p2sh(&redeem_script),
+        });
+        psbt.inputs[0].sighash_type = Some(0x41);
+        assert_eq!(
+            psbt.sign_input(0, &secret, &pubkey, 0),
+            Err(PsbtError::UnsupportedSighash(0, 0x41))
+        );
+        psbt.inputs[0].sighash_type = Some(SIGHASH_SINGLE | SIGHASH_ANYONECANPAY);
+        psbt.sign_input(0, &secret, &pubkey, 0).expect("sign");
+        assert_eq!(
+            psbt.inputs[0].partial_sigs[&pubkey].last(),
+            Some(&((SIGHASH_SINGLE | SIGHASH_ANYONECANPAY) as u8))
+        );
+    }
+}
@@ -265,7 +265,6 @@ fn refresh_richlist_cache<S: fluxd_storage::KeyValueStore>(
     Ok(())
 }
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpListener;
 use tokio::sync::{broadcast, watch};
 use transparent::address::TransparentAddress;
 use zcash_note_encryption::{EphemeralKeyBytes, ShieldedOutput, ENC_CIPHERTEXT_SIZE};
@@ -279,28 +278,28 @@ use fluxd_chainstate::address_neighbors::{
     address_id_from_script_pubkey, AddressId, AddressNeighborBuildState, AddressNeighborStats,
 };
 use fluxd_chainstate::address_balance::AddressBalanceEntry;
+use fluxd_chainstate::fluxnode::fluxnode_confirm_expire_height;
 use fluxd_chainstate::index::HeaderEntry;
-use fluxd_chainstate::state::{ChainState, ChainStateError};
-use fluxd_chainstate::validation::ValidationFlags;
+use fluxd_chainstate::state::{ChainState, ChainStateError, MTP_WINDOW_SIZE};
+use fluxd_chainstate::validation::{count_sigops, ValidationFlags};
 use fluxd_consensus::constants::{
     COINBASE_MATURITY, FLUXNODE_DOS_REMOVE_AMOUNT, FLUXNODE_DOS_REMOVE_AMOUNT_V2,
     FLUXNODE_START_TX_EXPIRATION_HEIGHT, FLUXNODE_START_TX_EXPIRATION_HEIGHT_V2, MAX_BLOCK_SIGOPS,
     MAX_BLOCK_SIZE, PROTOCOL_VERSION,
 };
-use fluxd_consensus::money::{money_range, CENT, COIN, MAX_MONEY};
-use fluxd_consensus::params::{hash256_from_hex, ChainParams, Network};
+use fluxd_consensus::money::{self, money_range, CENT, COIN, MAX_MONEY};
+use fluxd_consensus::params::{ChainParams, Network};
 use fluxd_consensus::upgrades::{
-    current_epoch_branch_id, network_upgrade_active, network_upgrade_state, UpgradeIndex,
-    UpgradeState, ALL_UPGRADES, NETWORK_UPGRADE_INFO,
+    branch_id_for_height, consensus_rules, network_upgrade_active, network_upgrade_state,
+    UpgradeIndex, UpgradeState, ALL_UPGRADES, NETWORK_UPGRADE_INFO,
 };
 use fluxd_consensus::Hash256;
-use fluxd_consensus::{
-    block_subsidy, exchange_fund_amount, foundation_fund_amount, swap_pool_amount,
-};
+use fluxd_consensus::{block_subsidy, required_funding_outputs, FundingOutputKind};
 use fluxd_fluxnode::storage::FluxnodeRecord;
 use fluxd_pow::difficulty::compact_to_u256;
-use fluxd_primitives::block::{Block, CURRENT_VERSION, PON_VERSION};
-use fluxd_primitives::hash::{hash160, sha256d};
+use fluxd_primitives::block::{Block, BlockHeader, CURRENT_VERSION, PON_VERSION};
+use fluxd_primitives::hash::{ct_eq, hash160, hash256_from_hex, hash256_to_hex, sha256d};
+use fluxd_primitives::ids::{BlockHash, Txid};
 use fluxd_primitives::merkleblock::{MerkleBlock, PartialMerkleTree};
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::transaction::{
@@ -311,12 +310,12 @@ use fluxd_primitives::transaction::{
     FLUXNODE_TX_VERSION, SAPLING_VERSION_GROUP_ID,
 };
 use fluxd_primitives::{
-    address_to_script_pubkey, script_pubkey_to_address, secret_key_to_wif, wif_to_secret_key,
-    AddressError,
+    address_to_script_pubkey, decode_bech32, script_pubkey_to_address, secret_key_to_wif,
+    wif_to_secret_key, AddressError,
 };
 use fluxd_storage::WriteBatch;
-use fluxd_script::interpreter::{verify_script, STANDARD_SCRIPT_VERIFY_FLAGS};
-use fluxd_script::message::{recover_signed_message_pubkey, signed_message_hash};
+use fluxd_script::interpreter::{verify_script, SCRIPT_VERIFY_NONE, STANDARD_SCRIPT_VERIFY_FLAGS};
+use fluxd_script::message::{signed_message_hash, verify_signed_message_key_hash};
 use fluxd_script::sighash::{
     signature_hash, SighashType, SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_NONE, SIGHASH_SINGLE,
 };
@@ -324,14 +323,17 @@ use fluxd_script::standard::{classify_script_pubkey, ScriptType};
 use primitive_types::U256;
 use secp256k1::{ecdsa::RecoverableSignature, Message, PublicKey, Secp256k1, SecretKey};
 
-use crate::fee_estimator::FeeEstimator;
-use crate::mempool::{build_mempool_entry, Mempool, MempoolErrorKind, MempoolPolicy};
-use crate::p2p::{NetTotals, PeerKind, PeerRegistry};
+use crate::fee_estimator::{FeeEstimator, RawBucketRange};
+use crate::listen::{self, ListenAddr};
+use crate::mempool::{build_mempool_entry, Mempool, MempoolEntry, MempoolErrorKind, MempoolPolicy};
+use crate::p2p::{NetTotals, PeerKind, PeerRegistry, UPLOAD_TARGET_TIMEFRAME_SECS};
 use crate::peer_book::HeaderPeerBook;
-use crate::stats::{hash256_to_hex, HeaderMetrics, MempoolMetrics};
+use crate::psbt::Psbt;
+use crate::stats::{HeaderMetrics, MempoolMetrics};
+use crate::tx_relay::{TxFeedback, TxFeedbackWaiters};
 use crate::wallet::{Wallet, WalletError, WALLET_FILE_VERSION};
 use crate::AddrBook;
-use crate::{db_info, Backend, Store};
+use crate::{db_info, Store};
 
 const MAX_REQUEST_BYTES: usize = 1024 * 1024;
 const RPC_REALM: &str = "fluxd";
@@ -364,6 +366,7 @@ const RPC_METHODS: &[&str] = &[
     "ping",
     "stop",
     "restart",
+    "setloglevel",
     "reindex",
     "rescanblockchain",
     "importaddress",
@@ -416,6 +419,10 @@ const RPC_METHODS: &[&str] = &[
     "settxfee",
     "fundrawtransaction",
     "signrawtransaction",
+    "createpsbt",
+    "combinepsbt",
+    "walletprocesspsbt",
+    "finalizepsbt",
     "sendrawtransaction",
     "sendfrom",
     "sendtoaddress",
@@ -463,13 +470,17 @@ const RPC_METHODS: &[&str] = &[
     "z_shieldcoinbase",
     "getmempoolinfo",
     "getrawmempool",
+    "getmempoolancestors",
+    "getmempooldescendants",
     "gettxout",
     "gettxoutproof",
     "verifytxoutproof",
     "gettxoutsetinfo",
     "getindexstats",
+    "getindexinfo",
     "getrichlist",
     "getblockdeltas",
+    "getblockstats",
     "getspentinfo",
     "getaddressutxos",
     "getaddressbalance",
@@ -484,11 +495,14 @@ const RPC_METHODS: &[&str] = &[
     "getmininginfo",
     "getblocktemplate",
     "submitblock",
+    "generate",
+    "generatetoaddress",
     "getnetworkhashps",
     "getnetworksolps",
     "getlocalsolps",
     "estimatefee",
     "estimatepriority",
+    "estimaterawfee",
     "prioritisetransaction",
     "getconnectioncount",
     "getnettotals",
@@ -519,6 +533,9 @@ const RPC_METHODS: &[&str] = &[
     "stopzelbenchd",
     "zcbenchmark",
     "verifychain",
+    "debugvalidateblock",
+    "invalidateblock",
+    "reconsiderblock",
     "addnode",
     "disconnectnode",
     "getaddednodeinfo",
@@ -557,6 +574,7 @@ struct RpcContext<S> {
     addr_book: Arc<AddrBook>,
     added_nodes: Arc<Mutex<HashSet<String>>>,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
     wallet: Arc<Mutex<Wallet>>,
     shutdown_tx: watch::Sender<bool>,
 }
@@ -806,8 +824,8 @@ fn current_unix_seconds_u64() -> u64 {
         .as_secs()
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum IpNet {
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum IpNet {
     V4 { network: u32, mask: u32 },
     V6 { network: u128, mask: u128 },
 }
@@ -844,11 +862,34 @@ impl RpcAllowList {
     }
 }
 
+impl std::fmt::Display for IpNet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpNet::V4 { network, mask } => write!(
+                f,
+                "{}/{}",
+                std::net::Ipv4Addr::from(*network),
+                mask.count_ones()
+            ),
+            IpNet::V6 { network, mask } => write!(
+                f,
+                "{}/{}",
+                std::net::Ipv6Addr::from(*network),
+                mask.count_ones()
+            ),
+        }
+    }
+}
+
 impl IpNet {
     fn parse(raw: &str) -> Result<Self, String> {
+        Self::parse_named(raw, "rpcallowip")
+    }
+
+    pub(crate) fn parse_named(raw: &str, option: &str) -> Result<Self, String> {
         let raw = raw.trim();
         if raw.is_empty() {
-            return Err("rpcallowip entry is empty".to_string());
+            return Err(format!("{option} entry is empty"));
         }
 
         let (ip_raw, prefix_raw) = match raw.split_once('/') {
@@ -859,22 +900,22 @@ impl IpNet {
         let ip = ip_raw
             .trim()
             .parse::<IpAddr>()
-            .map_err(|_| format!("invalid rpcallowip '{raw}'"))?;
+            .map_err(|_| format!("invalid {option} '{raw}'"))?;
         let prefix = match prefix_raw {
             None => None,
             Some(prefix) => {
                 let prefix = prefix.trim();
                 if prefix.is_empty() {
-                    return Err(format!("invalid rpcallowip '{raw}'"));
+                    return Err(format!("invalid {option} '{raw}'"));
                 }
                 Some(
                     prefix
                         .parse::<u8>()
-                        .map_err(|_| format!("invalid rpcallowip '{raw}'"))?,
+                        .map_err(|_| format!("invalid {option} '{raw}'"))?,
                 )
             }
         };
-        Self::from_cidr(ip, prefix).map_err(|_| format!("invalid rpcallowip '{raw}'"))
+        Self::from_cidr(ip, prefix).map_err(|_| format!("invalid {option} '{raw}'"))
     }
 
     fn from_cidr(ip: IpAddr, prefix: Option<u8>) -> Result<Self, ()> {
@@ -910,7 +951,7 @@ impl IpNet {
         }
     }
 
-    fn contains(&self, ip: IpAddr) -> bool {
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
         match (self, ip) {
             (IpNet::V4 { network, mask }, IpAddr::V4(ip)) => {
                 let ip = u32::from_be_bytes(ip.octets());
@@ -960,7 +1001,7 @@ pub fn load_or_create_auth(
 
 #[allow(clippy::too_many_arguments)]
 pub async fn serve_rpc<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
-    addr: SocketAddr,
+    addr: ListenAddr,
     auth: RpcAuth,
     allowlist: RpcAllowList,
     chainstate: Arc<ChainState<S>>,
@@ -983,13 +1024,18 @@ pub async fn serve_rpc<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
     addr_book: Arc<AddrBook>,
     added_nodes: Arc<Mutex<HashSet<String>>>,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
     wallet: Arc<Mutex<Wallet>>,
     shutdown_tx: watch::Sender<bool>,
 ) -> Result<(), String> {
-    let listener = TcpListener::bind(addr)
+    let listener = addr
+        .bind()
         .await
-        .map_err(|err| format!("rpc bind failed: {err}"))?;
-    log_info!("RPC listening on http://{addr}");
+        .map_err(|err| format!("rpc bind {addr} failed: {err}"))?;
+    match &addr {
+        ListenAddr::Tcp(tcp_addr) => log_info!("RPC listening on http://{tcp_addr}"),
+        ListenAddr::Unix(_) => log_info!("RPC listening on {addr}"),
+    }
 
     let auth = Arc::new(auth);
     let allowlist = Arc::new(allowlist);
@@ -998,7 +1044,8 @@ pub async fn serve_rpc<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
             .accept()
             .await
             .map_err(|err| format!("rpc accept failed: {err}"))?;
-        if !allowlist.allows(peer_addr.ip()) {
+        // Unix socket clients have no address; the socket's file permissions admit them.
+        if peer_addr.is_some_and(|peer_addr| !allowlist.allows(peer_addr.ip())) {
             let response = build_forbidden();
             tokio::spawn(async move {
                 let _ = stream.write_all(&response).await;
@@ -1026,6 +1073,7 @@ pub async fn serve_rpc<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
         let addr_book = Arc::clone(&addr_book);
         let added_nodes = Arc::clone(&added_nodes);
         let tx_announce = tx_announce.clone();
+        let tx_feedback = Arc::clone(&tx_feedback);
         let wallet = Arc::clone(&wallet);
         let shutdown_tx = shutdown_tx.clone();
         tokio::spawn(async move {
@@ -1052,6 +1100,7 @@ pub async fn serve_rpc<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
                 addr_book,
                 added_nodes,
                 tx_announce,
+                tx_feedback,
                 wallet,
                 shutdown_tx,
             )
@@ -1065,7 +1114,7 @@ pub async fn serve_rpc<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
 
 #[allow(clippy::too_many_arguments)]
 async fn handle_connection<S: fluxd_storage::KeyValueStore + Send + Sync + 'static>(
-    mut stream: tokio::net::TcpStream,
+    mut stream: listen::Stream,
     auth: Arc<RpcAuth>,
     chainstate: Arc<ChainState<S>>,
     store: Arc<Store>,
@@ -1087,6 +1136,7 @@ async fn handle_connection<S: fluxd_storage::KeyValueStore + Send + Sync + 'stat
     addr_book: Arc<AddrBook>,
     added_nodes: Arc<Mutex<HashSet<String>>>,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
     wallet: Arc<Mutex<Wallet>>,
     shutdown_tx: watch::Sender<bool>,
 ) -> Result<(), String> {
@@ -1136,6 +1186,7 @@ async fn handle_connection<S: fluxd_storage::KeyValueStore + Send + Sync + 'stat
         addr_book,
         added_nodes,
         tx_announce,
+        tx_feedback,
         wallet,
         shutdown_tx,
     };
@@ -1202,7 +1253,7 @@ async fn handle_connection<S: fluxd_storage::KeyValueStore + Send + Sync + 'stat
                 }
             }
         } else {
-            match handle_daemon_request(method, &request, &ctx) {
+            match handle_daemon_request(method, &request, &ctx).await {
                 Ok(value) => rpc_ok(Value::Null, value),
                 Err(err) => rpc_error(Value::Null, err.code, err.message),
             }
@@ -1228,7 +1279,7 @@ async fn handle_connection<S: fluxd_storage::KeyValueStore + Send + Sync + 'stat
         return Ok(());
     }
 
-    let rpc_response = match handle_rpc_request(&request.body, &ctx) {
+    let rpc_response = match handle_rpc_request(&request.body, &ctx).await {
         Ok(value) => value,
         Err(err) => err,
     };
@@ -1353,7 +1404,7 @@ async fn handle_json_rpc_getblocktemplate_longpoll<S: fluxd_storage::KeyValueSto
     Ok(Some(rpc_response))
 }
 
-fn handle_daemon_request<S: fluxd_storage::KeyValueStore + 'static>(
+async fn handle_daemon_request<S: fluxd_storage::KeyValueStore + 'static>(
     method: &str,
     request: &HttpRequest,
     ctx: &RpcContext<S>,
@@ -1365,10 +1416,10 @@ fn handle_daemon_request<S: fluxd_storage::KeyValueStore + 'static>(
     } else {
         return Err(RpcError::new(RPC_INVALID_REQUEST, "method not allowed"));
     };
-    dispatch_method(method, params, ctx)
+    dispatch_method_with_peer_feedback(method, params, ctx).await
 }
 
-fn handle_rpc_request<S: fluxd_storage::KeyValueStore + 'static>(
+async fn handle_rpc_request<S: fluxd_storage::KeyValueStore + 'static>(
     body: &[u8],
     ctx: &RpcContext<S>,
 ) -> Result<Value, Value> {
@@ -1404,7 +1455,7 @@ fn handle_rpc_request<S: fluxd_storage::KeyValueStore + 'static>(
         }
     };
 
-    let result = dispatch_method(method, params, ctx);
+    let result = dispatch_method_with_peer_feedback(method, params, ctx).await;
 
     match result {
         Ok(value) => Ok(rpc_ok(id, value)),
@@ -1412,6 +1463,53 @@ fn handle_rpc_request<S: fluxd_storage::KeyValueStore + 'static>(
     }
 }
 
+/// `sendrawtransaction` holds its reply for up to `--txrejectwait` after relaying, so a
+/// transaction that peers refuse is reported to the submitter instead of silently never
+/// confirming. Every other method dispatches directly.
+async fn dispatch_method_with_peer_feedback<S: fluxd_storage::KeyValueStore + 'static>(
+    method: &str,
+    params: Vec<Value>,
+    ctx: &RpcContext<S>,
+) -> Result<Value, RpcError> {
+    if method != "sendrawtransaction" {
+        return dispatch_method(method, params, ctx);
+    }
+    let pending = params
+        .first()
+        .and_then(Value::as_str)
+        .and_then(bytes_from_hex)
+        .and_then(|raw| Transaction::consensus_decode(&raw).ok())
+        .and_then(|tx| tx.txid().ok())
+        .and_then(|txid| Some((txid, ctx.tx_feedback.register(txid)?)));
+    let result = dispatch_method(method, params, ctx);
+    let Some((txid, receiver)) = pending else {
+        return result;
+    };
+    if result.is_err() {
+        ctx.tx_feedback.forget(&txid);
+        return result;
+    }
+    let feedback = tokio::time::timeout(ctx.tx_feedback.wait(), receiver)
+        .await
+        .ok()
+        .and_then(Result::ok);
+    ctx.tx_feedback.forget(&txid);
+    apply_peer_feedback(result, feedback)
+}
+
+fn apply_peer_feedback(
+    result: Result<Value, RpcError>,
+    feedback: Option<TxFeedback>,
+) -> Result<Value, RpcError> {
+    match feedback {
+        Some(TxFeedback::Rejected { code, reason }) => Err(RpcError::new(
+            RPC_TRANSACTION_REJECTED,
+            format!("peer rejected: {reason} (code {code})"),
+        )),
+        Some(TxFeedback::NotFound) | None => result,
+    }
+}
+
 fn parse_body_params(body: &[u8]) -> Result<Vec<Value>, RpcError> {
     if body.is_empty() {
         return Ok(Vec::new());
@@ -1623,6 +1721,7 @@ fn dispatch_method<S: fluxd_storage::KeyValueStore + 'static>(
         "ping" => rpc_ping(params),
         "stop" => rpc_stop(params, shutdown_tx),
         "restart" => rpc_restart(params, shutdown_tx),
+        "setloglevel" => rpc_setloglevel(params),
         "reindex" => rpc_reindex(params, data_dir, shutdown_tx),
         "rescanblockchain" => rpc_rescanblockchain(chainstate, wallet, params),
         "importaddress" => rpc_importaddress(chainstate, wallet, params, chain_params),
@@ -1692,6 +1791,12 @@ fn dispatch_method<S: fluxd_storage::KeyValueStore + 'static>(
         "signrawtransaction" => {
             rpc_signrawtransaction(chainstate, mempool, wallet, params, chain_params)
         }
+        "createpsbt" => rpc_createpsbt(chainstate, params, chain_params),
+        "combinepsbt" => rpc_combinepsbt(params),
+        "walletprocesspsbt" => {
+            rpc_walletprocesspsbt(chainstate, mempool, wallet, params, chain_params)
+        }
+        "finalizepsbt" => rpc_finalizepsbt(params),
         "sendrawtransaction" => rpc_sendrawtransaction(
             chainstate,
             mempool,
@@ -1744,13 +1849,17 @@ fn dispatch_method<S: fluxd_storage::KeyValueStore + 'static>(
         ),
         "getmempoolinfo" => rpc_getmempoolinfo(params, mempool),
         "getrawmempool" => rpc_getrawmempool(chainstate, params, mempool),
+        "getmempoolancestors" => rpc_getmempoolrelatives(chainstate, params, mempool, false),
+        "getmempooldescendants" => rpc_getmempoolrelatives(chainstate, params, mempool, true),
         "gettxout" => rpc_gettxout(chainstate, mempool, params, chain_params),
         "gettxoutproof" => rpc_gettxoutproof(chainstate, params),
         "verifytxoutproof" => rpc_verifytxoutproof(chainstate, params),
         "gettxoutsetinfo" => rpc_gettxoutsetinfo(chainstate, params, data_dir),
         "getindexstats" => rpc_getindexstats(chainstate, params),
+        "getindexinfo" => rpc_getindexinfo(chainstate, params),
         "getrichlist" => rpc_getrichlist(chainstate, params, chain_params),
         "getblockdeltas" => rpc_getblockdeltas(chainstate, params, chain_params),
+        "getblockstats" => rpc_getblockstats(chainstate, params, chain_params),
         "getspentinfo" => rpc_getspentinfo(chainstate, params),
         "getaddressutxos" => rpc_getaddressutxos(chainstate, params, chain_params),
         "getaddressbalance" => rpc_getaddressbalance(chainstate, params, chain_params),
@@ -1793,11 +1902,34 @@ fn dispatch_method<S: fluxd_storage::KeyValueStore + 'static>(
             chain_params,
             mempool_flags,
         ),
+        "generate" => rpc_generate(
+            chainstate,
+            write_lock,
+            mempool,
+            mempool_policy,
+            fee_estimator,
+            wallet,
+            miner_address,
+            params,
+            chain_params,
+            mempool_flags,
+        ),
+        "generatetoaddress" => rpc_generatetoaddress(
+            chainstate,
+            write_lock,
+            mempool,
+            mempool_policy,
+            fee_estimator,
+            params,
+            chain_params,
+            mempool_flags,
+        ),
         "getnetworkhashps" => rpc_getnetworkhashps(chainstate, params, chain_params),
         "getnetworksolps" => rpc_getnetworksolps(chainstate, params, chain_params),
         "getlocalsolps" => rpc_getlocalsolps(params, header_metrics),
         "estimatefee" => rpc_estimatefee(params, fee_estimator),
         "estimatepriority" => rpc_estimatepriority(params, fee_estimator),
+        "estimaterawfee" => rpc_estimaterawfee(params, fee_estimator),
         "prioritisetransaction" => rpc_prioritisetransaction(params, mempool),
         "getconnectioncount" => rpc_getconnectioncount(params, peer_registry, net_totals),
         "getnettotals" => rpc_getnettotals(params, net_totals),
@@ -1812,10 +1944,10 @@ fn dispatch_method<S: fluxd_storage::KeyValueStore + 'static>(
         "getaddednodeinfo" => {
             rpc_getaddednodeinfo(params, chain_params, peer_registry, added_nodes)
         }
-        "getfluxnodecount" | "getzelnodecount" => rpc_getfluxnodecount(chainstate, params),
-        "listfluxnodes" | "listzelnodes" => {
-            rpc_viewdeterministicfluxnodelist(chainstate, params, chain_params)
+        "getfluxnodecount" | "getzelnodecount" => {
+            rpc_getfluxnodecount(chainstate, params, chain_params)
         }
+        "listfluxnodes" | "listzelnodes" => rpc_listfluxnodes(chainstate, params, chain_params),
         "viewdeterministicfluxnodelist" | "viewdeterministiczelnodelist" => {
             rpc_viewdeterministicfluxnodelist(chainstate, params, chain_params)
         }
@@ -1921,6 +2053,11 @@ fn dispatch_method<S: fluxd_storage::KeyValueStore + 'static>(
             )
         }
         "verifychain" => rpc_verifychain(chainstate, params),
+        "debugvalidateblock" => {
+            rpc_debugvalidateblock(chainstate, params, chain_params, mempool_flags)
+        }
+        "invalidateblock" => rpc_invalidateblock(chainstate, write_lock, params),
+        "reconsiderblock" => rpc_reconsiderblock(chainstate, write_lock, params),
         "validateaddress" => rpc_validateaddress(wallet, params, chain_params),
         "zcrawjoinsplit" => rpc_zcrawjoinsplit(chainstate, params, chain_params, params_dir),
         "zcrawreceive" => rpc_zcrawreceive(chainstate, params, chain_params),
@@ -2147,6 +2284,59 @@ fn rpc_restart(params: Vec<Value>, shutdown_tx: &watch::Sender<bool>) -> Result<
     ))
 }
 
+/// `setloglevel <level|reset> [target]`: changes the global level, or with `target` the
+/// override for that module path and everything below it. `reset` restores the configured level
+/// and clears all overrides, or with `target` drops just that override.
+fn rpc_setloglevel(params: Vec<Value>) -> Result<Value, RpcError> {
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "setloglevel expects 1 or 2 parameters",
+        ));
+    }
+    let raw = params[0]
+        .as_str()
+        .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "level must be a string"))?;
+    let level = if raw.trim().eq_ignore_ascii_case("reset") {
+        None
+    } else {
+        Some(crate::logging::Level::parse(raw).ok_or_else(|| {
+            RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "level must be one of error, warn, info, debug, trace, reset",
+            )
+        })?)
+    };
+    let target = match params.get(1) {
+        None | Some(Value::Null) => None,
+        Some(value) => {
+            let target = value
+                .as_str()
+                .map(str::trim)
+                .filter(|target| !target.is_empty())
+                .ok_or_else(|| {
+                    RpcError::new(RPC_INVALID_PARAMETER, "target must be a non-empty string")
+                })?;
+            Some(target)
+        }
+    };
+    match (target, level) {
+        (Some(target), level) => crate::logging::set_target_level(target, level),
+        (None, Some(level)) => crate::logging::set_level(level),
+        (None, None) => crate::logging::reset_levels(),
+    }
+
+    let targets: serde_json::Map<String, Value> = crate::logging::target_levels()
+        .into_iter()
+        .map(|(target, level)| (target, Value::String(level.as_str().to_string())))
+        .collect();
+    Ok(json!({
+        "level": crate::logging::level().as_str(),
+        "configured": crate::logging::configured_level().as_str(),
+        "targets": targets,
+    }))
+}
+
 fn rpc_reindex(
     params: Vec<Value>,
     data_dir: &Path,
@@ -2240,7 +2430,10 @@ fn rpc_rescanblockchain<S: fluxd_storage::KeyValueStore>(
     let mut blocks: std::collections::BTreeMap<(u32, u64, u32), Vec<(Hash256, u32)>> =
         std::collections::BTreeMap::new();
     for txid in &txids {
-        if let Some(location) = chainstate.tx_location(txid).map_err(map_internal)? {
+        if let Some(location) = chainstate
+            .tx_location(&Txid::from_internal(*txid))
+            .map_err(map_internal)?
+        {
             blocks
                 .entry((
                     location.block.file_id,
@@ -2831,11 +3024,14 @@ fn rpc_listaddressgroupings<S: fluxd_storage::KeyValueStore>(
             let prevouts = mempool_guard.prevouts_for_tx(&entry.tx);
             for input in &entry.tx.vin {
                 let mut script_pubkey = None;
-                if let Ok(Some(utxo)) = chainstate.utxo_entry(&input.prevout) {
+                if let Ok(Some(utxo)) =
+                    chainstate.utxo_entry(&input.prevout.txid(), input.prevout.index)
+                {
                     script_pubkey = Some(utxo.script_pubkey);
                 } else if let Some(prev) = prevouts.get(&input.prevout) {
                     script_pubkey = Some(prev.script_pubkey.clone());
-                } else if let Ok(Some(prev_location)) = chainstate.tx_location(&input.prevout.hash)
+                } else if let Ok(Some(prev_location)) =
+                    chainstate.tx_location(&input.prevout.txid())
                 {
                     if let Ok(prev_bytes) = chainstate.read_block(prev_location.block) {
                         if let Ok(prev_block) = Block::consensus_decode(&prev_bytes) {
@@ -2989,7 +3185,7 @@ fn collect_wallet_utxos<S: fluxd_storage::KeyValueStore>(
                 continue;
             }
             let entry = chainstate
-                .utxo_entry(&outpoint)
+                .utxo_entry(&outpoint.txid(), outpoint.index)
                 .map_err(map_internal)?
                 .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "missing utxo entry"))?;
             let height_i32 = i32::try_from(entry.height).unwrap_or(0);
@@ -3749,7 +3945,7 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
                 let mut prev_script_pubkey = None;
 
                 match chainstate
-                    .utxo_entry(&input.prevout)
+                    .utxo_entry(&input.prevout.txid(), input.prevout.index)
                     .map_err(map_internal)?
                 {
                     Some(utxo) => {
@@ -3761,7 +3957,7 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
                             prev_value = Some(prev.value);
                             prev_script_pubkey = Some(prev.script_pubkey.clone());
                         } else if let Ok(Some(prev_location)) =
-                            chainstate.tx_location(&input.prevout.hash)
+                            chainstate.tx_location(&input.prevout.txid())
                         {
                             if let Ok(prev_bytes) = chainstate.read_block(prev_location.block) {
                                 if let Ok(prev_block) = Block::consensus_decode(&prev_bytes) {
@@ -3941,7 +4137,10 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
         }
     }
 
-    let location = match chainstate.tx_location(&txid).map_err(map_internal)? {
+    let location = match chainstate
+        .tx_location(&Txid::from_internal(txid))
+        .map_err(map_internal)?
+    {
         Some(location) => location,
         None => {
             let raw = wallet_tx_raw.ok_or_else(|| {
@@ -3986,7 +4185,7 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
                 let mut prev_script_pubkey = None;
 
                 match chainstate
-                    .utxo_entry(&input.prevout)
+                    .utxo_entry(&input.prevout.txid(), input.prevout.index)
                     .map_err(map_internal)?
                 {
                     Some(utxo) => {
@@ -3994,7 +4193,8 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
                         prev_script_pubkey = Some(utxo.script_pubkey);
                     }
                     None => {
-                        if let Ok(Some(prev_location)) = chainstate.tx_location(&input.prevout.hash)
+                        if let Ok(Some(prev_location)) =
+                            chainstate.tx_location(&input.prevout.txid())
                         {
                             if let Ok(prev_bytes) = chainstate.read_block(prev_location.block) {
                                 if let Ok(prev_block) = Block::consensus_decode(&prev_bytes) {
@@ -4054,7 +4254,7 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
             let receive_category = if is_coinbase {
                 if confirmations < 1 {
                     "orphan"
-                } else if confirmations < COINBASE_MATURITY {
+                } else if confirmations < chain_params.consensus.coinbase_maturity {
                     "immature"
                 } else {
                     "generate"
@@ -4182,6 +4382,12 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
             if is_coinbase {
                 if let Value::Object(map) = &mut obj {
                     map.insert("generated".to_string(), Value::Bool(true));
+                    map.insert(
+                        "maturity_remaining".to_string(),
+                        json!(chain_params
+                            .consensus
+                            .coinbase_maturity_remaining(confirmations)),
+                    );
                 }
             }
 
@@ -4286,7 +4492,7 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
             }
         }
         if prev_value.is_none() {
-            if let Ok(Some(prev_location)) = chainstate.tx_location(&input.prevout.hash) {
+            if let Ok(Some(prev_location)) = chainstate.tx_location(&input.prevout.txid()) {
                 if let Ok(prev_bytes) = chainstate.read_block(prev_location.block) {
                     if let Ok(prev_block) = Block::consensus_decode(&prev_bytes) {
                         let idx = prev_location.index as usize;
@@ -4343,7 +4549,7 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
     let receive_category = if is_coinbase {
         if confirmations < 1 {
             "orphan"
-        } else if confirmations < COINBASE_MATURITY {
+        } else if confirmations < chain_params.consensus.coinbase_maturity {
             "immature"
         } else {
             "generate"
@@ -4469,6 +4675,12 @@ fn rpc_gettransaction<S: fluxd_storage::KeyValueStore>(
     if is_coinbase {
         if let Value::Object(map) = &mut obj {
             map.insert("generated".to_string(), Value::Bool(true));
+            map.insert(
+                "maturity_remaining".to_string(),
+                json!(chain_params
+                    .consensus
+                    .coinbase_maturity_remaining(confirmations)),
+            );
         }
     }
 
@@ -4602,10 +4814,10 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
     params: Vec<Value>,
     chain_params: &ChainParams,
 ) -> Result<Value, RpcError> {
-    if params.len() > 4 {
+    if params.len() > 5 {
         return Err(RpcError::new(
             RPC_INVALID_PARAMETER,
-            "listtransactions expects 0 to 4 parameters",
+            "listtransactions expects 0 to 5 parameters",
         ));
     }
     if let Some(value) = params.first() {
@@ -4659,6 +4871,42 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
         Some(value) => parse_bool(value)?,
     };
 
+    // `Some(cursor)` switches to cursor paging over confirmed history; the inner `None` starts
+    // from the tip.
+    let paging = match params.get(4) {
+        None | Some(Value::Null) => None,
+        Some(Value::Object(map)) => match map.get("cursor") {
+            None | Some(Value::Null) => Some(None),
+            Some(Value::String(raw)) => Some(Some(parse_wallet_history_cursor(raw)?)),
+            Some(_) => {
+                return Err(RpcError::new(
+                    RPC_INVALID_PARAMETER,
+                    "cursor must be a string",
+                ))
+            }
+        },
+        Some(_) => {
+            return Err(RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "options must be an object",
+            ))
+        }
+    };
+    if paging.is_some() {
+        if from != 0 {
+            return Err(RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "from must be 0 when paging by cursor",
+            ));
+        }
+        if count == 0 {
+            return Err(RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "count must be positive when paging by cursor",
+            ));
+        }
+    }
+
     let (wallet_scripts, stored_transactions) = {
         let guard = wallet
             .lock()
@@ -4674,6 +4922,9 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
         (scripts, stored_transactions)
     };
     if wallet_scripts.is_empty() {
+        if paging.is_some() {
+            return Ok(json!({ "transactions": [], "next_cursor": null }));
+        }
         return Ok(Value::Array(Vec::new()));
     }
 
@@ -4684,7 +4935,7 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
     enum CandidateOrder {
         Mempool { time: u64 },
         WalletStore { time: u64 },
-        Chain { height: u32, tx_index: u32 },
+        Chain,
     }
 
     #[derive(Clone, Copy)]
@@ -4694,111 +4945,75 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
     }
 
     let mut unconfirmed_candidates = Vec::new();
-    if let Ok(mempool_guard) = mempool.lock() {
-        for entry in mempool_guard.entries() {
-            let mut touches_wallet = entry
-                .tx
-                .vout
-                .iter()
-                .any(|out| wallet_contains_script(&out.script_pubkey));
-            if !touches_wallet {
-                let prevouts = mempool_guard.prevouts_for_tx(&entry.tx);
-                for input in &entry.tx.vin {
-                    let script_pubkey = match chainstate
-                        .utxo_entry(&input.prevout)
-                        .map_err(map_internal)?
-                    {
-                        Some(utxo) => utxo.script_pubkey,
-                        None => match prevouts.get(&input.prevout) {
-                            Some(prev) => prev.script_pubkey.clone(),
-                            None => continue,
-                        },
-                    };
-                    if wallet_contains_script(&script_pubkey) {
-                        touches_wallet = true;
-                        break;
+    if paging.is_none() {
+        if let Ok(mempool_guard) = mempool.lock() {
+            for entry in mempool_guard.entries() {
+                let mut touches_wallet = entry
+                    .tx
+                    .vout
+                    .iter()
+                    .any(|out| wallet_contains_script(&out.script_pubkey));
+                if !touches_wallet {
+                    let prevouts = mempool_guard.prevouts_for_tx(&entry.tx);
+                    for input in &entry.tx.vin {
+                        let script_pubkey = match chainstate
+                            .utxo_entry(&input.prevout.txid(), input.prevout.index)
+                            .map_err(map_internal)?
+                        {
+                            Some(utxo) => utxo.script_pubkey,
+                            None => match prevouts.get(&input.prevout) {
+                                Some(prev) => prev.script_pubkey.clone(),
+                                None => continue,
+                            },
+                        };
+                        if wallet_contains_script(&script_pubkey) {
+                            touches_wallet = true;
+                            break;
+                        }
                     }
                 }
+                if touches_wallet {
+                    unconfirmed_candidates.push(Candidate {
+                        txid: entry.txid,
+                        order: CandidateOrder::Mempool { time: entry.time },
+                    });
+                }
             }
-            if touches_wallet {
-                unconfirmed_candidates.push(Candidate {
-                    txid: entry.txid,
-                    order: CandidateOrder::Mempool { time: entry.time },
-                });
-            }
-        }
-    }
-
-    let mut seen_chain: HashMap<Hash256, (u32, u32)> = HashMap::new();
-    for script_pubkey in &wallet_scripts {
-        let deltas = chainstate
-            .address_deltas(script_pubkey)
-            .map_err(map_internal)?;
-        for delta in deltas {
-            seen_chain
-                .entry(delta.txid)
-                .and_modify(|entry| {
-                    entry.0 = entry.0.max(delta.height);
-                    entry.1 = entry.1.max(delta.tx_index);
-                })
-                .or_insert((delta.height, delta.tx_index));
         }
     }
-    let mut chain_candidates = seen_chain
-        .into_iter()
-        .map(|(txid, (height, tx_index))| Candidate {
-            txid,
-            order: CandidateOrder::Chain { height, tx_index },
-        })
-        .collect::<Vec<_>>();
-    chain_candidates.sort_by(|a, b| match (a.order, b.order) {
-        (
-            CandidateOrder::Chain {
-                height: a_height,
-                tx_index: a_index,
-            },
-            CandidateOrder::Chain {
-                height: b_height,
-                tx_index: b_index,
-            },
-        ) => b_height
-            .cmp(&a_height)
-            .then_with(|| b_index.cmp(&a_index))
-            .then_with(|| b.txid.cmp(&a.txid)),
-        _ => std::cmp::Ordering::Equal,
-    });
 
-    let mut seen_txids: HashSet<Hash256> = unconfirmed_candidates
+    let unconfirmed_txids: HashSet<Hash256> = unconfirmed_candidates
         .iter()
         .map(|cand| cand.txid)
         .collect();
-    for cand in &chain_candidates {
-        seen_txids.insert(cand.txid);
-    }
-
+    let mut chain_extras = Vec::new();
     for (txid, time) in stored_transactions {
-        if !seen_txids.insert(txid) {
+        if unconfirmed_txids.contains(&txid) {
             continue;
         }
-        unconfirmed_candidates.push(Candidate {
-            txid,
-            order: CandidateOrder::WalletStore { time },
-        });
+        match wallet_tx_chain_position(chainstate, &txid)? {
+            Some(position) => chain_extras.push(position),
+            None if paging.is_none() => unconfirmed_candidates.push(Candidate {
+                txid,
+                order: CandidateOrder::WalletStore { time },
+            }),
+            None => {}
+        }
     }
 
     let order_rank = |order| match order {
         CandidateOrder::Mempool { .. } => 0u8,
         CandidateOrder::WalletStore { .. } => 1u8,
-        CandidateOrder::Chain { .. } => 2u8,
+        CandidateOrder::Chain => 2u8,
     };
     unconfirmed_candidates.sort_by(|a, b| {
         let a_time = match a.order {
             CandidateOrder::Mempool { time } | CandidateOrder::WalletStore { time } => time,
-            CandidateOrder::Chain { .. } => 0,
+            CandidateOrder::Chain => 0,
         };
         let b_time = match b.order {
             CandidateOrder::Mempool { time } | CandidateOrder::WalletStore { time } => time,
-            CandidateOrder::Chain { .. } => 0,
+            CandidateOrder::Chain => 0,
         };
         b_time
             .cmp(&a_time)
@@ -4806,27 +5021,42 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
             .then_with(|| b.txid.cmp(&a.txid))
     });
 
-    let mut candidates = Vec::with_capacity(
-        unconfirmed_candidates
-            .len()
-            .saturating_add(chain_candidates.len()),
-    );
-    candidates.extend(unconfirmed_candidates);
-    candidates.extend(chain_candidates);
-
     let from = usize::try_from(from).unwrap_or(usize::MAX);
     let count = usize::try_from(count).unwrap_or(0);
     if count == 0 {
         return Ok(Value::Array(Vec::new()));
     }
 
+    let mut script_hashes = wallet_scripts
+        .iter()
+        .filter_map(|script_pubkey| {
+            fluxd_chainstate::address_index::script_hash(script_pubkey.as_slice())
+        })
+        .collect::<Vec<_>>();
+    script_hashes.sort();
+    script_hashes.dedup();
+    let mut chain_history =
+        WalletChainHistory::new(chainstate, script_hashes, chain_extras, paging.flatten())?;
+
     let target = from.saturating_add(count);
     let mut newest_to_oldest = Vec::new();
-
-    'outer: for cand in candidates {
-        if newest_to_oldest.len() >= target {
-            break;
-        }
+    let mut unconfirmed = unconfirmed_candidates.into_iter();
+    let mut last_position = None;
+
+    'outer: while newest_to_oldest.len() < target {
+        let cand = match unconfirmed.next() {
+            Some(cand) => cand,
+            None => match chain_history.next_position()? {
+                Some(position) => {
+                    last_position = Some(position);
+                    Candidate {
+                        txid: position.txid,
+                        order: CandidateOrder::Chain,
+                    }
+                }
+                None => break,
+            },
+        };
         let view = match rpc_gettransaction(
             chainstate,
             mempool,
@@ -4964,12 +5194,29 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
             }
 
             newest_to_oldest.push(Value::Object(row));
-            if newest_to_oldest.len() >= target {
+            // Cursor pages end on a transaction boundary so the cursor never splits a txid.
+            if paging.is_none() && newest_to_oldest.len() >= target {
                 break 'outer;
             }
         }
     }
 
+    if paging.is_some() {
+        let next_cursor = match last_position {
+            Some(position)
+                if newest_to_oldest.len() >= target && chain_history.next_position()?.is_some() =>
+            {
+                Value::String(position.to_cursor())
+            }
+            _ => Value::Null,
+        };
+        newest_to_oldest.reverse();
+        return Ok(json!({
+            "transactions": newest_to_oldest,
+            "next_cursor": next_cursor,
+        }));
+    }
+
     let from = from.min(newest_to_oldest.len());
     let count = count.min(newest_to_oldest.len().saturating_sub(from));
     let mut out = newest_to_oldest
@@ -4981,6 +5228,155 @@ fn rpc_listtransactions<S: fluxd_storage::KeyValueStore>(
     Ok(Value::Array(out))
 }
 
+const WALLET_HISTORY_INITIAL_WINDOW: u32 = 1_024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct WalletHistoryPosition {
+    height: u32,
+    tx_index: u32,
+    txid: Hash256,
+}
+
+impl WalletHistoryPosition {
+    fn to_cursor(self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.height,
+            self.tx_index,
+            hash256_to_hex(&self.txid)
+        )
+    }
+}
+
+fn parse_wallet_history_cursor(raw: &str) -> Result<WalletHistoryPosition, RpcError> {
+    let invalid = || RpcError::new(RPC_INVALID_PARAMETER, "invalid cursor");
+    let mut parts = raw.trim().splitn(3, ':');
+    let height = parts
+        .next()
+        .and_then(|part| part.parse::<u32>().ok())
+        .ok_or_else(invalid)?;
+    let tx_index = parts
+        .next()
+        .and_then(|part| part.parse::<u32>().ok())
+        .ok_or_else(invalid)?;
+    let txid = parts
+        .next()
+        .and_then(|part| hash256_from_hex(part).ok())
+        .ok_or_else(invalid)?;
+    Ok(WalletHistoryPosition {
+        height,
+        tx_index,
+        txid,
+    })
+}
+
+fn wallet_tx_chain_position<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    txid: &Hash256,
+) -> Result<Option<WalletHistoryPosition>, RpcError> {
+    let Some(location) = chainstate
+        .tx_location(&Txid::from_internal(*txid))
+        .map_err(map_internal)?
+    else {
+        return Ok(None);
+    };
+    let bytes = chainstate
+        .read_block(location.block)
+        .map_err(map_internal)?;
+    let block = Block::consensus_decode(&bytes).map_err(map_internal)?;
+    let Some(entry) = chainstate
+        .header_entry(&block.header.hash())
+        .map_err(map_internal)?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(WalletHistoryPosition {
+        height: u32::try_from(entry.height).unwrap_or(0),
+        tx_index: location.index,
+        txid: *txid,
+    }))
+}
+
+/// Walks confirmed wallet history newest → oldest over the height-ordered address delta keys.
+///
+/// Heights below the tip (or the cursor) are scanned in doubling windows, so a page only reads
+/// the deltas it needs instead of the whole history of every wallet script.
+struct WalletChainHistory<'a, S> {
+    chainstate: &'a ChainState<S>,
+    script_hashes: Vec<Hash256>,
+    extras: Vec<WalletHistoryPosition>,
+    before: Option<WalletHistoryPosition>,
+    next_end_height: Option<u32>,
+    window: u32,
+    buffered: Vec<WalletHistoryPosition>,
+}
+
+impl<'a, S: fluxd_storage::KeyValueStore> WalletChainHistory<'a, S> {
+    fn new(
+        chainstate: &'a ChainState<S>,
+        script_hashes: Vec<Hash256>,
+        extras: Vec<WalletHistoryPosition>,
+        before: Option<WalletHistoryPosition>,
+    ) -> Result<Self, RpcError> {
+        let tip_height = u32::try_from(best_block_height(chainstate)?).unwrap_or(0);
+        let end_height = match before {
+            Some(position) => position.height.min(tip_height),
+            None => tip_height,
+        };
+        Ok(Self {
+            chainstate,
+            script_hashes,
+            extras,
+            before,
+            next_end_height: Some(end_height),
+            window: WALLET_HISTORY_INITIAL_WINDOW,
+            buffered: Vec::new(),
+        })
+    }
+
+    fn next_position(&mut self) -> Result<Option<WalletHistoryPosition>, RpcError> {
+        loop {
+            if let Some(position) = self.buffered.pop() {
+                return Ok(Some(position));
+            }
+            let Some(end_height) = self.next_end_height else {
+                return Ok(None);
+            };
+            let start_height = end_height.saturating_sub(self.window - 1);
+            let mut window = BTreeSet::new();
+            for script_hash in &self.script_hashes {
+                let mut visitor = |delta: fluxd_chainstate::address_deltas::AddressDeltaEntry| {
+                    window.insert(WalletHistoryPosition {
+                        height: delta.height,
+                        tx_index: delta.tx_index,
+                        txid: delta.txid,
+                    });
+                    Ok(())
+                };
+                self.chainstate
+                    .for_each_address_delta_range(
+                        script_hash,
+                        start_height,
+                        end_height,
+                        &mut visitor,
+                    )
+                    .map_err(map_internal)?;
+            }
+            for extra in &self.extras {
+                if (start_height..=end_height).contains(&extra.height) {
+                    window.insert(*extra);
+                }
+            }
+            if let Some(before) = self.before {
+                window.retain(|position| *position < before);
+            }
+            self.buffered = window.into_iter().collect();
+            self.next_end_height = start_height.checked_sub(1);
+            self.window = self.window.saturating_mul(2);
+        }
+    }
+}
+
 fn rpc_listsinceblock<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     mempool: &Mutex<Mempool>,
@@ -5072,7 +5468,7 @@ fn rpc_listsinceblock<S: fluxd_storage::KeyValueStore>(
                     let prevouts = mempool_guard.prevouts_for_tx(&entry.tx);
                     for input in &entry.tx.vin {
                         let script_pubkey = match chainstate
-                            .utxo_entry(&input.prevout)
+                            .utxo_entry(&input.prevout.txid(), input.prevout.index)
                             .map_err(map_internal)?
                         {
                             Some(utxo) => utxo.script_pubkey,
@@ -5341,6 +5737,7 @@ fn rpc_listsinceblock<S: fluxd_storage::KeyValueStore>(
         chainstate
             .height_hash(lastblock_height)
             .map_err(map_internal)?
+            .map(BlockHash::to_internal)
             .unwrap_or([0u8; 32])
     };
 
@@ -5795,11 +6192,7 @@ fn rpc_getdbinfo<S: fluxd_storage::KeyValueStore>(
     data_dir: &Path,
 ) -> Result<Value, RpcError> {
     ensure_no_params(&params)?;
-    let backend = match store {
-        Store::Fjall(_) => Backend::Fjall,
-        Store::Memory(_) => Backend::Memory,
-    };
-    db_info::collect_db_info(chainstate, store, data_dir, backend, false, false)
+    db_info::collect_db_info(chainstate, store, data_dir, store.backend(), false, false)
         .map_err(|err| RpcError::new(RPC_INTERNAL_ERROR, err))
 }
 
@@ -5990,6 +6383,7 @@ fn rpc_getblockhash<S: fluxd_storage::KeyValueStore>(
     let hash = chainstate
         .height_hash(height)
         .map_err(map_internal)?
+        .map(BlockHash::to_internal)
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "block not found"))?;
     Ok(Value::String(hash256_to_hex(&hash)))
 }
@@ -6211,7 +6605,8 @@ fn rpc_getblockchaininfo<S: fluxd_storage::KeyValueStore>(
         .as_ref()
         .map(|tip| hex_bytes(&tip.chainwork))
         .unwrap_or_else(|| "00".to_string());
-    let size_on_disk = db_info::dir_size_cached(data_dir, Duration::from_secs(30)).unwrap_or(0);
+    let mediantime = median_time_past(chainstate, best_block_height)?;
+    let size_on_disk = db_info::size_on_disk(data_dir).unwrap_or(0);
     let verificationprogress = if best_header_height > 0 && best_block_height >= 0 {
         (best_block_height as f64 / best_header_height as f64).min(1.0)
     } else {
@@ -6220,8 +6615,8 @@ fn rpc_getblockchaininfo<S: fluxd_storage::KeyValueStore>(
 
     let upgrades = build_upgrade_info(chain_params, best_block_height);
     let consensus = json!({
-        "chaintip": format!("{:08x}", current_epoch_branch_id(best_block_height, &chain_params.consensus.upgrades)),
-        "nextblock": format!("{:08x}", current_epoch_branch_id(best_block_height + 1, &chain_params.consensus.upgrades)),
+        "chaintip": format!("{:08x}", branch_id_for_height(best_block_height, &chain_params.consensus.upgrades)),
+        "nextblock": format!("{:08x}", branch_id_for_height(best_block_height + 1, &chain_params.consensus.upgrades)),
     });
 
     let commitments = chainstate.sprout_commitment_count().map_err(map_internal)?;
@@ -6243,6 +6638,7 @@ fn rpc_getblockchaininfo<S: fluxd_storage::KeyValueStore>(
         "headers": best_header_height.max(0),
         "bestblockhash": best_block_hash.map(|hash| hash256_to_hex(&hash)),
         "difficulty": difficulty,
+        "mediantime": mediantime,
         "verificationprogress": verificationprogress,
         "chainwork": chainwork,
         "pruned": false,
@@ -6375,6 +6771,7 @@ fn rpc_getchaintips<S: fluxd_storage::KeyValueStore>(
             "height": entry.height,
             "hash": hash256_to_hex(&hash),
             "branchlen": branchlen,
+            "chainwork": hex_bytes(&entry.chainwork),
             "status": status,
         }));
     }
@@ -6439,24 +6836,16 @@ fn rpc_createrawtransaction<S: fluxd_storage::KeyValueStore>(
         .map_err(map_internal)?
         .map(|tip| tip.height.saturating_add(1))
         .unwrap_or(1);
-    let sapling_active = network_upgrade_active(
-        next_height,
-        &chain_params.consensus.upgrades,
-        UpgradeIndex::Acadia,
-    );
+    let rules = consensus_rules(next_height, &chain_params.consensus);
 
     let mut tx = Transaction {
-        f_overwintered: sapling_active,
-        version: if sapling_active { 4 } else { 1 },
-        version_group_id: if sapling_active {
-            SAPLING_VERSION_GROUP_ID
-        } else {
-            0
-        },
+        f_overwintered: rules.overwinter_active,
+        version: rules.min_tx_version,
+        version_group_id: rules.version_group_id,
         vin: Vec::new(),
         vout: Vec::new(),
         lock_time: 0,
-        expiry_height: if sapling_active {
+        expiry_height: if rules.sapling_active {
             (next_height as u32).saturating_add(DEFAULT_TX_EXPIRY_DELTA)
         } else {
             0
@@ -6468,6 +6857,7 @@ fn rpc_createrawtransaction<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -6475,7 +6865,7 @@ fn rpc_createrawtransaction<S: fluxd_storage::KeyValueStore>(
         tx.lock_time = parse_u32(&params[2], "locktime")?;
     }
     if params.len() > 3 && !params[3].is_null() {
-        if !sapling_active {
+        if !rules.sapling_active {
             return Err(RpcError::new(
                 RPC_INVALID_PARAMETER,
                 "expiryheight can only be used if sapling is active when the transaction is mined",
@@ -6543,7 +6933,8 @@ fn rpc_createrawtransaction<S: fluxd_storage::KeyValueStore>(
                 AddressError::InvalidLength
                 | AddressError::InvalidCharacter
                 | AddressError::InvalidChecksum
-                | AddressError::UnknownPrefix => RpcError::new(
+                | AddressError::UnknownPrefix
+                | AddressError::InvalidWitnessVersion => RpcError::new(
                     RPC_INVALID_ADDRESS_OR_KEY,
                     format!("Invalid Flux address: {address}"),
                 ),
@@ -6850,7 +7241,7 @@ fn rpc_zcrawjoinsplit<S: fluxd_storage::KeyValueStore>(
         .map_err(map_internal)?
         .map(|block| block.height + 1)
         .unwrap_or(0);
-    let branch_id = current_epoch_branch_id(next_height, &chain_params.consensus.upgrades);
+    let branch_id = branch_id_for_height(next_height, &chain_params.consensus.upgrades);
     let sighash = signature_hash(&tx, None, &[], 0, SighashType(SIGHASH_ALL), branch_id)
         .map_err(|_| RpcError::new(RPC_INTERNAL_ERROR, "failed to compute joinsplit sighash"))?;
     tx.join_split_sig = join_split_keypair.sign(&sighash);
@@ -7223,7 +7614,7 @@ fn read_transaction_by_id<S: fluxd_storage::KeyValueStore>(
     txid: &Hash256,
 ) -> Result<Transaction, RpcError> {
     let location = chainstate
-        .tx_location(txid)
+        .tx_location(&Txid::from_internal(*txid))
         .map_err(map_internal)?
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "tx not found"))?;
     let bytes = chainstate
@@ -9200,6 +9591,12 @@ fn rpc_validateaddress(
         .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "address must be a string"))?;
     let script_pubkey = match address_to_script_pubkey(address, chain_params.network) {
         Ok(script) => script,
+        Err(_) if decode_bech32(address).is_ok() => {
+            return Ok(json!({
+                "isvalid": false,
+                "error": "bech32 witness addresses are not supported on Flux",
+            }))
+        }
         Err(_) => {
             return Ok(json!({
                 "isvalid": false,
@@ -9336,12 +9733,10 @@ fn rpc_verifymessage(params: Vec<Value>, chain_params: &ChainParams) -> Result<V
         .decode(signature.as_bytes())
         .map_err(|_| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Malformed base64 encoding"))?;
 
-    let recovered_pubkey = match recover_signed_message_pubkey(&sig_bytes, message.as_bytes()) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Ok(Value::Bool(false)),
-    };
-    let recovered_hash = hash160(&recovered_pubkey);
-    Ok(Value::Bool(recovered_hash == expected_key_hash))
+    let matched =
+        verify_signed_message_key_hash(&expected_key_hash, &sig_bytes, message.as_bytes())
+            .unwrap_or(None);
+    Ok(Value::Bool(matched.is_some()))
 }
 
 fn multisig_pubkey_from_input(
@@ -9592,10 +9987,10 @@ fn rpc_getrawtransaction<S: fluxd_storage::KeyValueStore>(
     params: Vec<Value>,
     chain_params: &ChainParams,
 ) -> Result<Value, RpcError> {
-    if params.is_empty() || params.len() > 2 {
+    if params.is_empty() || params.len() > 3 {
         return Err(RpcError::new(
             RPC_INVALID_PARAMETER,
-            "getrawtransaction expects 1 or 2 parameters",
+            "getrawtransaction expects 1 to 3 parameters",
         ));
     }
     let txid = parse_hash(&params[0])?;
@@ -9604,28 +9999,66 @@ fn rpc_getrawtransaction<S: fluxd_storage::KeyValueStore>(
     } else {
         false
     };
-    if let Ok(guard) = mempool.lock() {
-        if let Some(entry) = guard.get(&txid) {
-            if !verbose {
-                return Ok(Value::String(hex_bytes(&entry.raw)));
+    let block_hint = match params.get(2) {
+        Some(value) if !value.is_null() => Some(parse_hash(value)?),
+        _ => None,
+    };
+    // A block hint reads that block directly, like bitcoind, skipping the mempool and txindex.
+    if block_hint.is_none() {
+        if let Ok(guard) = mempool.lock() {
+            if let Some(entry) = guard.get(&txid) {
+                if !verbose {
+                    return Ok(Value::String(hex_bytes(&entry.raw)));
+                }
+                let mut obj = match tx_to_json(&entry.tx, chain_params.network)? {
+                    Value::Object(map) => map,
+                    _ => return Err(RpcError::new(RPC_INTERNAL_ERROR, "invalid tx json")),
+                };
+                obj.insert("hex".to_string(), Value::String(hex_bytes(&entry.raw)));
+                return Ok(Value::Object(obj));
             }
-            let mut obj = match tx_to_json(&entry.tx, chain_params.network)? {
-                Value::Object(map) => map,
-                _ => return Err(RpcError::new(RPC_INTERNAL_ERROR, "invalid tx json")),
-            };
-            obj.insert("hex".to_string(), Value::String(hex_bytes(&entry.raw)));
-            return Ok(Value::Object(obj));
         }
     }
-    let location = chainstate
-        .tx_location(&txid)
-        .map_err(map_internal)?
-        .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "transaction not found"))?;
-    let bytes = chainstate
-        .read_block(location.block)
-        .map_err(map_internal)?;
-    let block = fluxd_primitives::block::Block::consensus_decode(&bytes).map_err(map_internal)?;
-    let tx_index = location.index as usize;
+    let (block, tx_index) = match block_hint {
+        Some(block_hash) => {
+            let location = chainstate
+                .block_location(&block_hash)
+                .map_err(map_internal)?
+                .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Block hash not found"))?;
+            let bytes = chainstate.read_block(location).map_err(map_internal)?;
+            let block =
+                fluxd_primitives::block::Block::consensus_decode(&bytes).map_err(map_internal)?;
+            let tx_index = block
+                .transactions
+                .iter()
+                .position(|tx| tx.txid().is_ok_and(|id| id == txid))
+                .ok_or_else(|| {
+                    RpcError::new(
+                        RPC_INVALID_ADDRESS_OR_KEY,
+                        "No such transaction found in the provided block",
+                    )
+                })?;
+            (block, tx_index)
+        }
+        None => {
+            let location = chainstate
+                .tx_location(&Txid::from_internal(txid))
+                .map_err(map_internal)?;
+            let Some(location) = location else {
+                require_synced_index(chainstate, "txindex")?;
+                return Err(RpcError::new(
+                    RPC_INVALID_ADDRESS_OR_KEY,
+                    "transaction not found",
+                ));
+            };
+            let bytes = chainstate
+                .read_block(location.block)
+                .map_err(map_internal)?;
+            let block =
+                fluxd_primitives::block::Block::consensus_decode(&bytes).map_err(map_internal)?;
+            (block, location.index as usize)
+        }
+    };
     let tx = block
         .transactions
         .get(tx_index)
@@ -9644,6 +10077,12 @@ fn rpc_getrawtransaction<S: fluxd_storage::KeyValueStore>(
         let best_height = best_block_height(chainstate)?;
         let confirmations =
             confirmations_for_height(chainstate, entry.height, best_height, &block_hash)?;
+        if block_hint.is_some() {
+            obj.insert(
+                "in_active_chain".to_string(),
+                Value::Bool(confirmations > 0),
+            );
+        }
         obj.insert(
             "blockhash".to_string(),
             Value::String(hash256_to_hex(&block_hash)),
@@ -9840,7 +10279,10 @@ fn resolve_prevout_info<S: fluxd_storage::KeyValueStore>(
     if let Some(prevout) = overrides.get(outpoint) {
         return Ok(Some(prevout.clone()));
     }
-    if let Some(entry) = chainstate.utxo_entry(outpoint).map_err(map_internal)? {
+    if let Some(entry) = chainstate
+        .utxo_entry(&outpoint.txid(), outpoint.index)
+        .map_err(map_internal)?
+    {
         return Ok(Some(PrevoutInfo {
             value: entry.value,
             script_pubkey: entry.script_pubkey,
@@ -9997,21 +10439,10 @@ fn rpc_signrawtransaction<S: fluxd_storage::KeyValueStore>(
         ));
     }
 
-    let best_block_height = chainstate
-        .best_block()
-        .map_err(map_internal)?
-        .map(|tip| tip.height)
-        .unwrap_or(0);
-    let best_header_height = chainstate
-        .best_header()
-        .map_err(map_internal)?
-        .map(|tip| tip.height)
-        .unwrap_or(best_block_height);
-    let next_height = best_block_height.max(best_header_height).saturating_add(1);
-    let mut branch_id = current_epoch_branch_id(next_height, &chain_params.consensus.upgrades);
-    if let Some(override_branch_id) = parse_branch_id(params.get(4))? {
-        branch_id = override_branch_id;
-    }
+    let branch_id = match parse_branch_id(params.get(4))? {
+        Some(branch_id) => branch_id,
+        None => next_block_branch_id(chainstate, chain_params)?,
+    };
 
     let mut errors: Vec<Value> = Vec::new();
 
@@ -10344,6 +10775,225 @@ fn rpc_signrawtransaction<S: fluxd_storage::KeyValueStore>(
     Ok(Value::Object(out))
 }
 
+fn next_block_branch_id<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    chain_params: &ChainParams,
+) -> Result<u32, RpcError> {
+    let best_block_height = chainstate
+        .best_block()
+        .map_err(map_internal)?
+        .map(|tip| tip.height)
+        .unwrap_or(0);
+    let best_header_height = chainstate
+        .best_header()
+        .map_err(map_internal)?
+        .map(|tip| tip.height)
+        .unwrap_or(best_block_height);
+    let next_height = best_block_height.max(best_header_height).saturating_add(1);
+    Ok(branch_id_for_height(
+        next_height,
+        &chain_params.consensus.upgrades,
+    ))
+}
+
+fn parse_psbt_param(value: Option<&Value>) -> Result<Psbt, RpcError> {
+    let text = value
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "psbt must be a base64 string"))?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(text.trim())
+        .map_err(|_| RpcError::new(RPC_DESERIALIZATION_ERROR, "TX decode failed invalid base64"))?;
+    Psbt::decode(&bytes)
+        .map_err(|err| RpcError::new(RPC_DESERIALIZATION_ERROR, format!("TX decode failed {err}")))
+}
+
+fn psbt_to_base64(psbt: &Psbt) -> Result<String, RpcError> {
+    let bytes = psbt.encode().map_err(map_internal)?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+fn rpc_createpsbt<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+) -> Result<Value, RpcError> {
+    let raw = rpc_createrawtransaction(chainstate, params, chain_params)?;
+    let bytes = raw
+        .as_str()
+        .and_then(bytes_from_hex)
+        .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "createrawtransaction returned no hex"))?;
+    let tx = Transaction::consensus_decode(&bytes).map_err(map_internal)?;
+    let psbt = Psbt::from_unsigned_tx(tx)
+        .map_err(|err| RpcError::new(RPC_INVALID_PARAMETER, err.to_string()))?;
+    Ok(Value::String(psbt_to_base64(&psbt)?))
+}
+
+fn rpc_combinepsbt(params: Vec<Value>) -> Result<Value, RpcError> {
+    if params.len() != 1 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "combinepsbt expects 1 parameter",
+        ));
+    }
+    let entries = params[0]
+        .as_array()
+        .filter(|entries| !entries.is_empty())
+        .ok_or_else(|| {
+            RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "txs must be a non-empty array of base64 PSBT strings",
+            )
+        })?;
+    let mut combined = parse_psbt_param(entries.first())?;
+    for entry in &entries[1..] {
+        combined
+            .combine(parse_psbt_param(Some(entry))?)
+            .map_err(|err| RpcError::new(RPC_INVALID_PARAMETER, err.to_string()))?;
+    }
+    Ok(Value::String(psbt_to_base64(&combined)?))
+}
+
+fn rpc_walletprocesspsbt<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    mempool: &Mutex<Mempool>,
+    wallet: &Mutex<Wallet>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+) -> Result<Value, RpcError> {
+    if params.is_empty() || params.len() > 4 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "walletprocesspsbt expects 1 to 4 parameters",
+        ));
+    }
+    let mut psbt = parse_psbt_param(params.first())?;
+    let sign = match params.get(1) {
+        Some(value) if !value.is_null() => value
+            .as_bool()
+            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "sign must be a boolean"))?,
+        _ => true,
+    };
+    let sighash_type = parse_sighash_type(params.get(2))?;
+    if sighash_type.0 > 0xff {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "sighashtype must fit in one byte",
+        ));
+    }
+    let branch_id = match parse_branch_id(params.get(3))? {
+        Some(branch_id) => branch_id,
+        None => next_block_branch_id(chainstate, chain_params)?,
+    };
+
+    let wallet_guard = wallet
+        .lock()
+        .map_err(|_| RpcError::new(RPC_INTERNAL_ERROR, "wallet lock poisoned"))?;
+    let psbt_error = |err: crate::psbt::PsbtError| RpcError::new(RPC_WALLET_ERROR, err.to_string());
+
+    for index in 0..psbt.inputs.len() {
+        if psbt.inputs[index].final_script_sig.is_some() {
+            continue;
+        }
+        if psbt.inputs[index].utxo.is_none() && psbt.inputs[index].non_witness_utxo.is_none() {
+            let outpoint = psbt.tx.vin[index].prevout.clone();
+            if let Some(prevout) =
+                resolve_prevout_info(chainstate, mempool, &outpoint, &HashMap::new())?
+            {
+                psbt.inputs[index].utxo = Some(TxOut {
+                    value: prevout.value,
+                    script_pubkey: prevout.script_pubkey,
+                });
+            }
+        }
+        let Ok(utxo) = psbt.spent_output(index) else {
+            continue;
+        };
+        let script_type = classify_script_pubkey(&utxo.script_pubkey);
+        if script_type == ScriptType::P2Sh && psbt.inputs[index].redeem_script.is_none() {
+            psbt.inputs[index].redeem_script =
+                wallet_guard.redeem_script_for_p2sh_script_pubkey(&utxo.script_pubkey);
+        }
+        if !sign {
+            continue;
+        }
+        match psbt.inputs[index].sighash_type {
+            Some(existing) if existing != sighash_type.0 => {
+                return Err(RpcError::new(
+                    RPC_INVALID_PARAMETER,
+                    "Specified sighash value does not match value stored in PSBT",
+                ));
+            }
+            Some(_) => {}
+            None => psbt.inputs[index].sighash_type = Some(sighash_type.0),
+        }
+
+        let signing_script = match script_type {
+            ScriptType::P2Pkh => Some(utxo.script_pubkey.clone()),
+            ScriptType::P2Sh => psbt.inputs[index].redeem_script.clone(),
+            _ => None,
+        };
+        let Some(signing_script) = signing_script else {
+            continue;
+        };
+        if let Some((_, pubkeys)) = parse_multisig_redeem_script_with_required(&signing_script) {
+            for pubkey in pubkeys {
+                if let Some(secret) = wallet_guard
+                    .signing_key_for_pubkey(&pubkey)
+                    .map_err(map_wallet_error)?
+                {
+                    psbt.sign_input(index, &secret, &pubkey.serialize(), branch_id)
+                        .map_err(psbt_error)?;
+                }
+            }
+        } else if classify_script_pubkey(&signing_script) == ScriptType::P2Pkh {
+            if let Some((secret, pubkey_bytes)) = wallet_guard
+                .signing_key_for_script_pubkey(&signing_script)
+                .map_err(map_wallet_error)?
+            {
+                psbt.sign_input(index, &secret, &pubkey_bytes, branch_id)
+                    .map_err(psbt_error)?;
+            }
+        }
+    }
+
+    let complete = psbt.finalize().map_err(psbt_error)?;
+    Ok(json!({
+        "psbt": psbt_to_base64(&psbt)?,
+        "complete": complete,
+    }))
+}
+
+fn rpc_finalizepsbt(params: Vec<Value>) -> Result<Value, RpcError> {
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "finalizepsbt expects 1 or 2 parameters",
+        ));
+    }
+    let mut psbt = parse_psbt_param(params.first())?;
+    let extract = match params.get(1) {
+        Some(value) if !value.is_null() => value
+            .as_bool()
+            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "extract must be a boolean"))?,
+        _ => true,
+    };
+    let complete = psbt
+        .finalize()
+        .map_err(|err| RpcError::new(RPC_INVALID_PARAMETER, err.to_string()))?;
+    if complete && extract {
+        let tx = psbt.extract_tx().map_err(map_internal)?;
+        let encoded = tx.consensus_encode().map_err(map_internal)?;
+        return Ok(json!({
+            "hex": hex_bytes(&encoded),
+            "complete": true,
+        }));
+    }
+    Ok(json!({
+        "psbt": psbt_to_base64(&psbt)?,
+        "complete": complete,
+    }))
+}
+
 fn compact_size_len(value: usize) -> usize {
     if value < 0xfd {
         1
@@ -11202,28 +11852,20 @@ fn rpc_sendfrom<S: fluxd_storage::KeyValueStore>(
         .map_err(map_internal)?
         .map(|tip| tip.height.saturating_add(1))
         .unwrap_or(1);
-    let sapling_active = network_upgrade_active(
-        next_height,
-        &chain_params.consensus.upgrades,
-        UpgradeIndex::Acadia,
-    );
+    let rules = consensus_rules(next_height, &chain_params.consensus);
     let lock_time = wallet_tx_lock_time(next_height.saturating_sub(1));
 
     let tx = Transaction {
-        f_overwintered: sapling_active,
-        version: if sapling_active { 4 } else { 1 },
-        version_group_id: if sapling_active {
-            SAPLING_VERSION_GROUP_ID
-        } else {
-            0
-        },
+        f_overwintered: rules.overwinter_active,
+        version: rules.min_tx_version,
+        version_group_id: rules.version_group_id,
         vin: Vec::new(),
         vout: vec![TxOut {
             value: amount,
             script_pubkey,
         }],
         lock_time,
-        expiry_height: if sapling_active {
+        expiry_height: if rules.sapling_active {
             (next_height as u32).saturating_add(DEFAULT_TX_EXPIRY_DELTA)
         } else {
             0
@@ -11235,6 +11877,7 @@ fn rpc_sendfrom<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -11399,28 +12042,20 @@ pub(crate) fn rpc_sendtoaddress<S: fluxd_storage::KeyValueStore>(
         .map_err(map_internal)?
         .map(|tip| tip.height.saturating_add(1))
         .unwrap_or(1);
-    let sapling_active = network_upgrade_active(
-        next_height,
-        &chain_params.consensus.upgrades,
-        UpgradeIndex::Acadia,
-    );
+    let rules = consensus_rules(next_height, &chain_params.consensus);
     let lock_time = wallet_tx_lock_time(next_height.saturating_sub(1));
 
     let tx = Transaction {
-        f_overwintered: sapling_active,
-        version: if sapling_active { 4 } else { 1 },
-        version_group_id: if sapling_active {
-            SAPLING_VERSION_GROUP_ID
-        } else {
-            0
-        },
+        f_overwintered: rules.overwinter_active,
+        version: rules.min_tx_version,
+        version_group_id: rules.version_group_id,
         vin: Vec::new(),
         vout: vec![TxOut {
             value: amount,
             script_pubkey,
         }],
         lock_time,
-        expiry_height: if sapling_active {
+        expiry_height: if rules.sapling_active {
             (next_height as u32).saturating_add(DEFAULT_TX_EXPIRY_DELTA)
         } else {
             0
@@ -11432,6 +12067,7 @@ pub(crate) fn rpc_sendtoaddress<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -11619,11 +12255,7 @@ fn rpc_sendmany<S: fluxd_storage::KeyValueStore>(
         .map_err(map_internal)?
         .map(|tip| tip.height.saturating_add(1))
         .unwrap_or(1);
-    let sapling_active = network_upgrade_active(
-        next_height,
-        &chain_params.consensus.upgrades,
-        UpgradeIndex::Acadia,
-    );
+    let rules = consensus_rules(next_height, &chain_params.consensus);
     let lock_time = wallet_tx_lock_time(next_height.saturating_sub(1));
 
     let mut ordered: Vec<(&String, &Value)> = outputs.iter().collect();
@@ -11659,17 +12291,13 @@ fn rpc_sendmany<S: fluxd_storage::KeyValueStore>(
     }
 
     let tx = Transaction {
-        f_overwintered: sapling_active,
-        version: if sapling_active { 4 } else { 1 },
-        version_group_id: if sapling_active {
-            SAPLING_VERSION_GROUP_ID
-        } else {
-            0
-        },
+        f_overwintered: rules.overwinter_active,
+        version: rules.min_tx_version,
+        version_group_id: rules.version_group_id,
         vin: Vec::new(),
         vout,
         lock_time,
-        expiry_height: if sapling_active {
+        expiry_height: if rules.sapling_active {
             (next_height as u32).saturating_add(DEFAULT_TX_EXPIRY_DELTA)
         } else {
             0
@@ -11681,6 +12309,7 @@ fn rpc_sendmany<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -11765,7 +12394,7 @@ fn rpc_sendmany<S: fluxd_storage::KeyValueStore>(
     })
 }
 
-fn submit_raw_transaction<S: fluxd_storage::KeyValueStore>(
+pub(crate) fn submit_raw_transaction<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     mempool: &Mutex<Mempool>,
     mempool_policy: &MempoolPolicy,
@@ -11789,7 +12418,7 @@ fn submit_raw_transaction<S: fluxd_storage::KeyValueStore>(
         .map_err(|_| RpcError::new(RPC_DESERIALIZATION_ERROR, "TX decode failed"))?;
 
     if chainstate
-        .tx_location(&txid)
+        .tx_location(&Txid::from_internal(txid))
         .map_err(map_internal)?
         .is_some()
     {
@@ -12013,6 +12642,8 @@ fn rpc_getmempoolinfo(params: Vec<Value>, mempool: &Mutex<Mempool>) -> Result<Va
         "size": guard.size(),
         "bytes": guard.bytes(),
         "usage": guard.usage(),
+        "maxmempool": guard.max_bytes(),
+        "mempoolminfee": amount_to_value(guard.min_fee_to_enter()),
     }))
 }
 
@@ -12051,29 +12682,95 @@ fn rpc_getrawmempool<S: fluxd_storage::KeyValueStore>(
         .unwrap_or(0);
     let mut out = serde_json::Map::new();
     for entry in guard.entries() {
-        let depends: Vec<Value> = entry
-            .parents
-            .iter()
-            .map(|txid| Value::String(hash256_to_hex(txid)))
-            .collect();
-        let starting_priority = entry.starting_priority();
-        let current_priority = entry.current_priority(best_height);
         out.insert(
             hash256_to_hex(&entry.txid),
-            json!({
-                "size": entry.size(),
-                "fee": amount_to_value(entry.fee),
-                "time": entry.time,
-                "height": entry.height.max(0),
-                "startingpriority": Number::from_f64(starting_priority).unwrap_or(0.into()),
-                "currentpriority": Number::from_f64(current_priority).unwrap_or(0.into()),
-                "depends": depends,
-            }),
+            mempool_entry_json(entry, best_height),
         );
     }
     Ok(Value::Object(out))
 }
 
+fn mempool_entry_json(entry: &MempoolEntry, best_height: i32) -> Value {
+    let depends: Vec<Value> = entry
+        .parents
+        .iter()
+        .map(|txid| Value::String(hash256_to_hex(txid)))
+        .collect();
+    let starting_priority = entry.starting_priority();
+    let current_priority = entry.current_priority(best_height);
+    json!({
+        "size": entry.size(),
+        "fee": amount_to_value(entry.fee),
+        "time": entry.time,
+        "height": entry.height.max(0),
+        "startingpriority": Number::from_f64(starting_priority).unwrap_or(0.into()),
+        "currentpriority": Number::from_f64(current_priority).unwrap_or(0.into()),
+        "depends": depends,
+    })
+}
+
+/// `getmempoolancestors` / `getmempooldescendants`: `txid [verbose]`.
+fn rpc_getmempoolrelatives<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    params: Vec<Value>,
+    mempool: &Mutex<Mempool>,
+    descendants: bool,
+) -> Result<Value, RpcError> {
+    let method = if descendants {
+        "getmempooldescendants"
+    } else {
+        "getmempoolancestors"
+    };
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            format!("{method} expects 1 or 2 parameters"),
+        ));
+    }
+    let txid = parse_hash(&params[0])?;
+    let verbose = match params.get(1) {
+        None | Some(Value::Null) => false,
+        Some(value) => parse_verbose_flag(value)?,
+    };
+    let guard = mempool
+        .lock()
+        .map_err(|_| map_internal("mempool lock poisoned"))?;
+    if !guard.contains(&txid) {
+        return Err(RpcError::new(
+            RPC_INVALID_ADDRESS_OR_KEY,
+            "Transaction not in mempool",
+        ));
+    }
+    let related = if descendants {
+        guard.descendants(&txid)
+    } else {
+        guard.ancestors(&txid)
+    };
+    if !verbose {
+        return Ok(Value::Array(
+            related
+                .iter()
+                .map(|txid| Value::String(hash256_to_hex(txid)))
+                .collect(),
+        ));
+    }
+    let best_height = chainstate
+        .best_block()
+        .map_err(map_internal)?
+        .map(|tip| tip.height)
+        .unwrap_or(0);
+    let mut out = serde_json::Map::new();
+    for txid in related {
+        if let Some(entry) = guard.get(&txid) {
+            out.insert(
+                hash256_to_hex(&txid),
+                mempool_entry_json(entry, best_height),
+            );
+        }
+    }
+    Ok(Value::Object(out))
+}
+
 fn rpc_gettxout<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     mempool: &Mutex<Mempool>,
@@ -12123,16 +12820,20 @@ fn rpc_gettxout<S: fluxd_storage::KeyValueStore>(
                 "scriptPubKey": script,
                 "version": entry.tx.version,
                 "coinbase": false,
+                "maturity_remaining": null,
             }));
         }
     }
-    let entry = match chainstate.utxo_entry(&outpoint).map_err(map_internal)? {
+    let entry = match chainstate
+        .utxo_entry(&outpoint.txid(), outpoint.index)
+        .map_err(map_internal)?
+    {
         Some(entry) => entry,
         None => return Ok(Value::Null),
     };
     let tx_version = {
         let location = chainstate
-            .tx_location(&txid)
+            .tx_location(&Txid::from_internal(txid))
             .map_err(map_internal)?
             .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "transaction not found"))?;
         let bytes = chainstate
@@ -12154,6 +12855,11 @@ fn rpc_gettxout<S: fluxd_storage::KeyValueStore>(
     } else {
         0
     };
+    let maturity_remaining = entry.is_coinbase.then(|| {
+        chain_params
+            .consensus
+            .coinbase_maturity_remaining(confirmations)
+    });
     let script = script_pubkey_json(&entry.script_pubkey, chain_params.network);
     Ok(json!({
         "bestblock": hash256_to_hex(&best.hash),
@@ -12162,6 +12868,7 @@ fn rpc_gettxout<S: fluxd_storage::KeyValueStore>(
         "scriptPubKey": script,
         "version": tx_version,
         "coinbase": entry.is_coinbase,
+        "maturity_remaining": maturity_remaining,
     }))
 }
 
@@ -12218,7 +12925,7 @@ fn rpc_gettxoutproof<S: fluxd_storage::KeyValueStore>(
             .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Block not found"))?
     } else {
         let tx_location = chainstate
-            .tx_location(&one_txid)
+            .tx_location(&Txid::from_internal(one_txid))
             .map_err(map_internal)?
             .ok_or_else(|| {
                 RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Transaction not yet in block")
@@ -12292,6 +12999,7 @@ fn rpc_verifytxoutproof<S: fluxd_storage::KeyValueStore>(
     let best_at_height = chainstate
         .height_hash(entry.height)
         .map_err(map_internal)?
+        .map(BlockHash::to_internal)
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Block not found in chain"))?;
     if best_at_height != block_hash {
         return Err(RpcError::new(
@@ -12369,39 +13077,175 @@ fn rpc_getindexstats<S: fluxd_storage::KeyValueStore>(
     }))
 }
 
+/// Sync state of one secondary index, as reported by `getindexinfo`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IndexInfo {
+    name: &'static str,
+    synced: bool,
+    best_block_height: i64,
+    reindexing: bool,
+}
+
+/// The txindex, spent index and address index follow the chain tip atomically once built, so
+/// they are synced unless a rebuild is unfinished. The address neighbor index is built in the
+/// background and tracks its own height.
+fn secondary_index_info<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+) -> Result<Vec<IndexInfo>, RpcError> {
+    let tip_height = chainstate
+        .best_block()
+        .map_err(map_internal)?
+        .map(|tip| i64::from(tip.height))
+        .unwrap_or(-1);
+
+    let mut out = Vec::with_capacity(4);
+    for (name, version_key, version, rebuild_key) in [
+        (
+            "txindex",
+            crate::TXINDEX_VERSION_KEY,
+            crate::TXINDEX_VERSION,
+            crate::TXINDEX_REBUILD_HEIGHT_KEY,
+        ),
+        (
+            "spentindex",
+            crate::SPENTINDEX_VERSION_KEY,
+            crate::SPENTINDEX_VERSION,
+            crate::SPENTINDEX_REBUILD_HEIGHT_KEY,
+        ),
+        (
+            "addressindex",
+            crate::ADDRESSINDEX_VERSION_KEY,
+            crate::ADDRESSINDEX_VERSION,
+            crate::ADDRESSINDEX_REBUILD_HEIGHT_KEY,
+        ),
+    ] {
+        // A missing version key means a fresh store; startup initializes it to the current one.
+        let current = chainstate
+            .meta_u32(version_key)
+            .map_err(map_internal)?
+            .is_none_or(|found| found == version);
+        let rebuild_height = chainstate.meta_u32(rebuild_key).map_err(map_internal)?;
+        let best_block_height = match rebuild_height {
+            Some(height) => i64::from(height),
+            None if current => tip_height,
+            None => -1,
+        };
+        out.push(IndexInfo {
+            name,
+            synced: current && rebuild_height.is_none(),
+            best_block_height,
+            reindexing: rebuild_height.is_some(),
+        });
+    }
+
+    let neighbors = chainstate.address_neighbor_index();
+    let active_height = match neighbors.active_generation().map_err(map_internal)? {
+        Some(gen) if gen > 0 => neighbors
+            .active_height()
+            .map_err(map_internal)?
+            .map(i64::from),
+        _ => None,
+    };
+    out.push(IndexInfo {
+        name: "addressneighborindex",
+        synced: active_height == Some(tip_height),
+        best_block_height: active_height.unwrap_or(-1),
+        reindexing: neighbors.build_state().map_err(map_internal)?
+            == AddressNeighborBuildState::Running,
+    });
+    Ok(out)
+}
+
+/// Fails with the same error for every method that needs `name` while that index is not synced.
+fn require_synced_index<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    name: &str,
+) -> Result<(), RpcError> {
+    let info = secondary_index_info(chainstate)?
+        .into_iter()
+        .find(|info| info.name == name)
+        .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, format!("unknown index {name}")))?;
+    if info.synced {
+        return Ok(());
+    }
+    let state = if info.reindexing {
+        format!("rebuild at height {}", info.best_block_height)
+    } else {
+        "not built".to_string()
+    };
+    Err(RpcError::new(
+        RPC_MISC_ERROR,
+        format!("{name} not available ({state}); see getindexinfo"),
+    ))
+}
+
+fn rpc_getindexinfo<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    params: Vec<Value>,
+) -> Result<Value, RpcError> {
+    if params.len() > 1 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "getindexinfo expects 0 or 1 parameter",
+        ));
+    }
+    let filter = match params.first() {
+        None | Some(Value::Null) => None,
+        Some(Value::String(name)) => Some(name.as_str()),
+        Some(_) => {
+            return Err(RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "index_name must be a string",
+            ))
+        }
+    };
+
+    let mut out = serde_json::Map::new();
+    for info in secondary_index_info(chainstate)? {
+        if filter.is_some_and(|name| name != info.name) {
+            continue;
+        }
+        out.insert(
+            info.name.to_string(),
+            json!({
+                "synced": info.synced,
+                "best_block_height": info.best_block_height,
+                "reindexing": info.reindexing,
+            }),
+        );
+    }
+    Ok(Value::Object(out))
+}
+
 fn rpc_gettxoutsetinfo<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     params: Vec<Value>,
     data_dir: &Path,
 ) -> Result<Value, RpcError> {
     ensure_no_params(&params)?;
-    let best = chainstate.best_block().map_err(map_internal)?;
-    let (height, best_hash) = match best {
-        Some(tip) => (tip.height, tip.hash),
-        None => (0, [0u8; 32]),
-    };
-    let utxo_info = chainstate.utxo_set_info().map_err(map_internal)?;
+    let disk_size =
+        db_info::dir_size_cached(&data_dir.join("db"), Duration::from_secs(30)).unwrap_or(0);
+    let info = chainstate.txout_set_info(disk_size).map_err(map_internal)?;
     let value_pools = chainstate.value_pools_or_compute().map_err(map_internal)?;
     let shielded_total = value_pools
         .sprout
         .checked_add(value_pools.sapling)
         .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "shielded value pool overflow"))?;
-    let total_supply = utxo_info
+    let total_supply = info
         .total_amount
         .checked_add(shielded_total)
         .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "total supply overflow"))?;
-    let disk_size =
-        db_info::dir_size_cached(&data_dir.join("db"), Duration::from_secs(30)).unwrap_or(0);
     Ok(json!({
-        "height": height.max(0),
-        "bestblock": hash256_to_hex(&best_hash),
-        "transactions": utxo_info.transactions,
-        "txouts": utxo_info.txouts,
-        "bytes_serialized": utxo_info.bytes_serialized,
-        "hash_serialized": hash256_to_hex(&utxo_info.hash_serialized),
-        "disk_size": disk_size,
-        "total_amount": amount_to_value(utxo_info.total_amount),
-        "total_amount_zat": utxo_info.total_amount,
+        "height": info.height,
+        "bestblock": hash256_to_hex(&info.best_block),
+        "transactions": info.transactions,
+        "txouts": info.txouts,
+        "bytes_serialized": info.bytes_serialized,
+        "hash_serialized": hash256_to_hex(&info.hash_serialized),
+        "muhash": hash256_to_hex(&info.muhash),
+        "disk_size": info.disk_size,
+        "total_amount": amount_to_value(info.total_amount),
+        "total_amount_zat": info.total_amount,
         "sprout_pool": amount_to_value(value_pools.sprout),
         "sprout_pool_zat": value_pools.sprout,
         "sapling_pool": amount_to_value(value_pools.sapling),
@@ -12456,6 +13300,54 @@ fn rpc_getrichlist<S: fluxd_storage::KeyValueStore>(
     Err(RpcError::new(RPC_INTERNAL_ERROR, "rich list warming up"))
 }
 
+fn rpc_invalidateblock<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    params: Vec<Value>,
+) -> Result<Value, RpcError> {
+    let hash = parse_block_mark_params("invalidateblock", chainstate, &params)?;
+    let _guard = write_lock
+        .lock()
+        .map_err(|_| map_internal("write lock poisoned"))?;
+    chainstate
+        .invalidate_block(&hash)
+        .map_err(|err| RpcError::new(RPC_MISC_ERROR, err.to_string()))?;
+    Ok(Value::Null)
+}
+
+fn rpc_reconsiderblock<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    params: Vec<Value>,
+) -> Result<Value, RpcError> {
+    let hash = parse_block_mark_params("reconsiderblock", chainstate, &params)?;
+    let _guard = write_lock
+        .lock()
+        .map_err(|_| map_internal("write lock poisoned"))?;
+    chainstate
+        .reconsider_block(&hash)
+        .map_err(|err| RpcError::new(RPC_MISC_ERROR, err.to_string()))?;
+    Ok(Value::Null)
+}
+
+fn parse_block_mark_params<S: fluxd_storage::KeyValueStore>(
+    method: &str,
+    chainstate: &ChainState<S>,
+    params: &[Value],
+) -> Result<Hash256, RpcError> {
+    if params.len() != 1 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            format!("{method} expects 1 parameter"),
+        ));
+    }
+    let hash = parse_hash(&params[0])?;
+    if !chainstate.has_header_entry(&hash).map_err(map_internal)? {
+        return Err(RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Block not found"));
+    }
+    Ok(hash)
+}
+
 fn rpc_verifychain<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     params: Vec<Value>,
@@ -12492,6 +13384,98 @@ fn rpc_verifychain<S: fluxd_storage::KeyValueStore>(
     Ok(Value::Bool(result.is_ok()))
 }
 
+/// Re-runs validation of a stored block with the requested checks and reports every failure.
+/// Read-only: the block is never connected.
+fn rpc_debugvalidateblock<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+    mempool_flags: &ValidationFlags,
+) -> Result<Value, RpcError> {
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "debugvalidateblock expects 1 or 2 parameters",
+        ));
+    }
+    let hash = parse_hash(&params[0])?;
+    let mut check_script = true;
+    let mut check_shielded = true;
+    let mut check_pow = true;
+    if let Some(value) = params.get(1).filter(|value| !value.is_null()) {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "options must be an object"))?;
+        for (key, flag) in [
+            ("check_script", &mut check_script),
+            ("check_shielded", &mut check_shielded),
+            ("check_pow", &mut check_pow),
+        ] {
+            if let Some(value) = obj.get(key) {
+                if !value.is_null() {
+                    *flag = parse_bool(value)?;
+                }
+            }
+        }
+    }
+
+    let entry = chainstate
+        .header_entry(&hash)
+        .map_err(map_internal)?
+        .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Block not found"))?;
+    let bytes = match chainstate.block_location(&hash).map_err(map_internal)? {
+        Some(location) => Some(chainstate.read_block(location).map_err(map_internal)?),
+        None => chainstate
+            .unconnected_block_bytes(&hash)
+            .map_err(map_internal)?,
+    };
+    let bytes = bytes
+        .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Block data not available"))?;
+    let block = fluxd_primitives::block::Block::consensus_decode(&bytes).map_err(map_internal)?;
+
+    let mut flags = mempool_flags.clone();
+    flags.check_script = check_script;
+    flags.check_shielded = check_shielded;
+    flags.check_pow = check_pow;
+    flags.check_pon = check_pow;
+    flags.assume_valid = None;
+    flags.metrics = None;
+    let report = chainstate
+        .validate_block_report(&block, entry.height, &chain_params.consensus, &flags)
+        .map_err(map_internal)?;
+
+    let txs: Vec<Value> = report
+        .txs
+        .iter()
+        .map(|tx| {
+            let script_failures: Vec<Value> = tx
+                .script_failures
+                .iter()
+                .map(|(input, error)| json!({ "input": input, "error": error }))
+                .collect();
+            json!({
+                "txid": tx.txid.as_ref().map(hash256_to_hex),
+                "valid": tx.is_valid(),
+                "errors": tx.errors,
+                "script_failures": script_failures,
+                "missing_prevouts": tx.missing_prevouts,
+            })
+        })
+        .collect();
+    Ok(json!({
+        "hash": hash256_to_hex(&hash),
+        "height": entry.height,
+        "valid": report.is_valid(),
+        "flags": {
+            "check_script": check_script,
+            "check_shielded": check_shielded,
+            "check_pow": check_pow,
+        },
+        "block_errors": report.block_errors,
+        "txs": txs,
+    }))
+}
+
 fn rpc_getblockdeltas<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     params: Vec<Value>,
@@ -12549,7 +13533,7 @@ fn rpc_getblockdeltas<S: fluxd_storage::KeyValueStore>(
 
     let best_height = best_block_height(chainstate)?;
     let main_hash = chainstate.height_hash(entry.height).map_err(map_internal)?;
-    if main_hash.as_ref() != Some(&hash) {
+    if main_hash != Some(BlockHash::from_internal(hash)) {
         return Err(RpcError::new(
             RPC_INVALID_ADDRESS_OR_KEY,
             "Block is an orphan",
@@ -12747,6 +13731,272 @@ fn rpc_getblockdeltas<S: fluxd_storage::KeyValueStore>(
     Ok(result)
 }
 
+const BLOCK_STATS_NAMES: &[&str] = &[
+    "avgfee",
+    "avgfeerate",
+    "avgtxsize",
+    "blockhash",
+    "feerate_percentiles",
+    "height",
+    "ins",
+    "maxfee",
+    "maxfeerate",
+    "maxtxsize",
+    "medianfee",
+    "mediantime",
+    "mediantxsize",
+    "minfee",
+    "minfeerate",
+    "mintxsize",
+    "outs",
+    "subsidy",
+    "time",
+    "total_out",
+    "total_size",
+    "total_weight",
+    "totalfee",
+    "txs",
+    "utxo_increase",
+];
+
+const BLOCK_STATS_FEE_NAMES: &[&str] = &[
+    "avgfee",
+    "avgfeerate",
+    "feerate_percentiles",
+    "maxfee",
+    "maxfeerate",
+    "medianfee",
+    "minfee",
+    "minfeerate",
+    "totalfee",
+];
+
+fn rpc_getblockstats<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+) -> Result<Value, RpcError> {
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "getblockstats expects 1 or 2 parameters",
+        ));
+    }
+
+    let selected = match params.get(1) {
+        None | Some(Value::Null) => None,
+        Some(Value::Array(values)) => {
+            let mut selected = HashSet::with_capacity(values.len());
+            for value in values {
+                let name = value.as_str().ok_or_else(|| {
+                    RpcError::new(RPC_INVALID_PARAMETER, "stats must be an array of strings")
+                })?;
+                if !BLOCK_STATS_NAMES.contains(&name) {
+                    return Err(RpcError::new(
+                        RPC_INVALID_PARAMETER,
+                        format!("Invalid selected statistic {name}"),
+                    ));
+                }
+                selected.insert(name);
+            }
+            Some(selected)
+        }
+        Some(_) => {
+            return Err(RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "stats must be an array of strings",
+            ))
+        }
+    };
+    let wants = |name: &str| match selected.as_ref() {
+        Some(selected) => selected.contains(name),
+        None => true,
+    };
+    let need_fees = BLOCK_STATS_FEE_NAMES.iter().any(|name| wants(name));
+
+    let (hash, entry) = resolve_block_hash(chainstate, &params[0])?;
+    let location = chainstate
+        .block_location(&hash)
+        .map_err(map_internal)?
+        .ok_or_else(|| RpcError::new(RPC_MISC_ERROR, "Block not available (pruned data)"))?;
+    let bytes = chainstate.read_block(location).map_err(map_internal)?;
+    let block = fluxd_primitives::block::Block::consensus_decode(&bytes).map_err(map_internal)?;
+
+    let mut tx_cache: HashMap<Hash256, Transaction> = HashMap::new();
+    let mut ins = 0i64;
+    let mut outs = 0i64;
+    let mut utxo_increase = 0i64;
+    let mut total_out = 0i64;
+    let mut total_size = 0i64;
+    let mut sizes = Vec::with_capacity(block.transactions.len());
+    let mut fees = Vec::with_capacity(block.transactions.len());
+    let mut feerates = Vec::with_capacity(block.transactions.len());
+    let mut feerate_weights = Vec::with_capacity(block.transactions.len());
+
+    for tx in &block.transactions {
+        let is_coinbase =
+            tx.vin.len() == 1 && tx.vin[0].prevout == fluxd_primitives::outpoint::OutPoint::null();
+        outs += tx.vout.len() as i64;
+        utxo_increase += tx
+            .vout
+            .iter()
+            .filter(|output| output.script_pubkey.first() != Some(&0x6a))
+            .count() as i64;
+        if is_coinbase {
+            continue;
+        }
+
+        ins += tx.vin.len() as i64;
+        utxo_increase -= tx.vin.len() as i64;
+        total_out += tx.vout.iter().map(|output| output.value).sum::<i64>();
+        let size = tx.consensus_encode().map_err(map_internal)?.len() as i64;
+        total_size += size;
+        sizes.push(size);
+
+        if need_fees {
+            let mut value_in =
+                tx.join_splits.iter().map(|js| js.vpub_new).sum::<i64>() + tx.value_balance.max(0);
+            for input in &tx.vin {
+                let satoshis = match chainstate
+                    .spent_info(&input.prevout)
+                    .map_err(map_internal)?
+                    .and_then(|spent| spent.details)
+                {
+                    Some(details) => details.satoshis,
+                    None => {
+                        resolve_prevout_via_txindex(
+                            chainstate,
+                            &mut tx_cache,
+                            &input.prevout,
+                            chain_params.network,
+                        )?
+                        .0
+                    }
+                };
+                value_in += satoshis;
+            }
+            let fee = value_in - tx_value_out_for_fee(tx)?;
+            let feerate = if size > 0 { fee / size } else { 0 };
+            fees.push(fee);
+            feerates.push(feerate);
+            feerate_weights.push((feerate, size));
+        }
+    }
+
+    let tx_count = sizes.len() as i64;
+    let totalfee = fees.iter().sum::<i64>();
+    let mut result = serde_json::Map::new();
+    let mut put = |name: &str, value: Value| {
+        if wants(name) {
+            result.insert(name.to_string(), value);
+        }
+    };
+    put(
+        "avgfee",
+        json!(if tx_count > 0 { totalfee / tx_count } else { 0 }),
+    );
+    put(
+        "avgfeerate",
+        json!(if total_size > 0 {
+            totalfee / total_size
+        } else {
+            0
+        }),
+    );
+    put(
+        "avgtxsize",
+        json!(if tx_count > 0 {
+            total_size / tx_count
+        } else {
+            0
+        }),
+    );
+    put("blockhash", json!(hash256_to_hex(&hash)));
+    put(
+        "feerate_percentiles",
+        json!(feerate_percentiles_by_weight(feerate_weights, total_size)),
+    );
+    put("height", json!(entry.height));
+    put("ins", json!(ins));
+    put("maxfee", json!(fees.iter().copied().max().unwrap_or(0)));
+    put(
+        "maxfeerate",
+        json!(feerates.iter().copied().max().unwrap_or(0)),
+    );
+    put("maxtxsize", json!(sizes.iter().copied().max().unwrap_or(0)));
+    put("medianfee", json!(truncated_median(&mut fees)));
+    if wants("mediantime") {
+        put(
+            "mediantime",
+            json!(median_time_past(chainstate, entry.height)?),
+        );
+    }
+    put("mediantxsize", json!(truncated_median(&mut sizes)));
+    put("minfee", json!(fees.iter().copied().min().unwrap_or(0)));
+    put(
+        "minfeerate",
+        json!(feerates.iter().copied().min().unwrap_or(0)),
+    );
+    put("mintxsize", json!(sizes.iter().copied().min().unwrap_or(0)));
+    put("outs", json!(outs));
+    put(
+        "subsidy",
+        json!(block_subsidy(entry.height, &chain_params.consensus)),
+    );
+    put("time", json!(block.header.time));
+    put("total_out", json!(total_out));
+    put("total_size", json!(total_size));
+    put("total_weight", json!(total_size * 4));
+    put("totalfee", json!(totalfee));
+    put("txs", json!(block.transactions.len()));
+    put("utxo_increase", json!(utxo_increase));
+    Ok(Value::Object(result))
+}
+
+fn truncated_median(values: &mut [i64]) -> i64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[mid]
+    } else {
+        (values[mid - 1] + values[mid]) / 2
+    }
+}
+
+/// Size-weighted 10th/25th/50th/75th/90th percentile feerates, as in Bitcoin Core's
+/// `getblockstats`.
+fn feerate_percentiles_by_weight(mut scores: Vec<(i64, i64)>, total_weight: i64) -> [i64; 5] {
+    let mut result = [0i64; 5];
+    let Some(&(last_feerate, _)) = scores.iter().max() else {
+        return result;
+    };
+    scores.sort_unstable();
+    let total_weight = total_weight as f64;
+    let thresholds = [
+        total_weight / 10.0,
+        total_weight / 4.0,
+        total_weight / 2.0,
+        total_weight * 3.0 / 4.0,
+        total_weight * 9.0 / 10.0,
+    ];
+    let mut next = 0;
+    let mut cumulative = 0i64;
+    for (feerate, weight) in scores {
+        cumulative += weight;
+        while next < thresholds.len() && cumulative as f64 >= thresholds[next] {
+            result[next] = feerate;
+            next += 1;
+        }
+    }
+    for slot in result.iter_mut().skip(next) {
+        *slot = last_feerate;
+    }
+    result
+}
+
 fn rpc_getspentinfo<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     params: Vec<Value>,
@@ -12815,7 +14065,7 @@ fn rpc_getaddressutxos<S: fluxd_storage::KeyValueStore>(
             .map_err(map_internal)?;
         for outpoint in outpoints {
             let entry = chainstate
-                .utxo_entry(&outpoint)
+                .utxo_entry(&outpoint.txid(), outpoint.index)
                 .map_err(map_internal)?
                 .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "missing utxo entry"))?;
             rows.push(UtxoRow {
@@ -12911,7 +14161,7 @@ fn rpc_getaddressutxobalances<S: fluxd_storage::KeyValueStore>(
             let mut sum: i128 = 0;
             for outpoint in outpoints {
                 let entry = chainstate
-                    .utxo_entry(&outpoint)
+                    .utxo_entry(&outpoint.txid(), outpoint.index)
                     .map_err(map_internal)?
                     .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "missing utxo entry"))?;
                 sum += i128::from(entry.value);
@@ -12950,6 +14200,7 @@ fn rpc_getaddressbalance<S: fluxd_storage::KeyValueStore>(
     }
     let (addresses, _opts) = parse_addresses_param(&params[0])?;
     let address_scripts = decode_address_scripts(addresses, chain_params.network)?;
+    require_synced_index(chainstate, "addressindex")?;
 
     let best_height = best_block_height(chainstate)?;
 
@@ -12980,7 +14231,7 @@ fn rpc_getaddressbalance<S: fluxd_storage::KeyValueStore>(
             .map_err(map_internal)?;
         for outpoint in outpoints {
             let entry = chainstate
-                .utxo_entry(&outpoint)
+                .utxo_entry(&outpoint.txid(), outpoint.index)
                 .map_err(map_internal)?
                 .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "missing utxo entry"))?;
 
@@ -13082,10 +14333,12 @@ fn rpc_getaddressdeltas<S: fluxd_storage::KeyValueStore>(
         let start_hash = chainstate
             .height_hash(start_height)
             .map_err(map_internal)?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "block not found"))?;
         let end_hash = chainstate
             .height_hash(end_height)
             .map_err(map_internal)?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "block not found"))?;
 
         return Ok(json!({
@@ -13188,10 +14441,12 @@ fn rpc_getaddressdeltas<S: fluxd_storage::KeyValueStore>(
     let start_hash = chainstate
         .height_hash(start_height)
         .map_err(map_internal)?
+        .map(BlockHash::to_internal)
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "block not found"))?;
     let end_hash = chainstate
         .height_hash(end_height)
         .map_err(map_internal)?
+        .map(BlockHash::to_internal)
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "block not found"))?;
 
     Ok(json!({
@@ -13481,7 +14736,7 @@ fn rpc_getaddressmempool<S: fluxd_storage::KeyValueStore>(
         }
         for (index, input) in entry.tx.vin.iter().enumerate() {
             let prevout_entry = match chainstate
-                .utxo_entry(&input.prevout)
+                .utxo_entry(&input.prevout.txid(), input.prevout.index)
                 .map_err(map_internal)?
             {
                 Some(entry) => entry,
@@ -13731,6 +14986,7 @@ fn rpc_startaddressneighborsreindex<S: fluxd_storage::KeyValueStore + 'static>(
         let tip_hash = chainstate
             .height_hash(i32::try_from(resolved_end_height).unwrap_or(i32::MAX))
             .map_err(map_internal)?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "missing block hash for endHeight"))?;
 
         let index = chainstate.address_neighbor_index();
@@ -13859,7 +15115,11 @@ fn reindex_address_neighbors_range<S: fluxd_storage::KeyValueStore>(
 
     while current_height <= end_height {
         let height_i32 = i32::try_from(current_height).unwrap_or(i32::MAX);
-        let Some(hash) = chainstate.height_hash(height_i32).map_err(|err| err.to_string())? else {
+        let Some(hash) = chainstate
+            .height_hash(height_i32)
+            .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
+        else {
             break;
         };
         let location = match chainstate.block_location(&hash).map_err(|err| err.to_string())? {
@@ -14188,7 +15448,7 @@ fn schedule_address_neighbors_catchup<S: fluxd_storage::KeyValueStore + 'static>
         Err(_) => return,
     };
     let end_hash = match chainstate.height_hash(height_i32) {
-        Ok(Some(hash)) => hash,
+        Ok(Some(hash)) => hash.to_internal(),
         _ => return,
     };
 
@@ -14402,11 +15662,7 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
 
     let pon_active =
         network_upgrade_active(height, &chain_params.consensus.upgrades, UpgradeIndex::Pon);
-    let sapling_active = network_upgrade_active(
-        height,
-        &chain_params.consensus.upgrades,
-        UpgradeIndex::Acadia,
-    );
+    let rules = consensus_rules(height, &chain_params.consensus);
 
     let payouts = chainstate
         .deterministic_fluxnode_payouts(height, chain_params)
@@ -14421,9 +15677,11 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
         .checked_sub(payout_sum)
         .ok_or_else(|| map_internal("fluxnode payout remainder out of range"))?;
 
-    let exchange_fund = exchange_fund_amount(height, &chain_params.funding);
-    let foundation_fund = foundation_fund_amount(height, &chain_params.funding);
-    let swap_pool = swap_pool_amount(height as i64, &chain_params.swap_pool);
+    // The dev fund output is built from the payout remainder below.
+    let funding_outputs: Vec<_> = required_funding_outputs(height, chain_params)
+        .into_iter()
+        .filter(|output| output.kind != FundingOutputKind::DevFund)
+        .collect();
 
     let make_coinbase = |miner_value: i64| -> Result<Transaction, RpcError> {
         let mut outputs = Vec::new();
@@ -14450,35 +15708,17 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
             });
         }
 
-        if exchange_fund > 0 {
-            let exchange_script = address_to_script_pubkey(
-                chain_params.funding.exchange_address,
-                chain_params.network,
-            )
-            .map_err(|_| RpcError::new(RPC_INTERNAL_ERROR, "invalid exchange fund address"))?;
-            outputs.push(TxOut {
-                value: exchange_fund,
-                script_pubkey: exchange_script,
-            });
-        }
-        if foundation_fund > 0 {
-            let foundation_script = address_to_script_pubkey(
-                chain_params.funding.foundation_address,
-                chain_params.network,
-            )
-            .map_err(|_| RpcError::new(RPC_INTERNAL_ERROR, "invalid foundation fund address"))?;
-            outputs.push(TxOut {
-                value: foundation_fund,
-                script_pubkey: foundation_script,
-            });
-        }
-        if swap_pool > 0 {
-            let swap_script =
-                address_to_script_pubkey(chain_params.swap_pool.address, chain_params.network)
-                    .map_err(|_| RpcError::new(RPC_INTERNAL_ERROR, "invalid swap pool address"))?;
+        for funding in &funding_outputs {
+            let script_pubkey = address_to_script_pubkey(funding.address, chain_params.network)
+                .map_err(|_| {
+                    RpcError::new(
+                        RPC_INTERNAL_ERROR,
+                        format!("invalid {:?} funding address", funding.kind),
+                    )
+                })?;
             outputs.push(TxOut {
-                value: swap_pool,
-                script_pubkey: swap_script,
+                value: funding.amount,
+                script_pubkey,
             });
         }
 
@@ -14486,13 +15726,9 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
         crate::push_data(&mut script_sig, b"fluxd-rust");
 
         Ok(Transaction {
-            f_overwintered: sapling_active,
-            version: if sapling_active { 4 } else { 1 },
-            version_group_id: if sapling_active {
-                SAPLING_VERSION_GROUP_ID
-            } else {
-                0
-            },
+            f_overwintered: rules.overwinter_active,
+            version: rules.min_tx_version,
+            version_group_id: rules.version_group_id,
             vin: vec![TxIn {
                 prevout: OutPoint::null(),
                 script_sig,
@@ -14508,96 +15744,17 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         })
     };
 
-    fn legacy_sigops(script: &[u8]) -> u32 {
-        const OP_0: u8 = 0x00;
-        const OP_1: u8 = 0x51;
-        const OP_16: u8 = 0x60;
-        const OP_CHECKSIG: u8 = 0xac;
-        const OP_CHECKSIGVERIFY: u8 = 0xad;
-        const OP_CHECKMULTISIG: u8 = 0xae;
-        const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
-        const OP_PUSHDATA1: u8 = 0x4c;
-        const OP_PUSHDATA2: u8 = 0x4d;
-        const OP_PUSHDATA4: u8 = 0x4e;
-
-        let mut count = 0u32;
-        let mut cursor = 0usize;
-        let mut prev_opcode: Option<u8> = None;
-        while cursor < script.len() {
-            let opcode = script[cursor];
-            cursor = cursor.saturating_add(1);
-            match opcode {
-                OP_CHECKSIG | OP_CHECKSIGVERIFY => count = count.saturating_add(1),
-                OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
-                    let sigops = match prev_opcode {
-                        Some(OP_0) => 0,
-                        Some(opcode) if (OP_1..=OP_16).contains(&opcode) => {
-                            u32::from(opcode.saturating_sub(OP_1).saturating_add(1))
-                        }
-                        _ => 20,
-                    };
-                    count = count.saturating_add(sigops);
-                }
-                0x01..=0x4b => cursor = cursor.saturating_add(opcode as usize),
-                OP_PUSHDATA1 => {
-                    if let Some(len) = script.get(cursor).copied() {
-                        cursor = cursor.saturating_add(1 + len as usize);
-                    } else {
-                        break;
-                    }
-                }
-                OP_PUSHDATA2 => {
-                    if cursor + 1 >= script.len() {
-                        break;
-                    }
-                    let len = u16::from_le_bytes([script[cursor], script[cursor + 1]]) as usize;
-                    cursor = cursor.saturating_add(2 + len);
-                }
-                OP_PUSHDATA4 => {
-                    if cursor + 3 >= script.len() {
-                        break;
-                    }
-                    let len = u32::from_le_bytes([
-                        script[cursor],
-                        script[cursor + 1],
-                        script[cursor + 2],
-                        script[cursor + 3],
-                    ]) as usize;
-                    cursor = cursor.saturating_add(4 + len);
-                }
-                _ => {}
-            }
-            prev_opcode = Some(opcode);
-            if cursor > script.len() {
-                break;
-            }
-        }
-        count
-    }
-
-    fn tx_sigops(tx: &Transaction) -> u32 {
-        let input_ops: u32 = tx
-            .vin
-            .iter()
-            .map(|input| legacy_sigops(&input.script_sig))
-            .sum();
-        let output_ops: u32 = tx
-            .vout
-            .iter()
-            .map(|output| legacy_sigops(&output.script_pubkey))
-            .sum();
-        input_ops.saturating_add(output_ops)
-    }
-
     #[derive(Clone)]
     struct TemplateTx {
         fee: i64,
         modified_fee: i64,
         size: usize,
+        sigops: usize,
         priority: f64,
         fee_delta: i64,
         priority_delta: f64,
@@ -14677,9 +15834,13 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
         }
     }
 
-    let coinbase_size = make_coinbase(0)
-        .and_then(|tx| tx.consensus_encode().map_err(map_internal))
-        .map(|bytes| bytes.len())?;
+    let empty_coinbase = make_coinbase(0)?;
+    let coinbase_size = empty_coinbase
+        .consensus_encode()
+        .map_err(map_internal)?
+        .len();
+    let coinbase_sigops = count_sigops(&empty_coinbase, SCRIPT_VERIFY_NONE, &[]);
+    let block_sigops_limit = (MAX_BLOCK_SIGOPS as usize).saturating_sub(coinbase_sigops);
     let coinbase_overhead_bytes = 1024usize;
     let mut block_bytes_limit = usize::try_from(MAX_BLOCK_SIZE).unwrap_or(0);
     block_bytes_limit = block_bytes_limit
@@ -14710,6 +15871,7 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
                     fee: entry.fee,
                     modified_fee,
                     size: entry.size(),
+                    sigops: entry.sigops,
                     priority: entry.modified_current_priority(height),
                     fee_delta: entry.fee_delta,
                     priority_delta: entry.priority_delta,
@@ -14760,6 +15922,7 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
         let mut selected_set: HashSet<Hash256> = HashSet::new();
         let mut selected_fees: i64 = 0;
         let mut selected_bytes: usize = 0;
+        let mut selected_sigops: usize = 0;
         let mut sorted_by_fee = block_priority_bytes_limit == 0;
 
         while !heap.is_empty() || !priority_heap.is_empty() {
@@ -14787,6 +15950,9 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
             if selected_bytes.saturating_add(entry.size) > block_bytes_limit {
                 continue;
             }
+            if selected_sigops.saturating_add(entry.sigops) > block_sigops_limit {
+                continue;
+            }
 
             if sorted_by_fee
                 && entry.priority_delta <= 0.0
@@ -14807,6 +15973,7 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
                 .checked_add(entry.fee)
                 .ok_or_else(|| map_internal("mempool fee overflow"))?;
             selected_bytes = selected_bytes.saturating_add(entry.size);
+            selected_sigops = selected_sigops.saturating_add(entry.sigops);
             selected_set.insert(candidate_txid);
             selected.push(candidate_txid);
 
@@ -14859,7 +16026,7 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
                 "hash": hash256_to_hex(txid),
                 "fee": entry.fee,
                 "depends": depends,
-                "sigops": tx_sigops(&entry.tx),
+                "sigops": entry.sigops,
             }));
         }
 
@@ -14884,7 +16051,6 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
     let coinbase_bytes = coinbase.consensus_encode().map_err(map_internal)?;
     let coinbase_txid = coinbase.txid().map_err(map_internal)?;
     let miner_reward = coinbase.vout.first().map(|out| out.value).unwrap_or(0);
-    let coinbase_sigops = tx_sigops(&coinbase);
 
     let target = compact_to_u256(bits).map_err(|err| map_internal(err.to_string()))?;
     let target_hex = hex_bytes(&target.to_big_endian());
@@ -14987,32 +16153,15 @@ fn rpc_getblocktemplate<S: fluxd_storage::KeyValueStore>(
         );
     }
 
-    if exchange_fund > 0 {
-        result.insert(
-            "flux_creation_address".to_string(),
-            Value::String(chain_params.funding.exchange_address.to_string()),
-        );
-        result.insert(
-            "flux_creation_amount".to_string(),
-            Value::Number(Number::from(exchange_fund)),
-        );
-    } else if foundation_fund > 0 {
-        result.insert(
-            "flux_creation_address".to_string(),
-            Value::String(chain_params.funding.foundation_address.to_string()),
-        );
-        result.insert(
-            "flux_creation_amount".to_string(),
-            Value::Number(Number::from(foundation_fund)),
-        );
-    } else if swap_pool > 0 {
+    // fluxd reports one creation output: exchange, else foundation, else swap pool.
+    if let Some(funding) = funding_outputs.first() {
         result.insert(
             "flux_creation_address".to_string(),
-            Value::String(chain_params.swap_pool.address.to_string()),
+            Value::String(funding.address.to_string()),
         );
         result.insert(
             "flux_creation_amount".to_string(),
-            Value::Number(Number::from(swap_pool)),
+            Value::Number(Number::from(funding.amount)),
         );
     }
 
@@ -15114,7 +16263,8 @@ fn try_activate_best_header_from_unconnected<S: fluxd_storage::KeyValueStore>(
     chain_params: &ChainParams,
     flags: &ValidationFlags,
 ) -> Result<Option<String>, RpcError> {
-    crate::reorg_to_best_header(chainstate, write_lock).map_err(map_internal)?;
+    crate::reorg_to_best_header(chainstate, &chain_params.consensus, write_lock)
+        .map_err(map_internal)?;
 
     const MAX_CONNECT_PER_CALL: usize = 512;
     loop {
@@ -15172,7 +16322,8 @@ fn try_activate_best_header_from_unconnected<S: fluxd_storage::KeyValueStore>(
                 Err(ChainStateError::InvalidHeader("block does not extend best block tip"))
                 | Err(ChainStateError::InvalidHeader("block height does not match header index")) =>
                 {
-                    crate::reorg_to_best_header(chainstate, write_lock).map_err(map_internal)?;
+                    crate::reorg_to_best_header(chainstate, &chain_params.consensus, write_lock)
+                        .map_err(map_internal)?;
                     return Ok(None);
                 }
                 Err(ChainStateError::InvalidHeader(message)) => {
@@ -15218,7 +16369,8 @@ fn try_activate_best_header_from_unconnected<S: fluxd_storage::KeyValueStore>(
                 }
             };
             if should_reorg {
-                crate::reorg_to_best_header(chainstate, write_lock).map_err(map_internal)?;
+                crate::reorg_to_best_header(chainstate, &chain_params.consensus, write_lock)
+                    .map_err(map_internal)?;
                 return Ok(None);
             }
 
@@ -15517,9 +16669,328 @@ fn rpc_submitblock<S: fluxd_storage::KeyValueStore>(
     Ok(map_result(Value::Null))
 }
 
+const DEFAULT_GENERATE_MAX_TRIES: u64 = 1_000_000;
+
+fn rpc_generate<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    mempool: &Mutex<Mempool>,
+    mempool_policy: &MempoolPolicy,
+    fee_estimator: &Mutex<FeeEstimator>,
+    wallet: &Mutex<Wallet>,
+    miner_address: Option<&str>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+    flags: &ValidationFlags,
+) -> Result<Value, RpcError> {
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "generate expects 1 or 2 parameters",
+        ));
+    }
+    ensure_regtest(chain_params)?;
+    let nblocks = parse_u32(&params[0], "nblocks")?;
+    let max_tries = params
+        .get(1)
+        .map(|value| parse_u32(value, "maxtries"))
+        .transpose()?
+        .map(u64::from)
+        .unwrap_or(DEFAULT_GENERATE_MAX_TRIES);
+    let address = match miner_address {
+        Some(address) => address.to_string(),
+        None => {
+            let mut guard = wallet
+                .lock()
+                .map_err(|_| RpcError::new(RPC_INTERNAL_ERROR, "wallet lock poisoned"))?;
+            match guard.default_address().map_err(map_wallet_error)? {
+                Some(address) => address,
+                None => guard.generate_new_address(true).map_err(map_wallet_error)?,
+            }
+        }
+    };
+    generate_regtest_blocks(
+        chainstate,
+        write_lock,
+        mempool,
+        mempool_policy,
+        fee_estimator,
+        chain_params,
+        flags,
+        nblocks,
+        &address,
+        max_tries,
+    )
+}
+
+fn rpc_generatetoaddress<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    mempool: &Mutex<Mempool>,
+    mempool_policy: &MempoolPolicy,
+    fee_estimator: &Mutex<FeeEstimator>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+    flags: &ValidationFlags,
+) -> Result<Value, RpcError> {
+    if params.len() < 2 || params.len() > 3 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "generatetoaddress expects 2 or 3 parameters",
+        ));
+    }
+    ensure_regtest(chain_params)?;
+    let nblocks = parse_u32(&params[0], "nblocks")?;
+    let address = params[1]
+        .as_str()
+        .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "address must be a string"))?;
+    address_to_script_pubkey(address, chain_params.network)
+        .map_err(|_| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Invalid address"))?;
+    let max_tries = params
+        .get(2)
+        .map(|value| parse_u32(value, "maxtries"))
+        .transpose()?
+        .map(u64::from)
+        .unwrap_or(DEFAULT_GENERATE_MAX_TRIES);
+    generate_regtest_blocks(
+        chainstate,
+        write_lock,
+        mempool,
+        mempool_policy,
+        fee_estimator,
+        chain_params,
+        flags,
+        nblocks,
+        address,
+        max_tries,
+    )
+}
+
+fn ensure_regtest(chain_params: &ChainParams) -> Result<(), RpcError> {
+    if chain_params.network != Network::Regtest {
+        return Err(RpcError::new(
+            RPC_METHOD_NOT_FOUND,
+            "This method can only be used on regtest",
+        ));
+    }
+    Ok(())
+}
+
+/// Mines `nblocks` blocks on top of the tip from `getblocktemplate` output. Only the header
+/// hash is ground against the regtest target; no Equihash solution is attached and the blocks
+/// are connected with PoW checks off, so they are for local testing only. Stops early (with
+/// the hashes mined so far) once `max_tries` nonces have been tried in total.
+fn generate_regtest_blocks<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    mempool: &Mutex<Mempool>,
+    mempool_policy: &MempoolPolicy,
+    fee_estimator: &Mutex<FeeEstimator>,
+    chain_params: &ChainParams,
+    flags: &ValidationFlags,
+    nblocks: u32,
+    address: &str,
+    max_tries: u64,
+) -> Result<Value, RpcError> {
+    let mut flags = flags.clone();
+    flags.check_pow = false;
+    flags.check_pon = false;
+
+    let mut tries_left = max_tries;
+    let mut hashes = Vec::with_capacity(nblocks as usize);
+    while hashes.len() < nblocks as usize && tries_left > 0 {
+        let template = rpc_getblocktemplate(
+            chainstate,
+            mempool,
+            mempool_policy,
+            vec![json!({ "mineraddress": address })],
+            chain_params,
+            &flags,
+            None,
+        )?;
+        let (mut block, height) = block_from_template(&template)?;
+        let target =
+            compact_to_u256(block.header.bits).map_err(|err| map_internal(err.to_string()))?;
+
+        let mut nonce = 0u64;
+        let mut solved = false;
+        while tries_left > 0 {
+            tries_left -= 1;
+            block.header.nonce[..8].copy_from_slice(&nonce.to_le_bytes());
+            if U256::from_little_endian(&block.header.hash()) <= target {
+                solved = true;
+                break;
+            }
+            nonce = nonce.wrapping_add(1);
+        }
+        if !solved {
+            break;
+        }
+
+        let hash = connect_generated_block(
+            chainstate,
+            write_lock,
+            mempool,
+            fee_estimator,
+            chain_params,
+            &flags,
+            &block,
+            height,
+        )?;
+        hashes.push(Value::String(hash256_to_hex(&hash)));
+    }
+    Ok(Value::Array(hashes))
+}
+
+fn block_from_template(template: &Value) -> Result<(Block, i32), RpcError> {
+    let decode_tx = |value: Option<&Value>| -> Result<Transaction, RpcError> {
+        let bytes = value
+            .and_then(Value::as_str)
+            .and_then(bytes_from_hex)
+            .ok_or_else(|| map_internal("block template transaction missing data"))?;
+        Transaction::consensus_decode(&bytes).map_err(|err| map_internal(err.to_string()))
+    };
+
+    let mut transactions = vec![decode_tx(template["coinbasetxn"].get("data"))?];
+    for entry in template["transactions"].as_array().into_iter().flatten() {
+        transactions.push(decode_tx(entry.get("data"))?);
+    }
+    let txids = transactions
+        .iter()
+        .map(Transaction::txid)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| map_internal(err.to_string()))?;
+
+    let field = |name: &str| {
+        template
+            .get(name)
+            .ok_or_else(|| map_internal(format!("block template missing {name}")))
+    };
+    let version = field("version")?
+        .as_i64()
+        .and_then(|value| i32::try_from(value).ok())
+        .ok_or_else(|| map_internal("invalid block template version"))?;
+    let time = field("curtime")?
+        .as_u64()
+        .and_then(|value| u32::try_from(value).ok())
+        .ok_or_else(|| map_internal("invalid block template curtime"))?;
+    let bits = field("bits")?
+        .as_str()
+        .and_then(|value| u32::from_str_radix(value, 16).ok())
+        .ok_or_else(|| map_internal("invalid block template bits"))?;
+    let height = field("height")?
+        .as_i64()
+        .and_then(|value| i32::try_from(value).ok())
+        .ok_or_else(|| map_internal("invalid block template height"))?;
+
+    let header = BlockHeader {
+        version,
+        prev_block: parse_hash(field("previousblockhash")?)?,
+        merkle_root: crate::verify_chain::compute_merkle_root(&txids),
+        final_sapling_root: parse_hash(field("finalsaplingroothash")?)?,
+        time,
+        bits,
+        nonce: [0u8; 32],
+        solution: Vec::new(),
+        nodes_collateral: OutPoint::null(),
+        block_sig: Vec::new(),
+    };
+    Ok((
+        Block {
+            header,
+            transactions,
+        },
+        height,
+    ))
+}
+
+fn connect_generated_block<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    write_lock: &Mutex<()>,
+    mempool: &Mutex<Mempool>,
+    fee_estimator: &Mutex<FeeEstimator>,
+    chain_params: &ChainParams,
+    flags: &ValidationFlags,
+    block: &Block,
+    height: i32,
+) -> Result<Hash256, RpcError> {
+    let rejected = |err: &dyn std::fmt::Display| {
+        RpcError::new(RPC_MISC_ERROR, format!("generated block rejected: {err}"))
+    };
+    let hash = block.header.hash();
+    let bytes = block
+        .consensus_encode()
+        .map_err(|err| map_internal(err.to_string()))?;
+    let txids = fluxd_chainstate::validation::validate_block_with_txids(
+        block,
+        height,
+        &chain_params.consensus,
+        flags,
+    )
+    .map_err(|err| rejected(&err))?;
+
+    {
+        let _guard = write_lock
+            .lock()
+            .map_err(|_| map_internal("write lock poisoned"))?;
+        if chainstate
+            .best_block()
+            .map_err(map_internal)?
+            .is_some_and(|tip| tip.hash != block.header.prev_block)
+        {
+            return Err(RpcError::new(
+                RPC_MISC_ERROR,
+                "chain tip changed while generating block",
+            ));
+        }
+        let mut batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                std::slice::from_ref(&block.header),
+                &chain_params.consensus,
+                &mut batch,
+                false,
+            )
+            .map_err(|err| rejected(&err))?;
+        chainstate.commit_batch(batch).map_err(map_internal)?;
+        let batch = chainstate
+            .connect_block(
+                block,
+                height,
+                chain_params,
+                flags,
+                true,
+                Some(txids.as_slice()),
+                None,
+                Some(bytes.as_slice()),
+                None,
+            )
+            .map_err(|err| rejected(&err))?;
+        chainstate.commit_batch(batch).map_err(map_internal)?;
+    }
+
+    let purge = crate::purge_mempool_for_connected_block(mempool, height, block, txids.as_slice())
+        .map_err(map_internal)?;
+    if !purge.removed_txids.is_empty() || !purge.mined_entries.is_empty() {
+        let current_estimate = crate::current_fee_estimate(chainstate);
+        if let Ok(mut estimator) = fee_estimator.lock() {
+            for txid in &purge.removed_txids {
+                estimator.remove_transaction(txid);
+            }
+            estimator.process_block(
+                u32::try_from(height.max(0)).unwrap_or(0),
+                purge.mined_entries.as_slice(),
+                current_estimate,
+            );
+        }
+    }
+    Ok(hash)
+}
+
 fn rpc_getfluxnodecount<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     params: Vec<Value>,
+    chain_params: &ChainParams,
 ) -> Result<Value, RpcError> {
     ensure_no_params(&params)?;
     let records = chainstate.fluxnode_records().map_err(map_internal)?;
@@ -15551,6 +17022,27 @@ fn rpc_getfluxnodecount<S: fluxd_storage::KeyValueStore>(
     }
 
     let total = cumulus_enabled + nimbus_enabled + stratus_enabled;
+    let height = chainstate
+        .best_block()
+        .map_err(map_internal)?
+        .map(|tip| tip.height)
+        .unwrap_or(0);
+    let tiers: Vec<Value> = chain_params
+        .fluxnode
+        .tiers_at(height)
+        .into_iter()
+        .map(|spec| {
+            let mut entry = json!({
+                "tier": spec.tier,
+                "name": spec.name,
+                "collateral": amount_to_value(spec.collateral),
+            });
+            if let Some(legacy) = spec.legacy_collateral {
+                entry["legacy_collateral"] = amount_to_value(legacy);
+            }
+            entry
+        })
+        .collect();
 
     Ok(json!({
         "total": total,
@@ -15564,6 +17056,7 @@ fn rpc_getfluxnodecount<S: fluxd_storage::KeyValueStore>(
         "ipv4": ipv4,
         "ipv6": ipv6,
         "onion": onion,
+        "tiers": tiers,
     }))
 }
 
@@ -15687,7 +17180,7 @@ fn rpc_viewdeterministicfluxnodelist<S: fluxd_storage::KeyValueStore>(
             if record.collateral_value > 0 {
                 obj.insert(
                     "amount".to_string(),
-                    Value::String(format_money(record.collateral_value)),
+                    Value::String(money::format(record.collateral_value)),
                 );
             }
             obj.insert("rank".to_string(), Value::Number((rank as i64).into()));
@@ -15699,6 +17192,226 @@ fn rpc_viewdeterministicfluxnodelist<S: fluxd_storage::KeyValueStore>(
     Ok(Value::Array(out))
 }
 
+/// Start-transaction expiration window and DoS cooldown at `height`, in blocks.
+fn fluxnode_start_windows(height: i32, chain_params: &ChainParams) -> (i32, i32) {
+    if network_upgrade_active(height, &chain_params.consensus.upgrades, UpgradeIndex::Pon) {
+        (
+            FLUXNODE_START_TX_EXPIRATION_HEIGHT_V2,
+            FLUXNODE_DOS_REMOVE_AMOUNT_V2,
+        )
+    } else {
+        (
+            FLUXNODE_START_TX_EXPIRATION_HEIGHT,
+            FLUXNODE_DOS_REMOVE_AMOUNT,
+        )
+    }
+}
+
+fn fluxnode_record_status(
+    record: &FluxnodeRecord,
+    best_height: i32,
+    chain_params: &ChainParams,
+) -> &'static str {
+    let (expiration, dos_remove) = fluxnode_start_windows(best_height, chain_params);
+    if record.confirmed_height != 0 {
+        "CONFIRMED"
+    } else if best_height >= record.start_height as i32 {
+        let age = best_height.saturating_sub(record.start_height as i32);
+        if age <= expiration {
+            "STARTED"
+        } else if age <= dos_remove {
+            "DOS"
+        } else {
+            "OFFLINE"
+        }
+    } else {
+        "STARTED"
+    }
+}
+
+/// First height at which the record drops out of its current state: the confirm expiration
+/// used by payee selection for confirmed nodes, the start-transaction window otherwise.
+fn fluxnode_expires_at_height(
+    record: &FluxnodeRecord,
+    best_height: i32,
+    chain_params: &ChainParams,
+) -> Option<u32> {
+    if record.confirmed_height != 0 {
+        return Some(fluxnode_confirm_expire_height(
+            record.last_confirmed_height,
+            &chain_params.consensus,
+        ));
+    }
+    let (expiration, _) = fluxnode_start_windows(best_height, chain_params);
+    let expiration = u32::try_from(expiration).ok()?;
+    Some(
+        record
+            .start_height
+            .saturating_add(expiration)
+            .saturating_add(1),
+    )
+}
+
+enum FluxnodeListFilter {
+    All,
+    Tier(u8),
+    Payout(Vec<u8>),
+}
+
+fn parse_fluxnode_list_filter(
+    value: Option<&Value>,
+    chain_params: &ChainParams,
+) -> Result<FluxnodeListFilter, RpcError> {
+    let text = match value {
+        None | Some(Value::Null) => return Ok(FluxnodeListFilter::All),
+        Some(Value::Number(num)) => num.to_string(),
+        Some(Value::String(text)) => text.trim().to_string(),
+        Some(_) => {
+            return Err(RpcError::new(
+                RPC_INVALID_PARAMETER,
+                "filter must be a tier or a payout address",
+            ))
+        }
+    };
+    if text.is_empty() {
+        return Ok(FluxnodeListFilter::All);
+    }
+    let tier = match text.to_ascii_lowercase().as_str() {
+        "1" | "cumulus" | "basic" => Some(1),
+        "2" | "nimbus" | "super" => Some(2),
+        "3" | "stratus" | "bamf" => Some(3),
+        _ => None,
+    };
+    if let Some(tier) = tier {
+        return Ok(FluxnodeListFilter::Tier(tier));
+    }
+    if text.parse::<i64>().is_ok() {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "tier must be 1 (CUMULUS), 2 (NIMBUS) or 3 (STRATUS)",
+        ));
+    }
+    let script_pubkey = address_to_script_pubkey(&text, chain_params.network).map_err(|_| {
+        RpcError::new(
+            RPC_INVALID_ADDRESS_OR_KEY,
+            "filter is neither a tier nor a valid payout address",
+        )
+    })?;
+    Ok(FluxnodeListFilter::Payout(script_pubkey))
+}
+
+fn rpc_listfluxnodes<S: fluxd_storage::KeyValueStore>(
+    chainstate: &ChainState<S>,
+    params: Vec<Value>,
+    chain_params: &ChainParams,
+) -> Result<Value, RpcError> {
+    if params.len() > 1 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "listfluxnodes expects 0 or 1 parameter",
+        ));
+    }
+    let filter = parse_fluxnode_list_filter(params.first(), chain_params)?;
+    let indexed = chainstate.fluxnode_index_ready().map_err(map_internal)?;
+    let mut records = match &filter {
+        FluxnodeListFilter::Tier(tier) if indexed => {
+            chainstate.fluxnodes_by_tier(*tier).map_err(map_internal)?
+        }
+        FluxnodeListFilter::Payout(script_pubkey) if indexed => chainstate
+            .fluxnode_by_payout_script(script_pubkey, chain_params)
+            .map_err(map_internal)?,
+        _ => chainstate.fluxnode_records().map_err(map_internal)?,
+    };
+    records.sort_by(|a, b| {
+        a.tier
+            .cmp(&b.tier)
+            .then_with(|| a.collateral.hash.cmp(&b.collateral.hash))
+            .then_with(|| a.collateral.index.cmp(&b.collateral.index))
+    });
+
+    let best_height = best_block_height(chainstate)?;
+    let payout_filter = match &filter {
+        FluxnodeListFilter::Payout(script_pubkey) => {
+            script_pubkey_to_address(script_pubkey, chain_params.network)
+        }
+        _ => None,
+    };
+    let mut out = Vec::with_capacity(records.len());
+    for record in records {
+        if let FluxnodeListFilter::Tier(tier) = filter {
+            if record.tier != tier {
+                continue;
+            }
+        }
+        let payment_address =
+            fluxnode_payment_address(chainstate, &record, chain_params)?.unwrap_or_default();
+        if let Some(wanted) = payout_filter.as_deref() {
+            if payment_address != wanted {
+                continue;
+            }
+        }
+        let expires_at_height = fluxnode_expires_at_height(&record, best_height, chain_params);
+
+        let mut obj = serde_json::Map::new();
+        obj.insert(
+            "collateral".to_string(),
+            Value::String(format_outpoint(&record.collateral)),
+        );
+        obj.insert(
+            "txhash".to_string(),
+            Value::String(hash256_to_hex(&record.collateral.hash)),
+        );
+        obj.insert(
+            "outidx".to_string(),
+            Value::Number((record.collateral.index as i64).into()),
+        );
+        obj.insert(
+            "tier".to_string(),
+            Value::String(fluxnode_tier_name(record.tier).to_string()),
+        );
+        obj.insert(
+            "status".to_string(),
+            Value::String(fluxnode_record_status(&record, best_height, chain_params).to_string()),
+        );
+        obj.insert(
+            "payment_address".to_string(),
+            Value::String(payment_address),
+        );
+        obj.insert("ip".to_string(), Value::String(record.ip.clone()));
+        obj.insert(
+            "added_height".to_string(),
+            Value::Number((record.start_height as i64).into()),
+        );
+        obj.insert(
+            "confirmed_height".to_string(),
+            Value::Number((record.confirmed_height as i64).into()),
+        );
+        obj.insert(
+            "last_confirmed_height".to_string(),
+            Value::Number((record.last_confirmed_height as i64).into()),
+        );
+        obj.insert(
+            "last_paid_height".to_string(),
+            Value::Number((record.last_paid_height as i64).into()),
+        );
+        obj.insert(
+            "expires_at_height".to_string(),
+            expires_at_height
+                .map(|height| Value::Number(height.into()))
+                .unwrap_or(Value::Null),
+        );
+        if record.collateral_value > 0 {
+            obj.insert(
+                "amount".to_string(),
+                Value::String(money::format(record.collateral_value)),
+            );
+        }
+        out.push(Value::Object(obj));
+    }
+
+    Ok(Value::Array(out))
+}
+
 fn rpc_fluxnodecurrentwinner<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     params: Vec<Value>,
@@ -15925,7 +17638,7 @@ fn rpc_getfluxnodeoutputs<S: fluxd_storage::KeyValueStore>(
     let mut out = Vec::new();
     for entry in conf_entries {
         let utxo = chainstate
-            .utxo_entry(&entry.collateral)
+            .utxo_entry(&entry.collateral.txid(), entry.collateral.index)
             .map_err(map_internal)?;
         let Some(utxo) = utxo else {
             continue;
@@ -16125,7 +17838,7 @@ fn resolve_fluxnode_collateral_secret_and_redeem_script<S: fluxd_storage::KeyVal
     };
 
     let utxo = chainstate
-        .utxo_entry(&entry.collateral)
+        .utxo_entry(&entry.collateral.txid(), entry.collateral.index)
         .map_err(map_internal)?
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "collateral output not found"))?;
 
@@ -16267,7 +17980,7 @@ fn build_fluxnode_start_tx<S: fluxd_storage::KeyValueStore>(
     let operator_pubkey = secret_key_pubkey_bytes(&operator_secret, operator_compressed);
 
     let utxo = chainstate
-        .utxo_entry(&entry.collateral)
+        .utxo_entry(&entry.collateral.txid(), entry.collateral.index)
         .map_err(map_internal)?
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "collateral output not found"))?;
 
@@ -16314,6 +18027,7 @@ fn build_fluxnode_start_tx<S: fluxd_storage::KeyValueStore>(
                     join_split_pub_key: [0u8; 32],
                     join_split_sig: [0u8; 64],
                     binding_sig: [0u8; 64],
+                    raw_tail: Vec::new(),
                     fluxnode: Some(FluxnodeTx::V6(FluxnodeTxV6::Start(FluxnodeStartV6 {
                         flux_tx_version: if using_delegates {
                             FLUXNODE_TX_TYPE_NORMAL_BIT | FLUXNODE_TX_FEATURE_DELEGATES_BIT
@@ -16347,6 +18061,7 @@ fn build_fluxnode_start_tx<S: fluxd_storage::KeyValueStore>(
                     join_split_pub_key: [0u8; 32],
                     join_split_sig: [0u8; 64],
                     binding_sig: [0u8; 64],
+                    raw_tail: Vec::new(),
                     fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
                         collateral: entry.collateral.clone(),
                         collateral_pubkey: collateral_pubkey.clone(),
@@ -16435,6 +18150,7 @@ fn build_fluxnode_start_tx<S: fluxd_storage::KeyValueStore>(
                 join_split_pub_key: [0u8; 32],
                 join_split_sig: [0u8; 64],
                 binding_sig: [0u8; 64],
+                raw_tail: Vec::new(),
                 fluxnode: Some(FluxnodeTx::V6(FluxnodeTxV6::Start(FluxnodeStartV6 {
                     flux_tx_version: if using_delegates {
                         FLUXNODE_TX_TYPE_P2SH_BIT | FLUXNODE_TX_FEATURE_DELEGATES_BIT
@@ -17009,36 +18725,7 @@ fn rpc_getfluxnodestatus<S: fluxd_storage::KeyValueStore>(
     let record = record.expect("checked");
 
     let best_height = best_block_height(chainstate)?;
-    let pon_active = network_upgrade_active(
-        best_height,
-        &chain_params.consensus.upgrades,
-        UpgradeIndex::Pon,
-    );
-    let expiration = if pon_active {
-        FLUXNODE_START_TX_EXPIRATION_HEIGHT_V2
-    } else {
-        FLUXNODE_START_TX_EXPIRATION_HEIGHT
-    };
-    let dos_remove = if pon_active {
-        FLUXNODE_DOS_REMOVE_AMOUNT_V2
-    } else {
-        FLUXNODE_DOS_REMOVE_AMOUNT
-    };
-
-    let status = if record.confirmed_height != 0 {
-        "CONFIRMED"
-    } else if best_height >= record.start_height as i32 {
-        let age = best_height.saturating_sub(record.start_height as i32);
-        if age <= expiration {
-            "STARTED"
-        } else if age <= dos_remove {
-            "DOS"
-        } else {
-            "OFFLINE"
-        }
-    } else {
-        "STARTED"
-    };
+    let status = fluxnode_record_status(&record, best_height, chain_params);
 
     let payment_address =
         fluxnode_status_payment_address(chainstate, &record, chain_params.network)?
@@ -17115,7 +18802,7 @@ fn rpc_getfluxnodestatus<S: fluxd_storage::KeyValueStore>(
     if record.collateral_value > 0 {
         info.insert(
             "amount".to_string(),
-            Value::String(format_money(record.collateral_value)),
+            Value::String(money::format(record.collateral_value)),
         );
     }
 
@@ -17237,7 +18924,7 @@ fn rpc_createp2shstarttx<S: fluxd_storage::KeyValueStore>(
     };
 
     let utxo = chainstate
-        .utxo_entry(&collateral)
+        .utxo_entry(&collateral.txid(), collateral.index)
         .map_err(map_internal)?
         .ok_or_else(|| RpcError::new(RPC_TRANSACTION_ERROR, "Coins not found in chain"))?;
     let script_hash = extract_p2sh_hash(&utxo.script_pubkey).ok_or_else(|| {
@@ -17281,6 +18968,7 @@ fn rpc_createp2shstarttx<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: Some(FluxnodeTx::V6(FluxnodeTxV6::Start(FluxnodeStartV6 {
             flux_tx_version,
             variant: FluxnodeStartVariantV6::P2sh {
@@ -17854,6 +19542,7 @@ fn rpc_startfluxnodeasdelegate<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: Some(FluxnodeTx::V6(FluxnodeTxV6::Start(FluxnodeStartV6 {
             flux_tx_version: FLUXNODE_TX_TYPE_NORMAL_BIT | FLUXNODE_TX_FEATURE_DELEGATES_BIT,
             variant: FluxnodeStartVariantV6::Normal {
@@ -18073,7 +19762,7 @@ fn rpc_startp2shasdelegate<S: fluxd_storage::KeyValueStore>(
     }
 
     let utxo = chainstate
-        .utxo_entry(&outpoint)
+        .utxo_entry(&outpoint.txid(), outpoint.index)
         .map_err(map_internal)?
         .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "collateral output not found"))?;
     let script_hash = extract_p2sh_hash(&utxo.script_pubkey)
@@ -18106,6 +19795,7 @@ fn rpc_startp2shasdelegate<S: fluxd_storage::KeyValueStore>(
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: Some(FluxnodeTx::V6(FluxnodeTxV6::Start(FluxnodeStartV6 {
             flux_tx_version: FLUXNODE_TX_TYPE_P2SH_BIT | FLUXNODE_TX_FEATURE_DELEGATES_BIT,
             variant: FluxnodeStartVariantV6::P2sh {
@@ -18418,7 +20108,7 @@ fn rpc_getdoslist<S: fluxd_storage::KeyValueStore>(
         if record.collateral_value > 0 {
             obj.insert(
                 "amount".to_string(),
-                Value::String(format_money(record.collateral_value)),
+                Value::String(money::format(record.collateral_value)),
             );
         }
         entries.push(Value::Object(obj));
@@ -18493,7 +20183,7 @@ fn rpc_getstartlist<S: fluxd_storage::KeyValueStore>(
         if record.collateral_value > 0 {
             obj.insert(
                 "amount".to_string(),
-                Value::String(format_money(record.collateral_value)),
+                Value::String(money::format(record.collateral_value)),
             );
         }
         entries.push(Value::Object(obj));
@@ -18550,7 +20240,7 @@ fn rpc_getblockhashes<S: fluxd_storage::KeyValueStore>(
                 None => continue,
             };
             let main_hash = chainstate.height_hash(entry.height).map_err(map_internal)?;
-            if main_hash != Some(hash) {
+            if main_hash != Some(BlockHash::from_internal(hash)) {
                 continue;
             }
         }
@@ -18605,7 +20295,7 @@ fn rpc_gettxstats<S: fluxd_storage::KeyValueStore>(
                 None => continue,
             };
             let main_hash = chainstate.height_hash(entry.height).map_err(map_internal)?;
-            if main_hash != Some(hash) {
+            if main_hash != Some(BlockHash::from_internal(hash)) {
                 continue;
             }
         }
@@ -18665,7 +20355,7 @@ fn network_hashps<S: fluxd_storage::KeyValueStore>(
         if height >= 0 && height < best.height {
             if let Some(hash) = chainstate.height_hash(height).map_err(map_internal)? {
                 pb_height = height;
-                pb_hash = hash;
+                pb_hash = hash.to_internal();
             } else {
                 return Ok(0);
             }
@@ -18879,6 +20569,64 @@ fn rpc_estimatepriority(
     }
 }
 
+fn raw_bucket_range_json(range: &RawBucketRange) -> Value {
+    json!({
+        "startrange": range.start_range,
+        "endrange": range.end_range,
+        "withintarget": range.within_target,
+        "totalconfirmed": range.total_confirmed,
+        "inmempool": range.in_mempool,
+    })
+}
+
+fn rpc_estimaterawfee(
+    params: Vec<Value>,
+    fee_estimator: &Mutex<FeeEstimator>,
+) -> Result<Value, RpcError> {
+    if params.len() != 1 {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            "estimaterawfee expects 1 parameter",
+        ));
+    }
+    let target = parse_u32(&params[0], "conf_target")?;
+    let guard = fee_estimator
+        .lock()
+        .map_err(|_| map_internal("fee estimator lock poisoned"))?;
+    let max_target = guard.max_target();
+    if target < 1 || target > max_target {
+        return Err(RpcError::new(
+            RPC_INVALID_PARAMETER,
+            format!("Invalid conf_target, must be between 1 - {max_target}"),
+        ));
+    }
+    let raw = guard.raw_estimate(target);
+    drop(guard);
+
+    let mut result = json!({
+        "target": raw.target,
+        "threshold": raw.success_threshold,
+        "decay": raw.decay,
+        "scale": 1,
+        "bestseenheight": raw.best_seen_height,
+        "datapoints": raw.data_points,
+        "sufficientdatapoints": raw.sufficient_data_points,
+    });
+    if let Some(fee_per_kb) = raw.fee_per_kb {
+        result["feerate"] = amount_to_value(fee_per_kb);
+    }
+    if let Some(pass) = raw.pass.as_ref() {
+        result["pass"] = raw_bucket_range_json(pass);
+    }
+    if let Some(fail) = raw.fail.as_ref() {
+        result["fail"] = raw_bucket_range_json(fail);
+    }
+    if raw.fee_per_kb.is_none() {
+        result["errors"] = json!(["Insufficient data or no feerate found which meets threshold"]);
+    }
+    Ok(result)
+}
+
 fn rpc_prioritisetransaction(
     params: Vec<Value>,
     mempool: &Mutex<Mempool>,
@@ -18913,6 +20661,12 @@ fn rpc_getnetworkinfo(
     let local_services_hex = format!("{:016x}", local_services);
     let snapshot = net_totals.snapshot();
     let connections = snapshot.connections.max(peer_registry.count());
+    let (connections_in, connections_out) = peer_registry.direction_counts();
+    let local_addresses: Vec<Value> = peer_registry
+        .local_addr()
+        .map(|addr| json!({"address": addr.ip().to_string(), "port": addr.port(), "score": 1}))
+        .into_iter()
+        .collect();
     let networks = json!([
         {"name": "ipv4", "limited": false, "reachable": true, "proxy": ""},
         {"name": "ipv6", "limited": false, "reachable": true, "proxy": ""},
@@ -18926,9 +20680,11 @@ fn rpc_getnetworkinfo(
         "localservicesnames": service_flag_names(local_services),
         "timeoffset": 0,
         "connections": connections,
+        "connections_in": connections_in,
+        "connections_out": connections_out,
         "networks": networks,
         "relayfee": amount_to_value(mempool_policy.min_relay_fee_per_kb),
-        "localaddresses": [],
+        "localaddresses": local_addresses,
         "warnings": ""
     }))
 }
@@ -18951,10 +20707,20 @@ fn rpc_getnettotals(params: Vec<Value>, net_totals: &NetTotals) -> Result<Value,
         .duration_since(UNIX_EPOCH)
         .map(|value| value.as_millis() as i64)
         .unwrap_or(0);
+    let upload = net_totals.upload_target();
     Ok(json!({
         "totalbytesrecv": snapshot.bytes_recv,
         "totalbytessent": snapshot.bytes_sent,
         "timemillis": timemillis,
+        "uploadtarget": {
+            "timeframe": UPLOAD_TARGET_TIMEFRAME_SECS,
+            "target": upload.target,
+            "target_reached": upload.target_reached,
+            "serve_historical_blocks": !upload.target_reached,
+            "bytes_sent_in_cycle": upload.bytes_sent,
+            "bytes_left_in_cycle": upload.bytes_left,
+            "time_left_in_cycle": upload.secs_until_release,
+        },
     }))
 }
 
@@ -18965,7 +20731,7 @@ fn rpc_getpeerinfo(params: Vec<Value>, peer_registry: &PeerRegistry) -> Result<V
     for peer in peers {
         let services_hex = format!("{:016x}", peer.services);
         let services_names = service_flag_names(peer.services);
-        out.push(json!({
+        let mut entry = json!({
             "addr": peer.addr.to_string(),
             "subver": peer.user_agent,
             "version": peer.version,
@@ -18979,7 +20745,14 @@ fn rpc_getpeerinfo(params: Vec<Value>, peer_registry: &PeerRegistry) -> Result<V
             "bytesrecv": peer.bytes_recv,
             "inbound": peer.inbound,
             "kind": peer_kind_name(peer.kind),
-        }));
+        });
+        if let Some(ms) = peer.last_ping_ms {
+            entry["pingtime"] = json!(ms as f64 / 1000.0);
+        }
+        if let Some(ms) = peer.min_ping_ms {
+            entry["minping"] = json!(ms as f64 / 1000.0);
+        }
+        out.push(entry);
     }
     Ok(Value::Array(out))
 }
@@ -19024,13 +20797,20 @@ fn rpc_listbanned(
 ) -> Result<Value, RpcError> {
     ensure_no_params(&params)?;
     let banned = header_peer_book.banned_peers();
-    let mut out = Vec::with_capacity(banned.len());
+    let subnets = header_peer_book.banned_subnets();
+    let mut out = Vec::with_capacity(banned.len() + subnets.len());
     for entry in banned {
         out.push(json!({
             "address": entry.addr.to_string(),
             "banned_until": system_time_to_unix(entry.banned_until),
         }));
     }
+    for entry in subnets {
+        out.push(json!({
+            "address": entry.subnet.to_string(),
+            "banned_until": system_time_to_unix(entry.banned_until),
+        }));
+    }
     Ok(Value::Array(out))
 }
 
@@ -19081,6 +20861,21 @@ fn rpc_clearbanned(
     Ok(Value::Null)
 }
 
+#[derive(Clone, Copy)]
+enum BanTarget {
+    Addr(SocketAddr),
+    Subnet(IpNet),
+}
+
+impl BanTarget {
+    fn unban(self, header_peer_book: &HeaderPeerBook) -> bool {
+        match self {
+            BanTarget::Addr(addr) => header_peer_book.unban(addr),
+            BanTarget::Subnet(subnet) => header_peer_book.unban_subnet(subnet),
+        }
+    }
+}
+
 fn rpc_setban(
     params: Vec<Value>,
     chain_params: &ChainParams,
@@ -19096,16 +20891,25 @@ fn rpc_setban(
     let addr_raw = params[0]
         .as_str()
         .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "address must be a string"))?;
-    let addr = parse_socket_addr_with_default(addr_raw, chain_params.default_port)?;
+    let target = if addr_raw.contains('/') {
+        BanTarget::Subnet(
+            IpNet::parse_named(addr_raw, "subnet")
+                .map_err(|err| RpcError::new(RPC_INVALID_PARAMETER, err))?,
+        )
+    } else {
+        BanTarget::Addr(parse_socket_addr_with_default(
+            addr_raw,
+            chain_params.default_port,
+        )?)
+    };
     let command = params[1]
         .as_str()
         .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "command must be a string"))?;
 
     match command {
         "add" => {
-            const DEFAULT_BAN_SECS: u64 = 24 * 60 * 60;
-
-            let mut bantime: u64 = DEFAULT_BAN_SECS;
+            let default_bantime = header_peer_book.ban_policy().default_secs;
+            let mut bantime: u64 = default_bantime;
             let mut absolute = false;
             if params.len() >= 3 && !params[2].is_null() {
                 let value = params[2].as_i64().ok_or_else(|| {
@@ -19119,7 +20923,7 @@ fn rpc_setban(
                 })?;
             }
             if bantime == 0 {
-                bantime = DEFAULT_BAN_SECS;
+                bantime = default_bantime;
             }
             if absolute {
                 let now = SystemTime::now()
@@ -19127,17 +20931,29 @@ fn rpc_setban(
                     .unwrap_or_default()
                     .as_secs();
                 if bantime <= now {
-                    header_peer_book.unban(addr);
+                    target.unban(header_peer_book);
                     return Ok(Value::Null);
                 }
                 bantime = bantime.saturating_sub(now);
             }
 
-            header_peer_book.ban_for(addr, bantime);
-            peer_registry.request_disconnect(addr);
+            match target {
+                BanTarget::Addr(addr) => {
+                    header_peer_book.ban_for(addr, bantime);
+                    peer_registry.request_disconnect(addr);
+                }
+                BanTarget::Subnet(subnet) => {
+                    header_peer_book.ban_subnet_for(subnet, bantime);
+                    for peer in peer_registry.snapshot() {
+                        if header_peer_book.is_banned(peer.addr) {
+                            peer_registry.request_disconnect(peer.addr);
+                        }
+                    }
+                }
+            }
         }
         "remove" => {
-            header_peer_book.unban(addr);
+            target.unban(header_peer_book);
         }
         _ => {
             return Err(RpcError::new(
@@ -19367,7 +21183,7 @@ fn branch_len<S: fluxd_storage::KeyValueStore>(
     let mut hash = *tip_hash;
     loop {
         if let Some(main_hash) = chainstate.height_hash(height).map_err(map_internal)? {
-            if main_hash == hash {
+            if main_hash == BlockHash::from_internal(hash) {
                 return Ok(tip_height - height);
             }
         }
@@ -19478,7 +21294,8 @@ fn script_type_name(script: &[u8]) -> &'static str {
         ScriptType::P2Sh => "scripthash",
         ScriptType::P2Wpkh => "witness_v0_keyhash",
         ScriptType::P2Wsh => "witness_v0_scripthash",
-        ScriptType::Unknown => "nonstandard",
+        // Witness programs without a named type keep the historical `nonstandard` label.
+        ScriptType::Witness | ScriptType::Unknown => "nonstandard",
     }
 }
 
@@ -19633,28 +21450,6 @@ fn amount_to_value(amount: i64) -> Value {
         .unwrap_or(Value::Number(0.into()))
 }
 
-fn format_money(amount: i64) -> String {
-    let abs = (amount as i128).abs();
-    let whole = abs / COIN as i128;
-    let frac = abs % COIN as i128;
-    let mut out = format!("{whole}.{frac:08}");
-
-    while out.len() >= 3 {
-        let len = out.len();
-        let bytes = out.as_bytes();
-        if bytes[len - 1] == b'0' && bytes[len - 3].is_ascii_digit() {
-            out.pop();
-        } else {
-            break;
-        }
-    }
-
-    if amount < 0 {
-        out.insert(0, '-');
-    }
-    out
-}
-
 fn parse_amount(value: &Value) -> Result<i64, RpcError> {
     let text = match value {
         Value::Number(num) => num.to_string(),
@@ -19666,71 +21461,7 @@ fn parse_amount(value: &Value) -> Result<i64, RpcError> {
             ))
         }
     };
-    let text = text.trim();
-    if text.is_empty() {
-        return Err(RpcError::new(RPC_INVALID_PARAMETER, "amount is empty"));
-    }
-
-    let negative = text.starts_with('-');
-    let text = text.strip_prefix('-').unwrap_or(text);
-
-    let (whole, fractional) = match text.split_once('.') {
-        Some((whole, fractional)) => (whole, fractional),
-        None => (text, ""),
-    };
-    if whole.is_empty() && fractional.is_empty() {
-        return Err(RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"));
-    }
-    if !whole.is_empty() && !whole.chars().all(|ch| ch.is_ascii_digit()) {
-        return Err(RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"));
-    }
-    if !fractional.is_empty() && !fractional.chars().all(|ch| ch.is_ascii_digit()) {
-        return Err(RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"));
-    }
-    if fractional.len() > 8 {
-        return Err(RpcError::new(
-            RPC_INVALID_PARAMETER,
-            "amount has too many decimal places",
-        ));
-    }
-
-    let whole_value = if whole.is_empty() {
-        0i64
-    } else {
-        whole
-            .parse::<i64>()
-            .map_err(|_| RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"))?
-    };
-    let fractional_value = if fractional.is_empty() {
-        0i64
-    } else {
-        let parsed = fractional
-            .parse::<i64>()
-            .map_err(|_| RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"))?;
-        let scale = 10i64.pow(8u32.saturating_sub(fractional.len() as u32));
-        parsed
-            .checked_mul(scale)
-            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"))?
-    };
-
-    let amount = whole_value
-        .checked_mul(COIN)
-        .and_then(|value| value.checked_add(fractional_value))
-        .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"))?;
-    let amount = if negative {
-        amount
-            .checked_neg()
-            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMETER, "invalid amount"))?
-    } else {
-        amount
-    };
-    if amount < 0 {
-        return Err(RpcError::new(RPC_INVALID_PARAMETER, "amount out of range"));
-    }
-    if !fluxd_consensus::money::money_range(amount) {
-        return Err(RpcError::new(RPC_INVALID_PARAMETER, "amount out of range"));
-    }
-    Ok(amount)
+    money::parse(&text).map_err(|err| RpcError::new(RPC_INVALID_PARAMETER, err.to_string()))
 }
 
 #[cfg(test)]
@@ -19738,10 +21469,10 @@ mod tests {
     use fluxd_primitives::transaction::OVERWINTER_VERSION_GROUP_ID;
 
     use super::*;
-    use crate::mempool::MempoolEntry;
     use fluxd_chainstate::flatfiles::FlatFileStore;
     use fluxd_chainstate::validation::ValidationFlags;
     use fluxd_consensus::params::{chain_params, Network};
+    use fluxd_consensus::{exchange_fund_amount, foundation_fund_amount, swap_pool_amount};
     use fluxd_fluxnode::storage::dedupe_key;
     use fluxd_primitives::block::BlockHeader;
     use fluxd_storage::memory::MemoryStore;
@@ -19925,6 +21656,7 @@ mod tests {
                 join_split_pub_key: [0u8; 32],
                 join_split_sig: [0u8; 64],
                 binding_sig: [0u8; 64],
+                raw_tail: Vec::new(),
                 fluxnode: None,
             };
 
@@ -20039,6 +21771,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -20147,6 +21880,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -20202,6 +21936,8 @@ mod tests {
         (chainstate, params, data_dir, address, coinbase_txid, 0)
     }
 
+    /// Mines and connects one block paying its miner output to `miner_script_pubkey`. Returns
+    /// that coinbase output as `(txid, vout, height, value)`; look the block hash up by height.
     fn mine_regtest_block_to_script(
         chainstate: &ChainState<MemoryStore>,
         params: &fluxd_consensus::params::ChainParams,
@@ -20276,6 +22012,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -20432,6 +22169,7 @@ mod tests {
             "headers",
             "bestblockhash",
             "difficulty",
+            "mediantime",
             "verificationprogress",
             "chainwork",
             "pruned",
@@ -20450,6 +22188,26 @@ mod tests {
         assert!(is_hex_64(best_block));
         assert!(obj.get("softforks").and_then(Value::as_array).is_some());
         assert!(obj.get("commitments").and_then(Value::as_u64).is_some());
+
+        // Genesis is the tip, so its header time is the median time past.
+        assert_eq!(
+            obj.get("mediantime").and_then(Value::as_i64),
+            Some(i64::from(params.consensus.genesis_time))
+        );
+        assert!(obj.get("size_on_disk").and_then(Value::as_u64).unwrap_or(0) > 0);
+        let upgrades = obj
+            .get("upgrades")
+            .and_then(Value::as_object)
+            .expect("upgrades");
+        assert!(!upgrades.is_empty());
+        for upgrade in upgrades.values() {
+            assert!(upgrade
+                .get("activationheight")
+                .and_then(Value::as_i64)
+                .is_some());
+            let status = upgrade.get("status").and_then(Value::as_str).unwrap_or("");
+            assert!(status == "active" || status == "pending", "{status}");
+        }
     }
 
     #[test]
@@ -20465,6 +22223,8 @@ mod tests {
             "txouts",
             "bytes_serialized",
             "hash_serialized",
+            "muhash",
+            "disk_size",
             "total_amount",
         ] {
             assert!(obj.contains_key(key), "missing key {key}");
@@ -20504,6 +22264,56 @@ mod tests {
         assert_eq!(address, computed.address_outpoint_entries);
     }
 
+    #[test]
+    fn getindexinfo_reports_unfinished_rebuilds() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let mempool = Mutex::new(Mempool::new(0));
+
+        let value = rpc_getindexinfo(&chainstate, Vec::new()).expect("rpc");
+        let obj = value.as_object().expect("object");
+        for name in [
+            "txindex",
+            "spentindex",
+            "addressindex",
+            "addressneighborindex",
+        ] {
+            assert!(obj.contains_key(name), "missing index {name}");
+        }
+        assert_eq!(
+            obj["txindex"],
+            json!({"synced": true, "best_block_height": 0, "reindexing": false})
+        );
+        assert_eq!(obj["addressneighborindex"]["synced"], json!(false));
+
+        let value =
+            rpc_getindexinfo(&chainstate, vec![Value::String("txindex".to_string())]).expect("rpc");
+        assert_eq!(value.as_object().expect("object").len(), 1);
+
+        let mut batch = WriteBatch::new();
+        batch.put(
+            Column::Meta,
+            crate::TXINDEX_REBUILD_HEIGHT_KEY,
+            0u32.to_le_bytes(),
+        );
+        chainstate.commit_batch(batch).expect("mark rebuild");
+
+        let value = rpc_getindexinfo(&chainstate, Vec::new()).expect("rpc");
+        assert_eq!(
+            value["txindex"],
+            json!({"synced": false, "best_block_height": 0, "reindexing": true})
+        );
+        assert_eq!(value["spentindex"]["synced"], json!(true));
+
+        let err = rpc_getrawtransaction(
+            &chainstate,
+            &mempool,
+            vec![Value::String(hash256_to_hex(&[0x42u8; 32]))],
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_MISC_ERROR);
+        assert!(err.message.contains("getindexinfo"), "{}", err.message);
+    }
 
     #[test]
     fn txoutproof_roundtrip_returns_txids() {
@@ -20613,14 +22423,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn getnetworkinfo_counts_connections_by_direction() {
+        let net_totals = NetTotals::default();
+        let peer_registry = PeerRegistry::default();
+        let mempool_policy = MempoolPolicy::standard(0, false);
+        let outbound: std::net::SocketAddr = "10.0.0.1:16125".parse().expect("addr");
+        let relay: std::net::SocketAddr = "10.0.0.2:16125".parse().expect("addr");
+        let inbound: std::net::SocketAddr = "10.0.0.3:40000".parse().expect("addr");
+        peer_registry.register(outbound, PeerKind::Block);
+        peer_registry.register(relay, PeerKind::Relay);
+        peer_registry.register_inbound(inbound, PeerKind::Relay);
+        peer_registry.set_local_addr(Some("203.0.113.7:16125".parse().expect("addr")));
+
+        let value = rpc_getnetworkinfo(Vec::new(), &peer_registry, &net_totals, &mempool_policy)
+            .expect("rpc");
+        assert_eq!(value["connections"].as_i64(), Some(3));
+        assert_eq!(value["connections_in"].as_i64(), Some(1));
+        assert_eq!(value["connections_out"].as_i64(), Some(2));
+        assert_eq!(
+            value["localaddresses"],
+            json!([{"address": "203.0.113.7", "port": 16125, "score": 1}])
+        );
+        let count = rpc_getconnectioncount(Vec::new(), &peer_registry, &net_totals).expect("rpc");
+        assert_eq!(count.as_i64(), Some(3));
+    }
+
     #[test]
     fn getnettotals_has_cpp_schema_keys() {
         let net_totals = NetTotals::default();
         let value = rpc_getnettotals(Vec::new(), &net_totals).expect("rpc");
         let obj = value.as_object().expect("object");
-        for key in ["totalbytesrecv", "totalbytessent", "timemillis"] {
+        for key in [
+            "totalbytesrecv",
+            "totalbytessent",
+            "timemillis",
+            "uploadtarget",
+        ] {
             assert!(obj.contains_key(key), "missing key {key}");
         }
+        let upload = &value["uploadtarget"];
+        assert_eq!(upload["target"].as_u64(), Some(0));
+        assert_eq!(upload["serve_historical_blocks"], json!(true));
+
+        net_totals.set_max_upload_target(1_000);
+        net_totals.add_sent(1_200);
+        let value = rpc_getnettotals(Vec::new(), &net_totals).expect("rpc");
+        let upload = &value["uploadtarget"];
+        assert_eq!(upload["target_reached"], json!(true));
+        assert_eq!(upload["serve_historical_blocks"], json!(false));
+        assert_eq!(upload["bytes_sent_in_cycle"].as_u64(), Some(1_200));
+        assert_eq!(upload["bytes_left_in_cycle"].as_u64(), Some(0));
     }
 
     #[test]
@@ -20651,6 +22504,14 @@ mod tests {
         ] {
             assert!(obj.contains_key(key), "missing key {key}");
         }
+        assert!(!obj.contains_key("pingtime"));
+
+        peer_registry.note_ping(id, std::time::Duration::from_millis(250));
+        peer_registry.note_ping(id, std::time::Duration::from_millis(500));
+        let value = rpc_getpeerinfo(Vec::new(), &peer_registry).expect("rpc");
+        let obj = value[0].as_object().expect("object");
+        assert_eq!(obj.get("pingtime").and_then(Value::as_f64), Some(0.5));
+        assert_eq!(obj.get("minping").and_then(Value::as_f64), Some(0.25));
     }
 
     #[test]
@@ -20871,6 +22732,37 @@ mod tests {
         assert!(banned.is_empty());
     }
 
+    #[test]
+    fn setban_accepts_cidr_subnets() {
+        let (_chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let peer_registry = PeerRegistry::default();
+        let book = HeaderPeerBook::default();
+        let setban = |subnet: &str, command: &str| {
+            rpc_setban(
+                vec![
+                    Value::String(subnet.to_string()),
+                    Value::String(command.to_string()),
+                ],
+                &params,
+                &peer_registry,
+                &book,
+            )
+        };
+
+        setban("10.20.0.0/16", "add").expect("setban subnet");
+        assert!(book.is_banned("10.20.3.4:16125".parse().expect("addr")));
+        assert!(!book.is_banned("10.21.3.4:16125".parse().expect("addr")));
+        let banned = rpc_listbanned(Vec::new(), &book).expect("listbanned");
+        assert_eq!(banned[0]["address"], "10.20.0.0/16");
+        assert!(banned[0]["banned_until"].as_u64().expect("banned_until") > 0);
+
+        setban("10.20.0.0/16", "remove").expect("unban subnet");
+        assert!(!book.is_banned("10.20.3.4:16125".parse().expect("addr")));
+
+        let err = setban("10.20.0.0/40", "add").expect_err("invalid subnet");
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+    }
+
     #[test]
     fn getblockcount_has_cpp_schema() {
         let (chainstate, _params, _data_dir) = setup_regtest_chainstate();
@@ -20912,7 +22804,7 @@ mod tests {
         let tips = value.as_array().expect("array");
         assert!(!tips.is_empty());
         let tip = tips[0].as_object().expect("object");
-        for key in ["height", "hash", "branchlen", "status"] {
+        for key in ["height", "hash", "branchlen", "chainwork", "status"] {
             assert!(tip.contains_key(key), "missing key {key}");
         }
     }
@@ -20995,6 +22887,152 @@ mod tests {
         assert!(statuses.contains(&"invalid"));
     }
 
+    #[test]
+    fn getchaintips_lists_competing_headers_only_forks() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let best = chainstate
+            .best_block()
+            .expect("best block")
+            .expect("best block present");
+        let spacing = params.consensus.pow_target_spacing.max(1) as u32;
+
+        let stage_header = |prev: Hash256, tag: u8| {
+            let prev_entry = chainstate
+                .header_entry(&prev)
+                .expect("header entry")
+                .expect("prev header entry");
+            let height = prev_entry.height + 1;
+            let time = prev_entry.time.saturating_add(spacing);
+            let bits = chainstate
+                .next_work_required_bits(&prev, height, time as i64, &params.consensus)
+                .expect("next bits");
+            let header = BlockHeader {
+                version: CURRENT_VERSION,
+                prev_block: prev,
+                merkle_root: [tag; 32],
+                final_sapling_root: chainstate.sapling_root().expect("sapling root"),
+                time,
+                bits,
+                nonce: [0u8; 32],
+                solution: Vec::new(),
+                nodes_collateral: OutPoint::null(),
+                block_sig: Vec::new(),
+            };
+            let hash = header.hash();
+            let mut batch = WriteBatch::new();
+            chainstate
+                .insert_headers_batch_with_pow(&[header], &params.consensus, &mut batch, false)
+                .expect("insert header");
+            chainstate.commit_batch(batch).expect("commit header");
+            hash
+        };
+
+        let fork_a = stage_header(best.hash, 0x33);
+        let fork_b_first = stage_header(best.hash, 0x44);
+        let fork_b = stage_header(fork_b_first, 0x45);
+
+        let value = rpc_getchaintips(&chainstate, Vec::new()).expect("rpc");
+        let tips = value.as_array().expect("array");
+        let find = |hash: &Hash256| {
+            let hex = hash256_to_hex(hash);
+            tips.iter()
+                .find(|tip| tip.get("hash").and_then(Value::as_str) == Some(hex.as_str()))
+                .cloned()
+        };
+
+        let tip_a = find(&fork_a).expect("fork a listed");
+        assert_eq!(tip_a["status"], "headers-only");
+        assert_eq!(tip_a["height"], best.height + 1);
+        assert_eq!(tip_a["branchlen"], 1);
+
+        let tip_b = find(&fork_b).expect("fork b listed");
+        assert_eq!(tip_b["status"], "headers-only");
+        assert_eq!(tip_b["height"], best.height + 2);
+        assert_eq!(tip_b["branchlen"], 2);
+        // Fixed-width hex, so string order is numeric order.
+        let work = |tip: &Value| tip["chainwork"].as_str().expect("chainwork").to_string();
+        assert!(work(&tip_b) > work(&tip_a));
+
+        assert!(
+            find(&fork_b_first).is_none(),
+            "interior header listed as tip"
+        );
+        let active = find(&best.hash).expect("active tip listed");
+        assert_eq!(active["status"], "active");
+        assert_eq!(active["branchlen"], 0);
+    }
+
+    #[test]
+    fn invalidateblock_and_reconsiderblock_toggle_header_status() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let write_lock = Mutex::new(());
+        let best = chainstate
+            .best_block()
+            .expect("best block")
+            .expect("best block present");
+        let best_entry = chainstate
+            .header_entry(&best.hash)
+            .expect("header entry")
+            .expect("best header entry");
+        let time = best_entry
+            .time
+            .saturating_add(params.consensus.pow_target_spacing.max(1) as u32);
+        let bits = chainstate
+            .next_work_required_bits(&best.hash, best.height + 1, time as i64, &params.consensus)
+            .expect("next bits");
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: best.hash,
+            merkle_root: [0x55u8; 32],
+            final_sapling_root: chainstate.sapling_root().expect("sapling root"),
+            time,
+            bits,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let hash = header.hash();
+        let mut batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(&[header], &params.consensus, &mut batch, false)
+            .expect("insert header");
+        chainstate.commit_batch(batch).expect("commit header");
+
+        let status = |chainstate: &ChainState<MemoryStore>| {
+            let value = rpc_getchaintips(chainstate, Vec::new()).expect("rpc");
+            value
+                .as_array()
+                .expect("array")
+                .iter()
+                .find(|tip| tip["hash"] == hash256_to_hex(&hash))
+                .map(|tip| tip["status"].clone())
+        };
+        assert_eq!(status(&chainstate), Some(json!("headers-only")));
+
+        let hash_param = vec![json!(hash256_to_hex(&hash))];
+        let value = rpc_invalidateblock(&chainstate, &write_lock, hash_param.clone()).expect("rpc");
+        assert!(value.is_null());
+        assert_eq!(status(&chainstate), Some(json!("invalid")));
+        let tip = chainstate.best_block().expect("best block").expect("tip");
+        assert_eq!(tip.hash, best.hash);
+        let best_header = chainstate.best_header().expect("best header").expect("tip");
+        assert_eq!(best_header.hash, best.hash);
+
+        rpc_reconsiderblock(&chainstate, &write_lock, hash_param).expect("rpc");
+        assert_eq!(status(&chainstate), Some(json!("headers-only")));
+        let best_header = chainstate.best_header().expect("best header").expect("tip");
+        assert_eq!(best_header.hash, hash);
+
+        let err = rpc_invalidateblock(&chainstate, &write_lock, vec![json!("00".repeat(32))])
+            .expect_err("unknown block");
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+        let genesis = hash256_to_hex(&params.consensus.hash_genesis_block);
+        let err = rpc_invalidateblock(&chainstate, &write_lock, vec![json!(genesis)])
+            .expect_err("genesis");
+        assert_eq!(err.code, RPC_MISC_ERROR);
+    }
+
     #[test]
     fn getblockheader_has_cpp_schema_keys() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -21056,11 +23094,118 @@ mod tests {
         let mempool = Mutex::new(Mempool::new(0));
         let value = rpc_getmempoolinfo(Vec::new(), &mempool).expect("rpc");
         let obj = value.as_object().expect("object");
-        for key in ["size", "bytes", "usage"] {
+        for key in ["size", "bytes", "usage", "maxmempool", "mempoolminfee"] {
             assert!(obj.contains_key(key), "missing key {key}");
         }
     }
 
+    #[test]
+    fn getmempoolancestors_and_descendants_follow_chain() {
+        let (chainstate, _params, _data_dir) = setup_regtest_chainstate();
+        let entry = |txid: Hash256, parents: Vec<Hash256>| MempoolEntry {
+            txid,
+            tx: Transaction {
+                f_overwintered: false,
+                version: 1,
+                version_group_id: 0,
+                vin: Vec::new(),
+                vout: Vec::new(),
+                lock_time: 0,
+                expiry_height: 0,
+                value_balance: 0,
+                shielded_spends: Vec::new(),
+                shielded_outputs: Vec::new(),
+                join_splits: Vec::new(),
+                join_split_pub_key: [0u8; 32],
+                join_split_sig: [0u8; 64],
+                binding_sig: [0u8; 64],
+                raw_tail: Vec::new(),
+                fluxnode: None,
+            },
+            raw: vec![0u8; 10],
+            time: 0,
+            height: 0,
+            fee: 0,
+            value_in: 0,
+            modified_size: 0,
+            priority: 0.0,
+            was_clear_at_entry: true,
+            fee_delta: 0,
+            priority_delta: 0.0,
+            spent_outpoints: Vec::new(),
+            parents,
+            sigops: 0,
+        };
+        let (a, b, c) = ([0xa1; 32], [0xb2; 32], [0xc3; 32]);
+        let mut inner = Mempool::new(0);
+        inner.insert(entry(a, Vec::new())).expect("insert a");
+        inner.insert(entry(b, vec![a])).expect("insert b");
+        inner.insert(entry(c, vec![b])).expect("insert c");
+        let mempool = Mutex::new(inner);
+
+        let txids = |value: Value| -> Vec<String> {
+            value
+                .as_array()
+                .expect("array")
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        };
+        let ancestors = |txid: &Hash256| {
+            rpc_getmempoolrelatives(
+                &chainstate,
+                vec![json!(hash256_to_hex(txid))],
+                &mempool,
+                false,
+            )
+            .expect("rpc")
+        };
+        let descendants = |txid: &Hash256| {
+            rpc_getmempoolrelatives(
+                &chainstate,
+                vec![json!(hash256_to_hex(txid))],
+                &mempool,
+                true,
+            )
+            .expect("rpc")
+        };
+
+        assert_eq!(
+            txids(ancestors(&c)),
+            vec![hash256_to_hex(&b), hash256_to_hex(&a)]
+        );
+        assert!(txids(ancestors(&a)).is_empty());
+        assert_eq!(
+            txids(descendants(&a)),
+            vec![hash256_to_hex(&b), hash256_to_hex(&c)]
+        );
+        assert_eq!(txids(descendants(&b)), vec![hash256_to_hex(&c)]);
+        assert!(txids(descendants(&c)).is_empty());
+
+        let verbose = rpc_getmempoolrelatives(
+            &chainstate,
+            vec![json!(hash256_to_hex(&b)), json!(true)],
+            &mempool,
+            false,
+        )
+        .expect("rpc");
+        let obj = verbose.as_object().expect("object");
+        assert_eq!(obj.len(), 1);
+        let parent = obj.get(&hash256_to_hex(&a)).expect("ancestor entry");
+        assert_eq!(parent["size"], 10);
+        assert!(parent["depends"].as_array().expect("depends").is_empty());
+
+        let err = rpc_getmempoolrelatives(
+            &chainstate,
+            vec![json!(hash256_to_hex(&[0x55; 32]))],
+            &mempool,
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+    }
+
     #[test]
     fn getrawmempool_has_cpp_schema() {
         let (chainstate, _params, _data_dir) = setup_regtest_chainstate();
@@ -21108,6 +23253,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let parent_txid = parent_tx.txid().expect("txid");
@@ -21138,6 +23284,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let child_txid = child_tx.txid().expect("txid");
@@ -21160,6 +23307,7 @@ mod tests {
                 priority_delta: 0.0,
                 spent_outpoints: Vec::new(),
                 parents: Vec::new(),
+                sigops: 0,
             })
             .expect("insert parent");
         inner
@@ -21178,6 +23326,7 @@ mod tests {
                 priority_delta: 0.0,
                 spent_outpoints: Vec::new(),
                 parents: vec![parent_txid],
+                sigops: 0,
             })
             .expect("insert child");
         let mempool = Mutex::new(inner);
@@ -21229,6 +23378,48 @@ mod tests {
         assert!(is_hex_64(bestblock));
     }
 
+    #[test]
+    fn gettxout_reports_coinbase_maturity_remaining() {
+        let (chainstate, params, _data_dir, _address, txid, vout) =
+            setup_regtest_chain_with_p2pkh_utxo();
+        let mempool = Mutex::new(Mempool::new(0));
+        let outpoint = OutPoint {
+            hash: txid,
+            index: vout,
+        };
+        let gettxout = |chainstate: &ChainState<MemoryStore>| {
+            rpc_gettxout(
+                chainstate,
+                &mempool,
+                vec![Value::String(hash256_to_hex(&txid)), json!(vout)],
+                &params,
+            )
+            .expect("rpc")
+        };
+
+        let value = gettxout(&chainstate);
+        assert_eq!(value["coinbase"], json!(true));
+        assert_eq!(value["confirmations"], json!(1));
+        assert_eq!(value["maturity_remaining"], json!(COINBASE_MATURITY - 1));
+        assert_eq!(
+            chainstate
+                .coinbase_maturity_remaining(&outpoint, 1, &params.consensus)
+                .expect("maturity"),
+            Some(COINBASE_MATURITY - 1)
+        );
+
+        extend_regtest_chain_to_height(&chainstate, &params, COINBASE_MATURITY);
+        let value = gettxout(&chainstate);
+        assert_eq!(value["confirmations"], json!(COINBASE_MATURITY));
+        assert_eq!(value["maturity_remaining"], json!(0));
+        assert_eq!(
+            chainstate
+                .coinbase_maturity_remaining(&outpoint, COINBASE_MATURITY, &params.consensus)
+                .expect("maturity"),
+            Some(0)
+        );
+    }
+
     #[test]
     fn gettxout_can_serve_mempool_outputs() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -21259,6 +23450,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = tx.txid().expect("txid");
@@ -21279,6 +23471,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -21297,6 +23490,113 @@ mod tests {
         assert_eq!(obj.get("version").and_then(Value::as_i64), Some(1));
     }
 
+    #[test]
+    fn gettxout_hides_outputs_spent_in_mempool() {
+        let (chainstate, params, _data_dir, _address, confirmed_txid, confirmed_vout) =
+            setup_regtest_chain_with_p2pkh_utxo();
+        let confirmed = OutPoint {
+            hash: confirmed_txid,
+            index: confirmed_vout,
+        };
+
+        let make_tx = |prevout: OutPoint, outputs: usize| Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: vec![TxIn {
+                prevout,
+                script_sig: Vec::new(),
+                sequence: 0,
+            }],
+            vout: (0..outputs)
+                .map(|index| TxOut {
+                    value: 100 + index as i64,
+                    script_pubkey: p2pkh_script([0x22u8; 20]),
+                })
+                .collect(),
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+        let make_entry = |tx: Transaction, parents: Vec<Hash256>| {
+            let txid = tx.txid().expect("txid");
+            let raw = tx.consensus_encode().expect("encode tx");
+            let spent_outpoints = tx.vin.iter().map(|input| input.prevout.clone()).collect();
+            MempoolEntry {
+                txid,
+                tx,
+                raw,
+                time: 0,
+                height: 0,
+                fee: 0,
+                value_in: 0,
+                modified_size: 0,
+                priority: 0.0,
+                was_clear_at_entry: parents.is_empty(),
+                fee_delta: 0,
+                priority_delta: 0.0,
+                spent_outpoints,
+                parents,
+                sigops: 0,
+            }
+        };
+
+        let parent = make_entry(make_tx(confirmed.clone(), 2), Vec::new());
+        let parent_txid = parent.txid;
+        let child = make_entry(
+            make_tx(
+                OutPoint {
+                    hash: parent_txid,
+                    index: 0,
+                },
+                1,
+            ),
+            vec![parent_txid],
+        );
+        let mut inner = Mempool::new(0);
+        inner.insert(parent).expect("insert parent");
+        inner.insert(child).expect("insert child");
+        let mempool = Mutex::new(inner);
+
+        let gettxout = |txid: &Hash256, vout: u32, include_mempool: bool| {
+            rpc_gettxout(
+                &chainstate,
+                &mempool,
+                vec![
+                    Value::String(hash256_to_hex(txid)),
+                    json!(vout),
+                    json!(include_mempool),
+                ],
+                &params,
+            )
+            .expect("rpc")
+        };
+
+        assert!(gettxout(&confirmed_txid, confirmed_vout, true).is_null());
+        let confirmed_only = gettxout(&confirmed_txid, confirmed_vout, false);
+        assert!(confirmed_only
+            .get("confirmations")
+            .and_then(Value::as_i64)
+            .is_some_and(|confirmations| confirmations > 0));
+
+        assert!(gettxout(&parent_txid, 0, true).is_null());
+        let change = gettxout(&parent_txid, 1, true);
+        assert_eq!(change.get("confirmations").and_then(Value::as_i64), Some(0));
+        assert_eq!(
+            change.get("value").and_then(Value::as_f64),
+            Some(101.0 / COIN as f64)
+        );
+        assert!(gettxout(&parent_txid, 1, false).is_null());
+    }
+
     #[test]
     fn getspentinfo_has_cpp_schema_keys() {
         let (chainstate, _params, _data_dir, _address, txid, vout) =
@@ -21509,6 +23809,202 @@ mod tests {
         assert!(obj.get("deltas").and_then(Value::as_array).is_some());
     }
 
+    #[test]
+    fn getblockstats_reports_fees_and_selected_subset() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+
+        let funding = OutPoint {
+            hash: [0xc1u8; 32],
+            index: 0,
+        };
+        let funding_script = p2pkh_script([0x55u8; 20]);
+        let utxo_entry = fluxd_chainstate::utxo::UtxoEntry {
+            value: 2 * COIN,
+            script_pubkey: funding_script,
+            height: 0,
+            is_coinbase: false,
+        };
+        let mut batch = WriteBatch::new();
+        batch.put(
+            Column::Utxo,
+            fluxd_chainstate::utxo::outpoint_key_bytes(&funding).as_bytes(),
+            utxo_entry.encode(),
+        );
+        chainstate.commit_batch(batch).expect("commit utxo");
+
+        let fee = 12_345;
+        let spend_tx = Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: vec![TxIn {
+                prevout: funding,
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            vout: vec![
+                TxOut {
+                    value: COIN,
+                    script_pubkey: p2pkh_script([0x66u8; 20]),
+                },
+                TxOut {
+                    value: COIN - fee,
+                    script_pubkey: p2pkh_script([0x77u8; 20]),
+                },
+            ],
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+        let spend_size = spend_tx.consensus_encode().expect("encode tx").len() as i64;
+
+        let tip = chainstate
+            .best_block()
+            .expect("best block")
+            .expect("best block present");
+        let tip_entry = chainstate
+            .header_entry(&tip.hash)
+            .expect("header entry")
+            .expect("header entry present");
+        let height = tip.height + 1;
+        let time = tip_entry.time.saturating_add(1);
+        let bits = chainstate
+            .next_work_required_bits(&tip.hash, height, time as i64, &params.consensus)
+            .expect("next bits");
+        let mut coinbase_vout = vec![TxOut {
+            value: block_subsidy(height, &params.consensus),
+            script_pubkey: Vec::new(),
+        }];
+        for (amount, address) in [
+            (
+                exchange_fund_amount(height, &params.funding),
+                params.funding.exchange_address,
+            ),
+            (
+                foundation_fund_amount(height, &params.funding),
+                params.funding.foundation_address,
+            ),
+            (
+                swap_pool_amount(height as i64, &params.swap_pool),
+                params.swap_pool.address,
+            ),
+        ] {
+            if amount > 0 {
+                coinbase_vout.push(TxOut {
+                    value: amount,
+                    script_pubkey: address_to_script_pubkey(address, params.network)
+                        .expect("funding address script"),
+                });
+            }
+        }
+        let coinbase_outs = coinbase_vout.len() as i64;
+        let coinbase = Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: vec![TxIn {
+                prevout: OutPoint::null(),
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            vout: coinbase_vout,
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: tip.hash,
+            merkle_root: coinbase.txid().expect("coinbase txid"),
+            final_sapling_root: chainstate.sapling_root().expect("sapling root"),
+            time,
+            bits,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let mut header_batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                &[header.clone()],
+                &params.consensus,
+                &mut header_batch,
+                false,
+            )
+            .expect("insert header");
+        chainstate
+            .commit_batch(header_batch)
+            .expect("commit header");
+        let block = Block {
+            header,
+            transactions: vec![coinbase, spend_tx],
+        };
+        let block_bytes = block.consensus_encode().expect("encode block");
+        let batch = chainstate
+            .connect_block(
+                &block,
+                height,
+                &params,
+                &ValidationFlags::default(),
+                true,
+                None,
+                None,
+                Some(block_bytes.as_slice()),
+                None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+
+        let value = rpc_getblockstats(&chainstate, vec![json!(height)], &params).expect("rpc");
+        let obj = value.as_object().expect("object");
+        for key in BLOCK_STATS_NAMES {
+            assert!(obj.contains_key(*key), "missing key {key}");
+        }
+        assert_eq!(obj["txs"], json!(2));
+        assert_eq!(obj["ins"], json!(1));
+        assert_eq!(obj["outs"], json!(coinbase_outs + 2));
+        assert_eq!(obj["utxo_increase"], json!(coinbase_outs + 1));
+        assert_eq!(obj["total_out"], json!(2 * COIN - fee));
+        assert_eq!(obj["totalfee"], json!(fee));
+        assert_eq!(obj["total_size"], json!(spend_size));
+        assert_eq!(obj["minfee"], json!(fee));
+        assert_eq!(obj["avgfeerate"], json!(fee / spend_size));
+        assert_eq!(obj["feerate_percentiles"], json!(vec![fee / spend_size; 5]));
+        assert_eq!(
+            obj["subsidy"],
+            json!(block_subsidy(height, &params.consensus))
+        );
+
+        let subset = rpc_getblockstats(
+            &chainstate,
+            vec![json!(height), json!(["txs", "height"])],
+            &params,
+        )
+        .expect("rpc");
+        assert_eq!(subset, json!({ "txs": 2, "height": height }));
+
+        let err = rpc_getblockstats(&chainstate, vec![json!(height), json!(["bogus"])], &params)
+            .expect_err("unknown stat");
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+    }
+
     #[test]
     fn getblockdeltas_filters_by_txids() {
         let (chainstate, params, _data_dir, _address, txid, _vout) =
@@ -21570,6 +24066,47 @@ mod tests {
         assert!((estimate - 1000.0).abs() < 0.01);
     }
 
+    #[test]
+    fn estimaterawfee_reports_bucket_ranges() {
+        let empty = Mutex::new(FeeEstimator::new(0));
+        let value = rpc_estimaterawfee(vec![json!(2)], &empty).expect("rpc");
+        assert_eq!(value["target"].as_u64(), Some(2));
+        assert!(value.get("feerate").is_none());
+        assert!(value.get("pass").is_none());
+        assert_eq!(value["errors"].as_array().map(Vec::len), Some(1));
+        let err = rpc_estimaterawfee(vec![json!(26)], &empty).expect_err("target too high");
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+        let err = rpc_estimaterawfee(vec![json!(0)], &empty).expect_err("target too low");
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+
+        let mut estimator = FeeEstimator::new(0);
+        for height in 1u32..=20 {
+            let entry = crate::fee_estimator::BlockTxInfo {
+                fee: 1000,
+                size: 250,
+                height: height.saturating_sub(1),
+                priority: 0.0,
+                was_clear_at_entry: true,
+            };
+            let entries = vec![entry; 50];
+            estimator.process_block(height, &entries, true);
+        }
+        let expected_fee = estimator.estimate_fee_per_kb(1).expect("fee estimate");
+        let raw = estimator.raw_estimate(1);
+        assert_eq!(raw.fee_per_kb, Some(expected_fee));
+        assert!(raw.data_points >= raw.sufficient_data_points);
+        let pass = raw.pass.expect("pass range");
+        assert!(pass.start_range < 4000.0 && 4000.0 <= pass.end_range);
+        assert!(pass.within_target > 0.0);
+        assert_eq!(pass.within_target, pass.total_confirmed);
+
+        let fee_estimator = Mutex::new(estimator);
+        let value = rpc_estimaterawfee(vec![json!(1)], &fee_estimator).expect("rpc");
+        assert_eq!(value["feerate"], amount_to_value(expected_fee));
+        assert!(value["pass"]["endrange"].as_f64().is_some());
+        assert!(value.get("errors").is_none());
+    }
+
     #[test]
     fn estimatefee_returns_value_after_observing_blocks() {
         let mut estimator = FeeEstimator::new(0);
@@ -21591,6 +24128,174 @@ mod tests {
         assert!(estimate > 0.0);
     }
 
+    #[test]
+    fn fee_estimator_seeds_from_recent_blocks() {
+        let (chainstate, params, _data_dir, _address, coinbase_txid, coinbase_vout) =
+            setup_regtest_chain_with_p2pkh_utxo();
+        extend_regtest_chain_to_height(&chainstate, &params, COINBASE_MATURITY);
+
+        let mut estimator = FeeEstimator::new(0);
+        assert!(estimator.is_empty());
+        assert_eq!(
+            estimator
+                .seed_from_blocks(&chainstate, 10)
+                .expect("seed coinbase-only blocks"),
+            0
+        );
+        assert!(estimator.is_empty());
+
+        let fee = 10_000;
+        let spend_tx = Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: vec![TxIn {
+                prevout: OutPoint {
+                    hash: coinbase_txid,
+                    index: coinbase_vout,
+                },
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            vout: vec![TxOut {
+                value: block_subsidy(1, &params.consensus) - fee,
+                script_pubkey: p2pkh_script([0x33u8; 20]),
+            }],
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+
+        let tip = chainstate
+            .best_block()
+            .expect("best block")
+            .expect("best block present");
+        let tip_entry = chainstate
+            .header_entry(&tip.hash)
+            .expect("header entry")
+            .expect("header entry present");
+        let height = tip.height + 1;
+        let spacing = params.consensus.pow_target_spacing.max(1) as u32;
+        let time = tip_entry.time.saturating_add(spacing);
+        let bits = chainstate
+            .next_work_required_bits(&tip.hash, height, time as i64, &params.consensus)
+            .expect("next bits");
+        let mut vout = vec![TxOut {
+            value: block_subsidy(height, &params.consensus),
+            script_pubkey: Vec::new(),
+        }];
+        let exchange_amount = exchange_fund_amount(height, &params.funding);
+        if exchange_amount > 0 {
+            let script = address_to_script_pubkey(params.funding.exchange_address, params.network)
+                .expect("exchange address script");
+            vout.push(TxOut {
+                value: exchange_amount,
+                script_pubkey: script,
+            });
+        }
+        let foundation_amount = foundation_fund_amount(height, &params.funding);
+        if foundation_amount > 0 {
+            let script =
+                address_to_script_pubkey(params.funding.foundation_address, params.network)
+                    .expect("foundation address script");
+            vout.push(TxOut {
+                value: foundation_amount,
+                script_pubkey: script,
+            });
+        }
+        let swap_amount = swap_pool_amount(height as i64, &params.swap_pool);
+        if swap_amount > 0 {
+            let script = address_to_script_pubkey(params.swap_pool.address, params.network)
+                .expect("swap pool address script");
+            vout.push(TxOut {
+                value: swap_amount,
+                script_pubkey: script,
+            });
+        }
+        let coinbase = Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: vec![TxIn {
+                prevout: OutPoint::null(),
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            vout,
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+        let header = BlockHeader {
+            version: CURRENT_VERSION,
+            prev_block: tip.hash,
+            merkle_root: coinbase.txid().expect("coinbase txid"),
+            final_sapling_root: chainstate.sapling_root().expect("sapling root"),
+            time,
+            bits,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        };
+        let mut header_batch = WriteBatch::new();
+        chainstate
+            .insert_headers_batch_with_pow(
+                &[header.clone()],
+                &params.consensus,
+                &mut header_batch,
+                false,
+            )
+            .expect("insert header");
+        chainstate
+            .commit_batch(header_batch)
+            .expect("commit header");
+        let block = Block {
+            header,
+            transactions: vec![coinbase, spend_tx],
+        };
+        let block_bytes = block.consensus_encode().expect("encode block");
+        let batch = chainstate
+            .connect_block(
+                &block,
+                height,
+                &params,
+                &ValidationFlags::default(),
+                true,
+                None,
+                None,
+                Some(block_bytes.as_slice()),
+                None,
+            )
+            .expect("connect block");
+        chainstate.commit_batch(batch).expect("commit block");
+
+        let mut estimator = FeeEstimator::new(0);
+        assert_eq!(
+            estimator
+                .seed_from_blocks(&chainstate, 10)
+                .expect("seed with spend"),
+            1
+        );
+        assert!(!estimator.is_empty());
+    }
+
     #[test]
     fn prioritisetransaction_applies_fee_delta_to_mempool_entries() {
         let mut inner = Mempool::new(0);
@@ -21613,6 +24318,7 @@ mod tests {
                     join_split_pub_key: [0u8; 32],
                     join_split_sig: [0u8; 64],
                     binding_sig: [0u8; 64],
+                    raw_tail: Vec::new(),
                     fluxnode: None,
                 },
                 raw: vec![0u8; 10],
@@ -21627,6 +24333,7 @@ mod tests {
                 priority_delta: 0.0,
                 spent_outpoints: Vec::new(),
                 parents: Vec::new(),
+                sigops: 0,
             })
             .expect("insert");
         let mempool = Mutex::new(inner);
@@ -21701,6 +24408,203 @@ mod tests {
         assert_eq!(value.as_str(), Some("duplicate"));
     }
 
+    #[test]
+    fn generatetoaddress_mines_blocks_and_matures_coinbase() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let write_lock = Mutex::new(());
+        let mempool = Mutex::new(Mempool::new(0));
+        let mempool_policy = MempoolPolicy::standard(0, false);
+        let mempool_metrics = MempoolMetrics::default();
+        let fee_estimator = Mutex::new(FeeEstimator::new(128));
+        let flags = ValidationFlags::default();
+        let (tx_announce, _rx) = broadcast::channel(16);
+
+        let redeem_script = vec![0x51];
+        let mut script_pubkey = vec![0xa9, 0x14];
+        script_pubkey.extend_from_slice(&hash160(&redeem_script));
+        script_pubkey.push(0x87);
+        let address = script_pubkey_to_address(&script_pubkey, params.network).expect("address");
+
+        let generate = |nblocks: u32| {
+            rpc_generatetoaddress(
+                &chainstate,
+                &write_lock,
+                &mempool,
+                &mempool_policy,
+                &fee_estimator,
+                vec![json!(nblocks), json!(address)],
+                &params,
+                &flags,
+            )
+            .expect("rpc")
+        };
+
+        let hashes = generate(101);
+        let hashes = hashes.as_array().expect("array");
+        assert_eq!(hashes.len(), 101);
+        let tip = chainstate.best_block().expect("best block").expect("tip");
+        assert_eq!(tip.height, 101);
+        assert_eq!(
+            hashes[100].as_str(),
+            Some(hash256_to_hex(&tip.hash).as_str())
+        );
+
+        let first_hash = chainstate
+            .height_hash(1)
+            .expect("height hash")
+            .map(BlockHash::to_internal)
+            .expect("block 1");
+        let location = chainstate
+            .block_location(&first_hash)
+            .expect("block location")
+            .expect("block 1 location");
+        let first = Block::consensus_decode(&chainstate.read_block(location).expect("read"))
+            .expect("decode block 1");
+        let coinbase = &first.transactions[0];
+        let (vout, output) = coinbase
+            .vout
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.script_pubkey == script_pubkey)
+            .expect("coinbase pays address");
+
+        let spend = Transaction {
+            f_overwintered: false,
+            version: 1,
+            version_group_id: 0,
+            vin: vec![TxIn {
+                prevout: OutPoint {
+                    hash: coinbase.txid().expect("txid"),
+                    index: vout as u32,
+                },
+                script_sig: vec![0x01, 0x51],
+                sequence: u32::MAX,
+            }],
+            vout: vec![TxOut {
+                value: output.value - 10_000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: Vec::new(),
+            shielded_outputs: Vec::new(),
+            join_splits: Vec::new(),
+            join_split_pub_key: [0u8; 32],
+            join_split_sig: [0u8; 64],
+            binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
+            fluxnode: None,
+        };
+        let spend_txid = spend.txid().expect("txid");
+        let raw_hex = hex_bytes(&spend.consensus_encode().expect("encode tx"));
+        rpc_sendrawtransaction(
+            &chainstate,
+            &mempool,
+            &mempool_policy,
+            &mempool_metrics,
+            &fee_estimator,
+            &flags,
+            vec![Value::String(raw_hex)],
+            &params,
+            &tx_announce,
+        )
+        .expect("matured coinbase spend accepted");
+
+        let hashes = generate(1);
+        let mined_hash = parse_hash(&hashes[0]).expect("hash");
+        let location = chainstate
+            .block_location(&mined_hash)
+            .expect("block location")
+            .expect("mined block location");
+        let mined = Block::consensus_decode(&chainstate.read_block(location).expect("read"))
+            .expect("decode mined block");
+        assert_eq!(mined.transactions.len(), 2);
+        assert_eq!(mined.transactions[1].txid().expect("txid"), spend_txid);
+        assert_eq!(mempool.lock().expect("mempool").size(), 0);
+        assert_eq!(
+            chainstate
+                .best_block()
+                .expect("best block")
+                .expect("tip")
+                .height,
+            102
+        );
+    }
+
+    #[test]
+    fn generatetoaddress_refuses_outside_regtest() {
+        let (chainstate, params, _data_dir) = setup_testnet_chainstate();
+        let write_lock = Mutex::new(());
+        let mempool = Mutex::new(Mempool::new(0));
+        let mempool_policy = MempoolPolicy::standard(0, false);
+        let fee_estimator = Mutex::new(FeeEstimator::new(0));
+        let flags = ValidationFlags::default();
+        let err = rpc_generatetoaddress(
+            &chainstate,
+            &write_lock,
+            &mempool,
+            &mempool_policy,
+            &fee_estimator,
+            vec![json!(1), json!("tmRGc4CD1UyUdbSJmTUzcB6oDqk4qUaHnnh")],
+            &params,
+            &flags,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_METHOD_NOT_FOUND);
+        assert_eq!(
+            chainstate
+                .best_block()
+                .expect("best block")
+                .expect("tip")
+                .height,
+            0
+        );
+    }
+
+    #[test]
+    fn submitblock_orphan_returns_inconclusive() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let genesis_hash = params.consensus.hash_genesis_block;
+        let location = chainstate
+            .block_location(&genesis_hash)
+            .expect("block location")
+            .expect("genesis block location");
+        let bytes = chainstate.read_block(location).expect("read block");
+        let mut block = Block::consensus_decode(&bytes).expect("decode genesis");
+        block.header.prev_block = [0x42; 32];
+        let orphan_hash = block.header.hash();
+        let block_hex = hex_bytes(&block.consensus_encode().expect("encode block"));
+
+        let write_lock = Mutex::new(());
+        let mempool = Mutex::new(Mempool::new(0));
+        let fee_estimator = Mutex::new(FeeEstimator::new(0));
+        let flags = ValidationFlags::default();
+        let value = rpc_submitblock(
+            &chainstate,
+            &write_lock,
+            &mempool,
+            &fee_estimator,
+            vec![Value::String(block_hex)],
+            &params,
+            &flags,
+        )
+        .expect("rpc");
+        assert_eq!(value.as_str(), Some("inconclusive"));
+        assert!(chainstate
+            .header_entry(&orphan_hash)
+            .expect("header entry")
+            .is_none());
+        assert_eq!(
+            chainstate
+                .best_block()
+                .expect("best block")
+                .expect("best block present")
+                .hash,
+            genesis_hash
+        );
+    }
+
     #[test]
     fn submitblock_header_present_returns_duplicate() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -21774,6 +24678,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -21870,6 +24775,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -21985,6 +24891,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -22124,6 +25031,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -22290,6 +25198,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid_1 = coinbase_1.txid().expect("coinbase txid");
@@ -22412,6 +25321,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid_2 = coinbase_2.txid().expect("coinbase txid");
@@ -23019,6 +25929,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let shield_txid = shield_tx.txid().expect("txid");
@@ -23092,6 +26003,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -23334,6 +26246,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let shield_txid = shield_tx.txid().expect("txid");
@@ -23409,6 +26322,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -23540,6 +26454,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let spend_txid = spend_tx.txid().expect("spend txid");
@@ -23612,6 +26527,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -23984,6 +26900,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let shield_txid = shield_tx.txid().expect("txid");
@@ -24057,6 +26974,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -24150,7 +27068,7 @@ mod tests {
 
         assert!(mempool.lock().expect("mempool lock").contains(&txid));
         assert!(chainstate
-            .tx_location(&shield_txid)
+            .tx_location(&Txid::from_internal(shield_txid))
             .expect("tx index read")
             .is_some());
     }
@@ -24224,6 +27142,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn unnamed_witness_programs_are_nonstandard() {
+        let mut witness_v1 = vec![0x51, 0x20];
+        witness_v1.extend_from_slice(&[0x66u8; 32]);
+        assert_eq!(script_type_name(&witness_v1), "nonstandard");
+        assert_eq!(script_type_name(&[0x6a]), "nonstandard");
+    }
+
     #[test]
     fn createrawtransaction_and_decoderawtransaction_have_cpp_schema() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -24261,6 +27187,26 @@ mod tests {
         assert!(is_hex_64(txid));
     }
 
+    #[test]
+    fn sendrawtransaction_reports_peer_reject() {
+        let txid = Value::String(hash256_to_hex(&[0x11; 32]));
+        let err = apply_peer_feedback(
+            Ok(txid.clone()),
+            Some(TxFeedback::Rejected {
+                code: 0x42,
+                reason: "insufficient fee".to_string(),
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_TRANSACTION_REJECTED);
+        assert_eq!(err.message, "peer rejected: insufficient fee (code 66)");
+
+        for feedback in [None, Some(TxFeedback::NotFound)] {
+            let value = apply_peer_feedback(Ok(txid.clone()), feedback).expect("success");
+            assert_eq!(value, txid);
+        }
+    }
+
     #[test]
     fn sendrawtransaction_rejects_invalid_hex() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -24321,6 +27267,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -24387,6 +27334,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -24455,6 +27403,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -24520,6 +27469,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let parent_raw = parent_tx.consensus_encode().expect("encode tx");
@@ -24551,6 +27501,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let child_raw = child_tx.consensus_encode().expect("encode tx");
@@ -24579,6 +27530,7 @@ mod tests {
             child_raw,
             err.missing_inputs,
             true,
+            None,
         );
         assert_eq!(
             mempool.lock().expect("mempool lock").orphan_count(),
@@ -24670,6 +27622,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -24758,6 +27711,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex1 = hex_bytes(&tx1.consensus_encode().expect("encode tx"));
@@ -24847,6 +27801,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -24936,6 +27891,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = tx.txid().expect("txid");
@@ -24956,6 +27912,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -24992,6 +27949,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn getrawtransaction_block_hint_works_without_txindex() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let mempool = Mutex::new(Mempool::new(0));
+        let genesis_hash = params.consensus.hash_genesis_block;
+        let location = chainstate
+            .block_location(&genesis_hash)
+            .expect("block location")
+            .expect("genesis stored");
+        let bytes = chainstate.read_block(location).expect("read genesis");
+        let genesis = fluxd_primitives::block::Block::consensus_decode(&bytes).expect("decode");
+        let coinbase = &genesis.transactions[0];
+        let txid = coinbase.txid().expect("txid");
+        let raw_hex = hex_bytes(&coinbase.consensus_encode().expect("encode tx"));
+
+        let mut batch = WriteBatch::new();
+        batch.delete(Column::TxIndex, txid);
+        chainstate.commit_batch(batch).expect("drop txindex entry");
+
+        let err = rpc_getrawtransaction(
+            &chainstate,
+            &mempool,
+            vec![Value::String(hash256_to_hex(&txid))],
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+
+        let value = rpc_getrawtransaction(
+            &chainstate,
+            &mempool,
+            vec![
+                Value::String(hash256_to_hex(&txid)),
+                json!(false),
+                Value::String(hash256_to_hex(&genesis_hash)),
+            ],
+            &params,
+        )
+        .expect("rpc with block hint");
+        assert_eq!(value.as_str(), Some(raw_hex.as_str()));
+
+        let verbose = rpc_getrawtransaction(
+            &chainstate,
+            &mempool,
+            vec![
+                Value::String(hash256_to_hex(&txid)),
+                json!(1),
+                Value::String(hash256_to_hex(&genesis_hash)),
+            ],
+            &params,
+        )
+        .expect("verbose rpc with block hint");
+        let obj = verbose.as_object().expect("object");
+        assert_eq!(obj.get("in_active_chain"), Some(&Value::Bool(true)));
+        assert_eq!(
+            obj.get("blockhash").and_then(Value::as_str),
+            Some(hash256_to_hex(&genesis_hash).as_str())
+        );
+
+        let err = rpc_getrawtransaction(
+            &chainstate,
+            &mempool,
+            vec![
+                Value::String(hash256_to_hex(&[0x42u8; 32])),
+                json!(false),
+                Value::String(hash256_to_hex(&genesis_hash)),
+            ],
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+        assert!(err.message.contains("provided block"), "{}", err.message);
+
+        let err = rpc_getrawtransaction(
+            &chainstate,
+            &mempool,
+            vec![
+                Value::String(hash256_to_hex(&txid)),
+                json!(false),
+                Value::String(hash256_to_hex(&[0x42u8; 32])),
+            ],
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+    }
+
     #[test]
     fn verifymessage_accepts_valid_signature() {
         let (_chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -25017,6 +28061,20 @@ mod tests {
         .expect("rpc");
         assert_eq!(ok, Value::Bool(true));
 
+        // BIP137 P2SH-P2WPKH header for the same compressed key.
+        let mut segwit_sig = sig;
+        segwit_sig[0] += 4;
+        let ok = rpc_verifymessage(
+            vec![
+                Value::String(address.clone()),
+                Value::String(base64::engine::general_purpose::STANDARD.encode(segwit_sig)),
+                Value::String(message.to_string()),
+            ],
+            &params,
+        )
+        .expect("rpc");
+        assert_eq!(ok, Value::Bool(true));
+
         let bad = rpc_verifymessage(
             vec![
                 Value::String(address),
@@ -25326,6 +28384,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -25360,6 +28419,93 @@ mod tests {
         assert_eq!(signed_tx.vin[0].script_sig[0], 0x00);
     }
 
+    #[test]
+    fn psbt_rpcs_sign_multisig_and_finalize() {
+        let (chainstate, params, data_dir) = setup_regtest_chainstate();
+        let wallet = Mutex::new(Wallet::load_or_create(&data_dir, params.network).expect("wallet"));
+        let mempool = Mutex::new(Mempool::new(0));
+
+        let addr_a = rpc_getnewaddress(&wallet, Vec::new())
+            .expect("rpc")
+            .as_str()
+            .expect("address string")
+            .to_string();
+        let addr_b = rpc_getnewaddress(&wallet, Vec::new())
+            .expect("rpc")
+            .as_str()
+            .expect("address string")
+            .to_string();
+        let p2sh_address = rpc_addmultisigaddress(
+            &wallet,
+            vec![json!(2), json!([addr_a.clone(), addr_b])],
+            &params,
+        )
+        .expect("rpc")
+        .as_str()
+        .expect("p2sh address")
+        .to_string();
+        let script_pubkey =
+            address_to_script_pubkey(&p2sh_address, params.network).expect("script_pubkey");
+
+        let mut outputs = serde_json::Map::new();
+        outputs.insert(addr_a.clone(), json!("1.0"));
+        let created = rpc_createpsbt(
+            &chainstate,
+            vec![
+                json!([{ "txid": hash256_to_hex(&[0x42u8; 32]), "vout": 0 }]),
+                Value::Object(outputs),
+            ],
+            &params,
+        )
+        .expect("createpsbt");
+        let mut psbt = parse_psbt_param(Some(&created)).expect("decode psbt");
+        assert_eq!(psbt.inputs.len(), 1);
+        psbt.inputs[0].utxo = Some(TxOut {
+            value: 2 * COIN,
+            script_pubkey,
+        });
+        let unsigned = json!(psbt_to_base64(&psbt).expect("encode"));
+
+        let processed = rpc_walletprocesspsbt(
+            &chainstate,
+            &mempool,
+            &wallet,
+            vec![unsigned.clone(), json!(false)],
+            &params,
+        )
+        .expect("walletprocesspsbt");
+        assert_eq!(processed["complete"], json!(false));
+        let with_redeem = parse_psbt_param(processed.get("psbt")).expect("decode");
+        assert!(with_redeem.inputs[0].redeem_script.is_some());
+        assert!(with_redeem.inputs[0].partial_sigs.is_empty());
+
+        let signed = rpc_walletprocesspsbt(
+            &chainstate,
+            &mempool,
+            &wallet,
+            vec![processed["psbt"].clone()],
+            &params,
+        )
+        .expect("walletprocesspsbt");
+        assert_eq!(signed["complete"], json!(true));
+
+        let combined =
+            rpc_combinepsbt(vec![json!([unsigned, signed["psbt"].clone()])]).expect("combine");
+        assert!(parse_psbt_param(Some(&combined))
+            .expect("decode")
+            .is_finalized());
+
+        let finalized = rpc_finalizepsbt(vec![combined]).expect("finalizepsbt");
+        assert_eq!(finalized["complete"], json!(true));
+        let signed_bytes =
+            bytes_from_hex(finalized["hex"].as_str().expect("hex string")).expect("hex decode");
+        let signed_tx = Transaction::consensus_decode(&signed_bytes).expect("decode signed tx");
+        assert_eq!(signed_tx.vin[0].script_sig[0], 0x00);
+
+        let err = rpc_finalizepsbt(vec![json!("cHNidP8=")]).expect_err("truncated psbt");
+        assert_eq!(err.code, RPC_DESERIALIZATION_ERROR);
+    }
+
     #[test]
     fn signrawtransaction_accepts_prevtx_without_amount() {
         let (chainstate, params, data_dir) = setup_regtest_chainstate();
@@ -25408,6 +28554,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -25505,6 +28652,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -25601,6 +28749,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -25689,7 +28838,7 @@ mod tests {
         );
         chainstate.commit_batch(batch).expect("insert fluxnodes");
 
-        let value = rpc_getfluxnodecount(&chainstate, Vec::new()).expect("rpc");
+        let value = rpc_getfluxnodecount(&chainstate, Vec::new(), &params).expect("rpc");
         let obj = value.as_object().expect("object");
         for key in [
             "total",
@@ -25703,6 +28852,7 @@ mod tests {
             "ipv4",
             "ipv6",
             "onion",
+            "tiers",
         ] {
             assert!(obj.contains_key(key), "missing key {key}");
         }
@@ -25809,6 +28959,107 @@ mod tests {
         }
     }
 
+    #[test]
+    fn listfluxnodes_filters_by_tier_and_payout_address() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        extend_regtest_chain_to_height(&chainstate, &params, 69);
+
+        let mut batch = WriteBatch::new();
+        let confirmed = add_fluxnode_record_to_batch(
+            &mut batch,
+            OutPoint {
+                hash: [0x20u8; 32],
+                index: 0,
+            },
+            1,
+            10,
+            12,
+            vec![0x02u8; 33],
+            vec![0x03u8; 33],
+            100_000,
+        );
+        let started = add_fluxnode_record_to_batch(
+            &mut batch,
+            OutPoint {
+                hash: [0x21u8; 32],
+                index: 1,
+            },
+            2,
+            68,
+            0,
+            vec![0x04u8; 33],
+            vec![0x05u8; 33],
+            100_000,
+        );
+        chainstate.commit_batch(batch).expect("insert fluxnodes");
+        chainstate
+            .rebuild_fluxnode_index()
+            .expect("rebuild fluxnode index");
+
+        let value = rpc_listfluxnodes(&chainstate, Vec::new(), &params).expect("rpc");
+        let list = value.as_array().expect("array");
+        assert_eq!(list.len(), 2);
+        let first = list[0].as_object().expect("object");
+        for key in [
+            "collateral",
+            "txhash",
+            "outidx",
+            "tier",
+            "status",
+            "payment_address",
+            "ip",
+            "added_height",
+            "confirmed_height",
+            "last_confirmed_height",
+            "last_paid_height",
+            "expires_at_height",
+        ] {
+            assert!(first.contains_key(key), "missing key {key}");
+        }
+        assert_eq!(first["tier"], json!("CUMULUS"));
+        assert_eq!(first["status"], json!("CONFIRMED"));
+        assert_eq!(
+            first["expires_at_height"],
+            json!(fluxnode_confirm_expire_height(
+                confirmed.last_confirmed_height,
+                &params.consensus
+            ))
+        );
+
+        let value = rpc_listfluxnodes(&chainstate, vec![json!("nimbus")], &params).expect("rpc");
+        let list = value.as_array().expect("array");
+        assert_eq!(list.len(), 1);
+        assert_eq!(
+            list[0]["collateral"],
+            json!(format_outpoint(&started.collateral))
+        );
+        assert_eq!(list[0]["status"], json!("STARTED"));
+        let (expiration, _) = fluxnode_start_windows(69, &params);
+        assert_eq!(
+            list[0]["expires_at_height"],
+            json!(started.start_height + expiration as u32 + 1)
+        );
+
+        let value = rpc_listfluxnodes(&chainstate, vec![json!(1)], &params).expect("rpc");
+        assert_eq!(value.as_array().expect("array").len(), 1);
+
+        let address =
+            script_pubkey_to_address(&p2pkh_script(hash160(&[0x03u8; 33])), params.network)
+                .expect("address");
+        let value =
+            rpc_listfluxnodes(&chainstate, vec![json!(address.clone())], &params).expect("rpc");
+        let list = value.as_array().expect("array");
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0]["payment_address"], json!(address));
+        assert_eq!(list[0]["tier"], json!("CUMULUS"));
+
+        let err = rpc_listfluxnodes(&chainstate, vec![json!(7)], &params).unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+        let err =
+            rpc_listfluxnodes(&chainstate, vec![json!("not-an-address")], &params).unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+    }
+
     #[test]
     fn getfluxnodecount_counts_confirmed_nodes_by_tier() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -25869,7 +29120,7 @@ mod tests {
         );
         chainstate.commit_batch(batch).expect("insert fluxnodes");
 
-        let value = rpc_getfluxnodecount(&chainstate, Vec::new()).expect("rpc");
+        let value = rpc_getfluxnodecount(&chainstate, Vec::new(), &params).expect("rpc");
         let obj = value.as_object().expect("object");
 
         assert_eq!(obj.get("total").and_then(Value::as_i64), Some(4));
@@ -25883,6 +29134,16 @@ mod tests {
         assert_eq!(obj.get("ipv4").and_then(Value::as_i64), Some(0));
         assert_eq!(obj.get("ipv6").and_then(Value::as_i64), Some(0));
         assert_eq!(obj.get("onion").and_then(Value::as_i64), Some(0));
+
+        let tiers = obj.get("tiers").and_then(Value::as_array).expect("tiers");
+        let names: Vec<_> = tiers
+            .iter()
+            .filter_map(|tier| tier.get("name").and_then(Value::as_str))
+            .collect();
+        assert_eq!(names, ["CUMULUS", "NIMBUS", "STRATUS"]);
+        assert!(tiers
+            .iter()
+            .all(|tier| tier.get("collateral").and_then(Value::as_f64).is_some()));
     }
 
     #[test]
@@ -26938,6 +30199,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27080,6 +30342,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27185,6 +30448,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27291,6 +30555,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27364,6 +30629,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27438,6 +30704,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27528,6 +30795,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27618,6 +30886,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27714,6 +30983,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -27808,6 +31078,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let raw_hex = hex_bytes(&tx.consensus_encode().expect("encode tx"));
@@ -28102,6 +31373,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -28174,6 +31446,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -28711,6 +31984,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let incoming_txid = incoming_tx.txid().expect("txid");
@@ -28733,6 +32007,7 @@ mod tests {
                 priority_delta: 0.0,
                 spent_outpoints: vec![incoming_prevout],
                 parents: Vec::new(),
+                sigops: 0,
             })
             .expect("insert mempool tx");
         let mempool = Mutex::new(inner);
@@ -28811,6 +32086,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let spending_txid = spending_tx.txid().expect("txid");
@@ -28836,6 +32112,7 @@ mod tests {
                     index: 0,
                 }],
                 parents: vec![incoming_txid],
+                sigops: 0,
             })
             .expect("insert spending tx");
 
@@ -28924,6 +32201,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = incoming_tx.txid().expect("txid");
@@ -28946,6 +32224,7 @@ mod tests {
                 priority_delta: 0.0,
                 spent_outpoints: vec![incoming_prevout],
                 parents: Vec::new(),
+                sigops: 0,
             })
             .expect("insert mempool tx");
 
@@ -29314,6 +32593,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let spend_txid = spend_tx.txid().expect("txid");
@@ -29387,6 +32667,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let coinbase_txid = coinbase.txid().expect("coinbase txid");
@@ -29503,6 +32784,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = tx.txid().expect("txid");
@@ -29523,6 +32805,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -29610,6 +32893,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = spend_tx.txid().expect("txid");
@@ -29630,6 +32914,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: vec![prevout],
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -29719,6 +33004,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = spend_tx.txid().expect("txid");
@@ -29739,6 +33025,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: vec![prevout],
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -29833,6 +33120,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = spend_tx.txid().expect("txid");
@@ -29853,6 +33141,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: vec![prevout],
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -29996,6 +33285,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let mempool_txid = mempool_tx.txid().expect("txid");
@@ -30015,6 +33305,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -30104,6 +33395,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let mempool_txid = mempool_tx.txid().expect("txid");
@@ -30123,6 +33415,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -30153,6 +33446,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let stored_txid = stored_tx.txid().expect("txid");
@@ -30241,6 +33535,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let txid = spend_tx.txid().expect("txid");
@@ -30261,6 +33556,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: vec![prevout],
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -30298,11 +33594,13 @@ mod tests {
         let blockhash_a = chainstate
             .height_hash(height_a)
             .expect("height_hash")
-            .expect("blockhash a");
+            .expect("blockhash a")
+            .to_internal();
         let blockhash_b = chainstate
             .height_hash(height_b)
             .expect("height_hash")
-            .expect("blockhash b");
+            .expect("blockhash b")
+            .to_internal();
         let blockhash_a_hex = hash256_to_hex(&blockhash_a);
         let blockhash_b_hex = hash256_to_hex(&blockhash_b);
 
@@ -30331,6 +33629,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let mempool_txid = mempool_tx.txid().expect("txid");
@@ -30351,6 +33650,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -30505,6 +33805,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let mempool_txid = mempool_tx.txid().expect("txid");
@@ -30524,6 +33825,7 @@ mod tests {
             priority_delta: 0.0,
             spent_outpoints: Vec::new(),
             parents: Vec::new(),
+            sigops: 0,
         };
         let mut inner = Mempool::new(0);
         inner.insert(entry).expect("insert mempool tx");
@@ -30554,6 +33856,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
         let stored_txid = stored_tx.txid().expect("txid");
@@ -30813,6 +34116,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn listtransactions_pages_by_cursor() {
+        let (chainstate, params, data_dir) = setup_regtest_chainstate();
+        let wallet = Mutex::new(Wallet::load_or_create(&data_dir, params.network).expect("wallet"));
+
+        let mut txids = Vec::new();
+        for _ in 0..3 {
+            let address = rpc_getnewaddress(&wallet, Vec::new())
+                .expect("rpc")
+                .as_str()
+                .expect("address string")
+                .to_string();
+            let script =
+                address_to_script_pubkey(&address, params.network).expect("address script");
+            let (txid, _vout, _height, _value) =
+                mine_regtest_block_to_script(&chainstate, &params, script);
+            txids.push(hash256_to_hex(&txid));
+        }
+
+        let mempool = Mutex::new(Mempool::new(0));
+
+        let page = rpc_listtransactions(
+            &chainstate,
+            &mempool,
+            &wallet,
+            vec![json!("*"), json!(2), Value::Null, Value::Null, json!({})],
+            &params,
+        )
+        .expect("rpc");
+        let rows = page
+            .get("transactions")
+            .and_then(Value::as_array)
+            .expect("transactions array");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0].get("txid").and_then(Value::as_str),
+            Some(txids[1].as_str())
+        );
+        assert_eq!(
+            rows[1].get("txid").and_then(Value::as_str),
+            Some(txids[2].as_str())
+        );
+        assert_eq!(
+            rows[1].get("confirmations").and_then(Value::as_i64),
+            Some(1)
+        );
+        let cursor = page
+            .get("next_cursor")
+            .and_then(Value::as_str)
+            .expect("next cursor")
+            .to_string();
+        assert!(cursor.starts_with("2:0:"));
+
+        let page = rpc_listtransactions(
+            &chainstate,
+            &mempool,
+            &wallet,
+            vec![
+                json!("*"),
+                json!(2),
+                Value::Null,
+                Value::Null,
+                json!({ "cursor": cursor }),
+            ],
+            &params,
+        )
+        .expect("rpc");
+        let rows = page
+            .get("transactions")
+            .and_then(Value::as_array)
+            .expect("transactions array");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].get("txid").and_then(Value::as_str),
+            Some(txids[0].as_str())
+        );
+        assert_eq!(
+            rows[0].get("confirmations").and_then(Value::as_i64),
+            Some(3)
+        );
+        assert!(page.get("next_cursor").expect("next cursor").is_null());
+
+        let err = rpc_listtransactions(
+            &chainstate,
+            &mempool,
+            &wallet,
+            vec![
+                json!("*"),
+                json!(2),
+                json!(1),
+                Value::Null,
+                json!({ "cursor": "not-a-cursor" }),
+            ],
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+    }
+
     #[test]
     fn listtransactions_rejects_negative_count() {
         let (chainstate, params, data_dir) = setup_regtest_chainstate();
@@ -31760,6 +35162,25 @@ mod tests {
             .any(|row| { row.get("category").and_then(Value::as_str) == Some("orphan") }));
     }
 
+    #[test]
+    fn setloglevel_sets_and_resets_target_overrides() {
+        let value = rpc_setloglevel(vec![json!("debug"), json!("fluxd::p2p")]).expect("rpc");
+        assert_eq!(value["targets"]["fluxd::p2p"], json!("DEBUG"));
+        assert!(crate::logging::enabled_for(
+            crate::logging::Level::Debug,
+            "fluxd::p2p::peer"
+        ));
+
+        let value = rpc_setloglevel(vec![json!("reset"), json!("fluxd::p2p")]).expect("rpc");
+        assert!(value["targets"].get("fluxd::p2p").is_none());
+
+        let value = rpc_setloglevel(vec![json!("reset")]).expect("rpc");
+        assert_eq!(value["level"], value["configured"]);
+
+        let err = rpc_setloglevel(vec![json!("loud")]).unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+    }
+
     #[test]
     fn verifychain_returns_bool() {
         let (chainstate, _params, _data_dir) = setup_regtest_chainstate();
@@ -31767,6 +35188,68 @@ mod tests {
         assert_eq!(value.as_bool(), Some(true));
     }
 
+    #[test]
+    fn debugvalidateblock_reports_without_connecting() {
+        let (chainstate, params, _data_dir) = setup_regtest_chainstate();
+        let (_, _, height, _) =
+            mine_regtest_block_to_script(&chainstate, &params, p2pkh_script([0x22u8; 20]));
+        let hash = chainstate
+            .height_hash(height)
+            .expect("height hash")
+            .map(BlockHash::to_internal)
+            .expect("mined block");
+        let tip_before = chainstate
+            .best_block()
+            .expect("best block")
+            .map(|tip| tip.hash);
+        let flags = ValidationFlags::default();
+
+        let value = rpc_debugvalidateblock(
+            &chainstate,
+            vec![json!(hash256_to_hex(&hash)), json!({ "check_pow": false })],
+            &params,
+            &flags,
+        )
+        .expect("rpc");
+        assert_eq!(
+            value.get("hash").and_then(Value::as_str),
+            Some(hash256_to_hex(&hash).as_str())
+        );
+        assert_eq!(
+            value.get("height").and_then(Value::as_i64),
+            Some(height as i64)
+        );
+        assert_eq!(value["flags"]["check_pow"], json!(false));
+        assert_eq!(value["flags"]["check_script"], json!(true));
+        let txs = value.get("txs").and_then(Value::as_array).expect("txs");
+        assert_eq!(txs.len(), 1);
+        assert!(txs[0].get("txid").and_then(Value::as_str).is_some());
+        let unchecked_errors = value["block_errors"].as_array().expect("errors").len();
+
+        let value = rpc_debugvalidateblock(
+            &chainstate,
+            vec![json!(hash256_to_hex(&hash))],
+            &params,
+            &flags,
+        )
+        .expect("rpc");
+        assert_eq!(value.get("valid").and_then(Value::as_bool), Some(false));
+        assert!(value["block_errors"].as_array().expect("errors").len() > unchecked_errors);
+
+        assert_eq!(
+            chainstate
+                .best_block()
+                .expect("best block")
+                .map(|tip| tip.hash),
+            tip_before
+        );
+
+        let err =
+            rpc_debugvalidateblock(&chainstate, vec![json!("00".repeat(32))], &params, &flags)
+                .unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_ADDRESS_OR_KEY);
+    }
+
     #[test]
     fn network_hashrate_rpcs_return_numbers() {
         let (chainstate, params, _data_dir) = setup_regtest_chainstate();
@@ -31873,6 +35356,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -31902,6 +35386,7 @@ mod tests {
                     priority_delta: 0.0,
                     spent_outpoints: Vec::new(),
                     parents: Vec::new(),
+                    sigops: 0,
                 })
                 .expect("insert");
         }
@@ -32040,6 +35525,7 @@ fn peer_kind_name(kind: PeerKind) -> &'static str {
         PeerKind::Block => "block",
         PeerKind::Header => "header",
         PeerKind::Relay => "relay",
+        PeerKind::BlockRelayOnly => "block-relay-only",
     }
 }
 
@@ -32058,6 +35544,7 @@ fn resolve_block_hash<S: fluxd_storage::KeyValueStore>(
         chainstate
             .height_hash(height)
             .map_err(map_internal)?
+            .map(BlockHash::to_internal)
             .ok_or_else(|| RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "block not found"))?
     } else {
         parse_hash(value)?
@@ -32278,7 +35765,8 @@ fn decode_address_scripts(
             AddressError::InvalidLength
             | AddressError::InvalidCharacter
             | AddressError::InvalidChecksum
-            | AddressError::UnknownPrefix => {
+            | AddressError::UnknownPrefix
+            | AddressError::InvalidWitnessVersion => {
                 RpcError::new(RPC_INVALID_ADDRESS_OR_KEY, "Invalid address")
             }
         })?;
@@ -32381,7 +35869,7 @@ fn confirmations_for_height<S: fluxd_storage::KeyValueStore>(
         return Ok(-1);
     }
     let main_hash = chainstate.height_hash(height).map_err(map_internal)?;
-    if main_hash.as_ref() != Some(hash) {
+    if main_hash != Some(BlockHash::from_internal(*hash)) {
         return Ok(-1);
     }
     Ok(best_height - height + 1)
@@ -32397,34 +35885,29 @@ fn next_hash_for_height<S: fluxd_storage::KeyValueStore>(
         return Ok(None);
     }
     let main_hash = chainstate.height_hash(height).map_err(map_internal)?;
-    if main_hash.as_ref() != Some(hash) {
+    if main_hash != Some(BlockHash::from_internal(*hash)) {
         return Ok(None);
     }
-    chainstate.height_hash(height + 1).map_err(map_internal)
+    Ok(chainstate
+        .height_hash(height + 1)
+        .map_err(map_internal)?
+        .map(BlockHash::to_internal))
 }
 
 fn median_time_past<S: fluxd_storage::KeyValueStore>(
     chainstate: &ChainState<S>,
     height: i32,
 ) -> Result<i64, RpcError> {
-    let mut times = Vec::with_capacity(11);
-    let mut current = height;
-    for _ in 0..11 {
-        if current < 0 {
-            break;
-        }
-        let Some(hash) = chainstate.height_hash(current).map_err(map_internal)? else {
-            break;
-        };
-        let entry = chainstate
-            .header_entry(&hash)
-            .map_err(map_internal)?
-            .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "missing header entry"))?;
-        times.push(entry.time as i64);
-        current -= 1;
+    if height < 0 {
+        return Ok(0);
     }
-    times.sort_unstable();
-    Ok(times.get(times.len() / 2).copied().unwrap_or(0))
+    let Some(hash) = chainstate.height_hash(height).map_err(map_internal)? else {
+        return Ok(0);
+    };
+    chainstate
+        .median_time_past(hash.as_internal(), MTP_WINDOW_SIZE)
+        .map(i64::from)
+        .map_err(map_internal)
 }
 
 fn spent_details_address(
@@ -32460,7 +35943,7 @@ fn resolve_prevout_via_txindex<S: fluxd_storage::KeyValueStore>(
     let prev_txid = outpoint.hash;
     if !tx_cache.contains_key(&prev_txid) {
         let location = chainstate
-            .tx_location(&prev_txid)
+            .tx_location(&outpoint.txid())
             .map_err(map_internal)?
             .ok_or_else(|| RpcError::new(RPC_INTERNAL_ERROR, "Spent information not available"))?;
         let bytes = chainstate
@@ -32860,7 +36343,7 @@ fn fluxnode_payment_address<S: fluxd_storage::KeyValueStore>(
             .any(|expected| expected == pubkey);
         if is_p2sh_signing_key {
             let utxo = chainstate
-                .utxo_entry(&record.collateral)
+                .utxo_entry(&record.collateral.txid(), record.collateral.index)
                 .map_err(map_internal)?;
             let Some(utxo) = utxo else {
                 return Ok(None);
@@ -32918,7 +36401,7 @@ fn header_time_at_height<S: fluxd_storage::KeyValueStore>(
     }
     let hash = chainstate.height_hash(height).ok().flatten()?;
     chainstate
-        .header_entry(&hash)
+        .header_entry(hash.as_internal())
         .ok()
         .flatten()
         .map(|entry| entry.time)
@@ -32965,7 +36448,7 @@ fn base58check_decode(input: &str) -> Result<Vec<u8>, AddressError> {
     }
     let (payload, checksum) = bytes.split_at(bytes.len() - 4);
     let digest = sha256d(payload);
-    if checksum != &digest[..4] {
+    if !ct_eq(checksum, &digest[..4]) {
         return Err(AddressError::InvalidChecksum);
     }
     Ok(payload.to_vec())
@@ -33116,7 +36599,7 @@ struct HttpRequest {
     body: Vec<u8>,
 }
 
-async fn read_http_request(stream: &mut tokio::net::TcpStream) -> Result<HttpRequest, String> {
+async fn read_http_request(stream: &mut listen::Stream) -> Result<HttpRequest, String> {
     let mut buffer = Vec::new();
     let mut temp = [0u8; 4096];
     let mut header_end = None;
@@ -1,5 +1,5 @@
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Mutex;
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use fluxd_chainstate::metrics::ConnectMetrics;
@@ -7,6 +7,11 @@ use fluxd_chainstate::state::ChainState;
 use fluxd_chainstate::validation::ValidationMetrics;
 use fluxd_consensus::params::Network;
 use fluxd_consensus::Hash256;
+use fluxd_pow::difficulty::network_hashrate;
+use fluxd_primitives::block::BlockHeader;
+use fluxd_primitives::hash::hash256_to_hex;
+use fluxd_primitives::ids::BlockHash;
+use fluxd_storage::instrumented::{LatencyHistogramSnapshot, StoreLatency, StoreOp};
 use fluxd_storage::KeyValueStore;
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +33,7 @@ pub struct StatsSnapshot {
     pub uptime_secs: u64,
     pub unix_time_secs: u64,
     pub sync_state: String,
+    pub network_hashrate: f64,
     pub mempool_size: u64,
     pub mempool_bytes: u64,
     pub mempool_max_bytes: u64,
@@ -37,6 +43,7 @@ pub struct StatsSnapshot {
     pub mempool_relay_reject: u64,
     pub mempool_evicted: u64,
     pub mempool_evicted_bytes: u64,
+    pub mempool_expired: u64,
     pub mempool_loaded: u64,
     pub mempool_load_reject: u64,
     pub mempool_persisted_writes: u64,
@@ -52,6 +59,12 @@ pub struct StatsSnapshot {
     pub verify_blocks: u64,
     pub commit_us: u64,
     pub commit_blocks: u64,
+    pub download_p50_us: Option<u64>,
+    pub download_p95_us: Option<u64>,
+    pub verify_p50_us: Option<u64>,
+    pub verify_p95_us: Option<u64>,
+    pub commit_p50_us: Option<u64>,
+    pub commit_p95_us: Option<u64>,
     pub header_request_us: u64,
     pub header_request_batches: u64,
     pub header_validate_us: u64,
@@ -100,6 +113,10 @@ pub struct StatsSnapshot {
     pub pon_sig_blocks: u64,
     pub payout_us: u64,
     pub payout_blocks: u64,
+    pub utxo_flushes: u64,
+    pub utxo_flush_us: u64,
+    pub utxo_flush_entries: u64,
+    pub utxo_dirty_entries: u64,
     pub db_write_buffer_bytes: Option<u64>,
     pub db_max_write_buffer_bytes: Option<u64>,
     pub db_journal_count: Option<u64>,
@@ -121,6 +138,62 @@ pub struct StatsSnapshot {
     pub db_address_delta_flushes_completed: Option<u64>,
     pub db_header_index_segments: Option<u64>,
     pub db_header_index_flushes_completed: Option<u64>,
+    /// Per-column store latency; empty unless the node runs with `--db-latency`.
+    #[serde(default)]
+    pub db_latency: Vec<DbColumnLatency>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DbOpLatency {
+    pub count: u64,
+    pub total_us: u64,
+    pub p50_us: Option<u64>,
+    pub p95_us: Option<u64>,
+    pub p99_us: Option<u64>,
+}
+
+impl From<&LatencyHistogramSnapshot> for DbOpLatency {
+    fn from(histogram: &LatencyHistogramSnapshot) -> Self {
+        Self {
+            count: histogram.count(),
+            total_us: histogram.total_us,
+            p50_us: histogram.percentile_us(50),
+            p95_us: histogram.percentile_us(95),
+            p99_us: histogram.percentile_us(99),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DbColumnLatency {
+    pub column: String,
+    pub get: DbOpLatency,
+    pub put: DbOpLatency,
+    pub scan: DbOpLatency,
+}
+
+impl DbColumnLatency {
+    pub fn op(&self, op: StoreOp) -> &DbOpLatency {
+        match op {
+            StoreOp::Get => &self.get,
+            StoreOp::Put => &self.put,
+            StoreOp::Scan => &self.scan,
+        }
+    }
+}
+
+/// Columns that recorded at least one operation, in column order.
+pub fn db_latency_rows(latency: &StoreLatency) -> Vec<DbColumnLatency> {
+    latency
+        .snapshot()
+        .iter()
+        .map(|snapshot| DbColumnLatency {
+            column: snapshot.column.as_str().to_string(),
+            get: DbOpLatency::from(&snapshot.get),
+            put: DbOpLatency::from(&snapshot.put),
+            scan: DbOpLatency::from(&snapshot.scan),
+        })
+        .collect()
 }
 
 impl StatsSnapshot {
@@ -160,6 +233,8 @@ impl StatsSnapshot {
         json.push_str(&self.unix_time_secs.to_string());
         json.push_str(",\"sync_state\":");
         json.push_str(&json_string(&self.sync_state));
+        json.push_str(",\"network_hashrate\":");
+        json.push_str(&self.network_hashrate.to_string());
         json.push_str(",\"mempool_size\":");
         json.push_str(&self.mempool_size.to_string());
         json.push_str(",\"mempool_bytes\":");
@@ -178,6 +253,8 @@ impl StatsSnapshot {
         json.push_str(&self.mempool_evicted.to_string());
         json.push_str(",\"mempool_evicted_bytes\":");
         json.push_str(&self.mempool_evicted_bytes.to_string());
+        json.push_str(",\"mempool_expired\":");
+        json.push_str(&self.mempool_expired.to_string());
         json.push_str(",\"mempool_loaded\":");
         json.push_str(&self.mempool_loaded.to_string());
         json.push_str(",\"mempool_load_reject\":");
@@ -208,6 +285,18 @@ impl StatsSnapshot {
         json.push_str(&self.commit_us.to_string());
         json.push_str(",\"commit_blocks\":");
         json.push_str(&self.commit_blocks.to_string());
+        json.push_str(",\"download_p50_us\":");
+        push_json_u64_opt(&mut json, self.download_p50_us);
+        json.push_str(",\"download_p95_us\":");
+        push_json_u64_opt(&mut json, self.download_p95_us);
+        json.push_str(",\"verify_p50_us\":");
+        push_json_u64_opt(&mut json, self.verify_p50_us);
+        json.push_str(",\"verify_p95_us\":");
+        push_json_u64_opt(&mut json, self.verify_p95_us);
+        json.push_str(",\"commit_p50_us\":");
+        push_json_u64_opt(&mut json, self.commit_p50_us);
+        json.push_str(",\"commit_p95_us\":");
+        push_json_u64_opt(&mut json, self.commit_p95_us);
         json.push_str(",\"header_request_us\":");
         json.push_str(&self.header_request_us.to_string());
         json.push_str(",\"header_request_batches\":");
@@ -304,6 +393,14 @@ impl StatsSnapshot {
         json.push_str(&self.payout_us.to_string());
         json.push_str(",\"payout_blocks\":");
         json.push_str(&self.payout_blocks.to_string());
+        json.push_str(",\"utxo_flushes\":");
+        json.push_str(&self.utxo_flushes.to_string());
+        json.push_str(",\"utxo_flush_us\":");
+        json.push_str(&self.utxo_flush_us.to_string());
+        json.push_str(",\"utxo_flush_entries\":");
+        json.push_str(&self.utxo_flush_entries.to_string());
+        json.push_str(",\"utxo_dirty_entries\":");
+        json.push_str(&self.utxo_dirty_entries.to_string());
 
         json.push_str(",\"db_write_buffer_bytes\":");
         push_json_u64_opt(&mut json, self.db_write_buffer_bytes);
@@ -347,6 +444,10 @@ impl StatsSnapshot {
         push_json_u64_opt(&mut json, self.db_header_index_segments);
         json.push_str(",\"db_header_index_flushes_completed\":");
         push_json_u64_opt(&mut json, self.db_header_index_flushes_completed);
+        json.push_str(",\"db_latency\":");
+        json.push_str(
+            &serde_json::to_string(&self.db_latency).unwrap_or_else(|_| "[]".to_string()),
+        );
         json.push('}');
         json
     }
@@ -376,6 +477,7 @@ impl StatsSnapshot {
         gauge!("fluxd_header_gap", self.header_gap);
         gauge!("fluxd_uptime_secs", self.uptime_secs);
         gauge!("fluxd_unix_time_secs", self.unix_time_secs);
+        gauge!("fluxd_network_hashrate", self.network_hashrate);
 
         gauge!("fluxd_mempool_size", self.mempool_size);
         gauge!("fluxd_mempool_bytes", self.mempool_bytes);
@@ -396,6 +498,7 @@ impl StatsSnapshot {
             "fluxd_mempool_evicted_bytes_total",
             self.mempool_evicted_bytes
         );
+        gauge!("fluxd_mempool_expired_total", self.mempool_expired);
         gauge!("fluxd_mempool_loaded_total", self.mempool_loaded);
         gauge!("fluxd_mempool_load_reject_total", self.mempool_load_reject);
         gauge!(
@@ -413,6 +516,18 @@ impl StatsSnapshot {
         gauge!("fluxd_verify_blocks_total", self.verify_blocks);
         gauge!("fluxd_commit_us_total", self.commit_us);
         gauge!("fluxd_commit_blocks_total", self.commit_blocks);
+        for (name, value) in [
+            ("fluxd_download_p50_us", self.download_p50_us),
+            ("fluxd_download_p95_us", self.download_p95_us),
+            ("fluxd_verify_p50_us", self.verify_p50_us),
+            ("fluxd_verify_p95_us", self.verify_p95_us),
+            ("fluxd_commit_p50_us", self.commit_p50_us),
+            ("fluxd_commit_p95_us", self.commit_p95_us),
+        ] {
+            if let Some(value) = value {
+                gauge!(name, value);
+            }
+        }
 
         gauge!("fluxd_header_request_us_total", self.header_request_us);
         gauge!(
@@ -484,6 +599,10 @@ impl StatsSnapshot {
         gauge!("fluxd_pon_sig_blocks_total", self.pon_sig_blocks);
         gauge!("fluxd_payout_us_total", self.payout_us);
         gauge!("fluxd_payout_blocks_total", self.payout_blocks);
+        gauge!("fluxd_utxo_flushes_total", self.utxo_flushes);
+        gauge!("fluxd_utxo_flush_us_total", self.utxo_flush_us);
+        gauge!("fluxd_utxo_flush_entries_total", self.utxo_flush_entries);
+        gauge!("fluxd_utxo_dirty_entries", self.utxo_dirty_entries);
 
         if let Some(value) = self.db_write_buffer_bytes {
             gauge!("fluxd_db_write_buffer_bytes", value);
@@ -550,6 +669,39 @@ impl StatsSnapshot {
             gauge!("fluxd_db_header_index_flushes_completed_total", value);
         }
 
+        for row in &self.db_latency {
+            for op in StoreOp::ALL {
+                let latency = row.op(op);
+                if latency.count == 0 {
+                    continue;
+                }
+                let op_labels =
+                    format!("{labels},column=\"{}\",op=\"{}\"", row.column, op.as_str());
+                let _ = writeln!(
+                    &mut out,
+                    "fluxd_db_op_total{{{op_labels}}} {}",
+                    latency.count
+                );
+                let _ = writeln!(
+                    &mut out,
+                    "fluxd_db_op_us_total{{{op_labels}}} {}",
+                    latency.total_us
+                );
+                for (quantile, value) in [
+                    ("0.5", latency.p50_us),
+                    ("0.95", latency.p95_us),
+                    ("0.99", latency.p99_us),
+                ] {
+                    if let Some(value) = value {
+                        let _ = writeln!(
+                            &mut out,
+                            "fluxd_db_op_latency_us{{{op_labels},quantile=\"{quantile}\"}} {value}"
+                        );
+                    }
+                }
+            }
+        }
+
         out
     }
 }
@@ -561,6 +713,115 @@ fn push_json_u64_opt(json: &mut String, value: Option<u64>) {
     }
 }
 
+/// Number of log2 microsecond buckets in a [`StageHistogram`]; the last bucket
+/// absorbs everything from ~4.2s upwards.
+pub const STAGE_HISTOGRAM_BUCKETS: usize = 24;
+
+/// Samples older than one to two windows are dropped from the percentiles.
+const STAGE_HISTOGRAM_WINDOW: Duration = Duration::from_secs(60);
+
+/// Fixed-size rolling latency histogram for a single sync stage.
+///
+/// Samples land in power-of-two microsecond buckets. Two generations are kept
+/// and rotated every [`STAGE_HISTOGRAM_WINDOW`], so memory stays constant and
+/// percentiles describe the last one to two minutes rather than the whole run.
+#[derive(Debug)]
+pub struct StageHistogram {
+    inner: Mutex<StageHistogramWindow>,
+}
+
+#[derive(Debug)]
+struct StageHistogramWindow {
+    current: [u64; STAGE_HISTOGRAM_BUCKETS],
+    previous: [u64; STAGE_HISTOGRAM_BUCKETS],
+    rotated_at: Instant,
+}
+
+impl Default for StageHistogram {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(StageHistogramWindow {
+                current: [0; STAGE_HISTOGRAM_BUCKETS],
+                previous: [0; STAGE_HISTOGRAM_BUCKETS],
+                rotated_at: Instant::now(),
+            }),
+        }
+    }
+}
+
+impl StageHistogram {
+    /// Records `samples` items that took `elapsed` in total, each counted at
+    /// the batch average.
+    pub fn record(&self, samples: u64, elapsed: Duration) {
+        if samples == 0 {
+            return;
+        }
+        let bucket = stage_histogram_bucket(elapsed.as_micros() as u64 / samples);
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.rotate(Instant::now());
+        inner.current[bucket] = inner.current[bucket].saturating_add(samples);
+    }
+
+    pub fn snapshot(&self) -> StageHistogramSnapshot {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.rotate(Instant::now());
+        let mut buckets = inner.previous;
+        for (total, count) in buckets.iter_mut().zip(inner.current.iter()) {
+            *total = total.saturating_add(*count);
+        }
+        StageHistogramSnapshot { buckets }
+    }
+}
+
+impl StageHistogramWindow {
+    fn rotate(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.rotated_at);
+        if elapsed < STAGE_HISTOGRAM_WINDOW {
+            return;
+        }
+        self.previous = if elapsed < STAGE_HISTOGRAM_WINDOW * 2 {
+            self.current
+        } else {
+            [0; STAGE_HISTOGRAM_BUCKETS]
+        };
+        self.current = [0; STAGE_HISTOGRAM_BUCKETS];
+        self.rotated_at = now;
+    }
+}
+
+fn stage_histogram_bucket(us: u64) -> usize {
+    ((u64::BITS - us.leading_zeros()) as usize).min(STAGE_HISTOGRAM_BUCKETS - 1)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StageHistogramSnapshot {
+    pub buckets: [u64; STAGE_HISTOGRAM_BUCKETS],
+}
+
+impl StageHistogramSnapshot {
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Upper bound (in microseconds) of the bucket holding the `percent`th
+    /// sample, or `None` when the window is empty.
+    pub fn percentile_us(&self, percent: u64) -> Option<u64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (count * percent.min(100)).div_ceil(100).max(1);
+        let mut seen = 0u64;
+        for (bucket, samples) in self.buckets.iter().enumerate() {
+            seen += samples;
+            if seen >= rank {
+                return Some(1u64 << bucket);
+            }
+        }
+        None
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SyncMetrics {
     download_us: AtomicU64,
@@ -569,6 +830,9 @@ pub struct SyncMetrics {
     verify_blocks: AtomicU64,
     commit_us: AtomicU64,
     commit_blocks: AtomicU64,
+    download_hist: StageHistogram,
+    verify_hist: StageHistogram,
+    commit_hist: StageHistogram,
 }
 
 impl SyncMetrics {
@@ -576,18 +840,25 @@ impl SyncMetrics {
         self.download_us
             .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
         self.download_blocks.fetch_add(blocks, Ordering::Relaxed);
+        self.download_hist.record(blocks, elapsed);
     }
 
     pub fn record_verify(&self, blocks: u64, elapsed: Duration) {
         self.verify_us
             .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
         self.verify_blocks.fetch_add(blocks, Ordering::Relaxed);
+        self.verify_hist.record(blocks, elapsed);
     }
 
     pub fn record_commit(&self, blocks: u64, elapsed: Duration) {
         self.commit_us
             .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
         self.commit_blocks.fetch_add(blocks, Ordering::Relaxed);
+        self.commit_hist.record(blocks, elapsed);
+    }
+
+    pub fn committed_blocks(&self) -> u64 {
+        self.commit_blocks.load(Ordering::Relaxed)
     }
 
     pub fn snapshot(&self) -> MetricsSnapshot {
@@ -598,6 +869,9 @@ impl SyncMetrics {
             verify_blocks: self.verify_blocks.load(Ordering::Relaxed),
             commit_us: self.commit_us.load(Ordering::Relaxed),
             commit_blocks: self.commit_blocks.load(Ordering::Relaxed),
+            download_hist: self.download_hist.snapshot(),
+            verify_hist: self.verify_hist.snapshot(),
+            commit_hist: self.commit_hist.snapshot(),
         }
     }
 }
@@ -610,6 +884,9 @@ pub struct MetricsSnapshot {
     pub verify_blocks: u64,
     pub commit_us: u64,
     pub commit_blocks: u64,
+    pub download_hist: StageHistogramSnapshot,
+    pub verify_hist: StageHistogramSnapshot,
+    pub commit_hist: StageHistogramSnapshot,
 }
 
 #[derive(Debug, Default)]
@@ -622,6 +899,7 @@ pub struct HeaderMetrics {
     commit_headers: AtomicU64,
     pow_us: AtomicU64,
     pow_headers: AtomicU64,
+    last_commit_unix: AtomicU64,
 }
 
 impl HeaderMetrics {
@@ -641,6 +919,18 @@ impl HeaderMetrics {
         self.commit_us
             .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
         self.commit_headers.fetch_add(headers, Ordering::Relaxed);
+        if headers > 0 {
+            self.last_commit_unix
+                .store(unix_now_secs(), Ordering::Relaxed);
+        }
+    }
+
+    /// Unix time of the last commit that added headers, if any happened this run.
+    pub fn last_commit_unix(&self) -> Option<u64> {
+        match self.last_commit_unix.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(secs),
+        }
     }
 
     pub fn record_pow(&self, headers: u64, elapsed: Duration) {
@@ -683,6 +973,7 @@ pub struct MempoolMetrics {
     relay_reject: AtomicU64,
     evicted: AtomicU64,
     evicted_bytes: AtomicU64,
+    expired: AtomicU64,
     loaded: AtomicU64,
     load_reject: AtomicU64,
     persisted_writes: AtomicU64,
@@ -711,6 +1002,10 @@ impl MempoolMetrics {
         self.evicted_bytes.fetch_add(bytes, Ordering::Relaxed);
     }
 
+    pub fn note_expired(&self, count: u64) {
+        self.expired.fetch_add(count, Ordering::Relaxed);
+    }
+
     pub fn note_loaded(&self, count: u64) {
         self.loaded.fetch_add(count, Ordering::Relaxed);
     }
@@ -732,6 +1027,7 @@ impl MempoolMetrics {
             relay_reject: self.relay_reject.load(Ordering::Relaxed),
             evicted: self.evicted.load(Ordering::Relaxed),
             evicted_bytes: self.evicted_bytes.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
             loaded: self.loaded.load(Ordering::Relaxed),
             load_reject: self.load_reject.load(Ordering::Relaxed),
             persisted_writes: self.persisted_writes.load(Ordering::Relaxed),
@@ -748,12 +1044,63 @@ pub struct MempoolMetricsSnapshot {
     pub relay_reject: u64,
     pub evicted: u64,
     pub evicted_bytes: u64,
+    pub expired: u64,
     pub loaded: u64,
     pub load_reject: u64,
     pub persisted_writes: u64,
     pub persisted_bytes: u64,
 }
 
+/// Main-chain blocks averaged by [`network_hashrate_estimate`].
+const NETWORK_HASHRATE_WINDOW: usize = 120;
+
+/// Estimated PoW hashes per second over the last `window` main-chain blocks (PoN blocks are
+/// skipped). The result is cached per tip, so repeated `/stats` polls only re-read the window
+/// once a new block connects.
+pub fn network_hashrate_estimate<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    window: usize,
+) -> Result<f64, String> {
+    static CACHE: OnceLock<Mutex<Option<(Hash256, usize, f64)>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(None));
+
+    let Some(tip) = chainstate.best_block().map_err(|err| err.to_string())? else {
+        return Ok(0.0);
+    };
+    if let Ok(guard) = cache.lock() {
+        if let Some((hash, cached_window, hashrate)) = *guard {
+            if hash == tip.hash && cached_window == window {
+                return Ok(hashrate);
+            }
+        }
+    }
+
+    let window_height = i32::try_from(window).unwrap_or(i32::MAX);
+    let start = tip.height.saturating_sub(window_height).max(0);
+    let mut headers = Vec::with_capacity(window.saturating_add(1));
+    for height in start..=tip.height {
+        let Some(hash) = chainstate
+            .height_hash(height)
+            .map_err(|err| err.to_string())?
+            .map(BlockHash::to_internal)
+        else {
+            continue;
+        };
+        let Some(bytes) = chainstate
+            .block_header_bytes(&hash)
+            .map_err(|err| err.to_string())?
+        else {
+            continue;
+        };
+        headers.push(BlockHeader::consensus_decode(&bytes).map_err(|err| err.to_string())?);
+    }
+    let hashrate = network_hashrate(&headers, window);
+    if let Ok(mut guard) = cache.lock() {
+        *guard = Some((tip.hash, window, hashrate));
+    }
+    Ok(hashrate)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn snapshot_stats<S: KeyValueStore>(
     chainstate: &ChainState<S>,
@@ -790,6 +1137,7 @@ pub fn snapshot_stats<S: KeyValueStore>(
 
     let header_gap = best_header_height as i64 - best_block_height as i64;
     let sync_state = if header_gap <= 0 { "synced" } else { "syncing" };
+    let network_hashrate = network_hashrate_estimate(chainstate, NETWORK_HASHRATE_WINDOW)?;
 
     let uptime_secs = start_time.elapsed().as_secs();
     let unix_time_secs = SystemTime::now()
@@ -861,6 +1209,7 @@ pub fn snapshot_stats<S: KeyValueStore>(
         uptime_secs,
         unix_time_secs,
         sync_state: sync_state.to_string(),
+        network_hashrate,
         mempool_size,
         mempool_bytes,
         mempool_max_bytes,
@@ -870,6 +1219,7 @@ pub fn snapshot_stats<S: KeyValueStore>(
         mempool_relay_reject: mempool_metrics_snapshot.relay_reject,
         mempool_evicted: mempool_metrics_snapshot.evicted,
         mempool_evicted_bytes: mempool_metrics_snapshot.evicted_bytes,
+        mempool_expired: mempool_metrics_snapshot.expired,
         mempool_loaded: mempool_metrics_snapshot.loaded,
         mempool_load_reject: mempool_metrics_snapshot.load_reject,
         mempool_persisted_writes: mempool_metrics_snapshot.persisted_writes,
@@ -885,6 +1235,12 @@ pub fn snapshot_stats<S: KeyValueStore>(
         verify_blocks: metrics.verify_blocks,
         commit_us: metrics.commit_us,
         commit_blocks: metrics.commit_blocks,
+        download_p50_us: metrics.download_hist.percentile_us(50),
+        download_p95_us: metrics.download_hist.percentile_us(95),
+        verify_p50_us: metrics.verify_hist.percentile_us(50),
+        verify_p95_us: metrics.verify_hist.percentile_us(95),
+        commit_p50_us: metrics.commit_hist.percentile_us(50),
+        commit_p95_us: metrics.commit_hist.percentile_us(95),
         header_request_us: header_metrics.request_us,
         header_request_batches: header_metrics.request_batches,
         header_validate_us: header_metrics.validate_us,
@@ -933,6 +1289,10 @@ pub fn snapshot_stats<S: KeyValueStore>(
         pon_sig_blocks: connect.pon_sig_blocks,
         payout_us: connect.payout_us,
         payout_blocks: connect.payout_blocks,
+        utxo_flushes: connect.utxo_flushes,
+        utxo_flush_us: connect.utxo_flush_us,
+        utxo_flush_entries: connect.utxo_flush_entries,
+        utxo_dirty_entries: chainstate.utxo_dirty_entries() as u64,
         db_write_buffer_bytes: db.as_ref().map(|db| db.write_buffer_bytes),
         db_max_write_buffer_bytes: db.as_ref().and_then(|db| db.max_write_buffer_bytes),
         db_journal_count: db.as_ref().map(|db| db.journal_count),
@@ -958,25 +1318,101 @@ pub fn snapshot_stats<S: KeyValueStore>(
             .map(|db| db.address_delta_flushes_completed),
         db_header_index_segments: db.as_ref().map(|db| db.header_index_segments),
         db_header_index_flushes_completed: db.as_ref().map(|db| db.header_index_flushes_completed),
+        db_latency: store
+            .and_then(|store| store.latency())
+            .map(db_latency_rows)
+            .unwrap_or_default(),
     })
 }
 
-pub fn hash256_to_hex(hash: &Hash256) -> String {
-    let mut out = String::with_capacity(64);
-    for byte in hash.iter().rev() {
-        out.push(hex_digit(byte >> 4));
-        out.push(hex_digit(byte & 0x0f));
-    }
-    out
+/// Header sync is considered stalled once nothing was committed for this long, unless the best
+/// header is already recent (see [`HEALTH_HEADER_TIP_AGE_SECS`]).
+pub const HEALTH_HEADER_STALL_SECS: u64 = 600;
+
+/// A best header younger than this counts as being at the network tip.
+pub const HEALTH_HEADER_TIP_AGE_SECS: u64 = 3_600;
+
+/// Probe view served by the dashboard `/health` and `/ready` endpoints.
+///
+/// `healthy` needs a readable store, at least one connected peer and header sync that is either
+/// at the tip or still committing headers. `ready` additionally needs the block tip to be within
+/// `ready_max_lag` blocks of the best header.
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthSnapshot {
+    pub store_open: bool,
+    pub headers_ok: bool,
+    pub peers: usize,
+    pub best_header_height: i32,
+    pub best_block_height: i32,
+    pub block_lag: i64,
+    pub ready_max_lag: u32,
+    pub healthy: bool,
+    pub ready: bool,
 }
 
-fn hex_digit(value: u8) -> char {
-    match value {
-        0..=9 => (b'0' + value) as char,
-        _ => (b'a' + (value - 10)) as char,
+pub fn snapshot_health<S: KeyValueStore>(
+    chainstate: &ChainState<S>,
+    store: &Store,
+    header_metrics: &HeaderMetrics,
+    peers: usize,
+    start_time: Instant,
+    ready_max_lag: u32,
+) -> HealthSnapshot {
+    let store_open = crate::meta_u32(store, crate::DB_SCHEMA_VERSION_KEY).is_ok();
+    let best_header = chainstate.best_header().ok().flatten();
+    let best_block = chainstate.best_block().ok().flatten();
+    let best_header_time = best_header
+        .as_ref()
+        .and_then(|tip| chainstate.header_entry(&tip.hash).ok().flatten())
+        .map(|entry| u64::from(entry.time));
+    let best_header_height = best_header.as_ref().map(|tip| tip.height).unwrap_or(-1);
+    let best_block_height = best_block.as_ref().map(|tip| tip.height).unwrap_or(-1);
+
+    let headers_ok = header_sync_ok(
+        unix_now_secs(),
+        best_header_time,
+        header_metrics.last_commit_unix(),
+        start_time.elapsed().as_secs(),
+    );
+    let block_lag = i64::from(best_header_height) - i64::from(best_block_height);
+    let healthy = store_open && headers_ok && peers > 0;
+    HealthSnapshot {
+        store_open,
+        headers_ok,
+        peers,
+        best_header_height,
+        best_block_height,
+        block_lag,
+        ready_max_lag,
+        healthy,
+        ready: healthy && best_header.is_some() && block_lag <= i64::from(ready_max_lag),
     }
 }
 
+/// At the tip when the best header is recent; otherwise progressing when headers were committed
+/// within [`HEALTH_HEADER_STALL_SECS`], or the node started less than that long ago.
+fn header_sync_ok(
+    now: u64,
+    best_header_time: Option<u64>,
+    last_commit: Option<u64>,
+    uptime_secs: u64,
+) -> bool {
+    let at_tip =
+        best_header_time.is_some_and(|time| now.saturating_sub(time) <= HEALTH_HEADER_TIP_AGE_SECS);
+    let progressing = match last_commit {
+        Some(time) => now.saturating_sub(time) <= HEALTH_HEADER_STALL_SECS,
+        None => uptime_secs <= HEALTH_HEADER_STALL_SECS,
+    };
+    at_tip || progressing
+}
+
+fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 fn json_string(value: &str) -> String {
     let mut out = String::with_capacity(value.len() + 2);
     out.push('"');
@@ -999,3 +1435,60 @@ fn json_i64_opt(value: Option<i64>) -> String {
         .map(|value| value.to_string())
         .unwrap_or_else(|| "null".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_histogram_percentiles_use_bucket_upper_bounds() {
+        let histogram = StageHistogram::default();
+        assert_eq!(histogram.snapshot().percentile_us(50), None);
+
+        histogram.record(90, Duration::from_micros(700 * 90));
+        histogram.record(10, Duration::from_millis(40 * 10));
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count(), 100);
+        assert_eq!(snapshot.percentile_us(50), Some(1024));
+        assert_eq!(snapshot.percentile_us(90), Some(1024));
+        assert_eq!(snapshot.percentile_us(95), Some(65_536));
+
+        histogram.record(1, Duration::from_secs(3_600));
+        assert_eq!(histogram.snapshot().buckets[STAGE_HISTOGRAM_BUCKETS - 1], 1);
+    }
+
+    #[test]
+    fn stage_histogram_window_rotates_out_old_samples() {
+        let mut window = StageHistogramWindow {
+            current: [0; STAGE_HISTOGRAM_BUCKETS],
+            previous: [0; STAGE_HISTOGRAM_BUCKETS],
+            rotated_at: Instant::now(),
+        };
+        window.current[3] = 5;
+        let start = window.rotated_at;
+
+        window.rotate(start + STAGE_HISTOGRAM_WINDOW);
+        assert_eq!(window.previous[3], 5);
+        assert_eq!(window.current[3], 0);
+
+        window.rotate(start + STAGE_HISTOGRAM_WINDOW * 4);
+        assert_eq!(window.previous[3], 0);
+    }
+
+    #[test]
+    fn header_sync_ok_accepts_tip_or_recent_progress() {
+        let now = 1_700_000_000;
+        let stale = Some(now - HEALTH_HEADER_TIP_AGE_SECS - 1);
+        assert!(header_sync_ok(now, Some(now - 60), None, 86_400));
+        assert!(header_sync_ok(now, stale, Some(now - 30), 86_400));
+        assert!(header_sync_ok(now, stale, None, 60));
+        assert!(!header_sync_ok(now, stale, None, 86_400));
+        assert!(!header_sync_ok(
+            now,
+            stale,
+            Some(now - HEALTH_HEADER_STALL_SECS - 1),
+            86_400
+        ));
+        assert!(!header_sync_ok(now, None, None, 86_400));
+    }
+}
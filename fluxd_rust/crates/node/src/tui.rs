@@ -2,7 +2,7 @@ use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fmt::Write as FmtWrite;
 use std::fs;
 use std::io::{self, Read, Write};
-use std::net::TcpStream;
+use std::net::{SocketAddr, TcpStream};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
@@ -45,15 +45,18 @@ use fluxd_chainstate::state::ChainState;
 use fluxd_chainstate::validation::ValidationFlags;
 use fluxd_chainstate::validation::ValidationMetrics;
 use fluxd_consensus::constants::COINBASE_MATURITY;
+use fluxd_consensus::money;
 use fluxd_consensus::params::ChainParams;
 use fluxd_consensus::params::Network;
 use fluxd_consensus::Hash256;
+use fluxd_primitives::hash::hash256_to_hex;
 use fluxd_primitives::{address_to_script_pubkey, script_pubkey_to_address, OutPoint};
 
 use crate::fee_estimator::FeeEstimator;
 use crate::mempool::Mempool;
 use crate::mempool::MempoolPolicy;
 use crate::p2p::{NetTotals, PeerKind, PeerRegistry};
+use crate::peer_book::HeaderPeerBook;
 use crate::stats::{self, HeaderMetrics, MempoolMetrics, StatsSnapshot, SyncMetrics};
 use crate::wallet::{SaplingAddressInfo, TransparentAddressInfo, Wallet};
 use crate::RunProfile;
@@ -66,8 +69,9 @@ const LOG_SNAPSHOT_LIMIT: usize = 4096;
 const WALLET_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
 const WALLET_RECENT_TXS: usize = 12;
 const WALLET_PENDING_OPS: usize = 12;
-const PEER_SCROLL_STEP: u16 = 1;
-const PEER_PAGE_STEP: u16 = 10;
+const PEER_SCROLL_STEP: usize = 1;
+const PEER_PAGE_STEP: usize = 10;
+const PEER_CONFIRM_WINDOW: Duration = Duration::from_secs(5);
 const MOUSE_WHEEL_STEP: u16 = 3;
 const QR_MAX_DIM: usize = 48;
 const CTRL_C_GRACE: Duration = Duration::from_secs(2);
@@ -1358,6 +1362,37 @@ struct RemotePeerInfo {
     version: i32,
     start_height: i32,
     user_agent: String,
+    #[serde(default)]
+    last_ping_ms: Option<u64>,
+    #[serde(default)]
+    min_ping_ms: Option<u64>,
+    #[serde(default)]
+    bytes_sent: Option<u64>,
+    #[serde(default)]
+    bytes_recv: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+struct PeerRow {
+    kind_sort: u8,
+    kind: String,
+    inbound: bool,
+    addr: String,
+    start_height: i32,
+    version: i32,
+    user_agent: String,
+    services: Option<u64>,
+    connected_secs: Option<u64>,
+    last_ping_ms: Option<u64>,
+    min_ping_ms: Option<u64>,
+    bytes_sent: Option<u64>,
+    bytes_recv: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+struct BannedRow {
+    target: String,
+    remaining_secs: u64,
 }
 
 #[derive(Clone, Debug, Default, serde::Deserialize)]
@@ -2050,7 +2085,11 @@ struct TuiState {
     setup: Option<SetupWizard>,
     bps_history: RateHistory,
     hps_history: RateHistory,
-    peers_scroll: u16,
+    peers_selected: usize,
+    peers_status: Option<String>,
+    peers_ban_confirm: Option<(SocketAddr, Instant)>,
+    peers_clear_confirm: Option<Instant>,
+    peers_banned: Option<Vec<BannedRow>>,
     remote_peers: Vec<RemotePeerInfo>,
     remote_net_totals: Option<RemoteNetTotals>,
     command_mode: bool,
@@ -2112,7 +2151,11 @@ impl TuiState {
             setup: None,
             bps_history: RateHistory::new(HISTORY_SAMPLES),
             hps_history: RateHistory::new(HISTORY_SAMPLES),
-            peers_scroll: 0,
+            peers_selected: 0,
+            peers_status: None,
+            peers_ban_confirm: None,
+            peers_clear_confirm: None,
+            peers_banned: None,
             remote_peers: Vec::new(),
             remote_net_totals: None,
             command_mode: false,
@@ -2326,6 +2369,44 @@ impl TuiState {
         self.wallet_clamp_selection();
     }
 
+    fn peers_move_selection(&mut self, delta: isize, count: usize) {
+        if count == 0 {
+            self.peers_selected = 0;
+            return;
+        }
+        let current = self.peers_selected.min(count - 1) as isize;
+        self.peers_selected = (current + delta).clamp(0, count as isize - 1) as usize;
+    }
+
+    fn update_banned(&mut self, peer_book: &HeaderPeerBook) {
+        let now = std::time::SystemTime::now();
+        let remaining = |until: std::time::SystemTime| {
+            until
+                .duration_since(now)
+                .map(|left| left.as_secs())
+                .unwrap_or(0)
+        };
+        let mut rows = peer_book
+            .banned_peers()
+            .into_iter()
+            .map(|entry| BannedRow {
+                target: entry.addr.to_string(),
+                remaining_secs: remaining(entry.banned_until),
+            })
+            .chain(
+                peer_book
+                    .banned_subnets()
+                    .into_iter()
+                    .map(|entry| BannedRow {
+                        target: entry.subnet.to_string(),
+                        remaining_secs: remaining(entry.banned_until),
+                    }),
+            )
+            .collect::<Vec<_>>();
+        rows.sort_by(|a, b| a.target.cmp(&b.target));
+        self.peers_banned = Some(rows);
+    }
+
     fn update_logs(&mut self, logs: Vec<logging::CapturedLog>) {
         self.logs = logs;
     }
@@ -2396,6 +2477,7 @@ pub struct TuiInit {
     pub mempool_flags_rx: Receiver<ValidationFlags>,
     pub wallet: Arc<Mutex<Wallet>>,
     pub tx_announce: broadcast::Sender<Hash256>,
+    pub header_peer_book: Arc<HeaderPeerBook>,
 }
 
 pub fn run_tui(
@@ -2529,6 +2611,11 @@ pub fn run_tui(
             if matches!(state.screen, Screen::Logs) && !state.logs_paused {
                 state.update_logs(logging::capture_snapshot(LOG_SNAPSHOT_LIMIT));
             }
+            if matches!(state.screen, Screen::Peers) {
+                if let Some(runtime) = runtime.as_ref() {
+                    state.update_banned(runtime.header_peer_book.as_ref());
+                }
+            }
             if matches!(state.screen, Screen::Wallet)
                 && (now >= next_wallet_refresh || state.wallet_force_refresh)
             {
@@ -2604,7 +2691,19 @@ pub fn run_tui(
                                 })
                             })
                         };
-                        if handle_key(key, &mut state, &shutdown_tx, wallet_ops.as_ref())? {
+                        let peer_ops = PeerOps {
+                            registry: peer_registry.as_ref(),
+                            peer_book: runtime
+                                .as_ref()
+                                .map(|runtime| runtime.header_peer_book.as_ref()),
+                        };
+                        if handle_key(
+                            key,
+                            &mut state,
+                            &shutdown_tx,
+                            wallet_ops.as_ref(),
+                            &peer_ops,
+                        )? {
                             break;
                         }
                     }
@@ -2695,7 +2794,11 @@ pub fn run_remote_tui(endpoint: String) -> Result<(), String> {
             match event::read().map_err(|err| err.to_string())? {
                 Event::Key(key) => {
                     if key.kind == KeyEventKind::Press {
-                        if handle_key(key, &mut state, &shutdown_tx, None)? {
+                        let peer_ops = PeerOps {
+                            registry: &peer_registry,
+                            peer_book: None,
+                        };
+                        if handle_key(key, &mut state, &shutdown_tx, None, &peer_ops)? {
                             break;
                         }
                     }
@@ -3098,7 +3201,7 @@ fn collect_wallet_utxos(
                 continue;
             }
             let entry = chainstate
-                .utxo_entry(&outpoint)
+                .utxo_entry(&outpoint.txid(), outpoint.index)
                 .map_err(|err| err.to_string())?
                 .ok_or_else(|| "missing utxo entry".to_string())?;
             let height_i32 = i32::try_from(entry.height).unwrap_or(0);
@@ -3183,11 +3286,96 @@ fn wallet_send_unavailable(is_remote: bool) -> String {
     }
 }
 
+/// Peer-screen actions. `peer_book` is `None` until the node finishes starting and in remote
+/// attach mode, where the screen is read-only.
+struct PeerOps<'a> {
+    registry: &'a PeerRegistry,
+    peer_book: Option<&'a HeaderPeerBook>,
+}
+
+fn peer_ops_unavailable(is_remote: bool) -> String {
+    if is_remote {
+        "Remote attach mode: peer actions unavailable.".to_string()
+    } else {
+        "Peer actions unavailable until startup completes.".to_string()
+    }
+}
+
+fn selected_peer_addr(state: &TuiState, registry: &PeerRegistry) -> Option<SocketAddr> {
+    let rows = peer_rows(state, registry);
+    let row = rows.get(state.peers_selected.min(rows.len().checked_sub(1)?))?;
+    row.addr.parse().ok()
+}
+
+fn disconnect_selected_peer(state: &mut TuiState, ops: &PeerOps<'_>) {
+    if state.is_remote || ops.peer_book.is_none() {
+        state.peers_status = Some(peer_ops_unavailable(state.is_remote));
+        return;
+    }
+    let Some(addr) = selected_peer_addr(state, ops.registry) else {
+        state.peers_status = Some("No peer selected.".to_string());
+        return;
+    };
+    ops.registry.request_disconnect(addr);
+    state.peers_status = Some(format!("Disconnect requested for {addr}"));
+}
+
+/// Bans on the second press within `PEER_CONFIRM_WINDOW`, like `setban <addr> add`.
+fn ban_selected_peer(state: &mut TuiState, ops: &PeerOps<'_>) {
+    let Some(peer_book) = ops.peer_book.filter(|_| !state.is_remote) else {
+        state.peers_status = Some(peer_ops_unavailable(state.is_remote));
+        return;
+    };
+    let Some(addr) = selected_peer_addr(state, ops.registry) else {
+        state.peers_status = Some("No peer selected.".to_string());
+        return;
+    };
+    let bantime = peer_book.ban_policy().default_secs;
+    let now = Instant::now();
+    let confirmed = state.peers_ban_confirm.is_some_and(|(pending, since)| {
+        pending == addr && now.duration_since(since) <= PEER_CONFIRM_WINDOW
+    });
+    if !confirmed {
+        state.peers_ban_confirm = Some((addr, now));
+        state.peers_status = Some(format!(
+            "Press b again to ban {addr} for {}",
+            format_age(bantime)
+        ));
+        return;
+    }
+    state.peers_ban_confirm = None;
+    peer_book.ban_for(addr, bantime);
+    ops.registry.request_disconnect(addr);
+    state.update_banned(peer_book);
+    state.peers_status = Some(format!("Banned {addr} for {}", format_age(bantime)));
+}
+
+fn clear_peer_bans(state: &mut TuiState, ops: &PeerOps<'_>) {
+    let Some(peer_book) = ops.peer_book.filter(|_| !state.is_remote) else {
+        state.peers_status = Some(peer_ops_unavailable(state.is_remote));
+        return;
+    };
+    let now = Instant::now();
+    let confirmed = state
+        .peers_clear_confirm
+        .is_some_and(|since| now.duration_since(since) <= PEER_CONFIRM_WINDOW);
+    if !confirmed {
+        state.peers_clear_confirm = Some(now);
+        state.peers_status = Some("Press c again to clear all bans".to_string());
+        return;
+    }
+    state.peers_clear_confirm = None;
+    let removed = peer_book.clear_banned();
+    state.update_banned(peer_book);
+    state.peers_status = Some(format!("Cleared {removed} ban(s)"));
+}
+
 fn handle_key(
     key: KeyEvent,
     state: &mut TuiState,
     shutdown_tx: &watch::Sender<bool>,
     wallet_ops: Option<&InProcessWalletOps<'_>>,
+    peer_ops: &PeerOps<'_>,
 ) -> Result<bool, String> {
     if state.command_mode {
         let suggestions = command_suggestions(&state.command_input);
@@ -3933,6 +4121,14 @@ fn handle_key(
                 state.logs_scroll = 0;
                 state.logs_follow = true;
                 state.logs_paused = false;
+            } else if matches!(state.screen, Screen::Peers) {
+                clear_peer_bans(state, peer_ops);
+            }
+            Ok(false)
+        }
+        (KeyCode::Char('b'), _) => {
+            if matches!(state.screen, Screen::Peers) {
+                ban_selected_peer(state, peer_ops);
             }
             Ok(false)
         }
@@ -3961,7 +4157,9 @@ fn handle_key(
             Ok(false)
         }
         (KeyCode::Char('x'), _) => {
-            if matches!(state.screen, Screen::Wallet) {
+            if matches!(state.screen, Screen::Peers) {
+                disconnect_selected_peer(state, peer_ops);
+            } else if matches!(state.screen, Screen::Wallet) {
                 if state.is_remote {
                     state.wallet_status =
                         Some("Remote attach mode: wallet send unavailable.".to_string());
@@ -4046,7 +4244,8 @@ fn handle_key(
                 state.logs_follow = false;
                 state.logs_scroll = state.logs_scroll.saturating_sub(1);
             } else if matches!(state.screen, Screen::Peers) {
-                state.peers_scroll = state.peers_scroll.saturating_sub(PEER_SCROLL_STEP);
+                let count = peer_rows(state, peer_ops.registry).len();
+                state.peers_move_selection(-(PEER_SCROLL_STEP as isize), count);
             } else if matches!(state.screen, Screen::Wallet) {
                 state.wallet_move_selection(-1);
             }
@@ -4057,7 +4256,8 @@ fn handle_key(
                 state.logs_follow = false;
                 state.logs_scroll = state.logs_scroll.saturating_add(1);
             } else if matches!(state.screen, Screen::Peers) {
-                state.peers_scroll = state.peers_scroll.saturating_add(PEER_SCROLL_STEP);
+                let count = peer_rows(state, peer_ops.registry).len();
+                state.peers_move_selection(PEER_SCROLL_STEP as isize, count);
             } else if matches!(state.screen, Screen::Wallet) {
                 state.wallet_move_selection(1);
             }
@@ -4068,7 +4268,8 @@ fn handle_key(
                 state.logs_follow = false;
                 state.logs_scroll = state.logs_scroll.saturating_sub(10);
             } else if matches!(state.screen, Screen::Peers) {
-                state.peers_scroll = state.peers_scroll.saturating_sub(PEER_PAGE_STEP);
+                let count = peer_rows(state, peer_ops.registry).len();
+                state.peers_move_selection(-(PEER_PAGE_STEP as isize), count);
             } else if matches!(state.screen, Screen::Wallet) {
                 state.wallet_move_selection(-5);
             }
@@ -4079,7 +4280,8 @@ fn handle_key(
                 state.logs_follow = false;
                 state.logs_scroll = state.logs_scroll.saturating_add(10);
             } else if matches!(state.screen, Screen::Peers) {
-                state.peers_scroll = state.peers_scroll.saturating_add(PEER_PAGE_STEP);
+                let count = peer_rows(state, peer_ops.registry).len();
+                state.peers_move_selection(PEER_PAGE_STEP as isize, count);
             } else if matches!(state.screen, Screen::Wallet) {
                 state.wallet_move_selection(5);
             }
@@ -4091,7 +4293,7 @@ fn handle_key(
                 state.logs_paused = true;
                 state.logs_scroll = 0;
             } else if matches!(state.screen, Screen::Peers) {
-                state.peers_scroll = 0;
+                state.peers_selected = 0;
             } else if matches!(state.screen, Screen::Wallet) {
                 state.wallet_selected_address =
                     state.wallet_visible_indices().first().copied().unwrap_or(0);
@@ -4104,7 +4306,7 @@ fn handle_key(
                 state.logs_follow = true;
                 state.logs_scroll = 0;
             } else if matches!(state.screen, Screen::Peers) {
-                state.peers_scroll = u16::MAX;
+                state.peers_selected = peer_rows(state, peer_ops.registry).len().saturating_sub(1);
             } else if matches!(state.screen, Screen::Wallet) {
                 state.wallet_selected_address =
                     state.wallet_visible_indices().last().copied().unwrap_or(0);
@@ -4216,12 +4418,13 @@ fn handle_mouse(event: MouseEvent, state: &mut TuiState, area: Rect) -> Result<b
                 }
                 Screen::Peers => {
                     if rect_contains(main_area, event.column, event.row) {
+                        // Clamped against the peer count when drawn and on the next key press.
                         if scroll_up {
-                            state.peers_scroll =
-                                state.peers_scroll.saturating_sub(PEER_SCROLL_STEP);
+                            state.peers_selected =
+                                state.peers_selected.saturating_sub(PEER_SCROLL_STEP);
                         } else {
-                            state.peers_scroll =
-                                state.peers_scroll.saturating_add(PEER_SCROLL_STEP);
+                            state.peers_selected =
+                                state.peers_selected.saturating_add(PEER_SCROLL_STEP);
                         }
                     }
                 }
@@ -4374,7 +4577,7 @@ fn handle_mouse(event: MouseEvent, state: &mut TuiState, area: Rect) -> Result<b
                 let max_rows = tx_inner.height.saturating_sub(header_rows) as usize;
                 if row_index < max_rows && row_index < state.wallet_recent_txs.len() {
                     let entry = &state.wallet_recent_txs[row_index];
-                    let txid = stats::hash256_to_hex(&entry.txid);
+                    let txid = hash256_to_hex(&entry.txid);
                     let target = if link_click {
                         explorer_tx_url(&txid)
                     } else {
@@ -4848,9 +5051,12 @@ fn draw_help(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
         Line::raw("  n/p/l/[ / ] Network/profile/lead"),
         Line::raw(""),
         Line::raw("Peers view:"),
-        Line::raw("  Up/Down     Scroll"),
-        Line::raw("  PageUp/Down Page scroll"),
-        Line::raw("  Home/End    Top/Bottom"),
+        Line::raw("  Up/Down     Select peer"),
+        Line::raw("  PageUp/Down Page"),
+        Line::raw("  Home/End    First/Last"),
+        Line::raw("  x           Disconnect selected peer"),
+        Line::raw("  b           Ban selected peer (confirm)"),
+        Line::raw("  c           Clear all bans (confirm)"),
         Line::raw(""),
         Line::raw("Logs view:"),
         Line::raw("  f           Cycle level filter"),
@@ -5108,9 +5314,7 @@ fn draw_stats(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
 
     let snapshot = state.last_snapshot.as_ref();
     let format_supply = |value: Option<i64>| -> String {
-        value
-            .map(|amount| crate::format_amount(amount as i128))
-            .unwrap_or_else(|| "-".to_string())
+        value.map(money::format).unwrap_or_else(|| "-".to_string())
     };
 
     let (total, transparent, shielded, sprout, sapling) = if let Some(snapshot) = snapshot {
@@ -5567,60 +5771,40 @@ fn draw_monitor(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
     );
 }
 
-fn draw_peers(
-    frame: &mut ratatui::Frame<'_>,
-    state: &TuiState,
-    peer_registry: &PeerRegistry,
-    net_totals: &NetTotals,
-    area: Rect,
-) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(10)])
-        .split(area);
-
-    #[derive(Clone, Debug)]
-    struct PeerRow {
-        kind_sort: u8,
-        kind: String,
-        inbound: bool,
-        addr: String,
-        start_height: i32,
-        version: i32,
-        user_agent: String,
-    }
-
-    let kind_sort = |label: &str| match label {
+fn remote_peer_kind_sort_key(label: &str) -> u8 {
+    match label {
         "block" => 0,
         "header" => 1,
         "relay" => 2,
         _ => 3,
-    };
+    }
+}
 
-    let (_bytes_recv, _bytes_sent, _connections, mut peers) = if state.is_remote {
-        let totals = state.remote_net_totals.as_ref();
-        let peers = state
+/// Connected peers in display order: by kind, outbound first, then address.
+fn peer_rows(state: &TuiState, peer_registry: &PeerRegistry) -> Vec<PeerRow> {
+    let mut peers = if state.is_remote {
+        state
             .remote_peers
             .iter()
             .map(|peer| PeerRow {
-                kind_sort: kind_sort(&peer.kind),
+                kind_sort: remote_peer_kind_sort_key(&peer.kind),
                 kind: peer.kind.clone(),
                 inbound: peer.inbound,
                 addr: peer.addr.clone(),
                 start_height: peer.start_height,
                 version: peer.version,
                 user_agent: peer.user_agent.clone(),
+                services: None,
+                connected_secs: None,
+                last_ping_ms: peer.last_ping_ms,
+                min_ping_ms: peer.min_ping_ms,
+                bytes_sent: peer.bytes_sent,
+                bytes_recv: peer.bytes_recv,
             })
-            .collect::<Vec<_>>();
-        (
-            totals.map(|totals| totals.bytes_recv),
-            totals.map(|totals| totals.bytes_sent),
-            totals.map(|totals| totals.connections),
-            peers,
-        )
+            .collect::<Vec<_>>()
     } else {
-        let totals = net_totals.snapshot();
-        let peers = peer_registry
+        let now = std::time::SystemTime::now();
+        peer_registry
             .snapshot()
             .into_iter()
             .map(|peer| PeerRow {
@@ -5631,14 +5815,18 @@ fn draw_peers(
                 start_height: peer.start_height,
                 version: peer.version,
                 user_agent: peer.user_agent,
+                services: Some(peer.services),
+                connected_secs: Some(
+                    now.duration_since(peer.connected_since)
+                        .unwrap_or_default()
+                        .as_secs(),
+                ),
+                last_ping_ms: peer.last_ping_ms,
+                min_ping_ms: peer.min_ping_ms,
+                bytes_sent: Some(peer.bytes_sent),
+                bytes_recv: Some(peer.bytes_recv),
             })
-            .collect::<Vec<_>>();
-        (
-            Some(totals.bytes_recv),
-            Some(totals.bytes_sent),
-            Some(totals.connections),
-            peers,
-        )
+            .collect::<Vec<_>>()
     };
 
     peers.sort_by(|a, b| {
@@ -5647,8 +5835,77 @@ fn draw_peers(
             .then_with(|| a.inbound.cmp(&b.inbound))
             .then_with(|| a.addr.cmp(&b.addr))
     });
+    peers
+}
+
+fn fmt_opt_ping(value: Option<u64>) -> String {
+    value
+        .map(|ms| format!("{ms}ms"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn fmt_opt_bytes(value: Option<u64>) -> String {
+    let Some(bytes) = value else {
+        return "-".to_string();
+    };
+    const KIB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f < KIB {
+        format!("{bytes} B")
+    } else if bytes_f < KIB * KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else if bytes_f < KIB * KIB * KIB {
+        format!("{:.1} MiB", bytes_f / (KIB * KIB))
+    } else {
+        format!("{:.2} GiB", bytes_f / (KIB * KIB * KIB))
+    }
+}
 
-    let block = panel_block("Peer list");
+fn draw_peers(
+    frame: &mut ratatui::Frame<'_>,
+    state: &TuiState,
+    peer_registry: &PeerRegistry,
+    net_totals: &NetTotals,
+    area: Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(8), Constraint::Length(10)])
+        .split(area);
+    let lower = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+
+    let peers = peer_rows(state, peer_registry);
+    let selected = state.peers_selected.min(peers.len().saturating_sub(1));
+
+    draw_peer_detail(frame, state, peers.get(selected), lower[0]);
+    draw_banned_peers(frame, state, lower[1]);
+
+    let (bytes_recv, bytes_sent, connections) = if state.is_remote {
+        let totals = state.remote_net_totals.as_ref();
+        (
+            totals.map(|totals| totals.bytes_recv),
+            totals.map(|totals| totals.bytes_sent),
+            totals.map(|totals| totals.connections),
+        )
+    } else {
+        let totals = net_totals.snapshot();
+        (
+            Some(totals.bytes_recv),
+            Some(totals.bytes_sent),
+            Some(totals.connections),
+        )
+    };
+    let connections = connections
+        .map(|count| count.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let block = panel_block(format!(
+        "Peer list ({connections} connections)  recv {}  sent {}",
+        fmt_opt_bytes(bytes_recv),
+        fmt_opt_bytes(bytes_sent)
+    ));
     let inner = block.inner(chunks[0]);
     frame.render_widget(block, chunks[0]);
 
@@ -5666,8 +5923,7 @@ fn draw_peers(
     let header_overhead = 2u16;
     let max_rows = table_area.height.saturating_sub(header_overhead).max(1) as usize;
     let peer_count = peers.len();
-    let max_scroll = peer_count.saturating_sub(max_rows);
-    let scroll = (state.peers_scroll as usize).min(max_scroll);
+    let scroll = (selected + 1).saturating_sub(max_rows);
 
     if peers.is_empty() {
         let mut lines = vec![
@@ -5688,23 +5944,39 @@ fn draw_peers(
         Cell::from("addr"),
         Cell::from("height"),
         Cell::from("ver"),
+        Cell::from("ping"),
+        Cell::from("sent"),
+        Cell::from("recv"),
         Cell::from("ua"),
     ])
     .style(style_title())
     .bottom_margin(1);
 
-    let table_rows = peers.into_iter().skip(scroll).take(max_rows).map(|peer| {
-        let dir = if peer.inbound { "in" } else { "out" };
-        let ua = shorten(&peer.user_agent, 32);
-        Row::new(vec![
-            Cell::from(peer.kind),
-            Cell::from(dir),
-            Cell::from(peer.addr),
-            Cell::from(peer.start_height.to_string()),
-            Cell::from(peer.version.to_string()),
-            Cell::from(ua),
-        ])
-    });
+    let table_rows = peers
+        .into_iter()
+        .enumerate()
+        .skip(scroll)
+        .take(max_rows)
+        .map(|(index, peer)| {
+            let dir = if peer.inbound { "in" } else { "out" };
+            let ua = shorten(&peer.user_agent, 32);
+            let row = Row::new(vec![
+                Cell::from(peer.kind),
+                Cell::from(dir),
+                Cell::from(peer.addr),
+                Cell::from(peer.start_height.to_string()),
+                Cell::from(peer.version.to_string()),
+                Cell::from(fmt_opt_ping(peer.last_ping_ms)),
+                Cell::from(fmt_opt_bytes(peer.bytes_sent)),
+                Cell::from(fmt_opt_bytes(peer.bytes_recv)),
+                Cell::from(ua),
+            ]);
+            if index == selected {
+                row.style(style_command())
+            } else {
+                row
+            }
+        });
 
     let widths = [
         Constraint::Length(6),
@@ -5712,6 +5984,9 @@ fn draw_peers(
         Constraint::Length(22),
         Constraint::Length(8),
         Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(10),
+        Constraint::Length(10),
         Constraint::Min(10),
     ];
     let table = Table::new(table_rows, widths)
@@ -5722,6 +5997,130 @@ fn draw_peers(
     render_vertical_scrollbar(frame, scrollbar_area, peer_count, scroll, max_rows);
 }
 
+fn draw_peer_detail(
+    frame: &mut ratatui::Frame<'_>,
+    state: &TuiState,
+    peer: Option<&PeerRow>,
+    area: Rect,
+) {
+    let mut lines = Vec::new();
+    match peer {
+        Some(peer) => {
+            let dir = if peer.inbound { "inbound" } else { "outbound" };
+            lines.push(Line::from(vec![
+                Span::styled("Peer:", style_muted()),
+                Span::raw(format!(" {}  ({} {dir})", peer.addr, peer.kind)),
+            ]));
+            lines.push(Line::from(vec![
+                Span::styled("Agent:", style_muted()),
+                Span::raw(format!(
+                    " {}  v{}",
+                    shorten(&peer.user_agent, 40),
+                    peer.version
+                )),
+            ]));
+            let services = peer
+                .services
+                .map(|services| format!("{services:016x}"))
+                .unwrap_or_else(|| "-".to_string());
+            lines.push(Line::from(vec![
+                Span::styled("Height:", style_muted()),
+                Span::raw(format!(" {}", peer.start_height)),
+                Span::raw("  "),
+                Span::styled("Services:", style_muted()),
+                Span::raw(format!(" {services}")),
+            ]));
+            let connected = peer
+                .connected_secs
+                .map(format_hms)
+                .unwrap_or_else(|| "-".to_string());
+            lines.push(Line::from(vec![
+                Span::styled("Connected:", style_muted()),
+                Span::raw(format!(" {connected}")),
+                Span::raw("  "),
+                Span::styled("Ping:", style_muted()),
+                Span::raw(format!(
+                    " {} (min {})",
+                    fmt_opt_ping(peer.last_ping_ms),
+                    fmt_opt_ping(peer.min_ping_ms)
+                )),
+            ]));
+            lines.push(Line::from(vec![
+                Span::styled("Sent:", style_muted()),
+                Span::raw(format!(" {}", fmt_opt_bytes(peer.bytes_sent))),
+                Span::raw("  "),
+                Span::styled("Recv:", style_muted()),
+                Span::raw(format!(" {}", fmt_opt_bytes(peer.bytes_recv))),
+            ]));
+        }
+        None => lines.push(Line::from(vec![Span::styled(
+            "No peer selected.",
+            style_muted(),
+        )])),
+    }
+    lines.push(Line::raw(""));
+    if let Some(status) = state.peers_status.as_ref() {
+        lines.push(Line::from(vec![Span::styled(status.clone(), style_warn())]));
+    } else if state.is_remote {
+        lines.push(Line::from(vec![Span::styled(
+            "Remote attach mode: read-only.",
+            style_muted(),
+        )]));
+    } else {
+        lines.push(Line::from(vec![
+            Span::styled("x", style_key()),
+            Span::raw(" disconnect  "),
+            Span::styled("b", style_key()),
+            Span::raw(" ban  "),
+            Span::styled("c", style_key()),
+            Span::raw(" clear bans"),
+        ]));
+    }
+
+    let widget = Paragraph::new(lines)
+        .block(panel_block("Selected peer"))
+        .style(style_panel());
+    frame.render_widget(widget, area);
+}
+
+fn draw_banned_peers(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
+    let mut lines = Vec::new();
+    match state.peers_banned.as_ref() {
+        None => lines.push(Line::from(vec![Span::styled(
+            if state.is_remote {
+                "Unavailable in remote attach mode."
+            } else {
+                "Loading..."
+            },
+            style_muted(),
+        )])),
+        Some(banned) if banned.is_empty() => lines.push(Line::from(vec![Span::styled(
+            "No active bans.",
+            style_muted(),
+        )])),
+        Some(banned) => {
+            let max_lines = area.height.saturating_sub(2).max(1) as usize;
+            for entry in banned.iter().take(max_lines) {
+                lines.push(Line::from(vec![
+                    Span::raw(shorten(&entry.target, 28)),
+                    Span::styled(
+                        format!("  {}", format_age(entry.remaining_secs)),
+                        style_muted(),
+                    ),
+                ]));
+            }
+        }
+    }
+    let title = match state.peers_banned.as_ref() {
+        Some(banned) => format!("Banned ({})", banned.len()),
+        None => "Banned".to_string(),
+    };
+    let widget = Paragraph::new(lines)
+        .block(panel_block(title))
+        .style(style_panel());
+    frame.render_widget(widget, area);
+}
+
 fn draw_db(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -6192,7 +6591,7 @@ fn draw_wallet(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
     let txid_width = tx_inner.width.saturating_sub(12).max(12) as usize;
     let tx_rows = state.wallet_recent_txs.iter().map(|entry| {
         let age = unix_seconds().saturating_sub(entry.received_at);
-        let txid = stats::hash256_to_hex(&entry.txid);
+        let txid = hash256_to_hex(&entry.txid);
         Row::new(vec![
             Cell::from(format_age(age)),
             Cell::from(shorten(&txid, txid_width)),
@@ -6326,7 +6725,7 @@ fn draw_wallet(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
                         | WalletAddressKind::TransparentWatch
                 ) && (balance_total != 0 || selected);
                 let balance = if show_balance {
-                    format!(" {}", crate::format_amount(balance_total as i128))
+                    format!(" {}", money::format(balance_total))
                 } else {
                     String::new()
                 };
@@ -6408,9 +6807,9 @@ fn draw_wallet(frame: &mut ratatui::Frame<'_>, state: &TuiState, area: Rect) {
                 Span::styled("Balance:", style_muted()),
                 Span::raw(format!(
                     " confirmed {}  unconf {}  immature {}",
-                    crate::format_amount(bal.confirmed as i128),
-                    crate::format_amount(bal.unconfirmed as i128),
-                    crate::format_amount(bal.immature as i128),
+                    money::format(bal.confirmed),
+                    money::format(bal.unconfirmed),
+                    money::format(bal.immature),
                 )),
             ]));
         }
@@ -6877,6 +7276,7 @@ fn peer_kind_sort_key(kind: PeerKind) -> u8 {
         PeerKind::Block => 0,
         PeerKind::Header => 1,
         PeerKind::Relay => 2,
+        PeerKind::BlockRelayOnly => 3,
     }
 }
 
@@ -6885,6 +7285,7 @@ fn peer_kind_label(kind: PeerKind) -> &'static str {
         PeerKind::Block => "block",
         PeerKind::Header => "header",
         PeerKind::Relay => "relay",
+        PeerKind::BlockRelayOnly => "block-relay-only",
     }
 }
 
@@ -7027,9 +7428,7 @@ fn compute_mempool_detail(mempool: &Mutex<Mempool>) -> Option<RemoteMempoolSumma
 }
 
 fn fmt_opt_amount(value: Option<i64>) -> String {
-    value
-        .map(|value| crate::format_amount(value as i128))
-        .unwrap_or_else(|| "-".to_string())
+    value.map(money::format).unwrap_or_else(|| "-".to_string())
 }
 
 fn format_age(secs: u64) -> String {
@@ -7142,6 +7541,55 @@ mod tests {
         let _ = fs::remove_dir_all(&root);
     }
 
+    #[test]
+    fn peers_screen_disconnects_and_bans_selected_peer() {
+        let registry = PeerRegistry::default();
+        let first: SocketAddr = "10.0.0.1:16125".parse().expect("addr");
+        let second: SocketAddr = "10.0.0.2:16125".parse().expect("addr");
+        registry.register(second, PeerKind::Block);
+        registry.register(first, PeerKind::Block);
+        let peer_book = HeaderPeerBook::default();
+        let ops = PeerOps {
+            registry: &registry,
+            peer_book: Some(&peer_book),
+        };
+        let (shutdown_tx, _shutdown_rx) = watch::channel(false);
+        let mut state = TuiState::new();
+        state.screen = Screen::Peers;
+        let press = |state: &mut TuiState, code: KeyCode| {
+            let key = KeyEvent::new(code, KeyModifiers::NONE);
+            handle_key(key, state, &shutdown_tx, None, &ops).expect("handle key")
+        };
+
+        press(&mut state, KeyCode::Down);
+        press(&mut state, KeyCode::Down);
+        assert_eq!(state.peers_selected, 1);
+        press(&mut state, KeyCode::Char('x'));
+        assert!(registry.take_disconnect_request(second));
+        assert!(!registry.take_disconnect_request(first));
+
+        press(&mut state, KeyCode::Home);
+        press(&mut state, KeyCode::Char('b'));
+        assert!(!peer_book.is_banned(first));
+        press(&mut state, KeyCode::Char('b'));
+        assert!(peer_book.is_banned(first));
+        assert!(registry.take_disconnect_request(first));
+        assert_eq!(state.peers_banned.as_ref().map(Vec::len), Some(1));
+
+        press(&mut state, KeyCode::Char('c'));
+        press(&mut state, KeyCode::Char('c'));
+        assert!(!peer_book.is_banned(first));
+
+        let mut remote = TuiState::new();
+        remote.screen = Screen::Peers;
+        remote.is_remote = true;
+        press(&mut remote, KeyCode::Char('x'));
+        assert_eq!(
+            remote.peers_status.as_deref(),
+            Some("Remote attach mode: peer actions unavailable.")
+        );
+    }
+
     #[test]
     fn monitor_resample_window_max_maps_full_six_minutes() {
         let width = 180;
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -6,19 +6,124 @@ use fluxd_chainstate::state::ChainState;
 use fluxd_chainstate::validation::ValidationFlags;
 use fluxd_consensus::params::ChainParams;
 use fluxd_consensus::Hash256;
+use fluxd_primitives::hash::hash256_to_hex;
 use fluxd_primitives::transaction::Transaction;
 use fluxd_storage::KeyValueStore;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, oneshot};
 use tokio::task::JoinSet;
 
 use crate::mempool;
-use crate::p2p::{parse_feefilter, parse_inv, parse_reject, InventoryVector, Peer, MSG_TX};
+use crate::p2p::{
+    parse_feefilter, parse_inv, parse_reject, InventoryVector, Peer, RejectMessage, MSG_TX,
+};
 use crate::stats::MempoolMetrics;
 
 const TX_GETDATA_BATCH: usize = 128;
 const TX_KNOWN_CAP: usize = 50_000;
 const TX_RECONNECT_DELAY_SECS: u64 = 3;
 const TX_REJECT_LOG_INTERVAL_SECS: u64 = 60;
+pub const TX_FEEDBACK_CAP: usize = 1_024;
+const REJECT_DUPLICATE: u8 = 0x12;
+
+/// What a peer answered about a transaction this node submitted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TxFeedback {
+    Rejected { code: u8, reason: String },
+    NotFound,
+}
+
+struct FeedbackWaiter {
+    sender: oneshot::Sender<TxFeedback>,
+    expires: Instant,
+}
+
+/// Submitters waiting to hear whether peers refused a transaction we broadcast, keyed by
+/// txid. Relay and inbound peers resolve entries from `reject`/`notfound`; entries that nobody
+/// answers expire after `wait`. The set holds at most `capacity` waiters, and a submission that
+/// finds it full is not tracked.
+pub struct TxFeedbackWaiters {
+    waiters: Mutex<HashMap<Hash256, FeedbackWaiter>>,
+    capacity: usize,
+    wait: Duration,
+}
+
+impl TxFeedbackWaiters {
+    pub fn new(capacity: usize, wait: Duration) -> Self {
+        Self {
+            waiters: Mutex::new(HashMap::new()),
+            capacity,
+            wait,
+        }
+    }
+
+    pub fn wait(&self) -> Duration {
+        self.wait
+    }
+
+    /// Returns `None` when waiting is disabled or the set is full.
+    pub fn register(&self, txid: Hash256) -> Option<oneshot::Receiver<TxFeedback>> {
+        if self.wait.is_zero() || self.capacity == 0 {
+            return None;
+        }
+        let now = Instant::now();
+        let mut waiters = self.waiters.lock().ok()?;
+        if waiters.len() >= self.capacity {
+            waiters.retain(|_, waiter| waiter.expires > now && !waiter.sender.is_closed());
+            if waiters.len() >= self.capacity {
+                return None;
+            }
+        }
+        let (sender, receiver) = oneshot::channel();
+        waiters.insert(
+            txid,
+            FeedbackWaiter {
+                sender,
+                expires: now + self.wait,
+            },
+        );
+        Some(receiver)
+    }
+
+    pub fn forget(&self, txid: &Hash256) {
+        if let Ok(mut waiters) = self.waiters.lock() {
+            waiters.remove(txid);
+        }
+    }
+
+    fn resolve(&self, txid: &Hash256, feedback: TxFeedback) -> bool {
+        let waiter = match self.waiters.lock() {
+            Ok(mut waiters) => waiters.remove(txid),
+            Err(_) => return false,
+        };
+        match waiter {
+            Some(waiter) => waiter.sender.send(feedback).is_ok(),
+            None => false,
+        }
+    }
+
+    /// A peer that already has the transaction answers `duplicate`; that is not a refusal.
+    pub fn note_reject(&self, reject: &RejectMessage) -> bool {
+        if reject.message != "tx" || reject.code == REJECT_DUPLICATE {
+            return false;
+        }
+        let Some(txid) = reject.data else {
+            return false;
+        };
+        self.resolve(
+            &txid,
+            TxFeedback::Rejected {
+                code: reject.code,
+                reason: reject.reason.clone(),
+            },
+        )
+    }
+
+    pub fn note_notfound(&self, txids: &[Hash256]) {
+        for txid in txids {
+            self.resolve(txid, TxFeedback::NotFound);
+        }
+    }
+}
 
 pub async fn tx_relay_loop<S: KeyValueStore + 'static>(
     chainstate: Arc<ChainState<S>>,
@@ -31,6 +136,7 @@ pub async fn tx_relay_loop<S: KeyValueStore + 'static>(
     fee_estimator: Arc<Mutex<crate::fee_estimator::FeeEstimator>>,
     flags: ValidationFlags,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
     peer_target: usize,
 ) -> Result<(), String> {
     if peer_target == 0 {
@@ -68,6 +174,7 @@ pub async fn tx_relay_loop<S: KeyValueStore + 'static>(
                         let fee_estimator = Arc::clone(&fee_estimator);
                         let flags = flags.clone();
                         let tx_announce = tx_announce.clone();
+                        let tx_feedback = Arc::clone(&tx_feedback);
                         join_set.spawn(async move {
                             let addr = peer.addr();
                             let result = tx_relay_peer(
@@ -80,6 +187,7 @@ pub async fn tx_relay_loop<S: KeyValueStore + 'static>(
                                 fee_estimator,
                                 flags,
                                 tx_announce,
+                                tx_feedback,
                             )
                             .await;
                             if let Err(err) = &result {
@@ -119,6 +227,7 @@ async fn tx_relay_peer<S: KeyValueStore>(
     fee_estimator: Arc<Mutex<crate::fee_estimator::FeeEstimator>>,
     flags: ValidationFlags,
     tx_announce: broadcast::Sender<Hash256>,
+    tx_feedback: Arc<TxFeedbackWaiters>,
 ) -> Result<(), String> {
     let mut announce_rx = tx_announce.subscribe();
     let mut known: HashSet<Hash256> = HashSet::new();
@@ -126,9 +235,10 @@ async fn tx_relay_peer<S: KeyValueStore>(
     let mut reject_stats = TxRejectStats::new();
     let mut peer_fee_filter_per_kb: i64 = 0;
 
-    let _ = peer
-        .send_feefilter(mempool_policy.min_relay_fee_per_kb)
-        .await;
+    let mut fee_filter = mempool::FeeFilterAnnouncer::default();
+    if let Some(filter) = fee_filter.due(mempool.as_ref(), mempool_policy.as_ref()) {
+        let _ = peer.send_feefilter(filter).await;
+    }
 
     let _ = peer.send_mempool().await;
 
@@ -138,6 +248,11 @@ async fn tx_relay_peer<S: KeyValueStore>(
             log_info!("Disconnect requested for tx relay peer {addr}");
             return Ok(());
         }
+        peer.maybe_send_ping().await?;
+        if let Some(filter) = fee_filter.due(mempool.as_ref(), mempool_policy.as_ref()) {
+            let _ = peer.send_feefilter(filter).await;
+        }
+        let ping_at = peer.ping_deadline();
         tokio::select! {
             msg = peer.read_message() => {
                 let (command, payload) = msg?;
@@ -153,6 +268,7 @@ async fn tx_relay_peer<S: KeyValueStore>(
                     fee_estimator.as_ref(),
                     &flags,
                     &tx_announce,
+                    tx_feedback.as_ref(),
                     &mut known,
                     &mut requested,
                     &mut reject_stats,
@@ -175,6 +291,7 @@ async fn tx_relay_peer<S: KeyValueStore>(
                     Err(broadcast::error::RecvError::Lagged(_)) => continue,
                 }
             }
+            _ = tokio::time::sleep_until(ping_at) => {}
         }
     }
 }
@@ -191,6 +308,7 @@ async fn handle_peer_message<S: KeyValueStore>(
     fee_estimator: &Mutex<crate::fee_estimator::FeeEstimator>,
     flags: &ValidationFlags,
     tx_announce: &broadcast::Sender<Hash256>,
+    tx_feedback: &TxFeedbackWaiters,
     known: &mut HashSet<Hash256>,
     requested: &mut HashSet<Hash256>,
     reject_stats: &mut TxRejectStats,
@@ -257,6 +375,7 @@ async fn handle_peer_message<S: KeyValueStore>(
                                 payload.to_vec(),
                                 err.missing_inputs.clone(),
                                 true,
+                                Some(peer.addr()),
                             );
                         }
                         let _ = touch_known(known, txid);
@@ -282,11 +401,7 @@ async fn handle_peer_message<S: KeyValueStore>(
                         return Ok(());
                     }
                     if err.kind == mempool::MempoolErrorKind::Internal {
-                        log_warn!(
-                            "mempool reject {}: {}",
-                            crate::stats::hash256_to_hex(&txid),
-                            err
-                        );
+                        log_warn!("mempool reject {}: {}", hash256_to_hex(&txid), err);
                     }
                     reject_stats.note_build_error(err.kind);
                     reject_stats.maybe_log(peer.addr());
@@ -326,7 +441,7 @@ async fn handle_peer_message<S: KeyValueStore>(
                             if err.kind == mempool::MempoolErrorKind::Internal {
                                 log_warn!(
                                     "mempool insert failed {}: {}",
-                                    crate::stats::hash256_to_hex(&txid),
+                                    hash256_to_hex(&txid),
                                     err
                                 );
                             }
@@ -418,6 +533,7 @@ async fn handle_peer_message<S: KeyValueStore>(
                 return Ok(());
             }
             let count = txids.len() as u64;
+            tx_feedback.note_notfound(&txids);
             for txid in txids {
                 let _ = requested.remove(&txid);
             }
@@ -431,11 +547,19 @@ async fn handle_peer_message<S: KeyValueStore>(
                         let _ = requested.remove(&txid);
                     }
                 }
+                if tx_feedback.note_reject(&reject) {
+                    log_info!(
+                        "Peer {} rejected submitted tx: {}",
+                        peer.addr(),
+                        reject.reason
+                    );
+                }
                 reject_stats.note_peer_reject();
                 reject_stats.maybe_log(peer.addr());
             }
         }
         "ping" => peer.send_message("pong", payload).await?,
+        "pong" => peer.handle_pong(payload),
         "version" => peer.send_message("verack", &[]).await?,
         _ => {}
     }
@@ -653,3 +777,61 @@ impl TxRejectStats {
         self.insert_other = 0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fluxd_primitives::encoding::Encoder;
+
+    use super::*;
+
+    fn reject_payload(message: &str, code: u8, reason: &str, txid: &Hash256) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        encoder.write_var_str(message);
+        encoder.write_u8(code);
+        encoder.write_var_str(reason);
+        encoder.write_hash_le(txid);
+        encoder.into_inner()
+    }
+
+    #[test]
+    fn reject_for_broadcast_txid_reaches_submitter() {
+        let waiters = TxFeedbackWaiters::new(2, Duration::from_secs(5));
+        let txid = [0x11; 32];
+        let other = [0x22; 32];
+        let mut receiver = waiters.register(txid).expect("register");
+        let _other_receiver = waiters.register(other).expect("register");
+        assert!(waiters.register([0x33; 32]).is_none(), "set is bounded");
+
+        // A duplicate answer or a reject for another message type does not settle the wait.
+        let duplicate = parse_reject(&reject_payload(
+            "tx",
+            REJECT_DUPLICATE,
+            "txn-already-known",
+            &txid,
+        ))
+        .expect("parse");
+        assert!(!waiters.note_reject(&duplicate));
+        let block = parse_reject(&reject_payload("block", 0x10, "bad-blk", &txid)).expect("parse");
+        assert!(!waiters.note_reject(&block));
+        assert!(receiver.try_recv().is_err());
+
+        let reject =
+            parse_reject(&reject_payload("tx", 0x42, "insufficient fee", &txid)).expect("parse");
+        assert!(waiters.note_reject(&reject));
+        assert_eq!(
+            receiver.try_recv().expect("feedback"),
+            TxFeedback::Rejected {
+                code: 0x42,
+                reason: "insufficient fee".to_string(),
+            }
+        );
+        assert_eq!(waiters.waiters.lock().expect("waiters").len(), 1);
+        assert!(!waiters.note_reject(&reject), "already resolved");
+
+        waiters.forget(&other);
+        assert_eq!(waiters.waiters.lock().expect("waiters").len(), 0);
+        assert!(TxFeedbackWaiters::new(8, Duration::ZERO)
+            .register(txid)
+            .is_none());
+    }
+}
@@ -2,11 +2,10 @@ use fluxd_chainstate::state::ChainState;
 use fluxd_consensus::money::money_range;
 use fluxd_consensus::Hash256;
 use fluxd_primitives::block::Block;
-use fluxd_primitives::hash::sha256d;
+use fluxd_primitives::hash::{hash256_to_hex, sha256d};
+use fluxd_primitives::ids::{BlockHash, Txid};
 use fluxd_primitives::outpoint::OutPoint;
 
-use crate::stats::hash256_to_hex;
-
 fn p2pkh_script_pubkey(pubkey_hash: &[u8; 20]) -> Vec<u8> {
     let mut out = Vec::with_capacity(25);
     out.extend_from_slice(&[0x76, 0xa9, 0x14]);
@@ -58,7 +57,7 @@ pub(crate) fn verify_chain<S: fluxd_storage::KeyValueStore>(
         let main_hash = chainstate
             .height_hash(entry.height)
             .map_err(|err| err.to_string())?;
-        if main_hash.as_ref() != Some(&current_hash) {
+        if main_hash != Some(BlockHash::from_internal(current_hash)) {
             return Err(format!("height index mismatch at {}", entry.height.max(0)));
         }
 
@@ -100,7 +99,7 @@ pub(crate) fn verify_chain<S: fluxd_storage::KeyValueStore>(
                 if checklevel >= 3 {
                     for (index, txid) in txids.iter().enumerate() {
                         let tx_location = chainstate
-                            .tx_location(&txid)
+                            .tx_location(&Txid::from_internal(*txid))
                             .map_err(|err| err.to_string())?
                             .ok_or_else(|| {
                                 format!("missing txindex entry {}", hash256_to_hex(&txid))
@@ -523,6 +522,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -548,6 +548,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -678,6 +679,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
@@ -703,6 +705,7 @@ mod tests {
             join_split_pub_key: [0u8; 32],
             join_split_sig: [0u8; 64],
             binding_sig: [0u8; 64],
+            raw_tail: Vec::new(),
             fluxnode: None,
         };
 
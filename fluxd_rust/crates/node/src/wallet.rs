@@ -34,7 +34,8 @@ use fluxd_consensus::params::Network;
 use fluxd_consensus::Hash256;
 use fluxd_primitives::block::Block;
 use fluxd_primitives::encoding::{DecodeError, Decoder, Encoder};
-use fluxd_primitives::hash::hash160;
+use fluxd_primitives::hash::{ct_eq, hash160};
+use fluxd_primitives::ids::BlockHash;
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::{script_pubkey_to_address, secret_key_to_wif, wif_to_secret_key};
 use fluxd_script::message::signed_message_hash;
@@ -232,7 +233,7 @@ impl WalletKey {
         }
         PublicKey::from_slice(&self.pubkey_bytes)
             .map_err(|_| WalletError::InvalidData("wallet contains invalid pubkey bytes"))?;
-        if hash160(&self.pubkey_bytes) != self.key_hash {
+        if !ct_eq(&hash160(&self.pubkey_bytes), &self.key_hash) {
             return Err(WalletError::InvalidData("wallet pubkey hash mismatch"));
         }
         Ok(())
@@ -1369,7 +1370,7 @@ impl Wallet {
             let best_hash = chainstate
                 .height_hash(self.sapling_scan_height)
                 .map_err(|err| WalletError::ChainState(err.to_string()))?;
-            if best_hash != Some(self.sapling_scan_hash) {
+            if best_hash != Some(BlockHash::from_internal(self.sapling_scan_hash)) {
                 needs_full_rescan = true;
             }
         }
@@ -1388,6 +1389,7 @@ impl Wallet {
             let hash = chainstate
                 .height_hash(height)
                 .map_err(|err| WalletError::ChainState(err.to_string()))?
+                .map(BlockHash::to_internal)
                 .ok_or(WalletError::InvalidData(
                     "missing block hash for wallet scan",
                 ))?;
@@ -3104,7 +3106,9 @@ fn decode_small_int_opcode(opcode: u8) -> Option<usize> {
     }
 }
 
-fn parse_multisig_redeem_script_with_required(script: &[u8]) -> Option<(usize, Vec<PublicKey>)> {
+pub(crate) fn parse_multisig_redeem_script_with_required(
+    script: &[u8],
+) -> Option<(usize, Vec<PublicKey>)> {
     const OP_CHECKMULTISIG: u8 = 0xae;
     const OP_PUSHDATA1: u8 = 0x4c;
     const OP_PUSHDATA2: u8 = 0x4d;
@@ -3,10 +3,11 @@
 use std::collections::HashSet;
 use std::sync::OnceLock;
 
-use fluxd_consensus::params::{hash256_from_hex, ConsensusParams, Network};
+use fluxd_consensus::params::{ConsensusParams, Network};
 use fluxd_consensus::upgrades::UpgradeIndex;
 use fluxd_primitives::block::BlockHeader;
 use fluxd_primitives::encoding::Decoder;
+use fluxd_primitives::hash::hash256_from_hex;
 use fluxd_primitives::outpoint::OutPoint;
 use primitive_types::U256;
 use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1, VerifyOnly};
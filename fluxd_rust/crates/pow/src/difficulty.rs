@@ -4,6 +4,7 @@ use std::cmp::Ordering;
 
 use fluxd_consensus::upgrades::UpgradeIndex;
 use fluxd_consensus::{ConsensusParams, Hash256};
+use fluxd_primitives::block::BlockHeader;
 use primitive_types::U256;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -137,6 +138,35 @@ pub fn cmp_be(a: &Hash256, b: &Hash256) -> Ordering {
     left.cmp(&right)
 }
 
+/// Estimates network hashes per second from the last `window` proof-of-work headers in
+/// `headers` (oldest first): the work they add divided by the time they span.
+///
+/// PoN headers carry no hashing work and are skipped. Returns 0.0 when fewer than two PoW
+/// headers are available or their timestamps do not advance.
+pub fn network_hashrate(headers: &[BlockHeader], window: usize) -> f64 {
+    let pow_headers: Vec<&BlockHeader> = headers.iter().filter(|header| !header.is_pon()).collect();
+    let start = pow_headers.len().saturating_sub(window.saturating_add(1));
+    let pow_headers = &pow_headers[start..];
+    if pow_headers.len() < 2 {
+        return 0.0;
+    }
+
+    let (min_time, max_time) = pow_headers
+        .iter()
+        .fold((u32::MAX, 0), |(min, max), header| {
+            (min.min(header.time), max.max(header.time))
+        });
+    if max_time <= min_time {
+        return 0.0;
+    }
+
+    let work = pow_headers[1..]
+        .iter()
+        .filter_map(|header| block_proof(header.bits).ok())
+        .fold(U256::zero(), |acc, proof| acc.saturating_add(proof));
+    u256_to_f64(work) / f64::from(max_time - min_time)
+}
+
 pub fn get_next_work_required(
     chain: &[HeaderInfo],
     next_block_time: Option<i64>,
@@ -213,6 +243,12 @@ pub fn get_next_work_required(
     }
 }
 
+fn u256_to_f64(value: U256) -> f64 {
+    value.0.iter().rev().fold(0.0, |acc, limb| {
+        acc * 18_446_744_073_709_551_616.0 + *limb as f64
+    })
+}
+
 fn u256_to_hash(value: U256) -> Hash256 {
     value.to_little_endian()
 }
@@ -411,6 +447,49 @@ mod tests {
     use super::*;
     use fluxd_consensus::params::{consensus_params, Network};
     use fluxd_consensus::upgrades::UpgradeIndex;
+    use fluxd_primitives::block::{CURRENT_VERSION, PON_VERSION};
+    use fluxd_primitives::outpoint::OutPoint;
+
+    fn hashrate_header(version: i32, time: u32, bits: u32) -> BlockHeader {
+        BlockHeader {
+            version,
+            prev_block: [0u8; 32],
+            merkle_root: [0u8; 32],
+            final_sapling_root: [0u8; 32],
+            time,
+            bits,
+            nonce: [0u8; 32],
+            solution: Vec::new(),
+            nodes_collateral: OutPoint::null(),
+            block_sig: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn network_hashrate_skips_pon_headers() {
+        // 0x207fffff has a target of 2^255 - 1, so each header proves 2 hashes of work.
+        let bits = 0x207fffff;
+        assert_eq!(block_proof(bits).expect("proof"), U256::from(2u64));
+        let headers = vec![
+            hashrate_header(CURRENT_VERSION, 1_000, bits),
+            hashrate_header(CURRENT_VERSION, 1_010, bits),
+            hashrate_header(PON_VERSION, 1_015, 0x1d00ffff),
+            hashrate_header(CURRENT_VERSION, 1_020, bits),
+            hashrate_header(CURRENT_VERSION, 1_040, bits),
+        ];
+
+        assert_eq!(network_hashrate(&headers, 3), 6.0 / 40.0);
+        assert_eq!(network_hashrate(&headers, 1), 2.0 / 20.0);
+        assert_eq!(network_hashrate(&headers[..1], 10), 0.0);
+        assert_eq!(network_hashrate(&headers[2..3], 10), 0.0);
+
+        let stalled = vec![
+            hashrate_header(CURRENT_VERSION, 1_000, bits),
+            hashrate_header(CURRENT_VERSION, 1_000, bits),
+        ];
+        assert_eq!(network_hashrate(&stalled, 5), 0.0);
+        assert_eq!(u256_to_f64(U256::one() << 64), 18_446_744_073_709_551_616.0);
+    }
 
     #[test]
     fn digishield_vectors_match_cpp() {
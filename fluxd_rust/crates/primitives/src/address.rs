@@ -1,8 +1,8 @@
-//! Base58 address decoding and script construction.
+//! Base58 and bech32 address encoding and script construction.
 
 use fluxd_consensus::Network;
 
-use crate::hash::sha256d;
+use crate::hash::{ct_eq, sha256d};
 
 #[derive(Debug)]
 pub enum AddressError {
@@ -10,8 +10,14 @@ pub enum AddressError {
     InvalidCharacter,
     InvalidChecksum,
     UnknownPrefix,
+    InvalidWitnessVersion,
 }
 
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+const BECH32_MAX_LEN: usize = 90;
+
 pub fn address_to_script_pubkey(address: &str, network: Network) -> Result<Vec<u8>, AddressError> {
     let payload = base58check_decode(address)?;
     let (pubkey_prefix, script_prefix) = network_prefixes(network);
@@ -96,6 +102,172 @@ pub fn wif_to_secret_key(wif: &str, network: Network) -> Result<([u8; 32], bool)
     Err(AddressError::InvalidLength)
 }
 
+/// Encodes a witness program as a BIP173 (v0) or BIP350 bech32m (v1-16) address.
+///
+/// Flux does not use witness outputs; this exists for interop tooling only.
+pub fn encode_bech32(
+    hrp: &str,
+    witness_version: u8,
+    program: &[u8],
+) -> Result<String, AddressError> {
+    if hrp.is_empty() || hrp.len() > 83 || !hrp.bytes().all(|byte| (33..=126).contains(&byte)) {
+        return Err(AddressError::UnknownPrefix);
+    }
+    check_witness_program(witness_version, program)?;
+
+    let hrp = hrp.to_ascii_lowercase();
+    let mut data = Vec::with_capacity(1 + (program.len() * 8).div_ceil(5) + 6);
+    data.push(witness_version);
+    data.extend(convert_bits(program, 8, 5, true).ok_or(AddressError::InvalidLength)?);
+    let constant = if witness_version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let checksum = bech32_checksum(hrp.as_bytes(), &data, constant);
+    data.extend_from_slice(&checksum);
+
+    let mut out = String::with_capacity(hrp.len() + 1 + data.len());
+    out.push_str(&hrp);
+    out.push('1');
+    for value in data {
+        out.push(BECH32_CHARSET[value as usize] as char);
+    }
+    if out.len() > BECH32_MAX_LEN {
+        return Err(AddressError::InvalidLength);
+    }
+    Ok(out)
+}
+
+/// Decodes a bech32/bech32m witness address into `(hrp, witness_version, program)`.
+pub fn decode_bech32(address: &str) -> Result<(String, u8, Vec<u8>), AddressError> {
+    if address.len() > BECH32_MAX_LEN {
+        return Err(AddressError::InvalidLength);
+    }
+    if !address.bytes().all(|byte| (33..=126).contains(&byte)) {
+        return Err(AddressError::InvalidCharacter);
+    }
+    let has_lower = address.bytes().any(|byte| byte.is_ascii_lowercase());
+    let has_upper = address.bytes().any(|byte| byte.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(AddressError::InvalidCharacter);
+    }
+    let address = address.to_ascii_lowercase();
+    let separator = address.rfind('1').ok_or(AddressError::InvalidCharacter)?;
+    if separator == 0 || separator + 7 > address.len() {
+        return Err(AddressError::InvalidLength);
+    }
+
+    let (hrp, rest) = address.split_at(separator);
+    let data = rest[1..]
+        .bytes()
+        .map(|byte| {
+            BECH32_CHARSET
+                .iter()
+                .position(|value| *value == byte)
+                .map(|pos| pos as u8)
+                .ok_or(AddressError::InvalidCharacter)
+        })
+        .collect::<Result<Vec<u8>, AddressError>>()?;
+
+    let constant = bech32_polymod(&bech32_hrp_expand(hrp.as_bytes()), &data);
+    let (values, _) = data.split_at(data.len() - 6);
+    let witness_version = *values.first().ok_or(AddressError::InvalidLength)?;
+    let expected = if witness_version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    if constant != expected {
+        return Err(AddressError::InvalidChecksum);
+    }
+
+    let program = convert_bits(&values[1..], 5, 8, false).ok_or(AddressError::InvalidLength)?;
+    check_witness_program(witness_version, &program)?;
+    Ok((hrp.to_string(), witness_version, program))
+}
+
+fn check_witness_program(witness_version: u8, program: &[u8]) -> Result<(), AddressError> {
+    if witness_version > 16 {
+        return Err(AddressError::InvalidWitnessVersion);
+    }
+    if !(2..=40).contains(&program.len()) {
+        return Err(AddressError::InvalidLength);
+    }
+    if witness_version == 0 && program.len() != 20 && program.len() != 32 {
+        return Err(AddressError::InvalidLength);
+    }
+    Ok(())
+}
+
+fn bech32_polymod(hrp_expanded: &[u8], data: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for value in hrp_expanded.iter().chain(data) {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x01ff_ffff) << 5) ^ u32::from(*value);
+        for (bit, generator) in GENERATOR.iter().enumerate() {
+            if (top >> bit) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn bech32_hrp_expand(hrp: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(hrp.len() * 2 + 1);
+    out.extend(hrp.iter().map(|byte| byte >> 5));
+    out.push(0);
+    out.extend(hrp.iter().map(|byte| byte & 0x1f));
+    out
+}
+
+fn bech32_checksum(hrp: &[u8], data: &[u8], constant: u32) -> [u8; 6] {
+    let mut values = data.to_vec();
+    values.extend_from_slice(&[0u8; 6]);
+    let polymod = bech32_polymod(&bech32_hrp_expand(hrp), &values) ^ constant;
+    let mut out = [0u8; 6];
+    for (index, value) in out.iter_mut().enumerate() {
+        *value = ((polymod >> (5 * (5 - index))) & 0x1f) as u8;
+    }
+    out
+}
+
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let max_value = (1u32 << to) - 1;
+    let max_acc = (1u32 << (from + to - 1)) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for value in data {
+        let value = u32::from(*value);
+        if value >> from != 0 {
+            return None;
+        }
+        acc = ((acc << from) | value) & max_acc;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max_value) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max_value) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
 fn network_prefixes(network: Network) -> (&'static [u8], &'static [u8]) {
     match network {
         Network::Mainnet => (&[0x1C, 0xB8], &[0x1C, 0xBD]),
@@ -151,7 +323,7 @@ fn base58check_decode(input: &str) -> Result<Vec<u8>, AddressError> {
     }
     let (payload, checksum) = bytes.split_at(bytes.len() - 4);
     let digest = sha256d(payload);
-    if checksum != &digest[..4] {
+    if !ct_eq(checksum, &digest[..4]) {
         return Err(AddressError::InvalidChecksum);
     }
     Ok(payload.to_vec())
@@ -1,5 +1,7 @@
 //! Block header and block types.
 
+use std::io::{self, Read};
+
 use fluxd_consensus::Hash256;
 
 use crate::encoding::{Decodable, DecodeError, Decoder, Encodable, Encoder};
@@ -10,6 +12,11 @@ use crate::transaction::{Transaction, TransactionDecodeError, TransactionEncodeE
 pub const CURRENT_VERSION: i32 = 4;
 pub const PON_VERSION: i32 = 100;
 
+/// Minimum number of bytes `Block::read_from` pulls from the reader per refill.
+const READ_CHUNK: usize = 64 * 1024;
+/// Cap on the transaction vector preallocated from an untrusted count in `Block::read_from`.
+const MAX_PREALLOC_TXS: usize = 1024;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct BlockHeader {
     pub version: i32,
@@ -72,6 +79,11 @@ impl BlockHeader {
         Ok(header)
     }
 
+    /// Whether `original` decodes (signature included) and re-encodes to exactly the same bytes.
+    pub fn reencode_matches(original: &[u8]) -> bool {
+        Self::consensus_decode(original).is_ok_and(|header| header.consensus_encode() == original)
+    }
+
     pub fn consensus_decode_for_hash(bytes: &[u8]) -> Result<Self, DecodeError> {
         let mut decoder = Decoder::new(bytes);
         let header = Self::consensus_decode_from(&mut decoder, false)?;
@@ -150,8 +162,7 @@ impl Block {
     pub fn consensus_decode(bytes: &[u8]) -> Result<Self, BlockDecodeError> {
         let mut decoder = Decoder::new(bytes);
         let header = BlockHeader::consensus_decode_from(&mut decoder, true)?;
-        let count = decoder.read_varint()?;
-        let count = usize::try_from(count).map_err(|_| DecodeError::SizeTooLarge)?;
+        let count = decoder.read_length(1)?;
         let mut transactions = Vec::with_capacity(count);
         for _ in 0..count {
             transactions.push(Transaction::decode_from(&mut decoder, true)?);
@@ -164,12 +175,148 @@ impl Block {
             transactions,
         })
     }
+
+    /// Whether `original` decodes and re-encodes to exactly the same bytes, covering the header
+    /// and every transaction. See [`Transaction::reencode_matches`].
+    pub fn reencode_matches(original: &[u8]) -> bool {
+        Self::consensus_decode(original)
+            .ok()
+            .and_then(|block| block.consensus_encode().ok())
+            .is_some_and(|encoded| encoded == original)
+    }
+
+    /// Decodes a block from `reader`, consuming at most `max_size` bytes.
+    ///
+    /// Transactions are decoded as their bytes arrive and the raw bytes are dropped once each
+    /// one is decoded, so the raw block is never held alongside the decoded one. The reader must
+    /// end where the block does (use `Read::take` for length-prefixed records); extra bytes are
+    /// rejected as in `consensus_decode`. A stream longer than `max_size` fails with
+    /// `DecodeError::SizeTooLarge`.
+    pub fn read_from<R: Read>(reader: R, max_size: usize) -> Result<Self, BlockDecodeError> {
+        let mut stream = StreamDecoder::new(reader, max_size);
+        let header = stream.decode(|decoder| {
+            BlockHeader::consensus_decode_from(decoder, true).map_err(BlockDecodeError::from)
+        })?;
+        let count =
+            stream.decode(|decoder| decoder.read_varint().map_err(BlockDecodeError::from))?;
+        if count > stream.budget() as u64 {
+            return Err(BlockDecodeError::Decode(DecodeError::LengthExceedsInput));
+        }
+        let count = count as usize;
+        let mut transactions = Vec::with_capacity(count.min(MAX_PREALLOC_TXS));
+        for _ in 0..count {
+            transactions.push(stream.decode(|decoder| {
+                Transaction::decode_from(decoder, true).map_err(BlockDecodeError::from)
+            })?);
+        }
+        stream.finish()?;
+        Ok(Self {
+            header,
+            transactions,
+        })
+    }
+}
+
+/// Buffers just enough of a reader to run the slice-based decoders on it.
+struct StreamDecoder<R> {
+    reader: io::Take<R>,
+    buf: Vec<u8>,
+    consumed: usize,
+    max_size: usize,
+    eof: bool,
+}
+
+impl<R: Read> StreamDecoder<R> {
+    fn new(reader: R, max_size: usize) -> Self {
+        Self {
+            reader: reader.take((max_size as u64).saturating_add(1)),
+            buf: Vec::new(),
+            consumed: 0,
+            max_size,
+            eof: false,
+        }
+    }
+
+    /// Bytes the rest of the block may still occupy.
+    fn budget(&self) -> usize {
+        self.max_size.saturating_sub(self.consumed)
+    }
+
+    /// Runs `decode` on the buffered bytes, refilling and retrying while it fails only because
+    /// the buffer ends early.
+    fn decode<T>(
+        &mut self,
+        decode: impl Fn(&mut Decoder) -> Result<T, BlockDecodeError>,
+    ) -> Result<T, BlockDecodeError> {
+        loop {
+            let mut decoder = Decoder::new(&self.buf);
+            match decode(&mut decoder) {
+                Ok(value) => {
+                    let used = self.buf.len() - decoder.remaining();
+                    self.buf.drain(..used);
+                    self.consumed += used;
+                    return Ok(value);
+                }
+                Err(err) if !self.eof && err.is_truncation() => self.fill()?,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn fill(&mut self) -> Result<(), BlockDecodeError> {
+        let start = self.buf.len();
+        self.buf.resize(start + start.max(READ_CHUNK), 0);
+        let read = loop {
+            match self.reader.read(&mut self.buf[start..]) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.buf.truncate(start);
+                    return Err(BlockDecodeError::Io(err));
+                }
+            }
+        };
+        self.buf.truncate(start + read);
+        if read == 0 {
+            self.eof = true;
+        }
+        if self.consumed + self.buf.len() > self.max_size {
+            return Err(BlockDecodeError::Decode(DecodeError::SizeTooLarge));
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), BlockDecodeError> {
+        if self.buf.is_empty() && !self.eof {
+            self.fill()?;
+        }
+        if !self.buf.is_empty() {
+            return Err(BlockDecodeError::Decode(DecodeError::TrailingBytes));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub enum BlockDecodeError {
     Decode(DecodeError),
     Transaction(TransactionDecodeError),
+    Io(io::Error),
+}
+
+impl BlockDecodeError {
+    /// True when decoding ran out of input, i.e. more bytes might let it succeed.
+    fn is_truncation(&self) -> bool {
+        let error = match self {
+            BlockDecodeError::Decode(error)
+            | BlockDecodeError::Transaction(TransactionDecodeError::Decode(error)) => error,
+            _ => return false,
+        };
+        matches!(
+            error,
+            DecodeError::UnexpectedEof | DecodeError::LengthExceedsInput
+        )
+    }
 }
 
 impl From<DecodeError> for BlockDecodeError {
@@ -189,6 +336,7 @@ impl std::fmt::Display for BlockDecodeError {
         match self {
             BlockDecodeError::Decode(error) => write!(f, "{error}"),
             BlockDecodeError::Transaction(error) => write!(f, "{error}"),
+            BlockDecodeError::Io(error) => write!(f, "{error}"),
         }
     }
 }
@@ -1,5 +1,27 @@
+//! Consensus wire codec shared by every Flux message and on-disk record.
+//!
+//! Integers are little-endian, lengths and counts are Bitcoin-style CompactSize values, and
+//! hashes are written in internal (little-endian) byte order. Crates that build their own
+//! Flux-compatible messages should use [`Encoder`] and [`Decoder`] rather than re-implementing
+//! these rules.
+//!
+//! ```
+//! use fluxd_primitives::encoding::{Decoder, Encoder};
+//!
+//! let mut encoder = Encoder::new();
+//! encoder.write_i32_le(170_100);
+//! encoder.write_var_bytes(&[0x76, 0xa9]);
+//! let bytes = encoder.into_inner();
+//!
+//! let mut decoder = Decoder::new(&bytes);
+//! assert_eq!(decoder.read_i32_le().unwrap(), 170_100);
+//! assert_eq!(decoder.read_var_bytes().unwrap(), vec![0x76, 0xa9]);
+//! assert!(decoder.is_empty());
+//! ```
+
 use fluxd_consensus::Hash256;
 
+/// Append-only buffer that writes values in consensus encoding.
 #[derive(Default)]
 pub struct Encoder {
     buf: Vec<u8>,
@@ -10,6 +32,7 @@ impl Encoder {
         Self::default()
     }
 
+    /// Returns the bytes written so far.
     pub fn into_inner(self) -> Vec<u8> {
         self.buf
     }
@@ -42,10 +65,14 @@ impl Encoder {
         self.buf.extend_from_slice(&value.to_le_bytes());
     }
 
+    /// Writes `bytes` as-is, with no length prefix.
     pub fn write_bytes(&mut self, bytes: &[u8]) {
         self.buf.extend_from_slice(bytes);
     }
 
+    /// Writes `value` as a CompactSize: one byte below `0xfd`, otherwise a `0xfd`/`0xfe`/`0xff`
+    /// marker followed by the smallest of u16/u32/u64 that holds it.
+    #[doc(alias = "write_compact_size")]
     pub fn write_varint(&mut self, value: u64) {
         if value < 0xfd {
             self.write_u8(value as u8);
@@ -61,28 +88,41 @@ impl Encoder {
         }
     }
 
+    /// Writes a CompactSize length followed by `bytes`; this is how scripts are serialized.
+    #[doc(alias = "write_script")]
     pub fn write_var_bytes(&mut self, bytes: &[u8]) {
         self.write_varint(bytes.len() as u64);
         self.write_bytes(bytes);
     }
 
+    /// Writes a UTF-8 string as length-prefixed bytes.
     pub fn write_var_str(&mut self, value: &str) {
         self.write_var_bytes(value.as_bytes());
     }
 
+    /// Writes a 32-byte hash or uint256 in internal byte order (the reverse of its display hex).
+    #[doc(alias = "write_u256_le")]
     pub fn write_hash_le(&mut self, hash: &Hash256) {
         self.buf.extend_from_slice(hash);
     }
 }
 
-const MAX_COMPACT_SIZE: u64 = 0x0200_0000;
+/// Largest CompactSize accepted as a length or count (`MAX_SIZE` in the C++ daemon).
+pub const MAX_COMPACT_SIZE: u64 = 0x0200_0000;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DecodeError {
+    /// The input ended in the middle of a value.
     UnexpectedEof,
+    /// A CompactSize used a longer form than its value needs.
     NonCanonicalVarInt,
+    /// A length or count exceeds [`MAX_COMPACT_SIZE`].
     SizeTooLarge,
+    /// A length or count claims more data than the input (or allocation limit) can hold.
+    LengthExceedsInput,
+    /// The bytes decoded but do not form a valid value.
     InvalidData(&'static str),
+    /// Input was left over after a complete value was decoded.
     TrailingBytes,
 }
 
@@ -92,6 +132,9 @@ impl std::fmt::Display for DecodeError {
             DecodeError::UnexpectedEof => write!(f, "unexpected end of input"),
             DecodeError::NonCanonicalVarInt => write!(f, "non-canonical CompactSize"),
             DecodeError::SizeTooLarge => write!(f, "compact size exceeds maximum"),
+            DecodeError::LengthExceedsInput => {
+                write!(f, "length prefix exceeds remaining input")
+            }
             DecodeError::InvalidData(message) => write!(f, "{message}"),
             DecodeError::TrailingBytes => write!(f, "trailing bytes after decode"),
         }
@@ -100,16 +143,32 @@ impl std::fmt::Display for DecodeError {
 
 impl std::error::Error for DecodeError {}
 
+/// Cursor over a byte slice that reads values in consensus encoding.
+///
+/// Every read either consumes exactly the bytes of one value or fails without a partial value;
+/// after an error the cursor position is unspecified.
 pub struct Decoder<'a> {
     input: &'a [u8],
     cursor: usize,
+    max_alloc: usize,
 }
 
 impl<'a> Decoder<'a> {
     pub fn new(input: &'a [u8]) -> Self {
-        Self { input, cursor: 0 }
+        Self::with_limit(input, usize::MAX)
+    }
+
+    /// Like `new`, but length prefixes may also claim at most `max_alloc` bytes, even when the
+    /// input is longer.
+    pub fn with_limit(input: &'a [u8], max_alloc: usize) -> Self {
+        Self {
+            input,
+            cursor: 0,
+            max_alloc,
+        }
     }
 
+    /// Bytes not consumed yet.
     pub fn remaining(&self) -> usize {
         self.input.len().saturating_sub(self.cursor)
     }
@@ -160,6 +219,7 @@ impl<'a> Decoder<'a> {
         Ok(self.read_u64_le()? as i64)
     }
 
+    /// Reads exactly `N` bytes into an array.
     pub fn read_fixed<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
         let bytes = self.read_slice(N)?;
         let mut out = [0u8; N];
@@ -167,11 +227,48 @@ impl<'a> Decoder<'a> {
         Ok(out)
     }
 
+    /// Reads exactly `len` bytes, with no length prefix.
     pub fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, DecodeError> {
         Ok(self.read_slice(len)?.to_vec())
     }
 
+    /// Reads a canonical CompactSize no larger than [`MAX_COMPACT_SIZE`].
     pub fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let value = self.read_compact_size()?;
+        if value > MAX_COMPACT_SIZE {
+            return Err(DecodeError::SizeTooLarge);
+        }
+        Ok(value)
+    }
+
+    /// Reads a CompactSize element count, rejecting counts whose items (each at least
+    /// `min_item_size` bytes) could not fit in the remaining input or the allocation limit.
+    /// The result is safe to pass to `Vec::with_capacity`.
+    pub fn read_length(&mut self, min_item_size: usize) -> Result<usize, DecodeError> {
+        let count = self.read_compact_size()?;
+        let budget = self.remaining().min(self.max_alloc) as u64;
+        if count.saturating_mul(min_item_size.max(1) as u64) > budget {
+            return Err(DecodeError::LengthExceedsInput);
+        }
+        if count > MAX_COMPACT_SIZE {
+            return Err(DecodeError::SizeTooLarge);
+        }
+        usize::try_from(count).map_err(|_| DecodeError::SizeTooLarge)
+    }
+
+    /// Reads a canonical CompactSize of any magnitude. Use [`Decoder::read_varint`] or
+    /// [`Decoder::read_length`] for lengths and counts, which also enforce size limits.
+    ///
+    /// ```
+    /// use fluxd_primitives::encoding::{DecodeError, Decoder};
+    ///
+    /// let mut decoder = Decoder::new(&[0xfd, 0x00, 0x01]);
+    /// assert_eq!(decoder.read_compact_size(), Ok(0x100));
+    /// // 5 fits in a single byte, so the three-byte form is rejected.
+    /// let mut decoder = Decoder::new(&[0xfd, 0x05, 0x00]);
+    /// assert_eq!(decoder.read_compact_size(), Err(DecodeError::NonCanonicalVarInt));
+    /// ```
+    pub fn read_compact_size(&mut self) -> Result<u64, DecodeError> {
         let prefix = self.read_u8()? as u64;
         let value = if prefix < 0xfd {
             prefix
@@ -194,48 +291,53 @@ impl<'a> Decoder<'a> {
             }
             value
         };
-
-        if value > MAX_COMPACT_SIZE {
-            return Err(DecodeError::SizeTooLarge);
-        }
         Ok(value)
     }
 
+    /// Reads a CompactSize length followed by that many bytes; this is how scripts are serialized.
+    #[doc(alias = "read_script")]
     pub fn read_var_bytes(&mut self) -> Result<Vec<u8>, DecodeError> {
-        let len = self.read_varint()?;
-        let len = usize::try_from(len).map_err(|_| DecodeError::SizeTooLarge)?;
+        let len = self.read_length(1)?;
         self.read_bytes(len)
     }
 
+    /// Reads length-prefixed bytes that must be valid UTF-8.
     pub fn read_var_str(&mut self) -> Result<String, DecodeError> {
         let bytes = self.read_var_bytes()?;
         String::from_utf8(bytes).map_err(|_| DecodeError::InvalidData("invalid utf8 string"))
     }
 
+    /// Reads one byte; any non-zero value is `true`.
     pub fn read_bool(&mut self) -> Result<bool, DecodeError> {
         Ok(self.read_u8()? != 0)
     }
 
+    /// Reads a 32-byte hash or uint256 in internal byte order.
+    #[doc(alias = "read_u256_le")]
     pub fn read_hash_le(&mut self) -> Result<Hash256, DecodeError> {
         let bytes = self.read_slice(32)?;
         Ok(bytes.try_into().expect("read_slice length"))
     }
 }
 
+/// Types with a consensus encoding that cannot fail.
 pub trait Encodable {
     fn consensus_encode(&self, encoder: &mut Encoder);
 }
 
+/// Types that can be read back from their consensus encoding.
 pub trait Decodable: Sized {
     fn consensus_decode(decoder: &mut Decoder) -> Result<Self, DecodeError>;
 }
 
+/// Encodes `value` into a fresh buffer.
 pub fn encode<T: Encodable>(value: &T) -> Vec<u8> {
     let mut encoder = Encoder::new();
     value.consensus_encode(&mut encoder);
     encoder.into_inner()
 }
 
+/// Decodes a `T` that must span all of `bytes`.
 pub fn decode<T: Decodable>(bytes: &[u8]) -> Result<T, DecodeError> {
     let mut decoder = Decoder::new(bytes);
     let value = T::consensus_decode(&mut decoder)?;
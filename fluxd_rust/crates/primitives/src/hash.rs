@@ -2,6 +2,8 @@ use fluxd_consensus::Hash256;
 use ripemd::{Digest as RipemdDigest, Ripemd160};
 use sha2::Sha256;
 
+use crate::ids::ParseHashError;
+
 pub fn sha256(data: &[u8]) -> Hash256 {
     let digest = Sha256::digest(data);
     let mut out = [0u8; 32];
@@ -24,3 +26,119 @@ pub fn hash160(data: &[u8]) -> [u8; 20] {
     out.copy_from_slice(&digest);
     out
 }
+
+/// Compares two byte strings without an early exit on the first differing byte.
+///
+/// Use this for checksums, tags and key hashes derived from secret material. Only the length
+/// comparison is allowed to short-circuit.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a
+        .iter()
+        .zip(b)
+        .fold(0u8, |acc, (left, right)| acc | (left ^ right));
+    std::hint::black_box(diff) == 0
+}
+
+/// Formats a hash in RPC display order (byte-reversed), as used for txids and block hashes.
+pub fn hash256_to_hex(hash: &Hash256) -> String {
+    let mut out = String::with_capacity(64);
+    for byte in hash.iter().rev() {
+        push_hex_byte(&mut out, *byte);
+    }
+    out
+}
+
+/// Parses a hash given in RPC display order, the inverse of [`hash256_to_hex`].
+///
+/// Surrounding whitespace and a `0x` prefix are ignored, and shorter inputs are treated as
+/// numbers with the leading zero digits left off.
+pub fn hash256_from_hex(input: &str) -> Result<Hash256, ParseHashError> {
+    let mut hash = from_hex_internal(input)?;
+    hash.reverse();
+    Ok(hash)
+}
+
+/// Formats a hash in internal (digest and wire) order, without byte reversal.
+pub fn to_hex_internal(hash: &Hash256) -> String {
+    let mut out = String::with_capacity(64);
+    for byte in hash {
+        push_hex_byte(&mut out, *byte);
+    }
+    out
+}
+
+/// Parses a hash given in internal order, the inverse of [`to_hex_internal`]. Accepts the same
+/// input forms as [`hash256_from_hex`].
+pub fn from_hex_internal(input: &str) -> Result<Hash256, ParseHashError> {
+    let mut hex = input.trim().as_bytes();
+    if let Some(stripped) = hex.strip_prefix(b"0x").or_else(|| hex.strip_prefix(b"0X")) {
+        hex = stripped;
+    }
+    if hex.is_empty() || hex.len() > 64 {
+        return Err(ParseHashError::InvalidLength);
+    }
+
+    let mut digits = [b'0'; 64];
+    digits[64 - hex.len()..].copy_from_slice(hex);
+    let mut out = [0u8; 32];
+    for (byte, pair) in out.iter_mut().zip(digits.chunks_exact(2)) {
+        *byte = (hex_value(pair[0])? << 4) | hex_value(pair[1])?;
+    }
+    Ok(out)
+}
+
+fn push_hex_byte(out: &mut String, byte: u8) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    out.push(DIGITS[usize::from(byte >> 4)] as char);
+    out.push(DIGITS[usize::from(byte & 0x0f)] as char);
+}
+
+pub(crate) fn hex_value(digit: u8) -> Result<u8, ParseHashError> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(ParseHashError::InvalidHex),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_helpers_round_trip_both_byte_orders() {
+        let mut hash = [0u8; 32];
+        hash[0] = 0x01;
+        hash[31] = 0xab;
+
+        let display = hash256_to_hex(&hash);
+        assert_eq!(&display[..2], "ab");
+        assert_eq!(&display[62..], "01");
+        assert_eq!(hash256_from_hex(&display), Ok(hash));
+        assert_eq!(hash256_from_hex(&display.to_uppercase()), Ok(hash));
+
+        let internal = to_hex_internal(&hash);
+        assert_eq!(&internal[..2], "01");
+        assert_eq!(&internal[62..], "ab");
+        assert_eq!(from_hex_internal(&internal), Ok(hash));
+        assert_eq!(hash256_from_hex(&internal), from_hex_internal(&display));
+    }
+
+    #[test]
+    fn hash256_from_hex_accepts_prefix_and_short_forms() {
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(hash256_from_hex(" 0x1 "), Ok(one));
+        assert_eq!(hash256_from_hex("01"), Ok(one));
+        assert_eq!(hash256_from_hex(""), Err(ParseHashError::InvalidLength));
+        assert_eq!(
+            hash256_from_hex(&"0".repeat(65)),
+            Err(ParseHashError::InvalidLength)
+        );
+        assert_eq!(hash256_from_hex("0g"), Err(ParseHashError::InvalidHex));
+    }
+}
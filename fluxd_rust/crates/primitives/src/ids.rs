@@ -0,0 +1,121 @@
+//! Typed 32-byte identifiers.
+//!
+//! A bare `Hash256` is always held in internal byte order (as produced by the hash function and
+//! as serialized on the wire). Txids and block hashes are shown byte-reversed over RPC, script
+//! hashes are not. Wrapping each kind in its own type keeps the byte order next to the value and
+//! stops a txid from being passed where a block hash is expected.
+
+use std::fmt;
+use std::str::FromStr;
+
+use fluxd_consensus::Hash256;
+
+use crate::hash::hex_value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseHashError {
+    InvalidLength,
+    InvalidHex,
+}
+
+impl fmt::Display for ParseHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseHashError::InvalidLength => write!(f, "hash must be 64 hex characters"),
+            ParseHashError::InvalidHex => write!(f, "hash contains non-hex characters"),
+        }
+    }
+}
+
+impl std::error::Error for ParseHashError {}
+
+macro_rules! hash_newtype {
+    ($(#[$meta:meta])* $name:ident, reversed: $reversed:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(Hash256);
+
+        impl $name {
+            /// Wraps bytes that are already in internal (wire) order.
+            pub const fn from_internal(bytes: Hash256) -> Self {
+                Self(bytes)
+            }
+
+            /// Returns the bytes in internal (wire) order.
+            pub const fn to_internal(self) -> Hash256 {
+                self.0
+            }
+
+            pub const fn as_internal(&self) -> &Hash256 {
+                &self.0
+            }
+
+            /// Returns the bytes in the order used by the RPC hex form.
+            pub fn to_display_bytes(self) -> Hash256 {
+                let mut bytes = self.0;
+                if $reversed {
+                    bytes.reverse();
+                }
+                bytes
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for byte in self.to_display_bytes() {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "({})"), self)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseHashError;
+
+            /// Parses the RPC hex form, the inverse of `Display`.
+            fn from_str(input: &str) -> Result<Self, Self::Err> {
+                let mut bytes = parse_hex32(input)?;
+                if $reversed {
+                    bytes.reverse();
+                }
+                Ok(Self(bytes))
+            }
+        }
+    };
+}
+
+hash_newtype!(
+    /// Transaction id; shown byte-reversed over RPC.
+    Txid,
+    reversed: true
+);
+
+hash_newtype!(
+    /// Block header hash; shown byte-reversed over RPC.
+    BlockHash,
+    reversed: true
+);
+
+hash_newtype!(
+    /// SHA-256 of a scriptPubKey as keyed by the address indexes; shown in digest order.
+    ScriptHash,
+    reversed: false
+);
+
+fn parse_hex32(input: &str) -> Result<Hash256, ParseHashError> {
+    let input = input.as_bytes();
+    if input.len() != 64 {
+        return Err(ParseHashError::InvalidLength);
+    }
+    let mut out = [0u8; 32];
+    for (byte, pair) in out.iter_mut().zip(input.chunks_exact(2)) {
+        *byte = (hex_value(pair[0])? << 4) | hex_value(pair[1])?;
+    }
+    Ok(out)
+}
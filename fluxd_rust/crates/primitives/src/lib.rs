@@ -4,19 +4,23 @@ pub mod address;
 pub mod block;
 pub mod encoding;
 pub mod hash;
+pub mod ids;
 pub mod merkleblock;
 pub mod outpoint;
 pub mod transaction;
 
 pub use address::{
-    address_to_script_pubkey, script_pubkey_to_address, secret_key_to_wif, wif_to_secret_key,
-    AddressError,
+    address_to_script_pubkey, decode_bech32, encode_bech32, script_pubkey_to_address,
+    secret_key_to_wif, wif_to_secret_key, AddressError,
 };
 pub use block::{Block, BlockHeader};
-pub use hash::{sha256, sha256d};
+pub use encoding::{Decodable, DecodeError, Decoder, Encodable, Encoder};
+pub use hash::{hash256_from_hex, hash256_to_hex, sha256, sha256d};
+pub use ids::{BlockHash, ParseHashError, ScriptHash, Txid};
 pub use merkleblock::{MerkleBlock, PartialMerkleTree};
 pub use outpoint::OutPoint;
 pub use transaction::{
-    JoinSplit, OutputDescription, SpendDescription, SproutProof, Transaction,
-    TransactionDecodeError, TransactionEncodeError, TxIn, TxOut,
+    sequence_to_relative_lock, JoinSplit, OutputDescription, RelativeLock, SequenceLockPrevout,
+    SequenceLocks, SpendDescription, SproutProof, Transaction, TransactionDecodeError,
+    TransactionEncodeError, TxIn, TxOut,
 };
@@ -255,8 +255,7 @@ impl Decodable for PartialMerkleTree {
             ));
         }
 
-        let hash_count = decoder.read_varint()?;
-        let hash_count = usize::try_from(hash_count).map_err(|_| DecodeError::SizeTooLarge)?;
+        let hash_count = decoder.read_length(32)?;
         if hash_count > n_transactions as usize {
             return Err(DecodeError::InvalidData("too many hashes in merkle tree"));
         }
@@ -3,6 +3,7 @@
 use fluxd_consensus::Hash256;
 
 use crate::encoding::{Decodable, DecodeError, Decoder, Encodable, Encoder};
+use crate::ids::Txid;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct OutPoint {
@@ -17,6 +18,10 @@ impl OutPoint {
             index: u32::MAX,
         }
     }
+
+    pub fn txid(&self) -> Txid {
+        Txid::from_internal(self.hash)
+    }
 }
 
 impl Encodable for OutPoint {
@@ -1,14 +1,14 @@
 //! Transaction types and serialization.
 
+use fluxd_consensus::constants::LOCKTIME_VERIFY_SEQUENCE;
 use fluxd_consensus::Hash256;
 
+pub use fluxd_consensus::constants::{OVERWINTER_VERSION_GROUP_ID, SAPLING_VERSION_GROUP_ID};
+
 use crate::encoding::{Decodable, DecodeError, Decoder, Encodable, Encoder};
 use crate::hash::sha256d;
 use crate::outpoint::OutPoint;
 
-pub const OVERWINTER_VERSION_GROUP_ID: u32 = 0x03C4_8270;
-pub const SAPLING_VERSION_GROUP_ID: u32 = 0x892F_2085;
-
 pub const FLUXNODE_TX_VERSION: i32 = 5;
 pub const FLUXNODE_TX_UPGRADEABLE_VERSION: i32 = 6;
 
@@ -33,6 +33,14 @@ pub const SAPLING_OUT_CIPHERTEXT_SIZE: usize = 80;
 pub const GROTH_PROOF_SIZE: usize = 192;
 pub const PHGR_PROOF_SIZE: usize = 296;
 
+/// BIP68: if set, `nSequence` carries no relative lock-time.
+pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+/// BIP68: if set, the relative lock-time is in units of 512 seconds, otherwise in blocks.
+pub const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+pub const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000_ffff;
+/// Time-based relative locks are `value << SEQUENCE_LOCKTIME_GRANULARITY` seconds.
+pub const SEQUENCE_LOCKTIME_GRANULARITY: u32 = 9;
+
 pub fn has_conflicting_bits(version: i32) -> bool {
     (version & FLUXNODE_TX_TYPE_NORMAL_BIT) != 0 && (version & FLUXNODE_TX_TYPE_P2SH_BIT) != 0
 }
@@ -63,6 +71,43 @@ pub fn has_flux_tx_delegates_feature(version: i32) -> bool {
     (version & FLUXNODE_TX_FEATURE_DELEGATES_BIT) != 0
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RelativeLock {
+    Blocks(u16),
+    Seconds(u32),
+}
+
+/// Decodes a BIP68 relative lock-time; `None` when the disable flag is set.
+pub fn sequence_to_relative_lock(sequence: u32) -> Option<RelativeLock> {
+    if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+        return None;
+    }
+    let value = sequence & SEQUENCE_LOCKTIME_MASK;
+    if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+        Some(RelativeLock::Seconds(
+            value << SEQUENCE_LOCKTIME_GRANULARITY,
+        ))
+    } else {
+        Some(RelativeLock::Blocks(value as u16))
+    }
+}
+
+/// Where an input's prevout was confirmed, as needed for BIP68.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SequenceLockPrevout {
+    /// Height of the block that created the prevout.
+    pub height: i32,
+    /// Median time past of the block before `height`.
+    pub median_time_past: i64,
+}
+
+/// Last height and time at which a transaction is still locked; -1 means unconstrained.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SequenceLocks {
+    pub min_height: i32,
+    pub min_time: i64,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct TxIn {
     pub prevout: OutPoint,
@@ -321,8 +366,7 @@ impl Decodable for FluxnodeDelegates {
         let kind = decoder.read_u8()?;
         let mut delegate_starting_keys = Vec::new();
         if version == Self::INITIAL_VERSION && kind == Self::UPDATE {
-            let count = decoder.read_varint()?;
-            let count = usize::try_from(count).map_err(|_| DecodeError::SizeTooLarge)?;
+            let count = decoder.read_length(1)?;
             delegate_starting_keys.reserve(count);
             for _ in 0..count {
                 delegate_starting_keys.push(decoder.read_var_bytes()?);
@@ -417,10 +461,63 @@ pub struct Transaction {
     pub join_split_pub_key: [u8; 32],
     pub join_split_sig: [u8; 64],
     pub binding_sig: [u8; 64],
+    /// Everything after the lock time of an overwintered transaction whose version group this
+    /// crate does not know, as kept by [`Transaction::consensus_decode_lenient`]. Always empty
+    /// for known formats; the fields after `lock_time` are left at their defaults when it is in
+    /// use.
+    pub raw_tail: Vec<u8>,
     pub fluxnode: Option<FluxnodeTx>,
 }
 
 impl Transaction {
+    /// BIP68 locks for this transaction. Version 1 transactions are never sequence-locked.
+    /// Returns `None` if `prevouts` does not line up with `vin`.
+    pub fn sequence_locks(&self, prevouts: &[SequenceLockPrevout]) -> Option<SequenceLocks> {
+        if prevouts.len() != self.vin.len() {
+            return None;
+        }
+        let mut locks = SequenceLocks {
+            min_height: -1,
+            min_time: -1,
+        };
+        if self.version < 2 {
+            return Some(locks);
+        }
+        for (input, prevout) in self.vin.iter().zip(prevouts) {
+            match sequence_to_relative_lock(input.sequence) {
+                None => {}
+                Some(RelativeLock::Blocks(blocks)) => {
+                    let min_height = prevout.height.saturating_add(i32::from(blocks)) - 1;
+                    locks.min_height = locks.min_height.max(min_height);
+                }
+                Some(RelativeLock::Seconds(seconds)) => {
+                    let min_time = prevout.median_time_past.saturating_add(i64::from(seconds)) - 1;
+                    locks.min_time = locks.min_time.max(min_time);
+                }
+            }
+        }
+        Some(locks)
+    }
+
+    /// Whether BIP68 relative locks allow this transaction in a block at `height` whose
+    /// predecessor has median time past `prev_median_time_past`. Always passes unless `flags`
+    /// has `LOCKTIME_VERIFY_SEQUENCE`, which no Flux upgrade sets yet.
+    pub fn check_sequence_locks(
+        &self,
+        flags: u32,
+        prevouts: &[SequenceLockPrevout],
+        height: i32,
+        prev_median_time_past: i64,
+    ) -> bool {
+        if flags & LOCKTIME_VERIFY_SEQUENCE == 0 {
+            return true;
+        }
+        let Some(locks) = self.sequence_locks(prevouts) else {
+            return false;
+        };
+        locks.min_height < height && locks.min_time < prev_median_time_past
+    }
+
     pub fn header(&self) -> u32 {
         let mut header = self.version as u32;
         if self.f_overwintered {
@@ -456,6 +553,21 @@ impl Transaction {
             encoder.write_u32_le(self.version_group_id);
         }
 
+        if self.is_unknown_format() {
+            // Only a lenient decode produces these, with the transparent fields followed by the
+            // preserved bytes, whatever the version number says.
+            if self.raw_tail.is_empty() {
+                return Err(TransactionEncodeError::InvalidTransactionFormat(
+                    "unknown overwinter transaction format",
+                ));
+            }
+            write_vec(&mut encoder, &self.vin);
+            write_vec(&mut encoder, &self.vout);
+            encoder.write_u32_le(self.lock_time);
+            encoder.write_bytes(&self.raw_tail);
+            return Ok(encoder.into_inner());
+        }
+
         if self.version == FLUXNODE_TX_VERSION {
             if self.f_overwintered {
                 return Err(TransactionEncodeError::InvalidTransactionFormat(
@@ -495,9 +607,9 @@ impl Transaction {
             && self.version_group_id == SAPLING_VERSION_GROUP_ID
             && self.version == 4;
 
-        if self.f_overwintered && !(is_overwinter_v3 || is_sapling_v4) {
+        if !self.raw_tail.is_empty() {
             return Err(TransactionEncodeError::InvalidTransactionFormat(
-                "unknown overwinter transaction format",
+                "raw tail on a known transaction format",
             ));
         }
 
@@ -540,19 +652,47 @@ impl Transaction {
     }
 
     pub fn consensus_decode(bytes: &[u8]) -> Result<Self, TransactionDecodeError> {
-        Self::decode_with_mode(bytes, true)
+        Self::decode_with_mode(bytes, true, true)
     }
 
     pub fn consensus_decode_for_hash(bytes: &[u8]) -> Result<Self, TransactionDecodeError> {
-        Self::decode_with_mode(bytes, false)
+        Self::decode_with_mode(bytes, false, true)
+    }
+
+    /// Like [`Transaction::consensus_decode`], but an overwintered transaction from an unknown
+    /// version group decodes its header, version group id, inputs, outputs and lock time, with
+    /// the remaining bytes kept in `raw_tail` so re-encoding reproduces the input. For tooling that inspects future
+    /// formats; consensus paths use the strict decoders, which reject them.
+    pub fn consensus_decode_lenient(bytes: &[u8]) -> Result<Self, TransactionDecodeError> {
+        Self::decode_with_mode(bytes, true, false)
+    }
+
+    /// Whether `original` strictly decodes and re-encodes to exactly the same bytes. A decoder
+    /// that accepts a non-canonical form normalizes it on re-encode, so the txid computed from
+    /// the decoded value would differ from the hash of what the peer sent.
+    pub fn reencode_matches(original: &[u8]) -> bool {
+        Self::consensus_decode(original)
+            .ok()
+            .and_then(|tx| tx.consensus_encode().ok())
+            .is_some_and(|encoded| encoded == original)
+    }
+
+    /// Whether this transaction came from an unknown version group via a lenient decode.
+    pub fn is_unknown_format(&self) -> bool {
+        self.f_overwintered
+            && !matches!(
+                (self.version_group_id, self.version),
+                (OVERWINTER_VERSION_GROUP_ID, 3) | (SAPLING_VERSION_GROUP_ID, 4)
+            )
     }
 
     fn decode_with_mode(
         bytes: &[u8],
         include_signatures: bool,
+        strict: bool,
     ) -> Result<Self, TransactionDecodeError> {
         let mut decoder = Decoder::new(bytes);
-        let tx = Self::decode_from(&mut decoder, include_signatures)?;
+        let tx = Self::decode_from_with(&mut decoder, include_signatures, strict)?;
         if !decoder.is_empty() {
             return Err(TransactionDecodeError::Decode(DecodeError::TrailingBytes));
         }
@@ -562,6 +702,16 @@ impl Transaction {
     pub(crate) fn decode_from(
         decoder: &mut Decoder,
         include_signatures: bool,
+    ) -> Result<Self, TransactionDecodeError> {
+        Self::decode_from_with(decoder, include_signatures, true)
+    }
+
+    /// With `strict` unset, an unknown overwintered format consumes the rest of `decoder` into
+    /// `raw_tail`, so it is only usable for a standalone transaction.
+    fn decode_from_with(
+        decoder: &mut Decoder,
+        include_signatures: bool,
+        strict: bool,
     ) -> Result<Self, TransactionDecodeError> {
         let header = decoder.read_u32_le()?;
         let f_overwintered = (header >> 31) != 0;
@@ -578,9 +728,33 @@ impl Transaction {
             f_overwintered && version_group_id == SAPLING_VERSION_GROUP_ID && version == 4;
 
         if f_overwintered && !(is_overwinter_v3 || is_sapling_v4) {
-            return Err(TransactionDecodeError::InvalidTransactionFormat(
-                "unknown overwinter transaction format",
-            ));
+            if strict {
+                return Err(TransactionDecodeError::InvalidTransactionFormat(
+                    "unknown overwinter transaction format",
+                ));
+            }
+            let vin = read_vec(decoder)?;
+            let vout = read_vec(decoder)?;
+            let lock_time = decoder.read_u32_le()?;
+            let raw_tail = decoder.read_bytes(decoder.remaining())?;
+            return Ok(Transaction {
+                f_overwintered,
+                version,
+                version_group_id,
+                vin,
+                vout,
+                lock_time,
+                expiry_height: 0,
+                value_balance: 0,
+                shielded_spends: Vec::new(),
+                shielded_outputs: Vec::new(),
+                join_splits: Vec::new(),
+                join_split_pub_key: [0u8; 32],
+                join_split_sig: [0u8; 64],
+                binding_sig: [0u8; 64],
+                raw_tail,
+                fluxnode: None,
+            });
         }
 
         if version == FLUXNODE_TX_VERSION {
@@ -605,6 +779,7 @@ impl Transaction {
                 join_split_pub_key: [0u8; 32],
                 join_split_sig: [0u8; 64],
                 binding_sig: [0u8; 64],
+                raw_tail: Vec::new(),
                 fluxnode: Some(FluxnodeTx::V5(fluxnode)),
             });
         }
@@ -631,6 +806,7 @@ impl Transaction {
                 join_split_pub_key: [0u8; 32],
                 join_split_sig: [0u8; 64],
                 binding_sig: [0u8; 64],
+                raw_tail: Vec::new(),
                 fluxnode: Some(FluxnodeTx::V6(fluxnode)),
             });
         }
@@ -654,8 +830,7 @@ impl Transaction {
         };
 
         let (join_splits, join_split_pub_key, join_split_sig) = if version >= 2 {
-            let count = decoder.read_varint()?;
-            let count = usize::try_from(count).map_err(|_| DecodeError::SizeTooLarge)?;
+            let count = decoder.read_length(1)?;
             let use_groth = f_overwintered && version >= 4;
             let mut join_splits = Vec::with_capacity(count);
             for _ in 0..count {
@@ -694,6 +869,7 @@ impl Transaction {
             join_split_pub_key,
             join_split_sig,
             binding_sig,
+            raw_tail: Vec::new(),
             fluxnode: None,
         })
     }
@@ -707,8 +883,7 @@ fn write_vec<T: Encodable>(encoder: &mut Encoder, values: &[T]) {
 }
 
 fn read_vec<T: Decodable>(decoder: &mut Decoder) -> Result<Vec<T>, DecodeError> {
-    let count = decoder.read_varint()?;
-    let count = usize::try_from(count).map_err(|_| DecodeError::SizeTooLarge)?;
+    let count = decoder.read_length(1)?;
     let mut values = Vec::with_capacity(count);
     for _ in 0..count {
         values.push(T::consensus_decode(decoder)?);
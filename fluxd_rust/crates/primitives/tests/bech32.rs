@@ -0,0 +1,74 @@
+use fluxd_primitives::{decode_bech32, encode_bech32, AddressError};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[test]
+fn decodes_bip173_v0_vector() {
+    let (hrp, version, program) =
+        decode_bech32("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").expect("decode");
+    assert_eq!(hrp, "bc");
+    assert_eq!(version, 0);
+    assert_eq!(hex(&program), "751e76e8199196d454941c45d1b3a323f1433bd6");
+}
+
+#[test]
+fn decodes_bip350_v1_vector() {
+    let (hrp, version, program) =
+        decode_bech32("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0")
+            .expect("decode");
+    assert_eq!(hrp, "bc");
+    assert_eq!(version, 1);
+    assert_eq!(
+        hex(&program),
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+    );
+}
+
+#[test]
+fn encode_roundtrips_each_variant() {
+    for (version, program) in [
+        (0u8, vec![0x11u8; 20]),
+        (0, vec![0x22; 32]),
+        (1, vec![0x33; 32]),
+    ] {
+        let address = encode_bech32("tb", version, &program).expect("encode");
+        let (hrp, decoded_version, decoded_program) = decode_bech32(&address).expect("decode");
+        assert_eq!(hrp, "tb");
+        assert_eq!(decoded_version, version);
+        assert_eq!(decoded_program, program);
+    }
+}
+
+#[test]
+fn rejects_tampered_witness_version() {
+    let address = encode_bech32("bc", 0, &[0x44; 20]).expect("encode");
+    let mut chars: Vec<char> = address.chars().collect();
+    chars[3] = 'p';
+    let tampered: String = chars.into_iter().collect();
+    assert!(matches!(
+        decode_bech32(&tampered),
+        Err(AddressError::InvalidChecksum)
+    ));
+}
+
+#[test]
+fn rejects_malformed_inputs() {
+    assert!(matches!(
+        decode_bech32("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5"),
+        Err(AddressError::InvalidChecksum)
+    ));
+    assert!(matches!(
+        decode_bech32("bc1QW508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+        Err(AddressError::InvalidCharacter)
+    ));
+    assert!(matches!(
+        encode_bech32("bc", 17, &[0x55; 20]),
+        Err(AddressError::InvalidWitnessVersion)
+    ));
+    assert!(matches!(
+        encode_bech32("bc", 0, &[0x66; 21]),
+        Err(AddressError::InvalidLength)
+    ));
+}
@@ -0,0 +1,138 @@
+use std::io::{self, Read};
+
+use fluxd_primitives::block::{Block, BlockDecodeError, BlockHeader};
+use fluxd_primitives::encoding::DecodeError;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+
+/// Hands out at most `step` bytes per `read` call.
+struct Trickle<'a> {
+    bytes: &'a [u8],
+    step: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.step).min(self.bytes.len());
+        buf[..len].copy_from_slice(&self.bytes[..len]);
+        self.bytes = &self.bytes[len..];
+        Ok(len)
+    }
+}
+
+fn tx(seed: u8, script_len: usize) -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version: 1,
+        version_group_id: 0,
+        vin: vec![TxIn {
+            prevout: OutPoint {
+                hash: [seed; 32],
+                index: 0,
+            },
+            script_sig: vec![seed; script_len],
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value: 50,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+fn header() -> BlockHeader {
+    BlockHeader {
+        version: 4,
+        prev_block: [0x01; 32],
+        merkle_root: [0x02; 32],
+        final_sapling_root: [0x03; 32],
+        time: 1_600_000_000,
+        bits: 0x1f07ffff,
+        nonce: [0x04; 32],
+        solution: vec![0xaa; 400],
+        nodes_collateral: OutPoint::null(),
+        block_sig: Vec::new(),
+    }
+}
+
+fn sample_block_bytes() -> Vec<u8> {
+    let block = Block {
+        header: header(),
+        transactions: vec![tx(1, 2), tx(2, 200_000), tx(3, 10)],
+    };
+    block.consensus_encode().expect("encode block")
+}
+
+#[test]
+fn read_from_matches_slice_decode() {
+    let bytes = sample_block_bytes();
+    let expected = Block::consensus_decode(&bytes).expect("slice decode");
+
+    let block = Block::read_from(bytes.as_slice(), bytes.len()).expect("read_from");
+    assert_eq!(block.header, expected.header);
+    assert_eq!(block.transactions, expected.transactions);
+
+    let trickle = Trickle {
+        bytes: &bytes,
+        step: 7,
+    };
+    let block = Block::read_from(trickle, bytes.len()).expect("read_from trickle");
+    assert_eq!(block.transactions, expected.transactions);
+}
+
+#[test]
+fn read_from_enforces_size_cap() {
+    let bytes = sample_block_bytes();
+    let err = Block::read_from(bytes.as_slice(), bytes.len() - 1).unwrap_err();
+    assert!(
+        matches!(err, BlockDecodeError::Decode(DecodeError::SizeTooLarge)),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn read_from_rejects_bogus_tx_count() {
+    let mut bytes = header().consensus_encode();
+    // 1,000,000 transactions claimed with nothing behind them.
+    bytes.extend_from_slice(&[0xfe, 0x40, 0x42, 0x0f, 0x00]);
+    let err = Block::read_from(bytes.as_slice(), 4_096).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            BlockDecodeError::Decode(DecodeError::LengthExceedsInput)
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn read_from_rejects_truncated_and_trailing_input() {
+    let bytes = sample_block_bytes();
+    let err = Block::read_from(&bytes[..bytes.len() - 1], bytes.len()).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            BlockDecodeError::Transaction(_) | BlockDecodeError::Decode(_)
+        ),
+        "{err:?}"
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    let err = Block::read_from(trailing.as_slice(), trailing.len()).unwrap_err();
+    assert!(
+        matches!(err, BlockDecodeError::Decode(DecodeError::TrailingBytes)),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,18 @@
+use fluxd_primitives::hash::{ct_eq, sha256d};
+
+#[test]
+fn ct_eq_matches_slice_equality() {
+    let digest = sha256d(b"flux");
+    assert!(ct_eq(&digest, &digest));
+    assert!(ct_eq(&[], &[]));
+
+    let mut tampered = digest;
+    tampered[31] ^= 0x01;
+    assert!(!ct_eq(&digest, &tampered));
+    tampered = digest;
+    tampered[0] ^= 0x80;
+    assert!(!ct_eq(&digest, &tampered));
+
+    assert!(!ct_eq(&digest[..4], &digest[..5]));
+    assert!(!ct_eq(&digest, &[]));
+}
@@ -0,0 +1,46 @@
+use fluxd_primitives::{BlockHash, OutPoint, ParseHashError, ScriptHash, Txid};
+
+#[test]
+fn txid_display_is_reversed_and_round_trips() {
+    let mut internal = [0u8; 32];
+    internal[0] = 0x01;
+    internal[31] = 0xab;
+    let txid = Txid::from_internal(internal);
+    let hex = txid.to_string();
+    assert!(hex.starts_with("ab"));
+    assert!(hex.ends_with("01"));
+    assert_eq!(hex.parse::<Txid>(), Ok(txid));
+    assert_eq!(txid.to_internal(), internal);
+}
+
+#[test]
+fn block_hash_parses_rpc_order() {
+    let hex = "00000000000000000000000000000000000000000000000000000000000000ff";
+    let hash: BlockHash = hex.parse().expect("block hash");
+    assert_eq!(hash.as_internal()[0], 0xff);
+    assert_eq!(hash.to_string(), hex);
+}
+
+#[test]
+fn script_hash_display_keeps_digest_order() {
+    let digest = fluxd_primitives::sha256(b"script");
+    let hash = ScriptHash::from_internal(digest);
+    assert_eq!(hash.to_display_bytes(), digest);
+    assert_eq!(hash.to_string().parse::<ScriptHash>(), Ok(hash));
+}
+
+#[test]
+fn parse_rejects_bad_input() {
+    assert_eq!("abcd".parse::<Txid>(), Err(ParseHashError::InvalidLength));
+    let bad = "zz".repeat(32);
+    assert_eq!(bad.parse::<Txid>(), Err(ParseHashError::InvalidHex));
+}
+
+#[test]
+fn outpoint_exposes_typed_txid() {
+    let outpoint = OutPoint {
+        hash: [7u8; 32],
+        index: 1,
+    };
+    assert_eq!(outpoint.txid(), Txid::from_internal([7u8; 32]));
+}
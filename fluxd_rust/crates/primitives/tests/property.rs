@@ -1,5 +1,5 @@
 use fluxd_consensus::Hash256;
-use fluxd_primitives::encoding::{DecodeError, Decoder, Encoder};
+use fluxd_primitives::encoding::{DecodeError, Decoder, Encoder, MAX_COMPACT_SIZE};
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::transaction::{
     JoinSplit, OutputDescription, SpendDescription, SproutProof, Transaction, TxIn, TxOut,
@@ -7,8 +7,6 @@ use fluxd_primitives::transaction::{
     SAPLING_OUT_CIPHERTEXT_SIZE, SAPLING_VERSION_GROUP_ID, ZC_NOTE_CIPHERTEXT_SIZE,
 };
 
-const MAX_COMPACT_SIZE: u64 = 0x0200_0000;
-
 struct Lcg {
     state: u64,
 }
@@ -177,6 +175,7 @@ fn random_transaction(rng: &mut Lcg) -> Transaction {
         join_split_pub_key,
         join_split_sig,
         binding_sig,
+        raw_tail: Vec::new(),
         fluxnode: None,
     }
 }
@@ -218,6 +217,51 @@ fn compactsize_rejects_oversized() {
     assert_eq!(err, DecodeError::SizeTooLarge);
 }
 
+#[test]
+fn length_prefix_beyond_input_is_rejected_before_allocating() {
+    let bytes = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x01,
+    ];
+    let mut decoder = Decoder::new(&bytes);
+    let err = decoder
+        .read_var_bytes()
+        .expect_err("oversized length prefix");
+    assert_eq!(err, DecodeError::LengthExceedsInput);
+
+    let mut decoder = Decoder::new(&bytes);
+    let err = decoder
+        .read_length(32)
+        .expect_err("oversized element count");
+    assert_eq!(err, DecodeError::LengthExceedsInput);
+
+    let err =
+        Transaction::consensus_decode(&[0x01, 0x00, 0x00, 0x00, 0xfe, 0xff, 0xff, 0xff, 0x7f])
+            .expect_err("oversized vin count");
+    assert!(err
+        .to_string()
+        .contains("length prefix exceeds remaining input"));
+}
+
+#[test]
+fn decoder_limit_caps_length_prefixes() {
+    let mut encoder = Encoder::new();
+    encoder.write_var_bytes(&[0xab; 64]);
+    let bytes = encoder.into_inner();
+
+    let mut decoder = Decoder::with_limit(&bytes, 32);
+    assert_eq!(
+        decoder.read_var_bytes().expect_err("over limit"),
+        DecodeError::LengthExceedsInput
+    );
+
+    let mut decoder = Decoder::with_limit(&bytes, 64);
+    assert_eq!(
+        decoder.read_var_bytes().expect("within limit"),
+        vec![0xab; 64]
+    );
+    assert!(decoder.is_empty());
+}
+
 #[test]
 fn randomized_transaction_roundtrip() {
     let mut rng = Lcg::new(0x1234_5678);
@@ -228,3 +272,43 @@ fn randomized_transaction_roundtrip() {
         assert_eq!(decoded, tx);
     }
 }
+
+#[test]
+fn mutated_transactions_decode_canonically_or_fail() {
+    let mut rng = Lcg::new(0x0ca7_f00d);
+    let mut decoded_mutants = 0;
+    for _ in 0..200 {
+        let tx = random_transaction(&mut rng);
+        let encoded = tx.consensus_encode().expect("encode random tx");
+        assert!(Transaction::reencode_matches(&encoded));
+
+        for _ in 0..8 {
+            let mut mutated = encoded.clone();
+            match rng.gen_range(3) {
+                0 => {
+                    let index = rng.gen_range(mutated.len());
+                    mutated[index] ^= 1 << rng.gen_range(8);
+                }
+                1 => mutated.truncate(rng.gen_range(mutated.len())),
+                _ => {
+                    let index = rng.gen_range(mutated.len() + 1);
+                    mutated.insert(index, rng.next_u8());
+                }
+            }
+            // Fluxnode payloads carry a bool flag that is not canonical; every other format
+            // must either reject the bytes or reproduce them exactly.
+            let Ok(decoded) = Transaction::consensus_decode(&mutated) else {
+                continue;
+            };
+            if decoded.fluxnode.is_some() {
+                continue;
+            }
+            decoded_mutants += 1;
+            assert!(
+                Transaction::reencode_matches(&mutated),
+                "non-canonical decode of {mutated:02x?}"
+            );
+        }
+    }
+    assert!(decoded_mutants > 0);
+}
@@ -0,0 +1,140 @@
+use fluxd_consensus::constants::LOCKTIME_VERIFY_SEQUENCE;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{
+    sequence_to_relative_lock, RelativeLock, SequenceLockPrevout, SequenceLocks, Transaction, TxIn,
+    SEQUENCE_LOCKTIME_DISABLE_FLAG, SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
+
+fn tx_with_sequences(version: i32, sequences: &[u32]) -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version,
+        version_group_id: 0,
+        vin: sequences
+            .iter()
+            .enumerate()
+            .map(|(index, sequence)| TxIn {
+                prevout: OutPoint {
+                    hash: [0x11; 32],
+                    index: index as u32,
+                },
+                script_sig: Vec::new(),
+                sequence: *sequence,
+            })
+            .collect(),
+        vout: Vec::new(),
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+#[test]
+fn decodes_bip68_sequence_vectors() {
+    assert_eq!(sequence_to_relative_lock(u32::MAX), None);
+    assert_eq!(
+        sequence_to_relative_lock(SEQUENCE_LOCKTIME_DISABLE_FLAG),
+        None
+    );
+    assert_eq!(
+        sequence_to_relative_lock(SEQUENCE_LOCKTIME_DISABLE_FLAG | SEQUENCE_LOCKTIME_TYPE_FLAG | 1),
+        None
+    );
+    assert_eq!(sequence_to_relative_lock(0), Some(RelativeLock::Blocks(0)));
+    assert_eq!(
+        sequence_to_relative_lock(10),
+        Some(RelativeLock::Blocks(10))
+    );
+    assert_eq!(
+        sequence_to_relative_lock(0x0000_ffff),
+        Some(RelativeLock::Blocks(0xffff))
+    );
+    // Bits outside the type flag and value mask are ignored.
+    assert_eq!(
+        sequence_to_relative_lock(0x003f_0005),
+        Some(RelativeLock::Blocks(5))
+    );
+    assert_eq!(
+        sequence_to_relative_lock(SEQUENCE_LOCKTIME_TYPE_FLAG | 1),
+        Some(RelativeLock::Seconds(512))
+    );
+    assert_eq!(
+        sequence_to_relative_lock(SEQUENCE_LOCKTIME_TYPE_FLAG | 0xffff),
+        Some(RelativeLock::Seconds(0xffff * 512))
+    );
+}
+
+#[test]
+fn sequence_locks_take_the_strictest_input() {
+    let tx = tx_with_sequences(
+        2,
+        &[
+            10,
+            3,
+            SEQUENCE_LOCKTIME_TYPE_FLAG | 2,
+            SEQUENCE_LOCKTIME_DISABLE_FLAG | 500,
+        ],
+    );
+    let prevouts = [
+        SequenceLockPrevout {
+            height: 100,
+            median_time_past: 1_000,
+        },
+        SequenceLockPrevout {
+            height: 110,
+            median_time_past: 1_100,
+        },
+        SequenceLockPrevout {
+            height: 90,
+            median_time_past: 1_000,
+        },
+        SequenceLockPrevout {
+            height: 1,
+            median_time_past: 0,
+        },
+    ];
+    assert_eq!(
+        tx.sequence_locks(&prevouts),
+        Some(SequenceLocks {
+            min_height: 112,
+            min_time: 2_023,
+        })
+    );
+    assert_eq!(tx.sequence_locks(&prevouts[..3]), None);
+
+    let flags = LOCKTIME_VERIFY_SEQUENCE;
+    assert!(!tx.check_sequence_locks(flags, &prevouts, 112, 5_000));
+    assert!(!tx.check_sequence_locks(flags, &prevouts, 113, 2_023));
+    assert!(tx.check_sequence_locks(flags, &prevouts, 113, 2_024));
+    assert!(!tx.check_sequence_locks(flags, &prevouts[..3], 113, 2_024));
+}
+
+#[test]
+fn sequence_locks_are_gated_by_version_and_flag() {
+    let prevouts = [SequenceLockPrevout {
+        height: 100,
+        median_time_past: 1_000,
+    }];
+
+    let v1 = tx_with_sequences(1, &[10]);
+    assert_eq!(
+        v1.sequence_locks(&prevouts),
+        Some(SequenceLocks {
+            min_height: -1,
+            min_time: -1,
+        })
+    );
+    assert!(v1.check_sequence_locks(LOCKTIME_VERIFY_SEQUENCE, &prevouts, 101, 1_000));
+
+    let v2 = tx_with_sequences(2, &[10]);
+    assert!(!v2.check_sequence_locks(LOCKTIME_VERIFY_SEQUENCE, &prevouts, 101, 1_000));
+    assert!(v2.check_sequence_locks(0, &prevouts, 101, 1_000));
+}
@@ -1,9 +1,11 @@
 use fluxd_consensus::Hash256;
-use fluxd_primitives::block::{BlockHeader, PON_VERSION};
+use fluxd_primitives::block::{Block, BlockHeader, PON_VERSION};
 use fluxd_primitives::outpoint::OutPoint;
 use fluxd_primitives::transaction::{
-    FluxnodeStartV5, FluxnodeTx, FluxnodeTxV5, JoinSplit, OutputDescription, SpendDescription,
-    SproutProof, Transaction, TxIn, TxOut, FLUXNODE_TX_VERSION, GROTH_PROOF_SIZE, PHGR_PROOF_SIZE,
+    FluxnodeDelegates, FluxnodeStartV5, FluxnodeStartV6, FluxnodeStartVariantV6, FluxnodeTx,
+    FluxnodeTxV5, FluxnodeTxV6, JoinSplit, OutputDescription, SpendDescription, SproutProof,
+    Transaction, TxIn, TxOut, FLUXNODE_TX_FEATURE_DELEGATES_BIT, FLUXNODE_TX_TYPE_NORMAL_BIT,
+    FLUXNODE_TX_UPGRADEABLE_VERSION, FLUXNODE_TX_VERSION, GROTH_PROOF_SIZE, PHGR_PROOF_SIZE,
     SAPLING_ENC_CIPHERTEXT_SIZE, SAPLING_OUT_CIPHERTEXT_SIZE, SAPLING_VERSION_GROUP_ID,
     ZC_NOTE_CIPHERTEXT_SIZE,
 };
@@ -119,6 +121,7 @@ fn serialize_transaction_v1() {
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -160,6 +163,7 @@ fn serialize_fluxnode_v5_start() {
         join_split_pub_key: [0u8; 32],
         join_split_sig: [0u8; 64],
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: Some(FluxnodeTx::V5(FluxnodeTxV5::Start(FluxnodeStartV5 {
             collateral: OutPoint {
                 hash: seq_hash(0x00),
@@ -225,6 +229,7 @@ fn roundtrip_join_split_phgr() {
         join_split_pub_key: seq_array::<32>(0xc0),
         join_split_sig: seq_array::<64>(0xd0),
         binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -284,6 +289,7 @@ fn roundtrip_sapling_v4() {
         join_split_pub_key: seq_array::<32>(0x2d),
         join_split_sig: seq_array::<64>(0x2e),
         binding_sig: seq_array::<64>(0x2f),
+        raw_tail: Vec::new(),
         fluxnode: None,
     };
 
@@ -291,3 +297,159 @@ fn roundtrip_sapling_v4() {
     let decoded = Transaction::consensus_decode(&encoded).expect("decode sapling tx");
     assert_eq!(decoded, tx);
 }
+
+#[test]
+fn lenient_decode_preserves_unknown_version_group() {
+    let mut encoded = Vec::new();
+    encoded.extend_from_slice(&(0x8000_0005u32).to_le_bytes());
+    encoded.extend_from_slice(&0x26A7_270Au32.to_le_bytes());
+    // One input spending output 2 of `[0x11; 32]`, one output, then the lock time.
+    encoded.push(1);
+    encoded.extend_from_slice(&[0x11; 32]);
+    encoded.extend_from_slice(&2u32.to_le_bytes());
+    encoded.extend_from_slice(&[0x01, 0x51]);
+    encoded.extend_from_slice(&u32::MAX.to_le_bytes());
+    encoded.push(1);
+    encoded.extend_from_slice(&5_000i64.to_le_bytes());
+    encoded.extend_from_slice(&[0x01, 0x51]);
+    encoded.extend_from_slice(&7u32.to_le_bytes());
+    encoded.extend_from_slice(&[0x01, 0x02, 0x03, 0x00, 0xff, 0x7f]);
+
+    let err = Transaction::consensus_decode(&encoded).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unknown overwinter transaction format"),
+        "{err}"
+    );
+
+    let tx = Transaction::consensus_decode_lenient(&encoded).expect("lenient decode");
+    assert!(tx.f_overwintered);
+    assert_eq!(tx.version, 5);
+    assert_eq!(tx.version_group_id, 0x26A7_270A);
+    assert!(tx.is_unknown_format());
+    assert_eq!(
+        tx.vin,
+        vec![TxIn {
+            prevout: OutPoint {
+                hash: [0x11; 32],
+                index: 2,
+            },
+            script_sig: vec![0x51],
+            sequence: u32::MAX,
+        }]
+    );
+    assert_eq!(
+        tx.vout,
+        vec![TxOut {
+            value: 5_000,
+            script_pubkey: vec![0x51],
+        }]
+    );
+    assert_eq!(tx.lock_time, 7);
+    assert_eq!(tx.raw_tail, vec![0x01, 0x02, 0x03, 0x00, 0xff, 0x7f]);
+    assert_eq!(tx.consensus_encode().expect("re-encode"), encoded);
+
+    // Without a preserved tail the format cannot be written back.
+    let mut bare = tx.clone();
+    bare.raw_tail.clear();
+    let err = bare.consensus_encode().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unknown overwinter transaction format"),
+        "{err}"
+    );
+
+    let mut sapling = Vec::new();
+    sapling.extend_from_slice(&(0x8000_0004u32).to_le_bytes());
+    sapling.extend_from_slice(&SAPLING_VERSION_GROUP_ID.to_le_bytes());
+    // No inputs, outputs, shielded data or joinsplits.
+    sapling.extend_from_slice(&[0u8; 21]);
+    let known = Transaction::consensus_decode_lenient(&sapling).expect("lenient sapling decode");
+    assert!(!known.is_unknown_format());
+    assert!(known.raw_tail.is_empty());
+    assert_eq!(
+        known.consensus_encode().expect("re-encode sapling"),
+        sapling
+    );
+}
+
+#[test]
+fn reencode_matches_rejects_noncanonical_delegates_flag() {
+    let tx = Transaction {
+        f_overwintered: false,
+        version: FLUXNODE_TX_UPGRADEABLE_VERSION,
+        version_group_id: 0,
+        vin: Vec::new(),
+        vout: Vec::new(),
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: Some(FluxnodeTx::V6(FluxnodeTxV6::Start(FluxnodeStartV6 {
+            flux_tx_version: FLUXNODE_TX_TYPE_NORMAL_BIT | FLUXNODE_TX_FEATURE_DELEGATES_BIT,
+            variant: FluxnodeStartVariantV6::Normal {
+                collateral: OutPoint {
+                    hash: seq_hash(0x00),
+                    index: 0,
+                },
+                collateral_pubkey: vec![0x02, 0x03],
+                pubkey: vec![0x04, 0x05],
+                sig_time: 0x0a0b0c0d,
+                sig: vec![0xaa],
+            },
+            using_delegates: true,
+            delegates: Some(FluxnodeDelegates {
+                version: 0,
+                kind: FluxnodeDelegates::NONE,
+                delegate_starting_keys: Vec::new(),
+            }),
+        }))),
+    };
+    let canonical = tx.consensus_encode().expect("encode fluxnode v6 tx");
+    assert!(Transaction::reencode_matches(&canonical));
+
+    // The delegates flag is read as "any nonzero byte", but always written back as 1.
+    let flag_offset = canonical.len() - 3;
+    assert_eq!(canonical[flag_offset], 1);
+    let mut noncanonical = canonical.clone();
+    noncanonical[flag_offset] = 2;
+    let decoded = Transaction::consensus_decode(&noncanonical).expect("decode noncanonical tx");
+    assert_eq!(decoded, tx);
+    assert!(!Transaction::reencode_matches(&noncanonical));
+
+    let header = BlockHeader {
+        version: 4,
+        prev_block: seq_hash(0x00),
+        merkle_root: seq_hash(0x20),
+        final_sapling_root: seq_hash(0x40),
+        time: 0x01020304,
+        bits: 0x0a0b0c0d,
+        nonce: seq_hash(0x60),
+        solution: vec![0xaa, 0xbb, 0xcc],
+        nodes_collateral: OutPoint::null(),
+        block_sig: Vec::new(),
+    };
+    let header_bytes = header.consensus_encode();
+    assert!(BlockHeader::reencode_matches(&header_bytes));
+    let mut trailing = header_bytes.clone();
+    trailing.push(0);
+    assert!(!BlockHeader::reencode_matches(&trailing));
+
+    let block = Block {
+        header,
+        transactions: vec![tx],
+    };
+    let block_bytes = block.consensus_encode().expect("encode block");
+    assert!(Block::reencode_matches(&block_bytes));
+    let mut noncanonical_block = block_bytes.clone();
+    let flag_offset = noncanonical_block.len() - 3;
+    noncanonical_block[flag_offset] = 2;
+    assert!(Block::consensus_decode(&noncanonical_block).is_ok());
+    assert!(!Block::reencode_matches(&noncanonical_block));
+}
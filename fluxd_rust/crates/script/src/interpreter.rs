@@ -1,7 +1,10 @@
 //! Script interpreter and validation.
 
 use fluxd_primitives::hash::{sha256, sha256d};
-use fluxd_primitives::transaction::Transaction;
+use fluxd_primitives::transaction::{
+    Transaction, SEQUENCE_LOCKTIME_DISABLE_FLAG, SEQUENCE_LOCKTIME_MASK,
+    SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
 use ripemd::{Digest as RipemdDigest, Ripemd160};
 use secp256k1::{ecdsa::Signature, Message, PublicKey};
 use sha1::Sha1;
@@ -21,6 +24,9 @@ pub const SCRIPT_VERIFY_MINIMALDATA: ScriptFlags = 1 << 6;
 pub const SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS: ScriptFlags = 1 << 7;
 pub const SCRIPT_VERIFY_CLEANSTACK: ScriptFlags = 1 << 8;
 pub const SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY: ScriptFlags = 1 << 9;
+/// BIP112 relative locktime. Flux has no BIP68 sequence locks, so this is in neither the standard
+/// nor the block flags and `OP_CHECKSEQUENCEVERIFY` stays `OP_NOP3` on the network.
+pub const SCRIPT_VERIFY_CHECKSEQUENCEVERIFY: ScriptFlags = 1 << 10;
 
 pub const MANDATORY_SCRIPT_VERIFY_FLAGS: ScriptFlags = SCRIPT_VERIFY_P2SH;
 pub const STANDARD_SCRIPT_VERIFY_FLAGS: ScriptFlags = MANDATORY_SCRIPT_VERIFY_FLAGS
@@ -34,6 +40,15 @@ pub const STANDARD_SCRIPT_VERIFY_FLAGS: ScriptFlags = MANDATORY_SCRIPT_VERIFY_FL
 pub const BLOCK_SCRIPT_VERIFY_FLAGS: ScriptFlags =
     SCRIPT_VERIFY_P2SH | SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY;
 
+// Consensus limits, enforced regardless of the verify flags.
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+/// Non-push opcodes (anything above `OP_16`) per script; multisig also counts its keys.
+pub const MAX_OPS_PER_SCRIPT: usize = 201;
+/// Elements on the stack after any opcode. There is no altstack support, so this is the
+/// main stack alone.
+pub const MAX_STACK_SIZE: usize = 1_000;
+pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
 const OP_0: u8 = 0x00;
 const OP_1NEGATE: u8 = 0x4f;
 const OP_PUSHDATA1: u8 = 0x4c;
@@ -61,11 +76,12 @@ const OP_CHECKSIG: u8 = 0xac;
 const OP_CHECKSIGVERIFY: u8 = 0xad;
 const OP_CHECKMULTISIG: u8 = 0xae;
 const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+const OP_NOP: u8 = 0x61;
 const OP_VERIFY: u8 = 0x69;
 const OP_RETURN: u8 = 0x6a;
 const OP_NOP1: u8 = 0xb0;
 const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
-const OP_NOP3: u8 = 0xb2;
+const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
 const OP_NOP4: u8 = 0xb3;
 const OP_NOP10: u8 = 0xb9;
 
@@ -80,8 +96,13 @@ pub enum ScriptError {
     SigCheck,
     SigPushOnly,
     NullDummy,
-    LockTime,
+    NegativeLocktime,
+    UnsatisfiedLocktime,
     MinimalData,
+    ScriptSize,
+    OpCount,
+    StackSize,
+    PushSize,
     ScriptError(&'static str),
 }
 
@@ -97,8 +118,13 @@ impl std::fmt::Display for ScriptError {
             ScriptError::SigCheck => write!(f, "signature check failed"),
             ScriptError::SigPushOnly => write!(f, "scriptSig is not push-only"),
             ScriptError::NullDummy => write!(f, "null dummy element required"),
-            ScriptError::LockTime => write!(f, "locktime check failed"),
+            ScriptError::NegativeLocktime => write!(f, "negative locktime"),
+            ScriptError::UnsatisfiedLocktime => write!(f, "locktime requirement not satisfied"),
             ScriptError::MinimalData => write!(f, "non-minimal push"),
+            ScriptError::ScriptSize => write!(f, "script is too large"),
+            ScriptError::OpCount => write!(f, "operation limit exceeded"),
+            ScriptError::StackSize => write!(f, "stack size limit exceeded"),
+            ScriptError::PushSize => write!(f, "push value size limit exceeded"),
             ScriptError::ScriptError(message) => write!(f, "{message}"),
         }
     }
@@ -115,23 +141,45 @@ pub fn verify_script(
     flags: ScriptFlags,
     consensus_branch_id: u32,
 ) -> Result<(), ScriptError> {
-    if (flags & SCRIPT_VERIFY_SIGPUSHONLY) != 0 && !is_push_only(script_sig) {
-        return Err(ScriptError::SigPushOnly);
-    }
+    let checker = SignatureChecker::new(tx, input_index, amount, flags, consensus_branch_id);
+    verify_with_checker(script_sig, script_pubkey, &checker)
+}
 
+/// Runs the P2SH two-stage evaluation on its own: `script_sig` must be push-only, its stack must
+/// satisfy the `script_pubkey` hash check, and the last pushed item is then run as the redeem
+/// script against the rest of that stack. The redeem script is evaluated once and never as P2SH
+/// again, so nesting does not recurse. `SCRIPT_VERIFY_P2SH` is implied; the checker's other flags
+/// (such as `SCRIPT_VERIFY_CLEANSTACK`) apply as in [`verify_script`].
+pub fn verify_p2sh(
+    script_sig: &[u8],
+    script_pubkey: &[u8],
+    checker: &SignatureChecker<'_>,
+) -> Result<(), ScriptError> {
+    if !is_p2sh(script_pubkey) {
+        return Err(ScriptError::ScriptError("scriptPubKey is not P2SH"));
+    }
     let checker = SignatureChecker {
-        tx,
-        input_index,
-        amount,
-        flags,
-        consensus_branch_id,
+        flags: checker.flags | SCRIPT_VERIFY_P2SH,
+        ..*checker
     };
+    verify_with_checker(script_sig, script_pubkey, &checker)
+}
+
+fn verify_with_checker(
+    script_sig: &[u8],
+    script_pubkey: &[u8],
+    checker: &SignatureChecker<'_>,
+) -> Result<(), ScriptError> {
+    let flags = checker.flags;
+    if (flags & SCRIPT_VERIFY_SIGPUSHONLY) != 0 && !is_push_only(script_sig) {
+        return Err(ScriptError::SigPushOnly);
+    }
 
     let mut stack = Vec::new();
-    eval_script(script_sig, &mut stack, &checker)?;
+    run_script(script_sig, &mut stack, flags, Some(checker))?;
 
     let mut stack_copy = stack.clone();
-    eval_script(script_pubkey, &mut stack, &checker)?;
+    run_script(script_pubkey, &mut stack, flags, Some(checker))?;
 
     if stack.is_empty() || !cast_to_bool(stack.last().unwrap()) {
         return Err(ScriptError::EvalFalse);
@@ -146,7 +194,7 @@ pub fn verify_script(
         }
         let redeem_script = stack_copy.pop().ok_or(ScriptError::StackUnderflow)?;
         stack = stack_copy;
-        eval_script(&redeem_script, &mut stack, &checker)?;
+        run_script(&redeem_script, &mut stack, flags, Some(checker))?;
         if stack.is_empty() || !cast_to_bool(stack.last().unwrap()) {
             return Err(ScriptError::EvalFalse);
         }
@@ -159,7 +207,9 @@ pub fn verify_script(
     Ok(())
 }
 
-struct SignatureChecker<'a> {
+/// Signature and locktime checks for one transaction input.
+#[derive(Clone, Copy)]
+pub struct SignatureChecker<'a> {
     tx: &'a Transaction,
     input_index: usize,
     amount: i64,
@@ -168,6 +218,22 @@ struct SignatureChecker<'a> {
 }
 
 impl<'a> SignatureChecker<'a> {
+    pub fn new(
+        tx: &'a Transaction,
+        input_index: usize,
+        amount: i64,
+        flags: ScriptFlags,
+        consensus_branch_id: u32,
+    ) -> Self {
+        Self {
+            tx,
+            input_index,
+            amount,
+            flags,
+            consensus_branch_id,
+        }
+    }
+
     fn check_sig(
         &self,
         sig_bytes: &[u8],
@@ -236,34 +302,83 @@ impl<'a> SignatureChecker<'a> {
         if (tx_lock_time < LOCKTIME_THRESHOLD && lock_time >= LOCKTIME_THRESHOLD)
             || (tx_lock_time >= LOCKTIME_THRESHOLD && lock_time < LOCKTIME_THRESHOLD)
         {
-            return Err(ScriptError::LockTime);
+            return Err(ScriptError::UnsatisfiedLocktime);
         }
 
         if lock_time > tx_lock_time {
-            return Err(ScriptError::LockTime);
+            return Err(ScriptError::UnsatisfiedLocktime);
         }
 
         if self.tx.vin[self.input_index].sequence == u32::MAX {
-            return Err(ScriptError::LockTime);
+            return Err(ScriptError::UnsatisfiedLocktime);
+        }
+
+        Ok(())
+    }
+
+    fn check_sequence(&self, sequence: i64) -> Result<(), ScriptError> {
+        let tx_sequence = self.tx.vin[self.input_index].sequence as i64;
+        if self.tx.version < 2 || (tx_sequence & i64::from(SEQUENCE_LOCKTIME_DISABLE_FLAG)) != 0 {
+            return Err(ScriptError::UnsatisfiedLocktime);
+        }
+
+        let type_flag = i64::from(SEQUENCE_LOCKTIME_TYPE_FLAG);
+        let mask = type_flag | i64::from(SEQUENCE_LOCKTIME_MASK);
+        let tx_sequence = tx_sequence & mask;
+        let sequence = sequence & mask;
+        if (tx_sequence < type_flag) != (sequence < type_flag) {
+            return Err(ScriptError::UnsatisfiedLocktime);
+        }
+
+        if sequence > tx_sequence {
+            return Err(ScriptError::UnsatisfiedLocktime);
         }
 
         Ok(())
     }
 }
 
-fn eval_script(
+/// Evaluates a single script against `initial_stack` and returns the stack it leaves behind.
+/// Unlike [`verify_script`] this does not require a true top element, so it is suitable for
+/// inspecting intermediate results. Without a `checker`, signature opcodes push false and the
+/// locktime opcodes fail with [`ScriptError::UnsatisfiedLocktime`] when their flags are set.
+pub fn eval_script(
+    script: &[u8],
+    initial_stack: Vec<Vec<u8>>,
+    flags: ScriptFlags,
+    checker: Option<&SignatureChecker<'_>>,
+) -> Result<Vec<Vec<u8>>, ScriptError> {
+    let mut stack = initial_stack;
+    run_script(script, &mut stack, flags, checker)?;
+    Ok(stack)
+}
+
+fn run_script(
     script: &[u8],
     stack: &mut Vec<Vec<u8>>,
-    checker: &SignatureChecker<'_>,
+    flags: ScriptFlags,
+    checker: Option<&SignatureChecker<'_>>,
 ) -> Result<(), ScriptError> {
+    if script.len() > MAX_SCRIPT_SIZE {
+        return Err(ScriptError::ScriptSize);
+    }
     let mut cursor = 0usize;
     let mut script_code_start = 0usize;
     let mut exec_stack: Vec<bool> = Vec::new();
+    let mut op_count = 0usize;
     while cursor < script.len() {
         let opcode = script[cursor];
         cursor += 1;
         let exec = exec_stack.iter().all(|v| *v);
 
+        // Counted whether or not the branch executes.
+        if opcode > OP_16 {
+            op_count += 1;
+            if op_count > MAX_OPS_PER_SCRIPT {
+                return Err(ScriptError::OpCount);
+            }
+        }
+
         match opcode {
             OP_0 => {
                 if exec {
@@ -277,9 +392,9 @@ fn eval_script(
             }
             0x01..=0x4b => {
                 let len = opcode as usize;
-                let data = read_bytes(script, &mut cursor, len)?;
+                let data = read_push(script, &mut cursor, len)?;
                 if exec {
-                    if (checker.flags & SCRIPT_VERIFY_MINIMALDATA) != 0
+                    if (flags & SCRIPT_VERIFY_MINIMALDATA) != 0
                         && !check_minimal_push(&data, opcode)
                     {
                         return Err(ScriptError::MinimalData);
@@ -289,9 +404,9 @@ fn eval_script(
             }
             OP_PUSHDATA1 => {
                 let len = read_u8(script, &mut cursor)? as usize;
-                let data = read_bytes(script, &mut cursor, len)?;
+                let data = read_push(script, &mut cursor, len)?;
                 if exec {
-                    if (checker.flags & SCRIPT_VERIFY_MINIMALDATA) != 0
+                    if (flags & SCRIPT_VERIFY_MINIMALDATA) != 0
                         && !check_minimal_push(&data, opcode)
                     {
                         return Err(ScriptError::MinimalData);
@@ -301,9 +416,9 @@ fn eval_script(
             }
             OP_PUSHDATA2 => {
                 let len = read_u16(script, &mut cursor)? as usize;
-                let data = read_bytes(script, &mut cursor, len)?;
+                let data = read_push(script, &mut cursor, len)?;
                 if exec {
-                    if (checker.flags & SCRIPT_VERIFY_MINIMALDATA) != 0
+                    if (flags & SCRIPT_VERIFY_MINIMALDATA) != 0
                         && !check_minimal_push(&data, opcode)
                     {
                         return Err(ScriptError::MinimalData);
@@ -313,9 +428,9 @@ fn eval_script(
             }
             OP_PUSHDATA4 => {
                 let len = read_u32(script, &mut cursor)? as usize;
-                let data = read_bytes(script, &mut cursor, len)?;
+                let data = read_push(script, &mut cursor, len)?;
                 if exec {
-                    if (checker.flags & SCRIPT_VERIFY_MINIMALDATA) != 0
+                    if (flags & SCRIPT_VERIFY_MINIMALDATA) != 0
                         && !check_minimal_push(&data, opcode)
                     {
                         return Err(ScriptError::MinimalData);
@@ -452,19 +567,47 @@ fn eval_script(
                 if !exec {
                     continue;
                 }
-                if (checker.flags & SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY) != 0 {
+                if (flags & SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY) != 0 {
                     let locktime_bytes = stack.last().ok_or(ScriptError::StackUnderflow)?;
-                    let locktime = decode_script_num(locktime_bytes)?;
-                    checker.check_lock_time(locktime)?;
-                } else if (checker.flags & SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS) != 0 {
+                    // Five bytes, so locktimes up to 2^39-1 fit.
+                    let locktime = decode_script_num_sized(locktime_bytes, 5)?;
+                    if locktime < 0 {
+                        return Err(ScriptError::NegativeLocktime);
+                    }
+                    checker
+                        .ok_or(ScriptError::UnsatisfiedLocktime)?
+                        .check_lock_time(locktime)?;
+                } else if (flags & SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS) != 0 {
                     return Err(ScriptError::InvalidOpcode);
                 }
             }
-            OP_NOP1 | OP_NOP3 | OP_NOP4..=OP_NOP10 => {
+            OP_CHECKSEQUENCEVERIFY => {
                 if !exec {
                     continue;
                 }
-                if (checker.flags & SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS) != 0 {
+                if (flags & SCRIPT_VERIFY_CHECKSEQUENCEVERIFY) != 0 {
+                    let sequence_bytes = stack.last().ok_or(ScriptError::StackUnderflow)?;
+                    let sequence = decode_script_num_sized(sequence_bytes, 5)?;
+                    if sequence < 0 {
+                        return Err(ScriptError::NegativeLocktime);
+                    }
+                    // A disabled relative locktime leaves the opcode a NOP.
+                    if (sequence & i64::from(SEQUENCE_LOCKTIME_DISABLE_FLAG)) == 0 {
+                        checker
+                            .ok_or(ScriptError::UnsatisfiedLocktime)?
+                            .check_sequence(sequence)?;
+                    }
+                } else if (flags & SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS) != 0 {
+                    return Err(ScriptError::InvalidOpcode);
+                }
+            }
+            // Plain OP_NOP, unlike OP_NOP1..OP_NOP10, is a no-op in zcashd under every flag.
+            OP_NOP => {}
+            OP_NOP1 | OP_NOP4..=OP_NOP10 => {
+                if !exec {
+                    continue;
+                }
+                if (flags & SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS) != 0 {
                     return Err(ScriptError::InvalidOpcode);
                 }
             }
@@ -475,7 +618,10 @@ fn eval_script(
                 let pubkey = pop(stack)?;
                 let sig = pop(stack)?;
                 let script_code = &script[script_code_start..];
-                let ok = checker.check_sig(&sig, &pubkey, script_code)?;
+                let ok = match checker {
+                    Some(checker) => checker.check_sig(&sig, &pubkey, script_code)?,
+                    None => false,
+                };
                 if opcode == OP_CHECKSIGVERIFY {
                     if !ok {
                         return Err(ScriptError::SigCheck);
@@ -492,6 +638,10 @@ fn eval_script(
                 if !(0..=20).contains(&n) {
                     return Err(ScriptError::InvalidOpcode);
                 }
+                op_count += n as usize;
+                if op_count > MAX_OPS_PER_SCRIPT {
+                    return Err(ScriptError::OpCount);
+                }
                 let mut pubkeys = Vec::with_capacity(n as usize);
                 for _ in 0..n {
                     pubkeys.push(pop(stack)?);
@@ -508,7 +658,7 @@ fn eval_script(
                 sigs.reverse();
 
                 let dummy = pop(stack)?;
-                if (checker.flags & SCRIPT_VERIFY_NULLDUMMY) != 0 && !dummy.is_empty() {
+                if (flags & SCRIPT_VERIFY_NULLDUMMY) != 0 && !dummy.is_empty() {
                     return Err(ScriptError::NullDummy);
                 }
 
@@ -518,7 +668,10 @@ fn eval_script(
                     let sig = &sigs[sig_index];
                     let key = &pubkeys[key_index];
                     let script_code = &script[script_code_start..];
-                    let ok = checker.check_sig(sig, key, script_code)?;
+                    let ok = match checker {
+                        Some(checker) => checker.check_sig(sig, key, script_code)?,
+                        None => false,
+                    };
                     if ok {
                         sig_index += 1;
                     }
@@ -552,6 +705,10 @@ fn eval_script(
                 }
             }
         }
+
+        if stack.len() > MAX_STACK_SIZE {
+            return Err(ScriptError::StackSize);
+        }
     }
 
     if !exec_stack.is_empty() {
@@ -627,6 +784,15 @@ fn read_bytes(script: &[u8], cursor: &mut usize, len: usize) -> Result<Vec<u8>,
     Ok(out)
 }
 
+/// Reads push data, rejecting oversized elements even in unexecuted branches.
+fn read_push(script: &[u8], cursor: &mut usize, len: usize) -> Result<Vec<u8>, ScriptError> {
+    let data = read_bytes(script, cursor, len)?;
+    if data.len() > MAX_SCRIPT_ELEMENT_SIZE {
+        return Err(ScriptError::PushSize);
+    }
+    Ok(data)
+}
+
 fn read_u8(script: &[u8], cursor: &mut usize) -> Result<u8, ScriptError> {
     if *cursor >= script.len() {
         return Err(ScriptError::StackUnderflow);
@@ -647,10 +813,14 @@ fn read_u32(script: &[u8], cursor: &mut usize) -> Result<u32, ScriptError> {
 }
 
 fn decode_script_num(data: &[u8]) -> Result<i64, ScriptError> {
+    decode_script_num_sized(data, 4)
+}
+
+fn decode_script_num_sized(data: &[u8], max_len: usize) -> Result<i64, ScriptError> {
     if data.is_empty() {
         return Ok(0);
     }
-    if data.len() > 4 {
+    if data.len() > max_len {
         return Err(ScriptError::InvalidOpcode);
     }
     let mut result: i64 = 0;
@@ -3,7 +3,7 @@
 use fluxd_consensus::constants::SIGNED_MESSAGE_MAGIC;
 use fluxd_consensus::Hash256;
 use fluxd_primitives::encoding::Encoder;
-use fluxd_primitives::hash::sha256d;
+use fluxd_primitives::hash::{ct_eq, hash160, sha256d};
 use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
 use secp256k1::Message;
 
@@ -34,6 +34,46 @@ impl std::fmt::Display for SignedMessageError {
 
 impl std::error::Error for SignedMessageError {}
 
+/// Key type a compact signature's header byte declares, using the BIP137 ranges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignedMessageKeyType {
+    /// Header 27-30.
+    P2pkhUncompressed,
+    /// Header 31-34.
+    P2pkhCompressed,
+    /// Header 35-38 (compressed key, signed as a P2SH-wrapped P2WPKH address).
+    P2shP2wpkh,
+    /// Header 39-42 (compressed key, signed as a native P2WPKH address).
+    P2wpkh,
+}
+
+impl SignedMessageKeyType {
+    fn from_header(header: u8) -> Option<(Self, u8)> {
+        let key_type = match header {
+            27..=30 => SignedMessageKeyType::P2pkhUncompressed,
+            31..=34 => SignedMessageKeyType::P2pkhCompressed,
+            35..=38 => SignedMessageKeyType::P2shP2wpkh,
+            39..=42 => SignedMessageKeyType::P2wpkh,
+            _ => return None,
+        };
+        Some((key_type, (header - 27) % 4))
+    }
+
+    pub fn compressed(self) -> bool {
+        self != SignedMessageKeyType::P2pkhUncompressed
+    }
+}
+
+/// How a signature was matched by [`verify_signed_message_key_hash`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignedMessageMatch {
+    pub key_type: SignedMessageKeyType,
+    pub recovery_id: u8,
+    /// False when the header byte was wrong and the key was found by trying every recovery id
+    /// and both compression flags.
+    pub from_header: bool,
+}
+
 pub fn signed_message_hash(message: &[u8]) -> Hash256 {
     let mut encoder = Encoder::new();
     encoder.write_var_str(SIGNED_MESSAGE_MAGIC);
@@ -84,6 +124,64 @@ pub fn recover_signed_message_pubkey(
     }
 }
 
+/// Checks a compact signature against the key hash of a P2PKH address, tolerating the header
+/// conventions different wallets use. The recovery id and key type from the header byte are
+/// tried first; if they do not recover the key, all four recovery ids are tried with both a
+/// compressed and an uncompressed key. `Ok(None)` means no combination matched.
+pub fn verify_signed_message_key_hash(
+    expected_key_hash: &[u8; 20],
+    signature: &[u8],
+    message: &[u8],
+) -> Result<Option<SignedMessageMatch>, SignedMessageError> {
+    if signature.len() != 65 {
+        return Err(SignedMessageError::InvalidSignature);
+    }
+    let digest = signed_message_hash(message);
+    let msg =
+        Message::from_digest_slice(&digest).map_err(|_| SignedMessageError::InvalidMessage)?;
+    let compact = &signature[1..65];
+    let recovers_key = |recovery_id: u8, compressed: bool| -> Result<bool, SignedMessageError> {
+        let rec_id = RecoveryId::from_i32(i32::from(recovery_id))
+            .map_err(|_| SignedMessageError::InvalidRecoveryId)?;
+        let sig = RecoverableSignature::from_compact(compact, rec_id)
+            .map_err(|_| SignedMessageError::InvalidSignature)?;
+        let Ok(pubkey) = secp256k1_verify().recover_ecdsa(&msg, &sig) else {
+            return Ok(false);
+        };
+        let key_hash = if compressed {
+            hash160(&pubkey.serialize())
+        } else {
+            hash160(&pubkey.serialize_uncompressed())
+        };
+        Ok(ct_eq(&key_hash, expected_key_hash))
+    };
+
+    if let Some((key_type, recovery_id)) = SignedMessageKeyType::from_header(signature[0]) {
+        if recovers_key(recovery_id, key_type.compressed())? {
+            return Ok(Some(SignedMessageMatch {
+                key_type,
+                recovery_id,
+                from_header: true,
+            }));
+        }
+    }
+    for recovery_id in 0..4u8 {
+        for key_type in [
+            SignedMessageKeyType::P2pkhCompressed,
+            SignedMessageKeyType::P2pkhUncompressed,
+        ] {
+            if recovers_key(recovery_id, key_type.compressed())? {
+                return Ok(Some(SignedMessageMatch {
+                    key_type,
+                    recovery_id,
+                    from_header: false,
+                }));
+            }
+        }
+    }
+    Ok(None)
+}
+
 fn decode_compact_signature(
     signature: &[u8],
 ) -> Result<(RecoverableSignature, bool), SignedMessageError> {
@@ -137,6 +235,56 @@ mod tests {
         assert!(matches!(err, SignedMessageError::PubkeyMismatch));
     }
 
+    #[test]
+    fn key_hash_verification_reads_bip137_headers_and_falls_back() {
+        let secp = Secp256k1::signing_only();
+        let secret = SecretKey::from_slice(&[1u8; 32]).expect("secret");
+        let pubkey = secp256k1::PublicKey::from_secret_key(&secp, &secret);
+        let compressed_hash = hash160(&pubkey.serialize());
+        let uncompressed_hash = hash160(&pubkey.serialize_uncompressed());
+
+        let message = b"hello";
+        let digest = signed_message_hash(message);
+        let msg = Message::from_digest_slice(&digest).expect("msg");
+        let sig = secp.sign_ecdsa_recoverable(&msg, &secret);
+        let (rec_id, _) = sig.serialize_compact();
+        let recovery_id = rec_id.to_i32() as u8;
+
+        let mut segwit = encode_compact(&sig, true);
+        segwit[0] = 35 + recovery_id;
+        let matched = verify_signed_message_key_hash(&compressed_hash, &segwit, message)
+            .expect("verify")
+            .expect("match");
+        assert_eq!(matched.key_type, SignedMessageKeyType::P2shP2wpkh);
+        assert!(matched.from_header);
+        segwit[0] = 39 + recovery_id;
+        let matched = verify_signed_message_key_hash(&compressed_hash, &segwit, message)
+            .expect("verify")
+            .expect("match");
+        assert_eq!(matched.key_type, SignedMessageKeyType::P2wpkh);
+
+        // Uncompressed header for a compressed key, and a raw recovery id as the header.
+        let wrong_flag = encode_compact(&sig, false);
+        let matched = verify_signed_message_key_hash(&compressed_hash, &wrong_flag, message)
+            .expect("verify")
+            .expect("match");
+        assert_eq!(matched.key_type, SignedMessageKeyType::P2pkhCompressed);
+        assert_eq!(matched.recovery_id, recovery_id);
+        assert!(!matched.from_header);
+        let mut raw = encode_compact(&sig, true);
+        raw[0] = recovery_id;
+        let matched = verify_signed_message_key_hash(&uncompressed_hash, &raw, message)
+            .expect("verify")
+            .expect("match");
+        assert_eq!(matched.key_type, SignedMessageKeyType::P2pkhUncompressed);
+
+        assert_eq!(
+            verify_signed_message_key_hash(&[0u8; 20], &wrong_flag, message).expect("verify"),
+            None
+        );
+        assert!(verify_signed_message_key_hash(&compressed_hash, &raw[..64], message).is_err());
+    }
+
     #[test]
     fn recover_signed_message_pubkey_matches_compact_header() {
         let secp = Secp256k1::signing_only();
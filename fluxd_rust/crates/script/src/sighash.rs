@@ -1,6 +1,8 @@
 //! Signature hashing for transparent inputs.
 
 use blake2b_simd::Params as Blake2bParams;
+use fluxd_consensus::params::ConsensusParams;
+use fluxd_consensus::upgrades::consensus_rules;
 use fluxd_consensus::Hash256;
 use fluxd_primitives::encoding::{Encodable, Encoder};
 use fluxd_primitives::hash::sha256d;
@@ -27,11 +29,15 @@ impl SighashType {
     }
 }
 
+/// Digest algorithm used to sign a transparent input.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum SigVersion {
-    Sprout,
-    Overwinter,
-    Sapling,
+pub enum SighashVariant {
+    /// Pre-Overwinter double-SHA256 over a modified copy of the transaction.
+    Legacy,
+    /// ZIP-143 BLAKE2b digest for v3 (Overwinter) transactions.
+    Zip143 { consensus_branch_id: u32 },
+    /// ZIP-243 BLAKE2b digest for v4 (Sapling) transactions.
+    Zip243 { consensus_branch_id: u32 },
 }
 
 #[derive(Debug)]
@@ -68,6 +74,38 @@ const ZCASH_JOINSPLITS_HASH_PERSONALIZATION: [u8; 16] = *b"ZcashJSplitsHash";
 const ZCASH_SHIELDED_SPENDS_HASH_PERSONALIZATION: [u8; 16] = *b"ZcashSSpendsHash";
 const ZCASH_SHIELDED_OUTPUTS_HASH_PERSONALIZATION: [u8; 16] = *b"ZcashSOutputHash";
 
+/// Selects the sighash algorithm for `tx` when it is validated in a block at `height`.
+pub fn sighash_variant(
+    tx: &Transaction,
+    height: i32,
+    params: &ConsensusParams,
+) -> Result<SighashVariant, SighashError> {
+    sighash_variant_for_branch(tx, consensus_rules(height, params).branch_id)
+}
+
+/// As `sighash_variant`, for callers that already hold the consensus branch id.
+pub fn sighash_variant_for_branch(
+    tx: &Transaction,
+    consensus_branch_id: u32,
+) -> Result<SighashVariant, SighashError> {
+    if !tx.f_overwintered {
+        return Ok(SighashVariant::Legacy);
+    }
+    if tx.version_group_id == SAPLING_VERSION_GROUP_ID {
+        Ok(SighashVariant::Zip243 {
+            consensus_branch_id,
+        })
+    } else if tx.version_group_id == OVERWINTER_VERSION_GROUP_ID {
+        Ok(SighashVariant::Zip143 {
+            consensus_branch_id,
+        })
+    } else {
+        Err(SighashError::UnsupportedTransactionFormat(
+            "unknown overwintered version group id",
+        ))
+    }
+}
+
 pub fn signature_hash(
     tx: &Transaction,
     input_index: Option<usize>,
@@ -76,11 +114,23 @@ pub fn signature_hash(
     sighash_type: SighashType,
     consensus_branch_id: u32,
 ) -> Result<Hash256, SighashError> {
-    let sigversion = signature_hash_version(tx)?;
+    let variant = sighash_variant_for_branch(tx, consensus_branch_id)?;
+    sighash(tx, input_index, script_code, amount, sighash_type, variant)
+}
 
-    match sigversion {
-        SigVersion::Sprout => signature_hash_sprout(tx, input_index, script_code, sighash_type),
-        SigVersion::Overwinter => signature_hash_overwinter(
+pub fn sighash(
+    tx: &Transaction,
+    input_index: Option<usize>,
+    script_code: &[u8],
+    amount: i64,
+    sighash_type: SighashType,
+    variant: SighashVariant,
+) -> Result<Hash256, SighashError> {
+    match variant {
+        SighashVariant::Legacy => signature_hash_sprout(tx, input_index, script_code, sighash_type),
+        SighashVariant::Zip143 {
+            consensus_branch_id,
+        } => signature_hash_overwinter(
             tx,
             input_index,
             script_code,
@@ -89,7 +139,9 @@ pub fn signature_hash(
             consensus_branch_id,
             false,
         ),
-        SigVersion::Sapling => signature_hash_overwinter(
+        SighashVariant::Zip243 {
+            consensus_branch_id,
+        } => signature_hash_overwinter(
             tx,
             input_index,
             script_code,
@@ -101,22 +153,6 @@ pub fn signature_hash(
     }
 }
 
-fn signature_hash_version(tx: &Transaction) -> Result<SigVersion, SighashError> {
-    if tx.f_overwintered {
-        if tx.version_group_id == SAPLING_VERSION_GROUP_ID {
-            Ok(SigVersion::Sapling)
-        } else if tx.version_group_id == OVERWINTER_VERSION_GROUP_ID {
-            Ok(SigVersion::Overwinter)
-        } else {
-            Err(SighashError::UnsupportedTransactionFormat(
-                "unknown overwintered version group id",
-            ))
-        }
-    } else {
-        Ok(SigVersion::Sprout)
-    }
-}
-
 fn signature_hash_sprout(
     tx: &Transaction,
     input_index: Option<usize>,
@@ -7,10 +7,14 @@ pub enum ScriptType {
     P2Sh,
     P2Wpkh,
     P2Wsh,
+    /// Any other witness program (v0 with a non-standard length, or v1-16).
+    Witness,
     Unknown,
 }
 
 const OP_0: u8 = 0x00;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
 const OP_DUP: u8 = 0x76;
 const OP_HASH160: u8 = 0xa9;
 const OP_EQUAL: u8 = 0x87;
@@ -26,6 +30,8 @@ pub fn classify_script_pubkey(script: &[u8]) -> ScriptType {
         ScriptType::P2Wpkh
     } else if is_p2wsh(script) {
         ScriptType::P2Wsh
+    } else if is_witness_program(script) {
+        ScriptType::Witness
     } else if is_p2pk(script) {
         ScriptType::P2Pk
     } else {
@@ -54,6 +60,15 @@ fn is_p2wsh(script: &[u8]) -> bool {
     script.len() == 34 && script[0] == OP_0 && script[1] == 0x20
 }
 
+fn is_witness_program(script: &[u8]) -> bool {
+    if !(4..=42).contains(&script.len()) {
+        return false;
+    }
+    let version_op = script[0];
+    (version_op == OP_0 || (OP_1..=OP_16).contains(&version_op))
+        && script[1] as usize == script.len() - 2
+}
+
 fn is_p2pk(script: &[u8]) -> bool {
     let key_len = match script.first().copied() {
         Some(len @ 33) => len,
@@ -0,0 +1,476 @@
+use fluxd_primitives::hash::hash160;
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{Transaction, TxIn, TxOut};
+use fluxd_script::interpreter::{
+    eval_script, verify_p2sh, verify_script, ScriptError, ScriptFlags, SignatureChecker,
+    MAX_OPS_PER_SCRIPT, MAX_SCRIPT_ELEMENT_SIZE, MAX_SCRIPT_SIZE, MAX_STACK_SIZE,
+    SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY, SCRIPT_VERIFY_CHECKSEQUENCEVERIFY, SCRIPT_VERIFY_CLEANSTACK,
+    SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS, SCRIPT_VERIFY_NONE,
+};
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_1NEGATE: u8 = 0x4f;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_IF: u8 = 0x63;
+const OP_ENDIF: u8 = 0x68;
+const OP_NOP: u8 = 0x61;
+const OP_DROP: u8 = 0x75;
+const OP_DUP: u8 = 0x76;
+const OP_SIZE: u8 = 0x82;
+const OP_EQUAL: u8 = 0x87;
+const OP_RIPEMD160: u8 = 0xa6;
+const OP_SHA1: u8 = 0xa7;
+const OP_SHA256: u8 = 0xa8;
+const OP_HASH160: u8 = 0xa9;
+const OP_HASH256: u8 = 0xaa;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKMULTISIG: u8 = 0xae;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+
+fn spending_tx() -> Transaction {
+    Transaction {
+        f_overwintered: false,
+        version: 1,
+        version_group_id: 0,
+        vin: vec![TxIn {
+            prevout: OutPoint {
+                hash: [0x11; 32],
+                index: 0,
+            },
+            script_sig: Vec::new(),
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value: 0,
+            script_pubkey: Vec::new(),
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
+fn verify(script_sig: &[u8], script_pubkey: &[u8]) -> Result<(), ScriptError> {
+    verify_script(
+        script_sig,
+        script_pubkey,
+        &spending_tx(),
+        0,
+        0,
+        SCRIPT_VERIFY_NONE,
+        0,
+    )
+}
+
+fn pushdata2(len: usize) -> Vec<u8> {
+    let mut script = vec![OP_PUSHDATA2];
+    script.extend_from_slice(&(len as u16).to_le_bytes());
+    script.resize(script.len() + len, 0x01);
+    script
+}
+
+#[test]
+fn stack_limit_rejects_extra_element() {
+    let at_limit = vec![OP_1; MAX_STACK_SIZE - 1];
+    verify(&at_limit, &[OP_1]).expect("1000 elements");
+
+    let over_limit = vec![OP_1; MAX_STACK_SIZE + 1];
+    let err = verify(&over_limit, &[OP_1]).unwrap_err();
+    assert!(matches!(err, ScriptError::StackSize), "{err:?}");
+
+    let err = verify(&at_limit, &[OP_1, OP_1]).unwrap_err();
+    assert!(matches!(err, ScriptError::StackSize), "{err:?}");
+}
+
+#[test]
+fn script_size_limit() {
+    let oversized = vec![OP_1; MAX_SCRIPT_SIZE + 1];
+    let err = verify(&[], &oversized).unwrap_err();
+    assert!(matches!(err, ScriptError::ScriptSize), "{err:?}");
+}
+
+#[test]
+fn op_count_limit() {
+    let drops = |count: usize| {
+        let mut script = [OP_1, OP_DROP].repeat(count);
+        script.push(OP_1);
+        script
+    };
+    verify(&[], &drops(MAX_OPS_PER_SCRIPT)).expect("201 ops");
+    let err = verify(&[], &drops(MAX_OPS_PER_SCRIPT + 1)).unwrap_err();
+    assert!(matches!(err, ScriptError::OpCount), "{err:?}");
+
+    // Unexecuted opcodes still count.
+    let mut skipped = vec![OP_0, OP_IF];
+    skipped.extend([OP_DROP].repeat(MAX_OPS_PER_SCRIPT - 1));
+    skipped.extend([OP_ENDIF, OP_1]);
+    let err = verify(&[], &skipped).unwrap_err();
+    assert!(matches!(err, ScriptError::OpCount), "{err:?}");
+}
+
+#[test]
+fn op_count_includes_multisig_keys() {
+    let multisig = |drops: usize| {
+        let mut script = [OP_1, OP_DROP].repeat(drops);
+        script.extend([OP_0, OP_0]);
+        for _ in 0..20 {
+            script.push(33);
+            script.extend_from_slice(&[0x02; 33]);
+        }
+        script.extend([0x01, 20, OP_CHECKMULTISIG]);
+        script
+    };
+    // 0-of-20 multisig: one opcode plus twenty keys.
+    verify(&[], &multisig(MAX_OPS_PER_SCRIPT - 21)).expect("201 ops");
+    let err = verify(&[], &multisig(MAX_OPS_PER_SCRIPT - 20)).unwrap_err();
+    assert!(matches!(err, ScriptError::OpCount), "{err:?}");
+}
+
+#[test]
+fn push_size_limit() {
+    let mut at_limit = pushdata2(MAX_SCRIPT_ELEMENT_SIZE);
+    at_limit.extend([OP_DROP, OP_1]);
+    verify(&[], &at_limit).expect("520 byte push");
+
+    let mut over_limit = pushdata2(MAX_SCRIPT_ELEMENT_SIZE + 1);
+    over_limit.extend([OP_DROP, OP_1]);
+    let err = verify(&[], &over_limit).unwrap_err();
+    assert!(matches!(err, ScriptError::PushSize), "{err:?}");
+
+    // Oversized pushes are rejected even in an unexecuted branch.
+    let mut skipped = vec![OP_0, OP_IF];
+    skipped.extend(pushdata2(MAX_SCRIPT_ELEMENT_SIZE + 1));
+    skipped.extend([OP_ENDIF, OP_1]);
+    let err = verify(&[], &skipped).unwrap_err();
+    assert!(matches!(err, ScriptError::PushSize), "{err:?}");
+}
+
+fn locktime_tx(version: i32, lock_time: u32, sequence: u32) -> Transaction {
+    let mut tx = spending_tx();
+    tx.version = version;
+    tx.lock_time = lock_time;
+    tx.vin[0].sequence = sequence;
+    tx
+}
+
+/// `<value> <opcode> DROP 1`, with `value` minimally encoded as a script number.
+fn locktime_script(value: i64, opcode: u8) -> Vec<u8> {
+    let mut num = Vec::new();
+    let mut abs = value.unsigned_abs();
+    while abs > 0 {
+        num.push((abs & 0xff) as u8);
+        abs >>= 8;
+    }
+    if num.last().is_some_and(|last| last & 0x80 != 0) {
+        num.push(if value < 0 { 0x80 } else { 0 });
+    } else if value < 0 {
+        *num.last_mut().expect("non-zero") |= 0x80;
+    }
+    let mut script = vec![num.len() as u8];
+    script.extend(num);
+    script.extend([opcode, OP_DROP, OP_1]);
+    script
+}
+
+fn verify_locktime(
+    tx: &Transaction,
+    value: i64,
+    opcode: u8,
+    flags: ScriptFlags,
+) -> Result<(), ScriptError> {
+    verify_script(&[], &locktime_script(value, opcode), tx, 0, 0, flags, 0)
+}
+
+#[test]
+fn checklocktimeverify_vectors() {
+    let cltv = |tx: &Transaction, value: i64| {
+        verify_locktime(
+            tx,
+            value,
+            OP_CHECKLOCKTIMEVERIFY,
+            SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY,
+        )
+    };
+    let height_tx = locktime_tx(1, 100, 0);
+    cltv(&height_tx, 0).expect("0 <= 100");
+    cltv(&height_tx, 100).expect("100 <= 100");
+    let err = cltv(&height_tx, 101).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+    let err = cltv(&height_tx, -1).unwrap_err();
+    assert!(matches!(err, ScriptError::NegativeLocktime), "{err:?}");
+
+    // Height and time locktimes never satisfy each other.
+    let err = cltv(&locktime_tx(1, 499_999_999, 0), 500_000_000).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+    let err = cltv(&locktime_tx(1, 500_000_000, 0), 499_999_999).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+
+    // The argument is a 5-byte number, so the full u32 range is usable.
+    cltv(&locktime_tx(1, u32::MAX, 0), u32::MAX as i64).expect("max locktime");
+
+    // A final input disables the transaction locktime, so CLTV fails.
+    let err = cltv(&locktime_tx(1, 100, u32::MAX), 0).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+}
+
+#[test]
+fn checksequenceverify_vectors() {
+    let csv = |tx: &Transaction, value: i64| {
+        verify_locktime(
+            tx,
+            value,
+            OP_CHECKSEQUENCEVERIFY,
+            SCRIPT_VERIFY_CHECKSEQUENCEVERIFY,
+        )
+    };
+    let type_flag = 1i64 << 22;
+    let disable_flag = 1i64 << 31;
+
+    let height_tx = locktime_tx(2, 0, 10);
+    csv(&height_tx, 0).expect("0 <= 10");
+    csv(&height_tx, 10).expect("10 <= 10");
+    let err = csv(&height_tx, 11).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+    let err = csv(&height_tx, -1).unwrap_err();
+    assert!(matches!(err, ScriptError::NegativeLocktime), "{err:?}");
+
+    // Bits outside the type flag and 16-bit value are ignored.
+    csv(&height_tx, 10 | (1 << 16)).expect("masked high bits");
+
+    let time_tx = locktime_tx(2, 0, (type_flag | 10) as u32);
+    csv(&time_tx, type_flag | 10).expect("time-based");
+    let err = csv(&time_tx, 10).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+    let err = csv(&height_tx, type_flag).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+
+    // A disabled argument is a NOP, even against an unsatisfiable transaction.
+    csv(&locktime_tx(1, 0, u32::MAX), disable_flag).expect("disabled argument");
+
+    let err = csv(&locktime_tx(1, 0, 10), 0).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+    let err = csv(&locktime_tx(2, 0, u32::MAX), 0).unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+}
+
+#[test]
+fn locktime_opcodes_are_nops_without_their_flags() {
+    let tx = locktime_tx(1, 0, u32::MAX);
+    for opcode in [OP_CHECKLOCKTIMEVERIFY, OP_CHECKSEQUENCEVERIFY] {
+        verify_locktime(&tx, -1, opcode, SCRIPT_VERIFY_NONE).expect("nop");
+        verify_locktime(&tx, 1_000, opcode, SCRIPT_VERIFY_NONE).expect("nop");
+        let err =
+            verify_locktime(&tx, 0, opcode, SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS).unwrap_err();
+        assert!(matches!(err, ScriptError::InvalidOpcode), "{err:?}");
+    }
+    // Each flag only arms its own opcode.
+    verify_locktime(
+        &tx,
+        -1,
+        OP_CHECKSEQUENCEVERIFY,
+        SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY,
+    )
+    .expect("csv stays a nop");
+}
+
+#[test]
+fn op_nop_is_valid_even_when_upgradable_nops_are_discouraged() {
+    verify(&[], &[OP_NOP, OP_1]).expect("nop");
+    verify_script(
+        &[],
+        &[OP_NOP, OP_1],
+        &spending_tx(),
+        0,
+        0,
+        SCRIPT_VERIFY_DISCOURAGE_UPGRADABLE_NOPS,
+        0,
+    )
+    .expect("OP_NOP is not an upgradable nop");
+    // It leaves the stack untouched, so a script of nops alone ends empty.
+    let err = verify(&[], &[OP_NOP]).unwrap_err();
+    assert!(matches!(err, ScriptError::EvalFalse), "{err:?}");
+    verify(&[OP_1], &[OP_NOP, OP_NOP]).expect("stack passes through");
+    verify(&[], &[OP_0, OP_IF, OP_NOP, OP_ENDIF, OP_1]).expect("skipped nop");
+    // It still counts toward the operation limit.
+    let err = verify(&[], &[OP_NOP].repeat(MAX_OPS_PER_SCRIPT + 1)).unwrap_err();
+    assert!(matches!(err, ScriptError::OpCount), "{err:?}");
+}
+
+fn push(data: &[u8]) -> Vec<u8> {
+    assert!(data.len() < 0x4c, "test pushes stay below OP_PUSHDATA1");
+    let mut script = vec![data.len() as u8];
+    script.extend_from_slice(data);
+    script
+}
+
+fn p2sh_script(redeem_script: &[u8]) -> Vec<u8> {
+    let mut script = vec![OP_HASH160, 0x14];
+    script.extend_from_slice(&hash160(redeem_script));
+    script.push(OP_EQUAL);
+    script
+}
+
+fn verify_p2sh_with(
+    script_sig: &[u8],
+    script_pubkey: &[u8],
+    flags: ScriptFlags,
+) -> Result<(), ScriptError> {
+    let tx = spending_tx();
+    let checker = SignatureChecker::new(&tx, 0, 0, flags, 0);
+    verify_p2sh(script_sig, script_pubkey, &checker)
+}
+
+#[test]
+fn p2sh_runs_redeem_script_against_remaining_stack() {
+    let redeem = [OP_DROP, OP_1];
+    let script_pubkey = p2sh_script(&redeem);
+    let mut script_sig = vec![OP_0];
+    script_sig.extend(push(&redeem));
+    verify_p2sh_with(&script_sig, &script_pubkey, SCRIPT_VERIFY_NONE).expect("redeem ok");
+
+    // The extra item the redeem script leaves behind trips CLEANSTACK.
+    let mut unclean = vec![OP_1];
+    unclean.extend(push(&[OP_1]));
+    let err =
+        verify_p2sh_with(&unclean, &p2sh_script(&[OP_1]), SCRIPT_VERIFY_CLEANSTACK).unwrap_err();
+    assert!(matches!(err, ScriptError::EvalFalse), "{err:?}");
+
+    // Only the hash is checked without the P2SH flag; verify_p2sh always runs the redeem script.
+    let failing = [OP_0];
+    let script_sig = push(&failing);
+    let script_pubkey = p2sh_script(&failing);
+    verify(&script_sig, &script_pubkey).expect("hash-only check passes");
+    let err = verify_p2sh_with(&script_sig, &script_pubkey, SCRIPT_VERIFY_NONE).unwrap_err();
+    assert!(matches!(err, ScriptError::EvalFalse), "{err:?}");
+}
+
+#[test]
+fn p2sh_rejects_bad_script_sig_and_non_p2sh_output() {
+    let redeem = [OP_1];
+    let script_pubkey = p2sh_script(&redeem);
+
+    let mut not_push_only = push(&redeem);
+    not_push_only.push(OP_DUP);
+    not_push_only.extend(push(&redeem));
+    let err = verify_p2sh_with(&not_push_only, &script_pubkey, SCRIPT_VERIFY_NONE).unwrap_err();
+    assert!(matches!(err, ScriptError::SigPushOnly), "{err:?}");
+
+    let err =
+        verify_p2sh_with(&push(&[OP_1, OP_1]), &script_pubkey, SCRIPT_VERIFY_NONE).unwrap_err();
+    assert!(matches!(err, ScriptError::EvalFalse), "{err:?}");
+
+    let err = verify_p2sh_with(&push(&redeem), &[OP_1], SCRIPT_VERIFY_NONE).unwrap_err();
+    assert!(matches!(err, ScriptError::ScriptError(_)), "{err:?}");
+}
+
+#[test]
+fn p2sh_redeem_script_is_not_evaluated_recursively() {
+    // The redeem script is itself P2SH-shaped. It only compares hashes; the inner script, which
+    // would fail, is never run.
+    let inner = [OP_0];
+    let redeem = p2sh_script(&inner);
+    let mut script_sig = push(&inner);
+    script_sig.extend(push(&redeem));
+    verify_p2sh_with(&script_sig, &p2sh_script(&redeem), SCRIPT_VERIFY_NONE)
+        .expect("single level of P2SH");
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[test]
+fn eval_script_returns_numeric_results() {
+    let mut script = vec![OP_1NEGATE, OP_0, OP_1, OP_16, OP_SIZE];
+    script.extend([OP_PUSHDATA1, 200]);
+    script.extend([0u8; 200]);
+    script.push(OP_SIZE);
+    let stack = eval_script(&script, vec![b"abc".to_vec()], SCRIPT_VERIFY_NONE, None)
+        .expect("eval numeric pushes");
+    assert_eq!(
+        stack,
+        vec![
+            b"abc".to_vec(),
+            vec![0x81],
+            Vec::new(),
+            vec![0x01],
+            vec![0x10],
+            vec![0x01],
+            vec![0u8; 200],
+            // 200 needs a sign byte so it does not read back as negative.
+            vec![0xc8, 0x00],
+        ]
+    );
+
+    // A false top element is returned as-is rather than failing evaluation.
+    let stack = eval_script(&[OP_DROP, OP_0], vec![vec![0x01]], SCRIPT_VERIFY_NONE, None)
+        .expect("false result");
+    assert_eq!(stack, vec![Vec::<u8>::new()]);
+}
+
+#[test]
+fn eval_script_hash_opcodes_match_known_digests() {
+    let cases: [(u8, &str); 5] = [
+        (
+            OP_SHA256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (OP_SHA1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+        (OP_RIPEMD160, "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+        (OP_HASH160, "bb1be98c142444d7a56aa3981c3942a978e4dc33"),
+        (
+            OP_HASH256,
+            "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358",
+        ),
+    ];
+    for (opcode, expected) in cases {
+        let stack = eval_script(&[opcode], vec![b"abc".to_vec()], SCRIPT_VERIFY_NONE, None)
+            .expect("eval hash opcode");
+        assert_eq!(stack.len(), 1);
+        assert_eq!(to_hex(&stack[0]), expected, "opcode 0x{opcode:02x}");
+    }
+
+    let mut script = vec![OP_DUP, OP_HASH160];
+    script.extend(push(&hash160(b"abc")));
+    script.push(OP_EQUAL);
+    let stack = eval_script(&script, vec![b"abc".to_vec()], SCRIPT_VERIFY_NONE, None)
+        .expect("eval hash compare");
+    assert_eq!(stack, vec![b"abc".to_vec(), vec![0x01]]);
+}
+
+#[test]
+fn eval_script_without_checker() {
+    // Signature opcodes evaluate to false instead of erroring.
+    let stack = eval_script(
+        &[OP_CHECKSIG],
+        vec![vec![0x30], vec![0x02; 33]],
+        SCRIPT_VERIFY_NONE,
+        None,
+    )
+    .expect("checksig without checker");
+    assert_eq!(stack, vec![Vec::<u8>::new()]);
+
+    let err = eval_script(
+        &locktime_script(0, OP_CHECKLOCKTIMEVERIFY),
+        Vec::new(),
+        SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY,
+        None,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ScriptError::UnsatisfiedLocktime), "{err:?}");
+
+    let err = eval_script(&[OP_DROP], Vec::new(), SCRIPT_VERIFY_NONE, None).unwrap_err();
+    assert!(matches!(err, ScriptError::StackUnderflow), "{err:?}");
+}
@@ -1,7 +1,44 @@
+use fluxd_consensus::params::{consensus_params, Network};
+use fluxd_primitives::outpoint::OutPoint;
+use fluxd_primitives::transaction::{
+    Transaction, TxIn, TxOut, OVERWINTER_VERSION_GROUP_ID, SAPLING_VERSION_GROUP_ID,
+};
 use fluxd_script::sighash::{
-    SighashType, SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_NONE, SIGHASH_SINGLE,
+    sighash, sighash_variant, signature_hash, SighashType, SighashVariant, SIGHASH_ALL,
+    SIGHASH_ANYONECANPAY, SIGHASH_NONE, SIGHASH_SINGLE,
 };
 
+fn transparent_tx(f_overwintered: bool, version: i32, version_group_id: u32) -> Transaction {
+    Transaction {
+        f_overwintered,
+        version,
+        version_group_id,
+        vin: vec![TxIn {
+            prevout: OutPoint {
+                hash: [0x22; 32],
+                index: 1,
+            },
+            script_sig: Vec::new(),
+            sequence: u32::MAX,
+        }],
+        vout: vec![TxOut {
+            value: 50_000,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+        expiry_height: 0,
+        value_balance: 0,
+        shielded_spends: Vec::new(),
+        shielded_outputs: Vec::new(),
+        join_splits: Vec::new(),
+        join_split_pub_key: [0u8; 32],
+        join_split_sig: [0u8; 64],
+        binding_sig: [0u8; 64],
+        raw_tail: Vec::new(),
+        fluxnode: None,
+    }
+}
+
 #[test]
 fn sighash_type_flags() {
     let combined = SighashType(SIGHASH_ALL | SIGHASH_ANYONECANPAY);
@@ -16,3 +53,99 @@ fn sighash_type_flags() {
     assert_eq!(single.base_type(), SIGHASH_SINGLE);
     assert!(single.has_anyone_can_pay());
 }
+
+#[test]
+fn sighash_variant_follows_tx_format_and_branch() {
+    let params = consensus_params(Network::Mainnet);
+    let upgraded_branch = 0x76b8_09bb;
+
+    let legacy = transparent_tx(false, 1, 0);
+    assert_eq!(
+        sighash_variant(&legacy, 0, &params).unwrap(),
+        SighashVariant::Legacy
+    );
+    assert_eq!(
+        sighash_variant(&legacy, 250_000, &params).unwrap(),
+        SighashVariant::Legacy
+    );
+
+    let overwinter = transparent_tx(true, 3, OVERWINTER_VERSION_GROUP_ID);
+    assert_eq!(
+        sighash_variant(&overwinter, 250_000, &params).unwrap(),
+        SighashVariant::Zip143 {
+            consensus_branch_id: upgraded_branch
+        }
+    );
+
+    // The branch id switches at the first upgrade, not at the tx format change.
+    let sapling = transparent_tx(true, 4, SAPLING_VERSION_GROUP_ID);
+    assert_eq!(
+        sighash_variant(&sapling, 124_999, &params).unwrap(),
+        SighashVariant::Zip243 {
+            consensus_branch_id: 0
+        }
+    );
+    assert_eq!(
+        sighash_variant(&sapling, 125_000, &params).unwrap(),
+        SighashVariant::Zip243 {
+            consensus_branch_id: upgraded_branch
+        }
+    );
+
+    let unknown = transparent_tx(true, 4, 0x1234_5678);
+    assert!(sighash_variant(&unknown, 250_000, &params).is_err());
+}
+
+#[test]
+fn signature_hash_dispatches_on_variant() {
+    let params = consensus_params(Network::Mainnet);
+    let sighash_type = SighashType(SIGHASH_ALL);
+    let script_code = [0x51];
+
+    for tx in [
+        transparent_tx(false, 1, 0),
+        transparent_tx(true, 3, OVERWINTER_VERSION_GROUP_ID),
+        transparent_tx(true, 4, SAPLING_VERSION_GROUP_ID),
+    ] {
+        let variant = sighash_variant(&tx, 250_000, &params).unwrap();
+        let branch_id = match variant {
+            SighashVariant::Legacy => 0,
+            SighashVariant::Zip143 {
+                consensus_branch_id,
+            }
+            | SighashVariant::Zip243 {
+                consensus_branch_id,
+            } => consensus_branch_id,
+        };
+        let expected =
+            signature_hash(&tx, Some(0), &script_code, 50_000, sighash_type, branch_id).unwrap();
+        let actual = sighash(&tx, Some(0), &script_code, 50_000, sighash_type, variant).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    // ZIP-143 and ZIP-243 commit to different personalizations and fields.
+    let sapling = transparent_tx(true, 4, SAPLING_VERSION_GROUP_ID);
+    let zip243 = sighash(
+        &sapling,
+        Some(0),
+        &script_code,
+        50_000,
+        sighash_type,
+        SighashVariant::Zip243 {
+            consensus_branch_id: 0x76b8_09bb,
+        },
+    )
+    .unwrap();
+    let zip143 = sighash(
+        &sapling,
+        Some(0),
+        &script_code,
+        50_000,
+        sighash_type,
+        SighashVariant::Zip143 {
+            consensus_branch_id: 0x76b8_09bb,
+        },
+    )
+    .unwrap();
+    assert_ne!(zip243, zip143);
+}
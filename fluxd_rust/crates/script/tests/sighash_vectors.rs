@@ -1,3 +1,4 @@
+use fluxd_primitives::hash::hash256_to_hex;
 use fluxd_primitives::transaction::Transaction;
 use fluxd_script::sighash::{signature_hash, SighashType};
 
@@ -15,16 +16,6 @@ fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
     Some(bytes)
 }
 
-fn hash256_to_hex(hash: &[u8; 32]) -> String {
-    use std::fmt::Write;
-
-    let mut out = String::with_capacity(64);
-    for byte in hash.iter().rev() {
-        let _ = write!(out, "{:02x}", byte);
-    }
-    out
-}
-
 #[test]
 fn sighash_vectors_match_cpp() {
     let vectors = include_str!("vectors/sighash.json");
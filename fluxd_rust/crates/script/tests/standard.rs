@@ -43,3 +43,10 @@ fn classify_unknown() {
     let script = vec![0x6a, 0x01, 0x01];
     assert_eq!(classify_script_pubkey(&script), ScriptType::Unknown);
 }
+
+#[test]
+fn classify_witness_v1() {
+    let mut script = vec![0x51, 0x20];
+    script.extend_from_slice(&[0x55; 32]);
+    assert_eq!(classify_script_pubkey(&script), ScriptType::Witness);
+}
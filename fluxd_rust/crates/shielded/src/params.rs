@@ -70,6 +70,15 @@ pub fn load_params(params_dir: &Path, network: Network) -> Result<ShieldedParams
     })
 }
 
+/// Resolves the parameter files for `network` and checks each one against its published SHA-256.
+pub fn verify_params(params_dir: &Path, network: Network) -> Result<ParamPaths, ShieldedError> {
+    let paths = resolve_param_paths(params_dir, network)?;
+    verify_sha256_cached(&paths.spend, SAPLING_SPEND_SHA256)?;
+    verify_sha256_cached(&paths.output, SAPLING_OUTPUT_SHA256)?;
+    verify_sha256_cached(&paths.sprout, SPROUT_GROTH16_SHA256)?;
+    Ok(paths)
+}
+
 pub fn fetch_params(params_dir: &Path, network: Network) -> Result<(), ShieldedError> {
     fs::create_dir_all(params_dir)?;
 
@@ -1,6 +1,6 @@
 use core2::io;
 
-use fluxd_consensus::params::hash256_from_hex;
+use fluxd_primitives::hash::hash256_from_hex;
 use incrementalmerkletree::{
     frontier::CommitmentTree, witness::IncrementalWitness, Hashable, Level, MerklePath,
 };
@@ -0,0 +1,134 @@
+//! Backend construction from a store URI.
+//!
+//! Supported schemes are `memory://` and `fjall:///absolute/path` (or
+//! `fjall://relative/path`). New backends add a scheme here and a variant to
+//! [`BackendUri`] and [`OpenedBackend`].
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "fjall")]
+use crate::fjall::{FjallOptions, FjallStore};
+use crate::memory::MemoryStore;
+use crate::{KeyValueStore, StoreError};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BackendUri {
+    Memory,
+    Fjall(PathBuf),
+}
+
+impl BackendUri {
+    pub fn parse(uri: &str) -> Result<Self, StoreError> {
+        let uri = uri.trim();
+        let Some((scheme, rest)) = uri.split_once("://") else {
+            return Err(StoreError::Backend(format!(
+                "invalid store uri '{uri}' (expected <scheme>://...)"
+            )));
+        };
+        match scheme.to_ascii_lowercase().as_str() {
+            "memory" => {
+                if !rest.is_empty() {
+                    return Err(StoreError::Backend(format!(
+                        "memory store uri takes no path (got '{uri}')"
+                    )));
+                }
+                Ok(Self::Memory)
+            }
+            "fjall" => {
+                if rest.is_empty() {
+                    return Err(StoreError::Backend(format!(
+                        "fjall store uri requires a path (got '{uri}')"
+                    )));
+                }
+                Ok(Self::Fjall(PathBuf::from(rest)))
+            }
+            other => Err(StoreError::Backend(format!(
+                "unsupported store backend '{other}'"
+            ))),
+        }
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Self::Memory => "memory",
+            Self::Fjall(_) => "fjall",
+        }
+    }
+}
+
+/// Backend-specific tuning; fields for backends that are not compiled in are absent.
+#[derive(Clone, Debug, Default)]
+pub struct BackendOptions {
+    #[cfg(feature = "fjall")]
+    pub fjall: FjallOptions,
+}
+
+/// A freshly opened store, keeping the concrete type for backend-specific APIs.
+pub enum OpenedBackend {
+    Memory(MemoryStore),
+    #[cfg(feature = "fjall")]
+    Fjall(FjallStore),
+}
+
+impl OpenedBackend {
+    pub fn open(uri: &BackendUri, options: BackendOptions) -> Result<Self, StoreError> {
+        match uri {
+            BackendUri::Memory => {
+                let _ = options;
+                Ok(Self::Memory(MemoryStore::new()))
+            }
+            #[cfg(feature = "fjall")]
+            BackendUri::Fjall(path) => Ok(Self::Fjall(FjallStore::open_with_options(
+                path,
+                options.fjall,
+            )?)),
+            #[cfg(not(feature = "fjall"))]
+            BackendUri::Fjall(_) => Err(StoreError::Backend(
+                "fjall backend not compiled in (enable the `fjall` feature)".to_string(),
+            )),
+        }
+    }
+
+    pub fn into_shared(self) -> Arc<dyn KeyValueStore> {
+        match self {
+            Self::Memory(store) => Arc::new(store),
+            #[cfg(feature = "fjall")]
+            Self::Fjall(store) => Arc::new(store),
+        }
+    }
+}
+
+pub fn open_backend(
+    uri: &str,
+    options: BackendOptions,
+) -> Result<Arc<dyn KeyValueStore>, StoreError> {
+    let uri = BackendUri::parse(uri)?;
+    Ok(OpenedBackend::open(&uri, options)?.into_shared())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_known_schemes() {
+        assert_eq!(BackendUri::parse("memory://").unwrap(), BackendUri::Memory);
+        assert_eq!(
+            BackendUri::parse("fjall:///var/lib/fluxd/db").unwrap(),
+            BackendUri::Fjall(PathBuf::from("/var/lib/fluxd/db"))
+        );
+        assert_eq!(
+            BackendUri::parse("fjall://data/db").unwrap(),
+            BackendUri::Fjall(PathBuf::from("data/db"))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_uris() {
+        assert!(BackendUri::parse("memory").is_err());
+        assert!(BackendUri::parse("memory://x").is_err());
+        assert!(BackendUri::parse("fjall://").is_err());
+        assert!(BackendUri::parse("rocksdb:///tmp/db").is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Optional per-column value compression.
+//!
+//! Every value in a compressed column carries a one-byte header: `RAW` values follow verbatim,
+//! `ZSTD` values are a zstd frame. Values below the threshold, or that do not shrink, are kept
+//! raw, so both forms coexist in the same column. Columns that are not selected are stored
+//! without a header. Compressing requires the `zstd` feature; decoding a `ZSTD` value without it
+//! fails.
+
+use std::borrow::Cow;
+
+use crate::{Column, StoreError};
+
+pub const RAW: u8 = 0;
+pub const ZSTD: u8 = 1;
+
+/// Values shorter than this are stored raw; the zstd frame overhead eats most of the gain.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 64;
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compression {
+    columns: u32,
+    threshold: usize,
+    level: i32,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            columns: 0,
+            threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl Compression {
+    pub fn new(columns: &[Column]) -> Self {
+        Self {
+            columns: columns.iter().fold(0, |mask, column| mask | column.bit()),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Whether this build can compress values (the `zstd` feature is enabled).
+    pub const fn available() -> bool {
+        cfg!(feature = "zstd")
+    }
+
+    /// Bitmask of `Column::bit()` for the selected columns.
+    pub fn column_mask(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = Column> + '_ {
+        Column::ALL
+            .into_iter()
+            .filter(move |column| self.is_enabled(*column))
+    }
+
+    pub fn is_enabled(&self, column: Column) -> bool {
+        self.columns & column.bit() != 0
+    }
+
+    /// Returns the bytes to store for `value` in `column`.
+    pub fn encode<'a>(&self, column: Column, value: &'a [u8]) -> Result<Cow<'a, [u8]>, StoreError> {
+        if !self.is_enabled(column) {
+            return Ok(Cow::Borrowed(value));
+        }
+        if value.len() >= self.threshold {
+            if let Some(compressed) = self.compress(value)? {
+                if compressed.len() < value.len() {
+                    let mut stored = Vec::with_capacity(compressed.len() + 1);
+                    stored.push(ZSTD);
+                    stored.extend_from_slice(&compressed);
+                    return Ok(Cow::Owned(stored));
+                }
+            }
+        }
+        let mut stored = Vec::with_capacity(value.len() + 1);
+        stored.push(RAW);
+        stored.extend_from_slice(value);
+        Ok(Cow::Owned(stored))
+    }
+
+    /// Returns the original value for bytes read from `column`.
+    pub fn decode<'a>(
+        &self,
+        column: Column,
+        stored: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>, StoreError> {
+        if !self.is_enabled(column) {
+            return Ok(Cow::Borrowed(stored));
+        }
+        match stored.split_first() {
+            Some((&RAW, value)) => Ok(Cow::Borrowed(value)),
+            Some((&ZSTD, frame)) => decompress(frame).map(Cow::Owned),
+            Some((flag, _)) => Err(StoreError::Backend(format!(
+                "unknown compression flag {flag} in {}",
+                column.as_str()
+            ))),
+            None => Err(StoreError::Backend(format!(
+                "missing compression flag in {}",
+                column.as_str()
+            ))),
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    fn compress(&self, value: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        zstd::bulk::compress(value, self.level)
+            .map(Some)
+            .map_err(|err| StoreError::Backend(format!("zstd compress failed: {err}")))
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    fn compress(&self, _value: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decompress(frame: &[u8]) -> Result<Vec<u8>, StoreError> {
+    zstd::stream::decode_all(frame)
+        .map_err(|err| StoreError::Backend(format!("zstd decompress failed: {err}")))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_frame: &[u8]) -> Result<Vec<u8>, StoreError> {
+    Err(StoreError::Backend(
+        "value is zstd compressed but fluxd-storage was built without the zstd feature".to_string(),
+    ))
+}
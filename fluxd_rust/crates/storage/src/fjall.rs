@@ -1,12 +1,18 @@
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use fjall::PersistMode;
-use fjall::{AbstractTree, Batch, Config, Keyspace, PartitionCreateOptions, PartitionHandle};
+use fjall::{
+    AbstractTree, Batch, Config, Keyspace, PartitionCreateOptions, PartitionHandle, Snapshot,
+};
 
-use crate::{Column, KeyValueStore, PrefixVisitor, StoreError, WriteBatch, WriteOp};
+use crate::compress::Compression;
+use crate::{
+    Column, KeyValueStore, PrefixVisitor, ReadSnapshot, ScanIter, StoreError, WriteBatch, WriteOp,
+};
 
 const SLOW_COMMIT_THRESHOLD: Duration = Duration::from_millis(500);
 const SLOW_COMMIT_LOG_INTERVAL_SECS: u64 = 30;
@@ -18,6 +24,12 @@ const JOURNAL_HIGH_WATERMARK_PCT: u64 = 80;
 const JOURNAL_RELIEF_LOG_INTERVAL_SECS: u64 = 30;
 const JOURNAL_RELIEF_COOLDOWN_SECS: u64 = 2;
 
+/// L0 segment count at which fjall starts throttling writes into a partition.
+pub const L0_WRITE_STALL_SEGMENTS: u64 = 20;
+
+/// Meta key recording the compressed column mask the database was created with.
+const META_COMPRESSED_COLUMNS_KEY: &[u8] = b"storage:compressed_columns";
+
 static LAST_SLOW_COMMIT_LOG_SECS: AtomicU64 = AtomicU64::new(0);
 static LAST_WRITE_BUFFER_RELIEF_LOG_SECS: AtomicU64 = AtomicU64::new(0);
 static LAST_JOURNAL_RELIEF_LOG_SECS: AtomicU64 = AtomicU64::new(0);
@@ -28,6 +40,10 @@ pub struct FjallStore {
     max_write_buffer_bytes: Option<u64>,
     max_journal_bytes: Option<u64>,
     last_pressure_relief_secs: AtomicU64,
+    /// Serializes `compare_and_swap` read-compare-write sequences within this process only; it
+    /// gives no atomicity against another process writing the same keyspace.
+    cas_lock: Mutex<()>,
+    compression: Compression,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -53,6 +69,9 @@ pub struct FjallTelemetrySnapshot {
     pub address_delta_flushes_completed: u64,
     pub header_index_segments: u64,
     pub header_index_flushes_completed: u64,
+    pub l0_segments: Vec<(&'static str, u64)>,
+    pub max_l0_segments: u64,
+    pub write_stalled_partitions: Vec<&'static str>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -64,6 +83,8 @@ pub struct FjallOptions {
     pub flush_workers: Option<usize>,
     pub compaction_workers: Option<usize>,
     pub fsync_ms: Option<u16>,
+    /// Columns whose values are zstd compressed; fixed when the database is created.
+    pub compression: Compression,
 }
 
 impl FjallOptions {
@@ -113,12 +134,14 @@ impl FjallStore {
     ) -> Result<Self, StoreError> {
         let config = options.apply_config(Config::new(path));
         let partition_options = options.partition_options();
-        Self::open_with_config_and_options(
+        let mut store = Self::open_with_config_and_options(
             config,
             partition_options,
             options.write_buffer_bytes,
             options.journal_bytes,
-        )
+        )?;
+        store.set_compression(options.compression)?;
+        Ok(store)
     }
 
     pub fn open_with_config_and_options(
@@ -141,11 +164,73 @@ impl FjallStore {
             max_write_buffer_bytes,
             max_journal_bytes,
             last_pressure_relief_secs: AtomicU64::new(0),
+            cas_lock: Mutex::new(()),
+            compression: Compression::default(),
         };
         store.spawn_journal_pressure_watchdog();
         Ok(store)
     }
 
+    /// Checks `compression` against the mask recorded in the database and adopts it. A fresh
+    /// database records the mask; an existing one must be reopened with the same columns, since
+    /// compressed and plain columns use different value layouts.
+    fn set_compression(&mut self, compression: Compression) -> Result<(), StoreError> {
+        let requested = compression.column_mask();
+        if requested & Column::Meta.bit() != 0 {
+            return Err(StoreError::Backend(
+                "the meta column cannot be compressed".to_string(),
+            ));
+        }
+        if requested != 0 && !Compression::available() {
+            return Err(StoreError::Backend(
+                "column compression requires fluxd-storage built with the zstd feature".to_string(),
+            ));
+        }
+        let meta = self.partition(Column::Meta)?;
+        let recorded = match meta.get(META_COMPRESSED_COLUMNS_KEY).map_err(map_err)? {
+            Some(bytes) => {
+                let bytes: [u8; 4] = bytes.as_ref().try_into().map_err(|_| {
+                    StoreError::Backend("invalid compressed column mask".to_string())
+                })?;
+                Some(u32::from_le_bytes(bytes))
+            }
+            None => None,
+        };
+        match recorded {
+            Some(mask) if mask != requested => {
+                let names: Vec<&str> = Column::ALL
+                    .into_iter()
+                    .filter(|column| mask & column.bit() != 0)
+                    .map(Column::as_str)
+                    .collect();
+                return Err(StoreError::Backend(format!(
+                    "database was created with compressed columns [{}]; reopen with the same selection",
+                    names.join(",")
+                )));
+            }
+            Some(_) => {}
+            None if requested != 0 => {
+                for column in compression.columns() {
+                    if !self.partition(column)?.is_empty().map_err(map_err)? {
+                        return Err(StoreError::Backend(format!(
+                            "column {} already holds uncompressed values; compression can only be enabled on a new database",
+                            column.as_str()
+                        )));
+                    }
+                }
+                meta.insert(META_COMPRESSED_COLUMNS_KEY, requested.to_le_bytes())
+                    .map_err(map_err)?;
+            }
+            None => {}
+        }
+        self.compression = compression;
+        Ok(())
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
     fn partition(&self, column: Column) -> Result<&PartitionHandle, StoreError> {
         self.partitions
             .get(column.index())
@@ -164,6 +249,26 @@ impl FjallStore {
             self.partition_telemetry(Column::AddressDelta);
         let (header_index_segments, header_index_flushes_completed) =
             self.partition_telemetry(Column::HeaderIndex);
+        let l0_segments: Vec<(&'static str, u64)> = Column::ALL
+            .iter()
+            .zip(&self.partitions)
+            .map(|(column, partition)| {
+                (
+                    column.as_str(),
+                    partition.tree.level_segment_count(0).unwrap_or(0) as u64,
+                )
+            })
+            .collect();
+        let max_l0_segments = l0_segments
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+        let write_stalled_partitions = l0_segments
+            .iter()
+            .filter(|(_, count)| *count >= L0_WRITE_STALL_SEGMENTS)
+            .map(|(name, _)| *name)
+            .collect();
 
         FjallTelemetrySnapshot {
             write_buffer_bytes: self.keyspace.write_buffer_size(),
@@ -187,9 +292,22 @@ impl FjallStore {
             address_delta_flushes_completed,
             header_index_segments,
             header_index_flushes_completed,
+            l0_segments,
+            max_l0_segments,
+            write_stalled_partitions,
         }
     }
 
+    /// Forces a major compaction of `column`, merging its L0 runs into the deeper levels.
+    pub fn compact(&self, column: Column) -> Result<(), StoreError> {
+        self.partition(column)?.major_compact().map_err(map_err)
+    }
+
+    /// Flushes the journal and fsyncs it so every committed batch survives a restart.
+    pub fn persist(&self) -> Result<(), StoreError> {
+        self.keyspace.persist(PersistMode::SyncAll).map_err(map_err)
+    }
+
     fn partition_telemetry(&self, column: Column) -> (u64, u64) {
         match self.partition(column) {
             Ok(partition) => (
@@ -359,12 +477,20 @@ impl KeyValueStore for FjallStore {
     fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
         let partition = self.partition(column)?;
         let value = partition.get(key).map_err(map_err)?;
-        Ok(value.map(|bytes| bytes.to_vec()))
+        value
+            .map(|bytes| Ok(self.compression.decode(column, &bytes)?.into_owned()))
+            .transpose()
+    }
+
+    fn contains(&self, column: Column, key: &[u8]) -> Result<bool, StoreError> {
+        let partition = self.partition(column)?;
+        partition.contains_key(key).map_err(map_err)
     }
 
     fn put(&self, column: Column, key: &[u8], value: &[u8]) -> Result<(), StoreError> {
         let partition = self.partition(column)?;
-        partition.insert(key, value).map_err(map_err)?;
+        let value = self.compression.encode(column, value)?;
+        partition.insert(key, value.as_ref()).map_err(map_err)?;
         Ok(())
     }
 
@@ -374,33 +500,42 @@ impl KeyValueStore for FjallStore {
         Ok(())
     }
 
-    fn scan_prefix(
+    fn compare_and_swap(
         &self,
         column: Column,
-        prefix: &[u8],
-    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StoreError> {
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, StoreError> {
         let partition = self.partition(column)?;
-        let mut results = Vec::new();
-        for entry in partition.prefix(prefix) {
-            let (key, value) = entry.map_err(map_err)?;
-            results.push((key.to_vec(), value.to_vec()));
+        let _guard = self.cas_lock.lock().expect("fjall cas lock");
+        let current = partition.get(key).map_err(map_err)?;
+        let current = current
+            .as_deref()
+            .map(|bytes| self.compression.decode(column, bytes))
+            .transpose()?;
+        if current.as_deref() != expected {
+            return Ok(false);
         }
-        Ok(results)
+        let new = self.compression.encode(column, new)?;
+        partition.insert(key, new.as_ref()).map_err(map_err)?;
+        Ok(true)
     }
 
-    fn scan_prefix_limited(
-        &self,
+    fn scan_prefix_iter<'a>(
+        &'a self,
         column: Column,
         prefix: &[u8],
-        limit: usize,
-    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StoreError> {
+    ) -> Result<ScanIter<'a>, StoreError> {
         let partition = self.partition(column)?;
-        let mut results = Vec::new();
-        for entry in partition.prefix(prefix).take(limit) {
-            let (key, value) = entry.map_err(map_err)?;
-            results.push((key.to_vec(), value.to_vec()));
-        }
-        Ok(results)
+        let compression = self.compression;
+        Ok(Box::new(partition.prefix(prefix.to_vec()).map(
+            move |entry| {
+                let (key, value) = entry.map_err(map_err)?;
+                let value = compression.decode(column, &value)?.into_owned();
+                Ok((key.to_vec(), value))
+            },
+        )))
     }
 
     fn for_each_prefix<'a>(
@@ -412,6 +547,7 @@ impl KeyValueStore for FjallStore {
         let partition = self.partition(column)?;
         for entry in partition.prefix(prefix) {
             let (key, value) = entry.map_err(map_err)?;
+            let value = self.compression.decode(column, &value)?;
             visitor(key.as_ref(), value.as_ref())?;
         }
         Ok(())
@@ -427,7 +563,8 @@ impl KeyValueStore for FjallStore {
         let mut results = Vec::new();
         for entry in partition.range(start..=end) {
             let (key, value) = entry.map_err(map_err)?;
-            results.push((key.to_vec(), value.to_vec()));
+            let value = self.compression.decode(column, &value)?;
+            results.push((key.to_vec(), value.into_owned()));
         }
         Ok(results)
     }
@@ -442,6 +579,7 @@ impl KeyValueStore for FjallStore {
         let partition = self.partition(column)?;
         for entry in partition.range(start..=end) {
             let (key, value) = entry.map_err(map_err)?;
+            let value = self.compression.decode(column, &value)?;
             visitor(key.as_ref(), value.as_ref())?;
         }
         Ok(())
@@ -460,7 +598,8 @@ impl KeyValueStore for FjallStore {
                 WriteOp::Put { column, key, value } => {
                     touched |= (*column).bit();
                     let partition = self.partition(*column)?;
-                    fjall_batch.insert(partition, key.as_slice(), value.as_slice());
+                    let value = self.compression.encode(*column, value.as_slice())?;
+                    fjall_batch.insert(partition, key.as_slice(), value.as_ref());
                 }
                 WriteOp::Delete { column, key } => {
                     touched |= (*column).bit();
@@ -498,8 +637,78 @@ impl KeyValueStore for FjallStore {
         }
         Ok(())
     }
+
+    /// Pins one keyspace sequence number across all partitions. Batches commit under a single
+    /// sequence number, so the snapshot holds each batch whole or not at all.
+    fn snapshot(&self) -> Result<Box<dyn ReadSnapshot>, StoreError> {
+        let instant = self.keyspace.instant();
+        Ok(Box::new(FjallSnapshot {
+            partitions: self
+                .partitions
+                .iter()
+                .map(|partition| partition.snapshot_at(instant))
+                .collect(),
+            compression: self.compression,
+        }))
+    }
+}
+
+struct FjallSnapshot {
+    partitions: Vec<Snapshot>,
+    compression: Compression,
+}
+
+impl FjallSnapshot {
+    fn partition(&self, column: Column) -> Result<&Snapshot, StoreError> {
+        self.partitions
+            .get(column.index())
+            .ok_or_else(|| StoreError::Backend(format!("missing partition {}", column.as_str())))
+    }
+}
+
+impl ReadSnapshot for FjallSnapshot {
+    fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        let value = self.partition(column)?.get(key).map_err(map_lsm_err)?;
+        value
+            .map(|bytes| Ok(self.compression.decode(column, &bytes)?.into_owned()))
+            .transpose()
+    }
+
+    fn for_each_prefix<'a>(
+        &self,
+        column: Column,
+        prefix: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        for entry in self.partition(column)?.prefix(prefix) {
+            let (key, value) = entry.map_err(map_lsm_err)?;
+            let value = self.compression.decode(column, &value)?;
+            visitor(key.as_ref(), value.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn for_each_range<'a>(
+        &self,
+        column: Column,
+        start: &[u8],
+        end: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        for entry in self.partition(column)?.range(start..=end) {
+            let (key, value) = entry.map_err(map_lsm_err)?;
+            let value = self.compression.decode(column, &value)?;
+            visitor(key.as_ref(), value.as_ref())?;
+        }
+        Ok(())
+    }
 }
 
 fn map_err(err: fjall::Error) -> StoreError {
     StoreError::Backend(err.to_string())
 }
+
+/// Snapshot reads go straight to the LSM tree, whose errors are not wrapped in `fjall::Error`.
+fn map_lsm_err(err: fjall::LsmError) -> StoreError {
+    StoreError::Backend(err.to_string())
+}
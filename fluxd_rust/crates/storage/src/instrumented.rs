@@ -0,0 +1,476 @@
+//! Per-column latency recording around any [`KeyValueStore`].
+//!
+//! [`InstrumentedStore`] times every call and files it under the column and the kind of
+//! operation: point reads (`get`, `contains`), writes (`put`, `delete`, `compare_and_swap` and the
+//! ops of a `write_batch`) and scans. Histograms are cumulative since the store was wrapped.
+//! Callers that do not want the overhead simply do not wrap the store.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{
+    Column, KeyValueStore, PrefixVisitor, ReadSnapshot, ScanIter, ScanResult, StoreError,
+    WriteBatch, WriteOp,
+};
+
+/// Number of log2 microsecond buckets per histogram; the last bucket absorbs everything from
+/// ~4.2s upwards.
+pub const LATENCY_BUCKETS: usize = 24;
+
+const COLUMN_COUNT: usize = Column::ALL.len();
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StoreOp {
+    Get,
+    Put,
+    Scan,
+}
+
+impl StoreOp {
+    pub const ALL: [StoreOp; 3] = [StoreOp::Get, StoreOp::Put, StoreOp::Scan];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StoreOp::Get => "get",
+            StoreOp::Put => "put",
+            StoreOp::Scan => "scan",
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            StoreOp::Get => 0,
+            StoreOp::Put => 1,
+            StoreOp::Scan => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+    total_us: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            total_us: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&self, samples: u64, each: Duration) {
+        let each_us = each.as_micros() as u64;
+        let bucket = ((u64::BITS - each_us.leading_zeros()) as usize).min(LATENCY_BUCKETS - 1);
+        self.buckets[bucket].fetch_add(samples, Ordering::Relaxed);
+        self.total_us
+            .fetch_add(each_us.saturating_mul(samples), Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyHistogramSnapshot {
+        LatencyHistogramSnapshot {
+            buckets: std::array::from_fn(|bucket| self.buckets[bucket].load(Ordering::Relaxed)),
+            total_us: self.total_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyHistogramSnapshot {
+    pub buckets: [u64; LATENCY_BUCKETS],
+    pub total_us: u64,
+}
+
+impl LatencyHistogramSnapshot {
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Upper bound (in microseconds) of the bucket holding the `percent`th sample, or `None`
+    /// when nothing was recorded.
+    pub fn percentile_us(&self, percent: u64) -> Option<u64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (count * percent.min(100)).div_ceil(100).max(1);
+        let mut seen = 0u64;
+        for (bucket, samples) in self.buckets.iter().enumerate() {
+            seen += samples;
+            if seen >= rank {
+                return Some(1u64 << bucket);
+            }
+        }
+        None
+    }
+}
+
+/// Histograms for every column and [`StoreOp`], shared by a store and its snapshots.
+#[derive(Debug)]
+pub struct StoreLatency {
+    histograms: [[LatencyHistogram; 3]; COLUMN_COUNT],
+}
+
+impl Default for StoreLatency {
+    fn default() -> Self {
+        Self {
+            histograms: std::array::from_fn(|_| std::array::from_fn(|_| Default::default())),
+        }
+    }
+}
+
+impl StoreLatency {
+    /// Records `samples` operations that each took `each`.
+    pub fn record(&self, column: Column, op: StoreOp, samples: u64, each: Duration) {
+        if samples == 0 {
+            return;
+        }
+        self.histograms[column.index()][op.index()].record(samples, each);
+    }
+
+    pub fn histogram(&self, column: Column, op: StoreOp) -> LatencyHistogramSnapshot {
+        self.histograms[column.index()][op.index()].snapshot()
+    }
+
+    /// Columns with at least one recorded operation, in [`Column::ALL`] order.
+    pub fn snapshot(&self) -> Vec<ColumnLatencySnapshot> {
+        Column::ALL
+            .into_iter()
+            .map(|column| ColumnLatencySnapshot {
+                column,
+                get: self.histogram(column, StoreOp::Get),
+                put: self.histogram(column, StoreOp::Put),
+                scan: self.histogram(column, StoreOp::Scan),
+            })
+            .filter(|snapshot| {
+                snapshot.get.count() > 0 || snapshot.put.count() > 0 || snapshot.scan.count() > 0
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnLatencySnapshot {
+    pub column: Column,
+    pub get: LatencyHistogramSnapshot,
+    pub put: LatencyHistogramSnapshot,
+    pub scan: LatencyHistogramSnapshot,
+}
+
+impl ColumnLatencySnapshot {
+    pub fn op(&self, op: StoreOp) -> &LatencyHistogramSnapshot {
+        match op {
+            StoreOp::Get => &self.get,
+            StoreOp::Put => &self.put,
+            StoreOp::Scan => &self.scan,
+        }
+    }
+}
+
+/// Wraps `S`, recording the latency of every call in a [`StoreLatency`].
+///
+/// Scans through [`KeyValueStore::for_each_prefix`] and friends include the time spent in the
+/// visitor; lazy scans count only the time spent inside the iterator.
+pub struct InstrumentedStore<S> {
+    inner: S,
+    latency: Arc<StoreLatency>,
+}
+
+impl<S: KeyValueStore> InstrumentedStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            latency: Arc::new(StoreLatency::default()),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    pub fn latency(&self) -> &Arc<StoreLatency> {
+        &self.latency
+    }
+
+    fn timed<T>(&self, column: Column, op: StoreOp, call: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = call();
+        self.latency.record(column, op, 1, start.elapsed());
+        result
+    }
+}
+
+impl<S: KeyValueStore> KeyValueStore for InstrumentedStore<S> {
+    fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        self.timed(column, StoreOp::Get, || self.inner.get(column, key))
+    }
+
+    fn contains(&self, column: Column, key: &[u8]) -> Result<bool, StoreError> {
+        self.timed(column, StoreOp::Get, || self.inner.contains(column, key))
+    }
+
+    fn put(&self, column: Column, key: &[u8], value: &[u8]) -> Result<(), StoreError> {
+        self.timed(column, StoreOp::Put, || self.inner.put(column, key, value))
+    }
+
+    fn delete(&self, column: Column, key: &[u8]) -> Result<(), StoreError> {
+        self.timed(column, StoreOp::Put, || self.inner.delete(column, key))
+    }
+
+    fn compare_and_swap(
+        &self,
+        column: Column,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, StoreError> {
+        self.timed(column, StoreOp::Put, || {
+            self.inner.compare_and_swap(column, key, expected, new)
+        })
+    }
+
+    fn scan_prefix_iter<'a>(
+        &'a self,
+        column: Column,
+        prefix: &[u8],
+    ) -> Result<ScanIter<'a>, StoreError> {
+        let start = Instant::now();
+        let inner = self.inner.scan_prefix_iter(column, prefix)?;
+        Ok(Box::new(TimedScanIter {
+            inner,
+            column,
+            latency: &self.latency,
+            elapsed: start.elapsed(),
+        }))
+    }
+
+    fn scan_prefix(&self, column: Column, prefix: &[u8]) -> Result<ScanResult, StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.scan_prefix(column, prefix)
+        })
+    }
+
+    fn scan_prefix_limited(
+        &self,
+        column: Column,
+        prefix: &[u8],
+        limit: usize,
+    ) -> Result<ScanResult, StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.scan_prefix_limited(column, prefix, limit)
+        })
+    }
+
+    fn for_each_prefix<'a>(
+        &self,
+        column: Column,
+        prefix: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.for_each_prefix(column, prefix, visitor)
+        })
+    }
+
+    fn scan_range(
+        &self,
+        column: Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<ScanResult, StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.scan_range(column, start, end)
+        })
+    }
+
+    fn for_each_range<'a>(
+        &self,
+        column: Column,
+        start: &[u8],
+        end: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.for_each_range(column, start, end, visitor)
+        })
+    }
+
+    /// The batch is timed as a whole and every op in it is counted at the batch average.
+    fn write_batch(&self, batch: &WriteBatch) -> Result<(), StoreError> {
+        let start = Instant::now();
+        let result = self.inner.write_batch(batch);
+        let elapsed = start.elapsed();
+        let mut ops = [0u64; COLUMN_COUNT];
+        for op in batch.iter() {
+            let column = match op {
+                WriteOp::Put { column, .. } | WriteOp::Delete { column, .. } => *column,
+            };
+            ops[column.index()] += 1;
+        }
+        let total = ops.iter().sum::<u64>();
+        if total > 0 {
+            let each = elapsed / u32::try_from(total).unwrap_or(u32::MAX);
+            for column in Column::ALL {
+                self.latency
+                    .record(column, StoreOp::Put, ops[column.index()], each);
+            }
+        }
+        result
+    }
+
+    fn snapshot(&self) -> Result<Box<dyn ReadSnapshot>, StoreError> {
+        Ok(Box::new(InstrumentedSnapshot {
+            inner: self.inner.snapshot()?,
+            latency: Arc::clone(&self.latency),
+        }))
+    }
+}
+
+struct TimedScanIter<'a> {
+    inner: ScanIter<'a>,
+    column: Column,
+    latency: &'a StoreLatency,
+    elapsed: Duration,
+}
+
+impl Iterator for TimedScanIter<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>), StoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = Instant::now();
+        let item = self.inner.next();
+        self.elapsed += start.elapsed();
+        item
+    }
+}
+
+impl Drop for TimedScanIter<'_> {
+    fn drop(&mut self) {
+        self.latency
+            .record(self.column, StoreOp::Scan, 1, self.elapsed);
+    }
+}
+
+struct InstrumentedSnapshot {
+    inner: Box<dyn ReadSnapshot>,
+    latency: Arc<StoreLatency>,
+}
+
+impl InstrumentedSnapshot {
+    fn timed<T>(&self, column: Column, op: StoreOp, call: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = call();
+        self.latency.record(column, op, 1, start.elapsed());
+        result
+    }
+}
+
+impl ReadSnapshot for InstrumentedSnapshot {
+    fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        self.timed(column, StoreOp::Get, || self.inner.get(column, key))
+    }
+
+    fn for_each_prefix<'a>(
+        &self,
+        column: Column,
+        prefix: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.for_each_prefix(column, prefix, visitor)
+        })
+    }
+
+    fn for_each_range<'a>(
+        &self,
+        column: Column,
+        start: &[u8],
+        end: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        self.timed(column, StoreOp::Scan, || {
+            self.inner.for_each_range(column, start, end, visitor)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryStore;
+
+    #[test]
+    fn operations_are_filed_under_their_column() {
+        let store = InstrumentedStore::new(MemoryStore::new());
+        store.put(Column::Utxo, b"a", b"1").unwrap();
+        assert_eq!(store.get(Column::Utxo, b"a").unwrap(), Some(b"1".to_vec()));
+        assert!(!store.contains(Column::Utxo, b"b").unwrap());
+
+        let mut batch = WriteBatch::new();
+        batch.put(Column::AddressDelta, b"x".to_vec(), b"1".to_vec());
+        batch.put(Column::AddressDelta, b"y".to_vec(), b"2".to_vec());
+        batch.delete(Column::Utxo, b"a".to_vec());
+        store.write_batch(&batch).unwrap();
+
+        let found = store
+            .scan_prefix_iter(Column::AddressDelta, b"")
+            .unwrap()
+            .count();
+        assert_eq!(found, 2);
+        store
+            .snapshot()
+            .unwrap()
+            .for_each_prefix(Column::AddressDelta, b"", &mut |_, _| Ok(()))
+            .unwrap();
+
+        let latency = store.latency();
+        assert_eq!(latency.histogram(Column::Utxo, StoreOp::Get).count(), 2);
+        assert_eq!(latency.histogram(Column::Utxo, StoreOp::Put).count(), 2);
+        assert_eq!(latency.histogram(Column::Utxo, StoreOp::Scan).count(), 0);
+        assert_eq!(
+            latency
+                .histogram(Column::AddressDelta, StoreOp::Put)
+                .count(),
+            2
+        );
+        assert_eq!(
+            latency
+                .histogram(Column::AddressDelta, StoreOp::Scan)
+                .count(),
+            2
+        );
+
+        let columns: Vec<Column> = latency
+            .snapshot()
+            .iter()
+            .map(|snapshot| snapshot.column)
+            .collect();
+        assert_eq!(columns, vec![Column::Utxo, Column::AddressDelta]);
+    }
+
+    #[test]
+    fn percentiles_use_bucket_upper_bounds() {
+        let latency = StoreLatency::default();
+        assert_eq!(
+            latency
+                .histogram(Column::Meta, StoreOp::Get)
+                .percentile_us(50),
+            None
+        );
+        latency.record(Column::Meta, StoreOp::Get, 9, Duration::from_micros(3));
+        latency.record(Column::Meta, StoreOp::Get, 1, Duration::from_micros(700));
+        let histogram = latency.histogram(Column::Meta, StoreOp::Get);
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.total_us, 9 * 3 + 700);
+        assert_eq!(histogram.percentile_us(50), Some(4));
+        assert_eq!(histogram.percentile_us(100), Some(1024));
+    }
+}
@@ -3,11 +3,17 @@ use std::sync::Arc;
 
 use smallvec::SmallVec;
 
+pub mod backend;
+pub mod compress;
+pub mod instrumented;
 pub mod memory;
 
 #[cfg(feature = "fjall")]
 pub mod fjall;
 
+pub use backend::{open_backend, BackendOptions, BackendUri, OpenedBackend};
+pub use instrumented::InstrumentedStore;
+
 #[derive(Debug)]
 pub enum StoreError {
     Backend(String),
@@ -50,10 +56,11 @@ pub enum Column {
     BlockUndo,
     Meta,
     UnconnectedBlock,
+    FluxnodeIndex,
 }
 
 impl Column {
-    pub const ALL: [Column; 25] = [
+    pub const ALL: [Column; 26] = [
         Column::BlockIndex,
         Column::HeaderIndex,
         Column::HeightIndex,
@@ -79,6 +86,7 @@ impl Column {
         Column::BlockUndo,
         Column::Meta,
         Column::UnconnectedBlock,
+        Column::FluxnodeIndex,
     ];
 
     pub const fn bit(self) -> u32 {
@@ -108,6 +116,7 @@ impl Column {
             Column::BlockUndo => 1 << 22,
             Column::Meta => 1 << 23,
             Column::UnconnectedBlock => 1 << 24,
+            Column::FluxnodeIndex => 1 << 25,
         }
     }
 
@@ -142,8 +151,15 @@ impl Column {
             Column::BlockUndo => "block_undo",
             Column::Meta => "meta",
             Column::UnconnectedBlock => "unconnected_block",
+            Column::FluxnodeIndex => "fluxnode_index",
         }
     }
+
+    pub fn from_name(name: &str) -> Option<Column> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.as_str() == name)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -284,23 +300,72 @@ impl WriteBatch {
 }
 
 pub type ScanResult = Vec<(Vec<u8>, Vec<u8>)>;
+pub type ScanIter<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>), StoreError>> + 'a>;
 pub type PrefixVisitor<'a> = dyn FnMut(&[u8], &[u8]) -> Result<(), StoreError> + 'a;
 
+/// Point-in-time view across every column, from [`KeyValueStore::snapshot`]. Writes committed
+/// after the snapshot was taken are never visible through it, and a `write_batch` is seen either
+/// entirely or not at all.
+pub trait ReadSnapshot: Send + Sync {
+    fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError>;
+    fn for_each_prefix<'a>(
+        &self,
+        column: Column,
+        prefix: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError>;
+    fn for_each_range<'a>(
+        &self,
+        column: Column,
+        start: &[u8],
+        end: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError>;
+
+    fn scan_prefix(&self, column: Column, prefix: &[u8]) -> Result<ScanResult, StoreError> {
+        let mut results = Vec::new();
+        self.for_each_prefix(column, prefix, &mut |key, value| {
+            results.push((key.to_vec(), value.to_vec()));
+            Ok(())
+        })?;
+        Ok(results)
+    }
+}
+
 pub trait KeyValueStore: Send + Sync {
     fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError>;
+    /// Whether `key` exists in `column`, without reading or decoding its value.
+    fn contains(&self, column: Column, key: &[u8]) -> Result<bool, StoreError>;
     fn put(&self, column: Column, key: &[u8], value: &[u8]) -> Result<(), StoreError>;
     fn delete(&self, column: Column, key: &[u8]) -> Result<(), StoreError>;
-
-    fn scan_prefix(&self, column: Column, prefix: &[u8]) -> Result<ScanResult, StoreError>;
+    /// Writes `new` only if the current value equals `expected` (`None` = key absent). Returns
+    /// whether the write happened. Atomic against other `compare_and_swap` calls on the same
+    /// store handle in this process; plain `put`/`write_batch` writers and other processes are
+    /// not serialized against it.
+    fn compare_and_swap(
+        &self,
+        column: Column,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, StoreError>;
+
+    /// Lazily yields `(key, value)` pairs under `prefix` in key order.
+    fn scan_prefix_iter<'a>(
+        &'a self,
+        column: Column,
+        prefix: &[u8],
+    ) -> Result<ScanIter<'a>, StoreError>;
+    fn scan_prefix(&self, column: Column, prefix: &[u8]) -> Result<ScanResult, StoreError> {
+        self.scan_prefix_iter(column, prefix)?.collect()
+    }
     fn scan_prefix_limited(
         &self,
         column: Column,
         prefix: &[u8],
         limit: usize,
     ) -> Result<ScanResult, StoreError> {
-        let mut results = self.scan_prefix(column, prefix)?;
-        results.truncate(limit);
-        Ok(results)
+        self.scan_prefix_iter(column, prefix)?.take(limit).collect()
     }
     fn for_each_prefix<'a>(
         &self,
@@ -323,7 +388,13 @@ pub trait KeyValueStore: Send + Sync {
         visitor: &mut PrefixVisitor<'a>,
     ) -> Result<(), StoreError>;
 
+    /// Applies every op in `batch`. Only [`ReadSnapshot`]s are guaranteed to see the batch
+    /// all-or-nothing; plain reads racing the commit may observe some columns updated before
+    /// others, so readers that combine several columns should take a snapshot first.
     fn write_batch(&self, batch: &WriteBatch) -> Result<(), StoreError>;
+
+    /// Opens a consistent read view of the whole store as of now.
+    fn snapshot(&self) -> Result<Box<dyn ReadSnapshot>, StoreError>;
 }
 
 impl<T: KeyValueStore + ?Sized> KeyValueStore for Arc<T> {
@@ -331,6 +402,10 @@ impl<T: KeyValueStore + ?Sized> KeyValueStore for Arc<T> {
         self.as_ref().get(column, key)
     }
 
+    fn contains(&self, column: Column, key: &[u8]) -> Result<bool, StoreError> {
+        self.as_ref().contains(column, key)
+    }
+
     fn put(&self, column: Column, key: &[u8], value: &[u8]) -> Result<(), StoreError> {
         self.as_ref().put(column, key, value)
     }
@@ -339,6 +414,24 @@ impl<T: KeyValueStore + ?Sized> KeyValueStore for Arc<T> {
         self.as_ref().delete(column, key)
     }
 
+    fn compare_and_swap(
+        &self,
+        column: Column,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, StoreError> {
+        self.as_ref().compare_and_swap(column, key, expected, new)
+    }
+
+    fn scan_prefix_iter<'a>(
+        &'a self,
+        column: Column,
+        prefix: &[u8],
+    ) -> Result<ScanIter<'a>, StoreError> {
+        self.as_ref().scan_prefix_iter(column, prefix)
+    }
+
     fn scan_prefix(&self, column: Column, prefix: &[u8]) -> Result<ScanResult, StoreError> {
         self.as_ref().scan_prefix(column, prefix)
     }
@@ -378,4 +471,8 @@ impl<T: KeyValueStore + ?Sized> KeyValueStore for Arc<T> {
     fn write_batch(&self, batch: &WriteBatch) -> Result<(), StoreError> {
         self.as_ref().write_batch(batch)
     }
+
+    fn snapshot(&self) -> Result<Box<dyn ReadSnapshot>, StoreError> {
+        self.as_ref().snapshot()
+    }
 }
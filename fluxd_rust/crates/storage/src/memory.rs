@@ -1,7 +1,9 @@
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
-use crate::{Column, KeyValueStore, PrefixVisitor, StoreError, WriteBatch, WriteOp};
+use crate::{
+    Column, KeyValueStore, PrefixVisitor, ReadSnapshot, ScanIter, StoreError, WriteBatch, WriteOp,
+};
 
 type MemoryStoreMap = BTreeMap<(Column, Vec<u8>), Vec<u8>>;
 
@@ -22,6 +24,11 @@ impl KeyValueStore for MemoryStore {
         Ok(guard.get(&(column, key.to_vec())).cloned())
     }
 
+    fn contains(&self, column: Column, key: &[u8]) -> Result<bool, StoreError> {
+        let guard = self.inner.read().expect("memory store lock");
+        Ok(guard.contains_key(&(column, key.to_vec())))
+    }
+
     fn put(&self, column: Column, key: &[u8], value: &[u8]) -> Result<(), StoreError> {
         let mut guard = self.inner.write().expect("memory store lock");
         guard.insert((column, key.to_vec()), value.to_vec());
@@ -34,19 +41,38 @@ impl KeyValueStore for MemoryStore {
         Ok(())
     }
 
-    fn scan_prefix(
+    fn compare_and_swap(
         &self,
         column: Column,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, StoreError> {
+        let mut guard = self.inner.write().expect("memory store lock");
+        let entry_key = (column, key.to_vec());
+        if guard.get(&entry_key).map(Vec::as_slice) != expected {
+            return Ok(false);
+        }
+        guard.insert(entry_key, new.to_vec());
+        Ok(true)
+    }
+
+    fn scan_prefix_iter<'a>(
+        &'a self,
+        column: Column,
         prefix: &[u8],
-    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StoreError> {
+    ) -> Result<ScanIter<'a>, StoreError> {
+        // Copied out under one read lock: the iterator sees a consistent view and callers may
+        // still write to the store while iterating.
         let guard = self.inner.read().expect("memory store lock");
-        let mut results = Vec::new();
-        for ((entry_column, key), value) in guard.iter() {
-            if *entry_column == column && key.starts_with(prefix) {
-                results.push((key.clone(), value.clone()));
-            }
-        }
-        Ok(results)
+        let entries: Vec<_> = guard
+            .range((column, prefix.to_vec())..)
+            .take_while(|((entry_column, key), _)| {
+                *entry_column == column && key.starts_with(prefix)
+            })
+            .map(|((_, key), value)| Ok((key.clone(), value.clone())))
+            .collect();
+        Ok(Box::new(entries.into_iter()))
     }
 
     fn for_each_prefix<'a>(
@@ -123,4 +149,54 @@ impl KeyValueStore for MemoryStore {
         }
         Ok(())
     }
+
+    /// Copies the whole map under the read lock, so each snapshot costs time and memory
+    /// proportional to the store size. Fine for tests and regtest, not for a mainnet-sized set.
+    fn snapshot(&self) -> Result<Box<dyn ReadSnapshot>, StoreError> {
+        let guard = self.inner.read().expect("memory store lock");
+        Ok(Box::new(MemorySnapshot { map: guard.clone() }))
+    }
+}
+
+struct MemorySnapshot {
+    map: MemoryStoreMap,
+}
+
+impl ReadSnapshot for MemorySnapshot {
+    fn get(&self, column: Column, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self.map.get(&(column, key.to_vec())).cloned())
+    }
+
+    fn for_each_prefix<'a>(
+        &self,
+        column: Column,
+        prefix: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        let start = (column, prefix.to_vec());
+        for ((entry_column, key), value) in self.map.range(start..) {
+            if *entry_column != column || !key.starts_with(prefix) {
+                break;
+            }
+            visitor(key.as_slice(), value.as_slice())?;
+        }
+        Ok(())
+    }
+
+    fn for_each_range<'a>(
+        &self,
+        column: Column,
+        start: &[u8],
+        end: &[u8],
+        visitor: &mut PrefixVisitor<'a>,
+    ) -> Result<(), StoreError> {
+        if start > end {
+            return Ok(());
+        }
+        let range = (column, start.to_vec())..=(column, end.to_vec());
+        for ((_, key), value) in self.map.range(range) {
+            visitor(key.as_slice(), value.as_slice())?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,62 @@
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, KeyValueStore};
+
+fn exercise_compare_and_swap(store: &dyn KeyValueStore) {
+    assert!(store
+        .compare_and_swap(Column::Meta, b"version", None, b"1")
+        .expect("cas insert"));
+    assert_eq!(
+        store.get(Column::Meta, b"version").expect("get"),
+        Some(b"1".to_vec())
+    );
+
+    // Expected value does not match: nothing is written.
+    assert!(!store
+        .compare_and_swap(Column::Meta, b"version", Some(b"2"), b"3")
+        .expect("cas mismatch"));
+    assert!(!store
+        .compare_and_swap(Column::Meta, b"version", None, b"3")
+        .expect("cas absent mismatch"));
+    assert_eq!(
+        store.get(Column::Meta, b"version").expect("get"),
+        Some(b"1".to_vec())
+    );
+
+    assert!(store
+        .compare_and_swap(Column::Meta, b"version", Some(b"1"), b"2")
+        .expect("cas update"));
+    assert_eq!(
+        store.get(Column::Meta, b"version").expect("get"),
+        Some(b"2".to_vec())
+    );
+
+    // Keys are scoped per column.
+    assert!(!store
+        .compare_and_swap(Column::Utxo, b"version", Some(b"2"), b"3")
+        .expect("cas other column"));
+}
+
+#[test]
+fn memory_compare_and_swap() {
+    exercise_compare_and_swap(&MemoryStore::new());
+}
+
+#[cfg(feature = "fjall")]
+#[test]
+fn fjall_compare_and_swap() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use fluxd_storage::fjall::FjallStore;
+
+    let mut dir = std::env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_nanos();
+    dir.push(format!("fluxd_fjall_cas_{nanos}"));
+
+    let store = FjallStore::open(&dir).expect("open fjall");
+    exercise_compare_and_swap(&store);
+    drop(store);
+    let _ = std::fs::remove_dir_all(&dir);
+}
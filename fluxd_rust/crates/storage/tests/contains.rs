@@ -0,0 +1,46 @@
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, KeyValueStore, WriteBatch};
+
+fn exercise_contains(store: &dyn KeyValueStore) {
+    assert!(!store.contains(Column::Meta, b"key").expect("contains"));
+
+    store.put(Column::Meta, b"key", b"value").expect("put");
+    assert!(store.contains(Column::Meta, b"key").expect("contains"));
+    // Keys are scoped per column and matched exactly, not by prefix.
+    assert!(!store.contains(Column::Utxo, b"key").expect("contains"));
+    assert!(!store.contains(Column::Meta, b"ke").expect("contains"));
+
+    // An empty value still counts as present.
+    store.put(Column::Meta, b"empty", b"").expect("put");
+    assert!(store.contains(Column::Meta, b"empty").expect("contains"));
+
+    let mut batch = WriteBatch::new();
+    batch.delete(Column::Meta, b"key");
+    store.write_batch(&batch).expect("write batch");
+    assert!(!store.contains(Column::Meta, b"key").expect("contains"));
+}
+
+#[test]
+fn memory_contains() {
+    exercise_contains(&MemoryStore::new());
+}
+
+#[cfg(feature = "fjall")]
+#[test]
+fn fjall_contains() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use fluxd_storage::fjall::FjallStore;
+
+    let mut dir = std::env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_nanos();
+    dir.push(format!("fluxd_fjall_contains_{nanos}"));
+
+    let store = FjallStore::open(&dir).expect("open fjall");
+    exercise_contains(&store);
+    drop(store);
+    let _ = std::fs::remove_dir_all(&dir);
+}
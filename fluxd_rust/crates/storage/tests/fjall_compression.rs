@@ -0,0 +1,123 @@
+#![cfg(all(feature = "fjall", feature = "zstd"))]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fluxd_storage::compress::{Compression, RAW, ZSTD};
+use fluxd_storage::fjall::{FjallOptions, FjallStore};
+use fluxd_storage::{Column, KeyValueStore, WriteBatch};
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_nanos();
+    std::env::temp_dir().join(format!("fluxd_fjall_{name}_{nanos}"))
+}
+
+fn undo_like_value(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 7) as u8).collect()
+}
+
+fn open(dir: &std::path::Path, columns: &[Column]) -> Result<FjallStore, String> {
+    FjallStore::open_with_options(
+        dir,
+        FjallOptions {
+            compression: Compression::new(columns),
+            ..FjallOptions::default()
+        },
+    )
+    .map_err(|err| err.to_string())
+}
+
+#[test]
+fn compressed_columns_round_trip() {
+    let dir = temp_dir("compression");
+    let store = open(&dir, &[Column::BlockUndo, Column::Utxo]).expect("open");
+
+    let large = undo_like_value(4_096);
+    let small = b"tiny".to_vec();
+    store.put(Column::BlockUndo, b"large", &large).expect("put");
+    store.put(Column::BlockUndo, b"small", &small).expect("put");
+    let mut batch = WriteBatch::new();
+    batch.put(Column::Utxo, b"batched", large.clone());
+    batch.put(Column::TxIndex, b"plain", large.clone());
+    store.write_batch(&batch).expect("batch");
+
+    assert_eq!(
+        store.get(Column::BlockUndo, b"large").unwrap(),
+        Some(large.clone())
+    );
+    assert_eq!(
+        store.get(Column::BlockUndo, b"small").unwrap(),
+        Some(small.clone())
+    );
+    assert_eq!(
+        store.get(Column::Utxo, b"batched").unwrap(),
+        Some(large.clone())
+    );
+    assert_eq!(
+        store.get(Column::TxIndex, b"plain").unwrap(),
+        Some(large.clone())
+    );
+    assert_eq!(
+        store.scan_prefix(Column::BlockUndo, b"").unwrap(),
+        vec![
+            (b"large".to_vec(), large.clone()),
+            (b"small".to_vec(), small.clone())
+        ]
+    );
+
+    assert!(store
+        .compare_and_swap(Column::Utxo, b"batched", Some(&large), &small)
+        .unwrap());
+    assert_eq!(store.get(Column::Utxo, b"batched").unwrap(), Some(small));
+
+    drop(store);
+    let err = open(&dir, &[Column::BlockUndo])
+        .err()
+        .expect("mask mismatch");
+    assert!(err.contains("utxo,block_undo"), "{err}");
+    let store = open(&dir, &[Column::BlockUndo, Column::Utxo]).expect("reopen");
+    assert_eq!(store.get(Column::BlockUndo, b"large").unwrap(), Some(large));
+
+    drop(store);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn compression_shrinks_large_values_only() {
+    let compression = Compression::new(&[Column::BlockUndo]);
+    let large = undo_like_value(4_096);
+
+    let stored = compression.encode(Column::BlockUndo, &large).unwrap();
+    assert_eq!(stored[0], ZSTD);
+    assert!(
+        stored.len() < large.len() / 10,
+        "compressed to {} bytes",
+        stored.len()
+    );
+    assert_eq!(
+        compression
+            .decode(Column::BlockUndo, &stored)
+            .unwrap()
+            .as_ref(),
+        large.as_slice()
+    );
+
+    let stored = compression.encode(Column::BlockUndo, b"tiny").unwrap();
+    assert_eq!(stored.as_ref(), &[RAW, b't', b'i', b'n', b'y']);
+    let stored = compression.encode(Column::Utxo, &large).unwrap();
+    assert_eq!(stored.as_ref(), large.as_slice());
+}
+
+#[test]
+fn compression_cannot_be_enabled_on_existing_data() {
+    let dir = temp_dir("compression_existing");
+    let store = open(&dir, &[]).expect("open");
+    store.put(Column::Utxo, b"key", b"value").expect("put");
+    drop(store);
+
+    let err = open(&dir, &[Column::Utxo]).err().expect("existing data");
+    assert!(err.contains("utxo"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
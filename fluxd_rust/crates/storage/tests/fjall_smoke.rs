@@ -39,6 +39,13 @@ fn fjall_smoke_roundtrip() {
             (b"prefix:2".to_vec(), b"b".to_vec()),
         ])
     );
+    let first = store
+        .scan_prefix_iter(Column::Meta, b"prefix:")
+        .expect("scan iter")
+        .next()
+        .expect("first entry")
+        .expect("entry");
+    assert_eq!(first, (b"prefix:1".to_vec(), b"a".to_vec()));
 
     let mut batch = WriteBatch::new();
     batch.put(Column::Meta, b"batch", b"ok");
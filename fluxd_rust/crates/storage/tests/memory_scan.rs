@@ -0,0 +1,69 @@
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, KeyValueStore};
+
+#[test]
+fn memory_scan_prefix_iter_is_ordered_and_scoped() {
+    let store = MemoryStore::new();
+    store.put(Column::Meta, b"a1", b"x").expect("put");
+    store.put(Column::Meta, b"b2", b"z").expect("put");
+    store.put(Column::Meta, b"b1", b"y").expect("put");
+    store.put(Column::Meta, b"c1", b"w").expect("put");
+    store
+        .put(Column::Utxo, b"b3", b"other column")
+        .expect("put");
+
+    let entries = store
+        .scan_prefix_iter(Column::Meta, b"b")
+        .expect("scan iter")
+        .collect::<Result<Vec<_>, _>>()
+        .expect("entries");
+    assert_eq!(
+        entries,
+        vec![
+            (b"b1".to_vec(), b"y".to_vec()),
+            (b"b2".to_vec(), b"z".to_vec()),
+        ]
+    );
+    assert_eq!(
+        store.scan_prefix(Column::Meta, b"b").expect("scan"),
+        entries
+    );
+    assert_eq!(
+        store
+            .scan_prefix_limited(Column::Meta, b"", 2)
+            .expect("scan limited")
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn memory_scan_prefix_iter_is_a_snapshot_that_allows_writes() {
+    let store = MemoryStore::new();
+    store.put(Column::Meta, b"k1", b"1").expect("put");
+    store.put(Column::Meta, b"k3", b"3").expect("put");
+
+    let mut iter = store
+        .scan_prefix_iter(Column::Meta, b"k")
+        .expect("scan iter");
+    assert_eq!(
+        iter.next().expect("first").expect("entry").0,
+        b"k1".to_vec()
+    );
+    store
+        .put(Column::Meta, b"k2", b"2")
+        .expect("put while iterating");
+    store
+        .delete(Column::Meta, b"k3")
+        .expect("delete while iterating");
+    // The iterator keeps the entries as they were when it was created.
+    assert_eq!(
+        iter.next().expect("second").expect("entry"),
+        (b"k3".to_vec(), b"3".to_vec())
+    );
+    assert!(iter.next().is_none());
+    assert_eq!(
+        store.get(Column::Meta, b"k2").expect("get"),
+        Some(b"2".to_vec())
+    );
+}
@@ -0,0 +1,41 @@
+use fluxd_storage::{open_backend, BackendOptions, Column, KeyValueStore};
+
+fn exercise_roundtrip(store: &dyn KeyValueStore) {
+    store.put(Column::Meta, b"key", b"value").expect("put");
+    assert_eq!(
+        store.get(Column::Meta, b"key").expect("get"),
+        Some(b"value".to_vec())
+    );
+}
+
+#[test]
+fn open_backend_memory_scheme() {
+    let store = open_backend("memory://", BackendOptions::default()).expect("open memory");
+    exercise_roundtrip(store.as_ref());
+}
+
+#[test]
+fn open_backend_rejects_unknown_scheme() {
+    assert!(open_backend("rocksdb:///tmp/db", BackendOptions::default()).is_err());
+}
+
+#[cfg(feature = "fjall")]
+#[test]
+fn open_backend_fjall_scheme() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let mut dir = std::env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_nanos();
+    dir.push(format!("fluxd_open_backend_{nanos}"));
+
+    let uri = format!("fjall://{}", dir.display());
+    {
+        let store = open_backend(&uri, BackendOptions::default()).expect("open fjall");
+        exercise_roundtrip(store.as_ref());
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
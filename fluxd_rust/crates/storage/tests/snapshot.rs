@@ -0,0 +1,91 @@
+use fluxd_storage::memory::MemoryStore;
+use fluxd_storage::{Column, KeyValueStore, WriteBatch};
+
+fn collect_prefix(
+    snapshot: &dyn fluxd_storage::ReadSnapshot,
+    column: Column,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    snapshot.scan_prefix(column, b"").expect("scan snapshot")
+}
+
+fn exercise_snapshot(store: &dyn KeyValueStore) {
+    store.put(Column::Utxo, b"a", b"1").expect("put a");
+    store.put(Column::Utxo, b"b", b"2").expect("put b");
+    store
+        .put(Column::Meta, b"best_block", b"old")
+        .expect("put tip");
+
+    let before = store.snapshot().expect("snapshot");
+
+    let mut batch = WriteBatch::new();
+    batch.delete(Column::Utxo, b"a");
+    batch.put(Column::Utxo, b"c", b"3");
+    batch.put(Column::Meta, b"best_block", b"new");
+    store.write_batch(&batch).expect("write batch");
+
+    // The earlier snapshot sees none of the batch, in any column.
+    assert_eq!(
+        before.get(Column::Meta, b"best_block").expect("get tip"),
+        Some(b"old".to_vec())
+    );
+    assert_eq!(
+        before.get(Column::Utxo, b"a").expect("get a"),
+        Some(b"1".to_vec())
+    );
+    assert_eq!(before.get(Column::Utxo, b"c").expect("get c"), None);
+    assert_eq!(
+        collect_prefix(before.as_ref(), Column::Utxo),
+        vec![
+            (b"a".to_vec(), b"1".to_vec()),
+            (b"b".to_vec(), b"2".to_vec())
+        ]
+    );
+    let mut ranged = Vec::new();
+    before
+        .for_each_range(Column::Utxo, b"b", b"z", &mut |key, _| {
+            ranged.push(key.to_vec());
+            Ok(())
+        })
+        .expect("range");
+    assert_eq!(ranged, vec![b"b".to_vec()]);
+
+    // The store and a fresh snapshot see all of it.
+    assert_eq!(
+        store.get(Column::Meta, b"best_block").expect("get tip"),
+        Some(b"new".to_vec())
+    );
+    let after = store.snapshot().expect("snapshot");
+    assert_eq!(
+        collect_prefix(after.as_ref(), Column::Utxo),
+        vec![
+            (b"b".to_vec(), b"2".to_vec()),
+            (b"c".to_vec(), b"3".to_vec())
+        ]
+    );
+    assert!(collect_prefix(after.as_ref(), Column::SpentIndex).is_empty());
+}
+
+#[test]
+fn memory_snapshot_ignores_later_batches() {
+    exercise_snapshot(&MemoryStore::new());
+}
+
+#[cfg(feature = "fjall")]
+#[test]
+fn fjall_snapshot_ignores_later_batches() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use fluxd_storage::fjall::FjallStore;
+
+    let mut dir = std::env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_nanos();
+    dir.push(format!("fluxd_fjall_snapshot_{nanos}"));
+
+    let store = FjallStore::open(&dir).expect("open fjall");
+    exercise_snapshot(&store);
+    drop(store);
+    let _ = std::fs::remove_dir_all(&dir);
+}